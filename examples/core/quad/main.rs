@@ -202,7 +202,7 @@ fn main() {
     //
     let pipelines = device.create_graphics_pipelines(&[
         (&shader_lib, &pipeline_layout, Subpass { index: 0, main_pass: &render_pass }, &pipeline_desc)
-    ]);
+    ], None);
 
     println!("pipelines: {:?}", pipelines);
 
@@ -396,7 +396,7 @@ fn main() {
 
         device.reset_fences(&[&frame_fence]);
         graphics_pool.reset();
-        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&mut frame_semaphore));
+        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&mut frame_semaphore)).unwrap();
 
         // Rendering
         let submit = {
@@ -451,7 +451,7 @@ fn main() {
         device.wait_for_fences(&[&frame_fence], d::WaitFor::All, !0);
 
         // present frame
-        swap_chain.present(&mut queue, &[]);
+        swap_chain.present(&mut queue, &[]).unwrap();
     }
 
     // cleanup!
@@ -91,7 +91,7 @@ pub fn main() {
         });
 
         // Get next frame
-        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&frame_semaphore));
+        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&frame_semaphore)).unwrap();
         data.out = views[frame.id()].clone();
 
         // Draw a frame
@@ -103,7 +103,7 @@ pub fn main() {
                    .expect("Could not flush encoder");
         }
 
-        swap_chain.present(&mut graphics_queue, &[&draw_semaphore]);
+        swap_chain.present(&mut graphics_queue, &[&draw_semaphore]).unwrap();
         device.wait_for_fences(&[&frame_fence], gfx::WaitFor::All, 1_000_000);
         graphics_queue.cleanup();
         graphics_pool.reset();
@@ -154,7 +154,7 @@ impl Renderer for GFX {
     fn render(&mut self, proj_view: &Matrix4<f32>) {
         let start = Instant::now();
 
-        let frame = self.swap_chain.acquire_frame(FrameSync::Semaphore(&self.frame_semaphore));
+        let frame = self.swap_chain.acquire_frame(FrameSync::Semaphore(&self.frame_semaphore)).unwrap();
         self.data.out_color = self.views[frame.id()].clone();
 
         self.pool.reset();
@@ -178,7 +178,7 @@ impl Renderer for GFX {
                              Some(&self.frame_fence))
                .expect("Could not flush encoder");
         let post_submit = start.elapsed();
-        self.swap_chain.present(&mut self.queue, &[&self.draw_semaphore]);
+        self.swap_chain.present(&mut self.queue, &[&self.draw_semaphore]).unwrap();
         self.device.wait_for_fences(&[&self.frame_fence], gfx::WaitFor::All, 1_000_000);
         self.queue.cleanup();
         let swap = start.elapsed();
@@ -0,0 +1,443 @@
+use std::ops::Range;
+use core::{self, buffer, command, image, memory, pso, query, target};
+use core::{IndexCount, InstanceCount, VertexCount, VertexOffset, Viewport};
+use core::image::ImageLayout;
+use trace::{Barrier, Command};
+use {Capture, Handle, Image};
+
+#[derive(Clone)]
+pub struct CommandBuffer<B: core::Backend> {
+    pub(crate) raw: B::CommandBuffer,
+    pub(crate) commands: Vec<Command>,
+}
+
+impl<B: core::Backend> CommandBuffer<B> {
+    pub(crate) fn new(raw: B::CommandBuffer) -> Self {
+        CommandBuffer {
+            raw,
+            commands: Vec::new(),
+        }
+    }
+
+    /// Access the command buffer of the wrapped backend.
+    pub fn raw(&self) -> &B::CommandBuffer {
+        &self.raw
+    }
+
+    /// Commands recorded since the last `begin` or `reset`.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+}
+
+impl<B: core::Backend> core::RawCommandBuffer<Capture<B>> for CommandBuffer<B> {
+    fn begin(&mut self) {
+        self.commands.clear();
+        self.raw.begin()
+    }
+
+    fn finish(&mut self) {
+        self.raw.finish()
+    }
+
+    fn reset(&mut self, release_resources: bool) {
+        self.commands.clear();
+        self.raw.reset(release_resources)
+    }
+
+    fn pipeline_barrier(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        barriers: &[memory::Barrier<Capture<B>>],
+    ) {
+        let (captured, raw): (Vec<_>, Vec<_>) = barriers
+            .iter()
+            .map(|barrier| match *barrier {
+                memory::Barrier::AllBuffers(ref access) => (
+                    Barrier::AllBuffers(access.clone()),
+                    memory::Barrier::AllBuffers(access.clone()),
+                ),
+                memory::Barrier::AllImages(ref access) => (
+                    Barrier::AllImages(access.clone()),
+                    memory::Barrier::AllImages(access.clone()),
+                ),
+                memory::Barrier::Buffer { ref states, target, ref range } => (
+                    Barrier::Buffer {
+                        states: states.clone(),
+                        target: target.id,
+                        range: range.clone(),
+                    },
+                    memory::Barrier::Buffer {
+                        states: states.clone(),
+                        target: &target.raw,
+                        range: range.clone(),
+                    },
+                ),
+                memory::Barrier::Image { ref states, target, ref range } => (
+                    Barrier::Image {
+                        states: states.clone(),
+                        target: target.id,
+                        range: range.clone(),
+                    },
+                    memory::Barrier::Image {
+                        states: states.clone(),
+                        target: target.raw(),
+                        range: range.clone(),
+                    },
+                ),
+            })
+            .unzip();
+
+        self.commands.push(Command::PipelineBarrier {
+            stages: stages.clone(),
+            barriers: captured,
+        });
+        self.raw.pipeline_barrier(stages, &raw)
+    }
+
+    fn fill_buffer(&mut self, buffer: &Handle<B::Buffer>, range: Range<u64>, data: u32) {
+        self.commands.push(Command::FillBuffer {
+            buffer: buffer.id,
+            range: range.clone(),
+            data,
+        });
+        self.raw.fill_buffer(&buffer.raw, range, data)
+    }
+
+    fn update_buffer(&mut self, buffer: &Handle<B::Buffer>, offset: u64, data: &[u8]) {
+        self.commands.push(Command::UpdateBuffer {
+            buffer: buffer.id,
+            offset,
+            data: data.to_vec(),
+        });
+        self.raw.update_buffer(&buffer.raw, offset, data)
+    }
+
+    fn clear_color(&mut self, rtv: &Handle<B::RenderTargetView>, layout: ImageLayout, color: command::ClearColor) {
+        self.commands.push(Command::ClearColor {
+            target: rtv.id,
+            layout,
+            color,
+        });
+        self.raw.clear_color(&rtv.raw, layout, color)
+    }
+
+    fn clear_depth_stencil(
+        &mut self,
+        dsv: &Handle<B::DepthStencilView>,
+        layout: ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
+    ) {
+        self.commands.push(Command::ClearDepthStencil {
+            target: dsv.id,
+            layout,
+            depth,
+            stencil,
+        });
+        self.raw.clear_depth_stencil(&dsv.raw, layout, depth, stencil)
+    }
+
+    fn resolve_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageResolve],
+    ) {
+        self.commands.push(Command::ResolveImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
+        self.raw.resolve_image(src.raw(), src_layout, dst.raw(), dst_layout, regions)
+    }
+
+    fn blit_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        self.commands.push(Command::BlitImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+            filter,
+        });
+        self.raw.blit_image(src.raw(), src_layout, dst.raw(), dst_layout, regions, filter)
+    }
+
+    fn bind_index_buffer(&mut self, ibv: buffer::IndexBufferView<Capture<B>>) {
+        self.commands.push(Command::BindIndexBuffer {
+            buffer: ibv.buffer.id,
+            offset: ibv.offset,
+            index_type: ibv.index_type,
+        });
+        self.raw.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &ibv.buffer.raw,
+            offset: ibv.offset,
+            index_type: ibv.index_type,
+        })
+    }
+
+    fn bind_vertex_buffers(&mut self, vbs: pso::VertexBufferSet<Capture<B>>) {
+        self.commands.push(Command::BindVertexBuffers(
+            vbs.0.iter().map(|&(buffer, offset)| (buffer.id, offset)).collect(),
+        ));
+        let buffers = vbs.0
+            .iter()
+            .map(|&(buffer, offset)| (&buffer.raw, offset))
+            .collect();
+        self.raw.bind_vertex_buffers(pso::VertexBufferSet(buffers))
+    }
+
+    fn set_viewports(&mut self, viewports: &[Viewport]) {
+        self.commands.push(Command::SetViewports(viewports.to_vec()));
+        self.raw.set_viewports(viewports)
+    }
+
+    fn set_scissors(&mut self, scissors: &[target::Rect]) {
+        self.commands.push(Command::SetScissors(scissors.to_vec()));
+        self.raw.set_scissors(scissors)
+    }
+
+    fn set_stencil_reference(&mut self, front: target::Stencil, back: target::Stencil) {
+        self.commands.push(Command::SetStencilReference { front, back });
+        self.raw.set_stencil_reference(front, back)
+    }
+
+    fn set_blend_constants(&mut self, color: target::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+        self.raw.set_blend_constants(color)
+    }
+
+    fn begin_renderpass(
+        &mut self,
+        render_pass: &Handle<B::RenderPass>,
+        frame_buffer: &Handle<B::FrameBuffer>,
+        render_area: target::Rect,
+        clear_values: &[command::ClearValue],
+        first_subpass: command::SubpassContents,
+    ) {
+        self.commands.push(Command::BeginRenderPass {
+            render_pass: render_pass.id,
+            frame_buffer: frame_buffer.id,
+            render_area,
+            clear_values: clear_values.to_vec(),
+            first_subpass,
+        });
+        self.raw.begin_renderpass(&render_pass.raw, &frame_buffer.raw, render_area, clear_values, first_subpass)
+    }
+
+    fn next_subpass(&mut self, contents: command::SubpassContents) {
+        self.commands.push(Command::NextSubpass(contents));
+        self.raw.next_subpass(contents)
+    }
+
+    fn end_renderpass(&mut self) {
+        self.commands.push(Command::EndRenderPass);
+        self.raw.end_renderpass()
+    }
+
+    fn bind_graphics_pipeline(&mut self, pipeline: &Handle<B::GraphicsPipeline>) {
+        self.commands.push(Command::BindGraphicsPipeline(pipeline.id));
+        self.raw.bind_graphics_pipeline(&pipeline.raw)
+    }
+
+    fn bind_graphics_descriptor_sets(
+        &mut self,
+        layout: &Handle<B::PipelineLayout>,
+        first_set: usize,
+        sets: &[&Handle<B::DescriptorSet>],
+    ) {
+        self.commands.push(Command::BindGraphicsDescriptorSets {
+            layout: layout.id,
+            first_set,
+            sets: sets.iter().map(|set| set.id).collect(),
+        });
+        let sets = sets.iter().map(|set| &set.raw).collect::<Vec<_>>();
+        self.raw.bind_graphics_descriptor_sets(&layout.raw, first_set, &sets)
+    }
+
+    fn bind_compute_pipeline(&mut self, pipeline: &Handle<B::ComputePipeline>) {
+        self.commands.push(Command::BindComputePipeline(pipeline.id));
+        self.raw.bind_compute_pipeline(&pipeline.raw)
+    }
+
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.commands.push(Command::Dispatch(x, y, z));
+        self.raw.dispatch(x, y, z)
+    }
+
+    fn dispatch_indirect(&mut self, buffer: &Handle<B::Buffer>, offset: u64) {
+        self.commands.push(Command::DispatchIndirect {
+            buffer: buffer.id,
+            offset,
+        });
+        self.raw.dispatch_indirect(&buffer.raw, offset)
+    }
+
+    fn copy_buffer(&mut self, src: &Handle<B::Buffer>, dst: &Handle<B::Buffer>, regions: &[command::BufferCopy]) {
+        self.commands.push(Command::CopyBuffer {
+            src: src.id,
+            dst: dst.id,
+            regions: regions.to_vec(),
+        });
+        self.raw.copy_buffer(&src.raw, &dst.raw, regions)
+    }
+
+    fn copy_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageCopy],
+    ) {
+        self.commands.push(Command::CopyImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
+        self.raw.copy_image(src.raw(), src_layout, dst.raw(), dst_layout, regions)
+    }
+
+    fn copy_buffer_to_image(
+        &mut self,
+        src: &Handle<B::Buffer>,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.commands.push(Command::CopyBufferToImage {
+            src: src.id,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
+        self.raw.copy_buffer_to_image(&src.raw, dst.raw(), dst_layout, regions)
+    }
+
+    fn copy_image_to_buffer(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Handle<B::Buffer>,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.commands.push(Command::CopyImageToBuffer {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            regions: regions.to_vec(),
+        });
+        self.raw.copy_image_to_buffer(src.raw(), src_layout, &dst.raw, regions)
+    }
+
+    fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.commands.push(Command::Draw {
+            vertices: vertices.clone(),
+            instances: instances.clone(),
+        });
+        self.raw.draw(vertices, instances)
+    }
+
+    fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        self.commands.push(Command::DrawIndexed {
+            indices: indices.clone(),
+            base_vertex,
+            instances: instances.clone(),
+        });
+        self.raw.draw_indexed(indices, base_vertex, instances)
+    }
+
+    fn draw_indirect(&mut self, buffer: &Handle<B::Buffer>, offset: u64, draw_count: u32, stride: u32) {
+        self.commands.push(Command::DrawIndirect {
+            buffer: buffer.id,
+            offset,
+            draw_count,
+            stride,
+        });
+        self.raw.draw_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: &Handle<B::Buffer>, offset: u64, draw_count: u32, stride: u32) {
+        self.commands.push(Command::DrawIndexedIndirect {
+            buffer: buffer.id,
+            offset,
+            draw_count,
+            stride,
+        });
+        self.raw.draw_indexed_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    fn begin_query(&mut self, query: query::Query<Capture<B>>, flags: query::QueryControl) {
+        self.commands.push(Command::BeginQuery {
+            pool: query.pool.id,
+            query: query.id,
+            flags,
+        });
+        self.raw.begin_query(query::Query { pool: &query.pool.raw, id: query.id }, flags)
+    }
+
+    fn end_query(&mut self, query: query::Query<Capture<B>>) {
+        self.commands.push(Command::EndQuery {
+            pool: query.pool.id,
+            query: query.id,
+        });
+        self.raw.end_query(query::Query { pool: &query.pool.raw, id: query.id })
+    }
+
+    fn reset_query_pool(&mut self, pool: &Handle<B::QueryPool>, queries: Range<query::QueryId>) {
+        self.commands.push(Command::ResetQueryPool {
+            pool: pool.id,
+            queries: queries.clone(),
+        });
+        self.raw.reset_query_pool(&pool.raw, queries)
+    }
+
+    fn write_timestamp(&mut self, stage: pso::PipelineStage, query: query::Query<Capture<B>>) {
+        self.commands.push(Command::WriteTimestamp {
+            stage,
+            pool: query.pool.id,
+            query: query.id,
+        });
+        self.raw.write_timestamp(stage, query::Query { pool: &query.pool.raw, id: query.id })
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &Handle<B::QueryPool>,
+        queries: Range<query::QueryId>,
+        buffer: &Handle<B::Buffer>,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    ) {
+        self.commands.push(Command::CopyQueryPoolResults {
+            pool: pool.id,
+            queries: queries.clone(),
+            buffer: buffer.id,
+            offset,
+            stride,
+            flags,
+        });
+        self.raw.copy_query_pool_results(&pool.raw, queries, &buffer.raw, offset, stride, flags)
+    }
+}
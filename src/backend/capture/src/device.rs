@@ -0,0 +1,443 @@
+use std::mem;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use core::{self, buffer, device, format, image, mapping, pass, pso, query};
+use core::memory::Requirements;
+use native::{MappedWrite, RawImage};
+use trace::{Event, Id, Trace};
+use {next_id, Capture, DescriptorPool, Handle, Heap, Image, Mapping, UnboundBuffer, UnboundImage};
+
+pub struct Device<B: core::Backend> {
+    raw: B::Device,
+    trace: Arc<Mutex<Trace>>,
+}
+
+impl<B: core::Backend> Device<B> {
+    pub(crate) fn new(raw: B::Device, trace: Arc<Mutex<Trace>>) -> Self {
+        Device { raw, trace }
+    }
+
+    /// Access the device of the wrapped backend.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    /// Mutably access the device of the wrapped backend.
+    pub fn raw_mut(&mut self) -> &mut B::Device {
+        &mut self.raw
+    }
+
+    /// Wrap an object created directly by the wrapped device, like a depth-stencil view.
+    ///
+    /// The object isn't part of the trace, the replayer needs to be provided with a counterpart.
+    pub fn wrap<T>(&mut self, raw: T) -> Handle<T> {
+        Handle { raw, id: next_id() }
+    }
+
+    /// Get a copy of the trace captured so far.
+    pub fn trace(&self) -> Trace {
+        self.trace.lock().unwrap().clone()
+    }
+
+    /// Take the trace captured so far, following events will start a new trace.
+    pub fn take_trace(&mut self) -> Trace {
+        mem::replace(&mut *self.trace.lock().unwrap(), Trace::default())
+    }
+
+    fn record(&self, event: Event) {
+        self.trace.lock().unwrap().events.push(event);
+    }
+
+    fn destroy(&self, id: Id) {
+        self.record(Event::Destroy(id));
+    }
+}
+
+impl<B: core::Backend> core::Device<Capture<B>> for Device<B> {
+    fn get_features(&self) -> &core::Features {
+        self.raw.get_features()
+    }
+
+    fn get_limits(&self) -> &core::Limits {
+        self.raw.get_limits()
+    }
+
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties {
+        self.raw.get_format_properties(format)
+    }
+
+    fn create_heap(&mut self, heap_type: &core::HeapType, resource_type: device::ResourceHeapType, size: u64) -> Result<Heap<B>, device::ResourceHeapError> {
+        let raw = self.raw.create_heap(heap_type, resource_type, size)?;
+        Ok(Heap {
+            raw,
+            properties: heap_type.properties,
+        })
+    }
+
+    fn create_shader_library(&mut self, shaders: &[(pso::EntryPoint, &[u8])]) -> Result<B::ShaderLib, pso::CreateShaderError> {
+        self.raw.create_shader_library(shaders)
+    }
+
+    fn create_renderpass(&mut self, attachments: &[pass::Attachment], subpasses: &[pass::SubpassDesc], dependencies: &[pass::SubpassDependency]) -> Handle<B::RenderPass> {
+        let id = next_id();
+        self.record(Event::CreateRenderPass {
+            id,
+            attachments: attachments.to_vec(),
+            subpasses: subpasses.iter().map(|subpass| subpass.color_attachments.to_vec()).collect(),
+            dependencies: dependencies.to_vec(),
+        });
+        Handle {
+            raw: self.raw.create_renderpass(attachments, subpasses, dependencies),
+            id,
+        }
+    }
+
+    fn create_pipeline_layout(&mut self, sets: &[&B::DescriptorSetLayout]) -> Handle<B::PipelineLayout> {
+        let raw = self.raw.create_pipeline_layout(sets);
+        self.wrap(raw)
+    }
+
+    fn create_pipeline_cache(&mut self, initial_data: &[u8]) -> B::PipelineCache {
+        self.raw.create_pipeline_cache(initial_data)
+    }
+
+    fn get_pipeline_cache_data(&mut self, cache: &B::PipelineCache) -> Vec<u8> {
+        self.raw.get_pipeline_cache_data(cache)
+    }
+
+    fn merge_pipeline_caches(&mut self, target: &B::PipelineCache, sources: &[&B::PipelineCache]) {
+        self.raw.merge_pipeline_caches(target, sources)
+    }
+
+    fn create_graphics_pipelines<'a>(
+        &mut self,
+        descs: &[(&B::ShaderLib, &Handle<B::PipelineLayout>, pass::Subpass<'a, Capture<B>>, &pso::GraphicsPipelineDesc)],
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<Handle<B::GraphicsPipeline>, pso::CreationError>> {
+        let descs = descs
+            .iter()
+            .map(|&(shader_lib, layout, ref subpass, desc)| {
+                let subpass = pass::Subpass { index: subpass.index, main_pass: &subpass.main_pass.raw };
+                (shader_lib, &layout.raw, subpass, desc)
+            })
+            .collect::<Vec<_>>();
+        self.raw
+            .create_graphics_pipelines(&descs, cache)
+            .into_iter()
+            .map(|result| result.map(|raw| Handle { raw, id: next_id() }))
+            .collect()
+    }
+
+    fn create_compute_pipelines(
+        &mut self,
+        descs: &[(&B::ShaderLib, pso::EntryPoint, &Handle<B::PipelineLayout>)],
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<Handle<B::ComputePipeline>, pso::CreationError>> {
+        let descs = descs
+            .iter()
+            .map(|&(shader_lib, entry_point, layout)| (shader_lib, entry_point, &layout.raw))
+            .collect::<Vec<_>>();
+        self.raw
+            .create_compute_pipelines(&descs, cache)
+            .into_iter()
+            .map(|result| result.map(|raw| Handle { raw, id: next_id() }))
+            .collect()
+    }
+
+    fn create_framebuffer(
+        &mut self,
+        renderpass: &Handle<B::RenderPass>,
+        color_attachments: &[&Handle<B::RenderTargetView>],
+        depth_stencil_attachments: &[&Handle<B::DepthStencilView>],
+        extent: device::Extent,
+    ) -> Handle<B::FrameBuffer> {
+        let id = next_id();
+        self.record(Event::CreateFrameBuffer {
+            id,
+            render_pass: renderpass.id,
+            color_attachments: color_attachments.iter().map(|rtv| rtv.id).collect(),
+            depth_stencil_attachments: depth_stencil_attachments.iter().map(|dsv| dsv.id).collect(),
+            extent,
+        });
+
+        let colors = color_attachments.iter().map(|rtv| &rtv.raw).collect::<Vec<_>>();
+        let depth_stencils = depth_stencil_attachments.iter().map(|dsv| &dsv.raw).collect::<Vec<_>>();
+        Handle {
+            raw: self.raw.create_framebuffer(&renderpass.raw, &colors, &depth_stencils, extent),
+            id,
+        }
+    }
+
+    fn create_sampler(&mut self, info: image::SamplerInfo) -> Handle<B::Sampler> {
+        let id = next_id();
+        self.record(Event::CreateSampler { id, info: info.clone() });
+        Handle {
+            raw: self.raw.create_sampler(info),
+            id,
+        }
+    }
+
+    fn create_buffer(&mut self, size: u64, stride: u64, usage: buffer::Usage) -> Result<UnboundBuffer<B>, buffer::CreationError> {
+        let raw = self.raw.create_buffer(size, stride, usage)?;
+        Ok(UnboundBuffer { raw, size, stride, usage })
+    }
+
+    fn get_buffer_requirements(&mut self, buffer: &UnboundBuffer<B>) -> Requirements {
+        self.raw.get_buffer_requirements(&buffer.raw)
+    }
+
+    fn bind_buffer_memory(&mut self, heap: &Heap<B>, offset: u64, buffer: UnboundBuffer<B>) -> Result<Handle<B::Buffer>, buffer::CreationError> {
+        let raw = self.raw.bind_buffer_memory(&heap.raw, offset, buffer.raw)?;
+        let id = next_id();
+        self.record(Event::CreateBuffer {
+            id,
+            size: buffer.size,
+            stride: buffer.stride,
+            usage: buffer.usage,
+            properties: heap.properties,
+        });
+        Ok(Handle { raw, id })
+    }
+
+    fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format, usage: image::Usage)
+         -> Result<UnboundImage<B>, image::CreationError>
+    {
+        let raw = self.raw.create_image(kind, levels, format, usage)?;
+        Ok(UnboundImage { raw, kind, levels, format, usage })
+    }
+
+    fn get_image_requirements(&mut self, image: &UnboundImage<B>) -> Requirements {
+        self.raw.get_image_requirements(&image.raw)
+    }
+
+    fn bind_image_memory(&mut self, heap: &Heap<B>, offset: u64, image: UnboundImage<B>) -> Result<Image<B>, image::CreationError> {
+        let raw = self.raw.bind_image_memory(&heap.raw, offset, image.raw)?;
+        let id = next_id();
+        self.record(Event::CreateImage {
+            id,
+            kind: image.kind,
+            levels: image.levels,
+            format: image.format,
+            usage: image.usage,
+            properties: heap.properties,
+        });
+        Ok(Image { raw: RawImage::Owned(raw), id })
+    }
+
+    fn view_buffer_as_constant(&mut self, buffer: &Handle<B::Buffer>, range: Range<u64>) -> Result<Handle<B::ConstantBufferView>, device::TargetViewError> {
+        let raw = self.raw.view_buffer_as_constant(&buffer.raw, range.clone())?;
+        let id = next_id();
+        self.record(Event::CreateConstantBufferView { id, buffer: buffer.id, range });
+        Ok(Handle { raw, id })
+    }
+
+    fn view_image_as_render_target(&mut self, image: &Image<B>, format: format::Format, range: image::SubresourceRange) -> Result<Handle<B::RenderTargetView>, device::TargetViewError> {
+        let raw = self.raw.view_image_as_render_target(image.raw(), format, range.clone())?;
+        let id = next_id();
+        self.record(Event::CreateRenderTargetView { id, image: image.id, format, range });
+        Ok(Handle { raw, id })
+    }
+
+    fn view_image_as_shader_resource(&mut self, image: &Image<B>, format: format::Format) -> Result<Handle<B::ShaderResourceView>, device::TargetViewError> {
+        let raw = self.raw.view_image_as_shader_resource(image.raw(), format)?;
+        let id = next_id();
+        self.record(Event::CreateShaderResourceView { id, image: image.id, format });
+        Ok(Handle { raw, id })
+    }
+
+    fn view_image_as_unordered_access(&mut self, image: &Image<B>, format: format::Format) -> Result<Handle<B::UnorderedAccessView>, device::TargetViewError> {
+        let raw = self.raw.view_image_as_unordered_access(image.raw(), format)?;
+        let id = next_id();
+        self.record(Event::CreateUnorderedAccessView { id, image: image.id, format });
+        Ok(Handle { raw, id })
+    }
+
+    fn create_descriptor_pool(&mut self, max_sets: usize, descriptor_ranges: &[pso::DescriptorRangeDesc]) -> DescriptorPool<B> {
+        DescriptorPool {
+            raw: self.raw.create_descriptor_pool(max_sets, descriptor_ranges),
+        }
+    }
+
+    fn create_descriptor_set_layout(&mut self, bindings: &[pso::DescriptorSetLayoutBinding]) -> B::DescriptorSetLayout {
+        self.raw.create_descriptor_set_layout(bindings)
+    }
+
+    fn update_descriptor_sets(&mut self, writes: &[pso::DescriptorSetWrite<Capture<B>>]) {
+        use core::pso::DescriptorWrite as Dw;
+
+        let writes = writes
+            .iter()
+            .map(|write| pso::DescriptorSetWrite {
+                set: &write.set.raw,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                write: match write.write {
+                    Dw::Sampler(ref samplers) => Dw::Sampler(samplers.iter().map(|sampler| &sampler.raw).collect()),
+                    Dw::SampledImage(ref images) => Dw::SampledImage(images.iter().map(|&(srv, layout)| (&srv.raw, layout)).collect()),
+                    Dw::StorageImage(ref images) => Dw::StorageImage(images.iter().map(|&(srv, layout)| (&srv.raw, layout)).collect()),
+                    Dw::UniformTexelBuffer => Dw::UniformTexelBuffer,
+                    Dw::StorageTexelBuffer => Dw::StorageTexelBuffer,
+                    Dw::ConstantBuffer(ref cbvs) => Dw::ConstantBuffer(cbvs.iter().map(|cbv| &cbv.raw).collect()),
+                    Dw::StorageBuffer => Dw::StorageBuffer,
+                    Dw::InputAttachment(ref images) => Dw::InputAttachment(images.iter().map(|&(srv, layout)| (&srv.raw, layout)).collect()),
+                },
+            })
+            .collect::<Vec<_>>();
+        self.raw.update_descriptor_sets(&writes)
+    }
+
+    fn read_mapping<'a, T>(&self, buffer: &'a Handle<B::Buffer>, offset: u64, size: u64)
+                    -> Result<mapping::Reader<'a, Capture<B>, T>, mapping::Error>
+        where T: Copy
+    {
+        let reader = self.raw.read_mapping::<T>(&buffer.raw, offset, size)?;
+        unsafe {
+            let (slice, raw) = reader.into_parts();
+            Ok(mapping::Reader::new(slice, Mapping::new(raw, None)))
+        }
+    }
+
+    fn write_mapping<'a, T>(&mut self, buffer: &'a Handle<B::Buffer>, offset: u64, size: u64)
+                     -> Result<mapping::Writer<'a, Capture<B>, T>, mapping::Error>
+        where T: Copy
+    {
+        let writer = self.raw.write_mapping::<T>(&buffer.raw, offset, size)?;
+        unsafe {
+            let (slice, raw) = writer.into_parts();
+            let write = MappedWrite {
+                trace: self.trace.clone(),
+                buffer: buffer.id,
+                offset,
+                ptr: slice.as_ptr() as *const u8,
+                len: slice.len() * mem::size_of::<T>(),
+            };
+            Ok(mapping::Writer::new(slice, Mapping::new(raw, Some(write))))
+        }
+    }
+
+    fn create_semaphore(&mut self) -> B::Semaphore {
+        self.raw.create_semaphore()
+    }
+
+    fn create_fence(&mut self, signaled: bool) -> B::Fence {
+        self.raw.create_fence(signaled)
+    }
+
+    fn reset_fences(&mut self, fences: &[&B::Fence]) {
+        self.raw.reset_fences(fences)
+    }
+
+    fn wait_for_fences(&mut self, fences: &[&B::Fence], wait: device::WaitFor, timeout_ms: u32) -> bool {
+        self.raw.wait_for_fences(fences, wait, timeout_ms)
+    }
+
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> Handle<B::QueryPool> {
+        let id = next_id();
+        self.record(Event::CreateQueryPool { id, ty, count });
+        Handle {
+            raw: self.raw.create_query_pool(ty, count),
+            id,
+        }
+    }
+
+    fn get_query_pool_results(&mut self, pool: &Handle<B::QueryPool>, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        self.raw.get_query_pool_results(&pool.raw, queries, data, flags)
+    }
+
+    fn destroy_heap(&mut self, heap: Heap<B>) {
+        self.raw.destroy_heap(heap.raw)
+    }
+
+    fn destroy_shader_lib(&mut self, shader_lib: B::ShaderLib) {
+        self.raw.destroy_shader_lib(shader_lib)
+    }
+
+    fn destroy_renderpass(&mut self, rp: Handle<B::RenderPass>) {
+        self.destroy(rp.id);
+        self.raw.destroy_renderpass(rp.raw)
+    }
+
+    fn destroy_pipeline_layout(&mut self, layout: Handle<B::PipelineLayout>) {
+        self.raw.destroy_pipeline_layout(layout.raw)
+    }
+
+    fn destroy_graphics_pipeline(&mut self, pipeline: Handle<B::GraphicsPipeline>) {
+        self.raw.destroy_graphics_pipeline(pipeline.raw)
+    }
+
+    fn destroy_compute_pipeline(&mut self, pipeline: Handle<B::ComputePipeline>) {
+        self.raw.destroy_compute_pipeline(pipeline.raw)
+    }
+
+    fn destroy_pipeline_cache(&mut self, cache: B::PipelineCache) {
+        self.raw.destroy_pipeline_cache(cache)
+    }
+
+    fn destroy_framebuffer(&mut self, fb: Handle<B::FrameBuffer>) {
+        self.destroy(fb.id);
+        self.raw.destroy_framebuffer(fb.raw)
+    }
+
+    fn destroy_buffer(&mut self, buffer: Handle<B::Buffer>) {
+        self.destroy(buffer.id);
+        self.raw.destroy_buffer(buffer.raw)
+    }
+
+    fn destroy_image(&mut self, image: Image<B>) {
+        if let RawImage::Owned(raw) = image.raw {
+            self.destroy(image.id);
+            self.raw.destroy_image(raw)
+        }
+    }
+
+    fn destroy_render_target_view(&mut self, rtv: Handle<B::RenderTargetView>) {
+        self.destroy(rtv.id);
+        self.raw.destroy_render_target_view(rtv.raw)
+    }
+
+    fn destroy_depth_stencil_view(&mut self, dsv: Handle<B::DepthStencilView>) {
+        self.raw.destroy_depth_stencil_view(dsv.raw)
+    }
+
+    fn destroy_constant_buffer_view(&mut self, cbv: Handle<B::ConstantBufferView>) {
+        self.destroy(cbv.id);
+        self.raw.destroy_constant_buffer_view(cbv.raw)
+    }
+
+    fn destroy_shader_resource_view(&mut self, srv: Handle<B::ShaderResourceView>) {
+        self.destroy(srv.id);
+        self.raw.destroy_shader_resource_view(srv.raw)
+    }
+
+    fn destroy_unordered_access_view(&mut self, uav: Handle<B::UnorderedAccessView>) {
+        self.destroy(uav.id);
+        self.raw.destroy_unordered_access_view(uav.raw)
+    }
+
+    fn destroy_sampler(&mut self, sampler: Handle<B::Sampler>) {
+        self.destroy(sampler.id);
+        self.raw.destroy_sampler(sampler.raw)
+    }
+
+    fn destroy_descriptor_pool(&mut self, pool: DescriptorPool<B>) {
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    fn destroy_descriptor_set_layout(&mut self, layout: B::DescriptorSetLayout) {
+        self.raw.destroy_descriptor_set_layout(layout)
+    }
+
+    fn destroy_fence(&mut self, fence: B::Fence) {
+        self.raw.destroy_fence(fence)
+    }
+
+    fn destroy_semaphore(&mut self, semaphore: B::Semaphore) {
+        self.raw.destroy_semaphore(semaphore)
+    }
+
+    fn destroy_query_pool(&mut self, pool: Handle<B::QueryPool>) {
+        self.destroy(pool.id);
+        self.raw.destroy_query_pool(pool.raw)
+    }
+}
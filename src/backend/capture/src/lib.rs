@@ -0,0 +1,211 @@
+//! Command stream capture and replay.
+//!
+//! `Capture<B>` implements `Backend` on top of any other backend `B`. Calls
+//! are forwarded to `B` while resource creation, buffer uploads and all
+//! submitted command buffers are recorded into a `Trace`. Each captured
+//! object gets a stable `Id`, which is used to refer to it from the trace.
+//! With the `serialize` feature enabled the trace can be stored with serde,
+//! e.g. to attach it to a bug report.
+//!
+//! A `Replayer` re-issues a trace against any other backend, allowing to
+//! reproduce issues and to compare the results of different backends.
+
+#[macro_use]
+extern crate log;
+extern crate gfx_core as core;
+#[cfg(feature = "serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use core::QueueType;
+
+mod command;
+mod device;
+mod native;
+mod pool;
+mod replay;
+pub mod trace;
+mod window;
+
+pub use command::CommandBuffer;
+pub use device::Device;
+pub use native::{Handle, Heap, Image, Mapping, UnboundBuffer, UnboundImage};
+pub use pool::{CommandPool, DescriptorPool, SubpassCommandPool};
+pub use replay::Replayer;
+pub use trace::{Id, Trace};
+pub use window::{Surface, Swapchain};
+
+static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn next_id() -> Id {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Backend wrapping `B`, capturing all work issued to it.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Capture<B>(PhantomData<B>);
+
+impl<B: core::Backend> core::Backend for Capture<B> {
+    type Adapter = Adapter<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+    type SubpassCommandBuffer = B::SubpassCommandBuffer;
+    type QueueFamily = B::QueueFamily;
+
+    type Heap = Heap<B>;
+    type Mapping = Mapping<B>;
+    type CommandPool = CommandPool<B>;
+    type SubpassCommandPool = SubpassCommandPool<B>;
+
+    type ShaderLib = B::ShaderLib;
+    type RenderPass = Handle<B::RenderPass>;
+    type FrameBuffer = Handle<B::FrameBuffer>;
+
+    type UnboundBuffer = UnboundBuffer<B>;
+    type Buffer = Handle<B::Buffer>;
+    type UnboundImage = UnboundImage<B>;
+    type Image = Image<B>;
+    type Sampler = Handle<B::Sampler>;
+
+    type ConstantBufferView = Handle<B::ConstantBufferView>;
+    type ShaderResourceView = Handle<B::ShaderResourceView>;
+    type UnorderedAccessView = Handle<B::UnorderedAccessView>;
+    type RenderTargetView = Handle<B::RenderTargetView>;
+    type DepthStencilView = Handle<B::DepthStencilView>;
+
+    type ComputePipeline = Handle<B::ComputePipeline>;
+    type GraphicsPipeline = Handle<B::GraphicsPipeline>;
+    type PipelineCache = B::PipelineCache;
+    type PipelineLayout = Handle<B::PipelineLayout>;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = Handle<B::DescriptorSet>;
+    type DescriptorSetLayout = B::DescriptorSetLayout;
+
+    type QueryPool = Handle<B::QueryPool>;
+
+    type Fence = B::Fence;
+    type Semaphore = B::Semaphore;
+}
+
+/// Instance wrapper, capturing the work of all enumerated adapters.
+pub struct Instance<I>(pub I);
+
+impl<B: core::Backend, I: core::Instance<B>> core::Instance<Capture<B>> for Instance<I> {
+    fn enumerate_adapters(&self) -> Vec<Adapter<B>> {
+        self.0
+            .enumerate_adapters()
+            .into_iter()
+            .map(Adapter::new)
+            .collect()
+    }
+}
+
+pub struct Adapter<B: core::Backend> {
+    raw: B::Adapter,
+}
+
+impl<B: core::Backend> Adapter<B> {
+    /// Wrap an adapter of the backend `B`.
+    pub fn new(raw: B::Adapter) -> Self {
+        Adapter { raw }
+    }
+}
+
+fn wrap_queues<B: core::Backend, C>(
+    queues: Vec<core::CommandQueue<B, C>>,
+    trace: &Arc<Mutex<Trace>>,
+) -> Vec<core::CommandQueue<Capture<B>, C>> {
+    queues
+        .into_iter()
+        .map(|queue| unsafe {
+            core::CommandQueue::new(CommandQueue::new(queue.into_raw(), trace.clone()))
+        })
+        .collect()
+}
+
+impl<B: core::Backend> core::Adapter<Capture<B>> for Adapter<B> {
+    fn open(&self, queue_descs: &[(&B::QueueFamily, QueueType, u32)]) -> core::Gpu<Capture<B>> {
+        let gpu = self.raw.open(queue_descs);
+        let trace = Arc::new(Mutex::new(Trace::default()));
+
+        core::Gpu {
+            general_queues: wrap_queues(gpu.general_queues, &trace),
+            graphics_queues: wrap_queues(gpu.graphics_queues, &trace),
+            compute_queues: wrap_queues(gpu.compute_queues, &trace),
+            transfer_queues: wrap_queues(gpu.transfer_queues, &trace),
+            device: Device::new(gpu.device, trace),
+            heap_types: gpu.heap_types,
+            memory_heaps: gpu.memory_heaps,
+        }
+    }
+
+    fn get_info(&self) -> &core::AdapterInfo {
+        self.raw.get_info()
+    }
+
+    fn get_queue_families(&self) -> &[(B::QueueFamily, QueueType)] {
+        self.raw.get_queue_families()
+    }
+}
+
+/// Command queue, appending all submissions to the trace.
+pub struct CommandQueue<B: core::Backend> {
+    // Only empty while temporarily handed out by `with_core`.
+    raw: RefCell<Option<B::CommandQueue>>,
+    trace: Arc<Mutex<Trace>>,
+}
+
+impl<B: core::Backend> CommandQueue<B> {
+    fn new(raw: B::CommandQueue, trace: Arc<Mutex<Trace>>) -> Self {
+        CommandQueue {
+            raw: RefCell::new(Some(raw)),
+            trace,
+        }
+    }
+
+    /// Temporarily expose the wrapped queue as a `core::CommandQueue` of `B`.
+    fn with_core<C, T, F>(&self, fun: F) -> T
+    where
+        F: FnOnce(&mut core::CommandQueue<B, C>) -> T,
+    {
+        let raw = self.raw.borrow_mut().take().unwrap();
+        let mut queue = unsafe { core::CommandQueue::new(raw) };
+        let result = fun(&mut queue);
+        *self.raw.borrow_mut() = Some(queue.into_raw());
+        result
+    }
+}
+
+impl<B: core::Backend> core::RawCommandQueue<Capture<B>> for CommandQueue<B> {
+    unsafe fn submit_raw(&mut self, submission: core::RawSubmission<Capture<B>>, fence: Option<&B::Fence>) {
+        let commands = submission.cmd_buffers
+            .iter()
+            .map(|cmd_buffer| cmd_buffer.commands.clone())
+            .collect();
+        self.trace.lock().unwrap().events.push(trace::Event::Submit(commands));
+
+        let cmd_buffers = submission.cmd_buffers
+            .iter()
+            .map(|cmd_buffer| cmd_buffer.raw.clone())
+            .collect::<Vec<_>>();
+        let raw = core::RawSubmission {
+            cmd_buffers: &cmd_buffers,
+            wait_semaphores: submission.wait_semaphores,
+            signal_semaphores: submission.signal_semaphores,
+        };
+        self.raw
+            .get_mut()
+            .as_mut()
+            .unwrap()
+            .submit_raw(raw, fence);
+    }
+}
@@ -0,0 +1,132 @@
+use std::{fmt, slice};
+use std::sync::{Arc, Mutex};
+use core::{self, buffer, format, image, memory};
+use trace::{Event, Id, Trace};
+
+/// Object of the wrapped backend, together with its capture id.
+#[derive(Debug)]
+pub struct Handle<T> {
+    pub(crate) raw: T,
+    pub(crate) id: Id,
+}
+
+impl<T> Handle<T> {
+    /// Id referring to this object in the trace.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Access the object of the wrapped backend.
+    pub fn raw(&self) -> &T {
+        &self.raw
+    }
+}
+
+#[derive(Debug)]
+pub struct Heap<B: core::Backend> {
+    pub(crate) raw: B::Heap,
+    pub(crate) properties: memory::HeapProperties,
+}
+
+#[derive(Debug)]
+pub struct UnboundBuffer<B: core::Backend> {
+    pub(crate) raw: B::UnboundBuffer,
+    pub(crate) size: u64,
+    pub(crate) stride: u64,
+    pub(crate) usage: buffer::Usage,
+}
+
+#[derive(Debug)]
+pub struct UnboundImage<B: core::Backend> {
+    pub(crate) raw: B::UnboundImage,
+    pub(crate) kind: image::Kind,
+    pub(crate) levels: image::Level,
+    pub(crate) format: format::Format,
+    pub(crate) usage: image::Usage,
+}
+
+pub(crate) enum RawImage<B: core::Backend> {
+    Owned(B::Image),
+    /// Backbuffer owned by the wrapped swapchain, which outlives the image.
+    Backbuffer(*const B::Image),
+}
+
+pub struct Image<B: core::Backend> {
+    pub(crate) raw: RawImage<B>,
+    pub(crate) id: Id,
+}
+
+unsafe impl<B: core::Backend> Send for Image<B> {}
+unsafe impl<B: core::Backend> Sync for Image<B> {}
+
+impl<B: core::Backend> Image<B> {
+    /// Id referring to this image in the trace.
+    ///
+    /// Backbuffers aren't created by the trace, the replayer needs to be
+    /// provided with a matching image.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Access the image of the wrapped backend.
+    pub fn raw(&self) -> &B::Image {
+        match self.raw {
+            RawImage::Owned(ref image) => image,
+            RawImage::Backbuffer(image) => unsafe { &*image },
+        }
+    }
+}
+
+impl<B: core::Backend> fmt::Debug for Image<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("raw", self.raw())
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+/// Mapped range of a buffer written by the application.
+pub(crate) struct MappedWrite {
+    pub(crate) trace: Arc<Mutex<Trace>>,
+    pub(crate) buffer: Id,
+    pub(crate) offset: u64,
+    pub(crate) ptr: *const u8,
+    pub(crate) len: usize,
+}
+
+pub struct Mapping<B: core::Backend> {
+    raw: B::Mapping,
+    write: Option<MappedWrite>,
+}
+
+unsafe impl<B: core::Backend> Send for Mapping<B> {}
+unsafe impl<B: core::Backend> Sync for Mapping<B> {}
+
+impl<B: core::Backend> Mapping<B> {
+    pub(crate) fn new(raw: B::Mapping, write: Option<MappedWrite>) -> Self {
+        Mapping { raw, write }
+    }
+}
+
+impl<B: core::Backend> Drop for Mapping<B> {
+    fn drop(&mut self) {
+        // The wrapped mapping is still alive, capture the written data before it gets unmapped.
+        if let Some(ref write) = self.write {
+            let data = unsafe { slice::from_raw_parts(write.ptr, write.len) };
+            write.trace.lock().unwrap().events.push(Event::WriteBuffer {
+                buffer: write.buffer,
+                offset: write.offset,
+                data: data.to_vec(),
+            });
+        }
+    }
+}
+
+impl<B: core::Backend> fmt::Debug for Mapping<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Mapping")
+            .field("raw", &self.raw)
+            .finish()
+    }
+}
@@ -0,0 +1,54 @@
+use core::{self, pool};
+use {Capture, CommandBuffer, CommandQueue, Handle};
+
+pub struct CommandPool<B: core::Backend> {
+    raw: B::CommandPool,
+}
+
+impl<B: core::Backend> core::RawCommandPool<Capture<B>> for CommandPool<B> {
+    fn reset(&mut self) {
+        self.raw.reset()
+    }
+
+    unsafe fn from_queue(queue: &CommandQueue<B>, flags: pool::CommandPoolCreateFlags) -> Self {
+        let raw_queue = queue.raw.borrow();
+        CommandPool {
+            raw: B::CommandPool::from_queue(raw_queue.as_ref().unwrap(), flags),
+        }
+    }
+
+    fn allocate(&mut self, num: usize) -> Vec<CommandBuffer<B>> {
+        self.raw
+            .allocate(num)
+            .into_iter()
+            .map(CommandBuffer::new)
+            .collect()
+    }
+
+    unsafe fn free(&mut self, buffers: Vec<CommandBuffer<B>>) {
+        self.raw.free(buffers.into_iter().map(|buffer| buffer.raw).collect())
+    }
+}
+
+pub struct SubpassCommandPool<B: core::Backend>(B::SubpassCommandPool);
+
+impl<B: core::Backend> core::SubpassCommandPool<Capture<B>> for SubpassCommandPool<B> { }
+
+#[derive(Debug)]
+pub struct DescriptorPool<B: core::Backend> {
+    pub(crate) raw: B::DescriptorPool,
+}
+
+impl<B: core::Backend> core::DescriptorPool<Capture<B>> for DescriptorPool<B> {
+    fn allocate_sets(&mut self, layouts: &[&B::DescriptorSetLayout]) -> Vec<Handle<B::DescriptorSet>> {
+        self.raw
+            .allocate_sets(layouts)
+            .into_iter()
+            .map(|raw| Handle { raw, id: ::next_id() })
+            .collect()
+    }
+
+    fn reset(&mut self) {
+        self.raw.reset()
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::HashMap;
+use core::{self, buffer, device, image, memory, pass, pool, pso, query};
+use core::{Device, RawCommandBuffer, RawCommandPool, RawCommandQueue};
+use trace::{Barrier, Command, Event, Id, Trace};
+
+fn get<T>(objects: &HashMap<Id, T>, id: Id) -> &T {
+    match objects.get(&id) {
+        Some(object) => object,
+        None => panic!("Object {} is not known to the replayer", id),
+    }
+}
+
+/// Re-issues captured traces against the backend `B`.
+///
+/// Objects are recreated from the trace events, each buffer and image gets
+/// a dedicated heap fitting the requirements of `B`. Submissions are executed
+/// one at a time, waiting for completion before continuing with the trace.
+///
+/// Objects not part of the trace, like pipelines or backbuffers, need to be
+/// provided with the `insert_*` methods before replaying. Replaying panics on
+/// unknown objects and on failing object creation.
+pub struct Replayer<B: core::Backend> {
+    heap_types: Vec<core::HeapType>,
+    heaps: HashMap<Id, B::Heap>,
+    buffers: HashMap<Id, B::Buffer>,
+    images: HashMap<Id, B::Image>,
+    render_targets: HashMap<Id, B::RenderTargetView>,
+    depth_stencils: HashMap<Id, B::DepthStencilView>,
+    shader_resources: HashMap<Id, B::ShaderResourceView>,
+    unordered_accesses: HashMap<Id, B::UnorderedAccessView>,
+    constant_buffers: HashMap<Id, B::ConstantBufferView>,
+    samplers: HashMap<Id, B::Sampler>,
+    query_pools: HashMap<Id, B::QueryPool>,
+    render_passes: HashMap<Id, B::RenderPass>,
+    frame_buffers: HashMap<Id, B::FrameBuffer>,
+    graphics_pipelines: HashMap<Id, B::GraphicsPipeline>,
+    compute_pipelines: HashMap<Id, B::ComputePipeline>,
+    pipeline_layouts: HashMap<Id, B::PipelineLayout>,
+    descriptor_sets: HashMap<Id, B::DescriptorSet>,
+}
+
+impl<B: core::Backend> Replayer<B> {
+    /// Create a replayer, allocating memory from the given heap types.
+    pub fn new(heap_types: &[core::HeapType]) -> Self {
+        Replayer {
+            heap_types: heap_types.to_vec(),
+            heaps: HashMap::new(),
+            buffers: HashMap::new(),
+            images: HashMap::new(),
+            render_targets: HashMap::new(),
+            depth_stencils: HashMap::new(),
+            shader_resources: HashMap::new(),
+            unordered_accesses: HashMap::new(),
+            constant_buffers: HashMap::new(),
+            samplers: HashMap::new(),
+            query_pools: HashMap::new(),
+            render_passes: HashMap::new(),
+            frame_buffers: HashMap::new(),
+            graphics_pipelines: HashMap::new(),
+            compute_pipelines: HashMap::new(),
+            pipeline_layouts: HashMap::new(),
+            descriptor_sets: HashMap::new(),
+        }
+    }
+
+    /// Provide the image for a captured backbuffer.
+    pub fn insert_image(&mut self, id: Id, image: B::Image) {
+        self.images.insert(id, image);
+    }
+
+    ///
+    pub fn insert_depth_stencil_view(&mut self, id: Id, dsv: B::DepthStencilView) {
+        self.depth_stencils.insert(id, dsv);
+    }
+
+    ///
+    pub fn insert_graphics_pipeline(&mut self, id: Id, pipeline: B::GraphicsPipeline) {
+        self.graphics_pipelines.insert(id, pipeline);
+    }
+
+    ///
+    pub fn insert_compute_pipeline(&mut self, id: Id, pipeline: B::ComputePipeline) {
+        self.compute_pipelines.insert(id, pipeline);
+    }
+
+    ///
+    pub fn insert_pipeline_layout(&mut self, id: Id, layout: B::PipelineLayout) {
+        self.pipeline_layouts.insert(id, layout);
+    }
+
+    ///
+    pub fn insert_descriptor_set(&mut self, id: Id, set: B::DescriptorSet) {
+        self.descriptor_sets.insert(id, set);
+    }
+
+    /// Get the replayed counterpart of a captured buffer.
+    pub fn buffer(&self, id: Id) -> Option<&B::Buffer> {
+        self.buffers.get(&id)
+    }
+
+    /// Get the replayed counterpart of a captured image.
+    pub fn image(&self, id: Id) -> Option<&B::Image> {
+        self.images.get(&id)
+    }
+
+    /// Get the replayed counterpart of a captured query pool.
+    pub fn query_pool(&self, id: Id) -> Option<&B::QueryPool> {
+        self.query_pools.get(&id)
+    }
+
+    /// Replay all events of `trace`, submitting work to `queue`.
+    pub fn replay<C>(&mut self, trace: &Trace, device: &mut B::Device, queue: &mut core::CommandQueue<B, C>) {
+        let mut pool = unsafe {
+            B::CommandPool::from_queue(queue.as_raw(), pool::TRANSIENT)
+        };
+        let fence = device.create_fence(false);
+
+        for event in &trace.events {
+            match *event {
+                Event::CreateBuffer { id, size, stride, usage, properties } => {
+                    let unbound = device.create_buffer(size, stride, usage).unwrap();
+                    let requirements = device.get_buffer_requirements(&unbound);
+                    let heap = self.create_heap(device, properties, device::ResourceHeapType::Buffers, requirements.size);
+                    let buffer = device.bind_buffer_memory(&heap, 0, unbound).unwrap();
+                    self.heaps.insert(id, heap);
+                    self.buffers.insert(id, buffer);
+                }
+                Event::CreateImage { id, kind, levels, format, usage, properties } => {
+                    let unbound = device.create_image(kind, levels, format, usage).unwrap();
+                    let requirements = device.get_image_requirements(&unbound);
+                    let resource_type = if usage.intersects(image::COLOR_ATTACHMENT | image::DEPTH_STENCIL_ATTACHMENT) {
+                        device::ResourceHeapType::Targets
+                    } else {
+                        device::ResourceHeapType::Images
+                    };
+                    let heap = self.create_heap(device, properties, resource_type, requirements.size);
+                    let image = device.bind_image_memory(&heap, 0, unbound).unwrap();
+                    self.heaps.insert(id, heap);
+                    self.images.insert(id, image);
+                }
+                Event::CreateRenderTargetView { id, image, format, ref range } => {
+                    let rtv = device.view_image_as_render_target(get(&self.images, image), format, range.clone()).unwrap();
+                    self.render_targets.insert(id, rtv);
+                }
+                Event::CreateShaderResourceView { id, image, format } => {
+                    let srv = device.view_image_as_shader_resource(get(&self.images, image), format).unwrap();
+                    self.shader_resources.insert(id, srv);
+                }
+                Event::CreateUnorderedAccessView { id, image, format } => {
+                    let uav = device.view_image_as_unordered_access(get(&self.images, image), format).unwrap();
+                    self.unordered_accesses.insert(id, uav);
+                }
+                Event::CreateConstantBufferView { id, buffer, ref range } => {
+                    let cbv = device.view_buffer_as_constant(get(&self.buffers, buffer), range.clone()).unwrap();
+                    self.constant_buffers.insert(id, cbv);
+                }
+                Event::CreateSampler { id, ref info } => {
+                    let sampler = device.create_sampler(info.clone());
+                    self.samplers.insert(id, sampler);
+                }
+                Event::CreateQueryPool { id, ty, count } => {
+                    let pool = device.create_query_pool(ty, count);
+                    self.query_pools.insert(id, pool);
+                }
+                Event::CreateRenderPass { id, ref attachments, ref subpasses, ref dependencies } => {
+                    let subpasses = subpasses
+                        .iter()
+                        .map(|colors| pass::SubpassDesc { color_attachments: colors })
+                        .collect::<Vec<_>>();
+                    let render_pass = device.create_renderpass(attachments, &subpasses, dependencies);
+                    self.render_passes.insert(id, render_pass);
+                }
+                Event::CreateFrameBuffer { id, render_pass, ref color_attachments, ref depth_stencil_attachments, extent } => {
+                    let frame_buffer = {
+                        let colors = color_attachments
+                            .iter()
+                            .map(|&rtv| get(&self.render_targets, rtv))
+                            .collect::<Vec<_>>();
+                        let depth_stencils = depth_stencil_attachments
+                            .iter()
+                            .map(|&dsv| get(&self.depth_stencils, dsv))
+                            .collect::<Vec<_>>();
+                        device.create_framebuffer(get(&self.render_passes, render_pass), &colors, &depth_stencils, extent)
+                    };
+                    self.frame_buffers.insert(id, frame_buffer);
+                }
+                Event::WriteBuffer { buffer, offset, ref data } => {
+                    let mut writer = device
+                        .write_mapping::<u8>(get(&self.buffers, buffer), offset, data.len() as u64)
+                        .unwrap();
+                    writer.copy_from_slice(data);
+                }
+                Event::Submit(ref cmd_buffers) => {
+                    let mut raw_buffers = pool.allocate(cmd_buffers.len());
+                    for (raw, commands) in raw_buffers.iter_mut().zip(cmd_buffers) {
+                        raw.begin();
+                        self.record(raw, commands);
+                        raw.finish();
+                    }
+
+                    unsafe {
+                        queue.as_mut().submit_raw(core::RawSubmission {
+                            cmd_buffers: &raw_buffers,
+                            wait_semaphores: &[],
+                            signal_semaphores: &[],
+                        }, Some(&fence));
+                    }
+                    device.wait_for_fences(&[&fence], device::WaitFor::All, !0);
+                    device.reset_fences(&[&fence]);
+                    unsafe { pool.free(raw_buffers) };
+                }
+                Event::Destroy(id) => self.destroy_object(device, id),
+            }
+        }
+
+        device.destroy_fence(fence);
+    }
+
+    /// Destroy all objects owned by the replayer, including inserted ones.
+    pub fn destroy(mut self, device: &mut B::Device) {
+        let ids = self.render_targets.keys()
+            .chain(self.depth_stencils.keys())
+            .chain(self.shader_resources.keys())
+            .chain(self.unordered_accesses.keys())
+            .chain(self.constant_buffers.keys())
+            .chain(self.samplers.keys())
+            .chain(self.query_pools.keys())
+            .chain(self.frame_buffers.keys())
+            .chain(self.render_passes.keys())
+            .chain(self.buffers.keys())
+            .chain(self.images.keys())
+            .cloned()
+            .collect::<Vec<_>>();
+        for id in ids {
+            self.destroy_object(device, id);
+        }
+
+        for (_, pipeline) in self.graphics_pipelines.drain() {
+            device.destroy_graphics_pipeline(pipeline);
+        }
+        for (_, pipeline) in self.compute_pipelines.drain() {
+            device.destroy_compute_pipeline(pipeline);
+        }
+        for (_, layout) in self.pipeline_layouts.drain() {
+            device.destroy_pipeline_layout(layout);
+        }
+    }
+
+    fn create_heap(
+        &self,
+        device: &mut B::Device,
+        properties: memory::HeapProperties,
+        resource_type: device::ResourceHeapType,
+        size: u64,
+    ) -> B::Heap {
+        let heap_type = self.heap_types
+            .iter()
+            .find(|heap_type| heap_type.properties.contains(properties))
+            .or_else(|| {
+                warn!("No heap type with {:?}, only matching CPU visibility", properties);
+                self.heap_types
+                    .iter()
+                    .find(|heap_type| heap_type.properties.contains(properties & memory::CPU_VISIBLE))
+            })
+            .expect("No compatible heap type found");
+        device.create_heap(heap_type, resource_type, size).unwrap()
+    }
+
+    fn destroy_object(&mut self, device: &mut B::Device, id: Id) {
+        if let Some(rtv) = self.render_targets.remove(&id) {
+            device.destroy_render_target_view(rtv);
+        } else if let Some(dsv) = self.depth_stencils.remove(&id) {
+            device.destroy_depth_stencil_view(dsv);
+        } else if let Some(srv) = self.shader_resources.remove(&id) {
+            device.destroy_shader_resource_view(srv);
+        } else if let Some(uav) = self.unordered_accesses.remove(&id) {
+            device.destroy_unordered_access_view(uav);
+        } else if let Some(cbv) = self.constant_buffers.remove(&id) {
+            device.destroy_constant_buffer_view(cbv);
+        } else if let Some(sampler) = self.samplers.remove(&id) {
+            device.destroy_sampler(sampler);
+        } else if let Some(pool) = self.query_pools.remove(&id) {
+            device.destroy_query_pool(pool);
+        } else if let Some(frame_buffer) = self.frame_buffers.remove(&id) {
+            device.destroy_framebuffer(frame_buffer);
+        } else if let Some(render_pass) = self.render_passes.remove(&id) {
+            device.destroy_renderpass(render_pass);
+        } else if let Some(buffer) = self.buffers.remove(&id) {
+            device.destroy_buffer(buffer);
+        } else if let Some(image) = self.images.remove(&id) {
+            device.destroy_image(image);
+        }
+
+        if let Some(heap) = self.heaps.remove(&id) {
+            device.destroy_heap(heap);
+        }
+    }
+
+    fn record(&self, cmd_buffer: &mut B::CommandBuffer, commands: &[Command]) {
+        for command in commands {
+            match *command {
+                Command::PipelineBarrier { ref stages, ref barriers } => {
+                    let barriers = barriers
+                        .iter()
+                        .map(|barrier| match *barrier {
+                            Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+                            Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+                            Barrier::Buffer { ref states, target, ref range } => memory::Barrier::Buffer {
+                                states: states.clone(),
+                                target: get(&self.buffers, target),
+                                range: range.clone(),
+                            },
+                            Barrier::Image { ref states, target, ref range } => memory::Barrier::Image {
+                                states: states.clone(),
+                                target: get(&self.images, target),
+                                range: range.clone(),
+                            },
+                        })
+                        .collect::<Vec<_>>();
+                    cmd_buffer.pipeline_barrier(stages.clone(), &barriers);
+                }
+                Command::FillBuffer { buffer, ref range, data } => {
+                    cmd_buffer.fill_buffer(get(&self.buffers, buffer), range.clone(), data);
+                }
+                Command::UpdateBuffer { buffer, offset, ref data } => {
+                    cmd_buffer.update_buffer(get(&self.buffers, buffer), offset, data);
+                }
+                Command::ClearColor { target, layout, color } => {
+                    cmd_buffer.clear_color(get(&self.render_targets, target), layout, color);
+                }
+                Command::ClearDepthStencil { target, layout, depth, stencil } => {
+                    cmd_buffer.clear_depth_stencil(get(&self.depth_stencils, target), layout, depth, stencil);
+                }
+                Command::ResolveImage { src, src_layout, dst, dst_layout, ref regions } => {
+                    cmd_buffer.resolve_image(get(&self.images, src), src_layout, get(&self.images, dst), dst_layout, regions);
+                }
+                Command::BlitImage { src, src_layout, dst, dst_layout, ref regions, filter } => {
+                    cmd_buffer.blit_image(get(&self.images, src), src_layout, get(&self.images, dst), dst_layout, regions, filter);
+                }
+                Command::BindIndexBuffer { buffer, offset, index_type } => {
+                    cmd_buffer.bind_index_buffer(buffer::IndexBufferView {
+                        buffer: get(&self.buffers, buffer),
+                        offset,
+                        index_type,
+                    });
+                }
+                Command::BindVertexBuffers(ref buffers) => {
+                    let buffers = buffers
+                        .iter()
+                        .map(|&(buffer, offset)| (get(&self.buffers, buffer), offset))
+                        .collect();
+                    cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(buffers));
+                }
+                Command::SetViewports(ref viewports) => cmd_buffer.set_viewports(viewports),
+                Command::SetScissors(ref scissors) => cmd_buffer.set_scissors(scissors),
+                Command::SetStencilReference { front, back } => cmd_buffer.set_stencil_reference(front, back),
+                Command::SetBlendConstants(color) => cmd_buffer.set_blend_constants(color),
+                Command::BeginRenderPass { render_pass, frame_buffer, render_area, ref clear_values, first_subpass } => {
+                    cmd_buffer.begin_renderpass(
+                        get(&self.render_passes, render_pass),
+                        get(&self.frame_buffers, frame_buffer),
+                        render_area,
+                        clear_values,
+                        first_subpass,
+                    );
+                }
+                Command::NextSubpass(contents) => cmd_buffer.next_subpass(contents),
+                Command::EndRenderPass => cmd_buffer.end_renderpass(),
+                Command::BindGraphicsPipeline(pipeline) => {
+                    cmd_buffer.bind_graphics_pipeline(get(&self.graphics_pipelines, pipeline));
+                }
+                Command::BindGraphicsDescriptorSets { layout, first_set, ref sets } => {
+                    let sets = sets
+                        .iter()
+                        .map(|&set| get(&self.descriptor_sets, set))
+                        .collect::<Vec<_>>();
+                    cmd_buffer.bind_graphics_descriptor_sets(get(&self.pipeline_layouts, layout), first_set, &sets);
+                }
+                Command::BindComputePipeline(pipeline) => {
+                    cmd_buffer.bind_compute_pipeline(get(&self.compute_pipelines, pipeline));
+                }
+                Command::Dispatch(x, y, z) => cmd_buffer.dispatch(x, y, z),
+                Command::DispatchIndirect { buffer, offset } => {
+                    cmd_buffer.dispatch_indirect(get(&self.buffers, buffer), offset);
+                }
+                Command::CopyBuffer { src, dst, ref regions } => {
+                    cmd_buffer.copy_buffer(get(&self.buffers, src), get(&self.buffers, dst), regions);
+                }
+                Command::CopyImage { src, src_layout, dst, dst_layout, ref regions } => {
+                    cmd_buffer.copy_image(get(&self.images, src), src_layout, get(&self.images, dst), dst_layout, regions);
+                }
+                Command::CopyBufferToImage { src, dst, dst_layout, ref regions } => {
+                    cmd_buffer.copy_buffer_to_image(get(&self.buffers, src), get(&self.images, dst), dst_layout, regions);
+                }
+                Command::CopyImageToBuffer { src, src_layout, dst, ref regions } => {
+                    cmd_buffer.copy_image_to_buffer(get(&self.images, src), src_layout, get(&self.buffers, dst), regions);
+                }
+                Command::Draw { ref vertices, ref instances } => {
+                    cmd_buffer.draw(vertices.clone(), instances.clone());
+                }
+                Command::DrawIndexed { ref indices, base_vertex, ref instances } => {
+                    cmd_buffer.draw_indexed(indices.clone(), base_vertex, instances.clone());
+                }
+                Command::DrawIndirect { buffer, offset, draw_count, stride } => {
+                    cmd_buffer.draw_indirect(get(&self.buffers, buffer), offset, draw_count, stride);
+                }
+                Command::DrawIndexedIndirect { buffer, offset, draw_count, stride } => {
+                    cmd_buffer.draw_indexed_indirect(get(&self.buffers, buffer), offset, draw_count, stride);
+                }
+                Command::BeginQuery { pool, query, flags } => {
+                    cmd_buffer.begin_query(query::Query { pool: get(&self.query_pools, pool), id: query }, flags);
+                }
+                Command::EndQuery { pool, query } => {
+                    cmd_buffer.end_query(query::Query { pool: get(&self.query_pools, pool), id: query });
+                }
+                Command::ResetQueryPool { pool, ref queries } => {
+                    cmd_buffer.reset_query_pool(get(&self.query_pools, pool), queries.clone());
+                }
+                Command::WriteTimestamp { stage, pool, query } => {
+                    cmd_buffer.write_timestamp(stage, query::Query { pool: get(&self.query_pools, pool), id: query });
+                }
+                Command::CopyQueryPoolResults { pool, ref queries, buffer, offset, stride, flags } => {
+                    cmd_buffer.copy_query_pool_results(get(&self.query_pools, pool), queries.clone(),
+                                                       get(&self.buffers, buffer), offset, stride, flags);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,269 @@
+//! Serializable representation of a captured command stream.
+
+use std::ops::Range;
+use core::{buffer, device, format, image, memory, pass, pso, query, target};
+use core::{IndexCount, IndexType, InstanceCount, VertexCount, VertexOffset, Viewport};
+use core::command::{BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageBlit, ImageCopy, ImageResolve,
+    SubpassContents};
+use core::image::ImageLayout;
+
+/// Stable identifier of a captured object.
+pub type Id = usize;
+
+/// Memory barrier, referencing resources by id.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Barrier {
+    AllBuffers(Range<buffer::Access>),
+    AllImages(Range<image::Access>),
+    Buffer {
+        states: Range<buffer::State>,
+        target: Id,
+        range: Range<u64>,
+    },
+    Image {
+        states: Range<image::State>,
+        target: Id,
+        range: image::SubresourceRange,
+    },
+}
+
+/// Command recorded into a command buffer, see `RawCommandBuffer`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Command {
+    PipelineBarrier {
+        stages: Range<pso::PipelineStage>,
+        barriers: Vec<Barrier>,
+    },
+    FillBuffer {
+        buffer: Id,
+        range: Range<u64>,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: Id,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    ClearColor {
+        target: Id,
+        layout: ImageLayout,
+        color: ClearColor,
+    },
+    ClearDepthStencil {
+        target: Id,
+        layout: ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
+    },
+    ResolveImage {
+        src: Id,
+        src_layout: ImageLayout,
+        dst: Id,
+        dst_layout: ImageLayout,
+        regions: Vec<ImageResolve>,
+    },
+    BlitImage {
+        src: Id,
+        src_layout: ImageLayout,
+        dst: Id,
+        dst_layout: ImageLayout,
+        regions: Vec<ImageBlit>,
+        filter: image::FilterMethod,
+    },
+    BindIndexBuffer {
+        buffer: Id,
+        offset: u64,
+        index_type: IndexType,
+    },
+    BindVertexBuffers(Vec<(Id, pso::BufferOffset)>),
+    SetViewports(Vec<Viewport>),
+    SetScissors(Vec<target::Rect>),
+    SetStencilReference {
+        front: target::Stencil,
+        back: target::Stencil,
+    },
+    SetBlendConstants(target::ColorValue),
+    BeginRenderPass {
+        render_pass: Id,
+        frame_buffer: Id,
+        render_area: target::Rect,
+        clear_values: Vec<ClearValue>,
+        first_subpass: SubpassContents,
+    },
+    NextSubpass(SubpassContents),
+    EndRenderPass,
+    BindGraphicsPipeline(Id),
+    BindGraphicsDescriptorSets {
+        layout: Id,
+        first_set: usize,
+        sets: Vec<Id>,
+    },
+    BindComputePipeline(Id),
+    Dispatch(u32, u32, u32),
+    DispatchIndirect {
+        buffer: Id,
+        offset: u64,
+    },
+    CopyBuffer {
+        src: Id,
+        dst: Id,
+        regions: Vec<BufferCopy>,
+    },
+    CopyImage {
+        src: Id,
+        src_layout: ImageLayout,
+        dst: Id,
+        dst_layout: ImageLayout,
+        regions: Vec<ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: Id,
+        dst: Id,
+        dst_layout: ImageLayout,
+        regions: Vec<BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: Id,
+        src_layout: ImageLayout,
+        dst: Id,
+        regions: Vec<BufferImageCopy>,
+    },
+    Draw {
+        vertices: Range<VertexCount>,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: Id,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: Id,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    BeginQuery {
+        pool: Id,
+        query: query::QueryId,
+        flags: query::QueryControl,
+    },
+    EndQuery {
+        pool: Id,
+        query: query::QueryId,
+    },
+    ResetQueryPool {
+        pool: Id,
+        queries: Range<query::QueryId>,
+    },
+    WriteTimestamp {
+        stage: pso::PipelineStage,
+        pool: Id,
+        query: query::QueryId,
+    },
+    CopyQueryPoolResults {
+        pool: Id,
+        queries: Range<query::QueryId>,
+        buffer: Id,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    },
+}
+
+/// Device level operation, in the order issued by the application.
+///
+/// Only objects which can be recreated from their description are part of
+/// the trace. Pipelines, pipeline and descriptor set layouts, descriptor sets
+/// and depth-stencil views depend on backend specific objects, their replay
+/// counterparts are provided by the caller of the `Replayer`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Event {
+    CreateBuffer {
+        id: Id,
+        size: u64,
+        stride: u64,
+        usage: buffer::Usage,
+        /// Properties of the heap the buffer was bound to.
+        properties: memory::HeapProperties,
+    },
+    CreateImage {
+        id: Id,
+        kind: image::Kind,
+        levels: image::Level,
+        format: format::Format,
+        usage: image::Usage,
+        /// Properties of the heap the image was bound to.
+        properties: memory::HeapProperties,
+    },
+    CreateRenderTargetView {
+        id: Id,
+        image: Id,
+        format: format::Format,
+        range: image::SubresourceRange,
+    },
+    CreateShaderResourceView {
+        id: Id,
+        image: Id,
+        format: format::Format,
+    },
+    CreateUnorderedAccessView {
+        id: Id,
+        image: Id,
+        format: format::Format,
+    },
+    CreateConstantBufferView {
+        id: Id,
+        buffer: Id,
+        range: Range<u64>,
+    },
+    CreateSampler {
+        id: Id,
+        info: image::SamplerInfo,
+    },
+    CreateRenderPass {
+        id: Id,
+        attachments: Vec<pass::Attachment>,
+        /// Color attachments of each subpass.
+        subpasses: Vec<Vec<pass::AttachmentRef>>,
+        dependencies: Vec<pass::SubpassDependency>,
+    },
+    CreateFrameBuffer {
+        id: Id,
+        render_pass: Id,
+        color_attachments: Vec<Id>,
+        depth_stencil_attachments: Vec<Id>,
+        extent: device::Extent,
+    },
+    CreateQueryPool {
+        id: Id,
+        ty: query::QueryType,
+        count: u32,
+    },
+    /// Data written to a buffer through a mapping.
+    WriteBuffer {
+        buffer: Id,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    /// Submission of one or multiple command buffers.
+    Submit(Vec<Vec<Command>>),
+    /// Destruction of an object created by one of the events above.
+    Destroy(Id),
+}
+
+/// Captured command stream.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Trace {
+    pub events: Vec<Event>,
+}
@@ -0,0 +1,100 @@
+use core::{self, FrameSync};
+use {next_id, Adapter, Capture};
+use native::{Image, RawImage};
+
+pub struct Surface<B: core::Backend> {
+    raw: B::Surface,
+}
+
+impl<B: core::Backend> Surface<B> {
+    /// Wrap a surface of the backend `B`.
+    pub fn new(raw: B::Surface) -> Self {
+        Surface { raw }
+    }
+}
+
+impl<B: core::Backend> core::Surface<Capture<B>> for Surface<B> {
+    fn supports_queue(&self, queue_family: &B::QueueFamily) -> bool {
+        self.raw.supports_queue(queue_family)
+    }
+
+    fn get_capabilities(&self, adapter: &Adapter<B>) -> core::SurfaceCapabilities {
+        self.raw.get_capabilities(&adapter.raw)
+    }
+
+    fn build_swapchain<C>(
+        &mut self,
+        config: core::SwapchainConfig,
+        present_queue: &core::CommandQueue<Capture<B>, C>,
+    ) -> Swapchain<B> {
+        let raw = present_queue
+            .as_raw()
+            .with_core::<C, _, _>(|queue| self.raw.build_swapchain(config, queue));
+        Swapchain::new(raw)
+    }
+
+    fn recreate_swapchain<C>(
+        &mut self,
+        old_swapchain: Swapchain<B>,
+        config: core::SwapchainConfig,
+        present_queue: &core::CommandQueue<Capture<B>, C>,
+    ) -> Swapchain<B> {
+        // The wrapped backbuffers point into the old swapchain, drop them first.
+        let Swapchain { raw: old_raw, backbuffers } = old_swapchain;
+        drop(backbuffers);
+        let raw = present_queue
+            .as_raw()
+            .with_core::<C, _, _>(|queue| self.raw.recreate_swapchain(*old_raw, config, queue));
+        Swapchain::new(raw)
+    }
+}
+
+pub struct Swapchain<B: core::Backend> {
+    // Boxed to keep the backbuffers in place, they are referenced by `backbuffers`.
+    raw: Box<B::Swapchain>,
+    backbuffers: Vec<core::Backbuffer<Capture<B>>>,
+}
+
+impl<B: core::Backend> Swapchain<B> {
+    fn new(raw: B::Swapchain) -> Self {
+        let mut raw = Box::new(raw);
+        let backbuffer_image = |image: &B::Image| Image {
+            raw: RawImage::Backbuffer(image),
+            id: next_id(),
+        };
+        let backbuffers = core::Swapchain::get_backbuffers(&mut *raw)
+            .iter()
+            .map(|backbuffer| core::Backbuffer {
+                color: backbuffer_image(&backbuffer.color),
+                depth_stencil: backbuffer.depth_stencil.as_ref().map(&backbuffer_image),
+            })
+            .collect();
+
+        Swapchain { raw, backbuffers }
+    }
+}
+
+impl<B: core::Backend> core::Swapchain<Capture<B>> for Swapchain<B> {
+    fn get_backbuffers(&mut self) -> &[core::Backbuffer<Capture<B>>] {
+        &self.backbuffers
+    }
+
+    fn acquire_frame(&mut self, sync: FrameSync<Capture<B>>) -> Result<core::Frame, core::AcquireError> {
+        let sync = match sync {
+            FrameSync::Semaphore(semaphore) => FrameSync::Semaphore(semaphore),
+            FrameSync::Fence(fence) => FrameSync::Fence(fence),
+        };
+        self.raw.acquire_frame(sync)
+    }
+
+    fn present<C>(
+        &mut self,
+        present_queue: &mut core::CommandQueue<Capture<B>, C>,
+        wait_semaphores: &[&B::Semaphore],
+    ) -> Result<core::Suboptimal, core::PresentError> {
+        let raw = &mut self.raw;
+        present_queue
+            .as_raw()
+            .with_core::<C, _, _>(|queue| raw.present(queue, wait_semaphores))
+    }
+}
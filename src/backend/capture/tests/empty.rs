@@ -0,0 +1,143 @@
+extern crate gfx_backend_capture as capture;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{buffer, pool, pso};
+use core::{Adapter, Device, QueueFamily, RawCommandBuffer, RawCommandPool, RawCommandQueue};
+use core::command::BufferCopy;
+use core::device::ResourceHeapType;
+use capture::{Replayer, Trace};
+use capture::trace::{Command, Event};
+
+fn open<B: core::Backend, A: Adapter<B>>(adapter: &A) -> core::Gpu<B> {
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    adapter.open(&descs)
+}
+
+struct Captured {
+    trace: Trace,
+    src: capture::Id,
+    dst: capture::Id,
+}
+
+fn capture_copy() -> Captured {
+    let adapter = capture::Adapter::<empty::Backend>::new(empty::Adapter::new());
+    let core::Gpu { mut device, mut general_queues, heap_types, .. } = open(&adapter);
+    let mut queue = general_queues.remove(0);
+
+    let heap = device.create_heap(&heap_types[1], ResourceHeapType::Buffers, 1024).unwrap();
+    let unbound = device.create_buffer(16, 0, buffer::TRANSFER_SRC).unwrap();
+    let src = device.bind_buffer_memory(&heap, 0, unbound).unwrap();
+    let unbound = device.create_buffer(16, 0, buffer::TRANSFER_DST | buffer::VERTEX).unwrap();
+    let dst = device.bind_buffer_memory(&heap, 256, unbound).unwrap();
+    {
+        let mut writer = device.write_mapping::<u8>(&src, 0, 16).unwrap();
+        for (i, byte) in writer.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+    }
+
+    let mut pool = unsafe {
+        capture::CommandPool::from_queue(queue.as_raw(), pool::CommandPoolCreateFlags::empty())
+    };
+    let mut cmd_buffer = pool.allocate(1).pop().unwrap();
+    cmd_buffer.begin();
+    cmd_buffer.copy_buffer(&src, &dst, &[BufferCopy { src: 0, dst: 0, size: 16 }]);
+    cmd_buffer.pipeline_barrier(pso::TRANSFER .. pso::VERTEX_INPUT, &[]);
+    cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(vec![(&dst, 0)]));
+    cmd_buffer.draw(0 .. 4, 0 .. 1);
+    cmd_buffer.finish();
+
+    unsafe {
+        queue.as_mut().submit_raw(core::RawSubmission {
+            cmd_buffers: &[cmd_buffer],
+            wait_semaphores: &[],
+            signal_semaphores: &[],
+        }, None);
+    }
+
+    let captured = Captured {
+        trace: device.trace(),
+        src: src.id(),
+        dst: dst.id(),
+    };
+    device.destroy_buffer(src);
+    device.destroy_buffer(dst);
+    device.destroy_heap(heap);
+    captured
+}
+
+#[test]
+fn capture_events() {
+    let Captured { trace, src, dst } = capture_copy();
+    assert_eq!(trace.events.len(), 4);
+
+    match trace.events[0] {
+        Event::CreateBuffer { id, size: 16, usage, .. } => {
+            assert_eq!(id, src);
+            assert_eq!(usage, buffer::TRANSFER_SRC);
+        }
+        ref other => panic!("Unexpected event {:?}", other),
+    }
+    match trace.events[1] {
+        Event::CreateBuffer { id, size: 16, .. } => assert_eq!(id, dst),
+        ref other => panic!("Unexpected event {:?}", other),
+    }
+    match trace.events[2] {
+        Event::WriteBuffer { buffer, offset: 0, ref data } => {
+            assert_eq!(buffer, src);
+            assert_eq!(data, &(0 .. 16).collect::<Vec<u8>>());
+        }
+        ref other => panic!("Unexpected event {:?}", other),
+    }
+    match trace.events[3] {
+        Event::Submit(ref cmd_buffers) => match &cmd_buffers[..] {
+            &[ref commands] => match &commands[..] {
+                &[Command::CopyBuffer { src: s, dst: d, .. }, Command::PipelineBarrier { .. }, Command::BindVertexBuffers(ref vbs), Command::Draw { .. }] => {
+                    assert_eq!((s, d), (src, dst));
+                    assert_eq!(vbs, &vec![(dst, 0)]);
+                }
+                other => panic!("Unexpected commands {:?}", other),
+            },
+            other => panic!("Unexpected command buffers {:?}", other),
+        },
+        ref other => panic!("Unexpected event {:?}", other),
+    }
+}
+
+#[test]
+fn replay_on_empty() {
+    let Captured { trace, src, dst } = capture_copy();
+
+    let adapter = empty::Adapter::new();
+    let core::Gpu { mut device, mut general_queues, heap_types, .. } = open(&adapter);
+    let mut queue = general_queues.remove(0);
+
+    let mut replayer = Replayer::<empty::Backend>::new(&heap_types);
+    replayer.replay(&trace, &mut device, &mut queue);
+
+    {
+        let reader = device.read_mapping::<u8>(replayer.buffer(src).unwrap(), 0, 16).unwrap();
+        assert_eq!(&reader[..], &(0 .. 16).collect::<Vec<u8>>()[..]);
+    }
+
+    let submissions = queue.as_raw().submissions();
+    assert_eq!(submissions.len(), 1);
+    match &submissions[0].command_buffers[..] {
+        &[ref commands] => match &commands[..] {
+            &[empty::Command::CopyBuffer { src: s, dst: d, .. }, empty::Command::PipelineBarrier { .. }, empty::Command::BindVertexBuffers(ref vbs), empty::Command::Draw { .. }] => {
+                assert_eq!(s, replayer.buffer(src).unwrap().id());
+                assert_eq!(d, replayer.buffer(dst).unwrap().id());
+                assert_eq!(vbs, &vec![(d, 0)]);
+            }
+            other => panic!("Unexpected commands {:?}", other),
+        },
+        other => panic!("Unexpected command buffers {:?}", other),
+    }
+
+    replayer.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
@@ -2,7 +2,7 @@ use wio::com::ComPtr;
 use core::{command, memory, pso, shade, state, target, texture};
 use core::{IndexType, VertexCount, VertexOffset, Viewport};
 use core::buffer::IndexBufferView;
-use core::command::{BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageCopy, ImageResolve,
+use core::command::{BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageBlit, ImageCopy, ImageResolve,
                     InstanceParams, SubpassContents};
 use winapi::{self, UINT8, FLOAT, UINT, UINT64};
 use {native as n, Backend};
@@ -156,6 +156,20 @@ impl command::RawCommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    fn blit_image(
+        &mut self,
+        _src: &n::Image,
+        _src_layout: texture::ImageLayout,
+        _dst: &n::Image,
+        _dst_layout: texture::ImageLayout,
+        _regions: &[ImageBlit],
+        _filter: texture::FilterMethod,
+    ) {
+        // D3D12 has no blit command, blits are skipped until a fullscreen triangle pass
+        // sampling the source region is implemented.
+        error!("Blitting images is not supported");
+    }
+
     fn bind_index_buffer(&mut self, ibv: IndexBufferView<Backend>) {
         let format = match ibv.index_type {
             IndexType::U16 => winapi::DXGI_FORMAT_R16_UINT,
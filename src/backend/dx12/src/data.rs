@@ -117,5 +117,43 @@ pub fn map_format(format: Format, is_target: bool) -> Option<DXGI_FORMAT> {
             (false, Uint)  => DXGI_FORMAT_X32_TYPELESS_G8X24_UINT,
             _ => return None,
         },
+        // Compressed formats can't be rendered to.
+        _ if is_target && format.0.is_compressed() => return None,
+        BC1_R8_G8_B8 | BC1_R8_G8_B8_A8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC1_UNORM,
+            Srgb  => DXGI_FORMAT_BC1_UNORM_SRGB,
+            _ => return None,
+        },
+        BC2_R8_G8_B8_A8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC2_UNORM,
+            Srgb  => DXGI_FORMAT_BC2_UNORM_SRGB,
+            _ => return None,
+        },
+        BC3_R8_G8_B8_A8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC3_UNORM,
+            Srgb  => DXGI_FORMAT_BC3_UNORM_SRGB,
+            _ => return None,
+        },
+        BC4_R8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC4_UNORM,
+            Inorm => DXGI_FORMAT_BC4_SNORM,
+            _ => return None,
+        },
+        BC5_R8_G8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC5_UNORM,
+            Inorm => DXGI_FORMAT_BC5_SNORM,
+            _ => return None,
+        },
+        BC6_R16_G16_B16 => match format.1 {
+            Float => DXGI_FORMAT_BC6H_UF16,
+            _ => return None,
+        },
+        BC7_R8_G8_B8_A8 => match format.1 {
+            Unorm => DXGI_FORMAT_BC7_UNORM,
+            Srgb  => DXGI_FORMAT_BC7_UNORM_SRGB,
+            _ => return None,
+        },
+        // ETC2, EAC and ASTC aren't available on D3D12.
+        _ => return None,
     })
 }
@@ -1,10 +1,196 @@
 //! Dummy backend implementation to test the code for compile errors
 //! outside of the graphics development environment.
+//!
+//! The backend doesn't talk to any GPU. Instead, it hands out valid handles,
+//! keeps track of all live objects created by a `Device` and records every
+//! call issued to a `RawCommandBuffer` into an inspectable `Command` log.
+//! Submissions are executed instantly, meaning that fences are signalled
+//! as soon as the submission reaches the `CommandQueue`. Adapters created with
+//! `Adapter::new_deferred` instead keep submissions pending until the device
+//! blocks on one of their fences, to test code waiting for the GPU.
+//!
+//! Queries complete along with their submission, with deterministic results: occlusion
+//! and pipeline statistics queries count zero, while each timestamp written by a
+//! queue is one tick after the previous one.
 
 extern crate gfx_core as core;
 
+use std::{fmt, hash, mem, slice};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
 use std::ops::Range;
-use core::{buffer, command, device, format, image, target, mapping, memory, pass, pool, pso};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use core::{buffer, command, device, format, image, target, mapping, memory, pass, pool, pso, query};
+use core::QueueType;
+
+/// Alignment of buffers placed into a heap.
+const BUFFER_ALIGNMENT: u64 = 16;
+/// Alignment of images placed into a heap.
+const IMAGE_ALIGNMENT: u64 = 256;
+/// Size of each of the memory heaps exposed by the adapter.
+const HEAP_SIZE: u64 = 1 << 30;
+/// Maximum number of images in a swapchain.
+const MAX_BACKBUFFERS: u32 = 8;
+
+/// Unique identifier of an object created by the dummy backend.
+pub type Id = usize;
+
+static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn next_id() -> Id {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Type of an object tracked by the dummy device.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ObjectKind {
+    ///
+    Heap,
+    ///
+    ShaderLib,
+    ///
+    RenderPass,
+    ///
+    FrameBuffer,
+    ///
+    Buffer,
+    ///
+    Image,
+    ///
+    Sampler,
+    ///
+    ConstantBufferView,
+    ///
+    ShaderResourceView,
+    ///
+    UnorderedAccessView,
+    ///
+    RenderTargetView,
+    ///
+    DepthStencilView,
+    ///
+    ComputePipeline,
+    ///
+    GraphicsPipeline,
+    ///
+    PipelineCache,
+    ///
+    PipelineLayout,
+    ///
+    DescriptorPool,
+    ///
+    DescriptorSetLayout,
+    ///
+    DescriptorSet,
+    ///
+    Fence,
+    ///
+    Semaphore,
+    ///
+    QueryPool,
+}
+
+// Set of live objects, shared between the device and objects which allocate
+// further objects on their own (e.g descriptor pools).
+type Registry = Arc<Mutex<HashMap<Id, ObjectKind>>>;
+/// Submissions of deferred queues which haven't completed yet, in submission order.
+type PendingSubmissions = Arc<Mutex<Vec<PendingSubmission>>>;
+
+/// Query results written by a submission.
+struct QueryWrite {
+    pool: QueryPool,
+    queries: Range<query::QueryId>,
+    values: Option<Vec<u64>>,
+}
+
+/// Effects of a deferred submission, applied once it completes.
+struct PendingSubmission {
+    fence: Option<Arc<AtomicBool>>,
+    queries: Vec<QueryWrite>,
+}
+
+impl PendingSubmission {
+    fn complete(self) {
+        for write in self.queries {
+            write.pool.set(write.queries, write.values);
+        }
+        if let Some(fence) = self.fence {
+            fence.store(true, Ordering::Release);
+        }
+    }
+}
+
+fn register(registry: &Registry, kind: ObjectKind) -> Id {
+    let id = next_id();
+    registry.lock().unwrap().insert(id, kind);
+    id
+}
+
+fn unregister(registry: &Registry, id: Id, kind: ObjectKind) {
+    match registry.lock().unwrap().remove(&id) {
+        Some(ref live) if *live == kind => (),
+        Some(live) => panic!("Destroying {:?} {} as {:?}", live, id, kind),
+        None => panic!("Destroying {:?} {} which is not alive", kind, id),
+    }
+}
+
+// Implements identity based comparison and hashing for handles.
+macro_rules! impl_handle {
+    ($name:ident) => {
+        impl $name {
+            /// Unique identifier of the object.
+            pub fn id(&self) -> Id {
+                self.id
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &$name) -> bool {
+                self.id == other.id
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl hash::Hash for $name {
+            fn hash<H: hash::Hasher>(&self, state: &mut H) {
+                self.id.hash(state)
+            }
+        }
+    }
+}
+
+// Defines handles which don't carry any information besides their identity.
+macro_rules! define_handles {
+    ($($name:ident,)*) => {
+        $(
+            /// Dummy handle.
+            #[derive(Clone, Debug)]
+            pub struct $name {
+                id: Id,
+            }
+            impl_handle!($name);
+        )*
+    }
+}
+
+define_handles! {
+    ShaderLib,
+    FrameBuffer,
+    Sampler,
+    ConstantBufferView,
+    ShaderResourceView,
+    UnorderedAccessView,
+    RenderTargetView,
+    DepthStencilView,
+    ComputePipeline,
+    GraphicsPipeline,
+    PipelineLayout,
+    DescriptorSetLayout,
+    DescriptorSet,
+    Semaphore,
+}
 
 /// Dummy backend.
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -21,266 +207,1099 @@ impl core::Backend for Backend {
     type SubpassCommandBuffer = SubpassCommandBuffer;
     type QueueFamily = QueueFamily;
 
-    type Heap = ();
-    type Mapping = ();
+    type Heap = Heap;
+    type Mapping = Mapping;
     type CommandPool = RawCommandPool;
     type SubpassCommandPool = SubpassCommandPool;
 
-    type ShaderLib = ();
-    type RenderPass = ();
-    type FrameBuffer = ();
-
-    type UnboundBuffer = ();
-    type Buffer = ();
-    type UnboundImage = ();
-    type Image = ();
-    type Sampler = ();
-
-    type ConstantBufferView = ();
-    type ShaderResourceView = ();
-    type UnorderedAccessView = ();
-    type RenderTargetView = ();
-    type DepthStencilView = ();
-
-    type ComputePipeline = ();
-    type GraphicsPipeline = ();
-    type PipelineLayout = ();
-    type DescriptorSetLayout = ();
+    type ShaderLib = ShaderLib;
+    type RenderPass = RenderPass;
+    type FrameBuffer = FrameBuffer;
+
+    type UnboundBuffer = UnboundBuffer;
+    type Buffer = Buffer;
+    type UnboundImage = UnboundImage;
+    type Image = Image;
+    type Sampler = Sampler;
+
+    type ConstantBufferView = ConstantBufferView;
+    type ShaderResourceView = ShaderResourceView;
+    type UnorderedAccessView = UnorderedAccessView;
+    type RenderTargetView = RenderTargetView;
+    type DepthStencilView = DepthStencilView;
+
+    type ComputePipeline = ComputePipeline;
+    type GraphicsPipeline = GraphicsPipeline;
+    type PipelineCache = PipelineCache;
+    type PipelineLayout = PipelineLayout;
+    type DescriptorSetLayout = DescriptorSetLayout;
     type DescriptorPool = DescriptorPool;
-    type DescriptorSet = ();
+    type DescriptorSet = DescriptorSet;
+
+    type QueryPool = QueryPool;
 
-    type Fence = ();
-    type Semaphore = ();
+    type Fence = Fence;
+    type Semaphore = Semaphore;
+}
+
+// Host memory backing a heap.
+// Aliasing between mapped ranges is left to the user, like with real device memory.
+struct HostMemory(UnsafeCell<Vec<u64>>);
+unsafe impl Send for HostMemory {}
+unsafe impl Sync for HostMemory {}
+
+impl HostMemory {
+    fn new(size: u64) -> Self {
+        let words = (size as usize + mem::size_of::<u64>() - 1) / mem::size_of::<u64>();
+        HostMemory(UnsafeCell::new(vec![0; words]))
+    }
+
+    fn as_ptr(&self) -> *mut u8 {
+        unsafe { (*self.0.get()).as_mut_ptr() as *mut u8 }
+    }
+}
+
+impl fmt::Debug for HostMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HostMemory({:p})", self.as_ptr())
+    }
+}
+
+/// Dummy heap, backed by host memory.
+#[derive(Debug)]
+pub struct Heap {
+    id: Id,
+    size: u64,
+    properties: memory::HeapProperties,
+    memory: Arc<HostMemory>,
+}
+impl_handle!(Heap);
+
+/// Dummy mapping, keeping the mapped memory alive.
+#[derive(Debug)]
+pub struct Mapping {
+    _memory: Arc<HostMemory>,
+}
+
+/// Dummy buffer without memory.
+#[derive(Clone, Debug)]
+pub struct UnboundBuffer {
+    size: u64,
+    usage: buffer::Usage,
+}
+
+/// Dummy buffer bound to a heap.
+#[derive(Clone, Debug)]
+pub struct Buffer {
+    id: Id,
+    size: u64,
+    usage: buffer::Usage,
+    cpu_visible: bool,
+    offset: u64,
+    memory: Arc<HostMemory>,
+}
+impl_handle!(Buffer);
+
+impl Buffer {
+    /// Size of the buffer in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Usage the buffer has been created with.
+    pub fn usage(&self) -> buffer::Usage {
+        self.usage
+    }
+}
+
+/// Dummy image without memory.
+#[derive(Clone, Debug)]
+pub struct UnboundImage {
+    kind: image::Kind,
+    levels: image::Level,
+    format: format::Format,
+    usage: image::Usage,
+}
+
+/// Dummy image.
+#[derive(Clone, Debug)]
+pub struct Image {
+    id: Id,
+    kind: image::Kind,
+    levels: image::Level,
+    format: format::Format,
+    usage: image::Usage,
+}
+impl_handle!(Image);
+
+impl Image {
+    /// Kind of the image.
+    pub fn kind(&self) -> image::Kind {
+        self.kind
+    }
+
+    /// Number of mipmap levels.
+    pub fn levels(&self) -> image::Level {
+        self.levels
+    }
+
+    /// Format of the image.
+    pub fn format(&self) -> format::Format {
+        self.format
+    }
+
+    /// Usage the image has been created with.
+    pub fn usage(&self) -> image::Usage {
+        self.usage
+    }
+}
+
+/// Dummy render pass.
+#[derive(Clone, Debug)]
+pub struct RenderPass {
+    id: Id,
+    num_subpasses: usize,
+}
+impl_handle!(RenderPass);
+
+/// Dummy pipeline cache, keeping the data it was created with.
+#[derive(Debug)]
+pub struct PipelineCache {
+    id: Id,
+    data: Mutex<Vec<u8>>,
+}
+impl_handle!(PipelineCache);
+
+/// Dummy fence.
+#[derive(Clone, Debug)]
+pub struct Fence {
+    id: Id,
+    signaled: Arc<AtomicBool>,
+}
+impl_handle!(Fence);
+
+impl Fence {
+    /// Check if the fence has been signalled.
+    pub fn is_signaled(&self) -> bool {
+        self.signaled.load(Ordering::Acquire)
+    }
+}
+
+/// Dummy query pool, holding the results written by submitted commands.
+#[derive(Clone, Debug)]
+pub struct QueryPool {
+    id: Id,
+    ty: query::QueryType,
+    // Values of each query, `None` while unavailable.
+    results: Arc<Mutex<Vec<Option<Vec<u64>>>>>,
+}
+impl_handle!(QueryPool);
+
+impl QueryPool {
+    fn set(&self, queries: Range<query::QueryId>, values: Option<Vec<u64>>) {
+        let mut results = self.results.lock().unwrap();
+        for result in &mut results[queries.start as usize .. queries.end as usize] {
+            *result = values.clone();
+        }
+    }
 }
 
 /// Dummy adapter.
-pub struct Adapter;
+pub struct Adapter {
+    info: core::AdapterInfo,
+    queue_families: Vec<(QueueFamily, QueueType)>,
+    deferred: bool,
+}
+
+impl Adapter {
+    /// Create a new dummy adapter, exposing one general, compute and transfer queue family.
+    pub fn new() -> Self {
+        Adapter {
+            info: core::AdapterInfo {
+                name: "Dummy adapter".into(),
+                vendor: 0,
+                device: 0,
+                software_rendering: true,
+            },
+            queue_families: vec![
+                (QueueFamily { num_queues: 1 }, QueueType::General),
+                (QueueFamily { num_queues: 1 }, QueueType::Compute),
+                (QueueFamily { num_queues: 1 }, QueueType::Transfer),
+            ],
+            deferred: false,
+        }
+    }
+
+    /// Create a new dummy adapter, whose submissions only complete once the device waits on
+    /// a fence with a non-zero timeout.
+    ///
+    /// Waiting on a fence completes its submission and all the ones submitted before it,
+    /// including the results of their queries.
+    pub fn new_deferred() -> Self {
+        Adapter {
+            deferred: true,
+            .. Adapter::new()
+        }
+    }
+}
+
 impl core::Adapter<Backend> for Adapter {
-    fn open(&self, _: &[(&QueueFamily, core::QueueType, u32)]) -> core::Gpu<Backend> {
-        unimplemented!()
+    fn open(&self, queue_descs: &[(&QueueFamily, QueueType, u32)]) -> core::Gpu<Backend> {
+        let pending = Arc::new(Mutex::new(Vec::new()));
+        let mut gpu = core::Gpu {
+            device: Device::new(pending.clone()),
+            general_queues: Vec::new(),
+            graphics_queues: Vec::new(),
+            compute_queues: Vec::new(),
+            transfer_queues: Vec::new(),
+            heap_types: vec![
+                core::HeapType {
+                    id: 0,
+                    properties: memory::DEVICE_LOCAL,
+                    heap_index: 0,
+                },
+                core::HeapType {
+                    id: 1,
+                    properties: memory::CPU_VISIBLE | memory::COHERENT,
+                    heap_index: 1,
+                },
+                core::HeapType {
+                    id: 2,
+                    properties: memory::CPU_VISIBLE | memory::CPU_CACHED,
+                    heap_index: 1,
+                },
+            ],
+            memory_heaps: vec![HEAP_SIZE, HEAP_SIZE],
+        };
+
+        for &(family, qtype, count) in queue_descs {
+            assert!(count <= family.num_queues);
+            for _ in 0 .. count {
+                let queue = CommandQueue::new(if self.deferred { Some(pending.clone()) } else { None });
+                unsafe {
+                    match qtype {
+                        QueueType::General => gpu.general_queues.push(core::CommandQueue::new(queue)),
+                        QueueType::Graphics => gpu.graphics_queues.push(core::CommandQueue::new(queue)),
+                        QueueType::Compute => gpu.compute_queues.push(core::CommandQueue::new(queue)),
+                        QueueType::Transfer => gpu.transfer_queues.push(core::CommandQueue::new(queue)),
+                    }
+                }
+            }
+        }
+
+        gpu
     }
 
     fn get_info(&self) -> &core::AdapterInfo {
-        unimplemented!()
+        &self.info
+    }
+
+    fn get_queue_families(&self) -> &[(QueueFamily, QueueType)] {
+        &self.queue_families
     }
+}
+
+/// Command recorded by a dummy command buffer.
+///
+/// Resources are referenced by their `Id`.
+#[allow(missing_docs)]
+#[derive(Clone, Debug)]
+pub enum Command {
+    PipelineBarrier {
+        stages: Range<pso::PipelineStage>,
+        barriers: Vec<Barrier>,
+    },
+    FillBuffer {
+        buffer: Id,
+        range: Range<u64>,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: Id,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    ClearColor {
+        view: Id,
+        layout: image::ImageLayout,
+        color: command::ClearColor,
+    },
+    ClearDepthStencil {
+        view: Id,
+        layout: image::ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
+    },
+    ResolveImage {
+        src: Id,
+        src_layout: image::ImageLayout,
+        dst: Id,
+        dst_layout: image::ImageLayout,
+        regions: Vec<command::ImageResolve>,
+    },
+    BlitImage {
+        src: Id,
+        src_layout: image::ImageLayout,
+        dst: Id,
+        dst_layout: image::ImageLayout,
+        regions: Vec<command::ImageBlit>,
+        filter: image::FilterMethod,
+    },
+    BindIndexBuffer {
+        buffer: Id,
+        offset: u64,
+        index_type: core::IndexType,
+    },
+    BindVertexBuffers(Vec<(Id, pso::BufferOffset)>),
+    SetViewports(Vec<core::Viewport>),
+    SetScissors(Vec<target::Rect>),
+    SetStencilReference {
+        front: target::Stencil,
+        back: target::Stencil,
+    },
+    SetBlendConstants(target::ColorValue),
+    BeginRenderPass {
+        render_pass: Id,
+        frame_buffer: Id,
+        area: target::Rect,
+        clear_values: Vec<command::ClearValue>,
+        contents: command::SubpassContents,
+    },
+    NextSubpass(command::SubpassContents),
+    EndRenderPass,
+    BindGraphicsPipeline(Id),
+    BindGraphicsDescriptorSets {
+        layout: Id,
+        first_set: usize,
+        sets: Vec<Id>,
+    },
+    BindComputePipeline(Id),
+    Dispatch(u32, u32, u32),
+    DispatchIndirect {
+        buffer: Id,
+        offset: u64,
+    },
+    CopyBuffer {
+        src: Id,
+        dst: Id,
+        regions: Vec<command::BufferCopy>,
+    },
+    CopyImage {
+        src: Id,
+        src_layout: image::ImageLayout,
+        dst: Id,
+        dst_layout: image::ImageLayout,
+        regions: Vec<command::ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: Id,
+        dst: Id,
+        dst_layout: image::ImageLayout,
+        regions: Vec<command::BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: Id,
+        src_layout: image::ImageLayout,
+        dst: Id,
+        regions: Vec<command::BufferImageCopy>,
+    },
+    Draw {
+        vertices: Range<core::VertexCount>,
+        instances: Range<core::InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<core::IndexCount>,
+        base_vertex: core::VertexOffset,
+        instances: Range<core::InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: Id,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: Id,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    BeginQuery {
+        pool: Id,
+        query: query::QueryId,
+        flags: query::QueryControl,
+    },
+    EndQuery {
+        pool: Id,
+        query: query::QueryId,
+    },
+    ResetQueryPool {
+        pool: Id,
+        queries: Range<query::QueryId>,
+    },
+    WriteTimestamp {
+        stage: pso::PipelineStage,
+        pool: Id,
+        query: query::QueryId,
+    },
+    CopyQueryPoolResults {
+        pool: Id,
+        queries: Range<query::QueryId>,
+        buffer: Id,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    },
+}
 
-    fn get_queue_families(&self) -> &[(QueueFamily, core::QueueType)] {
-        unimplemented!()
+/// Recorded memory barrier, see `memory::Barrier`.
+#[allow(missing_docs)]
+#[derive(Clone, Debug)]
+pub enum Barrier {
+    AllBuffers(Range<buffer::Access>),
+    AllImages(Range<image::Access>),
+    Buffer {
+        states: Range<buffer::State>,
+        target: Id,
+        range: Range<u64>,
+    },
+    Image {
+        states: Range<image::State>,
+        target: Id,
+        range: image::SubresourceRange,
+    },
+}
+
+impl<'a> From<&'a memory::Barrier<'a, Backend>> for Barrier {
+    fn from(barrier: &'a memory::Barrier<'a, Backend>) -> Self {
+        match *barrier {
+            memory::Barrier::AllBuffers(ref access) => Barrier::AllBuffers(access.clone()),
+            memory::Barrier::AllImages(ref access) => Barrier::AllImages(access.clone()),
+            memory::Barrier::Buffer { ref states, target, ref range } => Barrier::Buffer {
+                states: states.clone(),
+                target: target.id,
+                range: range.clone(),
+            },
+            memory::Barrier::Image { ref states, target, ref range } => Barrier::Image {
+                states: states.clone(),
+                target: target.id,
+                range: range.clone(),
+            },
+        }
+    }
+}
+
+/// Submission received by a dummy command queue.
+#[derive(Clone, Debug)]
+pub struct Submission {
+    /// Commands of each submitted command buffer.
+    pub command_buffers: Vec<Vec<Command>>,
+    /// Semaphores waited on, with the corresponding pipeline stages.
+    pub wait_semaphores: Vec<(Id, pso::PipelineStage)>,
+    /// Semaphores signalled after execution.
+    pub signal_semaphores: Vec<Id>,
+    /// Fence signalled after execution.
+    pub fence: Option<Id>,
+}
+
+/// Dummy command queue, executing submissions instantly.
+pub struct CommandQueue {
+    submissions: Vec<Submission>,
+    timestamp: u64,
+    // Submissions completed once the device waits for them, if completion is deferred.
+    pending: Option<PendingSubmissions>,
+}
+
+impl CommandQueue {
+    fn new(pending: Option<PendingSubmissions>) -> Self {
+        CommandQueue {
+            submissions: Vec::new(),
+            timestamp: 0,
+            pending,
+        }
+    }
+
+    fn write_queries(&mut self, cmd_buffer: &RawCommandBuffer, writes: &mut Vec<QueryWrite>) {
+        let pool = |id| cmd_buffer.query_pools
+            .iter()
+            .find(|pool| pool.id == id)
+            .expect("Query pool not referenced by the command buffer")
+            .clone();
+        for command in &cmd_buffer.commands {
+            let write = match *command {
+                Command::ResetQueryPool { pool: id, ref queries } => {
+                    QueryWrite { pool: pool(id), queries: queries.clone(), values: None }
+                }
+                Command::EndQuery { pool: id, query } => {
+                    let pool = pool(id);
+                    let values = Some(vec![0; pool.ty.num_values()]);
+                    QueryWrite { pool, queries: query .. query + 1, values }
+                }
+                Command::WriteTimestamp { pool: id, query, .. } => {
+                    // Timestamps count in submission order, even if completed later.
+                    self.timestamp += 1;
+                    QueryWrite { pool: pool(id), queries: query .. query + 1, values: Some(vec![self.timestamp]) }
+                }
+                _ => continue,
+            };
+            writes.push(write);
+        }
+    }
+
+    /// All submissions received by the queue so far.
+    pub fn submissions(&self) -> &[Submission] {
+        &self.submissions
+    }
+
+    /// Clear the list of received submissions.
+    pub fn clear_submissions(&mut self) {
+        self.submissions.clear();
     }
 }
 
-/// Dummy command queue doing nothing.
-pub struct CommandQueue;
 impl core::RawCommandQueue<Backend> for CommandQueue {
-    unsafe fn submit_raw(&mut self, _: core::RawSubmission<Backend>, _: Option<&()>) {
-        unimplemented!()
+    unsafe fn submit_raw(&mut self, submission: core::RawSubmission<Backend>, fence: Option<&Fence>) {
+        self.submissions.push(Submission {
+            command_buffers: submission.cmd_buffers
+                .iter()
+                .map(|cmd_buffer| cmd_buffer.commands.clone())
+                .collect(),
+            wait_semaphores: submission.wait_semaphores
+                .iter()
+                .map(|&(semaphore, stage)| (semaphore.id, stage))
+                .collect(),
+            signal_semaphores: submission.signal_semaphores
+                .iter()
+                .map(|semaphore| semaphore.id)
+                .collect(),
+            fence: fence.map(|fence| fence.id),
+        });
+        let mut queries = Vec::new();
+        for cmd_buffer in submission.cmd_buffers {
+            self.write_queries(cmd_buffer, &mut queries);
+        }
+
+        let pending = PendingSubmission {
+            fence: fence.map(|fence| fence.signaled.clone()),
+            queries,
+        };
+        match self.pending {
+            Some(ref submissions) => {
+                if pending.fence.is_some() || !pending.queries.is_empty() {
+                    submissions.lock().unwrap().push(pending);
+                }
+            }
+            None => pending.complete(),
+        }
+    }
+}
+
+/// Dummy device, keeping track of all created objects.
+pub struct Device {
+    features: core::Features,
+    limits: core::Limits,
+    heap_sizes: Vec<u64>,
+    objects: Registry,
+    pending: PendingSubmissions,
+}
+
+impl Device {
+    fn new(pending: PendingSubmissions) -> Self {
+        Device {
+            features: core::Features {
+                indirect_execution: true,
+                draw_instanced: true,
+                draw_instanced_base: true,
+                draw_indexed_base: true,
+                draw_indexed_instanced: true,
+                draw_indexed_instanced_base_vertex: true,
+                draw_indexed_instanced_base: true,
+                instance_rate: true,
+                vertex_base: true,
+                srgb_color: true,
+                constant_buffer: true,
+                unordered_access_view: true,
+                separate_blending_slots: true,
+                copy_buffer: true,
+                sampler_objects: true,
+                sampler_lod_bias: true,
+                sampler_anisotropy: true,
+                sampler_border_color: true,
+                precise_occlusion_query: true,
+                pipeline_statistics_query: true,
+                timestamp_query: true,
+            },
+            limits: core::Limits {
+                max_texture_size: 1 << 14,
+                max_patch_size: 32,
+                max_viewports: 16,
+                min_buffer_copy_offset_alignment: 4,
+                min_buffer_copy_pitch_alignment: 4,
+                min_constant_buffer_offset_alignment: 256,
+                timestamp_period: 1.0,
+            },
+            heap_sizes: vec![HEAP_SIZE, HEAP_SIZE, HEAP_SIZE],
+            objects: Arc::new(Mutex::new(HashMap::new())),
+            pending,
+        }
     }
+
+    /// All objects created by the device, which haven't been destroyed yet.
+    ///
+    /// The objects are sorted by their creation order.
+    pub fn live_objects(&self) -> Vec<(Id, ObjectKind)> {
+        let mut objects = self.objects
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(&id, &kind)| (id, kind))
+            .collect::<Vec<_>>();
+        objects.sort_by_key(|&(id, _)| id);
+        objects
+    }
+
+    /// Check if the object with the given id is still alive.
+    pub fn is_alive(&self, id: Id) -> bool {
+        self.objects.lock().unwrap().contains_key(&id)
+    }
+
+    fn map_buffer<T>(&self, buffer: &Buffer, offset: u64, size: u64) -> Result<(*mut T, Mapping), mapping::Error> {
+        if !buffer.cpu_visible || offset + size > buffer.size {
+            return Err(mapping::Error::InvalidAccess);
+        }
+
+        let ptr = unsafe {
+            buffer.memory.as_ptr().offset((buffer.offset + offset) as isize)
+        };
+        if ptr as usize % mem::align_of::<T>() != 0 {
+            return Err(mapping::Error::InvalidAccess);
+        }
+
+        Ok((ptr as *mut T, Mapping { _memory: buffer.memory.clone() }))
+    }
+}
+
+fn image_byte_count(kind: image::Kind, levels: image::Level, format: format::Format) -> u64 {
+    let layers = kind.get_num_slices().unwrap_or(1) as u64 * if kind.is_cube() { 6 } else { 1 };
+    let bytes = (0 .. levels)
+        .map(|level| {
+            let (w, h, d, _) = kind.get_level_dimensions(level);
+            format.0.get_byte_count(w.max(1) as u32, h.max(1) as u32, d.max(1) as u32) as u64
+        })
+        .sum::<u64>();
+    bytes * layers
 }
 
-/// Dummy device doing nothing.
-pub struct Device;
 impl core::Device<Backend> for Device {
     fn get_features(&self) -> &core::Features {
-        unimplemented!()
+        &self.features
     }
 
     fn get_limits(&self) -> &core::Limits {
-        unimplemented!()
+        &self.limits
+    }
+
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties {
+        use core::format::SurfaceType::*;
+        use core::format::ChannelType::{Int, Uint};
+        let (image_features, buffer_features) = match format.0 {
+            D16 | D24 | D24_S8 | D32 | D32_S8 => (
+                format::SAMPLED | format::DEPTH_STENCIL_ATTACHMENT | format::BLIT_SRC,
+                format::BufferFeature::empty(),
+            ),
+            surface if surface.is_compressed() => (
+                format::SAMPLED | format::SAMPLED_LINEAR | format::BLIT_SRC,
+                format::BufferFeature::empty(),
+            ),
+            _ => {
+                let mut features = format::ImageFeature::all();
+                features.remove(format::DEPTH_STENCIL_ATTACHMENT);
+                // Integer formats can't be filtered or blended.
+                if format.1 == Int || format.1 == Uint {
+                    features.remove(format::SAMPLED_LINEAR | format::COLOR_ATTACHMENT_BLEND);
+                }
+                (features, format::BufferFeature::all())
+            }
+        };
+        format::FormatProperties {
+            linear_tiling: image_features,
+            optimal_tiling: image_features,
+            buffer_features,
+        }
+    }
+
+    fn create_heap(&mut self, heap_type: &core::HeapType, _: device::ResourceHeapType, size: u64) -> Result<Heap, device::ResourceHeapError> {
+        match self.heap_sizes.get(heap_type.id) {
+            None => Err(device::ResourceHeapError::UnsupportedType),
+            Some(&max_size) if size > max_size => Err(device::ResourceHeapError::OutOfMemory),
+            Some(_) => Ok(Heap {
+                id: register(&self.objects, ObjectKind::Heap),
+                size,
+                properties: heap_type.properties,
+                memory: Arc::new(HostMemory::new(size)),
+            }),
+        }
     }
 
-    fn create_heap(&mut self, _: &core::HeapType, _: device::ResourceHeapType, _: u64) -> Result<(), device::ResourceHeapError> {
-        unimplemented!()
+    fn create_shader_library(&mut self, _: &[(pso::EntryPoint, &[u8])]) -> Result<ShaderLib, pso::CreateShaderError> {
+        // The shader code is not inspected.
+        Ok(ShaderLib { id: register(&self.objects, ObjectKind::ShaderLib) })
     }
 
-    fn create_renderpass(&mut self, _: &[pass::Attachment], _: &[pass::SubpassDesc], _: &[pass::SubpassDependency]) -> () {
-        unimplemented!()
+    fn create_renderpass(&mut self, _: &[pass::Attachment], subpasses: &[pass::SubpassDesc], _: &[pass::SubpassDependency]) -> RenderPass {
+        RenderPass {
+            id: register(&self.objects, ObjectKind::RenderPass),
+            num_subpasses: subpasses.len(),
+        }
+    }
+
+    fn create_pipeline_layout(&mut self, _: &[&DescriptorSetLayout]) -> PipelineLayout {
+        PipelineLayout { id: register(&self.objects, ObjectKind::PipelineLayout) }
+    }
+
+    fn create_pipeline_cache(&mut self, data: &[u8]) -> PipelineCache {
+        PipelineCache {
+            id: register(&self.objects, ObjectKind::PipelineCache),
+            data: Mutex::new(data.to_vec()),
+        }
+    }
+
+    fn get_pipeline_cache_data(&mut self, cache: &PipelineCache) -> Vec<u8> {
+        cache.data.lock().unwrap().clone()
     }
 
-    fn create_pipeline_layout(&mut self, _: &[&()]) -> () {
-        unimplemented!()
+    fn merge_pipeline_caches(&mut self, target: &PipelineCache, sources: &[&PipelineCache]) {
+        let mut data = target.data.lock().unwrap();
+        for source in sources {
+            data.extend_from_slice(&source.data.lock().unwrap());
+        }
     }
 
     fn create_graphics_pipelines<'a>(
         &mut self,
-        _: &[(&(), &(), pass::Subpass<'a, Backend>, &pso::GraphicsPipelineDesc)],
-    ) -> Vec<Result<(), pso::CreationError>> {
-        unimplemented!()
+        descs: &[(&ShaderLib, &PipelineLayout, pass::Subpass<'a, Backend>, &pso::GraphicsPipelineDesc)],
+        _: Option<&PipelineCache>,
+    ) -> Vec<Result<GraphicsPipeline, pso::CreationError>> {
+        descs
+            .iter()
+            .map(|&(_, _, ref subpass, _)| {
+                if subpass.index >= subpass.main_pass.num_subpasses {
+                    return Err(pso::CreationError::InvalidSubpass(subpass.index));
+                }
+                Ok(GraphicsPipeline { id: register(&self.objects, ObjectKind::GraphicsPipeline) })
+            })
+            .collect()
     }
 
     fn create_compute_pipelines(
         &mut self,
-        _: &[(&(), pso::EntryPoint, &())],
-    ) -> Vec<Result<(), pso::CreationError>> {
-        unimplemented!()
+        descs: &[(&ShaderLib, pso::EntryPoint, &PipelineLayout)],
+        _: Option<&PipelineCache>,
+    ) -> Vec<Result<ComputePipeline, pso::CreationError>> {
+        descs
+            .iter()
+            .map(|_| Ok(ComputePipeline { id: register(&self.objects, ObjectKind::ComputePipeline) }))
+            .collect()
     }
 
     fn create_framebuffer(
-        &mut self, _: &(),
-        _: &[&()], _: &[&()],
+        &mut self, _: &RenderPass,
+        _: &[&RenderTargetView], _: &[&DepthStencilView],
         _: device::Extent,
-    ) -> () {
-        unimplemented!()
+    ) -> FrameBuffer {
+        FrameBuffer { id: register(&self.objects, ObjectKind::FrameBuffer) }
     }
 
-    fn create_sampler(&mut self, _: image::SamplerInfo) -> () {
-        unimplemented!()
-    }
-    fn create_buffer(&mut self, _: u64, _: u64, _: buffer::Usage) -> Result<(), buffer::CreationError> {
-        unimplemented!()
+    fn create_sampler(&mut self, _: image::SamplerInfo) -> Sampler {
+        Sampler { id: register(&self.objects, ObjectKind::Sampler) }
     }
 
-    fn get_buffer_requirements(&mut self, _: &()) -> memory::Requirements {
-        unimplemented!()
+    fn create_buffer(&mut self, size: u64, _: u64, usage: buffer::Usage) -> Result<UnboundBuffer, buffer::CreationError> {
+        Ok(UnboundBuffer { size, usage })
     }
 
-    fn bind_buffer_memory(&mut self, _: &(), _: u64, _: ()) -> Result<(), buffer::CreationError> {
-        unimplemented!()
+    fn get_buffer_requirements(&mut self, buffer: &UnboundBuffer) -> memory::Requirements {
+        memory::Requirements {
+            size: buffer.size,
+            alignment: BUFFER_ALIGNMENT,
+        }
     }
 
-    fn create_image(&mut self, _: image::Kind, _: image::Level, _: format::Format, _: image::Usage)
-         -> Result<(), image::CreationError> {
-            unimplemented!()
-         }
+    fn bind_buffer_memory(&mut self, heap: &Heap, offset: u64, buffer: UnboundBuffer) -> Result<Buffer, buffer::CreationError> {
+        if offset % BUFFER_ALIGNMENT != 0 || offset + buffer.size > heap.size {
+            return Err(buffer::CreationError::Other);
+        }
+
+        Ok(Buffer {
+            id: register(&self.objects, ObjectKind::Buffer),
+            size: buffer.size,
+            usage: buffer.usage,
+            cpu_visible: heap.properties.contains(memory::CPU_VISIBLE),
+            offset,
+            memory: heap.memory.clone(),
+        })
+    }
+
+    fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format, usage: image::Usage)
+         -> Result<UnboundImage, image::CreationError>
+    {
+        if format.0.is_compressed() && !kind.supports_compression() {
+            return Err(image::CreationError::Kind);
+        }
+        let (w, h, d, _) = kind.get_dimensions();
+        let max_size = self.limits.max_texture_size as image::Size;
+        for &size in &[w, h, d] {
+            if size > max_size {
+                return Err(image::CreationError::Size(size));
+            }
+        }
 
-    fn get_image_requirements(&mut self, _: &()) -> memory::Requirements {
-        unimplemented!()
+        Ok(UnboundImage { kind, levels, format, usage })
     }
 
-    fn bind_image_memory(&mut self, _: &(), _: u64, _: ()) -> Result<(), image::CreationError> {
-        unimplemented!()
+    fn get_image_requirements(&mut self, image: &UnboundImage) -> memory::Requirements {
+        memory::Requirements {
+            size: image_byte_count(image.kind, image.levels, image.format),
+            alignment: IMAGE_ALIGNMENT,
+        }
     }
 
-    fn view_buffer_as_constant(&mut self, _: &(), _: Range<u64>) -> Result<(), device::TargetViewError> {
-        unimplemented!()
+    fn bind_image_memory(&mut self, heap: &Heap, offset: u64, image: UnboundImage) -> Result<Image, image::CreationError> {
+        let size = image_byte_count(image.kind, image.levels, image.format);
+        if offset % IMAGE_ALIGNMENT != 0 || offset + size > heap.size {
+            return Err(image::CreationError::Data(size as usize));
+        }
+
+        Ok(Image {
+            id: register(&self.objects, ObjectKind::Image),
+            kind: image.kind,
+            levels: image.levels,
+            format: image.format,
+            usage: image.usage,
+        })
     }
 
-    fn view_image_as_render_target(&mut self, _: &(), _: format::Format, _: image::SubresourceRange) -> Result<(), device::TargetViewError> {
-        unimplemented!()
+    fn view_buffer_as_constant(&mut self, _: &Buffer, _: Range<u64>) -> Result<ConstantBufferView, device::TargetViewError> {
+        Ok(ConstantBufferView { id: register(&self.objects, ObjectKind::ConstantBufferView) })
     }
 
-    fn view_image_as_shader_resource(&mut self, _: &(), _: format::Format) -> Result<(), device::TargetViewError> {
-        unimplemented!()
+    fn view_image_as_render_target(&mut self, _: &Image, _: format::Format, _: image::SubresourceRange) -> Result<RenderTargetView, device::TargetViewError> {
+        Ok(RenderTargetView { id: register(&self.objects, ObjectKind::RenderTargetView) })
     }
 
-    fn view_image_as_unordered_access(&mut self, _: &(), _: format::Format) -> Result<(), device::TargetViewError> {
-        unimplemented!()
+    fn view_image_as_shader_resource(&mut self, _: &Image, _: format::Format) -> Result<ShaderResourceView, device::TargetViewError> {
+        Ok(ShaderResourceView { id: register(&self.objects, ObjectKind::ShaderResourceView) })
     }
-    fn create_descriptor_pool(&mut self, _: usize, _: &[pso::DescriptorRangeDesc]) -> DescriptorPool {
-        unimplemented!()
+
+    fn view_image_as_unordered_access(&mut self, _: &Image, _: format::Format) -> Result<UnorderedAccessView, device::TargetViewError> {
+        Ok(UnorderedAccessView { id: register(&self.objects, ObjectKind::UnorderedAccessView) })
     }
-    fn create_descriptor_set_layout(&mut self, _: &[pso::DescriptorSetLayoutBinding]) -> () {
-        unimplemented!()
+
+    fn create_descriptor_pool(&mut self, _: usize, _: &[pso::DescriptorRangeDesc]) -> DescriptorPool {
+        DescriptorPool {
+            id: register(&self.objects, ObjectKind::DescriptorPool),
+            sets: Vec::new(),
+            objects: self.objects.clone(),
+        }
     }
 
+    fn create_descriptor_set_layout(&mut self, _: &[pso::DescriptorSetLayoutBinding]) -> DescriptorSetLayout {
+        DescriptorSetLayout { id: register(&self.objects, ObjectKind::DescriptorSetLayout) }
+    }
 
     fn update_descriptor_sets(&mut self, _: &[pso::DescriptorSetWrite<Backend>]) {
-        unimplemented!()
+        // Nothing to do, descriptor sets don't hold any state.
     }
 
-    fn read_mapping<'a, T>(&self, _: &'a (), _: u64, _: u64)
+    fn read_mapping<'a, T>(&self, buffer: &'a Buffer, offset: u64, size: u64)
                            -> Result<mapping::Reader<'a, Backend, T>, mapping::Error>
-        where T: Copy {
-            unimplemented!()
-        }
+        where T: Copy
+    {
+        self.map_buffer::<T>(buffer, offset, size)
+            .map(|(ptr, mapping)| unsafe {
+                let slice = slice::from_raw_parts(ptr as *const T, size as usize / mem::size_of::<T>());
+                mapping::Reader::new(slice, mapping)
+            })
+    }
 
-    fn write_mapping<'a, 'b, T>(&mut self, _: &'a (), _: u64, _: u64)
+    fn write_mapping<'a, 'b, T>(&mut self, buffer: &'a Buffer, offset: u64, size: u64)
                                 -> Result<mapping::Writer<'a, Backend, T>, mapping::Error>
-        where T: Copy {
-            unimplemented!()
+        where T: Copy
+    {
+        self.map_buffer::<T>(buffer, offset, size)
+            .map(|(ptr, mapping)| unsafe {
+                let slice = slice::from_raw_parts_mut(ptr, size as usize / mem::size_of::<T>());
+                mapping::Writer::new(slice, mapping)
+            })
+    }
+
+    fn create_semaphore(&mut self) -> Semaphore {
+        Semaphore { id: register(&self.objects, ObjectKind::Semaphore) }
+    }
+
+    fn create_fence(&mut self, signaled: bool) -> Fence {
+        Fence {
+            id: register(&self.objects, ObjectKind::Fence),
+            signaled: Arc::new(AtomicBool::new(signaled)),
         }
+    }
 
-    fn create_semaphore(&mut self) -> () {
-        unimplemented!()
+    fn reset_fences(&mut self, fences: &[&Fence]) {
+        for fence in fences {
+            fence.signaled.store(false, Ordering::Release);
+        }
     }
 
-    fn create_fence(&mut self, _: bool) -> () {
-        unimplemented!()
+    fn wait_for_fences(&mut self, fences: &[&Fence], wait: device::WaitFor, timeout_ms: u32) -> bool {
+        // Waiting completes deferred submissions in order, up to the ones signalling `fences`.
+        if timeout_ms != 0 {
+            let mut pending = self.pending.lock().unwrap();
+            let waited = |submission: &PendingSubmission| {
+                submission.fence.as_ref().map_or(false, |signaled| {
+                    fences.iter().any(|fence| Arc::ptr_eq(&fence.signaled, signaled))
+                })
+            };
+            let completed = match wait {
+                device::WaitFor::All => pending.iter().rposition(waited),
+                device::WaitFor::Any => pending.iter().position(waited),
+            };
+            for submission in pending.drain(.. completed.map_or(0, |i| i + 1)) {
+                submission.complete();
+            }
+        }
+        match wait {
+            device::WaitFor::All => fences.iter().all(|fence| fence.is_signaled()),
+            device::WaitFor::Any => fences.iter().any(|fence| fence.is_signaled()),
+        }
     }
 
-    fn reset_fences(&mut self, _: &[&()]) {
-        unimplemented!()
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> QueryPool {
+        QueryPool {
+            id: register(&self.objects, ObjectKind::QueryPool),
+            ty,
+            results: Arc::new(Mutex::new(vec![None; count as usize])),
+        }
     }
-    fn wait_for_fences(&mut self, _: &[&()], _: device::WaitFor, _: u32) -> bool {
-        unimplemented!()
+
+    fn get_query_pool_results(&mut self, pool: &QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        let results = pool.results.lock().unwrap();
+        let num_values = pool.ty.num_values();
+        let availability = flags.contains(query::WITH_AVAILABILITY);
+        let stride = num_values + availability as usize;
+        let mut all_available = true;
+        let ranges = results[queries.start as usize .. queries.end as usize].iter().zip(data.chunks_mut(stride));
+        for (result, values) in ranges {
+            match *result {
+                Some(ref result) => values[.. num_values].copy_from_slice(result),
+                None if flags.contains(query::PARTIAL) => {
+                    for value in &mut values[.. num_values] {
+                        *value = 0;
+                    }
+                }
+                None => (),
+            }
+            if availability {
+                values[num_values] = result.is_some() as u64;
+            }
+            all_available &= result.is_some();
+        }
+        all_available
     }
 
-    fn destroy_heap(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_heap(&mut self, heap: Heap) {
+        unregister(&self.objects, heap.id, ObjectKind::Heap);
     }
 
-    fn destroy_shader_lib(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_shader_lib(&mut self, shader_lib: ShaderLib) {
+        unregister(&self.objects, shader_lib.id, ObjectKind::ShaderLib);
     }
 
-    fn destroy_renderpass(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_renderpass(&mut self, rp: RenderPass) {
+        unregister(&self.objects, rp.id, ObjectKind::RenderPass);
     }
 
-    fn destroy_pipeline_layout(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_pipeline_layout(&mut self, layout: PipelineLayout) {
+        unregister(&self.objects, layout.id, ObjectKind::PipelineLayout);
     }
-    fn destroy_graphics_pipeline(&mut self, _: ()) {
-        unimplemented!()
+
+    fn destroy_graphics_pipeline(&mut self, pipeline: GraphicsPipeline) {
+        unregister(&self.objects, pipeline.id, ObjectKind::GraphicsPipeline);
     }
-    fn destroy_compute_pipeline(&mut self, _: ()) {
-        unimplemented!()
+
+    fn destroy_compute_pipeline(&mut self, pipeline: ComputePipeline) {
+        unregister(&self.objects, pipeline.id, ObjectKind::ComputePipeline);
     }
-    fn destroy_framebuffer(&mut self, _: ()) {
-        unimplemented!()
+
+    fn destroy_pipeline_cache(&mut self, cache: PipelineCache) {
+        unregister(&self.objects, cache.id, ObjectKind::PipelineCache);
     }
-    fn destroy_buffer(&mut self, _: ()) {
-        unimplemented!()
+
+    fn destroy_framebuffer(&mut self, fb: FrameBuffer) {
+        unregister(&self.objects, fb.id, ObjectKind::FrameBuffer);
+    }
+
+    fn destroy_buffer(&mut self, buffer: Buffer) {
+        unregister(&self.objects, buffer.id, ObjectKind::Buffer);
     }
-    fn destroy_image(&mut self, _: ()) {
-        unimplemented!()
+
+    fn destroy_image(&mut self, image: Image) {
+        unregister(&self.objects, image.id, ObjectKind::Image);
     }
 
-    fn destroy_render_target_view(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_render_target_view(&mut self, rtv: RenderTargetView) {
+        unregister(&self.objects, rtv.id, ObjectKind::RenderTargetView);
     }
 
-    fn destroy_depth_stencil_view(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_depth_stencil_view(&mut self, dsv: DepthStencilView) {
+        unregister(&self.objects, dsv.id, ObjectKind::DepthStencilView);
     }
 
-    fn destroy_constant_buffer_view(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_constant_buffer_view(&mut self, cbv: ConstantBufferView) {
+        unregister(&self.objects, cbv.id, ObjectKind::ConstantBufferView);
     }
 
-    fn destroy_shader_resource_view(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_shader_resource_view(&mut self, srv: ShaderResourceView) {
+        unregister(&self.objects, srv.id, ObjectKind::ShaderResourceView);
     }
 
-    fn destroy_unordered_access_view(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_unordered_access_view(&mut self, uav: UnorderedAccessView) {
+        unregister(&self.objects, uav.id, ObjectKind::UnorderedAccessView);
     }
 
-    fn destroy_sampler(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_sampler(&mut self, sampler: Sampler) {
+        unregister(&self.objects, sampler.id, ObjectKind::Sampler);
     }
 
-    fn destroy_descriptor_pool(&mut self, _: DescriptorPool) {
-        unimplemented!()
+    fn destroy_descriptor_pool(&mut self, mut pool: DescriptorPool) {
+        core::DescriptorPool::reset(&mut pool);
+        unregister(&self.objects, pool.id, ObjectKind::DescriptorPool);
     }
 
-    fn destroy_descriptor_set_layout(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_descriptor_set_layout(&mut self, layout: DescriptorSetLayout) {
+        unregister(&self.objects, layout.id, ObjectKind::DescriptorSetLayout);
     }
 
-    fn destroy_fence(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_fence(&mut self, fence: Fence) {
+        unregister(&self.objects, fence.id, ObjectKind::Fence);
     }
 
-    fn destroy_semaphore(&mut self, _: ()) {
-        unimplemented!()
+    fn destroy_semaphore(&mut self, semaphore: Semaphore) {
+        unregister(&self.objects, semaphore.id, ObjectKind::Semaphore);
+    }
+
+    fn destroy_query_pool(&mut self, pool: QueryPool) {
+        unregister(&self.objects, pool.id, ObjectKind::QueryPool);
     }
 }
 
 /// Dummy queue family;
-pub struct QueueFamily;
+pub struct QueueFamily {
+    num_queues: u32,
+}
 impl core::QueueFamily for QueueFamily {
     fn num_queues(&self) -> u32 {
-        unimplemented!()
+        self.num_queues
     }
 }
 
@@ -291,20 +1310,19 @@ pub struct SubpassCommandBuffer;
 pub struct RawCommandPool;
 impl core::RawCommandPool<Backend> for RawCommandPool {
     fn reset(&mut self) {
-        unimplemented!()
+        // Command buffers own their recorded commands,
+        // which are cleared on `begin`.
     }
 
     unsafe fn from_queue(_: &CommandQueue, _: pool::CommandPoolCreateFlags) -> Self {
-        unimplemented!()
+        RawCommandPool
     }
 
-    fn allocate(&mut self, _: usize) -> Vec<RawCommandBuffer> {
-        unimplemented!()
+    fn allocate(&mut self, num: usize) -> Vec<RawCommandBuffer> {
+        (0 .. num).map(|_| RawCommandBuffer::new()).collect()
     }
 
-    unsafe fn free(&mut self, _: Vec<RawCommandBuffer>) {
-        unimplemented!()
-    }
+    unsafe fn free(&mut self, _: Vec<RawCommandBuffer>) { }
 }
 
 /// Dummy subpass command pool.
@@ -313,248 +1331,694 @@ impl core::SubpassCommandPool<Backend> for SubpassCommandPool {
 
 }
 
-/// Dummy command buffer, which ignores all the calls.
-#[derive(Clone)]
-pub struct RawCommandBuffer;
+/// Dummy command buffer, which records all the calls.
+#[derive(Clone, Debug)]
+pub struct RawCommandBuffer {
+    commands: Vec<Command>,
+    // Query pools referenced by the commands, written on submission.
+    query_pools: Vec<QueryPool>,
+}
+
+impl RawCommandBuffer {
+    fn new() -> Self {
+        RawCommandBuffer {
+            commands: Vec::new(),
+            query_pools: Vec::new(),
+        }
+    }
+
+    fn use_query_pool(&mut self, pool: &QueryPool) {
+        if !self.query_pools.contains(pool) {
+            self.query_pools.push(pool.clone());
+        }
+    }
+
+    /// Commands recorded since the last `begin` or `reset`.
+    pub fn commands(&self) -> &[Command] {
+        &self.commands
+    }
+}
+
 impl core::RawCommandBuffer<Backend> for RawCommandBuffer {
     fn begin(&mut self) {
-        unimplemented!()
+        self.commands.clear();
+        self.query_pools.clear();
     }
 
     fn finish(&mut self) {
-        unimplemented!()
+        // no-op
     }
 
     fn reset(&mut self, _: bool) {
-        unimplemented!()
+        self.commands.clear();
+        self.query_pools.clear();
     }
 
     fn pipeline_barrier(
         &mut self,
-        _: Range<pso::PipelineStage>,
-        _: &[memory::Barrier<Backend>],
+        stages: Range<pso::PipelineStage>,
+        barriers: &[memory::Barrier<Backend>],
     ) {
-        unimplemented!()
+        self.commands.push(Command::PipelineBarrier {
+            stages,
+            barriers: barriers.iter().map(Barrier::from).collect(),
+        });
     }
 
-    fn fill_buffer(&mut self, _: &(), _: Range<u64>, _: u32) {
-        unimplemented!()
+    fn fill_buffer(&mut self, buffer: &Buffer, range: Range<u64>, data: u32) {
+        self.commands.push(Command::FillBuffer {
+            buffer: buffer.id,
+            range,
+            data,
+        });
     }
 
-    fn update_buffer(&mut self, _: &(), _: u64, _: &[u8]) {
-        unimplemented!()
+    fn update_buffer(&mut self, buffer: &Buffer, offset: u64, data: &[u8]) {
+        self.commands.push(Command::UpdateBuffer {
+            buffer: buffer.id,
+            offset,
+            data: data.to_vec(),
+        });
     }
 
-    fn clear_color(&mut self, _: &(), _: image::ImageLayout, _: command::ClearColor) {
-        unimplemented!()
+    fn clear_color(&mut self, rtv: &RenderTargetView, layout: image::ImageLayout, color: command::ClearColor) {
+        self.commands.push(Command::ClearColor {
+            view: rtv.id,
+            layout,
+            color,
+        });
     }
 
     fn clear_depth_stencil(
         &mut self,
-        _: &(),
-        _: image::ImageLayout,
-        _: Option<target::Depth>,
-        _: Option<target::Stencil>,
+        dsv: &DepthStencilView,
+        layout: image::ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
     ) {
-        unimplemented!()
+        self.commands.push(Command::ClearDepthStencil {
+            view: dsv.id,
+            layout,
+            depth,
+            stencil,
+        });
     }
 
-
     fn resolve_image(
         &mut self,
-        _: &(),
-        _: image::ImageLayout,
-        _: &(),
-        _: image::ImageLayout,
-        _: &[command::ImageResolve],
+        src: &Image,
+        src_layout: image::ImageLayout,
+        dst: &Image,
+        dst_layout: image::ImageLayout,
+        regions: &[command::ImageResolve],
     ) {
-        unimplemented!()
+        self.commands.push(Command::ResolveImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
     }
 
-    fn bind_index_buffer(&mut self, _: buffer::IndexBufferView<Backend>) {
-        unimplemented!()
+    fn blit_image(
+        &mut self,
+        src: &Image,
+        src_layout: image::ImageLayout,
+        dst: &Image,
+        dst_layout: image::ImageLayout,
+        regions: &[command::ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        self.commands.push(Command::BlitImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+            filter,
+        });
     }
 
-    fn bind_vertex_buffers(&mut self, _: pso::VertexBufferSet<Backend>) {
-        unimplemented!()
+    fn bind_index_buffer(&mut self, ibv: buffer::IndexBufferView<Backend>) {
+        self.commands.push(Command::BindIndexBuffer {
+            buffer: ibv.buffer.id,
+            offset: ibv.offset,
+            index_type: ibv.index_type,
+        });
     }
 
-    fn set_viewports(&mut self, _: &[core::Viewport]) {
-
+    fn bind_vertex_buffers(&mut self, vbs: pso::VertexBufferSet<Backend>) {
+        self.commands.push(Command::BindVertexBuffers(
+            vbs.0.iter().map(|&(buffer, offset)| (buffer.id, offset)).collect()
+        ));
     }
 
-    fn set_scissors(&mut self, _: &[target::Rect]) {
-        unimplemented!()
+    fn set_viewports(&mut self, viewports: &[core::Viewport]) {
+        self.commands.push(Command::SetViewports(viewports.to_vec()));
     }
 
-
-    fn set_stencil_reference(&mut self, _: target::Stencil, _: target::Stencil) {
-        unimplemented!()
+    fn set_scissors(&mut self, scissors: &[target::Rect]) {
+        self.commands.push(Command::SetScissors(scissors.to_vec()));
     }
 
-
-    fn set_blend_constants(&mut self, _: target::ColorValue) {
-        unimplemented!()
+    fn set_stencil_reference(&mut self, front: target::Stencil, back: target::Stencil) {
+        self.commands.push(Command::SetStencilReference { front, back });
     }
 
+    fn set_blend_constants(&mut self, color: target::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+    }
 
     fn begin_renderpass(
         &mut self,
-        _: &(),
-        _: &(),
-        _: target::Rect,
-        _: &[command::ClearValue],
-        _: command::SubpassContents,
+        render_pass: &RenderPass,
+        frame_buffer: &FrameBuffer,
+        area: target::Rect,
+        clear_values: &[command::ClearValue],
+        contents: command::SubpassContents,
     ) {
-        unimplemented!()
+        self.commands.push(Command::BeginRenderPass {
+            render_pass: render_pass.id,
+            frame_buffer: frame_buffer.id,
+            area,
+            clear_values: clear_values.to_vec(),
+            contents,
+        });
     }
 
-    fn next_subpass(&mut self, _: command::SubpassContents) {
-        unimplemented!()
+    fn next_subpass(&mut self, contents: command::SubpassContents) {
+        self.commands.push(Command::NextSubpass(contents));
     }
 
     fn end_renderpass(&mut self) {
-        unimplemented!()
+        self.commands.push(Command::EndRenderPass);
     }
 
-    fn bind_graphics_pipeline(&mut self, _: &()) {
-        unimplemented!()
+    fn bind_graphics_pipeline(&mut self, pipeline: &GraphicsPipeline) {
+        self.commands.push(Command::BindGraphicsPipeline(pipeline.id));
     }
 
     fn bind_graphics_descriptor_sets(
         &mut self,
-        _: &(),
-        _: usize,
-        _: &[&()],
+        layout: &PipelineLayout,
+        first_set: usize,
+        sets: &[&DescriptorSet],
     ) {
-        unimplemented!()
+        self.commands.push(Command::BindGraphicsDescriptorSets {
+            layout: layout.id,
+            first_set,
+            sets: sets.iter().map(|set| set.id).collect(),
+        });
     }
 
-    fn bind_compute_pipeline(&mut self, _: &()) {
-        unimplemented!()
+    fn bind_compute_pipeline(&mut self, pipeline: &ComputePipeline) {
+        self.commands.push(Command::BindComputePipeline(pipeline.id));
     }
 
-    fn dispatch(&mut self, _: u32, _: u32, _: u32) {
-        unimplemented!()
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.commands.push(Command::Dispatch(x, y, z));
     }
 
-    fn dispatch_indirect(&mut self, _: &(), _: u64) {
-        unimplemented!()
+    fn dispatch_indirect(&mut self, buffer: &Buffer, offset: u64) {
+        self.commands.push(Command::DispatchIndirect {
+            buffer: buffer.id,
+            offset,
+        });
     }
 
-    fn copy_buffer(&mut self, _: &(), _: &(), _: &[command::BufferCopy]) {
-        unimplemented!()
+    fn copy_buffer(&mut self, src: &Buffer, dst: &Buffer, regions: &[command::BufferCopy]) {
+        self.commands.push(Command::CopyBuffer {
+            src: src.id,
+            dst: dst.id,
+            regions: regions.to_vec(),
+        });
     }
 
     fn copy_image(
         &mut self,
-        _: &(),
-        _: image::ImageLayout,
-        _: &(),
-        _: image::ImageLayout,
-        _: &[command::ImageCopy],
+        src: &Image,
+        src_layout: image::ImageLayout,
+        dst: &Image,
+        dst_layout: image::ImageLayout,
+        regions: &[command::ImageCopy],
     ) {
-        unimplemented!()
+        self.commands.push(Command::CopyImage {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
     }
 
     fn copy_buffer_to_image(
         &mut self,
-        _: &(),
-        _: &(),
-        _: image::ImageLayout,
-        _: &[command::BufferImageCopy],
+        src: &Buffer,
+        dst: &Image,
+        dst_layout: image::ImageLayout,
+        regions: &[command::BufferImageCopy],
     ) {
-        unimplemented!()
+        self.commands.push(Command::CopyBufferToImage {
+            src: src.id,
+            dst: dst.id,
+            dst_layout,
+            regions: regions.to_vec(),
+        });
     }
 
     fn copy_image_to_buffer(
         &mut self,
-        _: &(),
-        _: image::ImageLayout,
-        _: &(),
-        _: &[command::BufferImageCopy],
+        src: &Image,
+        src_layout: image::ImageLayout,
+        dst: &Buffer,
+        regions: &[command::BufferImageCopy],
     ) {
-        unimplemented!()
+        self.commands.push(Command::CopyImageToBuffer {
+            src: src.id,
+            src_layout,
+            dst: dst.id,
+            regions: regions.to_vec(),
+        });
     }
 
     fn draw(&mut self,
-        _: Range<core::VertexCount>,
-        _: Range<core::InstanceCount>,
+        vertices: Range<core::VertexCount>,
+        instances: Range<core::InstanceCount>,
     ) {
-        unimplemented!()
+        self.commands.push(Command::Draw { vertices, instances });
     }
 
     fn draw_indexed(
         &mut self,
-        _: Range<core::IndexCount>,
-        _: core::VertexOffset,
-        _: Range<core::InstanceCount>,
+        indices: Range<core::IndexCount>,
+        base_vertex: core::VertexOffset,
+        instances: Range<core::InstanceCount>,
     ) {
-        unimplemented!()
+        self.commands.push(Command::DrawIndexed { indices, base_vertex, instances });
     }
 
-    fn draw_indirect(&mut self, _: &(), _: u64, _: u32, _: u32) {
-        unimplemented!()
+    fn draw_indirect(&mut self, buffer: &Buffer, offset: u64, draw_count: u32, stride: u32) {
+        self.commands.push(Command::DrawIndirect {
+            buffer: buffer.id,
+            offset,
+            draw_count,
+            stride,
+        });
     }
 
     fn draw_indexed_indirect(
         &mut self,
-        _: &(),
-        _: u64,
-        _: u32,
-        _: u32,
+        buffer: &Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        self.commands.push(Command::DrawIndexedIndirect {
+            buffer: buffer.id,
+            offset,
+            draw_count,
+            stride,
+        });
+    }
+
+    fn begin_query(&mut self, query: query::Query<Backend>, flags: query::QueryControl) {
+        self.use_query_pool(query.pool);
+        self.commands.push(Command::BeginQuery {
+            pool: query.pool.id,
+            query: query.id,
+            flags,
+        });
+    }
+
+    fn end_query(&mut self, query: query::Query<Backend>) {
+        self.use_query_pool(query.pool);
+        self.commands.push(Command::EndQuery {
+            pool: query.pool.id,
+            query: query.id,
+        });
+    }
+
+    fn reset_query_pool(&mut self, pool: &QueryPool, queries: Range<query::QueryId>) {
+        self.use_query_pool(pool);
+        self.commands.push(Command::ResetQueryPool {
+            pool: pool.id,
+            queries,
+        });
+    }
+
+    fn write_timestamp(&mut self, stage: pso::PipelineStage, query: query::Query<Backend>) {
+        self.use_query_pool(query.pool);
+        self.commands.push(Command::WriteTimestamp {
+            stage,
+            pool: query.pool.id,
+            query: query.id,
+        });
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: &Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
     ) {
-        unimplemented!()
+        self.commands.push(Command::CopyQueryPoolResults {
+            pool: pool.id,
+            queries,
+            buffer: buffer.id,
+            offset,
+            stride,
+            flags,
+        });
     }
 }
 
-// Dummy descriptor pool.
-pub struct DescriptorPool;
+/// Dummy descriptor pool.
+pub struct DescriptorPool {
+    id: Id,
+    sets: Vec<Id>,
+    objects: Registry,
+}
 impl core::DescriptorPool<Backend> for DescriptorPool {
-    fn allocate_sets(&mut self, _: &[&()]) -> Vec<()> {
-        unimplemented!()
+    fn allocate_sets(&mut self, layouts: &[&DescriptorSetLayout]) -> Vec<DescriptorSet> {
+        layouts
+            .iter()
+            .map(|_| {
+                let id = register(&self.objects, ObjectKind::DescriptorSet);
+                self.sets.push(id);
+                DescriptorSet { id }
+            })
+            .collect()
     }
 
     fn reset(&mut self) {
-        unimplemented!()
+        for id in self.sets.drain(..) {
+            unregister(&self.objects, id, ObjectKind::DescriptorSet);
+        }
     }
 }
 
 /// Dummy surface.
-pub struct Surface;
+pub struct Surface {
+    // Shared with the swapchains, to detect resizing.
+    extent: Arc<Mutex<(image::Size, image::Size)>>,
+}
+
+impl Surface {
+    /// Create a new surface with the given backbuffer dimensions.
+    pub fn new(width: image::Size, height: image::Size) -> Self {
+        Surface {
+            extent: Arc::new(Mutex::new((width, height))),
+        }
+    }
+
+    /// Change the dimensions of the surface, as if the window got resized.
+    ///
+    /// Existing swapchains become out of date.
+    pub fn resize(&mut self, width: image::Size, height: image::Size) {
+        *self.extent.lock().unwrap() = (width, height);
+    }
+}
+
 impl core::Surface<Backend> for Surface {
     fn supports_queue(&self, _: &QueueFamily) -> bool {
-        unimplemented!()
+        true
+    }
+
+    fn get_capabilities(&self, _: &Adapter) -> core::SurfaceCapabilities {
+        let (width, height) = *self.extent.lock().unwrap();
+        core::SurfaceCapabilities {
+            formats: None,
+            min_image_count: 1,
+            max_image_count: Some(MAX_BACKBUFFERS),
+            current_extent: Some(core::Extent2D {
+                width: width as u32,
+                height: height as u32,
+            }),
+            present_modes: vec![
+                core::PresentMode::Immediate,
+                core::PresentMode::Mailbox,
+                core::PresentMode::Fifo,
+                core::PresentMode::Relaxed,
+            ],
+        }
     }
 
-    fn build_swapchain<C>(&mut self, _: core::SwapchainConfig, _: &core::CommandQueue<Backend, C>)-> Swapchain {
-        unimplemented!()
+    fn build_swapchain<C>(&mut self, config: core::SwapchainConfig, _: &core::CommandQueue<Backend, C>)-> Swapchain {
+        // The surface has a fixed extent, the requested one is ignored.
+        let (width, height) = *self.extent.lock().unwrap();
+        let kind = image::Kind::D2(width, height, image::AaMode::Single);
+        let backbuffer_image = |format, usage| Image {
+            id: next_id(),
+            kind,
+            levels: 1,
+            format,
+            usage,
+        };
+
+        let num_backbuffers = config.image_count.max(1).min(MAX_BACKBUFFERS);
+        let backbuffers = (0 .. num_backbuffers)
+            .map(|_| core::Backbuffer {
+                color: backbuffer_image(config.color_format, image::COLOR_ATTACHMENT | image::TRANSFER_SRC),
+                depth_stencil: config.depth_stencil_format.map(|format| {
+                    backbuffer_image(format, image::DEPTH_STENCIL_ATTACHMENT)
+                }),
+            })
+            .collect();
+
+        Swapchain {
+            backbuffers,
+            extent: (width, height),
+            surface_extent: self.extent.clone(),
+            next_frame: 0,
+            num_presented: 0,
+        }
     }
 }
 
 /// Dummy swapchain.
-pub struct Swapchain;
+pub struct Swapchain {
+    backbuffers: Vec<core::Backbuffer<Backend>>,
+    extent: (image::Size, image::Size),
+    surface_extent: Arc<Mutex<(image::Size, image::Size)>>,
+    next_frame: usize,
+    num_presented: usize,
+}
+
+impl Swapchain {
+    /// Number of frames presented so far.
+    pub fn num_presented(&self) -> usize {
+        self.num_presented
+    }
+}
+
 impl core::Swapchain<Backend> for Swapchain {
     fn get_backbuffers(&mut self) -> &[core::Backbuffer<Backend>] {
-        unimplemented!()
+        &self.backbuffers
     }
 
-    fn acquire_frame(&mut self, _: core::FrameSync<Backend>) -> core::Frame {
-        unimplemented!()
+    fn acquire_frame(&mut self, sync: core::FrameSync<Backend>) -> Result<core::Frame, core::AcquireError> {
+        if *self.surface_extent.lock().unwrap() != self.extent {
+            return Err(core::AcquireError::OutOfDate);
+        }
+        if let core::FrameSync::Fence(fence) = sync {
+            fence.signaled.store(true, Ordering::Release);
+        }
+
+        let frame = self.next_frame;
+        self.next_frame = (frame + 1) % self.backbuffers.len();
+        Ok(core::Frame::new(frame))
     }
 
     fn present<C>(
         &mut self,
         _: &mut core::CommandQueue<Backend, C>,
-        _: &[&()],
-    ) {
-        unimplemented!()
+        _: &[&Semaphore],
+    ) -> Result<core::Suboptimal, core::PresentError> {
+        self.num_presented += 1;
+        // Frames acquired before a resize can still be presented.
+        Ok(*self.surface_extent.lock().unwrap() != self.extent)
     }
 }
 
+/// Dummy instance, exposing a single adapter.
 pub struct Instance;
 impl core::Instance<Backend> for Instance {
     fn enumerate_adapters(&self) -> Vec<Adapter> {
-        Vec::new()
+        vec![Adapter::new()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::{self, buffer, memory, Adapter, Device, QueueFamily, RawCommandQueue};
+    use core::command::BufferCopy;
+    use core::device::{ResourceHeapType, WaitFor};
+    use super::*;
+
+    fn open() -> core::Gpu<Backend> {
+        let adapter = super::Adapter::new();
+        let descs = adapter.get_queue_families()
+            .iter()
+            .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+            .collect::<Vec<_>>();
+        adapter.open(&descs)
+    }
+
+    fn create_buffer(device: &mut super::Device, heap: &Heap, offset: u64, size: u64) -> Buffer {
+        let unbound = device.create_buffer(size, 0, buffer::TRANSFER_SRC | buffer::TRANSFER_DST).unwrap();
+        device.bind_buffer_memory(heap, offset, unbound).unwrap()
+    }
+
+    #[test]
+    fn track_lifetimes() {
+        let core::Gpu { mut device, heap_types, .. } = open();
+        let heap = device.create_heap(&heap_types[1], ResourceHeapType::Buffers, 1024).unwrap();
+        let buffer = create_buffer(&mut device, &heap, 0, 64);
+        assert_eq!(device.live_objects(), vec![(heap.id(), ObjectKind::Heap), (buffer.id(), ObjectKind::Buffer)]);
+
+        device.destroy_buffer(buffer);
+        device.destroy_heap(heap);
+        assert!(device.live_objects().is_empty());
+    }
+
+    #[test]
+    fn write_and_read_mapping() {
+        let core::Gpu { mut device, heap_types, .. } = open();
+        let heap = device.create_heap(&heap_types[1], ResourceHeapType::Buffers, 1024).unwrap();
+        let buffer = create_buffer(&mut device, &heap, 16, 64);
+        {
+            let mut writer = device.write_mapping::<u32>(&buffer, 0, 64).unwrap();
+            for (i, value) in writer.iter_mut().enumerate() {
+                *value = i as u32;
+            }
+        }
+        let reader = device.read_mapping::<u32>(&buffer, 8, 8).unwrap();
+        assert_eq!(&reader[..], &[2, 3]);
+
+        let device_local = device.create_heap(&heap_types[0], ResourceHeapType::Buffers, 64).unwrap();
+        assert!(device_local.properties.contains(memory::DEVICE_LOCAL));
+        let hidden = create_buffer(&mut device, &device_local, 0, 64);
+        assert_eq!(device.read_mapping::<u32>(&hidden, 0, 64).err(), Some(mapping::Error::InvalidAccess));
+    }
+
+    #[test]
+    fn record_and_submit() {
+        let core::Gpu { mut device, mut general_queues, heap_types, .. } = open();
+        let heap = device.create_heap(&heap_types[0], ResourceHeapType::Buffers, 1024).unwrap();
+        let src = create_buffer(&mut device, &heap, 0, 64);
+        let dst = create_buffer(&mut device, &heap, 64, 64);
+        let fence = device.create_fence(false);
+
+        let mut cmd_buffer = RawCommandBuffer::new();
+        {
+            use core::RawCommandBuffer;
+            cmd_buffer.begin();
+            cmd_buffer.copy_buffer(&src, &dst, &[BufferCopy { src: 0, dst: 0, size: 64 }]);
+            cmd_buffer.dispatch(1, 2, 3);
+            cmd_buffer.finish();
+        }
+        match cmd_buffer.commands() {
+            &[Command::CopyBuffer { src: s, dst: d, .. }, Command::Dispatch(1, 2, 3)] => {
+                assert_eq!((s, d), (src.id(), dst.id()));
+            }
+            other => panic!("Unexpected commands: {:?}", other),
+        }
+
+        let queue = general_queues[0].as_mut();
+        unsafe {
+            queue.submit_raw(core::RawSubmission {
+                cmd_buffers: &[cmd_buffer],
+                wait_semaphores: &[],
+                signal_semaphores: &[],
+            }, Some(&fence));
+        }
+        assert_eq!(queue.submissions().len(), 1);
+        assert_eq!(queue.submissions()[0].fence, Some(fence.id()));
+        assert!(device.wait_for_fences(&[&fence], WaitFor::All, 0));
+
+        device.reset_fences(&[&fence]);
+        assert!(!fence.is_signaled());
+    }
+
+    #[test]
+    fn query_results() {
+        use core::query::{self, Query, QueryType};
+        let core::Gpu { mut device, mut general_queues, .. } = open();
+        let occlusion = device.create_query_pool(QueryType::Occlusion, 2);
+        let timestamps = device.create_query_pool(QueryType::Timestamp, 2);
+
+        let mut cmd_buffer = RawCommandBuffer::new();
+        {
+            use core::RawCommandBuffer;
+            cmd_buffer.begin();
+            cmd_buffer.reset_query_pool(&occlusion, 0 .. 2);
+            cmd_buffer.reset_query_pool(&timestamps, 0 .. 2);
+            cmd_buffer.write_timestamp(pso::TOP_OF_PIPE, Query { pool: &timestamps, id: 0 });
+            cmd_buffer.begin_query(Query { pool: &occlusion, id: 1 }, query::PRECISE);
+            cmd_buffer.end_query(Query { pool: &occlusion, id: 1 });
+            cmd_buffer.write_timestamp(pso::BOTTOM_OF_PIPE, Query { pool: &timestamps, id: 1 });
+            cmd_buffer.finish();
+        }
+        let mut data = [7; 4];
+        assert!(!device.get_query_pool_results(&occlusion, 0 .. 2, &mut data, query::WITH_AVAILABILITY));
+
+        unsafe {
+            general_queues[0].as_mut().submit_raw(core::RawSubmission {
+                cmd_buffers: &[cmd_buffer],
+                wait_semaphores: &[],
+                signal_semaphores: &[],
+            }, None);
+        }
+        assert!(device.get_query_pool_results(&occlusion, 1 .. 2, &mut data, query::WITH_AVAILABILITY));
+        assert_eq!(&data[.. 2], &[0, 1]);
+        assert!(device.get_query_pool_results(&timestamps, 0 .. 2, &mut data, query::ResultFlags::empty()));
+        assert_eq!(&data[.. 2], &[1, 2]);
+
+        device.destroy_query_pool(occlusion);
+        device.destroy_query_pool(timestamps);
+        assert!(device.live_objects().is_empty());
+    }
+
+    #[test]
+    fn recreate_swapchain() {
+        use core::{Surface, Swapchain};
+        let adapter = super::Adapter::new();
+        let core::Gpu { general_queues, .. } = open();
+        let mut surface = super::Surface::new(64, 32);
+
+        let caps = surface.get_capabilities(&adapter);
+        assert_eq!(caps.current_extent, Some(core::Extent2D { width: 64, height: 32 }));
+        assert_eq!(caps.max_image_count, Some(MAX_BACKBUFFERS));
+
+        let config = core::SwapchainConfig::new().with_image_count(3);
+        let mut swapchain = surface.build_swapchain(config.clone(), &general_queues[0]);
+        assert_eq!(swapchain.get_backbuffers().len(), 3);
+
+        let config = config.with_image_count(100);
+        let mut swapchain = surface.recreate_swapchain(swapchain, config, &general_queues[0]);
+        assert_eq!(swapchain.get_backbuffers().len(), MAX_BACKBUFFERS as usize);
+    }
+
+    #[test]
+    fn resize_surface() {
+        use core::{FrameSync, Surface, Swapchain};
+        let core::Gpu { mut device, mut general_queues, .. } = open();
+        let mut surface = super::Surface::new(64, 32);
+        let config = core::SwapchainConfig::new();
+        let mut swapchain = surface.build_swapchain(config.clone(), &general_queues[0]);
+        let semaphore = device.create_semaphore();
+
+        assert!(swapchain.acquire_frame(FrameSync::Semaphore(&semaphore)).is_ok());
+        surface.resize(32, 32);
+        assert_eq!(swapchain.present(&mut general_queues[0], &[]), Ok(true));
+        assert_eq!(swapchain.acquire_frame(FrameSync::Semaphore(&semaphore)).err(), Some(core::AcquireError::OutOfDate));
+
+        let mut swapchain = surface.recreate_swapchain(swapchain, config, &general_queues[0]);
+        assert!(swapchain.acquire_frame(FrameSync::Semaphore(&semaphore)).is_ok());
+        assert_eq!(swapchain.present(&mut general_queues[0], &[]), Ok(false));
     }
 }
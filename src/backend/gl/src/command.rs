@@ -1,10 +1,10 @@
 #![allow(missing_docs)]
 
 use gl;
-use core::{self as c, command, image, memory, target, Viewport};
+use core::{self as c, command, image, memory, query, target, Viewport};
 use core::buffer::IndexBufferView;
 use core::command::{
-    BufferCopy, BufferImageCopy, ClearValue, ImageCopy, ImageResolve, SubpassContents,
+    BufferCopy, BufferImageCopy, ClearValue, ImageBlit, ImageCopy, ImageResolve, SubpassContents,
 };
 use core::target::{ColorValue, Stencil};
 use {native as n, Backend};
@@ -82,6 +82,22 @@ pub enum Command {
     BindFrameBuffer(FrameBufferTarget, n::FrameBuffer),
     BindTargetView(FrameBufferTarget, AttachmentPoint, n::TargetView),
     SetDrawColorBuffers(usize),
+    BlitImage {
+        src: n::Image,
+        dst: n::Image,
+        region: ImageBlit,
+        filter: gl::types::GLenum,
+    },
+    BeginQuery(gl::types::GLenum, n::Query),
+    EndQuery(gl::types::GLenum),
+    WriteTimestamp(n::Query),
+    CopyQueryPoolResults {
+        queries: BufferSlice,
+        buffer: n::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    },
 }
 
 pub type FrameBufferTarget = gl::types::GLenum;
@@ -100,6 +116,8 @@ struct Cache {
     blend_color: Option<ColorValue>,
     ///
     framebuffer: Option<(FrameBufferTarget, n::FrameBuffer)>,
+    // Target of the active occlusion query, depending on its precision.
+    occlusion_target: Option<gl::types::GLenum>,
     ///
     // Indicates that invalid commands have been recorded.
     error_state: bool,
@@ -113,6 +131,7 @@ impl Cache {
             stencil_ref: None,
             blend_color: None,
             framebuffer: None,
+            occlusion_target: None,
             error_state: false,
         }
     }
@@ -372,6 +391,29 @@ impl command::RawCommandBuffer<Backend> for RawCommandBuffer {
         unimplemented!()
     }
 
+    fn blit_image(
+        &mut self,
+        src: &n::Image,
+        _src_layout: image::ImageLayout,
+        dst: &n::Image,
+        _dst_layout: image::ImageLayout,
+        regions: &[ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        let filter = match filter {
+            image::FilterMethod::Scale => gl::NEAREST,
+            _ => gl::LINEAR,
+        };
+        for region in regions {
+            self.push_cmd(Command::BlitImage {
+                src: *src,
+                dst: *dst,
+                region: region.clone(),
+                filter,
+            });
+        }
+    }
+
     fn bind_index_buffer(&mut self, ibv: IndexBufferView<Backend>) {
         // TODO: how can we incoporate the buffer offset?
         if ibv.offset > 0 {
@@ -589,6 +631,62 @@ impl command::RawCommandBuffer<Backend> for RawCommandBuffer {
     ) {
         unimplemented!()
     }
+
+    fn begin_query(&mut self, query: query::Query<Backend>, flags: query::QueryControl) {
+        match query.pool.ty {
+            query::QueryType::Occlusion => {
+                let target = if flags.contains(query::PRECISE) {
+                    gl::SAMPLES_PASSED
+                } else {
+                    gl::ANY_SAMPLES_PASSED
+                };
+                self.cache.occlusion_target = Some(target);
+                self.push_cmd(Command::BeginQuery(target, query.pool.queries[query.id as usize]));
+            }
+            ty => {
+                error!("Queries of type {:?} can't be started", ty);
+                self.cache.error_state = true;
+            }
+        }
+    }
+
+    fn end_query(&mut self, _query: query::Query<Backend>) {
+        match self.cache.occlusion_target.take() {
+            Some(target) => self.push_cmd(Command::EndQuery(target)),
+            None => {
+                error!("No active query to end");
+                self.cache.error_state = true;
+            }
+        }
+    }
+
+    fn reset_query_pool(&mut self, _pool: &n::QueryPool, _queries: Range<query::QueryId>) {
+        // GL overwrites the results of a query object each time it is used.
+    }
+
+    fn write_timestamp(&mut self, _stage: c::pso::PipelineStage, query: query::Query<Backend>) {
+        // GL records the timestamp once all previous commands have completed.
+        self.push_cmd(Command::WriteTimestamp(query.pool.queries[query.id as usize]));
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &n::QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: &n::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    ) {
+        let queries = self.add(&pool.queries[queries.start as usize .. queries.end as usize]);
+        self.push_cmd(Command::CopyQueryPoolResults {
+            queries,
+            buffer: *buffer,
+            offset,
+            stride,
+            flags,
+        });
+    }
 }
 
 /// A subpass command buffer abstraction for OpenGL
@@ -1,7 +1,207 @@
 
+use core::format::Format;
 use core::image as i;
 use gl::{self, types as t};
 
+// EXT_texture_compression_s3tc and EXT_texture_sRGB
+const COMPRESSED_RGB_S3TC_DXT1: t::GLenum = 0x83F0;
+const COMPRESSED_RGBA_S3TC_DXT1: t::GLenum = 0x83F1;
+const COMPRESSED_RGBA_S3TC_DXT3: t::GLenum = 0x83F2;
+const COMPRESSED_RGBA_S3TC_DXT5: t::GLenum = 0x83F3;
+const COMPRESSED_SRGB_S3TC_DXT1: t::GLenum = 0x8C4C;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT1: t::GLenum = 0x8C4D;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT3: t::GLenum = 0x8C4E;
+const COMPRESSED_SRGB_ALPHA_S3TC_DXT5: t::GLenum = 0x8C4F;
+// KHR_texture_compression_astc_ldr, ordered by block size
+const COMPRESSED_RGBA_ASTC_4X4: t::GLenum = 0x93B0;
+const COMPRESSED_SRGB8_ALPHA8_ASTC_4X4: t::GLenum = 0x93D0;
+
+pub fn format_to_gl(format: Format) -> Option<t::GLenum> {
+    use core::format::SurfaceType::*;
+    use core::format::ChannelType::*;
+    Some(match format.0 {
+        R4_G4 => return None,
+        R4_G4_B4_A4 => match format.1 {
+            Unorm => gl::RGBA4,
+            _ => return None,
+        },
+        R5_G5_B5_A1 => match format.1 {
+            Unorm => gl::RGB5_A1,
+            _ => return None,
+        },
+        R5_G6_B5 => match format.1 {
+            Unorm => gl::RGB565,
+            _ => return None,
+        },
+        R8 => match format.1 {
+            Int   => gl::R8I,
+            Uint  => gl::R8UI,
+            Inorm => gl::R8_SNORM,
+            Unorm => gl::R8,
+            _ => return None,
+        },
+        R8_G8 => match format.1 {
+            Int   => gl::RG8I,
+            Uint  => gl::RG8UI,
+            Inorm => gl::RG8_SNORM,
+            Unorm => gl::RG8,
+            _ => return None,
+        },
+        // BGRA is only a client side ordering, the storage is the same.
+        R8_G8_B8_A8 | B8_G8_R8_A8 => match format.1 {
+            Int   => gl::RGBA8I,
+            Uint  => gl::RGBA8UI,
+            Inorm => gl::RGBA8_SNORM,
+            Unorm => gl::RGBA8,
+            Srgb  => gl::SRGB8_ALPHA8,
+            _ => return None,
+        },
+        R10_G10_B10_A2 => match format.1 {
+            Uint  => gl::RGB10_A2UI,
+            Unorm => gl::RGB10_A2,
+            _ => return None,
+        },
+        R11_G11_B10 => match format.1 {
+            Float => gl::R11F_G11F_B10F,
+            _ => return None,
+        },
+        R16 => match format.1 {
+            Int   => gl::R16I,
+            Uint  => gl::R16UI,
+            Inorm => gl::R16_SNORM,
+            Unorm => gl::R16,
+            Float => gl::R16F,
+            _ => return None,
+        },
+        R16_G16 => match format.1 {
+            Int   => gl::RG16I,
+            Uint  => gl::RG16UI,
+            Inorm => gl::RG16_SNORM,
+            Unorm => gl::RG16,
+            Float => gl::RG16F,
+            _ => return None,
+        },
+        R16_G16_B16 => match format.1 {
+            Int   => gl::RGB16I,
+            Uint  => gl::RGB16UI,
+            Inorm => gl::RGB16_SNORM,
+            Unorm => gl::RGB16,
+            Float => gl::RGB16F,
+            _ => return None,
+        },
+        R16_G16_B16_A16 => match format.1 {
+            Int   => gl::RGBA16I,
+            Uint  => gl::RGBA16UI,
+            Inorm => gl::RGBA16_SNORM,
+            Unorm => gl::RGBA16,
+            Float => gl::RGBA16F,
+            _ => return None,
+        },
+        R32 => match format.1 {
+            Int   => gl::R32I,
+            Uint  => gl::R32UI,
+            Float => gl::R32F,
+            _ => return None,
+        },
+        R32_G32 => match format.1 {
+            Int   => gl::RG32I,
+            Uint  => gl::RG32UI,
+            Float => gl::RG32F,
+            _ => return None,
+        },
+        R32_G32_B32 => match format.1 {
+            Int   => gl::RGB32I,
+            Uint  => gl::RGB32UI,
+            Float => gl::RGB32F,
+            _ => return None,
+        },
+        R32_G32_B32_A32 => match format.1 {
+            Int   => gl::RGBA32I,
+            Uint  => gl::RGBA32UI,
+            Float => gl::RGBA32F,
+            _ => return None,
+        },
+        D16 => gl::DEPTH_COMPONENT16,
+        D24 => gl::DEPTH_COMPONENT24,
+        D24_S8 => gl::DEPTH24_STENCIL8,
+        D32 => gl::DEPTH_COMPONENT32F,
+        D32_S8 => gl::DEPTH32F_STENCIL8,
+        BC1_R8_G8_B8 => match format.1 {
+            Unorm => COMPRESSED_RGB_S3TC_DXT1,
+            Srgb  => COMPRESSED_SRGB_S3TC_DXT1,
+            _ => return None,
+        },
+        BC1_R8_G8_B8_A8 => match format.1 {
+            Unorm => COMPRESSED_RGBA_S3TC_DXT1,
+            Srgb  => COMPRESSED_SRGB_ALPHA_S3TC_DXT1,
+            _ => return None,
+        },
+        BC2_R8_G8_B8_A8 => match format.1 {
+            Unorm => COMPRESSED_RGBA_S3TC_DXT3,
+            Srgb  => COMPRESSED_SRGB_ALPHA_S3TC_DXT3,
+            _ => return None,
+        },
+        BC3_R8_G8_B8_A8 => match format.1 {
+            Unorm => COMPRESSED_RGBA_S3TC_DXT5,
+            Srgb  => COMPRESSED_SRGB_ALPHA_S3TC_DXT5,
+            _ => return None,
+        },
+        BC4_R8 => match format.1 {
+            Unorm => gl::COMPRESSED_RED_RGTC1,
+            Inorm => gl::COMPRESSED_SIGNED_RED_RGTC1,
+            _ => return None,
+        },
+        BC5_R8_G8 => match format.1 {
+            Unorm => gl::COMPRESSED_RG_RGTC2,
+            Inorm => gl::COMPRESSED_SIGNED_RG_RGTC2,
+            _ => return None,
+        },
+        BC6_R16_G16_B16 => match format.1 {
+            Float => gl::COMPRESSED_RGB_BPTC_UNSIGNED_FLOAT,
+            _ => return None,
+        },
+        BC7_R8_G8_B8_A8 => match format.1 {
+            Unorm => gl::COMPRESSED_RGBA_BPTC_UNORM,
+            Srgb  => gl::COMPRESSED_SRGB_ALPHA_BPTC_UNORM,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8 => match format.1 {
+            Unorm => gl::COMPRESSED_RGB8_ETC2,
+            Srgb  => gl::COMPRESSED_SRGB8_ETC2,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8_A1 => match format.1 {
+            Unorm => gl::COMPRESSED_RGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            Srgb  => gl::COMPRESSED_SRGB8_PUNCHTHROUGH_ALPHA1_ETC2,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8_A8 => match format.1 {
+            Unorm => gl::COMPRESSED_RGBA8_ETC2_EAC,
+            Srgb  => gl::COMPRESSED_SRGB8_ALPHA8_ETC2_EAC,
+            _ => return None,
+        },
+        EAC_R11 => match format.1 {
+            Unorm => gl::COMPRESSED_R11_EAC,
+            Inorm => gl::COMPRESSED_SIGNED_R11_EAC,
+            _ => return None,
+        },
+        EAC_R11_G11 => match format.1 {
+            Unorm => gl::COMPRESSED_RG11_EAC,
+            Inorm => gl::COMPRESSED_SIGNED_RG11_EAC,
+            _ => return None,
+        },
+        ASTC_4x4 | ASTC_5x4 | ASTC_5x5 | ASTC_6x5 | ASTC_6x6 | ASTC_8x5 | ASTC_8x6 |
+        ASTC_8x8 | ASTC_10x5 | ASTC_10x6 | ASTC_10x8 | ASTC_10x10 | ASTC_12x10 | ASTC_12x12 => {
+            let index = format.0 as t::GLenum - ASTC_4x4 as t::GLenum;
+            match format.1 {
+                Unorm => COMPRESSED_RGBA_ASTC_4X4 + index,
+                Srgb  => COMPRESSED_SRGB8_ALPHA8_ASTC_4X4 + index,
+                _ => return None,
+            }
+        }
+    })
+}
+
 pub fn image_kind_to_gl(kind: i::Kind) -> t::GLenum {
     match kind {
         i::Kind::D1(_) => gl::TEXTURE_1D,
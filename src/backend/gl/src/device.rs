@@ -4,12 +4,13 @@ use std::rc::Rc;
 
 use gl;
 use gl::types::{GLint, GLfloat};
-use core::{self as c, device as d, image as i, memory, pass, pso, buffer, mapping};
+use core::{self as c, device as d, image as i, memory, pass, pso, buffer, mapping, query};
 use core::format::Format;
+use spirv_cross::{glsl, spirv, ErrorCode as SpirvErrorCode};
 use std::iter::repeat;
 
 use {Backend as B, Share};
-use {conv, device, native as n, state};
+use {conv, device, info, native as n, state};
 
 
 fn get_shader_iv(gl: &gl::Gl, name: n::Shader, query: gl::types::GLenum) -> gl::types::GLint {
@@ -40,6 +41,65 @@ fn get_shader_log(gl: &gl::Gl, name: n::Shader) -> String {
     }
 }
 
+/// GLSL version to translate SPIR-V into, matching the shading language version of the context.
+fn glsl_version(version: &info::Version) -> glsl::Version {
+    match (version.is_embedded, version.major, version.minor) {
+        (true, 3, _) => glsl::Version::V3_00Es,
+        (true, _, _) => glsl::Version::V1_00Es,
+        (false, 4, minor) if minor >= 50 => glsl::Version::V4_50,
+        (false, 4, minor) if minor >= 40 => glsl::Version::V4_40,
+        (false, 4, minor) if minor >= 30 => glsl::Version::V4_30,
+        (false, 4, minor) if minor >= 20 => glsl::Version::V4_20,
+        (false, 4, minor) if minor >= 10 => glsl::Version::V4_10,
+        (false, 4, _) => glsl::Version::V4_00,
+        (false, 3, _) => glsl::Version::V3_30,
+        (false, 1, minor) if minor >= 50 => glsl::Version::V1_50,
+        (false, 1, minor) if minor >= 40 => glsl::Version::V1_40,
+        (false, 1, minor) if minor >= 30 => glsl::Version::V1_30,
+        (false, _, _) => glsl::Version::V1_20,
+    }
+}
+
+/// Translate a SPIR-V module into GLSL source, returning the stage of its entry point.
+fn translate_spirv(byte_code: &[u8], version: glsl::Version) -> Result<(pso::Stage, String), pso::CreateShaderError> {
+    if byte_code.len() % 4 != 0 {
+        let message = format!("SPIR-V code size of {} is not a multiple of 4", byte_code.len());
+        return Err(pso::CreateShaderError::CompilationFailed(message));
+    }
+    let words = byte_code
+        .chunks(4)
+        .map(|bytes| bytes[0] as u32 | (bytes[1] as u32) << 8 | (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24)
+        .collect::<Vec<_>>();
+    let compilation_failed = |err| pso::CreateShaderError::CompilationFailed(match err {
+        SpirvErrorCode::CompilationError(message) => message,
+        SpirvErrorCode::Unhandled => "Unhandled SPIR-V translation error".to_string(),
+    });
+
+    let module = spirv::Module::from_words(&words);
+    let mut ast = spirv::Ast::<glsl::Target>::parse(&module).map_err(&compilation_failed)?;
+    let stage = {
+        let entry_points = ast.get_entry_points().map_err(&compilation_failed)?;
+        let entry_point = entry_points
+            .first()
+            .ok_or_else(|| pso::CreateShaderError::CompilationFailed("SPIR-V module without entry point".to_string()))?;
+        match entry_point.execution_model {
+            spirv::ExecutionModel::Vertex => pso::Stage::Vertex,
+            spirv::ExecutionModel::TessellationControl => pso::Stage::Hull,
+            spirv::ExecutionModel::TessellationEvaluation => pso::Stage::Domain,
+            spirv::ExecutionModel::Geometry => pso::Stage::Geometry,
+            spirv::ExecutionModel::Fragment => pso::Stage::Pixel,
+            spirv::ExecutionModel::GlCompute => pso::Stage::Compute,
+            spirv::ExecutionModel::Kernel => return Err(pso::CreateShaderError::ModelNotSupported),
+        }
+    };
+
+    let mut options = glsl::CompilerOptions::default();
+    options.version = version;
+    ast.set_compiler_options(&options).map_err(&compilation_failed)?;
+    let source = ast.compile().map_err(&compilation_failed)?;
+    Ok((stage, source))
+}
+
 pub fn get_program_log(gl: &gl::Gl, name: n::Program) -> String {
     let mut length  = get_program_iv(gl, name, gl::INFO_LOG_LENGTH);
     if length > 0 {
@@ -148,10 +208,30 @@ impl d::Device<B> for Device {
         &self.share.limits
     }
 
+    fn get_format_properties(&self, format: Format) -> c::format::FormatProperties {
+        info::get_format_properties(&self.share.info, format)
+    }
+
     fn create_heap(&mut self, _: &c::HeapType, _: d::ResourceHeapType, _: u64) -> Result<n::Heap, d::ResourceHeapError> {
         Ok(n::Heap)
     }
 
+    fn create_shader_library(&mut self, shaders: &[(pso::EntryPoint, &[u8])]) -> Result<n::ShaderLib, pso::CreateShaderError> {
+        // Translate each SPIR-V module into GLSL of the version supported by the context.
+        let version = glsl_version(&self.share.info.shading_language);
+        let sources = shaders
+            .iter()
+            .map(|&(entry_point, byte_code)| {
+                translate_spirv(byte_code, version).map(|(stage, source)| (entry_point, stage, source))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let shaders = sources
+            .iter()
+            .map(|&(entry_point, stage, ref source)| (entry_point, stage, source.as_bytes()))
+            .collect::<Vec<_>>();
+        self.create_shader_library_from_source(&shaders)
+    }
+
     fn create_renderpass(
         &mut self,
         attachments: &[pass::Attachment],
@@ -185,9 +265,21 @@ impl d::Device<B> for Device {
         n::PipelineLayout
     }
 
+    fn create_pipeline_cache(&mut self, _: &[u8]) -> n::PipelineCache {
+        n::PipelineCache
+    }
+
+    fn get_pipeline_cache_data(&mut self, _: &n::PipelineCache) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn merge_pipeline_caches(&mut self, _: &n::PipelineCache, _: &[&n::PipelineCache]) {
+    }
+
     fn create_graphics_pipelines<'a>(
         &mut self,
         descs: &[(&n::ShaderLib, &n::PipelineLayout, pass::Subpass<'a, B>, &pso::GraphicsPipelineDesc)],
+        _: Option<&n::PipelineCache>,
     ) -> Vec<Result<n::GraphicsPipeline, pso::CreationError>> {
         let gl = &self.share.context;
         let priv_caps = &self.share.private_caps;
@@ -259,6 +351,7 @@ impl d::Device<B> for Device {
     fn create_compute_pipelines(
         &mut self,
         _descs: &[(&n::ShaderLib, pso::EntryPoint, &n::PipelineLayout)],
+        _: Option<&n::PipelineCache>,
     ) -> Vec<Result<n::ComputePipeline, pso::CreationError>> {
         unimplemented!()
     }
@@ -483,6 +576,52 @@ impl d::Device<B> for Device {
         }
     }
 
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> n::QueryPool {
+        if let query::QueryType::PipelineStatistics(_) = ty {
+            panic!("Pipeline statistics queries are not supported");
+        }
+
+        let gl = &self.share.context;
+        let mut queries = vec![0; count as usize];
+        unsafe { gl.GenQueries(count as _, queries.as_mut_ptr()) };
+
+        n::QueryPool { ty, queries }
+    }
+
+    fn get_query_pool_results(&mut self, pool: &n::QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        let gl = &self.share.context;
+        let with_availability = flags.contains(query::WITH_AVAILABILITY);
+        let stride = 1 + with_availability as usize;
+        let mut all_available = true;
+
+        for (query, values) in pool.queries[queries.start as usize .. queries.end as usize]
+            .iter()
+            .zip(data.chunks_mut(stride))
+        {
+            // Retrieving the result blocks until it is available.
+            let available = flags.contains(query::WAIT) || {
+                let mut available = 0;
+                unsafe { gl.GetQueryObjectuiv(*query, gl::QUERY_RESULT_AVAILABLE, &mut available) };
+                available != 0
+            };
+
+            if available {
+                unsafe { gl.GetQueryObjectui64v(*query, gl::QUERY_RESULT, &mut values[0]) };
+            } else {
+                all_available = false;
+                if flags.contains(query::PARTIAL) {
+                    values[0] = 0;
+                }
+            }
+            if with_availability {
+                values[1] = available as u64;
+            }
+        }
+
+        all_available
+    }
+
     fn destroy_heap(&mut self, _: n::Heap) {
         unimplemented!()
     }
@@ -504,6 +643,8 @@ impl d::Device<B> for Device {
     fn destroy_compute_pipeline(&mut self, _: n::ComputePipeline) {
         unimplemented!()
     }
+    fn destroy_pipeline_cache(&mut self, _: n::PipelineCache) {
+    }
     fn destroy_framebuffer(&mut self, _: n::FrameBuffer) {
         unimplemented!()
     }
@@ -553,6 +694,11 @@ impl d::Device<B> for Device {
     fn destroy_semaphore(&mut self, _: n::Semaphore) {
         unimplemented!()
     }
+
+    fn destroy_query_pool(&mut self, pool: n::QueryPool) {
+        let gl = &self.share.context;
+        unsafe { gl.DeleteQueries(pool.queries.len() as _, pool.queries.as_ptr()) };
+    }
 }
 
 pub fn wait_fence(fence: &n::Fence, gl: &gl::Gl, timeout_ms: u32) -> gl::types::GLenum {
@@ -1,7 +1,8 @@
 use std::collections::HashSet;
 use std::{ffi, fmt, mem, str};
 use gl;
-use core::{Features, Limits};
+use core::{format, Features, Limits};
+use conv;
 
 /// A version number for a specific component of an OpenGL implementation
 #[derive(Copy, Clone, Eq, Ord, PartialEq, PartialOrd)]
@@ -156,6 +157,8 @@ pub struct PrivateCaps {
     pub clear_buffer_supported: bool,
     pub frag_data_location_supported: bool,
     pub sync_supported: bool,
+    /// Indicates if query results can be written into buffers by the GPU.
+    pub query_buffer_supported: bool,
     /// Indicates if we only have support via the EXT.
     pub sampler_anisotropy_ext: bool,
 }
@@ -251,6 +254,9 @@ pub fn get(gl: &gl::Gl) -> (Info, Features, Limits, PrivateCaps) {
 
         min_buffer_copy_offset_alignment: 1,
         min_buffer_copy_pitch_alignment: 1,
+        min_constant_buffer_offset_alignment: get_usize(gl, gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT),
+        // Timestamps are reported in nanoseconds.
+        timestamp_period: 1.0,
 
     };
     let features = Features {
@@ -295,6 +301,11 @@ pub fn get(gl: &gl::Gl) -> (Info, Features, Limits, PrivateCaps) {
                                                                 Ext ("GL_ARB_texture_filter_anisotropic"),
                                                                 Ext ("GL_EXT_texture_filter_anisotropic")]),
         sampler_border_color:               info.is_supported(&[Core(3,3)]), // TODO: extensions
+        precise_occlusion_query:            info.is_supported(&[Core(1,5),
+                                                                Ext ("GL_ARB_occlusion_query")]),
+        pipeline_statistics_query:          false, // TODO: GL_ARB_pipeline_statistics_query
+        timestamp_query:                    info.is_supported(&[Core(3,3),
+                                                                Ext ("GL_ARB_timer_query")]),
     };
     let private = PrivateCaps {
         array_buffer_supported:             info.is_supported(&[Core(3,0),
@@ -314,6 +325,8 @@ pub fn get(gl: &gl::Gl) -> (Info, Features, Limits, PrivateCaps) {
                                                                 Es  (3,0)]),
         frag_data_location_supported:       !info.version.is_embedded,
 
+        query_buffer_supported:             info.is_supported(&[Core(4,4),
+                                                                Ext ("GL_ARB_query_buffer_object")]),
         sync_supported:                     info.is_supported(&[Core(3,2),
                                                                 Es  (3,0),
                                                                 Ext ("GL_ARB_sync")]),
@@ -325,6 +338,87 @@ pub fn get(gl: &gl::Gl) -> (Info, Features, Limits, PrivateCaps) {
     (info, features, limits, private)
 }
 
+/// Query the capabilities of the implementation for a format.
+///
+/// OpenGL doesn't expose the tiling of textures, so only optimal tiling is reported.
+pub fn get_format_properties(info: &Info, format: format::Format) -> format::FormatProperties {
+    use self::Requirement::*;
+    use core::format::SurfaceType::*;
+    use core::format::ChannelType::{Int, Uint};
+
+    if conv::format_to_gl(format).is_none() {
+        return format::FormatProperties::unsupported();
+    }
+    let compression_supported = match format.0 {
+        BC1_R8_G8_B8 | BC1_R8_G8_B8_A8 | BC2_R8_G8_B8_A8 | BC3_R8_G8_B8_A8 =>
+                                            info.is_supported(&[Ext ("GL_EXT_texture_compression_s3tc")]),
+        BC4_R8 | BC5_R8_G8 =>               info.is_supported(&[Core(3,0),
+                                                                Ext ("GL_ARB_texture_compression_rgtc")]),
+        BC6_R16_G16_B16 | BC7_R8_G8_B8_A8 =>
+                                            info.is_supported(&[Core(4,2),
+                                                                Ext ("GL_ARB_texture_compression_bptc")]),
+        ETC2_R8_G8_B8 | ETC2_R8_G8_B8_A1 | ETC2_R8_G8_B8_A8 | EAC_R11 | EAC_R11_G11 =>
+                                            info.is_supported(&[Core(4,3),
+                                                                Es  (3,0),
+                                                                Ext ("GL_ARB_ES3_compatibility")]),
+        surface if surface.is_compressed() =>
+                                            info.is_supported(&[Es  (3,2),
+                                                                Ext ("GL_KHR_texture_compression_astc_ldr")]),
+        _ => true,
+    };
+    if !compression_supported {
+        return format::FormatProperties::unsupported();
+    }
+    let image_load_store_supported =        info.is_supported(&[Core(4,2),
+                                                                Es  (3,1),
+                                                                Ext ("GL_ARB_shader_image_load_store")]);
+    let texture_buffer_supported =          info.is_supported(&[Core(3,1),
+                                                                Es  (3,2),
+                                                                Ext ("GL_ARB_texture_buffer_object")]);
+    // Blits are performed between framebuffer objects.
+    let blit_supported =                    info.is_supported(&[Core(3,0),
+                                                                Es  (3,0),
+                                                                Ext ("GL_ARB_framebuffer_object")]);
+    let integer = format.1 == Int || format.1 == Uint;
+    let blit = if blit_supported {
+        format::BLIT_SRC | format::BLIT_DST
+    } else {
+        format::ImageFeature::empty()
+    };
+
+    let (image_features, buffer_features) = match format.0 {
+        D16 | D24 | D24_S8 | D32 | D32_S8 => (
+            format::SAMPLED | format::DEPTH_STENCIL_ATTACHMENT | blit,
+            format::BufferFeature::empty(),
+        ),
+        surface if surface.is_compressed() => (
+            format::SAMPLED | format::SAMPLED_LINEAR,
+            format::BufferFeature::empty(),
+        ),
+        _ => {
+            let mut image = format::SAMPLED | format::COLOR_ATTACHMENT | blit;
+            let mut buffer = format::VERTEX;
+            if !integer {
+                image |= format::SAMPLED_LINEAR | format::COLOR_ATTACHMENT_BLEND;
+            }
+            if image_load_store_supported {
+                image |= format::STORAGE;
+                buffer |= format::STORAGE_TEXEL;
+            }
+            if texture_buffer_supported {
+                buffer |= format::UNIFORM_TEXEL;
+            }
+            (image, buffer)
+        }
+    };
+
+    format::FormatProperties {
+        linear_tiling: format::ImageFeature::empty(),
+        optimal_tiling: image_features,
+        buffer_features,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::Version;
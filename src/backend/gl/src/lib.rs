@@ -9,6 +9,7 @@ extern crate log;
 extern crate gfx_gl as gl;
 extern crate gfx_core as core;
 extern crate smallvec;
+extern crate spirv_cross;
 #[cfg(feature = "glutin")]
 extern crate glutin;
 
@@ -71,11 +72,14 @@ impl c::Backend for Backend {
 
     type ComputePipeline = native::ComputePipeline;
     type GraphicsPipeline = native::GraphicsPipeline;
+    type PipelineCache = native::PipelineCache;
     type PipelineLayout = native::PipelineLayout;
     type DescriptorSetLayout = native::DescriptorSetLayout;
     type DescriptorPool = native::DescriptorPool;
     type DescriptorSet = native::DescriptorSet;
 
+    type QueryPool = native::QueryPool;
+
     type Fence = native::Fence;
     type Semaphore = native::Semaphore;
 }
@@ -259,10 +263,19 @@ impl c::Adapter<Backend> for Adapter {
             memory_heaps: Vec::new(), // TODO
         };
 
+        let mut blit_fbos = (0, 0);
+        if self.share.private_caps.frame_buffer_supported {
+            unsafe {
+                gl.GenFramebuffers(1, &mut blit_fbos.0);
+                gl.GenFramebuffers(1, &mut blit_fbos.1);
+            }
+        }
+
         let raw_queue = || {
             CommandQueue {
                 share: self.share.clone(),
                 vao,
+                blit_fbos,
                 state: State::new(),
             }
         };
@@ -302,6 +315,8 @@ impl c::Adapter<Backend> for Adapter {
 pub struct CommandQueue {
     share: Rc<Share>,
     vao: ArrayBuffer,
+    // Read and draw framebuffers for blitting between images.
+    blit_fbos: (native::FrameBuffer, native::FrameBuffer),
     state: State,
 }
 
@@ -445,6 +460,114 @@ impl CommandQueue {
         }
     }
 
+    // Attach a layer of an image level to the bound framebuffer of `point`.
+    fn bind_image_layer(&mut self, point: gl::types::GLenum, attachment: gl::types::GLenum,
+                        image: native::Image, level: c::image::Level, layer: Option<c::image::Layer>) {
+        let view = match (image, layer) {
+            (native::Image::Surface(surface), _) => native::TargetView::Surface(surface),
+            (native::Image::Texture(texture), None) => native::TargetView::Texture(texture, level),
+            (native::Image::Texture(texture), Some(layer)) =>
+                native::TargetView::TextureLayer(texture, level, layer),
+        };
+        self.bind_target(point, attachment, &view)
+    }
+
+    fn blit_image(&mut self, src: native::Image, dst: native::Image,
+                  region: &com::ImageBlit, filter: gl::types::GLenum) {
+        if !self.share.private_caps.frame_buffer_supported {
+            error!("Blitting images requires framebuffer objects");
+            return;
+        }
+
+        let aspects = region.aspect_mask;
+        let (attachment, mask) = if aspects.contains(c::image::ASPECT_COLOR) {
+            (gl::COLOR_ATTACHMENT0, gl::COLOR_BUFFER_BIT)
+        } else if aspects.contains(c::image::ASPECT_DEPTH | c::image::ASPECT_STENCIL) {
+            (gl::DEPTH_STENCIL_ATTACHMENT, gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT)
+        } else if aspects.contains(c::image::ASPECT_DEPTH) {
+            (gl::DEPTH_ATTACHMENT, gl::DEPTH_BUFFER_BIT)
+        } else {
+            (gl::STENCIL_ATTACHMENT, gl::STENCIL_BUFFER_BIT)
+        };
+        // Depth and stencil values can't be interpolated.
+        let filter = if mask == gl::COLOR_BUFFER_BIT { filter } else { gl::NEAREST };
+
+        let (src_level, ref src_layers) = region.src_subresource;
+        let (dst_level, ref dst_layers) = region.dst_subresource;
+        // Non-array images are attached as a whole.
+        let layered = src_layers.end - src_layers.start > 1 || src_layers.start > 0 ||
+                      dst_layers.end - dst_layers.start > 1 || dst_layers.start > 0;
+        let (read_fbo, draw_fbo) = self.blit_fbos;
+        let (src0, src1) = (region.src_bounds.start, region.src_bounds.end);
+        let (dst0, dst1) = (region.dst_bounds.start, region.dst_bounds.end);
+
+        for (src_layer, dst_layer) in src_layers.clone().zip(dst_layers.clone()) {
+            unsafe { self.share.context.BindFramebuffer(gl::READ_FRAMEBUFFER, read_fbo) };
+            self.bind_image_layer(gl::READ_FRAMEBUFFER, attachment, src, src_level,
+                                  if layered { Some(src_layer) } else { None });
+            unsafe { self.share.context.BindFramebuffer(gl::DRAW_FRAMEBUFFER, draw_fbo) };
+            self.bind_image_layer(gl::DRAW_FRAMEBUFFER, attachment, dst, dst_level,
+                                  if layered { Some(dst_layer) } else { None });
+            unsafe {
+                self.share.context.BlitFramebuffer(
+                    src0.x, src0.y, src1.x, src1.y,
+                    dst0.x, dst0.y, dst1.x, dst1.y,
+                    mask, filter,
+                );
+            }
+        }
+
+        // Blits are recorded outside of render passes, which bind their own framebuffer.
+        unsafe { self.share.context.BindFramebuffer(gl::FRAMEBUFFER, 0) };
+    }
+
+    fn copy_query_pool_results(&mut self, queries: &[native::Query], buffer: native::Buffer,
+                               offset: u64, stride: u64, flags: c::query::ResultFlags) {
+        let gl = &self.share.context;
+        let with_availability = flags.contains(c::query::WITH_AVAILABILITY);
+
+        if self.share.private_caps.query_buffer_supported {
+            // Results are written by the GPU, unavailable ones are left untouched.
+            let pname = if flags.contains(c::query::WAIT) { gl::QUERY_RESULT } else { gl::QUERY_RESULT_NO_WAIT };
+            unsafe { gl.BindBuffer(gl::QUERY_BUFFER, buffer) };
+            for (i, &query) in queries.iter().enumerate() {
+                let address = offset + i as u64 * stride;
+                unsafe {
+                    gl.GetQueryObjectui64v(query, pname, address as *mut _);
+                    if with_availability {
+                        gl.GetQueryObjectui64v(query, gl::QUERY_RESULT_AVAILABLE, (address + 8) as *mut _);
+                    }
+                }
+            }
+            unsafe { gl.BindBuffer(gl::QUERY_BUFFER, 0) };
+            return;
+        }
+
+        // Read the results back and upload them, stalling on `WAIT`.
+        unsafe { gl.BindBuffer(gl::ARRAY_BUFFER, buffer) };
+        for (i, &query) in queries.iter().enumerate() {
+            let address = offset + i as u64 * stride;
+            let available = flags.contains(c::query::WAIT) || {
+                let mut available = 0;
+                unsafe { gl.GetQueryObjectuiv(query, gl::QUERY_RESULT_AVAILABLE, &mut available) };
+                available != 0
+            };
+            let mut value = 0u64;
+            if available {
+                unsafe { gl.GetQueryObjectui64v(query, gl::QUERY_RESULT, &mut value) };
+            }
+            // Unavailable results are only written as partial values.
+            if available || flags.contains(c::query::PARTIAL) {
+                unsafe { gl.BufferSubData(gl::ARRAY_BUFFER, address as _, 8, &value as *const u64 as *const _) };
+            }
+            if with_availability {
+                let available = available as u64;
+                unsafe { gl.BufferSubData(gl::ARRAY_BUFFER, (address + 8) as _, 8, &available as *const u64 as *const _) };
+            }
+        }
+        unsafe { gl.BindBuffer(gl::ARRAY_BUFFER, 0) };
+    }
+
     fn unbind_target(&mut self, point: gl::types::GLenum, attachment: gl::types::GLenum) {
         let gl = &self.share.context;
         unsafe { gl.FramebufferTexture(point, attachment, 0, 0) };
@@ -744,6 +867,22 @@ impl CommandQueue {
             Command::SetDrawColorBuffers(num) => {
                 state::bind_draw_color_buffers(&self.share.context, num);
             }
+            Command::BlitImage { src, dst, ref region, filter } => {
+                self.blit_image(src, dst, region, filter);
+            }
+            Command::BeginQuery(target, query) => unsafe {
+                self.share.context.BeginQuery(target, query);
+            },
+            Command::EndQuery(target) => unsafe {
+                self.share.context.EndQuery(target);
+            },
+            Command::WriteTimestamp(query) => unsafe {
+                self.share.context.QueryCounter(query, gl::TIMESTAMP);
+            },
+            Command::CopyQueryPoolResults { queries, buffer, offset, stride, flags } => {
+                let queries = Self::get::<native::Query>(data_buf, queries);
+                self.copy_query_pool_results(queries, buffer, offset, stride, flags);
+            }
             /*
             Command::BindProgram(program) => unsafe {
                 self.share.context.UseProgram(program);
@@ -1,6 +1,6 @@
 
 use conv;
-use core::{self, pass, pso};
+use core::{self, pass, pso, query};
 use core::target::{Layer, Level};
 use core::image as i;
 use gl;
@@ -15,6 +15,7 @@ pub type FrameBuffer = gl::types::GLuint;
 pub type Surface     = gl::types::GLuint;
 pub type Texture     = gl::types::GLuint;
 pub type Sampler     = gl::types::GLuint;
+pub type Query       = gl::types::GLuint;
 
 #[derive(Debug)]
 pub struct Fence(pub Cell<gl::types::GLsync>);
@@ -27,6 +28,12 @@ impl Fence {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct QueryPool {
+    pub(crate) ty: query::QueryType,
+    pub(crate) queries: Vec<Query>,
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub struct ResourceView {
     pub object: Texture,
@@ -62,6 +69,10 @@ pub struct ComputePipeline {
     pub program: Program,
 }
 
+/// OpenGL doesn't expose a pipeline cache, programs are compiled on creation.
+#[derive(Clone, Copy, Debug)]
+pub struct PipelineCache;
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Image {
     Surface(Surface),
@@ -91,6 +91,8 @@ pub struct Swapchain {
     window: Rc<glutin::GlWindow>,
     // Single element backbuffer
     backbuffer: [core::Backbuffer<B>; 1],
+    // Window dimensions at creation, to detect resizing
+    dimensions: image::Dimensions,
 }
 
 impl core::Swapchain<B> for Swapchain {
@@ -98,13 +100,24 @@ impl core::Swapchain<B> for Swapchain {
         &self.backbuffer
     }
 
-    fn acquire_frame(&mut self, _sync: core::FrameSync<B>) -> core::Frame {
+    fn acquire_frame(&mut self, _sync: core::FrameSync<B>) -> Result<core::Frame, core::AcquireError> {
+        if get_window_dimensions(&self.window) != self.dimensions {
+            return Err(core::AcquireError::OutOfDate);
+        }
         // TODO: sync
-        core::Frame::new(0)
+        Ok(core::Frame::new(0))
     }
 
-    fn present<C>(&mut self, _: &mut core::CommandQueue<B, C>, _: &[&n::Semaphore]) {
-        self.window.swap_buffers().unwrap();
+    fn present<C>(
+        &mut self,
+        _: &mut core::CommandQueue<B, C>,
+        _: &[&n::Semaphore],
+    ) -> Result<core::Suboptimal, core::PresentError> {
+        match self.window.swap_buffers() {
+            Ok(()) => Ok(get_window_dimensions(&self.window) != self.dimensions),
+            Err(glutin::ContextError::ContextLost) => Err(core::PresentError::SurfaceLost),
+            Err(err) => panic!("Unable to present: {:?}", err),
+        }
     }
 }
 
@@ -123,11 +136,32 @@ impl Surface {
 impl core::Surface<B> for Surface {
     fn supports_queue(&self, _: &QueueFamily) -> bool { true }
 
+    fn get_capabilities(&self, _: &Adapter) -> core::SurfaceCapabilities {
+        let (width, height, _, _) = get_window_dimensions(&self.window);
+        // The default framebuffer and its formats are fixed by the context,
+        // presentation is synchronized according to its vsync setting.
+        core::SurfaceCapabilities {
+            formats: None,
+            min_image_count: 1,
+            max_image_count: Some(1),
+            current_extent: Some(core::Extent2D {
+                width: width as u32,
+                height: height as u32,
+            }),
+            present_modes: vec![core::PresentMode::Fifo],
+        }
+    }
+
     fn build_swapchain<C>(
         &mut self,
         _config: core::SwapchainConfig,
         _: &core::CommandQueue<B, C>,
     ) -> Swapchain {
+        // The default framebuffer follows the window, but some platforms
+        // require the context to be resized explicitly.
+        let dimensions = get_window_dimensions(&self.window);
+        self.window.resize(dimensions.0 as u32, dimensions.1 as u32);
+
         let backbuffer = core::Backbuffer {
             color: n::Image::Surface(0),
             depth_stencil: Some(n::Image::Surface(0)),
@@ -136,6 +170,7 @@ impl core::Surface<B> for Surface {
         Swapchain {
             window: self.window.clone(),
             backbuffer: [backbuffer; 1],
+            dimensions,
         }
     }
 }
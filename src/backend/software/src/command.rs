@@ -0,0 +1,422 @@
+use core::{self, buffer, command, image, memory, pso, query, target};
+use std::ops::Range;
+use {native as n, Backend};
+
+/// Command recorded into a command buffer, executed on submission.
+#[derive(Clone, Debug)]
+pub enum Command {
+    FillBuffer {
+        buffer: n::Buffer,
+        range: Range<u64>,
+        data: u32,
+    },
+    UpdateBuffer {
+        buffer: n::Buffer,
+        offset: u64,
+        data: Vec<u8>,
+    },
+    ClearColor {
+        view: n::RenderTargetView,
+        color: command::ClearColor,
+    },
+    ClearDepthStencil {
+        view: n::DepthStencilView,
+        depth: Option<target::Depth>,
+    },
+    ResolveImage {
+        src: n::Image,
+        dst: n::Image,
+        regions: Vec<command::ImageResolve>,
+    },
+    BlitImage {
+        src: n::Image,
+        dst: n::Image,
+        regions: Vec<command::ImageBlit>,
+        filter: image::FilterMethod,
+    },
+    BindIndexBuffer {
+        buffer: n::Buffer,
+        offset: u64,
+        index_type: core::IndexType,
+    },
+    BindVertexBuffers(Vec<(n::Buffer, pso::BufferOffset)>),
+    SetViewports(Vec<core::Viewport>),
+    SetScissors(Vec<target::Rect>),
+    SetBlendConstants(target::ColorValue),
+    BeginRenderPass {
+        render_pass: n::RenderPass,
+        frame_buffer: n::FrameBuffer,
+        area: target::Rect,
+        clear_values: Vec<command::ClearValue>,
+    },
+    NextSubpass,
+    EndRenderPass,
+    BindGraphicsPipeline(n::GraphicsPipeline),
+    CopyBuffer {
+        src: n::Buffer,
+        dst: n::Buffer,
+        regions: Vec<command::BufferCopy>,
+    },
+    CopyImage {
+        src: n::Image,
+        dst: n::Image,
+        regions: Vec<command::ImageCopy>,
+    },
+    CopyBufferToImage {
+        src: n::Buffer,
+        dst: n::Image,
+        regions: Vec<command::BufferImageCopy>,
+    },
+    CopyImageToBuffer {
+        src: n::Image,
+        dst: n::Buffer,
+        regions: Vec<command::BufferImageCopy>,
+    },
+    Draw {
+        vertices: Range<core::VertexCount>,
+        instances: Range<core::InstanceCount>,
+    },
+    DrawIndexed {
+        indices: Range<core::IndexCount>,
+        base_vertex: core::VertexOffset,
+        instances: Range<core::InstanceCount>,
+    },
+    DrawIndirect {
+        buffer: n::Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    DrawIndexedIndirect {
+        buffer: n::Buffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    BeginQuery {
+        pool: n::QueryPool,
+        query: query::QueryId,
+    },
+    EndQuery {
+        pool: n::QueryPool,
+        query: query::QueryId,
+    },
+    ResetQueryPool {
+        pool: n::QueryPool,
+        queries: Range<query::QueryId>,
+    },
+    WriteTimestamp {
+        pool: n::QueryPool,
+        query: query::QueryId,
+    },
+    CopyQueryPoolResults {
+        pool: n::QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: n::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    },
+}
+
+/// Command buffer recording commands, which are executed by the
+/// `CommandQueue` on submission.
+#[derive(Clone, Debug)]
+pub struct RawCommandBuffer {
+    pub(crate) commands: Vec<Command>,
+}
+
+impl RawCommandBuffer {
+    pub(crate) fn new() -> Self {
+        RawCommandBuffer {
+            commands: Vec::new(),
+        }
+    }
+}
+
+impl core::RawCommandBuffer<Backend> for RawCommandBuffer {
+    fn begin(&mut self) {
+        self.commands.clear();
+    }
+
+    fn finish(&mut self) {
+        // no-op
+    }
+
+    fn reset(&mut self, _: bool) {
+        self.commands.clear();
+    }
+
+    fn pipeline_barrier(
+        &mut self,
+        _: Range<pso::PipelineStage>,
+        _: &[memory::Barrier<Backend>],
+    ) {
+        // Commands are executed in order, all memory accesses are coherent.
+    }
+
+    fn fill_buffer(&mut self, buffer: &n::Buffer, range: Range<u64>, data: u32) {
+        self.commands.push(Command::FillBuffer {
+            buffer: buffer.clone(),
+            range,
+            data,
+        });
+    }
+
+    fn update_buffer(&mut self, buffer: &n::Buffer, offset: u64, data: &[u8]) {
+        self.commands.push(Command::UpdateBuffer {
+            buffer: buffer.clone(),
+            offset,
+            data: data.to_vec(),
+        });
+    }
+
+    fn clear_color(&mut self, rtv: &n::RenderTargetView, _: image::ImageLayout, color: command::ClearColor) {
+        self.commands.push(Command::ClearColor {
+            view: rtv.clone(),
+            color,
+        });
+    }
+
+    fn clear_depth_stencil(
+        &mut self,
+        dsv: &n::DepthStencilView,
+        _: image::ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
+    ) {
+        if stencil.is_some() {
+            warn!("Stencil clears are not supported");
+        }
+        self.commands.push(Command::ClearDepthStencil {
+            view: dsv.clone(),
+            depth,
+        });
+    }
+
+    fn resolve_image(
+        &mut self,
+        src: &n::Image,
+        _: image::ImageLayout,
+        dst: &n::Image,
+        _: image::ImageLayout,
+        regions: &[command::ImageResolve],
+    ) {
+        self.commands.push(Command::ResolveImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+        });
+    }
+
+    fn blit_image(
+        &mut self,
+        src: &n::Image,
+        _: image::ImageLayout,
+        dst: &n::Image,
+        _: image::ImageLayout,
+        regions: &[command::ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        self.commands.push(Command::BlitImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+            filter,
+        });
+    }
+
+    fn bind_index_buffer(&mut self, ibv: buffer::IndexBufferView<Backend>) {
+        self.commands.push(Command::BindIndexBuffer {
+            buffer: ibv.buffer.clone(),
+            offset: ibv.offset,
+            index_type: ibv.index_type,
+        });
+    }
+
+    fn bind_vertex_buffers(&mut self, vbs: pso::VertexBufferSet<Backend>) {
+        self.commands.push(Command::BindVertexBuffers(
+            vbs.0.iter().map(|&(buffer, offset)| (buffer.clone(), offset)).collect()
+        ));
+    }
+
+    fn set_viewports(&mut self, viewports: &[core::Viewport]) {
+        self.commands.push(Command::SetViewports(viewports.to_vec()));
+    }
+
+    fn set_scissors(&mut self, scissors: &[target::Rect]) {
+        self.commands.push(Command::SetScissors(scissors.to_vec()));
+    }
+
+    fn set_stencil_reference(&mut self, _: target::Stencil, _: target::Stencil) {
+        warn!("Stencil test is not supported");
+    }
+
+    fn set_blend_constants(&mut self, color: target::ColorValue) {
+        self.commands.push(Command::SetBlendConstants(color));
+    }
+
+    fn begin_renderpass(
+        &mut self,
+        render_pass: &n::RenderPass,
+        frame_buffer: &n::FrameBuffer,
+        area: target::Rect,
+        clear_values: &[command::ClearValue],
+        _: command::SubpassContents,
+    ) {
+        self.commands.push(Command::BeginRenderPass {
+            render_pass: render_pass.clone(),
+            frame_buffer: frame_buffer.clone(),
+            area,
+            clear_values: clear_values.to_vec(),
+        });
+    }
+
+    fn next_subpass(&mut self, _: command::SubpassContents) {
+        self.commands.push(Command::NextSubpass);
+    }
+
+    fn end_renderpass(&mut self) {
+        self.commands.push(Command::EndRenderPass);
+    }
+
+    fn bind_graphics_pipeline(&mut self, pipeline: &n::GraphicsPipeline) {
+        self.commands.push(Command::BindGraphicsPipeline(pipeline.clone()));
+    }
+
+    fn bind_graphics_descriptor_sets(
+        &mut self,
+        _: &n::PipelineLayout,
+        _: usize,
+        _: &[&n::DescriptorSet],
+    ) {
+        // Shader closures capture their resources directly.
+    }
+
+    fn bind_compute_pipeline(&mut self, _: &n::ComputePipeline) {
+        warn!("Compute is not supported");
+    }
+
+    fn dispatch(&mut self, _: u32, _: u32, _: u32) {
+        warn!("Compute is not supported");
+    }
+
+    fn dispatch_indirect(&mut self, _: &n::Buffer, _: u64) {
+        warn!("Compute is not supported");
+    }
+
+    fn copy_buffer(&mut self, src: &n::Buffer, dst: &n::Buffer, regions: &[command::BufferCopy]) {
+        self.commands.push(Command::CopyBuffer {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+        });
+    }
+
+    fn copy_image(
+        &mut self,
+        src: &n::Image,
+        _: image::ImageLayout,
+        dst: &n::Image,
+        _: image::ImageLayout,
+        regions: &[command::ImageCopy],
+    ) {
+        self.commands.push(Command::CopyImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+        });
+    }
+
+    fn copy_buffer_to_image(
+        &mut self,
+        src: &n::Buffer,
+        dst: &n::Image,
+        _: image::ImageLayout,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.commands.push(Command::CopyBufferToImage {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+        });
+    }
+
+    fn copy_image_to_buffer(
+        &mut self,
+        src: &n::Image,
+        _: image::ImageLayout,
+        dst: &n::Buffer,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.commands.push(Command::CopyImageToBuffer {
+            src: src.clone(),
+            dst: dst.clone(),
+            regions: regions.to_vec(),
+        });
+    }
+
+    fn draw(&mut self,
+        vertices: Range<core::VertexCount>,
+        instances: Range<core::InstanceCount>,
+    ) {
+        self.commands.push(Command::Draw { vertices, instances });
+    }
+
+    fn draw_indexed(
+        &mut self,
+        indices: Range<core::IndexCount>,
+        base_vertex: core::VertexOffset,
+        instances: Range<core::InstanceCount>,
+    ) {
+        self.commands.push(Command::DrawIndexed { indices, base_vertex, instances });
+    }
+
+    fn draw_indirect(&mut self, buffer: &n::Buffer, offset: u64, draw_count: u32, stride: u32) {
+        self.commands.push(Command::DrawIndirect { buffer: buffer.clone(), offset, draw_count, stride });
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: &n::Buffer, offset: u64, draw_count: u32, stride: u32) {
+        self.commands.push(Command::DrawIndexedIndirect { buffer: buffer.clone(), offset, draw_count, stride });
+    }
+
+    fn begin_query(&mut self, query: query::Query<Backend>, _: query::QueryControl) {
+        // Occlusion queries are always precise.
+        self.commands.push(Command::BeginQuery { pool: query.pool.clone(), query: query.id });
+    }
+
+    fn end_query(&mut self, query: query::Query<Backend>) {
+        self.commands.push(Command::EndQuery { pool: query.pool.clone(), query: query.id });
+    }
+
+    fn reset_query_pool(&mut self, pool: &n::QueryPool, queries: Range<query::QueryId>) {
+        self.commands.push(Command::ResetQueryPool { pool: pool.clone(), queries });
+    }
+
+    fn write_timestamp(&mut self, _: pso::PipelineStage, query: query::Query<Backend>) {
+        // Commands are executed in order, all previous ones have completed every stage.
+        self.commands.push(Command::WriteTimestamp { pool: query.pool.clone(), query: query.id });
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &n::QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: &n::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    ) {
+        self.commands.push(Command::CopyQueryPoolResults {
+            pool: pool.clone(),
+            queries,
+            buffer: buffer.clone(),
+            offset,
+            stride,
+            flags,
+        });
+    }
+}
+
+/// Subpass command buffer, secondary command buffers are not supported.
+pub struct SubpassCommandBuffer;
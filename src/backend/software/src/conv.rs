@@ -0,0 +1,264 @@
+//! Conversion between texels stored in memory and their `[f32; 4]` representation.
+
+use core::format::{ChannelType, Format, SurfaceType};
+
+/// Memory layout of the components of a texel.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Layout {
+    /// `count` components of `bits` each, stored in RGBA order.
+    Plain { count: usize, bits: usize },
+    /// Four 8-bit components, stored in BGRA order.
+    Bgra8,
+    /// 16-bit unsigned normalized depth.
+    D16,
+    /// 32-bit float depth, possibly followed by unused or stencil bits.
+    D32,
+    /// 24-bit unsigned normalized depth in the low bits, stencil in the high bits.
+    D24S8,
+}
+
+fn layout(surface: SurfaceType) -> Option<Layout> {
+    use self::SurfaceType::*;
+    Some(match surface {
+        R8 => Layout::Plain { count: 1, bits: 8 },
+        R8_G8 => Layout::Plain { count: 2, bits: 8 },
+        R8_G8_B8_A8 => Layout::Plain { count: 4, bits: 8 },
+        R16 => Layout::Plain { count: 1, bits: 16 },
+        R16_G16 => Layout::Plain { count: 2, bits: 16 },
+        R16_G16_B16 => Layout::Plain { count: 3, bits: 16 },
+        R16_G16_B16_A16 => Layout::Plain { count: 4, bits: 16 },
+        R32 => Layout::Plain { count: 1, bits: 32 },
+        R32_G32 => Layout::Plain { count: 2, bits: 32 },
+        R32_G32_B32 => Layout::Plain { count: 3, bits: 32 },
+        R32_G32_B32_A32 => Layout::Plain { count: 4, bits: 32 },
+        B8_G8_R8_A8 => Layout::Bgra8,
+        D16 => Layout::D16,
+        D24 | D32 | D32_S8 => Layout::D32,
+        D24_S8 => Layout::D24S8,
+        // Packed and block-compressed formats.
+        _ => return None,
+    })
+}
+
+/// Check if texels of the format can be read and written by the backend.
+pub fn is_supported(format: Format) -> bool {
+    layout(format.0).is_some()
+}
+
+/// Check if the format holds depth values.
+pub fn is_depth(format: Format) -> bool {
+    match layout(format.0) {
+        Some(Layout::D16) | Some(Layout::D32) | Some(Layout::D24S8) => true,
+        _ => false,
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1F) as i32;
+    let mantissa = (half & 0x3FF) as f32;
+    sign * match exponent {
+        0 => mantissa * (2.0f32).powi(-24),
+        0x1F if mantissa == 0.0 => ::std::f32::INFINITY,
+        0x1F => ::std::f32::NAN,
+        _ => (1.0 + mantissa / 1024.0) * (2.0f32).powi(exponent - 15),
+    }
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32 - 127 + 15;
+    let mantissa = bits & 0x7F_FFFF;
+    if value.is_nan() {
+        sign | 0x7E00
+    } else if exponent >= 0x1F {
+        sign | 0x7C00
+    } else if exponent <= 0 {
+        // Denormals are flushed to zero.
+        sign
+    } else {
+        sign | (exponent as u16) << 10 | (mantissa >> 13) as u16
+    }
+}
+
+fn read_bits(data: &[u8], bits: usize, index: usize) -> u32 {
+    let bytes = bits / 8;
+    (0 .. bytes).fold(0, |value, i| value | (data[index * bytes + i] as u32) << (8 * i))
+}
+
+fn write_bits(data: &mut [u8], bits: usize, index: usize, value: u32) {
+    let bytes = bits / 8;
+    for i in 0 .. bytes {
+        data[index * bytes + i] = (value >> (8 * i)) as u8;
+    }
+}
+
+fn decode(raw: u32, bits: usize, channel: ChannelType) -> f32 {
+    let max_unsigned = if bits == 32 { u32::max_value() } else { (1 << bits) - 1 };
+    // Sign extension of the raw value.
+    let shift = 32 - bits;
+    let signed = ((raw << shift) as i32) >> shift;
+    match channel {
+        ChannelType::Unorm => raw as f32 / max_unsigned as f32,
+        ChannelType::Srgb => srgb_to_linear(raw as f32 / max_unsigned as f32),
+        ChannelType::Inorm => (signed as f32 / (max_unsigned >> 1) as f32).max(-1.0),
+        ChannelType::Uint => raw as f32,
+        ChannelType::Int => signed as f32,
+        ChannelType::Float if bits == 16 => f16_to_f32(raw as u16),
+        ChannelType::Float => f32::from_bits(raw),
+    }
+}
+
+fn encode(value: f32, bits: usize, channel: ChannelType) -> u32 {
+    let max_unsigned = if bits == 32 { u32::max_value() } else { (1 << bits) - 1 };
+    let mask = max_unsigned;
+    match channel {
+        ChannelType::Unorm => (value.max(0.0).min(1.0) * max_unsigned as f32).round() as u32,
+        ChannelType::Srgb => (linear_to_srgb(value.max(0.0).min(1.0)) * max_unsigned as f32).round() as u32,
+        ChannelType::Inorm => {
+            let max_signed = (max_unsigned >> 1) as f32;
+            ((value.max(-1.0).min(1.0) * max_signed).round() as i32) as u32 & mask
+        }
+        ChannelType::Uint => (value.max(0.0) as u64).min(max_unsigned as u64) as u32,
+        ChannelType::Int => {
+            let max_signed = (max_unsigned >> 1) as i64;
+            ((value as i64).max(-max_signed - 1).min(max_signed) as i32) as u32 & mask
+        }
+        ChannelType::Float if bits == 16 => f32_to_f16(value) as u32,
+        ChannelType::Float => value.to_bits(),
+    }
+}
+
+/// Decode a texel into its components.
+///
+/// Missing color components are filled with `(0, 0, 0, 1)`.
+/// Depth formats return the depth in the first component.
+pub fn read_texel(format: Format, data: &[u8]) -> [f32; 4] {
+    let mut texel = [0.0, 0.0, 0.0, 1.0];
+    match layout(format.0).expect("Unsupported texel format") {
+        Layout::Plain { count, bits } => {
+            for i in 0 .. count {
+                let raw = read_bits(data, bits, i);
+                // Alpha is never sRGB encoded.
+                let channel = match format.1 {
+                    ChannelType::Srgb if i == 3 => ChannelType::Unorm,
+                    other => other,
+                };
+                texel[i] = decode(raw, bits, channel);
+            }
+        }
+        Layout::Bgra8 => {
+            for (i, &component) in [2, 1, 0, 3].iter().enumerate() {
+                let channel = match format.1 {
+                    ChannelType::Srgb if component == 3 => ChannelType::Unorm,
+                    other => other,
+                };
+                texel[component] = decode(data[i] as u32, 8, channel);
+            }
+        }
+        Layout::D16 => texel[0] = decode(read_bits(data, 16, 0), 16, ChannelType::Unorm),
+        Layout::D32 => texel[0] = decode(read_bits(data, 32, 0), 32, ChannelType::Float),
+        Layout::D24S8 => texel[0] = (read_bits(data, 32, 0) & 0xFF_FFFF) as f32 / 0xFF_FFFF as f32,
+    }
+    texel
+}
+
+/// Encode the components of a texel.
+///
+/// Depth formats take the depth from the first component,
+/// stencil bits are preserved.
+pub fn write_texel(format: Format, texel: [f32; 4], data: &mut [u8]) {
+    match layout(format.0).expect("Unsupported texel format") {
+        Layout::Plain { count, bits } => {
+            for i in 0 .. count {
+                let channel = match format.1 {
+                    ChannelType::Srgb if i == 3 => ChannelType::Unorm,
+                    other => other,
+                };
+                write_bits(data, bits, i, encode(texel[i], bits, channel));
+            }
+        }
+        Layout::Bgra8 => {
+            for (i, &component) in [2, 1, 0, 3].iter().enumerate() {
+                let channel = match format.1 {
+                    ChannelType::Srgb if component == 3 => ChannelType::Unorm,
+                    other => other,
+                };
+                data[i] = encode(texel[component], 8, channel) as u8;
+            }
+        }
+        Layout::D16 => write_bits(data, 16, 0, encode(texel[0], 16, ChannelType::Unorm)),
+        Layout::D32 => write_bits(data, 32, 0, encode(texel[0], 32, ChannelType::Float)),
+        Layout::D24S8 => {
+            let depth = (texel[0].max(0.0).min(1.0) * 0xFF_FFFF as f32).round() as u32;
+            let stencil = read_bits(data, 32, 0) & 0xFF00_0000;
+            write_bits(data, 32, 0, stencil | depth);
+        }
+    }
+}
+
+/// Encode an integer texel, used for clearing integer formats losslessly.
+pub fn write_texel_int(format: Format, texel: [u32; 4], data: &mut [u8]) {
+    match layout(format.0).expect("Unsupported texel format") {
+        Layout::Plain { count, bits } => {
+            let mask = if bits == 32 { u32::max_value() } else { (1 << bits) - 1 };
+            for i in 0 .. count {
+                write_bits(data, bits, i, texel[i] & mask);
+            }
+        }
+        Layout::Bgra8 => {
+            for (i, &component) in [2, 1, 0, 3].iter().enumerate() {
+                data[i] = texel[component] as u8;
+            }
+        }
+        _ => panic!("Integer write to a depth format {:?}", format),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::format::{ChannelType, Format, SurfaceType};
+    use super::*;
+
+    #[test]
+    fn roundtrip_unorm() {
+        let format = Format(SurfaceType::B8_G8_R8_A8, ChannelType::Unorm);
+        let mut data = [0; 4];
+        write_texel(format, [1.0, 0.5, 0.0, 1.0], &mut data);
+        assert_eq!(data, [0, 128, 255, 255]);
+        assert_eq!(read_texel(format, &data), [1.0, 128.0 / 255.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn roundtrip_half() {
+        let format = Format(SurfaceType::R16, ChannelType::Float);
+        let mut data = [0; 2];
+        write_texel(format, [-2.5, 0.0, 0.0, 0.0], &mut data);
+        assert_eq!(read_texel(format, &data), [-2.5, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn keep_stencil() {
+        let format = Format(SurfaceType::D24_S8, ChannelType::Unorm);
+        let mut data = [0, 0, 0, 7];
+        write_texel(format, [1.0, 0.0, 0.0, 0.0], &mut data);
+        assert_eq!(data, [0xFF, 0xFF, 0xFF, 7]);
+    }
+}
@@ -0,0 +1,483 @@
+use core::{self, buffer, device as d, format, image, mapping, memory, pass, pso, query, Primitive};
+use core::state as s;
+use std::{mem, slice};
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::AtomicBool;
+
+use {conv, native as n, pool, Backend, Device};
+
+/// Alignment of buffers placed into a heap.
+const BUFFER_ALIGNMENT: u64 = 16;
+/// Alignment of images placed into a heap.
+const IMAGE_ALIGNMENT: u64 = 256;
+
+impl Device {
+    /// Create a depth-stencil view of an image.
+    ///
+    /// The core `Device` interface doesn't expose depth-stencil view creation yet.
+    pub fn view_image_as_depth_stencil(
+        &mut self,
+        image: &n::Image,
+        format: format::Format,
+        range: image::SubresourceRange,
+    ) -> Result<n::DepthStencilView, d::TargetViewError> {
+        if !image.usage.contains(image::DEPTH_STENCIL_ATTACHMENT) {
+            return Err(d::TargetViewError::NoBindFlag);
+        }
+        if !conv::is_depth(format) {
+            return Err(d::TargetViewError::Unsupported);
+        }
+        let (level, layers) = check_subresource(image, format, range)?;
+        Ok(n::DepthStencilView {
+            image: image.clone(),
+            format,
+            level,
+            layers,
+        })
+    }
+
+    fn map_buffer<T>(&self, buffer: &n::Buffer, offset: u64, size: u64) -> Result<(*mut T, n::Mapping), mapping::Error> {
+        if !buffer.cpu_visible || offset + size > buffer.size {
+            return Err(mapping::Error::InvalidAccess);
+        }
+
+        let ptr = unsafe {
+            buffer.memory.as_ptr().offset((buffer.offset + offset) as isize)
+        };
+        if ptr as usize % mem::align_of::<T>() != 0 {
+            return Err(mapping::Error::InvalidAccess);
+        }
+
+        Ok((ptr as *mut T, n::Mapping { _memory: buffer.memory.clone() }))
+    }
+}
+
+// Validate a subresource range used for a view, returning the viewed level and layers.
+fn check_subresource(
+    image: &n::Image,
+    format: format::Format,
+    (levels, layers): image::SubresourceRange,
+) -> Result<(image::Level, Range<image::Layer>), d::TargetViewError> {
+    if format.0.get_total_bits() != image.format.0.get_total_bits() {
+        return Err(d::TargetViewError::Channel(format.1));
+    }
+    if levels.start >= image.levels || levels.end - levels.start != 1 {
+        return Err(d::TargetViewError::Level(levels.start));
+    }
+    let num_layers = image.num_layers() as image::Layer;
+    if layers.end > num_layers || layers.start >= layers.end {
+        return Err(d::TargetViewError::Layer(image::LayerError::OutOfBounds(layers.end, num_layers)));
+    }
+    Ok((levels.start, layers))
+}
+
+impl core::Device<Backend> for Device {
+    fn get_features(&self) -> &core::Features {
+        &self.features
+    }
+
+    fn get_limits(&self) -> &core::Limits {
+        &self.limits
+    }
+
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties {
+        if !conv::is_supported(format) {
+            return format::FormatProperties::unsupported();
+        }
+        // Images are always stored linearly, and can't be sampled without descriptor sets.
+        let (image_features, buffer_features) = if conv::is_depth(format) {
+            (format::DEPTH_STENCIL_ATTACHMENT | format::BLIT_SRC | format::BLIT_DST,
+             format::BufferFeature::empty())
+        } else {
+            (format::COLOR_ATTACHMENT | format::COLOR_ATTACHMENT_BLEND | format::BLIT_SRC | format::BLIT_DST,
+             format::VERTEX)
+        };
+        format::FormatProperties {
+            linear_tiling: image_features,
+            optimal_tiling: image_features,
+            buffer_features,
+        }
+    }
+
+    fn create_heap(&mut self, heap_type: &core::HeapType, _: d::ResourceHeapType, size: u64) -> Result<n::Heap, d::ResourceHeapError> {
+        if heap_type.id >= self.heap_types.len() {
+            return Err(d::ResourceHeapError::UnsupportedType);
+        }
+
+        Ok(n::Heap {
+            size,
+            cpu_visible: heap_type.properties.contains(memory::CPU_VISIBLE),
+            memory: Arc::new(n::HostMemory::new(size)),
+        })
+    }
+
+    fn create_shader_library(&mut self, _: &[(pso::EntryPoint, &[u8])]) -> Result<n::ShaderLib, pso::CreateShaderError> {
+        // Shaders are Rust closures, registered with `ShaderLib::new`.
+        Err(pso::CreateShaderError::ModelNotSupported)
+    }
+
+    fn create_renderpass(
+        &mut self,
+        attachments: &[pass::Attachment],
+        subpasses: &[pass::SubpassDesc],
+        _dependencies: &[pass::SubpassDependency],
+    ) -> n::RenderPass {
+        // Dependencies are implicitly satisfied, subpasses are executed in order.
+        n::RenderPass {
+            attachments: attachments.to_vec(),
+            subpasses: subpasses
+                .iter()
+                .map(|subpass| subpass.color_attachments.to_vec())
+                .collect(),
+        }
+    }
+
+    fn create_pipeline_layout(&mut self, _: &[&n::DescriptorSetLayout]) -> n::PipelineLayout {
+        n::PipelineLayout
+    }
+
+    fn create_pipeline_cache(&mut self, _: &[u8]) -> n::PipelineCache {
+        n::PipelineCache
+    }
+
+    fn get_pipeline_cache_data(&mut self, _: &n::PipelineCache) -> Vec<u8> {
+        Vec::new()
+    }
+
+    fn merge_pipeline_caches(&mut self, _: &n::PipelineCache, _: &[&n::PipelineCache]) {
+    }
+
+    fn create_graphics_pipelines<'a>(
+        &mut self,
+        descs: &[(&n::ShaderLib, &n::PipelineLayout, pass::Subpass<'a, Backend>, &pso::GraphicsPipelineDesc)],
+        _: Option<&n::PipelineCache>,
+    ) -> Vec<Result<n::GraphicsPipeline, pso::CreationError>> {
+        descs
+            .iter()
+            .map(|&(shader_lib, _, ref subpass, desc)| {
+                if subpass.index >= subpass.main_pass.subpasses.len() {
+                    return Err(pso::CreationError::InvalidSubpass(subpass.index));
+                }
+
+                match desc.input_assembler.primitive {
+                    Primitive::TriangleList | Primitive::TriangleStrip => (),
+                    other => {
+                        error!("Unsupported primitive {:?}", other);
+                        return Err(pso::CreationError::Other);
+                    }
+                }
+                if desc.rasterizer.polgyon_mode != s::RasterMethod::Fill {
+                    error!("Unsupported polygon mode {:?}", desc.rasterizer.polgyon_mode);
+                    return Err(pso::CreationError::Other);
+                }
+
+                let entries = &desc.shader_entries;
+                let vertex = match shader_lib.vertex.get(entries.vertex_shader) {
+                    Some(shader) => shader.clone(),
+                    None => {
+                        error!("Vertex shader {} not found", entries.vertex_shader);
+                        return Err(pso::CreationError::Other);
+                    }
+                };
+                let pixel = match entries.pixel_shader {
+                    Some(entry) => match shader_lib.pixel.get(entry) {
+                        Some(shader) => Some(shader.clone()),
+                        None => {
+                            error!("Pixel shader {} not found", entry);
+                            return Err(pso::CreationError::Other);
+                        }
+                    },
+                    None => None,
+                };
+                if entries.hull_shader.is_some() || entries.domain_shader.is_some() || entries.geometry_shader.is_some() {
+                    error!("Only vertex and pixel shaders are supported");
+                    return Err(pso::CreationError::Other);
+                }
+
+                Ok(n::GraphicsPipeline {
+                    vertex,
+                    pixel,
+                    primitive: desc.input_assembler.primitive,
+                    rasterizer: desc.rasterizer.clone(),
+                    vertex_buffers: desc.vertex_buffers.clone(),
+                    attributes: desc.attributes.clone(),
+                    targets: desc.blender.targets.clone(),
+                    depth: desc.depth_stencil.and_then(|(_, info)| info.depth),
+                })
+            })
+            .collect()
+    }
+
+    fn create_compute_pipelines(
+        &mut self,
+        descs: &[(&n::ShaderLib, pso::EntryPoint, &n::PipelineLayout)],
+        _: Option<&n::PipelineCache>,
+    ) -> Vec<Result<n::ComputePipeline, pso::CreationError>> {
+        error!("Compute pipelines are not supported");
+        descs
+            .iter()
+            .map(|_| Err(pso::CreationError::Other))
+            .collect()
+    }
+
+    fn create_framebuffer(
+        &mut self,
+        _: &n::RenderPass,
+        color_attachments: &[&n::RenderTargetView],
+        depth_stencil_attachments: &[&n::DepthStencilView],
+        extent: d::Extent,
+    ) -> n::FrameBuffer {
+        n::FrameBuffer {
+            colors: color_attachments.iter().map(|&view| view.clone()).collect(),
+            depth_stencil: depth_stencil_attachments.first().map(|&view| view.clone()),
+            width: extent.width,
+            height: extent.height,
+        }
+    }
+
+    fn create_sampler(&mut self, _: image::SamplerInfo) -> n::Sampler {
+        n::Sampler
+    }
+
+    fn create_buffer(&mut self, size: u64, _: u64, _: buffer::Usage) -> Result<n::UnboundBuffer, buffer::CreationError> {
+        Ok(n::UnboundBuffer { size })
+    }
+
+    fn get_buffer_requirements(&mut self, buffer: &n::UnboundBuffer) -> memory::Requirements {
+        memory::Requirements {
+            size: buffer.size,
+            alignment: BUFFER_ALIGNMENT,
+        }
+    }
+
+    fn bind_buffer_memory(&mut self, heap: &n::Heap, offset: u64, buffer: n::UnboundBuffer) -> Result<n::Buffer, buffer::CreationError> {
+        if offset % BUFFER_ALIGNMENT != 0 || offset + buffer.size > heap.size {
+            return Err(buffer::CreationError::Other);
+        }
+
+        Ok(n::Buffer {
+            memory: heap.memory.clone(),
+            offset,
+            size: buffer.size,
+            cpu_visible: heap.cpu_visible,
+        })
+    }
+
+    fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format, usage: image::Usage)
+         -> Result<n::UnboundImage, image::CreationError>
+    {
+        if !conv::is_supported(format) {
+            return Err(image::CreationError::Format(format.0, Some(format.1)));
+        }
+        match kind {
+            image::Kind::D2(_, _, aa) |
+            image::Kind::D2Array(_, _, _, aa) if aa != image::AaMode::Single => {
+                return Err(image::CreationError::Samples(aa));
+            }
+            _ => (),
+        }
+        let (w, h, d, _) = kind.get_dimensions();
+        let max_size = self.limits.max_texture_size as image::Size;
+        for &size in &[w, h, d] {
+            if size > max_size {
+                return Err(image::CreationError::Size(size));
+            }
+        }
+
+        Ok(n::UnboundImage { kind, levels, format, usage })
+    }
+
+    fn get_image_requirements(&mut self, image: &n::UnboundImage) -> memory::Requirements {
+        memory::Requirements {
+            size: image.size(),
+            alignment: IMAGE_ALIGNMENT,
+        }
+    }
+
+    fn bind_image_memory(&mut self, heap: &n::Heap, offset: u64, image: n::UnboundImage) -> Result<n::Image, image::CreationError> {
+        let size = image.size();
+        if offset % IMAGE_ALIGNMENT != 0 || offset + size > heap.size {
+            return Err(image::CreationError::Data(size as usize));
+        }
+
+        Ok(n::Image {
+            memory: heap.memory.clone(),
+            offset,
+            kind: image.kind,
+            levels: image.levels,
+            format: image.format,
+            usage: image.usage,
+        })
+    }
+
+    fn view_buffer_as_constant(&mut self, _: &n::Buffer, _: Range<u64>) -> Result<n::ConstantBufferView, d::TargetViewError> {
+        Ok(n::ConstantBufferView)
+    }
+
+    fn view_image_as_render_target(&mut self, image: &n::Image, format: format::Format, range: image::SubresourceRange) -> Result<n::RenderTargetView, d::TargetViewError> {
+        if !image.usage.contains(image::COLOR_ATTACHMENT) {
+            return Err(d::TargetViewError::NoBindFlag);
+        }
+        if !conv::is_supported(format) || conv::is_depth(format) {
+            return Err(d::TargetViewError::Unsupported);
+        }
+        let (level, layers) = check_subresource(image, format, range)?;
+        Ok(n::RenderTargetView {
+            image: image.clone(),
+            format,
+            level,
+            layers,
+        })
+    }
+
+    fn view_image_as_shader_resource(&mut self, _: &n::Image, _: format::Format) -> Result<n::ShaderResourceView, d::TargetViewError> {
+        Ok(n::ShaderResourceView)
+    }
+
+    fn view_image_as_unordered_access(&mut self, _: &n::Image, _: format::Format) -> Result<n::UnorderedAccessView, d::TargetViewError> {
+        Ok(n::UnorderedAccessView)
+    }
+
+    fn create_descriptor_pool(&mut self, _: usize, _: &[pso::DescriptorRangeDesc]) -> pool::DescriptorPool {
+        pool::DescriptorPool
+    }
+
+    fn create_descriptor_set_layout(&mut self, _: &[pso::DescriptorSetLayoutBinding]) -> n::DescriptorSetLayout {
+        n::DescriptorSetLayout
+    }
+
+    fn update_descriptor_sets(&mut self, _: &[pso::DescriptorSetWrite<Backend>]) {
+        // Shader closures capture their resources directly.
+    }
+
+    fn read_mapping<'a, T>(&self, buffer: &'a n::Buffer, offset: u64, size: u64)
+                           -> Result<mapping::Reader<'a, Backend, T>, mapping::Error>
+        where T: Copy
+    {
+        self.map_buffer::<T>(buffer, offset, size)
+            .map(|(ptr, mapping)| unsafe {
+                let slice = slice::from_raw_parts(ptr as *const T, size as usize / mem::size_of::<T>());
+                mapping::Reader::new(slice, mapping)
+            })
+    }
+
+    fn write_mapping<'a, 'b, T>(&mut self, buffer: &'a n::Buffer, offset: u64, size: u64)
+                                -> Result<mapping::Writer<'a, Backend, T>, mapping::Error>
+        where T: Copy
+    {
+        self.map_buffer::<T>(buffer, offset, size)
+            .map(|(ptr, mapping)| unsafe {
+                let slice = slice::from_raw_parts_mut(ptr, size as usize / mem::size_of::<T>());
+                mapping::Writer::new(slice, mapping)
+            })
+    }
+
+    fn create_semaphore(&mut self) -> n::Semaphore {
+        n::Semaphore
+    }
+
+    fn create_fence(&mut self, signaled: bool) -> n::Fence {
+        n::Fence(Arc::new(AtomicBool::new(signaled)))
+    }
+
+    fn reset_fences(&mut self, fences: &[&n::Fence]) {
+        for fence in fences {
+            fence.set(false);
+        }
+    }
+
+    fn wait_for_fences(&mut self, fences: &[&n::Fence], wait: d::WaitFor, _: u32) -> bool {
+        // Submissions are executed on the calling thread, waiting wouldn't change the fence states.
+        match wait {
+            d::WaitFor::All => fences.iter().all(|fence| fence.is_signaled()),
+            d::WaitFor::Any => fences.iter().any(|fence| fence.is_signaled()),
+        }
+    }
+
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> n::QueryPool {
+        n::QueryPool {
+            ty,
+            results: Arc::new(Mutex::new(vec![None; count as usize])),
+        }
+    }
+
+    fn get_query_pool_results(&mut self, pool: &n::QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        // Submissions have completed once they return, waiting wouldn't change the results.
+        let (results, all_available) = pool.read(queries, flags);
+        let mut offset = 0;
+        for values in results {
+            for value in values {
+                if let Some(value) = value {
+                    data[offset] = value;
+                }
+                offset += 1;
+            }
+        }
+        all_available
+    }
+
+    fn destroy_heap(&mut self, _: n::Heap) {
+    }
+
+    fn destroy_shader_lib(&mut self, _: n::ShaderLib) {
+    }
+
+    fn destroy_renderpass(&mut self, _: n::RenderPass) {
+    }
+
+    fn destroy_pipeline_layout(&mut self, _: n::PipelineLayout) {
+    }
+
+    fn destroy_graphics_pipeline(&mut self, _: n::GraphicsPipeline) {
+    }
+
+    fn destroy_compute_pipeline(&mut self, _: n::ComputePipeline) {
+    }
+
+    fn destroy_pipeline_cache(&mut self, _: n::PipelineCache) {
+    }
+
+    fn destroy_framebuffer(&mut self, _: n::FrameBuffer) {
+    }
+
+    fn destroy_buffer(&mut self, _: n::Buffer) {
+    }
+
+    fn destroy_image(&mut self, _: n::Image) {
+    }
+
+    fn destroy_render_target_view(&mut self, _: n::RenderTargetView) {
+    }
+
+    fn destroy_depth_stencil_view(&mut self, _: n::DepthStencilView) {
+    }
+
+    fn destroy_constant_buffer_view(&mut self, _: n::ConstantBufferView) {
+    }
+
+    fn destroy_shader_resource_view(&mut self, _: n::ShaderResourceView) {
+    }
+
+    fn destroy_unordered_access_view(&mut self, _: n::UnorderedAccessView) {
+    }
+
+    fn destroy_sampler(&mut self, _: n::Sampler) {
+    }
+
+    fn destroy_descriptor_pool(&mut self, _: pool::DescriptorPool) {
+    }
+
+    fn destroy_descriptor_set_layout(&mut self, _: n::DescriptorSetLayout) {
+    }
+
+    fn destroy_fence(&mut self, _: n::Fence) {
+    }
+
+    fn destroy_semaphore(&mut self, _: n::Semaphore) {
+    }
+
+    fn destroy_query_pool(&mut self, _: n::QueryPool) {
+    }
+}
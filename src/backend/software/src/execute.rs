@@ -0,0 +1,550 @@
+//! Execution of recorded commands on the CPU.
+
+use core::{command, image, pass, query, target, IndexType, Viewport};
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Instant;
+use command::Command;
+use {conv, native as n, raster};
+
+/// Color written into attachments with `DontCare` load or store operations,
+/// making their undefined contents easy to spot.
+pub const POISON_COLOR: [f32; 4] = [1.0, 0.0, 1.0, 1.0];
+
+/// Render pass instance being executed.
+pub struct RenderPassState {
+    pub render_pass: n::RenderPass,
+    pub frame_buffer: n::FrameBuffer,
+    pub area: target::Rect,
+    pub subpass: usize,
+}
+
+impl RenderPassState {
+    /// Color attachments of the current subpass.
+    pub fn color_attachments(&self) -> Vec<&n::RenderTargetView> {
+        self.render_pass.subpasses[self.subpass]
+            .iter()
+            .map(|&(index, _)| &self.frame_buffer.colors[index])
+            .collect()
+    }
+}
+
+/// State of a command buffer during execution.
+///
+/// Like on real devices, state isn't inherited between command buffers.
+pub struct State {
+    pub index_buffer: Option<(n::Buffer, u64, IndexType)>,
+    pub vertex_buffers: Vec<(n::Buffer, usize)>,
+    pub viewports: Vec<Viewport>,
+    pub scissors: Vec<target::Rect>,
+    pub blend_constants: target::ColorValue,
+    pub pipeline: Option<n::GraphicsPipeline>,
+    pub pass: Option<RenderPassState>,
+    /// Queries which have begun, with the work counted so far.
+    pub queries: Vec<(n::QueryPool, query::QueryId, raster::Statistics)>,
+}
+
+impl State {
+    fn new() -> Self {
+        State {
+            index_buffer: None,
+            vertex_buffers: Vec::new(),
+            viewports: Vec::new(),
+            scissors: Vec::new(),
+            blend_constants: [0.0; 4],
+            pipeline: None,
+            pass: None,
+            queries: Vec::new(),
+        }
+    }
+}
+
+/// Execute the commands recorded into a command buffer.
+///
+/// Timestamps count the nanoseconds passed since `origin`.
+pub fn execute(commands: &[Command], origin: Instant) {
+    let mut state = State::new();
+
+    for command in commands {
+        match *command {
+            Command::FillBuffer { ref buffer, ref range, data } => {
+                let bytes = (range.start .. range.end)
+                    .map(|i| (data >> (8 * (i % 4))) as u8)
+                    .collect::<Vec<_>>();
+                buffer.write(range.start, &bytes);
+            }
+            Command::UpdateBuffer { ref buffer, offset, ref data } => {
+                buffer.write(offset, data);
+            }
+            Command::ClearColor { ref view, color } => {
+                let (width, height, _) = view.image.extent(view.level);
+                let rect = (0, 0, width, height);
+                clear_color(&view.image, view.format, view.level, view.layers.clone(), rect, color);
+            }
+            Command::ClearDepthStencil { ref view, depth } => {
+                if let Some(depth) = depth {
+                    let (width, height, _) = view.image.extent(view.level);
+                    let rect = (0, 0, width, height);
+                    clear_depth(&view.image, view.format, view.level, view.layers.clone(), rect, depth);
+                }
+            }
+            Command::ResolveImage { ref src, ref dst, ref regions } => {
+                // Only single-sampled images are supported, resolving is a plain copy.
+                for region in regions {
+                    let (width, height, depth) = src.extent(region.src_subresource.0);
+                    for layer in 0 .. region.num_layers {
+                        copy_image_region(
+                            src, (region.src_subresource.0, region.src_subresource.1 + layer), (0, 0, 0),
+                            dst, (region.dst_subresource.0, region.dst_subresource.1 + layer), (0, 0, 0),
+                            (width, height, depth),
+                        );
+                    }
+                }
+            }
+            Command::BlitImage { ref src, ref dst, ref regions, filter } => {
+                let linear = match filter {
+                    image::FilterMethod::Scale | image::FilterMethod::Mipmap => false,
+                    _ => true,
+                };
+                for region in regions {
+                    blit_image_region(src, dst, region, linear);
+                }
+            }
+            Command::BindIndexBuffer { ref buffer, offset, index_type } => {
+                state.index_buffer = Some((buffer.clone(), offset, index_type));
+            }
+            Command::BindVertexBuffers(ref buffers) => {
+                state.vertex_buffers = buffers.clone();
+            }
+            Command::SetViewports(ref viewports) => {
+                state.viewports = viewports.clone();
+            }
+            Command::SetScissors(ref scissors) => {
+                state.scissors = scissors.clone();
+            }
+            Command::SetBlendConstants(color) => {
+                state.blend_constants = color;
+            }
+            Command::BeginRenderPass { ref render_pass, ref frame_buffer, area, ref clear_values } => {
+                let pass = RenderPassState {
+                    render_pass: render_pass.clone(),
+                    frame_buffer: frame_buffer.clone(),
+                    area,
+                    subpass: 0,
+                };
+                load_attachments(&pass, clear_values);
+                state.pass = Some(pass);
+            }
+            Command::NextSubpass => {
+                let pass = state.pass.as_mut().expect("No render pass in progress");
+                pass.subpass += 1;
+            }
+            Command::EndRenderPass => {
+                let pass = state.pass.take().expect("No render pass in progress");
+                store_attachments(&pass);
+            }
+            Command::BindGraphicsPipeline(ref pipeline) => {
+                state.pipeline = Some(pipeline.clone());
+            }
+            Command::CopyBuffer { ref src, ref dst, ref regions } => {
+                for region in regions {
+                    assert!(region.src + region.size <= src.size, "Buffer copy out of bounds");
+                    assert!(region.dst + region.size <= dst.size, "Buffer copy out of bounds");
+                    n::HostMemory::copy(
+                        &src.memory, src.offset + region.src,
+                        &dst.memory, dst.offset + region.dst,
+                        region.size,
+                    );
+                }
+            }
+            Command::CopyImage { ref src, ref dst, ref regions } => {
+                for region in regions {
+                    let extent = (region.extent.width as u64, region.extent.height as u64, region.extent.depth as u64);
+                    for layer in 0 .. region.num_layers {
+                        copy_image_region(
+                            src, (region.src_subresource.0, region.src_subresource.1 + layer), offset(region.src_offset),
+                            dst, (region.dst_subresource.0, region.dst_subresource.1 + layer), offset(region.dst_offset),
+                            extent,
+                        );
+                    }
+                }
+            }
+            Command::CopyBufferToImage { ref src, ref dst, ref regions } => {
+                for region in regions {
+                    copy_buffer_image(src, dst, region, |buffer, buffer_offset, image, image_offset, size| {
+                        n::HostMemory::copy(buffer, buffer_offset, image, image_offset, size)
+                    });
+                }
+            }
+            Command::CopyImageToBuffer { ref src, ref dst, ref regions } => {
+                for region in regions {
+                    copy_buffer_image(dst, src, region, |buffer, buffer_offset, image, image_offset, size| {
+                        n::HostMemory::copy(image, image_offset, buffer, buffer_offset, size)
+                    });
+                }
+            }
+            Command::Draw { ref vertices, ref instances } => {
+                draw(&mut state, vertices.clone(), instances.clone());
+            }
+            Command::DrawIndexed { ref indices, base_vertex, ref instances } => {
+                draw_indexed(&mut state, indices.clone(), base_vertex, instances.clone());
+            }
+            Command::DrawIndirect { ref buffer, offset, draw_count, stride } => {
+                for i in 0 .. draw_count as u64 {
+                    // vertex count, instance count, first vertex, first instance
+                    let args = read_u32s::<[u32; 4]>(buffer, offset + i * stride as u64);
+                    draw(&mut state, args[2] .. args[2] + args[0], args[3] .. args[3] + args[1]);
+                }
+            }
+            Command::DrawIndexedIndirect { ref buffer, offset, draw_count, stride } => {
+                for i in 0 .. draw_count as u64 {
+                    // index count, instance count, first index, vertex offset, first instance
+                    let args = read_u32s::<[u32; 5]>(buffer, offset + i * stride as u64);
+                    draw_indexed(&mut state, args[2] .. args[2] + args[0], args[3] as i32, args[4] .. args[4] + args[1]);
+                }
+            }
+            Command::BeginQuery { ref pool, query } => {
+                state.queries.push((pool.clone(), query, raster::Statistics::default()));
+            }
+            Command::EndQuery { ref pool, query } => {
+                let position = state.queries
+                    .iter()
+                    .position(|&(ref active, id, _)| Arc::ptr_eq(&active.results, &pool.results) && id == query)
+                    .expect("Ending a query which hasn't begun");
+                let (_, _, stats) = state.queries.remove(position);
+                pool.set(query .. query + 1, Some(query_values(pool.ty, &stats)));
+            }
+            Command::ResetQueryPool { ref pool, ref queries } => {
+                pool.set(queries.clone(), None);
+            }
+            Command::WriteTimestamp { ref pool, query } => {
+                let elapsed = origin.elapsed();
+                let nanos = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+                pool.set(query .. query + 1, Some(vec![nanos]));
+            }
+            Command::CopyQueryPoolResults { ref pool, ref queries, ref buffer, offset, stride, flags } => {
+                let (results, _) = pool.read(queries.clone(), flags);
+                for (i, values) in results.iter().enumerate() {
+                    let start = offset + i as u64 * stride;
+                    for (j, value) in values.iter().enumerate() {
+                        if let Some(value) = *value {
+                            let bytes = (0 .. 8).map(|byte| (value >> (8 * byte)) as u8).collect::<Vec<_>>();
+                            buffer.write(start + j as u64 * 8, &bytes);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn draw(state: &mut State, vertices: Range<u32>, instances: Range<u32>) {
+    let indices = vertices.collect::<Vec<_>>();
+    let stats = raster::draw(state, &indices, instances);
+    count_queries(state, &stats);
+}
+
+fn draw_indexed(state: &mut State, indices: Range<u32>, base_vertex: i32, instances: Range<u32>) {
+    let vertices = {
+        let (ref buffer, offset, index_type) = *state.index_buffer
+            .as_ref()
+            .expect("No index buffer bound");
+        let index_size = match index_type {
+            IndexType::U16 => 2,
+            IndexType::U32 => 4,
+        };
+        let mut data = vec![0; (indices.end - indices.start) as usize * index_size];
+        buffer.read(offset + indices.start as u64 * index_size as u64, &mut data);
+        data.chunks(index_size)
+            .map(|bytes| (le_u32(bytes) as i64 + base_vertex as i64) as u32)
+            .collect::<Vec<_>>()
+    };
+    let stats = raster::draw(state, &vertices, instances);
+    count_queries(state, &stats);
+}
+
+/// Read consecutive little endian `u32` values, e.g. the arguments of an indirect draw.
+fn read_u32s<A: Default + AsMut<[u32]>>(buffer: &n::Buffer, offset: u64) -> A {
+    let mut values = A::default();
+    let mut data = vec![0; 4 * values.as_mut().len()];
+    buffer.read(offset, &mut data);
+    for (value, bytes) in values.as_mut().iter_mut().zip(data.chunks(4)) {
+        *value = le_u32(bytes);
+    }
+    values
+}
+
+fn le_u32(bytes: &[u8]) -> u32 {
+    bytes.iter().rev().fold(0, |value, &byte| value << 8 | byte as u32)
+}
+
+fn count_queries(state: &mut State, stats: &raster::Statistics) {
+    for &mut (_, _, ref mut counted) in &mut state.queries {
+        counted.accumulate(stats);
+    }
+}
+
+/// Result values of a query which counted the given work.
+fn query_values(ty: query::QueryType, stats: &raster::Statistics) -> Vec<u64> {
+    match ty {
+        query::QueryType::Occlusion => vec![stats.samples_passed],
+        query::QueryType::PipelineStatistics(statistics) => {
+            // Ordered by the bit values of the statistics.
+            let counters = [
+                (query::INPUT_ASSEMBLY_VERTICES, stats.vertices),
+                (query::INPUT_ASSEMBLY_PRIMITIVES, stats.primitives),
+                (query::VERTEX_SHADER_INVOCATIONS, stats.vertices),
+                (query::GEOMETRY_SHADER_INVOCATIONS, 0),
+                (query::GEOMETRY_SHADER_PRIMITIVES, 0),
+                (query::CLIPPING_INVOCATIONS, stats.primitives),
+                (query::CLIPPING_PRIMITIVES, stats.clipped_primitives),
+                (query::PIXEL_SHADER_INVOCATIONS, stats.pixel_shader_invocations),
+                (query::HULL_SHADER_PATCHES, 0),
+                (query::DOMAIN_SHADER_INVOCATIONS, 0),
+                (query::COMPUTE_SHADER_INVOCATIONS, 0),
+            ];
+            counters
+                .iter()
+                .filter(|&&(statistic, _)| statistics.contains(statistic))
+                .map(|&(_, value)| value)
+                .collect()
+        }
+        query::QueryType::Timestamp => panic!("Timestamp queries can't be begun"),
+    }
+}
+
+fn offset(offset: command::Offset) -> (u64, u64, u64) {
+    (offset.x as u64, offset.y as u64, offset.z as u64)
+}
+
+/// Rectangle given as `(x0, y0, x1, y1)`, with exclusive upper bounds.
+pub type Bounds = (u64, u64, u64, u64);
+
+/// Call `f` on the bytes of each texel inside the bounds.
+fn modify_texels<F>(
+    image: &n::Image,
+    level: image::Level,
+    layers: Range<image::Layer>,
+    bounds: Bounds,
+    f: F,
+) where
+    F: Fn(&mut [u8]),
+{
+    let (width, height, _) = image.extent(level);
+    let (x0, y0) = (bounds.0.min(width), bounds.1.min(height));
+    let (x1, y1) = (bounds.2.min(width), bounds.3.min(height));
+    let mut texel = vec![0; image.texel_size() as usize];
+    for layer in layers {
+        for y in y0 .. y1 {
+            for x in x0 .. x1 {
+                image.read_texel(level, layer, x, y, 0, &mut texel);
+                f(&mut texel);
+                image.write_texel(level, layer, x, y, 0, &texel);
+            }
+        }
+    }
+}
+
+fn clear_color(
+    image: &n::Image,
+    format: ::core::format::Format,
+    level: image::Level,
+    layers: Range<image::Layer>,
+    bounds: Bounds,
+    color: command::ClearColor,
+) {
+    modify_texels(image, level, layers, bounds, |texel| match color {
+        command::ClearColor::Float(value) => conv::write_texel(format, value, texel),
+        command::ClearColor::Int(value) => conv::write_texel_int(format, [
+            value[0] as u32, value[1] as u32, value[2] as u32, value[3] as u32,
+        ], texel),
+        command::ClearColor::Uint(value) => conv::write_texel_int(format, value, texel),
+    });
+}
+
+fn clear_depth(
+    image: &n::Image,
+    format: ::core::format::Format,
+    level: image::Level,
+    layers: Range<image::Layer>,
+    bounds: Bounds,
+    depth: target::Depth,
+) {
+    modify_texels(image, level, layers, bounds, |texel| {
+        conv::write_texel(format, [depth, 0.0, 0.0, 0.0], texel)
+    });
+}
+
+fn area_bounds(area: target::Rect) -> Bounds {
+    let (x, y) = (area.x as u64, area.y as u64);
+    (x, y, x + area.w as u64, y + area.h as u64)
+}
+
+// Apply the load operations of all attachments at the start of a render pass.
+fn load_attachments(pass: &RenderPassState, clear_values: &[command::ClearValue]) {
+    let bounds = area_bounds(pass.area);
+    let colors = &pass.frame_buffer.colors;
+
+    for (i, attachment) in pass.render_pass.attachments.iter().enumerate() {
+        match attachment.ops.load {
+            pass::AttachmentLoadOp::Load => (),
+            pass::AttachmentLoadOp::Clear => match (colors.get(i), clear_values.get(i)) {
+                (Some(view), Some(&command::ClearValue::Color(color))) => {
+                    clear_color(&view.image, view.format, view.level, view.layers.clone(), bounds, color);
+                }
+                (None, Some(&command::ClearValue::DepthStencil(value))) => {
+                    let view = pass.frame_buffer.depth_stencil
+                        .as_ref()
+                        .expect("Missing depth-stencil attachment");
+                    clear_depth(&view.image, view.format, view.level, view.layers.clone(), bounds, value.depth);
+                }
+                (_, value) => panic!("Invalid clear value {:?} for attachment {}", value, i),
+            },
+            pass::AttachmentLoadOp::DontCare => poison_attachment(pass, i, bounds),
+        }
+    }
+}
+
+// Apply the store operations of all attachments at the end of a render pass.
+fn store_attachments(pass: &RenderPassState) {
+    let bounds = area_bounds(pass.area);
+    for (i, attachment) in pass.render_pass.attachments.iter().enumerate() {
+        match attachment.ops.store {
+            pass::AttachmentStoreOp::Store => (),
+            pass::AttachmentStoreOp::DontCare => poison_attachment(pass, i, bounds),
+        }
+    }
+}
+
+// Undefined depth contents are left as is, as there is no
+// depth value which would stand out.
+fn poison_attachment(pass: &RenderPassState, index: usize, bounds: Bounds) {
+    if let Some(view) = pass.frame_buffer.colors.get(index) {
+        let color = command::ClearColor::Float(POISON_COLOR);
+        clear_color(&view.image, view.format, view.level, view.layers.clone(), bounds, color);
+    }
+}
+
+fn copy_image_region(
+    src: &n::Image,
+    (src_level, src_layer): image::Subresource,
+    (src_x, src_y, src_z): (u64, u64, u64),
+    dst: &n::Image,
+    (dst_level, dst_layer): image::Subresource,
+    (dst_x, dst_y, dst_z): (u64, u64, u64),
+    (width, height, depth): (u64, u64, u64),
+) {
+    assert_eq!(src.texel_size(), dst.texel_size(), "Incompatible image formats");
+    let row_size = width * src.texel_size();
+    for z in 0 .. depth {
+        for y in 0 .. height {
+            n::HostMemory::copy(
+                &src.memory, src.texel_offset(src_level, src_layer, src_x, src_y + y, src_z + z),
+                &dst.memory, dst.texel_offset(dst_level, dst_layer, dst_x, dst_y + y, dst_z + z),
+                row_size,
+            );
+        }
+    }
+}
+
+/// Texels covered by the bounds `a` and `b` along one axis, given in either order.
+fn span(a: i32, b: i32) -> Range<i32> {
+    a.min(b) .. a.max(b)
+}
+
+/// Source texels to sample for destination texel `i` along one axis, with their weights.
+///
+/// The destination bounds `dst` are mapped onto the source bounds `src`, which
+/// mirrors the axis if only one of them is reversed. Samples are taken at the center
+/// of the destination texel and clamped to the source bounds.
+fn blit_taps(i: i32, dst: (i32, i32), src: (i32, i32), linear: bool) -> [(u64, f32); 2] {
+    let t = (i as f32 + 0.5 - dst.0 as f32) / (dst.1 - dst.0) as f32;
+    let pos = src.0 as f32 + t * (src.1 - src.0) as f32;
+    let texels = span(src.0, src.1);
+    let clamp = |x: i32| x.max(texels.start).min(texels.end - 1) as u64;
+    if linear {
+        let pos = pos - 0.5;
+        let base = pos.floor();
+        let weight = pos - base;
+        [(clamp(base as i32), 1.0 - weight), (clamp(base as i32 + 1), weight)]
+    } else {
+        [(clamp(pos.floor() as i32), 1.0), (0, 0.0)]
+    }
+}
+
+// Scale a region of `src` onto a region of `dst`, converting between their formats.
+// Linear filtering interpolates along the x and y axes, slices of 3D images are
+// always picked by the nearest texel.
+fn blit_image_region(src: &n::Image, dst: &n::Image, region: &command::ImageBlit, linear: bool) {
+    let (src_level, ref src_layers) = region.src_subresource;
+    let (dst_level, ref dst_layers) = region.dst_subresource;
+    let (s0, s1) = (region.src_bounds.start, region.src_bounds.end);
+    let (d0, d1) = (region.dst_bounds.start, region.dst_bounds.end);
+    let mut data = vec![0; src.texel_size() as usize];
+    let mut out = vec![0; dst.texel_size() as usize];
+
+    for (src_layer, dst_layer) in (src_layers.start .. src_layers.end).zip(dst_layers.start .. dst_layers.end) {
+        for z in span(d0.z, d1.z) {
+            let sz = blit_taps(z, (d0.z, d1.z), (s0.z, s1.z), false)[0].0;
+            for y in span(d0.y, d1.y) {
+                let ty = blit_taps(y, (d0.y, d1.y), (s0.y, s1.y), linear);
+                for x in span(d0.x, d1.x) {
+                    let tx = blit_taps(x, (d0.x, d1.x), (s0.x, s1.x), linear);
+                    let mut value = [0.0; 4];
+                    for &(sy, wy) in &ty {
+                        for &(sx, wx) in &tx {
+                            if wx * wy == 0.0 {
+                                continue;
+                            }
+                            src.read_texel(src_level, src_layer, sx, sy, sz, &mut data);
+                            let texel = conv::read_texel(src.format, &data);
+                            for (v, t) in value.iter_mut().zip(texel.iter()) {
+                                *v += t * wx * wy;
+                            }
+                        }
+                    }
+                    let (x, y, z) = (x as u64, y as u64, z as u64);
+                    // Read the texel first, keeping the stencil of depth-stencil formats.
+                    dst.read_texel(dst_level, dst_layer, x, y, z, &mut out);
+                    conv::write_texel(dst.format, value, &mut out);
+                    dst.write_texel(dst_level, dst_layer, x, y, z, &out);
+                }
+            }
+        }
+    }
+}
+
+// Walk the rows of a buffer-image copy, calling `copy_row` with
+// the buffer memory and offset, the image memory and offset and the row size.
+fn copy_buffer_image<F>(buffer: &n::Buffer, image: &n::Image, region: &command::BufferImageCopy, copy_row: F)
+where
+    F: Fn(&n::HostMemory, u64, &n::HostMemory, u64, u64),
+{
+    let (level, ref layers) = region.image_subresource;
+    let (x, y, z) = offset(region.image_offset);
+    let extent = &region.image_extent;
+    let row_size = extent.width as u64 * image.texel_size();
+    let row_pitch = match region.buffer_row_pitch {
+        0 => row_size,
+        pitch => pitch as u64,
+    };
+    let slice_pitch = match region.buffer_slice_pitch {
+        0 => row_pitch * extent.height as u64,
+        pitch => pitch as u64,
+    };
+    let layer_pitch = slice_pitch * extent.depth as u64;
+
+    for (i, layer) in (layers.start .. layers.end).enumerate() {
+        for slice in 0 .. extent.depth as u64 {
+            for row in 0 .. extent.height as u64 {
+                let buffer_offset = region.buffer_offset + i as u64 * layer_pitch + slice * slice_pitch + row * row_pitch;
+                assert!(buffer_offset + row_size <= buffer.size, "Buffer-image copy out of bounds");
+                copy_row(
+                    &buffer.memory, buffer.offset + buffer_offset,
+                    &image.memory, image.texel_offset(level, layer, x, y + row, z + slice),
+                    row_size,
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! CPU reference backend.
+//!
+//! Buffers and images live in host memory and every submission is executed
+//! on the calling thread by `submit_raw`, fences are signalled once it returns.
+//! Transfer commands, clears and render passes follow the core API semantics,
+//! draws are handled by a simple triangle rasterizer. Vertex and pixel shaders
+//! are Rust closures registered in a `ShaderLib`.
+//!
+//! The backend is meant for testing code against a known reference without
+//! requiring any driver, e.g. comparing rendered images to golden data.
+//! Performance isn't a goal. Unsupported features:
+//!
+//!   - compute and indirect commands
+//!   - point and line primitives, tessellation and geometry shaders
+//!   - stencil test and multisampling
+//!   - descriptor sets: shader closures capture their inputs directly
+
+#[macro_use]
+extern crate log;
+extern crate gfx_core as core;
+
+use core::{memory, QueueType};
+use std::time::Instant;
+
+mod command;
+mod conv;
+mod device;
+mod execute;
+mod native;
+mod pool;
+mod raster;
+mod window;
+
+pub use native::{PixelInput, PixelShader, ShaderLib, VertexInput, VertexOutput, VertexShader};
+pub use window::{Surface, Swapchain};
+
+/// Size of the memory heap exposed by the adapter.
+const HEAP_SIZE: u64 = 1 << 30;
+/// Number of queues of the graphics queue family.
+const NUM_QUEUES: u32 = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Backend { }
+impl core::Backend for Backend {
+    type Adapter = Adapter;
+    type Device = Device;
+
+    type Surface = window::Surface;
+    type Swapchain = window::Swapchain;
+
+    type CommandQueue = CommandQueue;
+    type CommandBuffer = command::RawCommandBuffer;
+    type SubpassCommandBuffer = command::SubpassCommandBuffer;
+    type QueueFamily = QueueFamily;
+
+    type Heap = native::Heap;
+    type Mapping = native::Mapping;
+    type CommandPool = pool::RawCommandPool;
+    type SubpassCommandPool = pool::SubpassCommandPool;
+
+    type ShaderLib = native::ShaderLib;
+    type RenderPass = native::RenderPass;
+    type FrameBuffer = native::FrameBuffer;
+
+    type UnboundBuffer = native::UnboundBuffer;
+    type Buffer = native::Buffer;
+    type UnboundImage = native::UnboundImage;
+    type Image = native::Image;
+    type Sampler = native::Sampler;
+
+    type ConstantBufferView = native::ConstantBufferView;
+    type ShaderResourceView = native::ShaderResourceView;
+    type UnorderedAccessView = native::UnorderedAccessView;
+    type RenderTargetView = native::RenderTargetView;
+    type DepthStencilView = native::DepthStencilView;
+
+    type ComputePipeline = native::ComputePipeline;
+    type GraphicsPipeline = native::GraphicsPipeline;
+    type PipelineCache = native::PipelineCache;
+    type PipelineLayout = native::PipelineLayout;
+    type DescriptorSetLayout = native::DescriptorSetLayout;
+    type DescriptorPool = pool::DescriptorPool;
+    type DescriptorSet = native::DescriptorSet;
+
+    type QueryPool = native::QueryPool;
+
+    type Fence = native::Fence;
+    type Semaphore = native::Semaphore;
+}
+
+/// Software instance, exposing a single adapter.
+pub struct Instance;
+
+impl Instance {
+    pub fn create() -> Self {
+        Instance
+    }
+}
+
+impl core::Instance<Backend> for Instance {
+    fn enumerate_adapters(&self) -> Vec<Adapter> {
+        vec![Adapter::new()]
+    }
+}
+
+pub struct Adapter {
+    info: core::AdapterInfo,
+    queue_families: Vec<(QueueFamily, QueueType)>,
+}
+
+impl Adapter {
+    /// Create a new adapter, exposing a single graphics queue family.
+    ///
+    /// All queues of the family execute their submissions on the calling thread.
+    /// Compute shaders aren't supported.
+    pub fn new() -> Self {
+        Adapter {
+            info: core::AdapterInfo {
+                name: "Software rasterizer".into(),
+                vendor: 0,
+                device: 0,
+                software_rendering: true,
+            },
+            queue_families: vec![(QueueFamily { num_queues: NUM_QUEUES }, QueueType::Graphics)],
+        }
+    }
+}
+
+impl core::Adapter<Backend> for Adapter {
+    fn open(&self, queue_descs: &[(&QueueFamily, QueueType, u32)]) -> core::Gpu<Backend> {
+        let heap_types = vec![
+            core::HeapType {
+                id: 0,
+                properties: memory::DEVICE_LOCAL | memory::CPU_VISIBLE | memory::COHERENT,
+                heap_index: 0,
+            },
+            core::HeapType {
+                id: 1,
+                properties: memory::CPU_VISIBLE | memory::COHERENT | memory::CPU_CACHED,
+                heap_index: 0,
+            },
+        ];
+
+        let mut gpu = core::Gpu {
+            device: Device::new(heap_types.clone()),
+            general_queues: Vec::new(),
+            graphics_queues: Vec::new(),
+            compute_queues: Vec::new(),
+            transfer_queues: Vec::new(),
+            heap_types,
+            memory_heaps: vec![HEAP_SIZE],
+        };
+
+        // Timestamps of all queues count from the same point in time.
+        let origin = Instant::now();
+        for &(family, qtype, count) in queue_descs {
+            assert!(count <= family.num_queues);
+            for _ in 0 .. count {
+                unsafe {
+                    match qtype {
+                        QueueType::General => gpu.general_queues.push(core::CommandQueue::new(CommandQueue { origin })),
+                        QueueType::Graphics => gpu.graphics_queues.push(core::CommandQueue::new(CommandQueue { origin })),
+                        QueueType::Compute => gpu.compute_queues.push(core::CommandQueue::new(CommandQueue { origin })),
+                        QueueType::Transfer => gpu.transfer_queues.push(core::CommandQueue::new(CommandQueue { origin })),
+                    }
+                }
+            }
+        }
+
+        gpu
+    }
+
+    fn get_info(&self) -> &core::AdapterInfo {
+        &self.info
+    }
+
+    fn get_queue_families(&self) -> &[(QueueFamily, QueueType)] {
+        &self.queue_families
+    }
+}
+
+pub struct QueueFamily {
+    num_queues: u32,
+}
+
+impl core::QueueFamily for QueueFamily {
+    fn num_queues(&self) -> u32 {
+        self.num_queues
+    }
+}
+
+/// Command queue, executing submissions immediately.
+pub struct CommandQueue {
+    origin: Instant,
+}
+
+impl core::RawCommandQueue<Backend> for CommandQueue {
+    unsafe fn submit_raw(&mut self, submission: core::RawSubmission<Backend>, fence: Option<&native::Fence>) {
+        // Semaphores can be ignored, all work is done once we return.
+        for cmd_buffer in submission.cmd_buffers {
+            execute::execute(&cmd_buffer.commands, self.origin);
+        }
+
+        if let Some(fence) = fence {
+            fence.set(true);
+        }
+    }
+}
+
+pub struct Device {
+    features: core::Features,
+    limits: core::Limits,
+    heap_types: Vec<core::HeapType>,
+}
+
+impl Device {
+    fn new(heap_types: Vec<core::HeapType>) -> Self {
+        Device {
+            features: core::Features {
+                indirect_execution: true,
+                draw_instanced: true,
+                draw_instanced_base: true,
+                draw_indexed_base: true,
+                draw_indexed_instanced: true,
+                draw_indexed_instanced_base_vertex: true,
+                draw_indexed_instanced_base: true,
+                instance_rate: true,
+                vertex_base: true,
+                srgb_color: true,
+                constant_buffer: false,
+                unordered_access_view: false,
+                separate_blending_slots: true,
+                copy_buffer: true,
+                sampler_objects: false,
+                sampler_lod_bias: false,
+                sampler_anisotropy: false,
+                sampler_border_color: false,
+                precise_occlusion_query: true,
+                pipeline_statistics_query: true,
+                timestamp_query: true,
+            },
+            limits: core::Limits {
+                max_texture_size: 1 << 14,
+                max_patch_size: 0,
+                max_viewports: 1,
+                min_buffer_copy_offset_alignment: 1,
+                min_buffer_copy_pitch_alignment: 1,
+                min_constant_buffer_offset_alignment: 1,
+                timestamp_period: 1.0,
+            },
+            heap_types,
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use core::{format, image, pass, pso, query};
+use core::state as s;
+use std::{fmt, mem, ptr};
+use std::cell::UnsafeCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Host memory backing heaps, buffers and images.
+//
+// Accesses are not synchronized, the user has to ensure that mapped ranges
+// don't alias with memory accessed by in-flight submissions,
+// like with real device memory.
+pub struct HostMemory(UnsafeCell<Vec<u64>>);
+unsafe impl Send for HostMemory {}
+unsafe impl Sync for HostMemory {}
+
+impl HostMemory {
+    pub fn new(size: u64) -> Self {
+        let words = (size as usize + mem::size_of::<u64>() - 1) / mem::size_of::<u64>();
+        HostMemory(UnsafeCell::new(vec![0; words]))
+    }
+
+    pub fn size(&self) -> u64 {
+        unsafe { ((*self.0.get()).len() * mem::size_of::<u64>()) as u64 }
+    }
+
+    pub fn as_ptr(&self) -> *mut u8 {
+        unsafe { (*self.0.get()).as_mut_ptr() as *mut u8 }
+    }
+
+    pub fn read(&self, offset: u64, data: &mut [u8]) {
+        assert!(offset + data.len() as u64 <= self.size());
+        unsafe {
+            ptr::copy(self.as_ptr().offset(offset as isize), data.as_mut_ptr(), data.len());
+        }
+    }
+
+    pub fn write(&self, offset: u64, data: &[u8]) {
+        assert!(offset + data.len() as u64 <= self.size());
+        unsafe {
+            ptr::copy(data.as_ptr(), self.as_ptr().offset(offset as isize), data.len());
+        }
+    }
+
+    pub fn copy(src: &HostMemory, src_offset: u64, dst: &HostMemory, dst_offset: u64, size: u64) {
+        assert!(src_offset + size <= src.size());
+        assert!(dst_offset + size <= dst.size());
+        unsafe {
+            ptr::copy(
+                src.as_ptr().offset(src_offset as isize),
+                dst.as_ptr().offset(dst_offset as isize),
+                size as usize,
+            );
+        }
+    }
+}
+
+impl fmt::Debug for HostMemory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "HostMemory({:p})", self.as_ptr())
+    }
+}
+
+#[derive(Debug)]
+pub struct Heap {
+    pub(crate) size: u64,
+    pub(crate) cpu_visible: bool,
+    pub(crate) memory: Arc<HostMemory>,
+}
+
+#[derive(Debug)]
+pub struct Mapping {
+    pub(crate) _memory: Arc<HostMemory>,
+}
+
+#[derive(Debug)]
+pub struct UnboundBuffer {
+    pub(crate) size: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Buffer {
+    pub(crate) memory: Arc<HostMemory>,
+    pub(crate) offset: u64,
+    pub(crate) size: u64,
+    pub(crate) cpu_visible: bool,
+}
+
+impl Buffer {
+    pub(crate) fn read(&self, offset: u64, data: &mut [u8]) {
+        assert!(offset + data.len() as u64 <= self.size, "Buffer read out of bounds");
+        self.memory.read(self.offset + offset, data);
+    }
+
+    pub(crate) fn write(&self, offset: u64, data: &[u8]) {
+        assert!(offset + data.len() as u64 <= self.size, "Buffer write out of bounds");
+        self.memory.write(self.offset + offset, data);
+    }
+}
+
+#[derive(Debug)]
+pub struct UnboundImage {
+    pub(crate) kind: image::Kind,
+    pub(crate) levels: image::Level,
+    pub(crate) format: format::Format,
+    pub(crate) usage: image::Usage,
+}
+
+impl UnboundImage {
+    pub(crate) fn size(&self) -> u64 {
+        footprint(self.kind, self.format, self.levels)
+    }
+}
+
+/// Image stored linearly in host memory.
+///
+/// Mip levels are stored one after each other, each containing all
+/// array layers, which are made of tightly packed slices and rows.
+#[derive(Clone, Debug)]
+pub struct Image {
+    pub(crate) memory: Arc<HostMemory>,
+    pub(crate) offset: u64,
+    pub(crate) kind: image::Kind,
+    pub(crate) levels: image::Level,
+    pub(crate) format: format::Format,
+    pub(crate) usage: image::Usage,
+}
+
+fn texel_size(format: format::Format) -> u64 {
+    format.0.get_total_bits() as u64 / 8
+}
+
+fn num_layers(kind: image::Kind) -> u64 {
+    let faces = if kind.is_cube() { 6 } else { 1 };
+    kind.get_num_slices().unwrap_or(1) as u64 * faces
+}
+
+fn level_extent(kind: image::Kind, level: image::Level) -> (u64, u64, u64) {
+    let (w, h, d, _) = kind.get_level_dimensions(level);
+    (w.max(1) as u64, h.max(1) as u64, d.max(1) as u64)
+}
+
+fn level_size(kind: image::Kind, format: format::Format, level: image::Level) -> u64 {
+    let (w, h, d) = level_extent(kind, level);
+    w * h * d * num_layers(kind) * texel_size(format)
+}
+
+fn footprint(kind: image::Kind, format: format::Format, levels: image::Level) -> u64 {
+    (0 .. levels).map(|level| level_size(kind, format, level)).sum()
+}
+
+impl Image {
+    pub(crate) fn new_owned(
+        kind: image::Kind,
+        levels: image::Level,
+        format: format::Format,
+        usage: image::Usage,
+    ) -> Self {
+        Image {
+            memory: Arc::new(HostMemory::new(footprint(kind, format, levels))),
+            offset: 0,
+            kind,
+            levels,
+            format,
+            usage,
+        }
+    }
+
+    pub(crate) fn texel_size(&self) -> u64 {
+        texel_size(self.format)
+    }
+
+    pub(crate) fn num_layers(&self) -> u64 {
+        num_layers(self.kind)
+    }
+
+    /// Extent of a mip level, each dimension is at least 1.
+    pub(crate) fn extent(&self, level: image::Level) -> (u64, u64, u64) {
+        level_extent(self.kind, level)
+    }
+
+    /// Offset of a texel in the backing memory.
+    pub(crate) fn texel_offset(&self, level: image::Level, layer: image::Layer, x: u64, y: u64, z: u64) -> u64 {
+        let (w, h, d) = self.extent(level);
+        debug_assert!(x < w && y < h && z < d && (layer as u64) < self.num_layers());
+        let level_offset = footprint(self.kind, self.format, level);
+        let texel = self.texel_size();
+        let row_pitch = w * texel;
+        let slice_pitch = h * row_pitch;
+        let layer_pitch = d * slice_pitch;
+        self.offset + level_offset + layer as u64 * layer_pitch + z * slice_pitch + y * row_pitch + x * texel
+    }
+
+    pub(crate) fn read_texel(&self, level: image::Level, layer: image::Layer, x: u64, y: u64, z: u64, data: &mut [u8]) {
+        self.memory.read(self.texel_offset(level, layer, x, y, z), data)
+    }
+
+    pub(crate) fn write_texel(&self, level: image::Level, layer: image::Layer, x: u64, y: u64, z: u64, data: &[u8]) {
+        self.memory.write(self.texel_offset(level, layer, x, y, z), data)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RenderTargetView {
+    pub(crate) image: Image,
+    pub(crate) format: format::Format,
+    pub(crate) level: image::Level,
+    pub(crate) layers: Range<image::Layer>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DepthStencilView {
+    pub(crate) image: Image,
+    pub(crate) format: format::Format,
+    pub(crate) level: image::Level,
+    pub(crate) layers: Range<image::Layer>,
+}
+
+// Shader closures capture their resources directly,
+// views and samplers don't need to carry any information.
+#[derive(Debug)]
+pub struct ShaderResourceView;
+
+#[derive(Debug)]
+pub struct UnorderedAccessView;
+
+#[derive(Debug)]
+pub struct ConstantBufferView;
+
+#[derive(Debug)]
+pub struct Sampler;
+
+#[derive(Clone, Debug)]
+pub struct RenderPass {
+    pub(crate) attachments: Vec<pass::Attachment>,
+    pub(crate) subpasses: Vec<Vec<pass::AttachmentRef>>,
+}
+
+#[derive(Clone, Debug)]
+pub struct FrameBuffer {
+    pub(crate) colors: Vec<RenderTargetView>,
+    pub(crate) depth_stencil: Option<DepthStencilView>,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+/// Inputs of a vertex shader invocation.
+pub struct VertexInput<'a> {
+    /// Vertex attributes, indexed by their location.
+    /// Missing components are filled with `(0, 0, 0, 1)`.
+    pub attributes: &'a [[f32; 4]],
+    /// Index of the vertex.
+    pub vertex_index: u32,
+    /// Index of the instance.
+    pub instance_index: u32,
+}
+
+/// Outputs of a vertex shader invocation.
+#[derive(Clone, Debug)]
+pub struct VertexOutput {
+    /// Position in clip space.
+    pub position: [f32; 4],
+    /// Values interpolated across the primitive and passed to the pixel shader.
+    pub varyings: Vec<[f32; 4]>,
+}
+
+/// Inputs of a pixel shader invocation.
+pub struct PixelInput<'a> {
+    /// Window coordinates of the pixel center, depth and `1/w`.
+    pub position: [f32; 4],
+    /// Perspective-correct interpolated vertex shader varyings.
+    pub varyings: &'a [[f32; 4]],
+}
+
+/// Vertex shader closure.
+pub type VertexShader = Arc<Fn(&VertexInput) -> VertexOutput + Send + Sync>;
+/// Pixel shader closure, returning the color written to all color attachments.
+pub type PixelShader = Arc<Fn(&PixelInput) -> [f32; 4] + Send + Sync>;
+
+/// Collection of shader closures, addressed by their entry point.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate gfx_backend_software as software;
+/// # fn main() {
+/// use software::{ShaderLib, VertexOutput};
+///
+/// let shaders = ShaderLib::new()
+///     .with_vertex("vs_main", |input| VertexOutput {
+///         position: input.attributes[0],
+///         varyings: vec![input.attributes[1]],
+///     })
+///     .with_pixel("ps_main", |input| input.varyings[0]);
+/// # let _ = shaders;
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ShaderLib {
+    pub(crate) vertex: HashMap<pso::EntryPoint, VertexShader>,
+    pub(crate) pixel: HashMap<pso::EntryPoint, PixelShader>,
+}
+
+impl ShaderLib {
+    /// Create an empty shader library.
+    pub fn new() -> Self {
+        ShaderLib::default()
+    }
+
+    /// Register a vertex shader.
+    pub fn with_vertex<F>(mut self, entry: pso::EntryPoint, shader: F) -> Self
+    where
+        F: Fn(&VertexInput) -> VertexOutput + Send + Sync + 'static,
+    {
+        self.vertex.insert(entry, Arc::new(shader));
+        self
+    }
+
+    /// Register a pixel shader.
+    pub fn with_pixel<F>(mut self, entry: pso::EntryPoint, shader: F) -> Self
+    where
+        F: Fn(&PixelInput) -> [f32; 4] + Send + Sync + 'static,
+    {
+        self.pixel.insert(entry, Arc::new(shader));
+        self
+    }
+}
+
+impl fmt::Debug for ShaderLib {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ShaderLib")
+            .field("vertex", &self.vertex.keys().collect::<Vec<_>>())
+            .field("pixel", &self.pixel.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+pub struct GraphicsPipeline {
+    pub(crate) vertex: VertexShader,
+    pub(crate) pixel: Option<PixelShader>,
+    pub(crate) primitive: ::core::Primitive,
+    pub(crate) rasterizer: pso::Rasterizer,
+    pub(crate) vertex_buffers: Vec<pso::VertexBufferDesc>,
+    pub(crate) attributes: Vec<pso::AttributeDesc>,
+    pub(crate) targets: Vec<pso::ColorInfo>,
+    pub(crate) depth: Option<s::Depth>,
+}
+
+impl fmt::Debug for GraphicsPipeline {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GraphicsPipeline")
+            .field("primitive", &self.primitive)
+            .field("rasterizer", &self.rasterizer)
+            .field("vertex_buffers", &self.vertex_buffers)
+            .field("attributes", &self.attributes)
+            .field("targets", &self.targets)
+            .field("depth", &self.depth)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct ComputePipeline;
+
+/// Pipelines are cheap to create, so there is nothing to cache.
+#[derive(Debug)]
+pub struct PipelineCache;
+
+#[derive(Debug)]
+pub struct PipelineLayout;
+
+#[derive(Debug)]
+pub struct DescriptorSetLayout;
+
+#[derive(Debug)]
+pub struct DescriptorSet;
+
+#[derive(Clone, Debug)]
+pub struct Fence(pub(crate) Arc<AtomicBool>);
+
+impl Fence {
+    pub(crate) fn is_signaled(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set(&self, signaled: bool) {
+        self.0.store(signaled, Ordering::Release)
+    }
+}
+
+#[derive(Debug)]
+pub struct Semaphore;
+
+#[derive(Clone, Debug)]
+pub struct QueryPool {
+    pub(crate) ty: query::QueryType,
+    // Values of each query, `None` while unavailable.
+    pub(crate) results: Arc<Mutex<Vec<Option<Vec<u64>>>>>,
+}
+
+impl QueryPool {
+    pub(crate) fn set(&self, queries: Range<query::QueryId>, values: Option<Vec<u64>>) {
+        let mut results = self.results.lock().unwrap();
+        for result in &mut results[queries.start as usize .. queries.end as usize] {
+            *result = values.clone();
+        }
+    }
+
+    /// Collect the results of a range of queries as laid out by `Device::get_query_pool_results`,
+    /// `None` for values which are left unwritten. Returns whether all results were available.
+    pub(crate) fn read(&self, queries: Range<query::QueryId>, flags: query::ResultFlags)
+                       -> (Vec<Vec<Option<u64>>>, bool) {
+        let results = self.results.lock().unwrap();
+        let num_values = self.ty.num_values();
+        let mut all_available = true;
+        let values = results[queries.start as usize .. queries.end as usize]
+            .iter()
+            .map(|result| {
+                let mut values = match *result {
+                    Some(ref values) => values.iter().map(|&value| Some(value)).collect(),
+                    None if flags.contains(query::PARTIAL) => vec![Some(0); num_values],
+                    None => vec![None; num_values],
+                };
+                if flags.contains(query::WITH_AVAILABILITY) {
+                    values.push(Some(result.is_some() as u64));
+                }
+                all_available &= result.is_some();
+                values
+            })
+            .collect();
+        (values, all_available)
+    }
+}
@@ -0,0 +1,38 @@
+use core::{self, pool};
+use command::RawCommandBuffer;
+use {native as n, Backend, CommandQueue};
+
+pub struct RawCommandPool;
+
+impl core::RawCommandPool<Backend> for RawCommandPool {
+    fn reset(&mut self) {
+        // Command buffers own their recorded commands,
+        // which are cleared on `begin`.
+    }
+
+    unsafe fn from_queue(_: &CommandQueue, _: pool::CommandPoolCreateFlags) -> Self {
+        RawCommandPool
+    }
+
+    fn allocate(&mut self, num: usize) -> Vec<RawCommandBuffer> {
+        (0 .. num).map(|_| RawCommandBuffer::new()).collect()
+    }
+
+    unsafe fn free(&mut self, _: Vec<RawCommandBuffer>) { }
+}
+
+pub struct SubpassCommandPool;
+impl core::SubpassCommandPool<Backend> for SubpassCommandPool {
+
+}
+
+#[derive(Debug)]
+pub struct DescriptorPool;
+impl core::DescriptorPool<Backend> for DescriptorPool {
+    fn allocate_sets(&mut self, layouts: &[&n::DescriptorSetLayout]) -> Vec<n::DescriptorSet> {
+        layouts.iter().map(|_| n::DescriptorSet).collect()
+    }
+
+    fn reset(&mut self) {
+    }
+}
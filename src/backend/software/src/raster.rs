@@ -0,0 +1,390 @@
+//! Triangle rasterization.
+//!
+//! Follows the Vulkan conventions: the framebuffer origin is in the top-left
+//! corner, clip space depth ranges from 0 to 1 and pixel centers are sampled
+//! with the top-left fill rule. Primitives aren't clipped, triangles with a
+//! vertex behind the eye are discarded instead.
+
+use core::{self, image, Primitive};
+use core::state as s;
+use std::ops::Range;
+use execute::{Bounds, State};
+use native::{self as n, PixelInput, VertexInput, VertexOutput};
+use conv;
+
+/// Work done by draws, reported by queries.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Statistics {
+    /// Number of vertices fetched, each of them is shaded.
+    pub vertices: u64,
+    /// Number of assembled triangles.
+    pub primitives: u64,
+    /// Number of triangles which weren't discarded for being behind the eye.
+    pub clipped_primitives: u64,
+    /// Number of samples passing the depth test.
+    pub samples_passed: u64,
+    /// Number of pixel shader invocations.
+    pub pixel_shader_invocations: u64,
+}
+
+impl Statistics {
+    /// Add the counters of `other`.
+    pub fn accumulate(&mut self, other: &Statistics) {
+        self.vertices += other.vertices;
+        self.primitives += other.primitives;
+        self.clipped_primitives += other.clipped_primitives;
+        self.samples_passed += other.samples_passed;
+        self.pixel_shader_invocations += other.pixel_shader_invocations;
+    }
+}
+
+/// Vertex after the viewport transformation.
+#[derive(Clone, Copy, Debug)]
+struct WindowVertex {
+    x: f32,
+    y: f32,
+    /// Depth in normalized device coordinates.
+    z: f32,
+    inv_w: f32,
+}
+
+fn fetch_attributes(state: &State, pipeline: &n::GraphicsPipeline, vertex: u32, instance: u32) -> Vec<[f32; 4]> {
+    let count = pipeline.attributes
+        .iter()
+        .map(|attribute| attribute.location as usize + 1)
+        .max()
+        .unwrap_or(0);
+    let mut attributes = vec![[0.0, 0.0, 0.0, 1.0]; count];
+
+    for attribute in &pipeline.attributes {
+        let binding = attribute.binding as usize;
+        let desc = &pipeline.vertex_buffers[binding];
+        let (ref buffer, offset) = state.vertex_buffers[binding];
+        let index = match desc.rate {
+            0 => vertex,
+            rate => instance / rate as u32,
+        };
+        let format = attribute.element.format;
+        let mut data = vec![0; format.0.get_total_bits() as usize / 8];
+        let position = offset as u64 + index as u64 * desc.stride as u64 + attribute.element.offset as u64;
+        buffer.read(position, &mut data);
+        attributes[attribute.location as usize] = conv::read_texel(format, &data);
+    }
+
+    attributes
+}
+
+fn viewport_transform(viewport: &core::Viewport, position: [f32; 4]) -> WindowVertex {
+    let inv_w = 1.0 / position[3];
+    let (x, y, z) = (position[0] * inv_w, position[1] * inv_w, position[2] * inv_w);
+    WindowVertex {
+        x: viewport.x as f32 + (x + 1.0) * 0.5 * viewport.w as f32,
+        y: viewport.y as f32 + (y + 1.0) * 0.5 * viewport.h as f32,
+        z,
+        inv_w,
+    }
+}
+
+fn intersect(a: Bounds, b: Bounds) -> Bounds {
+    (a.0.max(b.0), a.1.max(b.1), a.2.min(b.2), a.3.min(b.3))
+}
+
+fn rect_bounds(rect: &core::target::Rect) -> Bounds {
+    let (x, y) = (rect.x as u64, rect.y as u64);
+    (x, y, x + rect.w as u64, y + rect.h as u64)
+}
+
+// Edge function, positive if `p` lies on the right side of the edge from `a` to `b`
+// in the framebuffer coordinate system.
+fn edge(a: &WindowVertex, b: &WindowVertex, px: f32, py: f32) -> f32 {
+    (b.x - a.x) * (py - a.y) - (b.y - a.y) * (px - a.x)
+}
+
+// Top and left edges of a triangle with positive area own the pixels
+// lying exactly on them.
+fn is_top_left(a: &WindowVertex, b: &WindowVertex) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    (dy == 0.0 && dx > 0.0) || dy < 0.0
+}
+
+fn compare(fun: s::Comparison, value: f32, reference: f32) -> bool {
+    match fun {
+        s::Comparison::Never => false,
+        s::Comparison::Less => value < reference,
+        s::Comparison::LessEqual => value <= reference,
+        s::Comparison::Equal => value == reference,
+        s::Comparison::GreaterEqual => value >= reference,
+        s::Comparison::Greater => value > reference,
+        s::Comparison::NotEqual => value != reference,
+        s::Comparison::Always => true,
+    }
+}
+
+fn blend_factor(factor: s::Factor, i: usize, src: &[f32; 4], dst: &[f32; 4], constant: &[f32; 4]) -> f32 {
+    let value = |value| match value {
+        s::BlendValue::SourceColor => src[i],
+        s::BlendValue::SourceAlpha => src[3],
+        s::BlendValue::DestColor => dst[i],
+        s::BlendValue::DestAlpha => dst[3],
+        s::BlendValue::ConstColor => constant[i],
+        s::BlendValue::ConstAlpha => constant[3],
+    };
+    match factor {
+        s::Factor::Zero => 0.0,
+        s::Factor::One => 1.0,
+        s::Factor::SourceAlphaSaturated if i == 3 => 1.0,
+        s::Factor::SourceAlphaSaturated => src[3].min(1.0 - dst[3]),
+        s::Factor::ZeroPlus(v) => value(v),
+        s::Factor::OneMinus(v) => 1.0 - value(v),
+    }
+}
+
+fn blend_component(channel: &s::BlendChannel, i: usize, src: &[f32; 4], dst: &[f32; 4], constant: &[f32; 4]) -> f32 {
+    let source = src[i] * blend_factor(channel.source, i, src, dst, constant);
+    let dest = dst[i] * blend_factor(channel.destination, i, src, dst, constant);
+    match channel.equation {
+        s::Equation::Add => source + dest,
+        s::Equation::Sub => source - dest,
+        s::Equation::RevSub => dest - source,
+        s::Equation::Min => src[i].min(dst[i]),
+        s::Equation::Max => src[i].max(dst[i]),
+    }
+}
+
+/// Blend the pixel shader output with the current target contents.
+fn blend(info: &core::pso::ColorInfo, src: [f32; 4], dst: [f32; 4], constant: &[f32; 4]) -> [f32; 4] {
+    let mut result = src;
+    if let Some(ref channel) = info.color {
+        for i in 0 .. 3 {
+            result[i] = blend_component(channel, i, &src, &dst, constant);
+        }
+    }
+    if let Some(ref channel) = info.alpha {
+        result[3] = blend_component(channel, 3, &src, &dst, constant);
+    }
+
+    for (i, &mask) in [s::RED, s::GREEN, s::BLUE, s::ALPHA].iter().enumerate() {
+        if !info.mask.contains(mask) {
+            result[i] = dst[i];
+        }
+    }
+    result
+}
+
+/// Draw the vertices with the currently bound pipeline for each instance.
+pub fn draw(state: &State, vertices: &[u32], instances: Range<core::InstanceCount>) -> Statistics {
+    let pipeline = state.pipeline.as_ref().expect("No graphics pipeline bound");
+    let pass = state.pass.as_ref().expect("Drawing outside of a render pass");
+    let frame_buffer = &pass.frame_buffer;
+
+    // Without a viewport, the whole framebuffer is covered.
+    let viewport = state.viewports.first().cloned().unwrap_or(core::Viewport {
+        x: 0,
+        y: 0,
+        w: frame_buffer.width as u16,
+        h: frame_buffer.height as u16,
+        near: 0.0,
+        far: 1.0,
+    });
+    let mut bounds = intersect(
+        rect_bounds(&pass.area),
+        (0, 0, frame_buffer.width as u64, frame_buffer.height as u64),
+    );
+    bounds = intersect(bounds, (
+        viewport.x as u64,
+        viewport.y as u64,
+        viewport.x as u64 + viewport.w as u64,
+        viewport.y as u64 + viewport.h as u64,
+    ));
+    if let Some(scissor) = state.scissors.first() {
+        bounds = intersect(bounds, rect_bounds(scissor));
+    }
+
+    let colors = pass.color_attachments();
+    let depth_target = match (pipeline.depth, frame_buffer.depth_stencil.as_ref()) {
+        (Some(depth), Some(view)) => Some((depth, view)),
+        _ => None,
+    };
+
+    let raster = Rasterizer {
+        pipeline,
+        viewport: &viewport,
+        bounds,
+        colors: &colors,
+        depth: depth_target,
+        blend_constants: &state.blend_constants,
+    };
+
+    let mut stats = Statistics::default();
+    for instance in instances {
+        stats.vertices += vertices.len() as u64;
+        let outputs = vertices
+            .iter()
+            .map(|&vertex| {
+                let attributes = fetch_attributes(state, pipeline, vertex, instance);
+                (pipeline.vertex)(&VertexInput {
+                    attributes: &attributes,
+                    vertex_index: vertex,
+                    instance_index: instance,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        match pipeline.primitive {
+            Primitive::TriangleList => {
+                for triangle in outputs.chunks(3).filter(|triangle| triangle.len() == 3) {
+                    raster.triangle([&triangle[0], &triangle[1], &triangle[2]], &mut stats);
+                }
+            }
+            Primitive::TriangleStrip => {
+                for i in 0 .. outputs.len().saturating_sub(2) {
+                    // Every other triangle is flipped to keep the winding order consistent.
+                    if i % 2 == 0 {
+                        raster.triangle([&outputs[i], &outputs[i + 1], &outputs[i + 2]], &mut stats);
+                    } else {
+                        raster.triangle([&outputs[i + 1], &outputs[i], &outputs[i + 2]], &mut stats);
+                    }
+                }
+            }
+            other => unreachable!("Unsupported primitive {:?}", other),
+        }
+    }
+    stats
+}
+
+struct Rasterizer<'a> {
+    pipeline: &'a n::GraphicsPipeline,
+    viewport: &'a core::Viewport,
+    bounds: Bounds,
+    colors: &'a [&'a n::RenderTargetView],
+    depth: Option<(s::Depth, &'a n::DepthStencilView)>,
+    blend_constants: &'a [f32; 4],
+}
+
+impl<'a> Rasterizer<'a> {
+    fn triangle(&self, mut vertices: [&VertexOutput; 3], stats: &mut Statistics) {
+        stats.primitives += 1;
+        if vertices.iter().any(|vertex| vertex.position[3] <= 0.0) {
+            return;
+        }
+        stats.clipped_primitives += 1;
+
+        let mut window = [
+            viewport_transform(self.viewport, vertices[0].position),
+            viewport_transform(self.viewport, vertices[1].position),
+            viewport_transform(self.viewport, vertices[2].position),
+        ];
+
+        let mut area = edge(&window[0], &window[1], window[2].x, window[2].y);
+        if area == 0.0 {
+            return;
+        }
+
+        // Negative area means counter-clockwise winding on screen.
+        let counter_clockwise = area < 0.0;
+        let front = match self.pipeline.rasterizer.front_face {
+            s::FrontFace::CounterClockwise => counter_clockwise,
+            s::FrontFace::Clockwise => !counter_clockwise,
+        };
+        match self.pipeline.rasterizer.cull_mode {
+            s::CullFace::Front if front => return,
+            s::CullFace::Back if !front => return,
+            _ => (),
+        }
+
+        if area < 0.0 {
+            window.swap(1, 2);
+            vertices.swap(1, 2);
+            area = -area;
+        }
+
+        let min_x = window.iter().fold(::std::f32::MAX, |m, v| m.min(v.x)).floor().max(0.0) as u64;
+        let min_y = window.iter().fold(::std::f32::MAX, |m, v| m.min(v.y)).floor().max(0.0) as u64;
+        let max_x = window.iter().fold(::std::f32::MIN, |m, v| m.max(v.x)).ceil().max(0.0) as u64;
+        let max_y = window.iter().fold(::std::f32::MIN, |m, v| m.max(v.y)).ceil().max(0.0) as u64;
+        let (x0, y0, x1, y1) = intersect(self.bounds, (min_x, min_y, max_x, max_y));
+
+        let owned = [
+            is_top_left(&window[1], &window[2]),
+            is_top_left(&window[2], &window[0]),
+            is_top_left(&window[0], &window[1]),
+        ];
+        let num_varyings = vertices.iter().map(|v| v.varyings.len()).min().unwrap_or(0);
+        let mut varyings = vec![[0.0; 4]; num_varyings];
+
+        for y in y0 .. y1 {
+            for x in x0 .. x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let weights = [
+                    edge(&window[1], &window[2], px, py),
+                    edge(&window[2], &window[0], px, py),
+                    edge(&window[0], &window[1], px, py),
+                ];
+                let inside = weights
+                    .iter()
+                    .zip(owned.iter())
+                    .all(|(&w, &owned)| w > 0.0 || (w == 0.0 && owned));
+                if !inside {
+                    continue;
+                }
+
+                let l = [weights[0] / area, weights[1] / area, weights[2] / area];
+                let mut z = l[0] * window[0].z + l[1] * window[1].z + l[2] * window[2].z;
+                if self.pipeline.rasterizer.depth_clamping {
+                    z = z.max(0.0).min(1.0);
+                } else if z < 0.0 || z > 1.0 {
+                    continue;
+                }
+                let depth = self.viewport.near + z * (self.viewport.far - self.viewport.near);
+
+                if let Some((ref state, view)) = self.depth {
+                    let mut texel = vec![0; view.image.texel_size() as usize];
+                    view.image.read_texel(view.level, view.layers.start, x, y, 0, &mut texel);
+                    let stored = conv::read_texel(view.format, &texel)[0];
+                    if !compare(state.fun, depth, stored) {
+                        continue;
+                    }
+                    if state.write {
+                        conv::write_texel(view.format, [depth, 0.0, 0.0, 0.0], &mut texel);
+                        view.image.write_texel(view.level, view.layers.start, x, y, 0, &texel);
+                    }
+                }
+                stats.samples_passed += 1;
+
+                let shader = match self.pipeline.pixel {
+                    Some(ref shader) => shader,
+                    None => continue,
+                };
+                stats.pixel_shader_invocations += 1;
+
+                // Perspective-correct interpolation.
+                let inv_w = l[0] * window[0].inv_w + l[1] * window[1].inv_w + l[2] * window[2].inv_w;
+                for (i, varying) in varyings.iter_mut().enumerate() {
+                    for c in 0 .. 4 {
+                        varying[c] = (0 .. 3)
+                            .map(|v| l[v] * window[v].inv_w * vertices[v].varyings[i][c])
+                            .sum::<f32>() / inv_w;
+                    }
+                }
+
+                let color = shader(&PixelInput {
+                    position: [px, py, depth, inv_w],
+                    varyings: &varyings,
+                });
+                self.write_colors(x, y, color);
+            }
+        }
+    }
+
+    fn write_colors(&self, x: u64, y: u64, color: [f32; 4]) {
+        for (view, info) in self.colors.iter().zip(self.pipeline.targets.iter()) {
+            let layer: image::Layer = view.layers.start;
+            let mut texel = vec![0; view.image.texel_size() as usize];
+            view.image.read_texel(view.level, layer, x, y, 0, &mut texel);
+            let dst = conv::read_texel(view.format, &texel);
+            let result = blend(info, color, dst, self.blend_constants);
+            conv::write_texel(view.format, result, &mut texel);
+            view.image.write_texel(view.level, layer, x, y, 0, &texel);
+        }
+    }
+}
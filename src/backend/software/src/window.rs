@@ -0,0 +1,90 @@
+use core::{self, image};
+use {native as n, Adapter, Backend, QueueFamily};
+
+/// Maximum number of images in a swapchain.
+const MAX_BACKBUFFERS: u32 = 8;
+
+/// Headless surface, backbuffers are regular images living in host memory.
+pub struct Surface {
+    width: image::Size,
+    height: image::Size,
+}
+
+impl Surface {
+    /// Create a new surface with the given backbuffer dimensions.
+    pub fn new(width: image::Size, height: image::Size) -> Self {
+        Surface { width, height }
+    }
+}
+
+impl core::Surface<Backend> for Surface {
+    fn supports_queue(&self, _: &QueueFamily) -> bool {
+        true
+    }
+
+    fn get_capabilities(&self, _: &Adapter) -> core::SurfaceCapabilities {
+        core::SurfaceCapabilities {
+            formats: None,
+            min_image_count: 1,
+            max_image_count: Some(MAX_BACKBUFFERS),
+            current_extent: None,
+            // Nothing is shown, presentation never waits.
+            present_modes: vec![core::PresentMode::Immediate, core::PresentMode::Fifo],
+        }
+    }
+
+    fn build_swapchain<C>(&mut self, config: core::SwapchainConfig, _: &core::CommandQueue<Backend, C>) -> Swapchain {
+        let (width, height) = match config.extent {
+            Some(extent) => (extent.width as image::Size, extent.height as image::Size),
+            None => (self.width, self.height),
+        };
+        let kind = image::Kind::D2(width, height, image::AaMode::Single);
+        let usage = image::COLOR_ATTACHMENT | image::TRANSFER_SRC | image::TRANSFER_DST;
+
+        let num_backbuffers = config.image_count.max(1).min(MAX_BACKBUFFERS);
+        let backbuffers = (0 .. num_backbuffers)
+            .map(|_| core::Backbuffer {
+                color: n::Image::new_owned(kind, 1, config.color_format, usage),
+                depth_stencil: config.depth_stencil_format.map(|format| {
+                    n::Image::new_owned(kind, 1, format, image::DEPTH_STENCIL_ATTACHMENT)
+                }),
+            })
+            .collect();
+
+        Swapchain {
+            backbuffers,
+            next_frame: 0,
+        }
+    }
+}
+
+pub struct Swapchain {
+    backbuffers: Vec<core::Backbuffer<Backend>>,
+    next_frame: usize,
+}
+
+impl core::Swapchain<Backend> for Swapchain {
+    fn get_backbuffers(&mut self) -> &[core::Backbuffer<Backend>] {
+        &self.backbuffers
+    }
+
+    fn acquire_frame(&mut self, sync: core::FrameSync<Backend>) -> Result<core::Frame, core::AcquireError> {
+        // Submissions are executed on submit, backbuffers are always available.
+        if let core::FrameSync::Fence(fence) = sync {
+            fence.set(true);
+        }
+
+        let frame = self.next_frame;
+        self.next_frame = (frame + 1) % self.backbuffers.len();
+        Ok(core::Frame::new(frame))
+    }
+
+    fn present<C>(
+        &mut self,
+        _: &mut core::CommandQueue<Backend, C>,
+        _: &[&n::Semaphore],
+    ) -> Result<core::Suboptimal, core::PresentError> {
+        // Nothing to show, the backbuffer contents can be read back with a copy.
+        Ok(false)
+    }
+}
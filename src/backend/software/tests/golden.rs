@@ -0,0 +1,344 @@
+//! Golden image tests of render passes, rendered into a 4x4 target.
+
+extern crate gfx_core as core;
+extern crate gfx_backend_software as software;
+
+use core::{buffer, image, pass, pso, target, Adapter, Device, Primitive, QueueFamily};
+use core::command::{BufferImageCopy, ClearColor, ClearValue, Offset};
+use core::device::{Extent, ResourceHeapType};
+use core::format::{ChannelType, Format, SurfaceType};
+use core::pool::CommandPoolCreateFlags;
+use core::queue::{Graphics, Submission};
+use core::state as s;
+use software::{Backend, ShaderLib, VertexOutput};
+
+const SIZE: u16 = 4;
+const FORMAT: Format = Format(SurfaceType::R8_G8_B8_A8, ChannelType::Unorm);
+const AREA: target::Rect = target::Rect { x: 0, y: 0, w: SIZE, h: SIZE };
+
+// Two triangles covering the whole clip space.
+const QUAD: [[f32; 2]; 6] = [
+    [-1.0, -1.0], [1.0, -1.0], [-1.0, 1.0],
+    [-1.0, 1.0], [1.0, -1.0], [1.0, 1.0],
+];
+
+struct Harness {
+    device: software::Device,
+    queue: core::CommandQueue<Backend, Graphics>,
+    image: <Backend as core::Backend>::Image,
+    rtv: <Backend as core::Backend>::RenderTargetView,
+    readback: <Backend as core::Backend>::Buffer,
+    vertices: <Backend as core::Backend>::Buffer,
+    indirect: <Backend as core::Backend>::Buffer,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let adapter = software::Adapter::new();
+        let &(ref family, qtype) = &adapter.get_queue_families()[0];
+        let core::Gpu { mut device, mut graphics_queues, heap_types, .. } =
+            adapter.open(&[(family, qtype, family.num_queues())]);
+
+        let heap = device.create_heap(&heap_types[0], ResourceHeapType::Any, 1024).unwrap();
+        let kind = image::Kind::D2(SIZE, SIZE, image::AaMode::Single);
+        let unbound = device.create_image(kind, 1, FORMAT, image::COLOR_ATTACHMENT | image::TRANSFER_SRC).unwrap();
+        let image = device.bind_image_memory(&heap, 0, unbound).unwrap();
+        let rtv = device.view_image_as_render_target(&image, FORMAT, (0 .. 1, 0 .. 1)).unwrap();
+
+        let size = SIZE as u64 * SIZE as u64 * 4;
+        let unbound = device.create_buffer(size, 0, buffer::TRANSFER_DST).unwrap();
+        let readback = device.bind_buffer_memory(&heap, 256, unbound).unwrap();
+
+        let unbound = device.create_buffer(48, 0, buffer::VERTEX).unwrap();
+        let vertices = device.bind_buffer_memory(&heap, 512, unbound).unwrap();
+        {
+            let mut writer = device.write_mapping::<[f32; 2]>(&vertices, 0, 48).unwrap();
+            writer.copy_from_slice(&QUAD);
+        }
+
+        let unbound = device.create_buffer(32, 0, buffer::INDIRECT).unwrap();
+        let indirect = device.bind_buffer_memory(&heap, 768, unbound).unwrap();
+
+        Harness {
+            device,
+            queue: graphics_queues.remove(0),
+            image,
+            rtv,
+            readback,
+            vertices,
+            indirect,
+        }
+    }
+
+    fn render_pass(&mut self, ops: pass::AttachmentOps) -> (<Backend as core::Backend>::RenderPass, <Backend as core::Backend>::FrameBuffer) {
+        let attachment = pass::Attachment {
+            format: FORMAT,
+            ops,
+            stencil_ops: pass::AttachmentOps::DONT_CARE,
+            layouts: image::ImageLayout::Undefined .. image::ImageLayout::Present,
+        };
+        let subpass = pass::SubpassDesc {
+            color_attachments: &[(0, image::ImageLayout::ColorAttachmentOptimal)],
+        };
+        let render_pass = self.device.create_renderpass(&[attachment], &[subpass], &[]);
+        let extent = Extent { width: SIZE as u32, height: SIZE as u32, depth: 1 };
+        let frame_buffer = self.device.create_framebuffer(&render_pass, &[&self.rtv], &[], extent);
+        (render_pass, frame_buffer)
+    }
+
+    fn pipeline(
+        &mut self,
+        render_pass: &<Backend as core::Backend>::RenderPass,
+        color: [f32; 4],
+        target: pso::ColorInfo,
+    ) -> <Backend as core::Backend>::GraphicsPipeline {
+        let shaders = ShaderLib::new()
+            .with_vertex("vs", |input| VertexOutput {
+                position: [input.attributes[0][0], input.attributes[0][1], 0.0, 1.0],
+                varyings: Vec::new(),
+            })
+            .with_pixel("ps", move |_| color);
+        let layout = self.device.create_pipeline_layout(&[]);
+        let mut desc = pso::GraphicsPipelineDesc::new(
+            Primitive::TriangleList,
+            pso::Rasterizer::new_fill(),
+            pso::GraphicsShaderSet {
+                vertex_shader: "vs",
+                hull_shader: None,
+                domain_shader: None,
+                geometry_shader: None,
+                pixel_shader: Some("ps"),
+            },
+        );
+        desc.vertex_buffers.push(pso::VertexBufferDesc { stride: 8, rate: 0 });
+        desc.attributes.push(pso::AttributeDesc {
+            location: 0,
+            binding: 0,
+            element: pso::Element {
+                format: Format(SurfaceType::R32_G32, ChannelType::Float),
+                offset: 0,
+            },
+        });
+        desc.blender.targets.push(target);
+
+        let subpass = pass::Subpass { index: 0, main_pass: render_pass };
+        self.device
+            .create_graphics_pipelines(&[(&shaders, &layout, subpass, &desc)], None)
+            .remove(0)
+            .unwrap()
+    }
+
+    // Record commands with the given closure, submit them and read the image back.
+    fn run<F>(&mut self, record: F) -> Vec<u8>
+    where
+        F: FnOnce(&mut core::command::CommandBuffer<Backend, Graphics>, &Harness),
+    {
+        let mut pool = self.queue.create_graphics_pool(1, CommandPoolCreateFlags::empty());
+        let submit = {
+            let mut cmd_buffer = pool.acquire_command_buffer();
+            record(&mut cmd_buffer, self);
+            cmd_buffer.copy_image_to_buffer(
+                &self.image,
+                image::ImageLayout::TransferSrcOptimal,
+                &self.readback,
+                &[BufferImageCopy {
+                    buffer_offset: 0,
+                    buffer_row_pitch: 0,
+                    buffer_slice_pitch: 0,
+                    image_aspect: image::ASPECT_COLOR,
+                    image_subresource: (0, 0 .. 1),
+                    image_offset: Offset { x: 0, y: 0, z: 0 },
+                    image_extent: Extent { width: SIZE as u32, height: SIZE as u32, depth: 1 },
+                }],
+            );
+            cmd_buffer.finish()
+        };
+
+        let fence = self.device.create_fence(false);
+        self.queue.submit(Submission::new().submit(&[submit]), Some(&fence));
+        assert!(self.device.wait_for_fences(&[&fence], core::device::WaitFor::All, 0));
+
+        let reader = self.device.read_mapping::<u8>(&self.readback, 0, self.readback_size()).unwrap();
+        reader.to_vec()
+    }
+
+    fn readback_size(&self) -> u64 {
+        SIZE as u64 * SIZE as u64 * 4
+    }
+}
+
+// Build the expected image from a textual description, one character per pixel.
+fn golden(rows: &[&str]) -> Vec<u8> {
+    rows.iter()
+        .flat_map(|row| row.chars())
+        .flat_map(|pixel| match pixel {
+            'r' => vec![255, 0, 0, 255],
+            'g' => vec![0, 255, 0, 255],
+            'b' => vec![0, 0, 255, 255],
+            'w' => vec![255, 255, 255, 255],
+            'p' => vec![128, 0, 128, 255],
+            'm' => vec![255, 0, 255, 255], // poison
+            other => panic!("Unknown pixel {:?}", other),
+        })
+        .collect()
+}
+
+fn clear(cmd_buffer: &mut core::command::CommandBuffer<Backend, Graphics>, h: &Harness, color: [f32; 4]) {
+    cmd_buffer.clear_color(&h.rtv, image::ImageLayout::TransferDstOptimal, ClearColor::Float(color));
+}
+
+#[test]
+fn load_op_clear() {
+    let mut h = Harness::new();
+    let (render_pass, frame_buffer) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Clear, pass::AttachmentStoreOp::Store)
+    );
+    let pixels = h.run(|cmd_buffer, h| {
+        clear(cmd_buffer, h, [0.0, 1.0, 0.0, 1.0]);
+        let area = target::Rect { x: 0, y: 0, w: 2, h: SIZE };
+        let _ = cmd_buffer.begin_renderpass_inline(
+            &render_pass, &frame_buffer, area,
+            &[ClearValue::Color(ClearColor::Float([1.0, 0.0, 0.0, 1.0]))],
+        );
+    });
+    assert_eq!(pixels, golden(&[
+        "rrgg",
+        "rrgg",
+        "rrgg",
+        "rrgg",
+    ]));
+}
+
+#[test]
+fn load_op_load_and_dont_care() {
+    let mut h = Harness::new();
+    let (load_pass, load_fb) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Load, pass::AttachmentStoreOp::Store)
+    );
+    let (undefined_pass, undefined_fb) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::DontCare, pass::AttachmentStoreOp::Store)
+    );
+    let pixels = h.run(|cmd_buffer, h| {
+        clear(cmd_buffer, h, [0.0, 0.0, 1.0, 1.0]);
+        let _ = cmd_buffer.begin_renderpass_inline(&load_pass, &load_fb, AREA, &[]);
+    });
+    assert_eq!(pixels, golden(&["bbbb"; 4]));
+
+    let pixels = h.run(|cmd_buffer, h| {
+        clear(cmd_buffer, h, [0.0, 0.0, 1.0, 1.0]);
+        let area = target::Rect { x: 1, y: 1, w: 2, h: 2 };
+        let _ = cmd_buffer.begin_renderpass_inline(&undefined_pass, &undefined_fb, area, &[]);
+    });
+    assert_eq!(pixels, golden(&[
+        "bbbb",
+        "bmmb",
+        "bmmb",
+        "bbbb",
+    ]));
+}
+
+#[test]
+fn store_op_dont_care() {
+    let mut h = Harness::new();
+    let (render_pass, frame_buffer) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Clear, pass::AttachmentStoreOp::DontCare)
+    );
+    let pixels = h.run(|cmd_buffer, h| {
+        clear(cmd_buffer, h, [0.0, 1.0, 0.0, 1.0]);
+        let area = target::Rect { x: 0, y: 2, w: SIZE, h: 2 };
+        let _ = cmd_buffer.begin_renderpass_inline(
+            &render_pass, &frame_buffer, area,
+            &[ClearValue::Color(ClearColor::Float([1.0, 0.0, 0.0, 1.0]))],
+        );
+    });
+    assert_eq!(pixels, golden(&[
+        "gggg",
+        "gggg",
+        "mmmm",
+        "mmmm",
+    ]));
+}
+
+#[test]
+fn viewport_and_scissor() {
+    let mut h = Harness::new();
+    let (render_pass, frame_buffer) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Clear, pass::AttachmentStoreOp::Store)
+    );
+    let pipeline = h.pipeline(&render_pass, [1.0, 1.0, 1.0, 1.0], s::MASK_ALL.into());
+    let pixels = h.run(|cmd_buffer, h| {
+        cmd_buffer.set_viewports(&[core::Viewport { x: 1, y: 0, w: 3, h: 4, near: 0.0, far: 1.0 }]);
+        cmd_buffer.set_scissors(&[target::Rect { x: 0, y: 1, w: 3, h: 2 }]);
+        cmd_buffer.bind_graphics_pipeline(&pipeline);
+        cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(vec![(&h.vertices, 0)]));
+        let mut encoder = cmd_buffer.begin_renderpass_inline(
+            &render_pass, &frame_buffer, AREA,
+            &[ClearValue::Color(ClearColor::Float([0.0, 0.0, 1.0, 1.0]))],
+        );
+        encoder.draw(0 .. 6, 0 .. 1);
+    });
+    assert_eq!(pixels, golden(&[
+        "bbbb",
+        "bwwb",
+        "bwwb",
+        "bbbb",
+    ]));
+}
+
+#[test]
+fn alpha_blending() {
+    let mut h = Harness::new();
+    let (render_pass, frame_buffer) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Load, pass::AttachmentStoreOp::Store)
+    );
+    let blend = s::BlendChannel {
+        equation: s::Equation::Add,
+        source: s::Factor::ZeroPlus(s::BlendValue::SourceAlpha),
+        destination: s::Factor::OneMinus(s::BlendValue::SourceAlpha),
+    };
+    let target = pso::ColorInfo {
+        mask: s::RED | s::GREEN | s::BLUE,
+        color: Some(blend),
+        alpha: Some(blend),
+    };
+    let pipeline = h.pipeline(&render_pass, [1.0, 0.0, 0.0, 0.5], target);
+    let pixels = h.run(|cmd_buffer, h| {
+        clear(cmd_buffer, h, [0.0, 0.0, 1.0, 1.0]);
+        cmd_buffer.bind_graphics_pipeline(&pipeline);
+        cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(vec![(&h.vertices, 0)]));
+        cmd_buffer.set_scissors(&[target::Rect { x: 0, y: 0, w: 2, h: SIZE }]);
+        let mut encoder = cmd_buffer.begin_renderpass_inline(&render_pass, &frame_buffer, AREA, &[]);
+        encoder.draw(0 .. 6, 0 .. 1);
+    });
+    assert_eq!(pixels, golden(&["ppbb"; 4]));
+}
+
+#[test]
+fn draw_indirect() {
+    let mut h = Harness::new();
+    let (render_pass, frame_buffer) = h.render_pass(
+        pass::AttachmentOps::new(pass::AttachmentLoadOp::Clear, pass::AttachmentStoreOp::Store)
+    );
+    let pipeline = h.pipeline(&render_pass, [1.0, 1.0, 1.0, 1.0], s::MASK_ALL.into());
+    {
+        // One draw for each triangle of the quad.
+        let mut writer = h.device.write_mapping::<[u32; 4]>(&h.indirect, 0, 32).unwrap();
+        writer.copy_from_slice(&[[3, 1, 0, 0], [3, 1, 3, 0]]);
+    }
+    let pixels = h.run(|cmd_buffer, h| {
+        cmd_buffer.set_scissors(&[target::Rect { x: 1, y: 1, w: 2, h: 2 }]);
+        cmd_buffer.bind_graphics_pipeline(&pipeline);
+        cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(vec![(&h.vertices, 0)]));
+        let mut encoder = cmd_buffer.begin_renderpass_inline(
+            &render_pass, &frame_buffer, AREA,
+            &[ClearValue::Color(ClearColor::Float([0.0, 0.0, 1.0, 1.0]))],
+        );
+        encoder.draw_indirect(&h.indirect, 0, 2, 16);
+    });
+    assert_eq!(pixels, golden(&[
+        "bbbb",
+        "bwwb",
+        "bwwb",
+        "bbbb",
+    ]));
+}
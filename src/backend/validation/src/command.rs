@@ -0,0 +1,383 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::sync::Arc;
+use core::{self, buffer, command, image, memory, pso, query, target};
+use core::{IndexCount, InstanceCount, VertexCount, VertexOffset, Viewport};
+use core::image::ImageLayout;
+use {Buffer, Error, Id, Image, Shared, Validation};
+
+#[derive(Clone)]
+pub struct CommandBuffer<B: core::Backend> {
+    pub(crate) raw: B::CommandBuffer,
+    shared: Arc<Shared>,
+    index_buffer: bool,
+    /// Buffers and images used by the recorded commands.
+    pub(crate) resources: HashSet<Id>,
+    /// Layout expected by the first barrier and the current layout of
+    /// each image transitioned by the recorded commands.
+    pub(crate) layouts: HashMap<Id, (ImageLayout, ImageLayout)>,
+}
+
+impl<B: core::Backend> CommandBuffer<B> {
+    pub(crate) fn new(raw: B::CommandBuffer, shared: Arc<Shared>) -> Self {
+        CommandBuffer {
+            raw,
+            shared,
+            index_buffer: false,
+            resources: HashSet::new(),
+            layouts: HashMap::new(),
+        }
+    }
+
+    /// Access the command buffer of the wrapped backend.
+    pub fn raw(&self) -> &B::CommandBuffer {
+        &self.raw
+    }
+
+    fn clear(&mut self) {
+        self.index_buffer = false;
+        self.resources.clear();
+        self.layouts.clear();
+    }
+
+    fn use_buffer(&mut self, buffer: &Buffer<B>, required: buffer::Usage) {
+        if !buffer.usage.contains(required) {
+            self.shared.report(Error::MissingUsage {
+                buffer: buffer.id,
+                required,
+                usage: buffer.usage,
+            });
+        }
+        self.resources.insert(buffer.id);
+    }
+
+    fn check_range(&self, buffer: &Buffer<B>, range: Range<u64>) {
+        if range.start > range.end || range.end > buffer.size {
+            self.shared.report(Error::OutOfBounds {
+                buffer: buffer.id,
+                range,
+                size: buffer.size,
+            });
+        }
+    }
+
+    fn use_image(&mut self, image: &Image<B>) {
+        self.resources.insert(image.id);
+    }
+
+    fn transition(&mut self, image: &Image<B>, layouts: Range<ImageLayout>) {
+        self.use_image(image);
+        let mismatch = match self.layouts.get_mut(&image.id) {
+            Some(&mut (_, ref mut current)) => {
+                let expected = *current;
+                *current = layouts.end;
+                if layouts.start != ImageLayout::Undefined && layouts.start != expected {
+                    Some(expected)
+                } else {
+                    None
+                }
+            }
+            None => {
+                // Checked against the global layout on submission.
+                self.layouts.insert(image.id, (layouts.start, layouts.end));
+                None
+            }
+        };
+
+        if let Some(expected) = mismatch {
+            self.shared.report(Error::LayoutMismatch {
+                image: image.id,
+                expected,
+                found: layouts.start,
+            });
+        }
+    }
+}
+
+impl<B: core::Backend> core::RawCommandBuffer<Validation<B>> for CommandBuffer<B> {
+    fn begin(&mut self) {
+        self.clear();
+        self.raw.begin()
+    }
+
+    fn finish(&mut self) {
+        self.raw.finish()
+    }
+
+    fn reset(&mut self, release_resources: bool) {
+        self.clear();
+        self.raw.reset(release_resources)
+    }
+
+    fn pipeline_barrier(
+        &mut self,
+        stages: Range<pso::PipelineStage>,
+        barriers: &[memory::Barrier<Validation<B>>],
+    ) {
+        let barriers = barriers
+            .iter()
+            .map(|barrier| match *barrier {
+                memory::Barrier::AllBuffers(ref access) => memory::Barrier::AllBuffers(access.clone()),
+                memory::Barrier::AllImages(ref access) => memory::Barrier::AllImages(access.clone()),
+                memory::Barrier::Buffer { ref states, target, ref range } => {
+                    self.resources.insert(target.id);
+                    memory::Barrier::Buffer {
+                        states: states.clone(),
+                        target: &target.raw,
+                        range: range.clone(),
+                    }
+                }
+                memory::Barrier::Image { ref states, target, ref range } => {
+                    self.transition(target, states.start.1 .. states.end.1);
+                    memory::Barrier::Image {
+                        states: states.clone(),
+                        target: target.raw(),
+                        range: range.clone(),
+                    }
+                }
+            })
+            .collect::<Vec<_>>();
+
+        self.raw.pipeline_barrier(stages, &barriers)
+    }
+
+    fn fill_buffer(&mut self, buffer: &Buffer<B>, range: Range<u64>, data: u32) {
+        self.use_buffer(buffer, buffer::TRANSFER_DST);
+        self.check_range(buffer, range.clone());
+        self.raw.fill_buffer(&buffer.raw, range, data)
+    }
+
+    fn update_buffer(&mut self, buffer: &Buffer<B>, offset: u64, data: &[u8]) {
+        self.use_buffer(buffer, buffer::TRANSFER_DST);
+        self.check_range(buffer, offset .. offset + data.len() as u64);
+        self.raw.update_buffer(&buffer.raw, offset, data)
+    }
+
+    fn clear_color(&mut self, rtv: &B::RenderTargetView, layout: ImageLayout, color: command::ClearColor) {
+        self.raw.clear_color(rtv, layout, color)
+    }
+
+    fn clear_depth_stencil(
+        &mut self,
+        dsv: &B::DepthStencilView,
+        layout: ImageLayout,
+        depth: Option<target::Depth>,
+        stencil: Option<target::Stencil>,
+    ) {
+        self.raw.clear_depth_stencil(dsv, layout, depth, stencil)
+    }
+
+    fn resolve_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageResolve],
+    ) {
+        self.use_image(src);
+        self.use_image(dst);
+        self.raw.resolve_image(src.raw(), src_layout, dst.raw(), dst_layout, regions)
+    }
+
+    fn blit_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        self.use_image(src);
+        self.use_image(dst);
+        self.raw.blit_image(src.raw(), src_layout, dst.raw(), dst_layout, regions, filter)
+    }
+
+    fn bind_index_buffer(&mut self, ibv: buffer::IndexBufferView<Validation<B>>) {
+        self.use_buffer(ibv.buffer, buffer::INDEX);
+        self.index_buffer = true;
+        self.raw.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &ibv.buffer.raw,
+            offset: ibv.offset,
+            index_type: ibv.index_type,
+        })
+    }
+
+    fn bind_vertex_buffers(&mut self, vbs: pso::VertexBufferSet<Validation<B>>) {
+        for &(buffer, _) in &vbs.0 {
+            self.use_buffer(buffer, buffer::VERTEX);
+        }
+        let buffers = vbs.0
+            .iter()
+            .map(|&(buffer, offset)| (&buffer.raw, offset))
+            .collect();
+        self.raw.bind_vertex_buffers(pso::VertexBufferSet(buffers))
+    }
+
+    fn set_viewports(&mut self, viewports: &[Viewport]) {
+        self.raw.set_viewports(viewports)
+    }
+
+    fn set_scissors(&mut self, scissors: &[target::Rect]) {
+        self.raw.set_scissors(scissors)
+    }
+
+    fn set_stencil_reference(&mut self, front: target::Stencil, back: target::Stencil) {
+        self.raw.set_stencil_reference(front, back)
+    }
+
+    fn set_blend_constants(&mut self, color: target::ColorValue) {
+        self.raw.set_blend_constants(color)
+    }
+
+    fn begin_renderpass(
+        &mut self,
+        render_pass: &B::RenderPass,
+        frame_buffer: &B::FrameBuffer,
+        render_area: target::Rect,
+        clear_values: &[command::ClearValue],
+        first_subpass: command::SubpassContents,
+    ) {
+        self.raw.begin_renderpass(render_pass, frame_buffer, render_area, clear_values, first_subpass)
+    }
+
+    fn next_subpass(&mut self, contents: command::SubpassContents) {
+        self.raw.next_subpass(contents)
+    }
+
+    fn end_renderpass(&mut self) {
+        self.raw.end_renderpass()
+    }
+
+    fn bind_graphics_pipeline(&mut self, pipeline: &B::GraphicsPipeline) {
+        self.raw.bind_graphics_pipeline(pipeline)
+    }
+
+    fn bind_graphics_descriptor_sets(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: &[&B::DescriptorSet],
+    ) {
+        self.raw.bind_graphics_descriptor_sets(layout, first_set, sets)
+    }
+
+    fn bind_compute_pipeline(&mut self, pipeline: &B::ComputePipeline) {
+        self.raw.bind_compute_pipeline(pipeline)
+    }
+
+    fn dispatch(&mut self, x: u32, y: u32, z: u32) {
+        self.raw.dispatch(x, y, z)
+    }
+
+    fn dispatch_indirect(&mut self, buffer: &Buffer<B>, offset: u64) {
+        self.use_buffer(buffer, buffer::INDIRECT);
+        self.raw.dispatch_indirect(&buffer.raw, offset)
+    }
+
+    fn copy_buffer(&mut self, src: &Buffer<B>, dst: &Buffer<B>, regions: &[command::BufferCopy]) {
+        self.use_buffer(src, buffer::TRANSFER_SRC);
+        self.use_buffer(dst, buffer::TRANSFER_DST);
+        for region in regions {
+            self.check_range(src, region.src .. region.src + region.size);
+            self.check_range(dst, region.dst .. region.dst + region.size);
+        }
+        self.raw.copy_buffer(&src.raw, &dst.raw, regions)
+    }
+
+    fn copy_image(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::ImageCopy],
+    ) {
+        self.use_image(src);
+        self.use_image(dst);
+        self.raw.copy_image(src.raw(), src_layout, dst.raw(), dst_layout, regions)
+    }
+
+    fn copy_buffer_to_image(
+        &mut self,
+        src: &Buffer<B>,
+        dst: &Image<B>,
+        dst_layout: ImageLayout,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.use_buffer(src, buffer::TRANSFER_SRC);
+        self.use_image(dst);
+        self.raw.copy_buffer_to_image(&src.raw, dst.raw(), dst_layout, regions)
+    }
+
+    fn copy_image_to_buffer(
+        &mut self,
+        src: &Image<B>,
+        src_layout: ImageLayout,
+        dst: &Buffer<B>,
+        regions: &[command::BufferImageCopy],
+    ) {
+        self.use_image(src);
+        self.use_buffer(dst, buffer::TRANSFER_DST);
+        self.raw.copy_image_to_buffer(src.raw(), src_layout, &dst.raw, regions)
+    }
+
+    fn draw(&mut self, vertices: Range<VertexCount>, instances: Range<InstanceCount>) {
+        self.raw.draw(vertices, instances)
+    }
+
+    fn draw_indexed(
+        &mut self,
+        indices: Range<IndexCount>,
+        base_vertex: VertexOffset,
+        instances: Range<InstanceCount>,
+    ) {
+        if !self.index_buffer {
+            self.shared.report(Error::NoIndexBuffer);
+        }
+        self.raw.draw_indexed(indices, base_vertex, instances)
+    }
+
+    fn draw_indirect(&mut self, buffer: &Buffer<B>, offset: u64, draw_count: u32, stride: u32) {
+        self.use_buffer(buffer, buffer::INDIRECT);
+        self.raw.draw_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    fn draw_indexed_indirect(&mut self, buffer: &Buffer<B>, offset: u64, draw_count: u32, stride: u32) {
+        if !self.index_buffer {
+            self.shared.report(Error::NoIndexBuffer);
+        }
+        self.use_buffer(buffer, buffer::INDIRECT);
+        self.raw.draw_indexed_indirect(&buffer.raw, offset, draw_count, stride)
+    }
+
+    fn begin_query(&mut self, query: query::Query<Validation<B>>, flags: query::QueryControl) {
+        self.raw.begin_query(query::Query { pool: query.pool, id: query.id }, flags)
+    }
+
+    fn end_query(&mut self, query: query::Query<Validation<B>>) {
+        self.raw.end_query(query::Query { pool: query.pool, id: query.id })
+    }
+
+    fn reset_query_pool(&mut self, pool: &B::QueryPool, queries: Range<query::QueryId>) {
+        self.raw.reset_query_pool(pool, queries)
+    }
+
+    fn write_timestamp(&mut self, stage: pso::PipelineStage, query: query::Query<Validation<B>>) {
+        self.raw.write_timestamp(stage, query::Query { pool: query.pool, id: query.id })
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: &Buffer<B>,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    ) {
+        self.use_buffer(buffer, buffer::TRANSFER_DST);
+        self.raw.copy_query_pool_results(pool, queries, &buffer.raw, offset, stride, flags)
+    }
+}
@@ -0,0 +1,373 @@
+use std::ops::Range;
+use std::sync::Arc;
+use core::{self, buffer, device, format, image, mapping, pass, pso, query};
+use core::memory::Requirements;
+use {next_id, Buffer, DescriptorPool, Error, Fence, Id, Shared, UnboundBuffer, Validation};
+use native::{Image, RawImage};
+
+pub struct Device<B: core::Backend> {
+    raw: B::Device,
+    shared: Arc<Shared>,
+}
+
+impl<B: core::Backend> Device<B> {
+    pub(crate) fn new(raw: B::Device, shared: Arc<Shared>) -> Self {
+        Device { raw, shared }
+    }
+
+    /// Access the device of the wrapped backend.
+    pub fn raw(&self) -> &B::Device {
+        &self.raw
+    }
+
+    /// Mutably access the device of the wrapped backend.
+    pub fn raw_mut(&mut self) -> &mut B::Device {
+        &mut self.raw
+    }
+
+    /// Report an error if `id` is still used by a pending submission.
+    fn check_idle(&self, id: Id) -> bool {
+        let pending = self.shared.lock().is_pending(id);
+        if pending {
+            self.shared.report(Error::InUse(id));
+        }
+        !pending
+    }
+}
+
+impl<B: core::Backend> core::Device<Validation<B>> for Device<B> {
+    fn get_features(&self) -> &core::Features {
+        self.raw.get_features()
+    }
+
+    fn get_limits(&self) -> &core::Limits {
+        self.raw.get_limits()
+    }
+
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties {
+        self.raw.get_format_properties(format)
+    }
+
+    fn create_heap(&mut self, heap_type: &core::HeapType, resource_type: device::ResourceHeapType, size: u64) -> Result<B::Heap, device::ResourceHeapError> {
+        self.raw.create_heap(heap_type, resource_type, size)
+    }
+
+    fn create_shader_library(&mut self, shaders: &[(pso::EntryPoint, &[u8])]) -> Result<B::ShaderLib, pso::CreateShaderError> {
+        self.raw.create_shader_library(shaders)
+    }
+
+    fn create_renderpass(&mut self, attachments: &[pass::Attachment], subpasses: &[pass::SubpassDesc], dependencies: &[pass::SubpassDependency]) -> B::RenderPass {
+        self.raw.create_renderpass(attachments, subpasses, dependencies)
+    }
+
+    fn create_pipeline_layout(&mut self, sets: &[&B::DescriptorSetLayout]) -> B::PipelineLayout {
+        self.raw.create_pipeline_layout(sets)
+    }
+
+    fn create_pipeline_cache(&mut self, initial_data: &[u8]) -> B::PipelineCache {
+        self.raw.create_pipeline_cache(initial_data)
+    }
+
+    fn get_pipeline_cache_data(&mut self, cache: &B::PipelineCache) -> Vec<u8> {
+        self.raw.get_pipeline_cache_data(cache)
+    }
+
+    fn merge_pipeline_caches(&mut self, target: &B::PipelineCache, sources: &[&B::PipelineCache]) {
+        self.raw.merge_pipeline_caches(target, sources)
+    }
+
+    fn create_graphics_pipelines<'a>(
+        &mut self,
+        descs: &[(&B::ShaderLib, &B::PipelineLayout, pass::Subpass<'a, Validation<B>>, &pso::GraphicsPipelineDesc)],
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<B::GraphicsPipeline, pso::CreationError>> {
+        let descs = descs
+            .iter()
+            .map(|&(shader_lib, layout, ref subpass, desc)| {
+                let subpass = pass::Subpass { index: subpass.index, main_pass: subpass.main_pass };
+                (shader_lib, layout, subpass, desc)
+            })
+            .collect::<Vec<_>>();
+        self.raw.create_graphics_pipelines(&descs, cache)
+    }
+
+    fn create_compute_pipelines(
+        &mut self,
+        descs: &[(&B::ShaderLib, pso::EntryPoint, &B::PipelineLayout)],
+        cache: Option<&B::PipelineCache>,
+    ) -> Vec<Result<B::ComputePipeline, pso::CreationError>> {
+        self.raw.create_compute_pipelines(descs, cache)
+    }
+
+    fn create_framebuffer(
+        &mut self,
+        renderpass: &B::RenderPass,
+        color_attachments: &[&B::RenderTargetView],
+        depth_stencil_attachments: &[&B::DepthStencilView],
+        extent: device::Extent,
+    ) -> B::FrameBuffer {
+        self.raw.create_framebuffer(renderpass, color_attachments, depth_stencil_attachments, extent)
+    }
+
+    fn create_sampler(&mut self, info: image::SamplerInfo) -> B::Sampler {
+        self.raw.create_sampler(info)
+    }
+
+    fn create_buffer(&mut self, size: u64, stride: u64, usage: buffer::Usage) -> Result<UnboundBuffer<B>, buffer::CreationError> {
+        let raw = self.raw.create_buffer(size, stride, usage)?;
+        Ok(UnboundBuffer { raw, size, usage })
+    }
+
+    fn get_buffer_requirements(&mut self, buffer: &UnboundBuffer<B>) -> Requirements {
+        self.raw.get_buffer_requirements(&buffer.raw)
+    }
+
+    fn bind_buffer_memory(&mut self, heap: &B::Heap, offset: u64, buffer: UnboundBuffer<B>) -> Result<Buffer<B>, buffer::CreationError> {
+        let raw = self.raw.bind_buffer_memory(heap, offset, buffer.raw)?;
+        Ok(Buffer {
+            raw,
+            id: next_id(),
+            size: buffer.size,
+            usage: buffer.usage,
+        })
+    }
+
+    fn create_image(&mut self, kind: image::Kind, mip_levels: image::Level, format: format::Format, usage: image::Usage)
+         -> Result<B::UnboundImage, image::CreationError>
+    {
+        self.raw.create_image(kind, mip_levels, format, usage)
+    }
+
+    fn get_image_requirements(&mut self, image: &B::UnboundImage) -> Requirements {
+        self.raw.get_image_requirements(image)
+    }
+
+    fn bind_image_memory(&mut self, heap: &B::Heap, offset: u64, image: B::UnboundImage) -> Result<Image<B>, image::CreationError> {
+        let raw = self.raw.bind_image_memory(heap, offset, image)?;
+        let id = next_id();
+        self.shared.lock().layouts.insert(id, image::ImageLayout::Undefined);
+        Ok(Image { raw: RawImage::Owned(raw), id })
+    }
+
+    fn view_buffer_as_constant(&mut self, buffer: &Buffer<B>, range: Range<u64>) -> Result<B::ConstantBufferView, device::TargetViewError> {
+        if !buffer.usage.contains(buffer::CONSTANT) {
+            self.shared.report(Error::MissingUsage {
+                buffer: buffer.id,
+                required: buffer::CONSTANT,
+                usage: buffer.usage,
+            });
+        }
+        if range.start > range.end || range.end > buffer.size {
+            self.shared.report(Error::OutOfBounds {
+                buffer: buffer.id,
+                range: range.clone(),
+                size: buffer.size,
+            });
+        }
+        self.raw.view_buffer_as_constant(&buffer.raw, range)
+    }
+
+    fn view_image_as_render_target(&mut self, image: &Image<B>, format: format::Format, range: image::SubresourceRange) -> Result<B::RenderTargetView, device::TargetViewError> {
+        self.raw.view_image_as_render_target(image.raw(), format, range)
+    }
+
+    fn view_image_as_shader_resource(&mut self, image: &Image<B>, format: format::Format) -> Result<B::ShaderResourceView, device::TargetViewError> {
+        self.raw.view_image_as_shader_resource(image.raw(), format)
+    }
+
+    fn view_image_as_unordered_access(&mut self, image: &Image<B>, format: format::Format) -> Result<B::UnorderedAccessView, device::TargetViewError> {
+        self.raw.view_image_as_unordered_access(image.raw(), format)
+    }
+
+    fn create_descriptor_pool(&mut self, max_sets: usize, descriptor_ranges: &[pso::DescriptorRangeDesc]) -> DescriptorPool<B> {
+        DescriptorPool {
+            raw: self.raw.create_descriptor_pool(max_sets, descriptor_ranges),
+        }
+    }
+
+    fn create_descriptor_set_layout(&mut self, bindings: &[pso::DescriptorSetLayoutBinding]) -> B::DescriptorSetLayout {
+        self.raw.create_descriptor_set_layout(bindings)
+    }
+
+    fn update_descriptor_sets(&mut self, writes: &[pso::DescriptorSetWrite<Validation<B>>]) {
+        use core::pso::DescriptorWrite as Dw;
+
+        let writes = writes
+            .iter()
+            .map(|write| pso::DescriptorSetWrite {
+                set: write.set,
+                binding: write.binding,
+                array_offset: write.array_offset,
+                write: match write.write {
+                    Dw::Sampler(ref samplers) => Dw::Sampler(samplers.clone()),
+                    Dw::SampledImage(ref images) => Dw::SampledImage(images.clone()),
+                    Dw::StorageImage(ref images) => Dw::StorageImage(images.clone()),
+                    Dw::UniformTexelBuffer => Dw::UniformTexelBuffer,
+                    Dw::StorageTexelBuffer => Dw::StorageTexelBuffer,
+                    Dw::ConstantBuffer(ref cbvs) => Dw::ConstantBuffer(cbvs.clone()),
+                    Dw::StorageBuffer => Dw::StorageBuffer,
+                    Dw::InputAttachment(ref images) => Dw::InputAttachment(images.clone()),
+                },
+            })
+            .collect::<Vec<_>>();
+        self.raw.update_descriptor_sets(&writes)
+    }
+
+    fn read_mapping<'a, T>(&self, buffer: &'a Buffer<B>, offset: u64, size: u64)
+                    -> Result<mapping::Reader<'a, Validation<B>, T>, mapping::Error>
+        where T: Copy
+    {
+        let reader = self.raw.read_mapping::<T>(&buffer.raw, offset, size)?;
+        unsafe {
+            let (slice, mapping) = reader.into_parts();
+            Ok(mapping::Reader::new(slice, mapping))
+        }
+    }
+
+    fn write_mapping<'a, T>(&mut self, buffer: &'a Buffer<B>, offset: u64, size: u64)
+                     -> Result<mapping::Writer<'a, Validation<B>, T>, mapping::Error>
+        where T: Copy
+    {
+        let writer = self.raw.write_mapping::<T>(&buffer.raw, offset, size)?;
+        unsafe {
+            let (slice, mapping) = writer.into_parts();
+            Ok(mapping::Writer::new(slice, mapping))
+        }
+    }
+
+    fn create_semaphore(&mut self) -> B::Semaphore {
+        self.raw.create_semaphore()
+    }
+
+    fn create_fence(&mut self, signaled: bool) -> Fence<B> {
+        Fence {
+            raw: self.raw.create_fence(signaled),
+            id: next_id(),
+        }
+    }
+
+    fn reset_fences(&mut self, fences: &[&Fence<B>]) {
+        let fences = fences.iter().map(|fence| &fence.raw).collect::<Vec<_>>();
+        self.raw.reset_fences(&fences)
+    }
+
+    fn wait_for_fences(&mut self, fences: &[&Fence<B>], wait: device::WaitFor, timeout_ms: u32) -> bool {
+        let raw_fences = fences.iter().map(|fence| &fence.raw).collect::<Vec<_>>();
+        let result = self.raw.wait_for_fences(&raw_fences, wait, timeout_ms);
+        if !result {
+            return false;
+        }
+
+        for fence in fences {
+            let signaled = match wait {
+                device::WaitFor::All => true,
+                device::WaitFor::Any => self.raw.wait_for_fences(&[&fence.raw], device::WaitFor::All, 0),
+            };
+            if signaled {
+                self.shared.lock().retire(fence.id);
+            }
+        }
+        true
+    }
+
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> B::QueryPool {
+        self.raw.create_query_pool(ty, count)
+    }
+
+    fn get_query_pool_results(&mut self, pool: &B::QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        self.raw.get_query_pool_results(pool, queries, data, flags)
+    }
+
+    fn destroy_heap(&mut self, heap: B::Heap) {
+        self.raw.destroy_heap(heap)
+    }
+
+    fn destroy_shader_lib(&mut self, shader_lib: B::ShaderLib) {
+        self.raw.destroy_shader_lib(shader_lib)
+    }
+
+    fn destroy_renderpass(&mut self, rp: B::RenderPass) {
+        self.raw.destroy_renderpass(rp)
+    }
+
+    fn destroy_pipeline_layout(&mut self, layout: B::PipelineLayout) {
+        self.raw.destroy_pipeline_layout(layout)
+    }
+
+    fn destroy_graphics_pipeline(&mut self, pipeline: B::GraphicsPipeline) {
+        self.raw.destroy_graphics_pipeline(pipeline)
+    }
+
+    fn destroy_compute_pipeline(&mut self, pipeline: B::ComputePipeline) {
+        self.raw.destroy_compute_pipeline(pipeline)
+    }
+
+    fn destroy_pipeline_cache(&mut self, cache: B::PipelineCache) {
+        self.raw.destroy_pipeline_cache(cache)
+    }
+
+    fn destroy_framebuffer(&mut self, fb: B::FrameBuffer) {
+        self.raw.destroy_framebuffer(fb)
+    }
+
+    fn destroy_buffer(&mut self, buffer: Buffer<B>) {
+        // Leak the buffer if still in use, the wrapped backend may not cope with it.
+        if self.check_idle(buffer.id) {
+            self.raw.destroy_buffer(buffer.raw)
+        }
+    }
+
+    fn destroy_image(&mut self, image: Image<B>) {
+        if self.check_idle(image.id) {
+            self.shared.lock().layouts.remove(&image.id);
+            if let RawImage::Owned(raw) = image.raw {
+                self.raw.destroy_image(raw)
+            }
+        }
+    }
+
+    fn destroy_render_target_view(&mut self, rtv: B::RenderTargetView) {
+        self.raw.destroy_render_target_view(rtv)
+    }
+
+    fn destroy_depth_stencil_view(&mut self, dsv: B::DepthStencilView) {
+        self.raw.destroy_depth_stencil_view(dsv)
+    }
+
+    fn destroy_constant_buffer_view(&mut self, cbv: B::ConstantBufferView) {
+        self.raw.destroy_constant_buffer_view(cbv)
+    }
+
+    fn destroy_shader_resource_view(&mut self, srv: B::ShaderResourceView) {
+        self.raw.destroy_shader_resource_view(srv)
+    }
+
+    fn destroy_unordered_access_view(&mut self, uav: B::UnorderedAccessView) {
+        self.raw.destroy_unordered_access_view(uav)
+    }
+
+    fn destroy_sampler(&mut self, sampler: B::Sampler) {
+        self.raw.destroy_sampler(sampler)
+    }
+
+    fn destroy_descriptor_pool(&mut self, pool: DescriptorPool<B>) {
+        self.raw.destroy_descriptor_pool(pool.raw)
+    }
+
+    fn destroy_descriptor_set_layout(&mut self, layout: B::DescriptorSetLayout) {
+        self.raw.destroy_descriptor_set_layout(layout)
+    }
+
+    fn destroy_fence(&mut self, fence: Fence<B>) {
+        self.raw.destroy_fence(fence.raw)
+    }
+
+    fn destroy_semaphore(&mut self, semaphore: B::Semaphore) {
+        self.raw.destroy_semaphore(semaphore)
+    }
+
+    fn destroy_query_pool(&mut self, pool: B::QueryPool) {
+        self.raw.destroy_query_pool(pool)
+    }
+}
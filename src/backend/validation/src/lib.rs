@@ -0,0 +1,371 @@
+//! Validation layer.
+//!
+//! `Validation<B>` implements `Backend` on top of any other backend `B`.
+//! All calls are forwarded to `B` after being checked for API misuse, which
+//! would otherwise end up as undefined behavior in the wrapped backend.
+//! Detected errors are handed to a user supplied `Callback`, by default the
+//! layer panics. Checks performed:
+//!
+//!   - buffers need the `buffer::Usage` flags matching the way they are bound
+//!   - buffer copies, fills and updates must stay inside the buffer
+//!   - `draw_indexed` requires a bound index buffer
+//!   - the source layout of image barriers must match the current layout
+//!   - buffers and images can't be destroyed while pending submissions
+//!     still reference them
+//!
+//! Image layouts are tracked per image, not per subresource. Resources only
+//! referenced through views or descriptor sets aren't tracked. A submission is
+//! considered pending until `wait_for_fences` observed its fence, or the fence
+//! of a later submission on the same queue, being signalled.
+
+#[macro_use]
+extern crate log;
+extern crate gfx_core as core;
+
+use std::{error, fmt};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use core::{buffer, image, QueueType};
+
+mod command;
+mod device;
+mod native;
+mod pool;
+mod window;
+
+pub use command::CommandBuffer;
+pub use device::Device;
+pub use native::{Buffer, Fence, Image, UnboundBuffer};
+pub use pool::{CommandPool, DescriptorPool, SubpassCommandPool};
+pub use window::{Surface, Swapchain};
+
+/// Unique identifier of a resource tracked by the validation layer.
+pub type Id = usize;
+
+static NEXT_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+fn next_id() -> Id {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// API misuse detected by the validation layer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// A buffer is used in a way its usage flags don't allow.
+    MissingUsage {
+        buffer: Id,
+        required: buffer::Usage,
+        usage: buffer::Usage,
+    },
+    /// A buffer access reaches past the end of the buffer.
+    OutOfBounds {
+        buffer: Id,
+        range: Range<u64>,
+        size: u64,
+    },
+    /// An indexed draw was recorded without binding an index buffer.
+    NoIndexBuffer,
+    /// The source layout of an image barrier doesn't match the current layout.
+    LayoutMismatch {
+        image: Id,
+        expected: image::ImageLayout,
+        found: image::ImageLayout,
+    },
+    /// A resource was destroyed while still being used by a pending submission.
+    InUse(Id),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = error::Error::description(self);
+        match *self {
+            Error::MissingUsage { buffer, required, usage } =>
+                write!(f, "{}: buffer {} requires {:?}, has {:?}", description, buffer, required, usage),
+            Error::OutOfBounds { buffer, ref range, size } =>
+                write!(f, "{}: range {:?} of buffer {} with size {}", description, range, buffer, size),
+            Error::LayoutMismatch { image, expected, found } =>
+                write!(f, "{}: image {} is in {:?}, not {:?}", description, image, expected, found),
+            Error::InUse(id) => write!(f, "{}: {}", description, id),
+            Error::NoIndexBuffer => write!(f, "{}", description),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::MissingUsage { .. } => "Buffer is missing the required usage flags",
+            Error::OutOfBounds { .. } => "Buffer access is out of bounds",
+            Error::NoIndexBuffer => "Indexed draw without a bound index buffer",
+            Error::LayoutMismatch { .. } => "Image barrier doesn't match the current image layout",
+            Error::InUse(_) => "Resource destroyed while used by a pending submission",
+        }
+    }
+}
+
+/// Receiver of validation errors.
+pub type Callback = Arc<Fn(&Error) + Send + Sync>;
+
+fn panic_on_error(error: &Error) {
+    panic!("Validation error: {}", error);
+}
+
+/// Tracking state shared between a device and all objects created from it.
+struct Shared {
+    callback: Callback,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn new(callback: Callback) -> Self {
+        Shared {
+            callback,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    fn report(&self, error: Error) {
+        debug!("{}", error);
+        (self.callback)(&error);
+    }
+
+    fn lock(&self) -> MutexGuard<State> {
+        self.state.lock().unwrap()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// Layouts of all known images after the last submission.
+    layouts: HashMap<Id, image::ImageLayout>,
+    /// Submissions not known to be completed yet, in submission order.
+    pending: Vec<Pending>,
+}
+
+struct Pending {
+    queue: Id,
+    fence: Option<Id>,
+    resources: HashSet<Id>,
+}
+
+impl State {
+    fn is_pending(&self, id: Id) -> bool {
+        self.pending.iter().any(|pending| pending.resources.contains(&id))
+    }
+
+    /// Retire the latest submission guarded by `fence`, together with all
+    /// submissions issued before it on the same queue.
+    fn retire(&mut self, fence: Id) {
+        let last = match self.pending.iter().rposition(|pending| pending.fence == Some(fence)) {
+            Some(last) => last,
+            None => return,
+        };
+        let queue = self.pending[last].queue;
+        let mut index = 0;
+        self.pending.retain(|pending| {
+            let keep = index > last || pending.queue != queue;
+            index += 1;
+            keep
+        });
+    }
+}
+
+/// Backend wrapping `B` with validation.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Validation<B>(PhantomData<B>);
+
+impl<B: core::Backend> core::Backend for Validation<B> {
+    type Adapter = Adapter<B>;
+    type Device = Device<B>;
+
+    type Surface = Surface<B>;
+    type Swapchain = Swapchain<B>;
+
+    type CommandQueue = CommandQueue<B>;
+    type CommandBuffer = CommandBuffer<B>;
+    type SubpassCommandBuffer = B::SubpassCommandBuffer;
+    type QueueFamily = B::QueueFamily;
+
+    type Heap = B::Heap;
+    type Mapping = B::Mapping;
+    type CommandPool = CommandPool<B>;
+    type SubpassCommandPool = SubpassCommandPool<B>;
+
+    type ShaderLib = B::ShaderLib;
+    type RenderPass = B::RenderPass;
+    type FrameBuffer = B::FrameBuffer;
+
+    type UnboundBuffer = UnboundBuffer<B>;
+    type Buffer = Buffer<B>;
+    type UnboundImage = B::UnboundImage;
+    type Image = Image<B>;
+    type Sampler = B::Sampler;
+
+    type ConstantBufferView = B::ConstantBufferView;
+    type ShaderResourceView = B::ShaderResourceView;
+    type UnorderedAccessView = B::UnorderedAccessView;
+    type RenderTargetView = B::RenderTargetView;
+    type DepthStencilView = B::DepthStencilView;
+
+    type ComputePipeline = B::ComputePipeline;
+    type GraphicsPipeline = B::GraphicsPipeline;
+    type PipelineCache = B::PipelineCache;
+    type PipelineLayout = B::PipelineLayout;
+    type DescriptorPool = DescriptorPool<B>;
+    type DescriptorSet = B::DescriptorSet;
+    type DescriptorSetLayout = B::DescriptorSetLayout;
+
+    type QueryPool = B::QueryPool;
+
+    type Fence = Fence<B>;
+    type Semaphore = B::Semaphore;
+}
+
+/// Instance wrapper, enabling validation on all enumerated adapters.
+pub struct Instance<I>(pub I);
+
+impl<B: core::Backend, I: core::Instance<B>> core::Instance<Validation<B>> for Instance<I> {
+    fn enumerate_adapters(&self) -> Vec<Adapter<B>> {
+        self.0
+            .enumerate_adapters()
+            .into_iter()
+            .map(Adapter::new)
+            .collect()
+    }
+}
+
+pub struct Adapter<B: core::Backend> {
+    raw: B::Adapter,
+    callback: Callback,
+}
+
+impl<B: core::Backend> Adapter<B> {
+    /// Wrap an adapter, panicking on any validation error.
+    pub fn new(raw: B::Adapter) -> Self {
+        Adapter::with_callback(raw, Arc::new(panic_on_error))
+    }
+
+    /// Wrap an adapter, reporting validation errors to `callback`.
+    pub fn with_callback(raw: B::Adapter, callback: Callback) -> Self {
+        Adapter { raw, callback }
+    }
+}
+
+fn wrap_queues<B: core::Backend, C>(
+    queues: Vec<core::CommandQueue<B, C>>,
+    shared: &Arc<Shared>,
+) -> Vec<core::CommandQueue<Validation<B>, C>> {
+    queues
+        .into_iter()
+        .map(|queue| unsafe {
+            core::CommandQueue::new(CommandQueue::new(queue.into_raw(), shared.clone()))
+        })
+        .collect()
+}
+
+impl<B: core::Backend> core::Adapter<Validation<B>> for Adapter<B> {
+    fn open(&self, queue_descs: &[(&B::QueueFamily, QueueType, u32)]) -> core::Gpu<Validation<B>> {
+        let gpu = self.raw.open(queue_descs);
+        let shared = Arc::new(Shared::new(self.callback.clone()));
+
+        core::Gpu {
+            general_queues: wrap_queues(gpu.general_queues, &shared),
+            graphics_queues: wrap_queues(gpu.graphics_queues, &shared),
+            compute_queues: wrap_queues(gpu.compute_queues, &shared),
+            transfer_queues: wrap_queues(gpu.transfer_queues, &shared),
+            device: Device::new(gpu.device, shared),
+            heap_types: gpu.heap_types,
+            memory_heaps: gpu.memory_heaps,
+        }
+    }
+
+    fn get_info(&self) -> &core::AdapterInfo {
+        self.raw.get_info()
+    }
+
+    fn get_queue_families(&self) -> &[(B::QueueFamily, QueueType)] {
+        self.raw.get_queue_families()
+    }
+}
+
+/// Command queue, keeping track of pending submissions.
+pub struct CommandQueue<B: core::Backend> {
+    // Only empty while temporarily handed out by `with_core`.
+    raw: RefCell<Option<B::CommandQueue>>,
+    id: Id,
+    shared: Arc<Shared>,
+}
+
+impl<B: core::Backend> CommandQueue<B> {
+    fn new(raw: B::CommandQueue, shared: Arc<Shared>) -> Self {
+        CommandQueue {
+            raw: RefCell::new(Some(raw)),
+            id: next_id(),
+            shared,
+        }
+    }
+
+    /// Temporarily expose the wrapped queue as a `core::CommandQueue` of `B`.
+    fn with_core<C, T, F>(&self, fun: F) -> T
+    where
+        F: FnOnce(&mut core::CommandQueue<B, C>) -> T,
+    {
+        let raw = self.raw.borrow_mut().take().unwrap();
+        let mut queue = unsafe { core::CommandQueue::new(raw) };
+        let result = fun(&mut queue);
+        *self.raw.borrow_mut() = Some(queue.into_raw());
+        result
+    }
+}
+
+impl<B: core::Backend> core::RawCommandQueue<Validation<B>> for CommandQueue<B> {
+    unsafe fn submit_raw(&mut self, submission: core::RawSubmission<Validation<B>>, fence: Option<&Fence<B>>) {
+        let mut errors = Vec::new();
+        {
+            let mut state = self.shared.lock();
+            let mut resources = HashSet::new();
+            for cmd_buffer in submission.cmd_buffers {
+                for (&image, &(first, last)) in &cmd_buffer.layouts {
+                    if let Some(&current) = state.layouts.get(&image) {
+                        if first != image::ImageLayout::Undefined && first != current {
+                            errors.push(Error::LayoutMismatch {
+                                image,
+                                expected: current,
+                                found: first,
+                            });
+                        }
+                    }
+                    state.layouts.insert(image, last);
+                }
+                resources.extend(cmd_buffer.resources.iter().cloned());
+            }
+            state.pending.push(Pending {
+                queue: self.id,
+                fence: fence.map(|fence| fence.id),
+                resources,
+            });
+        }
+        for error in errors {
+            self.shared.report(error);
+        }
+
+        let cmd_buffers = submission.cmd_buffers
+            .iter()
+            .map(|cmd_buffer| cmd_buffer.raw.clone())
+            .collect::<Vec<_>>();
+        let raw = core::RawSubmission {
+            cmd_buffers: &cmd_buffers,
+            wait_semaphores: submission.wait_semaphores,
+            signal_semaphores: submission.signal_semaphores,
+        };
+        self.raw
+            .get_mut()
+            .as_mut()
+            .unwrap()
+            .submit_raw(raw, fence.map(|fence| &fence.raw));
+    }
+}
@@ -0,0 +1,82 @@
+use std::fmt;
+use core::{self, buffer};
+use Id;
+
+/// Buffer without memory, remembering its creation parameters.
+#[derive(Debug)]
+pub struct UnboundBuffer<B: core::Backend> {
+    pub(crate) raw: B::UnboundBuffer,
+    pub(crate) size: u64,
+    pub(crate) usage: buffer::Usage,
+}
+
+#[derive(Debug)]
+pub struct Buffer<B: core::Backend> {
+    pub(crate) raw: B::Buffer,
+    pub(crate) id: Id,
+    pub(crate) size: u64,
+    pub(crate) usage: buffer::Usage,
+}
+
+impl<B: core::Backend> Buffer<B> {
+    /// Identifier used to refer to this buffer in validation errors.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Access the buffer of the wrapped backend.
+    pub fn raw(&self) -> &B::Buffer {
+        &self.raw
+    }
+}
+
+pub(crate) enum RawImage<B: core::Backend> {
+    Owned(B::Image),
+    /// Backbuffer owned by the wrapped swapchain, which outlives the image.
+    Backbuffer(*const B::Image),
+}
+
+pub struct Image<B: core::Backend> {
+    pub(crate) raw: RawImage<B>,
+    pub(crate) id: Id,
+}
+
+unsafe impl<B: core::Backend> Send for Image<B> {}
+unsafe impl<B: core::Backend> Sync for Image<B> {}
+
+impl<B: core::Backend> Image<B> {
+    /// Identifier used to refer to this image in validation errors.
+    pub fn id(&self) -> Id {
+        self.id
+    }
+
+    /// Access the image of the wrapped backend.
+    pub fn raw(&self) -> &B::Image {
+        match self.raw {
+            RawImage::Owned(ref image) => image,
+            RawImage::Backbuffer(image) => unsafe { &*image },
+        }
+    }
+}
+
+impl<B: core::Backend> fmt::Debug for Image<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Image")
+            .field("raw", self.raw())
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+pub struct Fence<B: core::Backend> {
+    pub(crate) raw: B::Fence,
+    pub(crate) id: Id,
+}
+
+impl<B: core::Backend> Fence<B> {
+    /// Access the fence of the wrapped backend.
+    pub fn raw(&self) -> &B::Fence {
+        &self.raw
+    }
+}
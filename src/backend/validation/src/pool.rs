@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use core::{self, pool};
+use {CommandBuffer, CommandQueue, Shared, Validation};
+
+pub struct CommandPool<B: core::Backend> {
+    raw: B::CommandPool,
+    shared: Arc<Shared>,
+}
+
+impl<B: core::Backend> core::RawCommandPool<Validation<B>> for CommandPool<B> {
+    fn reset(&mut self) {
+        self.raw.reset()
+    }
+
+    unsafe fn from_queue(queue: &CommandQueue<B>, flags: pool::CommandPoolCreateFlags) -> Self {
+        let raw = {
+            let raw_queue = queue.raw.borrow();
+            B::CommandPool::from_queue(raw_queue.as_ref().unwrap(), flags)
+        };
+        CommandPool {
+            raw,
+            shared: queue.shared.clone(),
+        }
+    }
+
+    fn allocate(&mut self, num: usize) -> Vec<CommandBuffer<B>> {
+        let shared = &self.shared;
+        self.raw
+            .allocate(num)
+            .into_iter()
+            .map(|raw| CommandBuffer::new(raw, shared.clone()))
+            .collect()
+    }
+
+    unsafe fn free(&mut self, buffers: Vec<CommandBuffer<B>>) {
+        self.raw.free(buffers.into_iter().map(|buffer| buffer.raw).collect())
+    }
+}
+
+pub struct SubpassCommandPool<B: core::Backend>(B::SubpassCommandPool);
+
+impl<B: core::Backend> core::SubpassCommandPool<Validation<B>> for SubpassCommandPool<B> { }
+
+#[derive(Debug)]
+pub struct DescriptorPool<B: core::Backend> {
+    pub(crate) raw: B::DescriptorPool,
+}
+
+impl<B: core::Backend> core::DescriptorPool<Validation<B>> for DescriptorPool<B> {
+    fn allocate_sets(&mut self, layouts: &[&B::DescriptorSetLayout]) -> Vec<B::DescriptorSet> {
+        self.raw.allocate_sets(layouts)
+    }
+
+    fn reset(&mut self) {
+        self.raw.reset()
+    }
+}
@@ -0,0 +1,256 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_backend_validation as validation;
+extern crate gfx_core as core;
+
+use std::sync::{Arc, Mutex};
+use core::{buffer, format, image, memory, pool, pso};
+use core::{Adapter, Device, QueueFamily, RawCommandBuffer, RawCommandPool, RawCommandQueue};
+use core::command::BufferCopy;
+use core::device::{ResourceHeapType, WaitFor};
+use core::image::ImageLayout;
+use validation::{Error, Validation};
+
+type Backend = Validation<empty::Backend>;
+
+struct Harness {
+    device: validation::Device<empty::Backend>,
+    queue: core::CommandQueue<Backend, core::General>,
+    pool: validation::CommandPool<empty::Backend>,
+    heap: <Backend as core::Backend>::Heap,
+    errors: Arc<Mutex<Vec<Error>>>,
+}
+
+impl Harness {
+    fn new() -> Self {
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = errors.clone();
+        let adapter = validation::Adapter::<empty::Backend>::with_callback(
+            empty::Adapter::new(),
+            Arc::new(move |error: &Error| sink.lock().unwrap().push(error.clone())),
+        );
+        let descs = adapter.get_queue_families()
+            .iter()
+            .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+            .collect::<Vec<_>>();
+        let core::Gpu { mut device, mut general_queues, heap_types, .. } = adapter.open(&descs);
+
+        let heap = device.create_heap(&heap_types[0], ResourceHeapType::Any, 1 << 20).unwrap();
+        let queue = general_queues.remove(0);
+        let pool = unsafe {
+            validation::CommandPool::from_queue(queue.as_raw(), pool::CommandPoolCreateFlags::empty())
+        };
+
+        Harness { device, queue, pool, heap, errors }
+    }
+
+    fn create_buffer(&mut self, offset: u64, size: u64, usage: buffer::Usage) -> validation::Buffer<empty::Backend> {
+        let unbound = self.device.create_buffer(size, 0, usage).unwrap();
+        self.device.bind_buffer_memory(&self.heap, offset, unbound).unwrap()
+    }
+
+    fn create_image(&mut self, offset: u64) -> validation::Image<empty::Backend> {
+        let kind = image::Kind::D2(4, 4, image::AaMode::Single);
+        let unbound = self.device
+            .create_image(kind, 1, format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm), image::TRANSFER_DST | image::SAMPLED)
+            .unwrap();
+        self.device.bind_image_memory(&self.heap, offset, unbound).unwrap()
+    }
+
+    fn record<F>(&mut self, fun: F) -> validation::CommandBuffer<empty::Backend>
+    where
+        F: FnOnce(&mut validation::CommandBuffer<empty::Backend>),
+    {
+        let mut cmd_buffer = self.pool.allocate(1).pop().unwrap();
+        cmd_buffer.begin();
+        fun(&mut cmd_buffer);
+        cmd_buffer.finish();
+        cmd_buffer
+    }
+
+    fn submit(&mut self, cmd_buffer: validation::CommandBuffer<empty::Backend>, fence: Option<&validation::Fence<empty::Backend>>) {
+        unsafe {
+            self.queue.as_mut().submit_raw(core::RawSubmission {
+                cmd_buffers: &[cmd_buffer],
+                wait_semaphores: &[],
+                signal_semaphores: &[],
+            }, fence);
+        }
+    }
+
+    fn take_errors(&self) -> Vec<Error> {
+        let mut errors = self.errors.lock().unwrap();
+        errors.drain(..).collect()
+    }
+}
+
+fn image_barrier<'a>(
+    image: &'a validation::Image<empty::Backend>,
+    layouts: (ImageLayout, ImageLayout),
+) -> memory::Barrier<'a, Backend> {
+    memory::Barrier::Image {
+        states: (image::Access::empty(), layouts.0) .. (image::Access::empty(), layouts.1),
+        target: image,
+        range: (0 .. 1, 0 .. 1),
+    }
+}
+
+#[test]
+fn valid_usage() {
+    let mut harness = Harness::new();
+    let src = harness.create_buffer(0, 64, buffer::TRANSFER_SRC);
+    let dst = harness.create_buffer(64, 64, buffer::TRANSFER_DST | buffer::INDEX);
+    let cmd_buffer = harness.record(|cmd_buffer| {
+        cmd_buffer.copy_buffer(&src, &dst, &[BufferCopy { src: 0, dst: 32, size: 32 }]);
+        cmd_buffer.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &dst,
+            offset: 0,
+            index_type: core::IndexType::U16,
+        });
+        cmd_buffer.draw_indexed(0 .. 3, 0, 0 .. 1);
+    });
+    harness.submit(cmd_buffer, None);
+    assert_eq!(harness.take_errors(), vec![]);
+}
+
+#[test]
+fn missing_usage() {
+    let mut harness = Harness::new();
+    let vertices = harness.create_buffer(0, 64, buffer::TRANSFER_DST);
+    harness.record(|cmd_buffer| {
+        cmd_buffer.bind_vertex_buffers(pso::VertexBufferSet(vec![(&vertices, 0)]));
+    });
+    assert_eq!(harness.take_errors(), vec![
+        Error::MissingUsage {
+            buffer: vertices.id(),
+            required: buffer::VERTEX,
+            usage: buffer::TRANSFER_DST,
+        },
+    ]);
+}
+
+#[test]
+fn copy_out_of_bounds() {
+    let mut harness = Harness::new();
+    let src = harness.create_buffer(0, 64, buffer::TRANSFER_SRC);
+    let dst = harness.create_buffer(64, 32, buffer::TRANSFER_DST);
+    harness.record(|cmd_buffer| {
+        cmd_buffer.copy_buffer(&src, &dst, &[BufferCopy { src: 16, dst: 8, size: 32 }]);
+    });
+    assert_eq!(harness.take_errors(), vec![
+        Error::OutOfBounds {
+            buffer: dst.id(),
+            range: 8 .. 40,
+            size: 32,
+        },
+    ]);
+}
+
+#[test]
+fn draw_indexed_without_index_buffer() {
+    let mut harness = Harness::new();
+    harness.record(|cmd_buffer| {
+        cmd_buffer.draw(0 .. 3, 0 .. 1);
+        cmd_buffer.draw_indexed(0 .. 3, 0, 0 .. 1);
+    });
+    assert_eq!(harness.take_errors(), vec![Error::NoIndexBuffer]);
+
+    // Bindings don't carry over to the next recording.
+    let index = harness.create_buffer(0, 64, buffer::INDEX);
+    let mut cmd_buffer = harness.record(|cmd_buffer| {
+        cmd_buffer.bind_index_buffer(buffer::IndexBufferView {
+            buffer: &index,
+            offset: 0,
+            index_type: core::IndexType::U32,
+        });
+    });
+    cmd_buffer.begin();
+    cmd_buffer.draw_indexed(0 .. 3, 0, 0 .. 1);
+    assert_eq!(harness.take_errors(), vec![Error::NoIndexBuffer]);
+}
+
+#[test]
+fn image_layout_mismatch() {
+    let mut harness = Harness::new();
+    let image = harness.create_image(0);
+
+    // Mismatch inside a single command buffer.
+    harness.record(|cmd_buffer| {
+        cmd_buffer.pipeline_barrier(pso::TOP_OF_PIPE .. pso::TRANSFER, &[
+            image_barrier(&image, (ImageLayout::Undefined, ImageLayout::TransferDstOptimal)),
+            image_barrier(&image, (ImageLayout::ShaderReadOnlyOptimal, ImageLayout::General)),
+        ]);
+    });
+    assert_eq!(harness.take_errors(), vec![
+        Error::LayoutMismatch {
+            image: image.id(),
+            expected: ImageLayout::TransferDstOptimal,
+            found: ImageLayout::ShaderReadOnlyOptimal,
+        },
+    ]);
+
+    // Mismatch against the layout left behind by previous submissions.
+    let upload = harness.record(|cmd_buffer| {
+        cmd_buffer.pipeline_barrier(pso::TOP_OF_PIPE .. pso::TRANSFER, &[
+            image_barrier(&image, (ImageLayout::Undefined, ImageLayout::TransferDstOptimal)),
+        ]);
+    });
+    let sample = harness.record(|cmd_buffer| {
+        cmd_buffer.pipeline_barrier(pso::TRANSFER .. pso::PIXEL_SHADER, &[
+            image_barrier(&image, (ImageLayout::TransferDstOptimal, ImageLayout::ShaderReadOnlyOptimal)),
+        ]);
+    });
+    harness.submit(upload, None);
+    harness.submit(sample.clone(), None);
+    assert_eq!(harness.take_errors(), vec![]);
+
+    harness.submit(sample, None);
+    assert_eq!(harness.take_errors(), vec![
+        Error::LayoutMismatch {
+            image: image.id(),
+            expected: ImageLayout::ShaderReadOnlyOptimal,
+            found: ImageLayout::TransferDstOptimal,
+        },
+    ]);
+}
+
+#[test]
+fn destroy_in_use() {
+    let mut harness = Harness::new();
+    let src = harness.create_buffer(0, 64, buffer::TRANSFER_SRC);
+    let dst = harness.create_buffer(64, 64, buffer::TRANSFER_DST);
+    let unused = harness.create_buffer(128, 64, buffer::TRANSFER_DST);
+    let fence = harness.device.create_fence(false);
+
+    let copy = harness.record(|cmd_buffer| {
+        cmd_buffer.copy_buffer(&src, &dst, &[BufferCopy { src: 0, dst: 0, size: 64 }]);
+    });
+    harness.submit(copy.clone(), None);
+    harness.submit(copy, Some(&fence));
+
+    let src_id = src.id();
+    harness.device.destroy_buffer(unused);
+    harness.device.destroy_buffer(src);
+    assert_eq!(harness.take_errors(), vec![Error::InUse(src_id)]);
+
+    // The fence retires both submissions.
+    assert!(harness.device.wait_for_fences(&[&fence], WaitFor::All, !0));
+    harness.device.destroy_buffer(dst);
+    assert_eq!(harness.take_errors(), vec![]);
+}
+
+#[test]
+fn format_properties() {
+    let harness = Harness::new();
+    let depth = harness.device.get_format_properties(format::Format(format::SurfaceType::D24_S8, format::ChannelType::Unorm));
+    assert!(depth.optimal_tiling.contains(format::DEPTH_STENCIL_ATTACHMENT));
+    assert!(!depth.optimal_tiling.contains(format::COLOR_ATTACHMENT));
+
+    let integer = harness.device.get_format_properties(format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Uint));
+    assert!(integer.optimal_tiling.contains(format::SAMPLED | format::COLOR_ATTACHMENT));
+    assert!(!integer.optimal_tiling.intersects(format::SAMPLED_LINEAR | format::COLOR_ATTACHMENT_BLEND));
+    assert!(integer.buffer_features.contains(format::VERTEX));
+
+    let compressed = harness.device.get_format_properties(format::Format(format::SurfaceType::BC1_R8_G8_B8, format::ChannelType::Srgb));
+    assert!(!compressed.optimal_tiling.contains(format::COLOR_ATTACHMENT));
+    assert_eq!(compressed.buffer_features, format::BufferFeature::empty());
+}
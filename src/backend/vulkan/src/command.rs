@@ -5,11 +5,11 @@ use smallvec::SmallVec;
 use ash::vk;
 use ash::version::DeviceV1_0;
 
-use core::{command, memory, pso, target};
+use core::{command, image, memory, pso, query, target};
 use core::{IndexCount, InstanceCount, VertexCount, VertexOffset, Viewport};
 use core::buffer::IndexBufferView;
 use core::command::{
-    BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageCopy, ImageResolve,
+    BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageBlit, ImageCopy, ImageResolve,
     SubpassContents,
 };
 use core::image::ImageLayout;
@@ -41,12 +41,15 @@ fn map_buffer_image_regions(
         .iter()
         .map(|region| {
             let aspect_mask = conv::map_image_aspects(region.image_aspect);
-            let row_length = div(region.buffer_row_pitch, image.bytes_per_texel as u32);
+            // Vulkan expects the buffer layout in texels, rows of blocks hold several rows of texels.
+            let (block_width, block_height) = image.block_dim;
+            let row_length = div(region.buffer_row_pitch, image.bytes_per_block as u32) * block_width as u32;
+            let image_height = div(region.buffer_slice_pitch, region.buffer_row_pitch) * block_height as u32;
             let image_subresource = conv::map_subresource_layers(aspect_mask, &region.image_subresource);
             vk::BufferImageCopy {
                 buffer_offset: region.buffer_offset,
                 buffer_row_length: row_length,
-                buffer_image_height: div(region.buffer_slice_pitch, row_length),
+                buffer_image_height: image_height,
                 image_subresource,
                 image_offset: vk::Offset3D {
                     x: region.image_offset.x,
@@ -372,6 +375,50 @@ impl command::RawCommandBuffer<Backend> for CommandBuffer {
         }
     }
 
+    fn blit_image(
+        &mut self,
+        src: &n::Image,
+        src_layout: ImageLayout,
+        dst: &n::Image,
+        dst_layout: ImageLayout,
+        regions: &[ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        let regions: SmallVec<[vk::ImageBlit; 16]> = regions
+            .iter()
+            .map(|region| {
+                let aspect_mask = conv::map_image_aspects(region.aspect_mask);
+                vk::ImageBlit {
+                    src_subresource: conv::map_subresource_layers(aspect_mask, &region.src_subresource),
+                    src_offsets: [
+                        conv::map_offset(region.src_bounds.start),
+                        conv::map_offset(region.src_bounds.end),
+                    ],
+                    dst_subresource: conv::map_subresource_layers(aspect_mask, &region.dst_subresource),
+                    dst_offsets: [
+                        conv::map_offset(region.dst_bounds.start),
+                        conv::map_offset(region.dst_bounds.end),
+                    ],
+                }
+            })
+            .collect();
+        // Blits only use the filter within a level.
+        let (min_filter, _, _, _) = conv::map_filter(filter);
+
+        unsafe {
+            self.device.0.fp_v1_0().cmd_blit_image(
+                self.raw,
+                src.raw,
+                conv::map_image_layout(src_layout),
+                dst.raw,
+                conv::map_image_layout(dst_layout),
+                regions.len() as u32,
+                regions.as_ptr(),
+                min_filter,
+            );
+        }
+    }
+
     fn bind_index_buffer(&mut self, ibv: IndexBufferView<Backend>) {
         unsafe {
             self.device.0.cmd_bind_index_buffer(
@@ -695,6 +742,72 @@ impl command::RawCommandBuffer<Backend> for CommandBuffer {
             )
         }
     }
+
+    fn begin_query(&mut self, query: query::Query<Backend>, flags: query::QueryControl) {
+        unsafe {
+            self.device.0.fp_v1_0().cmd_begin_query(
+                self.raw,
+                query.pool.raw,
+                query.id,
+                conv::map_query_control(flags),
+            )
+        }
+    }
+
+    fn end_query(&mut self, query: query::Query<Backend>) {
+        unsafe {
+            self.device.0.fp_v1_0().cmd_end_query(
+                self.raw,
+                query.pool.raw,
+                query.id,
+            )
+        }
+    }
+
+    fn reset_query_pool(&mut self, pool: &n::QueryPool, queries: Range<query::QueryId>) {
+        unsafe {
+            self.device.0.fp_v1_0().cmd_reset_query_pool(
+                self.raw,
+                pool.raw,
+                queries.start,
+                queries.end - queries.start,
+            )
+        }
+    }
+
+    fn write_timestamp(&mut self, stage: pso::PipelineStage, query: query::Query<Backend>) {
+        unsafe {
+            self.device.0.fp_v1_0().cmd_write_timestamp(
+                self.raw,
+                conv::map_pipeline_stage(stage),
+                query.pool.raw,
+                query.id,
+            )
+        }
+    }
+
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &n::QueryPool,
+        queries: Range<query::QueryId>,
+        buffer: &n::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: query::ResultFlags,
+    ) {
+        unsafe {
+            self.device.0.fp_v1_0().cmd_copy_query_pool_results(
+                self.raw,
+                pool.raw,
+                queries.start,
+                queries.end - queries.start,
+                buffer.raw,
+                offset,
+                stride,
+                conv::map_query_result_flags(flags),
+            )
+        }
+    }
 }
 
 pub struct SubpassCommandBuffer(pub CommandBuffer);
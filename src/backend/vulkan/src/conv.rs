@@ -1,8 +1,8 @@
 use ash::vk;
-use core::{buffer, image, pass, pso, state};
+use core::{self, buffer, format, image, pass, pso, query, state};
 use core::command::{ClearColor, ClearValue, Offset};
 use core::device::Extent;
-use core::format::{SurfaceType, ChannelType};
+use core::format::{ChannelType, Format, SurfaceType};
 use core::{IndexType, Primitive};
 
 
@@ -145,9 +145,178 @@ pub fn map_format(surface: SurfaceType, chan: ChannelType) -> Option<vk::Format>
             Float => vk::Format::D32SfloatS8Uint,
             _ => return None,
         },
+        BC1_R8_G8_B8 => match chan {
+            Unorm => vk::Format::Bc1RgbUnormBlock,
+            Srgb  => vk::Format::Bc1RgbSrgbBlock,
+            _ => return None,
+        },
+        BC1_R8_G8_B8_A8 => match chan {
+            Unorm => vk::Format::Bc1RgbaUnormBlock,
+            Srgb  => vk::Format::Bc1RgbaSrgbBlock,
+            _ => return None,
+        },
+        BC2_R8_G8_B8_A8 => match chan {
+            Unorm => vk::Format::Bc2UnormBlock,
+            Srgb  => vk::Format::Bc2SrgbBlock,
+            _ => return None,
+        },
+        BC3_R8_G8_B8_A8 => match chan {
+            Unorm => vk::Format::Bc3UnormBlock,
+            Srgb  => vk::Format::Bc3SrgbBlock,
+            _ => return None,
+        },
+        BC4_R8 => match chan {
+            Unorm => vk::Format::Bc4UnormBlock,
+            Inorm => vk::Format::Bc4SnormBlock,
+            _ => return None,
+        },
+        BC5_R8_G8 => match chan {
+            Unorm => vk::Format::Bc5UnormBlock,
+            Inorm => vk::Format::Bc5SnormBlock,
+            _ => return None,
+        },
+        BC6_R16_G16_B16 => match chan {
+            Float => vk::Format::Bc6hUfloatBlock,
+            _ => return None,
+        },
+        BC7_R8_G8_B8_A8 => match chan {
+            Unorm => vk::Format::Bc7UnormBlock,
+            Srgb  => vk::Format::Bc7SrgbBlock,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8 => match chan {
+            Unorm => vk::Format::Etc2R8g8b8UnormBlock,
+            Srgb  => vk::Format::Etc2R8g8b8SrgbBlock,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8_A1 => match chan {
+            Unorm => vk::Format::Etc2R8g8b8a1UnormBlock,
+            Srgb  => vk::Format::Etc2R8g8b8a1SrgbBlock,
+            _ => return None,
+        },
+        ETC2_R8_G8_B8_A8 => match chan {
+            Unorm => vk::Format::Etc2R8g8b8a8UnormBlock,
+            Srgb  => vk::Format::Etc2R8g8b8a8SrgbBlock,
+            _ => return None,
+        },
+        EAC_R11 => match chan {
+            Unorm => vk::Format::EacR11UnormBlock,
+            Inorm => vk::Format::EacR11SnormBlock,
+            _ => return None,
+        },
+        EAC_R11_G11 => match chan {
+            Unorm => vk::Format::EacR11g11UnormBlock,
+            Inorm => vk::Format::EacR11g11SnormBlock,
+            _ => return None,
+        },
+        ASTC_4x4 => match chan {
+            Unorm => vk::Format::Astc4x4UnormBlock,
+            Srgb  => vk::Format::Astc4x4SrgbBlock,
+            _ => return None,
+        },
+        ASTC_5x4 => match chan {
+            Unorm => vk::Format::Astc5x4UnormBlock,
+            Srgb  => vk::Format::Astc5x4SrgbBlock,
+            _ => return None,
+        },
+        ASTC_5x5 => match chan {
+            Unorm => vk::Format::Astc5x5UnormBlock,
+            Srgb  => vk::Format::Astc5x5SrgbBlock,
+            _ => return None,
+        },
+        ASTC_6x5 => match chan {
+            Unorm => vk::Format::Astc6x5UnormBlock,
+            Srgb  => vk::Format::Astc6x5SrgbBlock,
+            _ => return None,
+        },
+        ASTC_6x6 => match chan {
+            Unorm => vk::Format::Astc6x6UnormBlock,
+            Srgb  => vk::Format::Astc6x6SrgbBlock,
+            _ => return None,
+        },
+        ASTC_8x5 => match chan {
+            Unorm => vk::Format::Astc8x5UnormBlock,
+            Srgb  => vk::Format::Astc8x5SrgbBlock,
+            _ => return None,
+        },
+        ASTC_8x6 => match chan {
+            Unorm => vk::Format::Astc8x6UnormBlock,
+            Srgb  => vk::Format::Astc8x6SrgbBlock,
+            _ => return None,
+        },
+        ASTC_8x8 => match chan {
+            Unorm => vk::Format::Astc8x8UnormBlock,
+            Srgb  => vk::Format::Astc8x8SrgbBlock,
+            _ => return None,
+        },
+        ASTC_10x5 => match chan {
+            Unorm => vk::Format::Astc10x5UnormBlock,
+            Srgb  => vk::Format::Astc10x5SrgbBlock,
+            _ => return None,
+        },
+        ASTC_10x6 => match chan {
+            Unorm => vk::Format::Astc10x6UnormBlock,
+            Srgb  => vk::Format::Astc10x6SrgbBlock,
+            _ => return None,
+        },
+        ASTC_10x8 => match chan {
+            Unorm => vk::Format::Astc10x8UnormBlock,
+            Srgb  => vk::Format::Astc10x8SrgbBlock,
+            _ => return None,
+        },
+        ASTC_10x10 => match chan {
+            Unorm => vk::Format::Astc10x10UnormBlock,
+            Srgb  => vk::Format::Astc10x10SrgbBlock,
+            _ => return None,
+        },
+        ASTC_12x10 => match chan {
+            Unorm => vk::Format::Astc12x10UnormBlock,
+            Srgb  => vk::Format::Astc12x10SrgbBlock,
+            _ => return None,
+        },
+        ASTC_12x12 => match chan {
+            Unorm => vk::Format::Astc12x12UnormBlock,
+            Srgb  => vk::Format::Astc12x12SrgbBlock,
+            _ => return None,
+        },
     })
 }
 
+/// Map a Vulkan format onto a `Format`, covering the formats commonly used by surfaces.
+pub fn map_vk_format(format: vk::Format) -> Option<Format> {
+    use core::format::SurfaceType::*;
+    use core::format::ChannelType::*;
+    let (surface, channel) = match format {
+        vk::Format::R5g6b5UnormPack16 => (R5_G6_B5, Unorm),
+        vk::Format::R8g8b8a8Unorm => (R8_G8_B8_A8, Unorm),
+        vk::Format::R8g8b8a8Srgb => (R8_G8_B8_A8, Srgb),
+        vk::Format::B8g8r8a8Unorm => (B8_G8_R8_A8, Unorm),
+        vk::Format::B8g8r8a8Srgb => (B8_G8_R8_A8, Srgb),
+        vk::Format::A2b10g10r10UnormPack32 => (R10_G10_B10_A2, Unorm),
+        vk::Format::R16g16b16a16Sfloat => (R16_G16_B16_A16, Float),
+        _ => return None,
+    };
+    Some(Format(surface, channel))
+}
+
+pub fn map_present_mode(mode: core::PresentMode) -> vk::PresentModeKHR {
+    match mode {
+        core::PresentMode::Immediate => vk::PresentModeKHR::Immediate,
+        core::PresentMode::Mailbox => vk::PresentModeKHR::Mailbox,
+        core::PresentMode::Fifo => vk::PresentModeKHR::Fifo,
+        core::PresentMode::Relaxed => vk::PresentModeKHR::FifoRelaxed,
+    }
+}
+
+pub fn map_vk_present_mode(mode: vk::PresentModeKHR) -> core::PresentMode {
+    match mode {
+        vk::PresentModeKHR::Immediate => core::PresentMode::Immediate,
+        vk::PresentModeKHR::Mailbox => core::PresentMode::Mailbox,
+        vk::PresentModeKHR::Fifo => core::PresentMode::Fifo,
+        vk::PresentModeKHR::FifoRelaxed => core::PresentMode::Relaxed,
+    }
+}
+
 pub fn map_index_type(index_type: IndexType) -> vk::IndexType {
     match index_type {
         IndexType::U16 => vk::IndexType::Uint16,
@@ -386,6 +555,82 @@ pub fn map_pipeline_stage(stage: pso::PipelineStage) -> vk::PipelineStageFlags {
     flags
 }
 
+pub fn map_query_type(ty: query::QueryType) -> (vk::QueryType, vk::QueryPipelineStatisticFlags) {
+    match ty {
+        query::QueryType::Occlusion => (vk::QueryType::Occlusion, vk::QueryPipelineStatisticFlags::empty()),
+        query::QueryType::PipelineStatistics(statistics) =>
+            (vk::QueryType::PipelineStatistics, map_pipeline_statistics(statistics)),
+        query::QueryType::Timestamp => (vk::QueryType::Timestamp, vk::QueryPipelineStatisticFlags::empty()),
+    }
+}
+
+pub fn map_pipeline_statistics(statistics: query::PipelineStatistic) -> vk::QueryPipelineStatisticFlags {
+    let mut flags = vk::QueryPipelineStatisticFlags::empty();
+
+    if statistics.contains(query::INPUT_ASSEMBLY_VERTICES) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_VERTICES_BIT;
+    }
+    if statistics.contains(query::INPUT_ASSEMBLY_PRIMITIVES) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_INPUT_ASSEMBLY_PRIMITIVES_BIT;
+    }
+    if statistics.contains(query::VERTEX_SHADER_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_VERTEX_SHADER_INVOCATIONS_BIT;
+    }
+    if statistics.contains(query::GEOMETRY_SHADER_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_INVOCATIONS_BIT;
+    }
+    if statistics.contains(query::GEOMETRY_SHADER_PRIMITIVES) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_GEOMETRY_SHADER_PRIMITIVES_BIT;
+    }
+    if statistics.contains(query::CLIPPING_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_INVOCATIONS_BIT;
+    }
+    if statistics.contains(query::CLIPPING_PRIMITIVES) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_CLIPPING_PRIMITIVES_BIT;
+    }
+    if statistics.contains(query::PIXEL_SHADER_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_FRAGMENT_SHADER_INVOCATIONS_BIT;
+    }
+    if statistics.contains(query::HULL_SHADER_PATCHES) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_CONTROL_SHADER_PATCHES_BIT;
+    }
+    if statistics.contains(query::DOMAIN_SHADER_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_TESSELLATION_EVALUATION_SHADER_INVOCATIONS_BIT;
+    }
+    if statistics.contains(query::COMPUTE_SHADER_INVOCATIONS) {
+        flags |= vk::QUERY_PIPELINE_STATISTIC_COMPUTE_SHADER_INVOCATIONS_BIT;
+    }
+
+    flags
+}
+
+pub fn map_query_control(control: query::QueryControl) -> vk::QueryControlFlags {
+    let mut flags = vk::QueryControlFlags::empty();
+
+    if control.contains(query::PRECISE) {
+        flags |= vk::QUERY_CONTROL_PRECISE_BIT;
+    }
+
+    flags
+}
+
+pub fn map_query_result_flags(result: query::ResultFlags) -> vk::QueryResultFlags {
+    // All results are retrieved as 64-bit values.
+    let mut flags = vk::QUERY_RESULT_64_BIT;
+
+    if result.contains(query::WAIT) {
+        flags |= vk::QUERY_RESULT_WAIT_BIT;
+    }
+    if result.contains(query::WITH_AVAILABILITY) {
+        flags |= vk::QUERY_RESULT_WITH_AVAILABILITY_BIT;
+    }
+    if result.contains(query::PARTIAL) {
+        flags |= vk::QUERY_RESULT_PARTIAL_BIT;
+    }
+
+    flags
+}
+
 pub fn map_buffer_usage(usage: buffer::Usage) -> vk::BufferUsageFlags {
     let mut flags = vk::BufferUsageFlags::empty();
 
@@ -433,6 +678,53 @@ pub fn map_image_usage(usage: image::Usage) -> vk::ImageUsageFlags {
     flags
 }
 
+pub fn map_image_features(features: vk::FormatFeatureFlags) -> format::ImageFeature {
+    let mut flags = format::ImageFeature::empty();
+
+    if features.intersects(vk::FORMAT_FEATURE_SAMPLED_IMAGE_BIT) {
+        flags |= format::SAMPLED;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_SAMPLED_IMAGE_FILTER_LINEAR_BIT) {
+        flags |= format::SAMPLED_LINEAR;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_STORAGE_IMAGE_BIT) {
+        flags |= format::STORAGE;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BIT) {
+        flags |= format::COLOR_ATTACHMENT;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_COLOR_ATTACHMENT_BLEND_BIT) {
+        flags |= format::COLOR_ATTACHMENT_BLEND;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT) {
+        flags |= format::DEPTH_STENCIL_ATTACHMENT;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_BLIT_SRC_BIT) {
+        flags |= format::BLIT_SRC;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_BLIT_DST_BIT) {
+        flags |= format::BLIT_DST;
+    }
+
+    flags
+}
+
+pub fn map_buffer_features(features: vk::FormatFeatureFlags) -> format::BufferFeature {
+    let mut flags = format::BufferFeature::empty();
+
+    if features.intersects(vk::FORMAT_FEATURE_VERTEX_BUFFER_BIT) {
+        flags |= format::VERTEX;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_UNIFORM_TEXEL_BUFFER_BIT) {
+        flags |= format::UNIFORM_TEXEL;
+    }
+    if features.intersects(vk::FORMAT_FEATURE_STORAGE_TEXEL_BUFFER_BIT) {
+        flags |= format::STORAGE_TEXEL;
+    }
+
+    flags
+}
+
 pub fn map_descriptor_type(ty: pso::DescriptorType) -> vk::DescriptorType {
     use core::pso::DescriptorType as Dt;
     match ty {
@@ -1,6 +1,6 @@
 use ash::vk;
-use ash::version::DeviceV1_0;
-use core::{buffer, device as d, format, image, mapping, pass, pso};
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use core::{buffer, device as d, format, image, mapping, pass, pso, query};
 use core::{Features, Limits, HeapType};
 use core::memory::Requirements;
 use native as n;
@@ -55,33 +55,6 @@ impl Device {
         }
     }
 
-    pub fn create_shader_library(&mut self,
-        shaders: &[(pso::EntryPoint, &[u8])],
-    ) -> Result<n::ShaderLib, pso::CreateShaderError>
-    {
-        let mut shader_map = BTreeMap::new();
-        // TODO: handle entry points with the same name
-        for &(entry_point, byte_code) in shaders {
-            // TODO
-            // TODO: check code size length (multiple of 4)
-            let info = vk::ShaderModuleCreateInfo {
-                s_type: vk::StructureType::ShaderModuleCreateInfo,
-                p_next: ptr::null(),
-                flags: vk::ShaderModuleCreateFlags::empty(),
-                code_size: byte_code.len(),
-                p_code: byte_code as *const _ as *const u32,
-            };
-
-            let module = unsafe {
-                self.raw.0.create_shader_module(&info, None)
-                            .expect("Error on shader module creation") // TODO: error handling
-            };
-
-            shader_map.insert(entry_point, module);
-        }
-        Ok(n::ShaderLib { shaders: shader_map })
-    }
-
     fn create_image_view(&mut self, image: &n::Image, format: format::Format) -> vk::ImageView {
         // TODO
         let components = vk::ComponentMapping {
@@ -122,6 +95,19 @@ impl d::Device<B> for Device {
     fn get_features(&self) -> &Features { &self.features }
     fn get_limits(&self) -> &Limits { &self.limits }
 
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties {
+        let vk_format = match conv::map_format(format.0, format.1) {
+            Some(format) => format,
+            None => return format::FormatProperties::unsupported(),
+        };
+        let properties = self.instance.0.get_physical_device_format_properties(self.physical_device, vk_format);
+        format::FormatProperties {
+            linear_tiling: conv::map_image_features(properties.linear_tiling_features),
+            optimal_tiling: conv::map_image_features(properties.optimal_tiling_features),
+            buffer_features: conv::map_buffer_features(properties.buffer_features),
+        }
+    }
+
     fn create_heap(&mut self, heap_type: &HeapType, _resource_type: d::ResourceHeapType, size: u64) -> Result<n::Heap, d::ResourceHeapError> {
         let info = vk::MemoryAllocateInfo {
             s_type: vk::StructureType::MemoryAllocateInfo,
@@ -138,6 +124,44 @@ impl d::Device<B> for Device {
         Ok(n::Heap(memory))
     }
 
+    fn create_shader_library(&mut self,
+        shaders: &[(pso::EntryPoint, &[u8])],
+    ) -> Result<n::ShaderLib, pso::CreateShaderError>
+    {
+        let mut shader_map = BTreeMap::new();
+        // TODO: handle entry points with the same name
+        for &(entry_point, byte_code) in shaders {
+            if byte_code.len() % 4 != 0 {
+                let message = format!("SPIR-V code size of {} is not a multiple of 4", byte_code.len());
+                self.destroy_shader_lib(n::ShaderLib { shaders: shader_map });
+                return Err(pso::CreateShaderError::CompilationFailed(message));
+            }
+
+            let info = vk::ShaderModuleCreateInfo {
+                s_type: vk::StructureType::ShaderModuleCreateInfo,
+                p_next: ptr::null(),
+                flags: vk::ShaderModuleCreateFlags::empty(),
+                code_size: byte_code.len(),
+                p_code: byte_code as *const _ as *const u32,
+            };
+
+            let module = unsafe {
+                self.raw.0.create_shader_module(&info, None)
+            };
+
+            match module {
+                Ok(module) => {
+                    shader_map.insert(entry_point, module);
+                }
+                Err(err) => {
+                    self.destroy_shader_lib(n::ShaderLib { shaders: shader_map });
+                    return Err(pso::CreateShaderError::CompilationFailed(format!("{:?}", err)));
+                }
+            }
+        }
+        Ok(n::ShaderLib { shaders: shader_map })
+    }
+
     fn create_renderpass(&mut self, attachments: &[pass::Attachment],
         subpasses: &[pass::SubpassDesc], dependencies: &[pass::SubpassDependency]) -> n::RenderPass
     {
@@ -248,8 +272,63 @@ impl d::Device<B> for Device {
         n::PipelineLayout { raw }
     }
 
+    fn create_pipeline_cache(&mut self, initial_data: &[u8]) -> n::PipelineCache {
+        let info = vk::PipelineCacheCreateInfo {
+            s_type: vk::StructureType::PipelineCacheCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::PipelineCacheCreateFlags::empty(),
+            initial_data_size: initial_data.len(),
+            p_initial_data: initial_data.as_ptr() as *const vk::types::c_void,
+        };
+
+        let mut raw = vk::PipelineCache::null();
+        let result = unsafe {
+            self.raw.0.fp_v1_0().create_pipeline_cache(
+                self.raw.0.handle(),
+                &info,
+                ptr::null(),
+                &mut raw,
+            )
+        };
+        assert_eq!(result, vk::Result::Success, "Error on pipeline cache creation"); // TODO: handle this better
+
+        n::PipelineCache(raw)
+    }
+
+    fn get_pipeline_cache_data(&mut self, cache: &n::PipelineCache) -> Vec<u8> {
+        let fp = self.raw.0.fp_v1_0();
+        let mut size = 0;
+        let mut data = Vec::new();
+        unsafe {
+            // Query the size first, the cache may grow in between, in which case the data is truncated.
+            fp.get_pipeline_cache_data(self.raw.0.handle(), cache.0, &mut size, ptr::null_mut());
+            data.resize(size, 0);
+            fp.get_pipeline_cache_data(
+                self.raw.0.handle(),
+                cache.0,
+                &mut size,
+                data.as_mut_ptr() as *mut vk::types::c_void,
+            );
+        }
+        data.truncate(size);
+        data
+    }
+
+    fn merge_pipeline_caches(&mut self, target: &n::PipelineCache, sources: &[&n::PipelineCache]) {
+        let sources = sources.iter().map(|cache| cache.0).collect::<Vec<_>>();
+        unsafe {
+            self.raw.0.fp_v1_0().merge_pipeline_caches(
+                self.raw.0.handle(),
+                target.0,
+                sources.len() as u32,
+                sources.as_ptr(),
+            );
+        }
+    }
+
     fn create_graphics_pipelines<'a>(&mut self,
         descs: &[(&n::ShaderLib, &n::PipelineLayout, pass::Subpass<'a, B>, &pso::GraphicsPipelineDesc)],
+        cache: Option<&n::PipelineCache>,
     ) -> Vec<Result<n::GraphicsPipeline, pso::CreationError>>
     {
         use core::state as s;
@@ -563,7 +642,7 @@ impl d::Device<B> for Device {
         } else {
             unsafe {
                 self.raw.0.create_graphics_pipelines(
-                    vk::PipelineCache::null(),
+                    cache.map_or(vk::PipelineCache::null(), |cache| cache.0),
                     &valid_infos,
                     None,
                 )
@@ -606,6 +685,7 @@ impl d::Device<B> for Device {
 
     fn create_compute_pipelines(&mut self,
         descs: &[(&n::ShaderLib, pso::EntryPoint, &n::PipelineLayout)],
+        cache: Option<&n::PipelineCache>,
     ) -> Vec<Result<n::ComputePipeline, pso::CreationError>>
     {
         let infos = descs.iter().map(|&(shader_lib, entry_point, layout)| {
@@ -638,7 +718,7 @@ impl d::Device<B> for Device {
         } else {
             unsafe {
                 self.raw.0.create_compute_pipelines(
-                    vk::PipelineCache::null(),
+                    cache.map_or(vk::PipelineCache::null(), |cache| cache.0),
                     &valid_infos,
                     None,
                 )
@@ -857,7 +937,11 @@ impl d::Device<B> for Device {
             ),
         };
 
-        let bytes_per_texel = format.0.get_total_bits() / 8;
+        if format.0.is_compressed() && !kind.supports_compression() {
+            return Err(image::CreationError::Kind);
+        }
+        let bytes_per_block = format.0.get_block_size();
+        let block_dim = format.0.get_block_dimensions();
         let samples = match aa_mode {
             image::AaMode::Single => vk::SAMPLE_COUNT_1_BIT,
             _ => unimplemented!(),
@@ -886,7 +970,7 @@ impl d::Device<B> for Device {
                       .expect("Error on image creation") // TODO: error handling
         };
 
-        Ok(UnboundImage(n::Image{ raw, bytes_per_texel, extent }))
+        Ok(UnboundImage(n::Image{ raw, bytes_per_block, block_dim, extent }))
     }
 
     ///
@@ -1204,6 +1288,55 @@ impl d::Device<B> for Device {
         }
     }
 
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> n::QueryPool {
+        let (query_type, pipeline_statistics) = conv::map_query_type(ty);
+        let info = vk::QueryPoolCreateInfo {
+            s_type: vk::StructureType::QueryPoolCreateInfo,
+            p_next: ptr::null(),
+            flags: vk::QueryPoolCreateFlags::empty(),
+            query_type,
+            query_count: count,
+            pipeline_statistics,
+        };
+
+        let mut raw = vk::QueryPool::null();
+        let result = unsafe {
+            self.raw.0.fp_v1_0().create_query_pool(
+                self.raw.0.handle(),
+                &info,
+                ptr::null(),
+                &mut raw,
+            )
+        };
+        assert_eq!(result, vk::Result::Success, "Error on query pool creation"); // TODO: handle this better
+
+        n::QueryPool { raw, ty }
+    }
+
+    fn get_query_pool_results(&mut self, pool: &n::QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool {
+        let num_values = pool.ty.num_values() + flags.contains(query::WITH_AVAILABILITY) as usize;
+        let count = queries.end - queries.start;
+        assert!(data.len() >= count as usize * num_values);
+        let result = unsafe {
+            self.raw.0.fp_v1_0().get_query_pool_results(
+                self.raw.0.handle(),
+                pool.raw,
+                queries.start,
+                count,
+                data.len() * mem::size_of::<u64>(),
+                data.as_mut_ptr() as *mut vk::types::c_void,
+                (num_values * mem::size_of::<u64>()) as vk::DeviceSize,
+                conv::map_query_result_flags(flags),
+            )
+        };
+        match result {
+            vk::Result::Success => true,
+            vk::Result::NotReady => false,
+            _ => panic!("Unexpected query result {:?}", result),
+        }
+    }
+
     fn destroy_heap(&mut self, heap: n::Heap) {
         unsafe { self.raw.0.free_memory(heap.0, None); }
     }
@@ -1230,6 +1363,12 @@ impl d::Device<B> for Device {
         unsafe { self.raw.0.destroy_pipeline(pipeline.0, None); }
     }
 
+    fn destroy_pipeline_cache(&mut self, cache: n::PipelineCache) {
+        unsafe {
+            self.raw.0.fp_v1_0().destroy_pipeline_cache(self.raw.0.handle(), cache.0, ptr::null());
+        }
+    }
+
     fn destroy_framebuffer(&mut self, fb: n::FrameBuffer) {
         unsafe { self.raw.0.destroy_framebuffer(fb.raw, None); }
     }
@@ -1289,4 +1428,10 @@ impl d::Device<B> for Device {
     fn destroy_semaphore(&mut self, semaphore: n::Semaphore) {
         unsafe { self.raw.0.destroy_semaphore(semaphore.0, None); }
     }
+
+    fn destroy_query_pool(&mut self, pool: n::QueryPool) {
+        unsafe {
+            self.raw.0.fp_v1_0().destroy_query_pool(self.raw.0.handle(), pool.raw, ptr::null());
+        }
+    }
 }
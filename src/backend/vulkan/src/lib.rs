@@ -320,6 +320,7 @@ fn collect_queues<C>(
         .filter(|&&(_, qtype, _)| qtype == collect_type)
         .flat_map(|&(qfamily, _, qcount)| {
             let family_index = qfamily.family_index;
+            let physical_device = qfamily.device;
             (0..qcount).map(move |id| {
                 let queue_raw = unsafe {
                     device_raw.0.get_device_queue(family_index, id)
@@ -327,6 +328,7 @@ fn collect_queues<C>(
                 let queue = CommandQueue {
                     raw: Arc::new(queue_raw),
                     device: device_raw.clone(),
+                    physical_device,
                     family_index,
                 };
                 unsafe {
@@ -364,6 +366,8 @@ impl core::Adapter<Backend> for Adapter {
                 }
             }).collect::<Vec<_>>();
 
+        let available_features = self.instance.0.get_physical_device_features(self.handle);
+
         // Create device
         let device_raw = {
             let cstrings = DEVICE_EXTENSIONS
@@ -376,7 +380,10 @@ impl core::Adapter<Backend> for Adapter {
                 .map(|s| s.as_ptr())
                 .collect::<Vec<_>>();
 
-            let features = unsafe { mem::zeroed() };
+            // Only enable the features exposed through `Features`, which may affect performance otherwise.
+            let mut features: vk::PhysicalDeviceFeatures = unsafe { mem::zeroed() };
+            features.occlusion_query_precise = available_features.occlusion_query_precise;
+            features.pipeline_statistics_query = available_features.pipeline_statistics_query;
             let info = vk::DeviceCreateInfo {
                 s_type: vk::StructureType::DeviceCreateInfo,
                 p_next: ptr::null(),
@@ -399,6 +406,8 @@ impl core::Adapter<Backend> for Adapter {
 
         let device = Device {
             raw: Arc::new(RawDevice(device_raw)),
+            instance: self.instance.clone(),
+            physical_device: self.handle,
             features: Features { //TODO
                 indirect_execution: limits.max_draw_indirect_count != 0,
                 draw_instanced: false,
@@ -418,6 +427,9 @@ impl core::Adapter<Backend> for Adapter {
                 sampler_border_color: false,
                 sampler_lod_bias: false,
                 sampler_objects: false,
+                precise_occlusion_query: available_features.occlusion_query_precise != 0,
+                pipeline_statistics_query: available_features.pipeline_statistics_query != 0,
+                timestamp_query: limits.timestamp_compute_and_graphics != 0,
             },
             limits: Limits {
                 max_texture_size: limits.max_image_dimension3d as usize,
@@ -425,6 +437,8 @@ impl core::Adapter<Backend> for Adapter {
                 max_viewports: limits.max_viewports as usize,
                 min_buffer_copy_offset_alignment: limits.optimal_buffer_copy_offset_alignment as usize,
                 min_buffer_copy_pitch_alignment: limits.optimal_buffer_copy_row_pitch_alignment as usize,
+                min_constant_buffer_offset_alignment: limits.min_uniform_buffer_offset_alignment as usize,
+                timestamp_period: limits.timestamp_period,
             },
         };
 
@@ -497,6 +511,7 @@ pub type RawCommandQueue = Arc<vk::Queue>;
 pub struct CommandQueue {
     raw: RawCommandQueue,
     device: Arc<RawDevice>,
+    physical_device: vk::PhysicalDevice,
     family_index: u32,
 }
 
@@ -511,6 +526,11 @@ impl CommandQueue {
         self.device.clone()
     }
 
+    #[doc(hidden)]
+    pub fn physical_device(&self) -> vk::PhysicalDevice {
+        self.physical_device
+    }
+
     #[doc(hidden)]
     pub fn device_handle(&self) -> vk::Device {
         self.device.0.handle()
@@ -563,6 +583,8 @@ impl core::RawCommandQueue<Backend> for CommandQueue {
 
 pub struct Device {
     raw: Arc<RawDevice>,
+    instance: Arc<RawInstance>,
+    physical_device: vk::PhysicalDevice,
     features: Features,
     limits: Limits,
 }
@@ -604,11 +626,14 @@ impl core::Backend for Backend {
 
     type ComputePipeline = native::ComputePipeline;
     type GraphicsPipeline = native::GraphicsPipeline;
+    type PipelineCache = native::PipelineCache;
     type PipelineLayout = native::PipelineLayout;
     type DescriptorSetLayout = native::DescriptorSetLayout;
     type DescriptorPool = native::DescriptorPool;
     type DescriptorSet = native::DescriptorSet;
 
+    type QueryPool = native::QueryPool;
+
     type Fence = native::Fence;
     type Semaphore = native::Semaphore;
 }
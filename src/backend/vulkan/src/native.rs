@@ -19,6 +19,15 @@ pub struct GraphicsPipeline(pub vk::Pipeline);
 #[derive(Debug, Hash)]
 pub struct ComputePipeline(pub vk::Pipeline);
 
+#[derive(Debug, Hash)]
+pub struct PipelineCache(pub vk::PipelineCache);
+
+#[derive(Debug, Hash)]
+pub struct QueryPool {
+    pub raw: vk::QueryPool,
+    pub ty: core::query::QueryType,
+}
+
 #[derive(Debug, Hash)]
 pub struct Heap(pub vk::DeviceMemory);
 
@@ -31,7 +40,9 @@ pub struct Buffer {
 #[derive(Debug, Hash, PartialEq, Eq)]
 pub struct Image {
     pub raw: vk::Image,
-    pub bytes_per_texel: u8,
+    pub bytes_per_block: u8,
+    /// Width and height of the blocks of a compressed format, or `(1, 1)`.
+    pub block_dim: (u8, u8),
     pub extent: vk::Extent3D,
 }
 
@@ -1,14 +1,15 @@
 use std::collections::VecDeque;
-use std::ptr;
+use std::{mem, ptr};
 use std::sync::Arc;
 
 use ash::vk;
 use ash::extensions as ext;
+use ash::version::InstanceV1_0;
 
 use {core, winit};
 
 use {conv, native};
-use {VK_ENTRY, Backend, Instance, QueueFamily, RawInstance};
+use {VK_ENTRY, Adapter, Backend, Instance, QueueFamily, RawInstance};
 
 
 pub struct Surface {
@@ -117,16 +118,110 @@ impl core::Surface<Backend> for Surface {
         )
     }
 
+    fn get_capabilities(&self, adapter: &Adapter) -> core::SurfaceCapabilities {
+        let caps = self.raw.functor
+            .get_physical_device_surface_capabilities_khr(adapter.handle, self.raw.handle)
+            .expect("Unable to query surface capabilities");
+        let formats = self.raw.functor
+            .get_physical_device_surface_formats_khr(adapter.handle, self.raw.handle)
+            .expect("Unable to query surface formats");
+        let present_modes = self.raw.functor
+            .get_physical_device_surface_present_modes_khr(adapter.handle, self.raw.handle)
+            .expect("Unable to query surface present modes");
+
+        // A single undefined format indicates that there are no restrictions.
+        let formats = match formats.first() {
+            Some(format) if formats.len() == 1 && format.format == vk::Format::Undefined => None,
+            _ => Some(formats.iter().filter_map(|sf| conv::map_vk_format(sf.format)).collect()),
+        };
+
+        core::SurfaceCapabilities {
+            formats,
+            min_image_count: caps.min_image_count,
+            max_image_count: if caps.max_image_count == 0 { None } else { Some(caps.max_image_count) },
+            current_extent: if caps.current_extent.width == !0 {
+                None
+            } else {
+                Some(core::Extent2D {
+                    width: caps.current_extent.width,
+                    height: caps.current_extent.height,
+                })
+            },
+            present_modes: present_modes
+                .into_iter()
+                .map(conv::map_vk_present_mode)
+                .collect(),
+        }
+    }
+
     fn build_swapchain<C>(
         &mut self,
         config: core::SwapchainConfig,
         present_queue: &core::CommandQueue<Backend, C>,
     ) -> Swapchain {
-        let functor = ext::Swapchain::new(&self.raw.instance.0, &present_queue.as_raw().device().0)
+        self.create_swapchain(config, present_queue, None)
+    }
+
+    fn recreate_swapchain<C>(
+        &mut self,
+        old_swapchain: Swapchain,
+        config: core::SwapchainConfig,
+        present_queue: &core::CommandQueue<Backend, C>,
+    ) -> Swapchain {
+        self.create_swapchain(config, present_queue, Some(old_swapchain))
+    }
+}
+
+impl Surface {
+    fn create_swapchain<C>(
+        &mut self,
+        config: core::SwapchainConfig,
+        present_queue: &core::CommandQueue<Backend, C>,
+        old_swapchain: Option<Swapchain>,
+    ) -> Swapchain {
+        let instance = &self.raw.instance.0;
+        let device = &present_queue.as_raw().device().0;
+        let functor = ext::Swapchain::new(instance, device)
             .expect("Unable to query swapchain function");
+        // The function table of `functor` isn't accessible, but acquiring needs the raw call
+        // to get the image index of suboptimal acquisitions.
+        let fp = vk::SwapchainFn::load(|name| unsafe {
+            mem::transmute(instance.get_device_proc_addr(device.handle(), name.as_ptr()))
+        }).expect("Unable to query swapchain function");
+        let physical_device = present_queue.as_raw().physical_device();
+
+        let caps = self.raw.functor
+            .get_physical_device_surface_capabilities_khr(physical_device, self.raw.handle)
+            .expect("Unable to query surface capabilities");
+        let present_modes = self.raw.functor
+            .get_physical_device_surface_present_modes_khr(physical_device, self.raw.handle)
+            .expect("Unable to query surface present modes");
+
+        let present_mode = conv::map_present_mode(config.present_mode);
+        let present_mode = if present_modes.contains(&present_mode) {
+            present_mode
+        } else {
+            vk::PresentModeKHR::Fifo // required to be supported
+        };
 
-        // TODO: check for better ones if available
-        let present_mode = vk::PresentModeKHR::Fifo; // required to be supported
+        let mut image_count = config.image_count.max(caps.min_image_count);
+        if caps.max_image_count != 0 {
+            image_count = image_count.min(caps.max_image_count);
+        }
+
+        // The surface dictates the extent, unless it reports the special value.
+        let extent = if caps.current_extent.width != !0 {
+            caps.current_extent
+        } else {
+            let (width, height) = config.extent
+                .map_or((self.width, self.height), |extent| (extent.width, extent.height));
+            vk::Extent2D {
+                width: width.max(caps.min_image_extent.width).min(caps.max_image_extent.width),
+                height: height.max(caps.min_image_extent.height).min(caps.max_image_extent.height),
+            }
+        };
+        self.width = extent.width;
+        self.height = extent.height;
 
         // TODO: handle depth stencil
         let format = config.color_format;
@@ -136,13 +231,10 @@ impl core::Surface<Backend> for Surface {
             p_next: ptr::null(),
             flags: vk::SwapchainCreateFlagsKHR::empty(),
             surface: self.raw.handle,
-            min_image_count: 2, // TODO: let the user specify the value
+            min_image_count: image_count,
             image_format: conv::map_format(format.0, format.1).unwrap(),
             image_color_space: vk::ColorSpaceKHR::SrgbNonlinear,
-            image_extent: vk::Extent2D {
-                width: self.width,
-                height: self.height,
-            },
+            image_extent: extent,
             image_array_layers: 1,
             image_usage: vk::IMAGE_USAGE_COLOR_ATTACHMENT_BIT,
             image_sharing_mode: vk::SharingMode::Exclusive,
@@ -152,12 +244,19 @@ impl core::Surface<Backend> for Surface {
             composite_alpha: vk::COMPOSITE_ALPHA_OPAQUE_BIT_KHR,
             present_mode: present_mode,
             clipped: 1,
-            old_swapchain: vk::SwapchainKHR::null(),
+            old_swapchain: old_swapchain
+                .as_ref()
+                .map_or(vk::SwapchainKHR::null(), |swapchain| swapchain.raw),
         };
 
         let swapchain = unsafe { functor.create_swapchain_khr(&info, None) }
             .expect("Unable to create a swapchain");
 
+        // The old swapchain is retired by the creation of the new one.
+        if let Some(old) = old_swapchain {
+            unsafe { old.functor.destroy_swapchain_khr(old.raw, None) };
+        }
+
         let backbuffer_images = { functor.get_swapchain_images_khr(swapchain) }
             .expect("Unable to get swapchain images");
 
@@ -167,10 +266,11 @@ impl core::Surface<Backend> for Surface {
                 core::Backbuffer {
                     color: native::Image {
                         raw: image,
-                        bytes_per_texel: 4,
+                        bytes_per_block: 4,
+                        block_dim: (1, 1),
                         extent: vk::Extent3D {
-                            width: self.width,
-                            height: self.height,
+                            width: extent.width,
+                            height: extent.height,
                             depth: 1,
                         },
                     },
@@ -181,7 +281,9 @@ impl core::Surface<Backend> for Surface {
 
         Swapchain {
             raw: swapchain,
+            device: device.handle(),
             functor,
+            fp,
             backbuffers,
             frame_queue: VecDeque::new(),
         }
@@ -190,7 +292,9 @@ impl core::Surface<Backend> for Surface {
 
 pub struct Swapchain {
     raw: vk::SwapchainKHR,
+    device: vk::Device,
     functor: ext::Swapchain,
+    fp: vk::SwapchainFn,
     backbuffers: Vec<core::Backbuffer<Backend>>,
     // Queued up frames for presentation
     frame_queue: VecDeque<usize>,
@@ -202,26 +306,41 @@ impl core::Swapchain<Backend> for Swapchain {
         &self.backbuffers
     }
 
-    fn acquire_frame(&mut self, sync: core::FrameSync<Backend>) -> core::Frame {
+    fn acquire_frame(&mut self, sync: core::FrameSync<Backend>) -> Result<core::Frame, core::AcquireError> {
         let (semaphore, fence) = match sync {
             core::FrameSync::Semaphore(semaphore) => (semaphore.0, vk::Fence::null()),
             core::FrameSync::Fence(fence) => (vk::Semaphore::null(), fence.0),
         };
 
-        let index = unsafe {
+        let mut index = 0;
+        let result = unsafe {
             // will block if no image is available
-            self.functor.acquire_next_image_khr(self.raw, !0, semaphore, fence)
-        }.expect("Unable to acquire a swapchain image");
+            self.fp.acquire_next_image_khr(self.device, self.raw, !0, semaphore, fence, &mut index)
+        };
+        // A suboptimal image is still acquired and `sync` will be signaled.
+        let suboptimal = match result {
+            vk::Result::Success => false,
+            vk::Result::SuboptimalKhr => true,
+            vk::Result::ErrorOutOfDateKhr => return Err(core::AcquireError::OutOfDate),
+            vk::Result::ErrorSurfaceLostKhr => return Err(core::AcquireError::SurfaceLost),
+            vk::Result::Timeout => return Err(core::AcquireError::Timeout),
+            vk::Result::NotReady => return Err(core::AcquireError::NotReady),
+            err => panic!("Unable to acquire a swapchain image: {:?}", err),
+        };
 
         self.frame_queue.push_back(index as usize);
-        core::Frame::new(index as usize)
+        Ok(if suboptimal {
+            core::Frame::new_suboptimal(index as usize)
+        } else {
+            core::Frame::new(index as usize)
+        })
     }
 
     fn present<C>(
         &mut self,
         present_queue: &mut core::CommandQueue<Backend, C>,
         wait_semaphores: &[&native::Semaphore],
-    ) {
+    ) -> Result<core::Suboptimal, core::PresentError> {
         let frame = self.frame_queue.pop_front().expect(
             "No frame currently queued up. Need to acquire a frame first.",
         );
@@ -240,10 +359,16 @@ impl core::Swapchain<Backend> for Swapchain {
             p_results: ptr::null_mut(),
         };
 
-        assert_eq!(Ok(()), unsafe {
+        let result = unsafe {
             self.functor
                 .queue_present_khr(*present_queue.as_raw().raw(), &info)
-        });
-        // TODO: handle result and return code
+        };
+        match result {
+            Ok(()) => Ok(false),
+            Err(vk::Result::SuboptimalKhr) => Ok(true),
+            Err(vk::Result::ErrorOutOfDateKhr) => Err(core::PresentError::OutOfDate),
+            Err(vk::Result::ErrorSurfaceLostKhr) => Err(core::PresentError::SurfaceLost),
+            Err(err) => panic!("Unable to present a swapchain image: {:?}", err),
+        }
     }
 }
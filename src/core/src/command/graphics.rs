@@ -1,9 +1,12 @@
-use {pso, target};
+use std::ops::Range;
+use {image, pso, target};
 use {Backend, Viewport};
 use buffer::IndexBufferView;
 use image::ImageLayout;
+use memory::Barrier;
+use query::{Query, QueryControl, QueryId, ResultFlags};
 use queue::capability::{Graphics, Supports};
-use super::{CommandBuffer, RawCommandBuffer, RenderPassInlineEncoder};
+use super::{CommandBuffer, ImageBlit, Offset, RawCommandBuffer, RenderPassInlineEncoder};
 
 
 /// A universal clear color supporting integet formats
@@ -160,4 +163,127 @@ impl<'a, B: Backend, C: Supports<Graphics>> CommandBuffer<'a, B, C> {
     pub fn set_blend_constants(&mut self, cv: target::ColorValue) {
         self.raw.set_blend_constants(cv)
     }
+
+    ///
+    pub fn blit_image(
+        &mut self,
+        src: &B::Image,
+        src_layout: ImageLayout,
+        dst: &B::Image,
+        dst_layout: ImageLayout,
+        regions: &[ImageBlit],
+        filter: image::FilterMethod,
+    ) {
+        self.raw.blit_image(src, src_layout, dst, dst_layout, regions, filter)
+    }
+
+    ///
+    pub fn begin_query(&mut self, query: Query<B>, flags: QueryControl) {
+        self.raw.begin_query(query, flags)
+    }
+
+    ///
+    pub fn end_query(&mut self, query: Query<B>) {
+        self.raw.end_query(query)
+    }
+
+    ///
+    pub fn reset_query_pool(&mut self, pool: &B::QueryPool, queries: Range<QueryId>) {
+        self.raw.reset_query_pool(pool, queries)
+    }
+
+    ///
+    pub fn write_timestamp(&mut self, stage: pso::PipelineStage, query: Query<B>) {
+        self.raw.write_timestamp(stage, query)
+    }
+
+    ///
+    pub fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: Range<QueryId>,
+        buffer: &B::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: ResultFlags,
+    ) {
+        self.raw.copy_query_pool_results(pool, queries, buffer, offset, stride, flags)
+    }
+
+    /// Generate the mip `levels` of a color image, blitting each of them from the level before.
+    ///
+    /// `kind` is the kind the image has been created with. All levels from the one preceding
+    /// `levels` on have to be in `state`, they are transitioned back into it at the end.
+    pub fn generate_mipmaps(
+        &mut self,
+        image: &B::Image,
+        kind: image::Kind,
+        levels: Range<image::Level>,
+        state: image::State,
+    ) {
+        assert!(levels.start > 0, "The base level can't be generated");
+        if levels.start >= levels.end {
+            return;
+        }
+        let faces = if kind.is_cube() { 6 } else { 1 };
+        let layers = 0 .. kind.get_num_slices().unwrap_or(1) * faces;
+        let src_state = (image::TRANSFER_READ, ImageLayout::TransferSrcOptimal);
+        let dst_state = (image::TRANSFER_WRITE, ImageLayout::TransferDstOptimal);
+        let all_stages = pso::PipelineStage::all();
+
+        self.raw.pipeline_barrier(all_stages .. pso::TRANSFER, &[
+            Barrier::Image {
+                states: state .. src_state,
+                target: image,
+                range: (levels.start - 1 .. levels.start, layers.clone()),
+            },
+            Barrier::Image {
+                states: state .. dst_state,
+                target: image,
+                range: (levels.clone(), layers.clone()),
+            },
+        ]);
+        for level in levels.clone() {
+            if level > levels.start {
+                // The source level has been written by the previous blit.
+                self.raw.pipeline_barrier(pso::TRANSFER .. pso::TRANSFER, &[Barrier::Image {
+                    states: dst_state .. src_state,
+                    target: image,
+                    range: (level - 1 .. level, layers.clone()),
+                }]);
+            }
+            let origin = Offset { x: 0, y: 0, z: 0 };
+            let region = ImageBlit {
+                aspect_mask: image::ASPECT_COLOR,
+                src_subresource: (level - 1, layers.clone()),
+                src_bounds: origin .. level_bounds(kind, level - 1),
+                dst_subresource: (level, layers.clone()),
+                dst_bounds: origin .. level_bounds(kind, level),
+            };
+            self.raw.blit_image(image, ImageLayout::TransferSrcOptimal, image, ImageLayout::TransferDstOptimal,
+                                &[region], image::FilterMethod::Bilinear);
+        }
+        self.raw.pipeline_barrier(pso::TRANSFER .. all_stages, &[
+            Barrier::Image {
+                states: src_state .. state,
+                target: image,
+                range: (levels.start - 1 .. levels.end - 1, layers.clone()),
+            },
+            Barrier::Image {
+                states: dst_state .. state,
+                target: image,
+                range: (levels.end - 1 .. levels.end, layers),
+            },
+        ]);
+    }
+}
+
+/// Exclusive upper bounds of a mip level, layers of array images are not counted.
+fn level_bounds(kind: image::Kind, level: image::Level) -> Offset {
+    let (width, height, depth, _) = kind.get_level_dimensions(level);
+    Offset {
+        x: width.max(1) as i32,
+        y: height.max(1) as i32,
+        z: depth.max(1) as i32,
+    }
 }
@@ -7,11 +7,13 @@ mod compute;
 mod graphics;
 mod raw;
 mod renderpass;
+mod tracker;
 mod transfer;
 
 pub use self::graphics::*;
 pub use self::raw::RawCommandBuffer;
 pub use self::renderpass::*;
+pub use self::tracker::{StateTracker, Transitions};
 pub use self::transfer::*;
 
 /// Thread-safe finished command buffer for submission.
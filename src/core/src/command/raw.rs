@@ -3,9 +3,10 @@ use std::ops::Range;
 use {pso, target};
 use {Backend, IndexCount, InstanceCount, VertexCount, VertexOffset, Viewport};
 use buffer::IndexBufferView;
-use image::ImageLayout;
+use image::{FilterMethod, ImageLayout};
 use memory::Barrier;
-use super::{BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageCopy, ImageResolve,
+use query::{Query, QueryControl, QueryId, ResultFlags};
+use super::{BufferCopy, BufferImageCopy, ClearColor, ClearValue, ImageBlit, ImageCopy, ImageResolve,
     SubpassContents};
 
 ///
@@ -69,6 +70,20 @@ pub trait RawCommandBuffer<B: Backend>: Clone + Send {
         regions: &[ImageResolve],
     );
 
+    /// Copy regions of `src` into regions of `dst`, scaling and converting the texels.
+    ///
+    /// Only the interpolation within a level is taken from `filter`: `Scale` and `Mipmap`
+    /// pick the nearest texel, all other methods filter linearly.
+    fn blit_image(
+        &mut self,
+        src: &B::Image,
+        src_layout: ImageLayout,
+        dst: &B::Image,
+        dst_layout: ImageLayout,
+        regions: &[ImageBlit],
+        filter: FilterMethod,
+    );
+
     /// Bind index buffer view.
     fn bind_index_buffer(&mut self, IndexBufferView<B>);
 
@@ -210,4 +225,35 @@ pub trait RawCommandBuffer<B: Backend>: Clone + Send {
         draw_count: u32,
         stride: u32,
     );
+
+    /// Begin a query, which counts until the matching `end_query`.
+    ///
+    /// The query has to be reset beforehand, see `reset_query_pool`.
+    /// Only one query of each type can be active at a time.
+    fn begin_query(&mut self, query: Query<B>, flags: QueryControl);
+
+    /// End a query started with `begin_query`, making its result available
+    /// once the command has completed.
+    fn end_query(&mut self, query: Query<B>);
+
+    /// Reset a range of queries of a pool, making their results unavailable.
+    fn reset_query_pool(&mut self, pool: &B::QueryPool, queries: Range<QueryId>);
+
+    /// Write the point in time at which all previous commands have completed `stage`
+    /// into a timestamp query, which has to be reset beforehand.
+    fn write_timestamp(&mut self, stage: pso::PipelineStage, query: Query<B>);
+
+    /// Copy the results of a range of queries into a buffer.
+    ///
+    /// The values of each query are written as 64-bit integers, starting `stride` bytes
+    /// after the ones of the previous query. See `Device::get_query_pool_results`.
+    fn copy_query_pool_results(
+        &mut self,
+        pool: &B::QueryPool,
+        queries: Range<QueryId>,
+        buffer: &B::Buffer,
+        offset: u64,
+        stride: u64,
+        flags: ResultFlags,
+    );
 }
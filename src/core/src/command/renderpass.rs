@@ -1,11 +1,14 @@
 use std::ops::Range;
-use {pso, target, Backend, IndexCount, InstanceCount, VertexCount, VertexOffset};
+use {pso, target, Backend, IndexCount, InstanceCount, VertexCount, VertexOffset, Viewport};
 use buffer::IndexBufferView;
+use query::{Query, QueryControl};
 use queue::{Supports, Graphics};
 use super::{ClearValue, CommandBuffer, RawCommandBuffer};
 
 
 /// Specifies how commands for the following renderpasses will be recorded.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum SubpassContents {
     ///
     Inline,
@@ -78,6 +81,51 @@ impl<'a, B: Backend> RenderPassInlineEncoder<'a, B> {
     pub fn bind_graphics_pipeline(&mut self, pipeline: &B::GraphicsPipeline) {
         self.0.bind_graphics_pipeline(pipeline)
     }
+
+    ///
+    pub fn bind_graphics_descriptor_sets(
+        &mut self,
+        layout: &B::PipelineLayout,
+        first_set: usize,
+        sets: &[&B::DescriptorSet],
+    ) {
+        self.0.bind_graphics_descriptor_sets(layout, first_set, sets)
+    }
+
+    ///
+    pub fn set_viewports(&mut self, viewports: &[Viewport]) {
+        self.0.set_viewports(viewports)
+    }
+
+    ///
+    pub fn set_scissors(&mut self, scissors: &[target::Rect]) {
+        self.0.set_scissors(scissors)
+    }
+
+    ///
+    pub fn set_stencil_reference(&mut self, front: target::Stencil, back: target::Stencil) {
+        self.0.set_stencil_reference(front, back)
+    }
+
+    ///
+    pub fn set_blend_constants(&mut self, cv: target::ColorValue) {
+        self.0.set_blend_constants(cv)
+    }
+
+    ///
+    pub fn begin_query(&mut self, query: Query<B>, flags: QueryControl) {
+        self.0.begin_query(query, flags)
+    }
+
+    ///
+    pub fn end_query(&mut self, query: Query<B>) {
+        self.0.end_query(query)
+    }
+
+    ///
+    pub fn write_timestamp(&mut self, stage: pso::PipelineStage, query: Query<B>) {
+        self.0.write_timestamp(stage, query)
+    }
 }
 
 impl<'a, B: Backend> Drop for RenderPassInlineEncoder<'a, B> {
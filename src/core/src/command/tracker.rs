@@ -0,0 +1,273 @@
+use std::cmp;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::ops::Range;
+use {buffer, image, Backend};
+use image::ImageLayout;
+use memory::Barrier;
+use pso::{self, PipelineStage};
+use queue::capability::{Supports, Transfer};
+use super::{CommandBuffer, RawCommandBuffer};
+
+/// Last use of a buffer range or image subresource.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Usage<S> {
+    state: S,
+    stages: PipelineStage,
+}
+
+fn buffer_writes(access: buffer::Access) -> bool {
+    access.intersects(buffer::TRANSFER_WRITE)
+}
+
+fn image_writes(access: image::Access) -> bool {
+    access.intersects(
+        image::COLOR_ATTACHMENT_WRITE | image::TRANSFER_WRITE |
+        image::RENDER_TARGET_CLEAR | image::RESOLVE_DST
+    )
+}
+
+/// Tracks the states of buffers and images used by recorded commands.
+///
+/// Instead of specifying full state transitions with `memory::Barrier`,
+/// users declare how a resource will be used next and the tracker generates
+/// the required barriers. Consecutive reads don't require barriers, so
+/// these are merged into a single state.
+///
+/// Resources are identified by user provided keys. The tracker assumes that
+/// commands are executed in the order they're tracked in, across all command
+/// buffers recorded with it.
+pub struct StateTracker<K> {
+    /// Sorted, non-overlapping buffer ranges.
+    buffers: HashMap<K, Vec<(Range<u64>, Usage<buffer::State>)>>,
+    images: HashMap<K, HashMap<image::Subresource, Usage<image::State>>>,
+}
+
+impl<K: Hash + Eq> StateTracker<K> {
+    /// Create an empty tracker.
+    ///
+    /// Untracked buffers are assumed to be unused, untracked images
+    /// to be in the `Undefined` layout.
+    pub fn new() -> Self {
+        StateTracker {
+            buffers: HashMap::new(),
+            images: HashMap::new(),
+        }
+    }
+
+    /// Set the state of image subresources without generating barriers.
+    ///
+    /// Used for images transitioned outside of the tracker, e.g. swapchain
+    /// backbuffers in the `Present` layout.
+    pub fn set_image_state(&mut self, key: K, range: image::SubresourceRange, state: image::State) {
+        let subresources = self.images.entry(key).or_insert_with(HashMap::new);
+        for level in range.0.clone() {
+            for layer in range.1.clone() {
+                subresources.insert((level, layer), Usage {
+                    state,
+                    stages: pso::BOTTOM_OF_PIPE,
+                });
+            }
+        }
+    }
+
+    /// Stop tracking a buffer, e.g. after destroying it.
+    pub fn remove_buffer(&mut self, key: &K) {
+        self.buffers.remove(key);
+    }
+
+    /// Stop tracking an image, e.g. after destroying it.
+    pub fn remove_image(&mut self, key: &K) {
+        self.images.remove(key);
+    }
+
+    /// Start declaring the next usages of resources.
+    pub fn transitions<'a, 'b, B: Backend>(&'a mut self) -> Transitions<'a, 'b, B, K> {
+        Transitions {
+            tracker: self,
+            src_stages: PipelineStage::empty(),
+            dst_stages: PipelineStage::empty(),
+            barriers: Vec::new(),
+        }
+    }
+}
+
+impl<K: Hash + Eq> Default for StateTracker<K> {
+    fn default() -> Self {
+        StateTracker::new()
+    }
+}
+
+/// Set of usages declared at the same point of a command buffer.
+///
+/// All required barriers are recorded with a single `pipeline_barrier` call.
+pub struct Transitions<'a, 'b, B: Backend, K: 'a> {
+    tracker: &'a mut StateTracker<K>,
+    src_stages: PipelineStage,
+    dst_stages: PipelineStage,
+    barriers: Vec<Barrier<'b, B>>,
+}
+
+impl<'a, 'b, B: Backend, K: Hash + Eq> Transitions<'a, 'b, B, K> {
+    /// Declare the next access of a buffer range by the given pipeline stages.
+    pub fn buffer(
+        &mut self,
+        key: K,
+        buffer: &'b B::Buffer,
+        range: Range<u64>,
+        access: buffer::Access,
+        stages: PipelineStage,
+    ) {
+        let segments = self.tracker.buffers.entry(key).or_insert_with(Vec::new);
+
+        // Split the tracked ranges into the parts outside and inside of `range`,
+        // untracked gaps inside of `range` are unused.
+        let mut outside = Vec::new();
+        let mut inside = Vec::new();
+        let mut offset = range.start;
+        for (r, usage) in segments.drain(..) {
+            if r.end <= range.start || r.start >= range.end {
+                outside.push((r, usage));
+                continue;
+            }
+            if r.start < range.start {
+                outside.push((r.start .. range.start, usage));
+            }
+            if r.end > range.end {
+                outside.push((range.end .. r.end, usage));
+            }
+            let start = cmp::max(r.start, range.start);
+            if offset < start {
+                inside.push((offset .. start, None));
+            }
+            offset = cmp::min(r.end, range.end);
+            inside.push((start .. offset, Some(usage)));
+        }
+        if offset < range.end {
+            inside.push((offset .. range.end, None));
+        }
+
+        for (r, prev) in inside {
+            let next = match prev {
+                Some(prev) if !buffer_writes(prev.state | access) => Usage {
+                    state: prev.state | access,
+                    stages: prev.stages | stages,
+                },
+                Some(prev) => {
+                    self.src_stages |= prev.stages;
+                    self.dst_stages |= stages;
+                    self.barriers.push(Barrier::Buffer {
+                        states: prev.state .. access,
+                        target: buffer,
+                        range: r.clone(),
+                    });
+                    Usage { state: access, stages }
+                }
+                None => Usage { state: access, stages },
+            };
+            outside.push((r, next));
+        }
+
+        outside.sort_by_key(|&(ref r, _)| r.start);
+        for (r, usage) in outside {
+            match segments.last_mut() {
+                Some(&mut (ref mut last, ref last_usage)) if last.end == r.start && *last_usage == usage => {
+                    last.end = r.end;
+                    continue;
+                }
+                _ => {}
+            }
+            segments.push((r, usage));
+        }
+    }
+
+    /// Declare the next access of image subresources by the given pipeline stages.
+    ///
+    /// Subresources are transitioned into the layout of `state`.
+    pub fn image(
+        &mut self,
+        key: K,
+        image: &'b B::Image,
+        range: image::SubresourceRange,
+        state: image::State,
+        stages: PipelineStage,
+    ) {
+        let subresources = self.tracker.images.entry(key).or_insert_with(HashMap::new);
+        let (access, layout) = state;
+
+        // Previous states of all subresources requiring a transition.
+        let mut transitions = Vec::new();
+        for level in range.0.clone() {
+            for layer in range.1.clone() {
+                let prev = subresources.get(&(level, layer)).cloned();
+                let next = match prev {
+                    Some(prev) if prev.state.1 == layout && !image_writes(prev.state.0 | access) => Usage {
+                        state: (prev.state.0 | access, layout),
+                        stages: prev.stages | stages,
+                    },
+                    None if layout == ImageLayout::Undefined => Usage { state, stages },
+                    _ => {
+                        let prev = prev.unwrap_or(Usage {
+                            state: (image::Access::empty(), ImageLayout::Undefined),
+                            stages: pso::TOP_OF_PIPE,
+                        });
+                        transitions.push((level, layer, prev.state));
+                        self.src_stages |= prev.stages;
+                        self.dst_stages |= stages;
+                        Usage { state, stages }
+                    }
+                };
+                subresources.insert((level, layer), next);
+            }
+        }
+
+        // Combine consecutive layers, and then consecutive levels, sharing the same previous state.
+        let mut ranges: Vec<(image::SubresourceRange, image::State)> = Vec::new();
+        for (level, layer, prev) in transitions {
+            match ranges.last_mut() {
+                Some(&mut ((ref levels, ref mut layers), last)) if levels.start == level && layers.end == layer && last == prev => {
+                    layers.end += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            ranges.push(((level .. level + 1, layer .. layer + 1), prev));
+        }
+        let mut merged: Vec<(image::SubresourceRange, image::State)> = Vec::new();
+        for ((levels, layers), prev) in ranges {
+            match merged.last_mut() {
+                Some(&mut ((ref mut last_levels, ref last_layers), last)) if last_levels.end == levels.start && *last_layers == layers && last == prev => {
+                    last_levels.end = levels.end;
+                    continue;
+                }
+                _ => {}
+            }
+            merged.push(((levels, layers), prev));
+        }
+
+        for (range, prev) in merged {
+            self.barriers.push(Barrier::Image {
+                states: prev .. state,
+                target: image,
+                range,
+            });
+        }
+    }
+
+    /// Check if any barriers are required.
+    pub fn is_empty(&self) -> bool {
+        self.barriers.is_empty()
+    }
+
+    /// Record the required barriers into a command buffer.
+    pub fn record<C: Supports<Transfer>>(self, cmd_buffer: &mut CommandBuffer<B, C>) {
+        self.record_raw(cmd_buffer.raw)
+    }
+
+    /// Record the required barriers into a raw command buffer.
+    pub fn record_raw(self, cmd_buffer: &mut B::CommandBuffer) {
+        if !self.barriers.is_empty() {
+            cmd_buffer.pipeline_barrier(self.src_stages .. self.dst_stages, &self.barriers);
+        }
+    }
+}
@@ -64,6 +64,25 @@ pub struct ImageCopy {
     pub num_layers: image::Layer,
 }
 
+/// Regions of two images for blitting, which may differ in size.
+///
+/// The bounds are given as the two opposite corners of each region,
+/// swapping the corners of one of them mirrors the image data.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct ImageBlit {
+    ///
+    pub aspect_mask: image::AspectFlags,
+    ///
+    pub src_subresource: image::SubresourceLayers,
+    ///
+    pub src_bounds: Range<Offset>,
+    ///
+    pub dst_subresource: image::SubresourceLayers,
+    ///
+    pub dst_bounds: Range<Offset>,
+}
+
 ///
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
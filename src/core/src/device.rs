@@ -6,7 +6,7 @@
 use std::fmt;
 use std::error::Error;
 use std::ops::Range;
-use {buffer, format, image, mapping, pass, pso, target};
+use {buffer, format, image, mapping, pass, pso, query, target};
 use {Backend, Features, HeapType, Limits};
 use memory::Requirements;
 
@@ -261,25 +261,51 @@ pub trait Device<B: Backend> {
     /// Returns the limits of this `Device`.
     fn get_limits(&self) -> &Limits;
 
+    /// Returns what images and buffers of the given format can be used for,
+    /// allowing to pick a supported fallback before creating resources.
+    fn get_format_properties(&self, format: format::Format) -> format::FormatProperties;
+
     /// Create an heap of a specific type.
     ///
     /// There is only a limited amount of allocations allowed depending on the implementation!
     fn create_heap(&mut self, heap_type: &HeapType, resource_type: ResourceHeapType, size: u64) -> Result<B::Heap, ResourceHeapError>;
 
+    /// Create a shader library from SPIR-V byte code, one module per entry point.
+    ///
+    /// Backends not consuming SPIR-V natively translate the modules, or return
+    /// `CreateShaderError::ModelNotSupported` if they can't.
+    fn create_shader_library(&mut self, shaders: &[(pso::EntryPoint, &[u8])]) -> Result<B::ShaderLib, pso::CreateShaderError>;
+
     ///
     fn create_renderpass(&mut self, attachments: &[pass::Attachment], subpasses: &[pass::SubpassDesc], dependencies: &[pass::SubpassDependency]) -> B::RenderPass;
 
     ///
     fn create_pipeline_layout(&mut self, sets: &[&B::DescriptorSetLayout]) -> B::PipelineLayout;
 
+    /// Create a pipeline cache, optionally filled with data retrieved from `get_pipeline_cache_data`.
+    ///
+    /// Data of incompatible devices or drivers is ignored.
+    fn create_pipeline_cache(&mut self, initial_data: &[u8]) -> B::PipelineCache;
+
+    /// Retrieve the content of a pipeline cache, e.g. to store it on disk.
+    fn get_pipeline_cache_data(&mut self, cache: &B::PipelineCache) -> Vec<u8>;
+
+    /// Merge the content of the `sources` caches into `target`.
+    fn merge_pipeline_caches(&mut self, target: &B::PipelineCache, sources: &[&B::PipelineCache]);
+
     /// Create graphics pipelines.
-    fn create_graphics_pipelines<'a>(&mut self, &[(&B::ShaderLib, &B::PipelineLayout, pass::Subpass<'a, B>, &pso::GraphicsPipelineDesc)])
+    ///
+    /// Compilation results are looked up in and added to `cache`, if provided.
+    fn create_graphics_pipelines<'a>(&mut self, &[(&B::ShaderLib, &B::PipelineLayout, pass::Subpass<'a, B>, &pso::GraphicsPipelineDesc)], cache: Option<&B::PipelineCache>)
             -> Vec<Result<B::GraphicsPipeline, pso::CreationError>>;
 
     /// Create compute pipelines.
+    ///
+    /// Compilation results are looked up in and added to `cache`, if provided.
     fn create_compute_pipelines(
         &mut self,
         &[(&B::ShaderLib, pso::EntryPoint, &B::PipelineLayout)],
+        cache: Option<&B::PipelineCache>,
     ) -> Vec<Result<B::ComputePipeline, pso::CreationError>>;
 
     ///
@@ -381,6 +407,18 @@ pub trait Device<B: Backend> {
     /// Returns true if fences were signaled before the timeout.
     fn wait_for_fences(&mut self, fences: &[&B::Fence], wait: WaitFor, timeout_ms: u32) -> bool;
 
+    /// Create a pool of `count` queries of the given type.
+    fn create_query_pool(&mut self, ty: query::QueryType, count: u32) -> B::QueryPool;
+
+    /// Retrieve the results of a range of queries.
+    ///
+    /// The results are written tightly packed into `data`: `QueryType::num_values` values
+    /// per query, followed by its availability if `WITH_AVAILABILITY` is set.
+    /// Returns false if not all results were available, the values of these queries are
+    /// only written if `PARTIAL` is set.
+    fn get_query_pool_results(&mut self, pool: &B::QueryPool, queries: Range<query::QueryId>,
+                              data: &mut [u64], flags: query::ResultFlags) -> bool;
+
     ///
     fn destroy_heap(&mut self, B::Heap);
 
@@ -405,6 +443,9 @@ pub trait Device<B: Backend> {
     /// which references the compute pipeline, has finished execution.
     fn destroy_compute_pipeline(&mut self, B::ComputePipeline);
 
+    ///
+    fn destroy_pipeline_cache(&mut self, B::PipelineCache);
+
     /// Destroys a framebuffer.
     ///
     /// The framebuffer shouldn't be destroy before any submitted command buffer,
@@ -452,4 +493,7 @@ pub trait Device<B: Backend> {
 
     ///
     fn destroy_semaphore(&mut self, B::Semaphore);
+
+    ///
+    fn destroy_query_pool(&mut self, B::QueryPool);
 }
@@ -1,11 +1,10 @@
 //! Universal format specification.
 //! Applicable to textures, views, and vertex buffers.
+//!
+//! Block-compressed surfaces (BC, ETC2/EAC and ASTC) are stored as blocks of texels
+//! rather than single texels, see `SurfaceType::get_block_dimensions`. Their data type
+//! is a single block.
 
-//TODO:
-//  DXT 1-5, BC7
-//  ETC2_RGB, // Use the EXT2 algorithm on 3 components.
-//  ETC2_SRGB, // Use the EXT2 algorithm on 4 components (RGBA) in the sRGB color space.
-//  ETC2_EAC_RGBA8, // Use the EXT2 EAC algorithm on 4 components.
 use memory::Pod;
 
 macro_rules! impl_channel_type {
@@ -60,7 +59,8 @@ macro_rules! impl_formats {
             $( $name, )*
         }
         impl SurfaceType {
-            /// Return the total number of bits for this format.
+            /// Return the total number of bits for this format,
+            /// per block for compressed surfaces.
             pub fn get_total_bits(&self) -> u8 {
                 use std::mem::size_of;
                 match *self {
@@ -138,6 +138,93 @@ impl_formats! {
     D24_S8          : Vec1<Unorm, Uint> = u32 {8} [TextureSurface, DepthSurface, StencilSurface],
     D32             : Vec1<Float> = f32 {0} [TextureSurface, DepthSurface],
     D32_S8          : Vec1<Unorm, Float, Uint> = (f32, u32) {32} [TextureSurface, DepthSurface, StencilSurface],
+    BC1_R8_G8_B8    : Vec3<Unorm, Srgb> = [u8; 8] {0} [TextureSurface],
+    BC1_R8_G8_B8_A8 : Vec4<Unorm, Srgb> = [u8; 8] {1} [TextureSurface],
+    BC2_R8_G8_B8_A8 : Vec4<Unorm, Srgb> = [u8; 16] {4} [TextureSurface],
+    BC3_R8_G8_B8_A8 : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    BC4_R8          : Vec1<Unorm, Inorm> = [u8; 8] {0} [TextureSurface],
+    BC5_R8_G8       : Vec2<Unorm, Inorm> = [u8; 16] {0} [TextureSurface],
+    BC6_R16_G16_B16 : Vec3<Float> = [u8; 16] {0} [TextureSurface], // unsigned
+    BC7_R8_G8_B8_A8 : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ETC2_R8_G8_B8   : Vec3<Unorm, Srgb> = [u8; 8] {0} [TextureSurface],
+    ETC2_R8_G8_B8_A1 : Vec4<Unorm, Srgb> = [u8; 8] {1} [TextureSurface],
+    ETC2_R8_G8_B8_A8 : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    EAC_R11         : Vec1<Unorm, Inorm> = [u8; 8] {0} [TextureSurface],
+    EAC_R11_G11     : Vec2<Unorm, Inorm> = [u8; 16] {0} [TextureSurface],
+    ASTC_4x4        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_5x4        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_5x5        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_6x5        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_6x6        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_8x5        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_8x6        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_8x8        : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_10x5       : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_10x6       : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_10x8       : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_10x10      : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_12x10      : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+    ASTC_12x12      : Vec4<Unorm, Srgb> = [u8; 16] {8} [TextureSurface],
+}
+
+impl SurfaceType {
+    /// Return the width and height in texels of the blocks this format is stored in.
+    /// Uncompressed formats are made of single texel blocks.
+    pub fn get_block_dimensions(&self) -> (u8, u8) {
+        use self::SurfaceType::*;
+        match *self {
+            BC1_R8_G8_B8 | BC1_R8_G8_B8_A8 | BC2_R8_G8_B8_A8 | BC3_R8_G8_B8_A8 |
+            BC4_R8 | BC5_R8_G8 | BC6_R16_G16_B16 | BC7_R8_G8_B8_A8 |
+            ETC2_R8_G8_B8 | ETC2_R8_G8_B8_A1 | ETC2_R8_G8_B8_A8 |
+            EAC_R11 | EAC_R11_G11 | ASTC_4x4 => (4, 4),
+            ASTC_5x4 => (5, 4),
+            ASTC_5x5 => (5, 5),
+            ASTC_6x5 => (6, 5),
+            ASTC_6x6 => (6, 6),
+            ASTC_8x5 => (8, 5),
+            ASTC_8x6 => (8, 6),
+            ASTC_8x8 => (8, 8),
+            ASTC_10x5 => (10, 5),
+            ASTC_10x6 => (10, 6),
+            ASTC_10x8 => (10, 8),
+            ASTC_10x10 => (10, 10),
+            ASTC_12x10 => (12, 10),
+            ASTC_12x12 => (12, 12),
+            _ => (1, 1),
+        }
+    }
+
+    /// Check if this format is block-compressed.
+    pub fn is_compressed(&self) -> bool {
+        self.get_block_dimensions() != (1, 1)
+    }
+
+    /// Return the number of bytes of a block, or of a texel for uncompressed formats.
+    pub fn get_block_size(&self) -> u8 {
+        self.get_total_bits() / 8
+    }
+
+    /// Return the number of bytes of a row of blocks covering `width` texels.
+    pub fn get_row_size(&self, width: u32) -> usize {
+        let (block_width, _) = self.get_block_dimensions();
+        div_ceil(width, block_width) * self.get_block_size() as usize
+    }
+
+    /// Return the number of rows of blocks covering `height` texels.
+    pub fn get_num_rows(&self, height: u32) -> usize {
+        let (_, block_height) = self.get_block_dimensions();
+        div_ceil(height, block_height)
+    }
+
+    /// Return the number of bytes of the blocks covering a region of texels.
+    pub fn get_byte_count(&self, width: u32, height: u32, depth: u32) -> usize {
+        self.get_row_size(width) * self.get_num_rows(height) * depth as usize
+    }
+}
+
+fn div_ceil(texels: u32, block: u8) -> usize {
+    let block = block as u32;
+    ((texels + block - 1) / block) as usize
 }
 
 
@@ -175,6 +262,65 @@ impl Swizzle {
 #[cfg_attr(feature="serialize", derive(Serialize, Deserialize))]
 pub struct Format(pub SurfaceType, pub ChannelType);
 
+bitflags!(
+    /// Features of images with a given format.
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    pub flags ImageFeature: u16 {
+        /// Views of the image can be sampled.
+        const SAMPLED = 0x1,
+        /// Views of the image can be sampled with linear filtering.
+        const SAMPLED_LINEAR = 0x2,
+        /// Views of the image can be used as storage images.
+        const STORAGE = 0x4,
+        /// Views of the image can be used as color attachments.
+        const COLOR_ATTACHMENT = 0x8,
+        /// Views of the image can be used as blended color attachments.
+        const COLOR_ATTACHMENT_BLEND = 0x10,
+        /// Views of the image can be used as depth-stencil attachments.
+        const DEPTH_STENCIL_ATTACHMENT = 0x20,
+        /// The image can be the source of a blit.
+        const BLIT_SRC = 0x40,
+        /// The image can be the destination of a blit.
+        const BLIT_DST = 0x80,
+    }
+);
+
+bitflags!(
+    /// Features of buffers holding data of a given format.
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    pub flags BufferFeature: u8 {
+        /// The buffer can hold vertex attributes.
+        const VERTEX = 0x1,
+        /// Views of the buffer can be used as uniform texel buffers.
+        const UNIFORM_TEXEL = 0x2,
+        /// Views of the buffer can be used as storage texel buffers.
+        const STORAGE_TEXEL = 0x4,
+    }
+);
+
+/// Capabilities of a device for a format, see `Device::get_format_properties`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature="serialize", derive(Serialize, Deserialize))]
+pub struct FormatProperties {
+    /// Features supported by images with linear tiling.
+    pub linear_tiling: ImageFeature,
+    /// Features supported by images with optimal tiling.
+    pub optimal_tiling: ImageFeature,
+    /// Features supported by buffers.
+    pub buffer_features: BufferFeature,
+}
+
+impl FormatProperties {
+    /// Properties of a format which can't be used at all.
+    pub fn unsupported() -> Self {
+        FormatProperties {
+            linear_tiling: ImageFeature::empty(),
+            optimal_tiling: ImageFeature::empty(),
+            buffer_features: BufferFeature::empty(),
+        }
+    }
+}
+
 /// Compile-time surface type trait.
 pub trait SurfaceTyped {
     /// The corresponding data type to be passed from CPU.
@@ -0,0 +1,897 @@
+//! Render graph.
+//!
+//! Passes declare the images and buffers they access, the graph derives
+//! everything else needed for execution:
+//!
+//! - passes whose results are never used are culled,
+//! - transient images are created on demand, images with disjoint lifetimes
+//!   share memory,
+//! - render passes and frame buffers are created for color attachments,
+//! - barriers are recorded between passes with a `StateTracker`,
+//! - semaphores synchronize passes running on different queues.
+//!
+//! Graphs are built once and can be executed multiple times. Imported images
+//! are borrowed for the lifetime of the graph, so a graph rendering into
+//! swapchain backbuffers is built per backbuffer.
+
+use std::cmp;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use {buffer, command, format, image, pass, pso, target};
+use {Backend, Device, HeapType, QueueType, RawCommandBuffer, RawCommandPool, RawCommandQueue, RawSubmission};
+use command::{ClearColor, StateTracker};
+use device::{Extent, ResourceHeapType, TargetViewError};
+use memory::{self, Allocation, AllocationError, Allocator, Requirements, Strategy};
+use queue::capability;
+
+/// Queue capability required by a pass.
+pub trait Capability {
+    /// Type of queue executing passes of this capability.
+    fn queue_type() -> QueueType;
+}
+
+impl Capability for capability::Graphics {
+    fn queue_type() -> QueueType { QueueType::Graphics }
+}
+
+impl Capability for capability::Compute {
+    fn queue_type() -> QueueType { QueueType::Compute }
+}
+
+impl Capability for capability::Transfer {
+    fn queue_type() -> QueueType { QueueType::Transfer }
+}
+
+/// Image used by a graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ImageId(usize);
+
+/// Buffer used by a graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BufferId(usize);
+
+/// Pass of a graph.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PassId(usize);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+enum Resource {
+    Image(usize),
+    Buffer(usize),
+}
+
+/// Description of a pass and its resource accesses.
+pub struct PassDesc {
+    name: String,
+    queue_type: QueueType,
+    colors: Vec<(ImageId, Option<ClearColor>)>,
+    images: Vec<(ImageId, image::State, pso::PipelineStage)>,
+    buffers: Vec<(BufferId, buffer::Access, pso::PipelineStage)>,
+}
+
+impl PassDesc {
+    /// Create a pass executed on a queue with capability `C`.
+    pub fn new<C: Capability>(name: &str) -> Self {
+        PassDesc {
+            name: name.to_string(),
+            queue_type: C::queue_type(),
+            colors: Vec::new(),
+            images: Vec::new(),
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Render into a color attachment, clearing it first if `clear` is set.
+    ///
+    /// Without clearing, the previous content of the image is loaded.
+    pub fn with_color(mut self, image: ImageId, clear: Option<ClearColor>) -> Self {
+        self.colors.push((image, clear));
+        self
+    }
+
+    /// Access an image in the given state from the given pipeline stages.
+    pub fn with_image(mut self, image: ImageId, state: image::State, stages: pso::PipelineStage) -> Self {
+        self.images.push((image, state, stages));
+        self
+    }
+
+    /// Sample an image in shaders of the given stages.
+    pub fn with_sampled(self, image: ImageId, stages: pso::PipelineStage) -> Self {
+        self.with_image(image, (image::SHADER_READ, image::ImageLayout::ShaderReadOnlyOptimal), stages)
+    }
+
+    /// Access a buffer from the given pipeline stages.
+    pub fn with_buffer(mut self, buffer: BufferId, access: buffer::Access, stages: pso::PipelineStage) -> Self {
+        self.buffers.push((buffer, access, stages));
+        self
+    }
+
+    fn accesses(&self) -> Vec<(Resource, bool, bool)> {
+        let colors = self.colors
+            .iter()
+            .map(|&(id, clear)| (Resource::Image(id.0), clear.is_none(), true));
+        let image_writes =
+            image::COLOR_ATTACHMENT_WRITE | image::TRANSFER_WRITE |
+            image::RENDER_TARGET_CLEAR | image::RESOLVE_DST;
+        let images = self.images
+            .iter()
+            .map(|&(id, (access, _), _)| {
+                (Resource::Image(id.0), !(access - image_writes).is_empty(), access.intersects(image_writes))
+            });
+        let buffers = self.buffers
+            .iter()
+            .map(|&(id, access, _)| {
+                (Resource::Buffer(id.0), access != buffer::TRANSFER_WRITE, access.contains(buffer::TRANSFER_WRITE))
+            });
+        colors.chain(images).chain(buffers).collect()
+    }
+}
+
+enum ImageSource<'a, B: Backend> {
+    Imported {
+        raw: &'a B::Image,
+        states: Range<image::State>,
+    },
+    Transient {
+        levels: image::Level,
+    },
+}
+
+struct ImageDesc<'a, B: Backend> {
+    source: ImageSource<'a, B>,
+    kind: image::Kind,
+    format: format::Format,
+}
+
+/// Collects passes and resources of a graph.
+pub struct GraphBuilder<'a, B: Backend> {
+    images: Vec<ImageDesc<'a, B>>,
+    buffers: Vec<(&'a B::Buffer, u64)>,
+    passes: Vec<PassDesc>,
+    dedicated: Vec<QueueType>,
+}
+
+impl<'a, B: Backend> GraphBuilder<'a, B> {
+    /// Create an empty graph.
+    ///
+    /// All passes are executed on the graphics queue, unless a dedicated
+    /// queue is requested for their capability.
+    pub fn new() -> Self {
+        GraphBuilder {
+            images: Vec::new(),
+            buffers: Vec::new(),
+            passes: Vec::new(),
+            dedicated: Vec::new(),
+        }
+    }
+
+    /// Execute compute or transfer passes on a dedicated queue.
+    pub fn dedicated_queue(&mut self, queue_type: QueueType) {
+        assert!(queue_type == QueueType::Compute || queue_type == QueueType::Transfer);
+        if !self.dedicated.contains(&queue_type) {
+            self.dedicated.push(queue_type);
+        }
+    }
+
+    /// Add a transient image, created and owned by the graph.
+    pub fn create_image(&mut self, kind: image::Kind, levels: image::Level, format: format::Format) -> ImageId {
+        self.images.push(ImageDesc {
+            source: ImageSource::Transient { levels },
+            kind,
+            format,
+        });
+        ImageId(self.images.len() - 1)
+    }
+
+    /// Add an image created outside of the graph.
+    ///
+    /// The image is expected in the first of `states` at the start of an execution
+    /// and left in the last one. Passes writing to imported images are never culled.
+    pub fn import_image(
+        &mut self,
+        image: &'a B::Image,
+        kind: image::Kind,
+        format: format::Format,
+        states: Range<image::State>,
+    ) -> ImageId {
+        self.images.push(ImageDesc {
+            source: ImageSource::Imported { raw: image, states },
+            kind,
+            format,
+        });
+        ImageId(self.images.len() - 1)
+    }
+
+    /// Add a buffer of `size` bytes created outside of the graph.
+    pub fn import_buffer(&mut self, buffer: &'a B::Buffer, size: u64) -> BufferId {
+        self.buffers.push((buffer, size));
+        BufferId(self.buffers.len() - 1)
+    }
+
+    /// Add a pass, executed after all previously added passes it depends on.
+    pub fn add_pass(&mut self, pass: PassDesc) -> PassId {
+        self.passes.push(pass);
+        PassId(self.passes.len() - 1)
+    }
+
+    fn is_imported(&self, resource: Resource) -> bool {
+        match resource {
+            Resource::Image(id) => match self.images[id].source {
+                ImageSource::Imported { .. } => true,
+                ImageSource::Transient { .. } => false,
+            },
+            Resource::Buffer(_) => true,
+        }
+    }
+
+    /// Passes contributing to imported resources, in execution order.
+    fn cull(&self) -> Vec<usize> {
+        let mut live = HashSet::new();
+        let mut kept = Vec::new();
+        for (index, pass) in self.passes.iter().enumerate().rev() {
+            let accesses = pass.accesses();
+            let needed = accesses.iter().any(|&(resource, _, writes)| {
+                writes && (self.is_imported(resource) || live.contains(&resource))
+            });
+            if !needed {
+                continue;
+            }
+            kept.push(index);
+            for &(resource, reads, writes) in &accesses {
+                if reads {
+                    live.insert(resource);
+                } else if writes && !self.is_imported(resource) {
+                    live.remove(&resource);
+                }
+            }
+        }
+        kept.reverse();
+        kept
+    }
+
+    /// Create all objects required to execute the graph.
+    pub fn build(self, device: &mut B::Device, heap_types: &[HeapType]) -> Result<Graph<'a, B>, GraphError> {
+        let order = self.cull();
+
+        // First and last use of each resource, as indices into `order`.
+        let mut lifetimes = HashMap::new();
+        let mut previous_writes = HashSet::new();
+        let mut load_colors = HashSet::new();
+        for (position, &index) in order.iter().enumerate() {
+            for (resource, _, writes) in self.passes[index].accesses() {
+                lifetimes.entry(resource).or_insert(position .. position).end = position;
+                if writes {
+                    previous_writes.insert(resource);
+                }
+            }
+            for &(id, clear) in &self.passes[index].colors {
+                let resource = Resource::Image(id.0);
+                if clear.is_none() && (self.is_imported(resource) || previous_writes.contains(&resource) && lifetimes[&resource].start < position) {
+                    load_colors.insert((index, id.0));
+                }
+            }
+        }
+
+        // Usage of transient images is derived from their accesses.
+        let mut usages = vec![image::Usage::empty(); self.images.len()];
+        for &index in &order {
+            let pass = &self.passes[index];
+            for &(id, _) in &pass.colors {
+                usages[id.0] |= image::COLOR_ATTACHMENT;
+            }
+            for &(id, (access, _), _) in &pass.images {
+                if access.contains(image::SHADER_READ) {
+                    usages[id.0] |= image::SAMPLED;
+                }
+                if access.contains(image::TRANSFER_READ) {
+                    usages[id.0] |= image::TRANSFER_SRC;
+                }
+                if access.contains(image::TRANSFER_WRITE) {
+                    usages[id.0] |= image::TRANSFER_DST;
+                }
+                if access.intersects(image::COLOR_ATTACHMENT_READ | image::COLOR_ATTACHMENT_WRITE) {
+                    usages[id.0] |= image::COLOR_ATTACHMENT;
+                }
+            }
+        }
+
+        // Place transient images, sharing memory between images with disjoint lifetimes.
+        let mut unbound = Vec::new();
+        for (id, desc) in self.images.iter().enumerate() {
+            let levels = match desc.source {
+                ImageSource::Transient { levels } => levels,
+                ImageSource::Imported { .. } => continue,
+            };
+            let lifetime = match lifetimes.get(&Resource::Image(id)) {
+                Some(lifetime) => lifetime.clone(),
+                None => continue,
+            };
+            let raw = device
+                .create_image(desc.kind, levels, desc.format, usages[id])
+                .map_err(|err| GraphError::Allocation(AllocationError::Image(err)))?;
+            let requirements = device.get_image_requirements(&raw);
+            let resource_type = if usages[id].contains(image::COLOR_ATTACHMENT) {
+                ResourceHeapType::Targets
+            } else {
+                ResourceHeapType::Images
+            };
+            unbound.push((id, raw, requirements, resource_type, lifetime));
+        }
+        unbound.sort_by_key(|&(id, _, _, _, ref lifetime)| (lifetime.start, id));
+
+        let mut placements: Vec<(usize, Range<u64>, ResourceHeapType, Range<usize>)> = Vec::new();
+        for &(id, _, requirements, resource_type, ref lifetime) in &unbound {
+            let overlapping = placements
+                .iter()
+                .filter(|&&(_, _, ty, ref other)| ty == resource_type && other.start <= lifetime.end && lifetime.start <= other.end)
+                .map(|&(_, ref memory, _, _)| memory.clone())
+                .collect::<Vec<_>>();
+            let offset = Some(0)
+                .into_iter()
+                .chain(overlapping.iter().map(|memory| memory.end))
+                .map(|offset| align(offset, requirements.alignment))
+                .filter(|&offset| overlapping.iter().all(|memory| offset + requirements.size <= memory.start || memory.end <= offset))
+                .min()
+                .unwrap();
+            placements.push((id, offset .. offset + requirements.size, resource_type, lifetime.clone()));
+        }
+
+        let mut allocator = Allocator::new(heap_types, Strategy::Linear, 0);
+        let mut heaps = Vec::new();
+        for &resource_type in &[ResourceHeapType::Images, ResourceHeapType::Targets] {
+            let requirements = unbound
+                .iter()
+                .zip(&placements)
+                .filter(|&(&(_, _, _, ty, _), _)| ty == resource_type)
+                .fold(None, |acc: Option<Requirements>, (&(_, _, req, _, _), &(_, ref memory, _, _))| {
+                    let acc = acc.unwrap_or(Requirements { size: 0, alignment: 1 });
+                    Some(Requirements {
+                        size: cmp::max(acc.size, memory.end),
+                        alignment: cmp::max(acc.alignment, req.alignment),
+                    })
+                });
+            if let Some(requirements) = requirements {
+                let allocation = allocator
+                    .allocate(device, memory::DEVICE_LOCAL, resource_type, requirements)
+                    .map_err(GraphError::Allocation)?;
+                heaps.push((resource_type, allocation));
+            }
+        }
+
+        let mut transient = HashMap::new();
+        let mut aliased = HashSet::new();
+        for ((id, raw, _, resource_type, _), (_, memory, _, lifetime)) in unbound.into_iter().zip(placements.clone()) {
+            let allocation = &heaps.iter().find(|&&(ty, _)| ty == resource_type).unwrap().1;
+            let image = device
+                .bind_image_memory(allocator.heap(allocation), allocation.offset() + memory.start, raw)
+                .map_err(|err| GraphError::Allocation(AllocationError::Image(err)))?;
+            transient.insert(id, (image, memory.clone()));
+
+            let shared = placements.iter().any(|&(other, ref other_memory, ty, ref other_lifetime)| {
+                other != id && ty == resource_type && other_lifetime.end < lifetime.start &&
+                other_memory.start < memory.end && memory.start < other_memory.end
+            });
+            if shared {
+                aliased.insert(id);
+            }
+        }
+
+        let mut images = Vec::new();
+        for (id, desc) in self.images.into_iter().enumerate() {
+            let source = match desc.source {
+                ImageSource::Imported { raw, states } => GraphImageSource::Imported { raw, states },
+                ImageSource::Transient { levels } => match transient.remove(&id) {
+                    Some((raw, memory)) => GraphImageSource::Transient { raw, memory, levels },
+                    None => GraphImageSource::Culled,
+                },
+            };
+            images.push(GraphImage {
+                source,
+                kind: desc.kind,
+                format: desc.format,
+                aliased: aliased.contains(&id),
+            });
+        }
+
+        // Dependencies between passes, including reuse of aliased memory.
+        let mut dependencies = HashSet::new();
+        {
+            let mut last_writer: HashMap<Resource, usize> = HashMap::new();
+            let mut readers: HashMap<Resource, Vec<usize>> = HashMap::new();
+            for &index in &order {
+                for (resource, reads, writes) in self.passes[index].accesses() {
+                    if let Some(&writer) = last_writer.get(&resource) {
+                        if writer != index {
+                            dependencies.insert((writer, index));
+                        }
+                    }
+                    if writes {
+                        for &reader in readers.remove(&resource).unwrap_or_default().iter().filter(|&&r| r != index) {
+                            dependencies.insert((reader, index));
+                        }
+                        last_writer.insert(resource, index);
+                    } else if reads {
+                        readers.entry(resource).or_insert_with(Vec::new).push(index);
+                    }
+                }
+            }
+            for &(_, ref memory, ty, ref lifetime) in &placements {
+                for &(_, ref other_memory, other_ty, ref other_lifetime) in &placements {
+                    if ty == other_ty && other_lifetime.end < lifetime.start &&
+                        other_memory.start < memory.end && memory.start < other_memory.end
+                    {
+                        dependencies.insert((order[other_lifetime.end], order[lifetime.start]));
+                    }
+                }
+            }
+        }
+
+        // Group consecutive passes running on the same queue into batches.
+        let dedicated = self.dedicated;
+        let slot = |queue_type: QueueType| if dedicated.contains(&queue_type) { queue_type } else { QueueType::Graphics };
+        let mut batches: Vec<Batch> = Vec::new();
+        let mut batch_of = HashMap::new();
+        for &index in &order {
+            let queue = slot(self.passes[index].queue_type);
+            if batches.last().map(|batch| batch.queue) != Some(queue) {
+                batches.push(Batch {
+                    queue,
+                    passes: Vec::new(),
+                    wait: Vec::new(),
+                    signal: Vec::new(),
+                });
+            }
+            batches.last_mut().unwrap().passes.push(PassId(index));
+            batch_of.insert(index, batches.len() - 1);
+        }
+
+        let mut semaphores = Vec::new();
+        let mut edges = dependencies
+            .iter()
+            .map(|&(from, to)| (batch_of[&from], batch_of[&to]))
+            .filter(|&(from, to)| batches[from].queue != batches[to].queue)
+            .collect::<Vec<_>>();
+        // The last batch on every other queue signals the final batch, so the
+        // fence passed to `execute` covers the work of all queues.
+        if let Some(last) = batches.len().checked_sub(1) {
+            for from in 0 .. last {
+                let queue = batches[from].queue;
+                if queue != batches[last].queue && batches[from + 1 ..].iter().all(|batch| batch.queue != queue) {
+                    edges.push((from, last));
+                }
+            }
+        }
+        edges.sort();
+        edges.dedup();
+        for (from, to) in edges {
+            semaphores.push(device.create_semaphore());
+            batches[from].signal.push(semaphores.len() - 1);
+            batches[to].wait.push(semaphores.len() - 1);
+        }
+
+        // Render passes and frame buffers of passes with color attachments.
+        let mut render_passes = HashMap::new();
+        let passes = &self.passes;
+        for &index in &order {
+            let pass = &passes[index];
+            if pass.colors.is_empty() {
+                continue;
+            }
+            let layout = image::ImageLayout::ColorAttachmentOptimal;
+            let attachments = pass.colors
+                .iter()
+                .map(|&(id, clear)| {
+                    let resource = Resource::Image(id.0);
+                    let load = if clear.is_some() {
+                        pass::AttachmentLoadOp::Clear
+                    } else if load_colors.contains(&(index, id.0)) {
+                        pass::AttachmentLoadOp::Load
+                    } else {
+                        pass::AttachmentLoadOp::DontCare
+                    };
+                    let needed_later = order
+                        .iter()
+                        .skip_while(|&&other| other != index)
+                        .skip(1)
+                        .any(|&other| passes[other].accesses().iter().any(|&(r, reads, _)| r == resource && reads));
+                    let imported = match images[id.0].source {
+                        GraphImageSource::Imported { .. } => true,
+                        _ => false,
+                    };
+                    let store = if needed_later || imported {
+                        pass::AttachmentStoreOp::Store
+                    } else {
+                        pass::AttachmentStoreOp::DontCare
+                    };
+                    pass::Attachment {
+                        format: images[id.0].format,
+                        ops: pass::AttachmentOps::new(load, store),
+                        stencil_ops: pass::AttachmentOps::DONT_CARE,
+                        layouts: layout .. layout,
+                    }
+                })
+                .collect::<Vec<_>>();
+            let refs = (0 .. attachments.len()).map(|i| (i, layout)).collect::<Vec<_>>();
+            let render_pass = device.create_renderpass(
+                &attachments,
+                &[pass::SubpassDesc { color_attachments: &refs }],
+                &[],
+            );
+
+            let mut views = Vec::new();
+            for &(id, _) in &pass.colors {
+                let image = &images[id.0];
+                let view = device.view_image_as_render_target(image.raw(), image.format, (0 .. 1, 0 .. 1));
+                match view {
+                    Ok(view) => views.push(view),
+                    Err(err) => return Err(GraphError::View(err)),
+                }
+            }
+            let (width, height, depth, _) = images[pass.colors[0].0 .0].kind.get_level_dimensions(0);
+            let extent = Extent {
+                width: width as u32,
+                height: height as u32,
+                depth: cmp::max(depth, 1) as u32,
+            };
+            let frame_buffer = {
+                let view_refs = views.iter().collect::<Vec<_>>();
+                device.create_framebuffer(&render_pass, &view_refs, &[], extent)
+            };
+            let clear_values = pass.colors
+                .iter()
+                .map(|&(_, clear)| command::ClearValue::Color(clear.unwrap_or(ClearColor::Float([0.0; 4]))))
+                .collect();
+            render_passes.insert(index, RenderPassInfo {
+                render_pass,
+                frame_buffer,
+                views,
+                area: target::Rect { x: 0, y: 0, w: width, h: height },
+                clear_values,
+            });
+        }
+
+        Ok(Graph {
+            images,
+            buffers: self.buffers,
+            passes: self.passes,
+            batches,
+            semaphores,
+            render_passes,
+            allocator,
+            heaps: heaps.into_iter().map(|(_, allocation)| allocation).collect(),
+            pools: HashMap::new(),
+            cmd_buffers: Vec::new(),
+        })
+    }
+}
+
+impl<'a, B: Backend> Default for GraphBuilder<'a, B> {
+    fn default() -> Self {
+        GraphBuilder::new()
+    }
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    if alignment <= 1 {
+        offset
+    } else {
+        (offset + alignment - 1) / alignment * alignment
+    }
+}
+
+/// Error building a graph.
+#[derive(Clone, Debug, PartialEq)]
+pub enum GraphError {
+    /// Creating or allocating memory for a transient image failed.
+    Allocation(AllocationError),
+    /// Creating a render target view for a color attachment failed.
+    View(TargetViewError),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GraphError::Allocation(ref err) => write!(f, "{}: {}", self.description(), err),
+            GraphError::View(ref err) => write!(f, "{}: {}", self.description(), err),
+        }
+    }
+}
+
+impl Error for GraphError {
+    fn description(&self) -> &str {
+        match *self {
+            GraphError::Allocation(_) => "Creating or allocating memory for a transient image failed",
+            GraphError::View(_) => "Creating a render target view for a color attachment failed",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            GraphError::Allocation(ref err) => Some(err),
+            GraphError::View(ref err) => Some(err),
+        }
+    }
+}
+
+enum GraphImageSource<'a, B: Backend> {
+    Imported {
+        raw: &'a B::Image,
+        states: Range<image::State>,
+    },
+    Transient {
+        raw: B::Image,
+        memory: Range<u64>,
+        levels: image::Level,
+    },
+    Culled,
+}
+
+struct GraphImage<'a, B: Backend> {
+    source: GraphImageSource<'a, B>,
+    kind: image::Kind,
+    format: format::Format,
+    aliased: bool,
+}
+
+impl<'a, B: Backend> GraphImage<'a, B> {
+    fn raw(&self) -> &B::Image {
+        match self.source {
+            GraphImageSource::Imported { raw, .. } => raw,
+            GraphImageSource::Transient { ref raw, .. } => raw,
+            GraphImageSource::Culled => unreachable!(),
+        }
+    }
+
+    fn range(&self) -> image::SubresourceRange {
+        let levels = match self.source {
+            GraphImageSource::Transient { levels, .. } => levels,
+            _ => 1,
+        };
+        let layers = self.kind.get_num_slices().unwrap_or(1) * if self.kind.is_cube() { 6 } else { 1 };
+        (0 .. levels, 0 .. layers)
+    }
+}
+
+/// Consecutive passes submitted together to one queue.
+struct Batch {
+    queue: QueueType,
+    passes: Vec<PassId>,
+    wait: Vec<usize>,
+    signal: Vec<usize>,
+}
+
+struct RenderPassInfo<B: Backend> {
+    render_pass: B::RenderPass,
+    frame_buffer: B::FrameBuffer,
+    views: Vec<B::RenderTargetView>,
+    area: target::Rect,
+    clear_values: Vec<command::ClearValue>,
+}
+
+/// Queues executing a graph.
+pub struct Queues<'q, B: Backend + 'q> {
+    /// Queue for graphics passes, and all passes without a dedicated queue.
+    pub graphics: &'q mut B::CommandQueue,
+    /// Dedicated queue for compute passes.
+    pub compute: Option<&'q mut B::CommandQueue>,
+    /// Dedicated queue for transfer passes.
+    pub transfer: Option<&'q mut B::CommandQueue>,
+}
+
+impl<'q, B: Backend> Queues<'q, B> {
+    fn get(&mut self, queue_type: QueueType) -> &mut B::CommandQueue {
+        match queue_type {
+            QueueType::Compute => self.compute.as_mut().map(|queue| &mut **queue),
+            QueueType::Transfer => self.transfer.as_mut().map(|queue| &mut **queue),
+            _ => Some(&mut *self.graphics),
+        }.expect("Dedicated queue requested by the graph is missing")
+    }
+}
+
+/// Built render graph, ready for execution.
+pub struct Graph<'a, B: Backend> {
+    images: Vec<GraphImage<'a, B>>,
+    buffers: Vec<(&'a B::Buffer, u64)>,
+    passes: Vec<PassDesc>,
+    batches: Vec<Batch>,
+    semaphores: Vec<B::Semaphore>,
+    render_passes: HashMap<usize, RenderPassInfo<B>>,
+    allocator: Allocator<B>,
+    heaps: Vec<Allocation>,
+    pools: HashMap<QueueType, B::CommandPool>,
+    cmd_buffers: Vec<B::CommandBuffer>,
+}
+
+impl<'a, B: Backend> Graph<'a, B> {
+    /// Passes in execution order, excluding culled ones.
+    pub fn order(&self) -> Vec<PassId> {
+        self.batches
+            .iter()
+            .flat_map(|batch| batch.passes.iter().cloned())
+            .collect()
+    }
+
+    /// Name of a pass.
+    pub fn name(&self, pass: PassId) -> &str {
+        &self.passes[pass.0].name
+    }
+
+    /// Render pass created for a pass with color attachments.
+    ///
+    /// Pipelines used by the pass need to be created for this render pass.
+    pub fn render_pass(&self, pass: PassId) -> Option<&B::RenderPass> {
+        self.render_passes.get(&pass.0).map(|info| &info.render_pass)
+    }
+
+    /// Image used by the graph, `None` for transient images of culled passes.
+    pub fn image(&self, image: ImageId) -> Option<&B::Image> {
+        match self.images[image.0].source {
+            GraphImageSource::Culled => None,
+            _ => Some(self.images[image.0].raw()),
+        }
+    }
+
+    /// Memory range of a transient image inside of the heap shared by transient images.
+    pub fn memory_range(&self, image: ImageId) -> Option<Range<u64>> {
+        match self.images[image.0].source {
+            GraphImageSource::Transient { ref memory, .. } => Some(memory.clone()),
+            _ => None,
+        }
+    }
+
+    /// Number of semaphores used to synchronize queues.
+    pub fn semaphore_count(&self) -> usize {
+        self.semaphores.len()
+    }
+
+    /// Record and submit all passes.
+    ///
+    /// `record` is called for every pass in execution order. Barriers for the
+    /// declared accesses are recorded beforehand, passes with color attachments
+    /// are already inside of their render pass. `fence` is signalled by the last
+    /// submission, which waits for the batches submitted to all other queues.
+    ///
+    /// The previous execution of the graph must have completed.
+    pub fn execute<F>(&mut self, queues: &mut Queues<B>, fence: Option<&B::Fence>, mut record: F)
+    where
+        F: FnMut(PassId, &mut B::CommandBuffer),
+    {
+        for batch in &self.batches {
+            self.pools.entry(batch.queue).or_insert_with(|| unsafe {
+                B::CommandPool::from_queue(queues.get(batch.queue), ::pool::CommandPoolCreateFlags::empty())
+            });
+        }
+        for pool in self.pools.values_mut() {
+            pool.reset();
+        }
+        if self.cmd_buffers.is_empty() {
+            for batch in &self.batches {
+                let cmd_buffer = self.pools.get_mut(&batch.queue).unwrap().allocate(1).pop().unwrap();
+                self.cmd_buffers.push(cmd_buffer);
+            }
+        }
+
+        let mut tracker = StateTracker::new();
+        let mut last_batch = HashMap::new();
+        for (id, image) in self.images.iter().enumerate() {
+            if let GraphImageSource::Imported { ref states, .. } = image.source {
+                tracker.set_image_state(id, image.range(), states.start);
+            }
+        }
+        for (index, batch) in self.batches.iter().enumerate() {
+            for pass in &batch.passes {
+                for (resource, _, _) in self.passes[pass.0].accesses() {
+                    if let Resource::Image(id) = resource {
+                        last_batch.insert(id, index);
+                    }
+                }
+            }
+        }
+
+        let mut first_use = HashSet::new();
+        for (index, batch) in self.batches.iter().enumerate() {
+            let cmd_buffer = &mut self.cmd_buffers[index];
+            cmd_buffer.begin();
+
+            for &pass_id in &batch.passes {
+                let pass = &self.passes[pass_id.0];
+                let color = (image::COLOR_ATTACHMENT_READ | image::COLOR_ATTACHMENT_WRITE, image::ImageLayout::ColorAttachmentOptimal);
+                let images = pass.colors
+                    .iter()
+                    .map(|&(id, _)| (id, color, pso::COLOR_ATTACHMENT_OUTPUT))
+                    .chain(pass.images.iter().cloned())
+                    .collect::<Vec<_>>();
+                // Memory shared with earlier images has undefined content and
+                // can only be reused after all previous work completed.
+                for &(id, _, _) in &images {
+                    let image = &self.images[id.0];
+                    if image.aliased && first_use.insert(id.0) {
+                        let undefined = (image::Access::empty(), image::ImageLayout::Undefined);
+                        tracker.set_image_state(id.0, image.range(), undefined);
+                    }
+                }
+                let mut transitions = tracker.transitions::<B>();
+                for (id, state, stages) in images {
+                    let image = &self.images[id.0];
+                    transitions.image(id.0, image.raw(), image.range(), state, stages);
+                }
+                for &(id, access, stages) in &pass.buffers {
+                    let (buffer, size) = self.buffers[id.0];
+                    transitions.buffer(id.0, buffer, 0 .. size, access, stages);
+                }
+                transitions.record_raw(cmd_buffer);
+
+                match self.render_passes.get(&pass_id.0) {
+                    Some(info) => {
+                        cmd_buffer.begin_renderpass(
+                            &info.render_pass,
+                            &info.frame_buffer,
+                            info.area,
+                            &info.clear_values,
+                            command::SubpassContents::Inline,
+                        );
+                        record(pass_id, cmd_buffer);
+                        cmd_buffer.end_renderpass();
+                    }
+                    None => record(pass_id, cmd_buffer),
+                }
+            }
+
+            // Leave imported images in their final state.
+            let mut transitions = tracker.transitions::<B>();
+            for (id, image) in self.images.iter().enumerate() {
+                if let GraphImageSource::Imported { raw, ref states } = image.source {
+                    if last_batch.get(&id) == Some(&index) {
+                        transitions.image(id, raw, image.range(), states.end, pso::BOTTOM_OF_PIPE);
+                    }
+                }
+            }
+            transitions.record_raw(cmd_buffer);
+            cmd_buffer.finish();
+
+            let wait = batch.wait
+                .iter()
+                .map(|&semaphore| (&self.semaphores[semaphore], pso::TOP_OF_PIPE))
+                .collect::<Vec<_>>();
+            let signal = batch.signal
+                .iter()
+                .map(|&semaphore| &self.semaphores[semaphore])
+                .collect::<Vec<_>>();
+            let submission = RawSubmission {
+                cmd_buffers: &self.cmd_buffers[index .. index + 1],
+                wait_semaphores: &wait,
+                signal_semaphores: &signal,
+            };
+            let fence = if index + 1 == self.batches.len() { fence } else { None };
+            unsafe {
+                queues.get(batch.queue).submit_raw(submission, fence);
+            }
+        }
+    }
+
+    /// Destroy all objects created by the graph.
+    pub fn destroy(self, device: &mut B::Device) {
+        for (_, info) in self.render_passes {
+            device.destroy_framebuffer(info.frame_buffer);
+            for view in info.views {
+                device.destroy_render_target_view(view);
+            }
+            device.destroy_renderpass(info.render_pass);
+        }
+        for semaphore in self.semaphores {
+            device.destroy_semaphore(semaphore);
+        }
+        for image in self.images {
+            if let GraphImageSource::Transient { raw, .. } = image.source {
+                device.destroy_image(raw);
+            }
+        }
+        let mut allocator = self.allocator;
+        for heap in self.heaps {
+            allocator.free(heap);
+        }
+        allocator.destroy(device);
+    }
+}
@@ -234,13 +234,15 @@ impl Kind {
         };
         (map(w), map(h), dm, AaMode::Single)
     }
-    /// Count the number of mipmap levels.
+    /// Count the number of mipmap levels, down to and including the 1x1 level.
     pub fn get_num_levels(&self) -> Level {
         use std::cmp::max;
         let (w, h, d, aa) = self.get_dimensions();
         let dominant = max(max(w, h), d);
         if aa == AaMode::Single {
-            (1..).find(|level| dominant>>level <= 1).unwrap()
+            // one level per significant bit, without shifting past the width of `Size`
+            let bits = 8 * ::std::mem::size_of::<Size>() as u32 - dominant.leading_zeros();
+            max(bits, 1) as Level
         }else {
             1 // anti-aliased textures can't have mipmaps
         }
@@ -261,6 +263,14 @@ impl Kind {
             _ => false,
         }
     }
+    /// Check if images of this kind can have a block-compressed format,
+    /// which excludes 1D and multisampled images.
+    pub fn supports_compression(&self) -> bool {
+        match *self {
+            Kind::D1(_) | Kind::D1Array(_, _) => false,
+            _ => self.get_dimensions().3 == AaMode::Single,
+        }
+    }
 }
 
 bitflags!(
@@ -335,13 +345,29 @@ impl<F> ImageInfoCommon<F> {
         self.yoffset + self.height <= h &&
         self.zoffset + self.depth <= d
     }
+
+    /// Check if it is made of whole blocks of `(width, height)` texels inside given
+    /// dimensions. The size only needs to be a multiple of the block size where the
+    /// region doesn't reach the edge of the image.
+    pub fn is_block_aligned(&self, (block_width, block_height): (u8, u8), (w, h, _, _): Dimensions) -> bool {
+        let aligned = |offset: Size, size: Size, block: u8, end: Size| {
+            let block = block as Size;
+            offset % block == 0 && (size % block == 0 || offset + size == end)
+        };
+        aligned(self.xoffset, self.width, block_width, w) &&
+        aligned(self.yoffset, self.height, block_height, h)
+    }
 }
 
 impl RawImageInfo {
-    /// Get the total number of bytes.
+    /// Get the total number of bytes, counting whole blocks for compressed formats.
     pub fn get_byte_count(&self) -> usize {
-        let texel_bytes = self.format.0.get_total_bits() / 8;
-        self.get_texel_count() * (texel_bytes as usize)
+        use std::cmp::max;
+        self.format.0.get_byte_count(
+            max(1, self.width) as u32,
+            max(1, self.height) as u32,
+            max(1, self.depth) as u32,
+        )
     }
 }
 
@@ -493,7 +519,7 @@ impl From<RenderDesc> for DepthStencilDesc {
 }
 
 ///
-#[derive(Copy, Clone, Debug, Hash)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum ImageLayout {
     ///
@@ -32,7 +32,8 @@ pub use self::queue::{
     General, Graphics, Compute, Transfer,
 };
 pub use self::window::{
-    Backbuffer, Frame, FrameSync, Surface, Swapchain, SwapchainConfig};
+    AcquireError, Backbuffer, Extent2D, Frame, FrameSync, PresentError, PresentMode, Suboptimal,
+    Surface, SurfaceCapabilities, Swapchain, SwapchainConfig};
 pub use draw_state::{state, target};
 
 pub mod adapter;
@@ -40,12 +41,14 @@ pub mod buffer;
 pub mod command;
 pub mod device;
 pub mod format;
+pub mod graph;
 pub mod image;
 pub mod mapping;
 pub mod memory;
 pub mod pass;
 pub mod pool;
 pub mod pso;
+pub mod query;
 pub mod queue;
 pub mod window;
 
@@ -128,10 +131,16 @@ pub struct Features {
     pub sampler_anisotropy: bool,
     /// Support setting border texel colors.
     pub sampler_border_color: bool,
+    /// Support counting the exact number of samples in occlusion queries.
+    pub precise_occlusion_query: bool,
+    /// Support pipeline statistics queries.
+    pub pipeline_statistics_query: bool,
+    /// Support timestamp queries on graphics and compute queues.
+    pub timestamp_query: bool,
 }
 
 /// Limits of the device.
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub struct Limits {
     /// Maximum supported texture size.
@@ -146,6 +155,10 @@ pub struct Limits {
     /// The alignment of the row pitch of the texture data stored in a buffer that is
     /// used in a GPU copy operation, in bytes, non-zero.
     pub min_buffer_copy_pitch_alignment: usize,
+    /// The alignment of the start of a constant buffer view into a buffer, in bytes, non-zero.
+    pub min_constant_buffer_offset_alignment: usize,
+    /// Number of nanoseconds per tick of timestamp queries.
+    pub timestamp_period: f32,
 }
 
 /// Describes what geometric primitives are created from vertex data.
@@ -239,7 +252,7 @@ pub trait Backend: 'static + Sized + Eq + Clone + Hash + Debug + Any {
     type RenderPass:          Debug + Any + Send + Sync;
     type FrameBuffer:         Debug + Any + Send + Sync;
 
-    type Heap:                Debug + Any;
+    type Heap:                Debug + Any + Send + Sync;
     type Mapping:             Debug + Any + Send + Sync;
     type CommandPool:         RawCommandPool<Self>;
     type SubpassCommandPool:  SubpassCommandPool<Self>;
@@ -258,10 +271,13 @@ pub trait Backend: 'static + Sized + Eq + Clone + Hash + Debug + Any {
 
     type ComputePipeline:     Debug + Any + Send + Sync;
     type GraphicsPipeline:    Debug + Any + Send + Sync;
+    type PipelineCache:       Debug + Any + Send + Sync;
     type PipelineLayout:      Debug + Any + Send + Sync;
     type DescriptorPool:      DescriptorPool<Self>;
     type DescriptorSet:       Debug + Any + Send + Sync;
-    type DescriptorSetLayout: Debug + Any;
+    type DescriptorSetLayout: Debug + Any + Send + Sync;
+
+    type QueryPool:           Debug + Any + Send + Sync;
 
     type Fence:               Debug + Any + Send + Sync;
     type Semaphore:           Debug + Any + Send + Sync;
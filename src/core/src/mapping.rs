@@ -52,6 +52,12 @@ impl<'a, B: Backend, T: 'a + Copy> Reader<'a, B, T> {
             _mapping: mapping,
         }
     }
+
+    /// Split the reader into the mapped slice and the backend mapping.
+    #[doc(hidden)]
+    pub unsafe fn into_parts(self) -> (&'a [T], B::Mapping) {
+        (self.slice, self._mapping)
+    }
 }
 
 impl<'a, B: Backend, T: 'a + Copy> ops::Deref for Reader<'a, B, T> {
@@ -76,6 +82,12 @@ impl<'a, B: Backend, T: 'a + Copy> Writer<'a, B, T> {
             _mapping: mapping,
         }
     }
+
+    /// Split the writer into the mapped slice and the backend mapping.
+    #[doc(hidden)]
+    pub unsafe fn into_parts(self) -> (&'a mut [T], B::Mapping) {
+        (self.slice, self._mapping)
+    }
 }
 
 impl<'a, B: Backend, T: 'a + Copy> ops::Deref for Writer<'a, B, T> {
@@ -0,0 +1,478 @@
+//! Sub-allocation of resource memory.
+//!
+//! The `Allocator` creates large heaps and carves them into aligned ranges,
+//! so resources don't each need a dedicated `create_heap` call and manually
+//! computed offsets.
+
+use std::cmp;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use {buffer, format, image};
+use {Backend, Device, HeapType};
+use device::{ResourceHeapError, ResourceHeapType};
+use memory::{HeapProperties, Requirements};
+
+/// Strategy used to sub-allocate ranges of a heap.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum Strategy {
+    /// Allocations can be freed in any order.
+    ///
+    /// Freed ranges are merged with adjacent free ranges.
+    FreeList,
+    /// Allocations are placed one after another.
+    ///
+    /// Memory of a heap is only reclaimed once all of its allocations are freed,
+    /// which suits resources sharing the same lifetime, e.g. the ones of a level.
+    Linear,
+    /// Allocations are placed one after another, wrapping around at the end of a heap.
+    ///
+    /// Memory is reclaimed once all older allocations of the heap are freed,
+    /// which suits short-lived data like per-frame uploads.
+    Ring,
+}
+
+/// Error allocating memory or binding it to a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AllocationError {
+    /// No heap type provides the requested properties.
+    NoHeapType(HeapProperties),
+    /// Creating a new heap failed.
+    Heap(ResourceHeapError),
+    /// Creating or binding the buffer failed.
+    Buffer(buffer::CreationError),
+    /// Creating or binding the image failed.
+    Image(image::CreationError),
+}
+
+impl fmt::Display for AllocationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AllocationError::NoHeapType(properties) => write!(f, "{}: {:?}", self.description(), properties),
+            AllocationError::Heap(ref err) => write!(f, "{}: {:?}", self.description(), err),
+            AllocationError::Buffer(ref err) => write!(f, "{}: {}", self.description(), err),
+            AllocationError::Image(ref err) => write!(f, "{}: {}", self.description(), err),
+        }
+    }
+}
+
+impl Error for AllocationError {
+    fn description(&self) -> &str {
+        match *self {
+            AllocationError::NoHeapType(_) => "No heap type provides the requested properties",
+            AllocationError::Heap(_) => "Creating a new heap failed",
+            AllocationError::Buffer(_) => "Creating or binding the buffer failed",
+            AllocationError::Image(_) => "Creating or binding the image failed",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            AllocationError::Buffer(ref err) => Some(err),
+            AllocationError::Image(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Range of a heap owned by an `Allocator`.
+///
+/// Needs to be returned with `Allocator::free` once the resource bound to it
+/// has been destroyed.
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct Allocation {
+    block: usize,
+    range: Range<u64>,
+}
+
+impl Allocation {
+    /// Offset of the allocation inside its heap.
+    pub fn offset(&self) -> u64 {
+        self.range.start
+    }
+
+    /// Size of the allocation in bytes.
+    pub fn size(&self) -> u64 {
+        self.range.end - self.range.start
+    }
+}
+
+/// Memory usage statistics of an `Allocator`.
+///
+/// Used to decide when live resources should be moved into fresh heaps.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Number of heaps created by the allocator.
+    pub heaps: usize,
+    /// Number of live allocations.
+    pub allocations: usize,
+    /// Total size of all heaps.
+    pub reserved: u64,
+    /// Total size of all live allocations.
+    pub used: u64,
+    /// Total size of the ranges available for new allocations.
+    pub free: u64,
+    /// Number of distinct ranges available for new allocations.
+    pub free_ranges: usize,
+    /// Largest range available without creating a new heap.
+    pub largest_free: u64,
+}
+
+impl Stats {
+    /// Fragmentation of the reserved memory, ranging from 0 to 1.
+    ///
+    /// 0 means all memory not used by live allocations is available as a single range.
+    /// Memory which isn't used anymore but not yet reclaimed by the `Linear` and `Ring`
+    /// strategies counts as fragmented.
+    pub fn fragmentation(&self) -> f32 {
+        let unused = self.reserved - self.used;
+        if unused == 0 {
+            0.0
+        } else {
+            1.0 - self.largest_free as f32 / unused as f32
+        }
+    }
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    if alignment <= 1 {
+        offset
+    } else {
+        (offset + alignment - 1) / alignment * alignment
+    }
+}
+
+enum Arena {
+    /// Sorted, non-adjacent free ranges.
+    FreeList(Vec<Range<u64>>),
+    Linear {
+        head: u64,
+    },
+    Ring {
+        head: u64,
+        /// Live allocations in allocation order, with a flag marking them as freed.
+        live: VecDeque<(Range<u64>, bool)>,
+    },
+}
+
+impl Arena {
+    fn new(strategy: Strategy, size: u64) -> Self {
+        match strategy {
+            Strategy::FreeList => Arena::FreeList(vec![0 .. size]),
+            Strategy::Linear => Arena::Linear { head: 0 },
+            Strategy::Ring => Arena::Ring { head: 0, live: VecDeque::new() },
+        }
+    }
+
+    fn allocate(&mut self, block_size: u64, requirements: Requirements) -> Option<Range<u64>> {
+        let length = requirements.size;
+        match *self {
+            Arena::FreeList(ref mut free) => {
+                let position = free.iter().position(|range| {
+                    align(range.start, requirements.alignment) + length <= range.end
+                });
+                position.map(|index| {
+                    let range = free.remove(index);
+                    let offset = align(range.start, requirements.alignment);
+                    if offset + length < range.end {
+                        free.insert(index, offset + length .. range.end);
+                    }
+                    if range.start < offset {
+                        free.insert(index, range.start .. offset);
+                    }
+                    offset .. offset + length
+                })
+            }
+            Arena::Linear { ref mut head } => {
+                let offset = align(*head, requirements.alignment);
+                if offset + length <= block_size {
+                    *head = offset + length;
+                    Some(offset .. offset + length)
+                } else {
+                    None
+                }
+            }
+            Arena::Ring { ref mut head, ref mut live } => {
+                let offset = align(*head, requirements.alignment);
+                let offset = match live.front() {
+                    None if length <= block_size => Some(0),
+                    None => None,
+                    // Live allocations are in `tail .. head`, the free space wraps around.
+                    Some(&(ref tail, _)) if *head > tail.start => {
+                        if offset + length <= block_size {
+                            Some(offset)
+                        } else if length <= tail.start {
+                            Some(0)
+                        } else {
+                            None
+                        }
+                    }
+                    // Live allocations wrap around, free space is in `head .. tail`.
+                    Some(&(ref tail, _)) => if offset + length <= tail.start {
+                        Some(offset)
+                    } else {
+                        None
+                    },
+                };
+                offset.map(|offset| {
+                    *head = offset + length;
+                    live.push_back((offset .. offset + length, false));
+                    offset .. offset + length
+                })
+            }
+        }
+    }
+
+    fn free(&mut self, range: Range<u64>, remaining: usize) {
+        match *self {
+            Arena::FreeList(ref mut free) => {
+                let index = free
+                    .iter()
+                    .position(|r| r.start > range.start)
+                    .unwrap_or(free.len());
+                let merge_prev = index > 0 && free[index - 1].end == range.start;
+                let merge_next = index < free.len() && free[index].start == range.end;
+                match (merge_prev, merge_next) {
+                    (true, true) => {
+                        let next = free.remove(index);
+                        free[index - 1].end = next.end;
+                    }
+                    (true, false) => free[index - 1].end = range.end,
+                    (false, true) => free[index].start = range.start,
+                    (false, false) => free.insert(index, range),
+                }
+            }
+            Arena::Linear { ref mut head } => {
+                if remaining == 0 {
+                    *head = 0;
+                }
+            }
+            Arena::Ring { ref mut head, ref mut live } => {
+                if let Some(entry) = live.iter_mut().find(|entry| entry.0 == range) {
+                    entry.1 = true;
+                }
+                while live.front().map_or(false, |entry| entry.1) {
+                    live.pop_front();
+                }
+                if live.is_empty() {
+                    *head = 0;
+                }
+            }
+        }
+    }
+
+    /// Ranges available for new allocations.
+    fn free_ranges(&self, block_size: u64) -> Vec<Range<u64>> {
+        let ranges = match *self {
+            Arena::FreeList(ref free) => free.clone(),
+            Arena::Linear { head } => vec![head .. block_size],
+            Arena::Ring { head, ref live } => match live.front() {
+                None => vec![0 .. block_size],
+                Some(&(ref tail, _)) if head > tail.start => vec![0 .. tail.start, head .. block_size],
+                Some(&(ref tail, _)) => vec![head .. tail.start],
+            },
+        };
+        ranges.into_iter().filter(|range| range.start < range.end).collect()
+    }
+}
+
+struct Block<B: Backend> {
+    heap: B::Heap,
+    heap_type: usize,
+    resource_type: ResourceHeapType,
+    size: u64,
+    arena: Arena,
+    allocations: usize,
+    used: u64,
+}
+
+/// Sub-allocates resource memory from heaps created on demand.
+///
+/// Heaps are created with at least the configured block size, larger
+/// resources get a heap matching their size. Heaps are only destroyed by
+/// `shrink` and `destroy`.
+pub struct Allocator<B: Backend> {
+    heap_types: Vec<HeapType>,
+    strategy: Strategy,
+    block_size: u64,
+    blocks: Vec<Option<Block<B>>>,
+}
+
+impl<B: Backend> Allocator<B> {
+    /// Create an allocator choosing from the given heap types.
+    pub fn new(heap_types: &[HeapType], strategy: Strategy, block_size: u64) -> Self {
+        Allocator {
+            heap_types: heap_types.to_vec(),
+            strategy,
+            block_size,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Allocate a range fitting `requirements` from a heap with the requested properties.
+    ///
+    /// The first heap type containing all of `properties` is used.
+    pub fn allocate(
+        &mut self,
+        device: &mut B::Device,
+        properties: HeapProperties,
+        resource_type: ResourceHeapType,
+        requirements: Requirements,
+    ) -> Result<Allocation, AllocationError> {
+        let heap_type = match self.heap_types.iter().find(|ty| ty.properties.contains(properties)) {
+            Some(heap_type) => heap_type.clone(),
+            None => return Err(AllocationError::NoHeapType(properties)),
+        };
+
+        for (index, slot) in self.blocks.iter_mut().enumerate() {
+            let block = match *slot {
+                Some(ref mut block) if block.heap_type == heap_type.id && block.resource_type == resource_type => block,
+                _ => continue,
+            };
+            if let Some(range) = block.arena.allocate(block.size, requirements) {
+                block.allocations += 1;
+                block.used += requirements.size;
+                return Ok(Allocation { block: index, range });
+            }
+        }
+
+        let size = cmp::max(self.block_size, requirements.size);
+        let heap = device
+            .create_heap(&heap_type, resource_type, size)
+            .map_err(AllocationError::Heap)?;
+        let mut block = Block {
+            heap,
+            heap_type: heap_type.id,
+            resource_type,
+            size,
+            arena: Arena::new(self.strategy, size),
+            allocations: 1,
+            used: requirements.size,
+        };
+        let range = block.arena.allocate(size, requirements).unwrap();
+
+        let index = match self.blocks.iter().position(Option::is_none) {
+            Some(index) => {
+                self.blocks[index] = Some(block);
+                index
+            }
+            None => {
+                self.blocks.push(Some(block));
+                self.blocks.len() - 1
+            }
+        };
+        Ok(Allocation { block: index, range })
+    }
+
+    /// Return an allocation, making its range available again.
+    ///
+    /// The resource bound to it must have been destroyed beforehand.
+    pub fn free(&mut self, allocation: Allocation) {
+        let block = self.blocks[allocation.block]
+            .as_mut()
+            .expect("Allocation doesn't belong to this allocator");
+        block.allocations -= 1;
+        block.used -= allocation.size();
+        block.arena.free(allocation.range, block.allocations);
+    }
+
+    /// Heap containing the allocation.
+    pub fn heap(&self, allocation: &Allocation) -> &B::Heap {
+        &self.blocks[allocation.block]
+            .as_ref()
+            .expect("Allocation doesn't belong to this allocator")
+            .heap
+    }
+
+    /// Create a buffer and bind it to newly allocated memory.
+    pub fn create_buffer(
+        &mut self,
+        device: &mut B::Device,
+        size: u64,
+        stride: u64,
+        usage: buffer::Usage,
+        properties: HeapProperties,
+    ) -> Result<(B::Buffer, Allocation), AllocationError> {
+        let unbound = device.create_buffer(size, stride, usage).map_err(AllocationError::Buffer)?;
+        let requirements = device.get_buffer_requirements(&unbound);
+        let allocation = self.allocate(device, properties, ResourceHeapType::Buffers, requirements)?;
+        let result = device.bind_buffer_memory(self.heap(&allocation), allocation.offset(), unbound);
+        match result {
+            Ok(buffer) => Ok((buffer, allocation)),
+            Err(err) => {
+                self.free(allocation);
+                Err(AllocationError::Buffer(err))
+            }
+        }
+    }
+
+    /// Create an image and bind it to newly allocated memory.
+    ///
+    /// Images used as color or depth-stencil attachments are placed into `Targets` heaps.
+    pub fn create_image(
+        &mut self,
+        device: &mut B::Device,
+        kind: image::Kind,
+        mip_levels: image::Level,
+        format: format::Format,
+        usage: image::Usage,
+        properties: HeapProperties,
+    ) -> Result<(B::Image, Allocation), AllocationError> {
+        let unbound = device
+            .create_image(kind, mip_levels, format, usage)
+            .map_err(AllocationError::Image)?;
+        let requirements = device.get_image_requirements(&unbound);
+        let resource_type = if usage.intersects(image::COLOR_ATTACHMENT | image::DEPTH_STENCIL_ATTACHMENT) {
+            ResourceHeapType::Targets
+        } else {
+            ResourceHeapType::Images
+        };
+        let allocation = self.allocate(device, properties, resource_type, requirements)?;
+        let result = device.bind_image_memory(self.heap(&allocation), allocation.offset(), unbound);
+        match result {
+            Ok(image) => Ok((image, allocation)),
+            Err(err) => {
+                self.free(allocation);
+                Err(AllocationError::Image(err))
+            }
+        }
+    }
+
+    /// Gather memory usage statistics.
+    pub fn stats(&self) -> Stats {
+        let mut stats = Stats::default();
+        for block in self.blocks.iter().filter_map(Option::as_ref) {
+            stats.heaps += 1;
+            stats.allocations += block.allocations;
+            stats.reserved += block.size;
+            stats.used += block.used;
+            for range in block.arena.free_ranges(block.size) {
+                let size = range.end - range.start;
+                stats.free += size;
+                stats.free_ranges += 1;
+                stats.largest_free = cmp::max(stats.largest_free, size);
+            }
+        }
+        stats
+    }
+
+    /// Destroy all heaps without live allocations.
+    pub fn shrink(&mut self, device: &mut B::Device) {
+        for slot in &mut self.blocks {
+            if slot.as_ref().map_or(false, |block| block.allocations == 0) {
+                device.destroy_heap(slot.take().unwrap().heap);
+            }
+        }
+    }
+
+    /// Destroy all heaps.
+    ///
+    /// Resources bound to memory of this allocator must have been destroyed beforehand.
+    pub fn destroy(self, device: &mut B::Device) {
+        for block in self.blocks.into_iter().filter_map(|block| block) {
+            device.destroy_heap(block.heap);
+        }
+    }
+}
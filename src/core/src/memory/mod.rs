@@ -5,6 +5,12 @@ use std::ops::Range;
 use {buffer, image};
 use Backend;
 
+mod allocator;
+mod upload;
+
+pub use self::allocator::{Allocation, AllocationError, Allocator, Stats, Strategy};
+pub use self::upload::{ImageRegion, UploadError, Uploader};
+
 /// A service trait used to get the raw data out of strong types.
 /// Not meant for public use.
 #[doc(hidden)]
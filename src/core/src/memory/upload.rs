@@ -0,0 +1,352 @@
+//! Uploads of data into device local resources through staging buffers.
+//!
+//! The `Uploader` writes data into CPU visible staging memory and records the copies
+//! into the destination resources on a transfer command pool. Recorded copies are
+//! submitted as a batch, signaling semaphores for the queues consuming the resources.
+//! Staging memory of a batch is reclaimed once the fence of the batch is signaled.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::{cmp, fmt};
+use {buffer, format, image, mapping};
+use {Backend, Device, HeapType};
+use command::{BufferCopy, BufferImageCopy, Offset, Submit};
+use device::{Extent, WaitFor};
+use memory::{self, Allocation, AllocationError, Allocator, Pod, Strategy};
+use pool::{self, CommandPool};
+use queue::{CommandQueue, Submission, Transfer};
+use queue::capability::Supports;
+
+/// Error uploading data.
+#[derive(Clone, Debug, PartialEq)]
+pub enum UploadError {
+    /// Allocating a staging buffer failed.
+    Staging(AllocationError),
+    /// Mapping a staging buffer failed.
+    Mapping(mapping::Error),
+    /// The size of the data doesn't match the size of the destination region, in bytes.
+    SizeMismatch {
+        /// Size of the region.
+        expected: u64,
+        /// Size of the data.
+        actual: u64,
+    },
+}
+
+impl fmt::Display for UploadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            UploadError::Staging(ref err) => write!(f, "{}: {}", self.description(), err),
+            UploadError::Mapping(ref err) => write!(f, "{}: {}", self.description(), err),
+            UploadError::SizeMismatch { expected, actual } =>
+                write!(f, "{}: expected {} bytes, got {}", self.description(), expected, actual),
+        }
+    }
+}
+
+impl Error for UploadError {
+    fn description(&self) -> &str {
+        match *self {
+            UploadError::Staging(_) => "Allocating a staging buffer failed",
+            UploadError::Mapping(_) => "Mapping a staging buffer failed",
+            UploadError::SizeMismatch { .. } => "Texel data doesn't match the region size",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            UploadError::Staging(ref err) => Some(err),
+            UploadError::Mapping(ref err) => Some(err),
+            UploadError::SizeMismatch { .. } => None,
+        }
+    }
+}
+
+impl From<AllocationError> for UploadError {
+    fn from(err: AllocationError) -> Self {
+        UploadError::Staging(err)
+    }
+}
+
+impl From<mapping::Error> for UploadError {
+    fn from(err: mapping::Error) -> Self {
+        UploadError::Mapping(err)
+    }
+}
+
+/// Region of an image to upload into.
+#[derive(Clone, Debug)]
+pub struct ImageRegion {
+    /// Mip level and layers of the region.
+    pub subresource: image::SubresourceLayers,
+    /// Offset of the region in texels.
+    pub offset: Offset,
+    /// Size of the region in texels.
+    pub extent: Extent,
+}
+
+/// A staging buffer, filled from its start.
+struct Chunk<B: Backend> {
+    buffer: B::Buffer,
+    memory: Allocation,
+    size: u64,
+    used: u64,
+}
+
+/// Command pool and staging buffers of a batch of uploads.
+struct Batch<B: Backend> {
+    pool: CommandPool<B, Transfer>,
+    fence: B::Fence,
+    submits: Vec<Submit<B, Transfer>>,
+    chunks: Vec<Chunk<B>>,
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+/// Uploads data into buffers and images through pooled staging buffers.
+///
+/// Up to `batches` submitted batches can be in flight. Starting a new batch beyond that
+/// blocks until the oldest one has completed. The destination resources need to be in
+/// a layout and state allowing transfer writes, the uploader doesn't record barriers.
+pub struct Uploader<B: Backend> {
+    allocator: Allocator<B>,
+    block_size: u64,
+    copy_offset_alignment: u64,
+    copy_pitch_alignment: u64,
+    current: Option<Batch<B>>,
+    pending: VecDeque<Batch<B>>,
+    free: Vec<Batch<B>>,
+}
+
+impl<B: Backend> Uploader<B> {
+    /// Create an uploader recording its copies for `queue`.
+    ///
+    /// Staging memory is taken from the CPU visible heap types of `heap_types`,
+    /// in blocks of at least `block_size` bytes.
+    pub fn new<C>(
+        device: &mut B::Device,
+        queue: &CommandQueue<B, C>,
+        heap_types: &[HeapType],
+        batches: usize,
+        block_size: u64,
+    ) -> Self
+    where
+        C: Supports<Transfer>,
+    {
+        assert!(batches > 0, "An uploader needs at least one batch");
+        let (copy_offset_alignment, copy_pitch_alignment) = {
+            let limits = device.get_limits();
+            (cmp::max(limits.min_buffer_copy_offset_alignment, 1) as u64,
+             cmp::max(limits.min_buffer_copy_pitch_alignment, 1) as u64)
+        };
+        let free = (0 .. batches)
+            .map(|_| Batch {
+                pool: CommandPool::from_queue(queue, 1, pool::TRANSIENT),
+                fence: device.create_fence(false),
+                submits: Vec::new(),
+                chunks: Vec::new(),
+            })
+            .collect();
+
+        Uploader {
+            allocator: Allocator::new(heap_types, Strategy::Ring, block_size),
+            block_size,
+            copy_offset_alignment,
+            copy_pitch_alignment,
+            current: None,
+            pending: VecDeque::new(),
+            free,
+        }
+    }
+
+    /// Upload `data` into `dst`, starting at `offset` bytes.
+    pub fn upload_buffer<T: Pod>(
+        &mut self,
+        device: &mut B::Device,
+        dst: &B::Buffer,
+        offset: u64,
+        data: &[T],
+    ) -> Result<(), UploadError> {
+        let bytes: &[u8] = memory::cast_slice(data);
+        let (chunk, src) = self.stage(device, bytes.len() as u64, self.copy_offset_alignment)?;
+        {
+            let batch = self.current.as_mut().unwrap();
+            let staging = &batch.chunks[chunk].buffer;
+            {
+                let mut writer = device.write_mapping::<u8>(staging, src, bytes.len() as u64)?;
+                writer.copy_from_slice(bytes);
+            }
+            let mut cmd_buffer = batch.pool.acquire_command_buffer();
+            cmd_buffer.copy_buffer(staging, dst, &[BufferCopy {
+                src,
+                dst: offset,
+                size: bytes.len() as u64,
+            }]);
+            batch.submits.push(cmd_buffer.finish());
+        }
+        Ok(())
+    }
+
+    /// Upload `data` into a region of the color image `dst`, which is in `dst_layout`.
+    ///
+    /// `data` contains the tightly packed texels of the region in `format`, layer after layer,
+    /// or rows of blocks for compressed formats.
+    /// Rows are padded in the staging buffer to satisfy the copy pitch alignment.
+    pub fn upload_image<T: Pod>(
+        &mut self,
+        device: &mut B::Device,
+        dst: &B::Image,
+        dst_layout: image::ImageLayout,
+        format: format::Format,
+        region: ImageRegion,
+        data: &[T],
+    ) -> Result<(), UploadError> {
+        let bytes: &[u8] = memory::cast_slice(data);
+        let block_size = format.0.get_block_size() as u64;
+        let Extent { width, height, depth } = region.extent;
+        let layers = (region.subresource.1.end - region.subresource.1.start) as u64;
+        let row_size = format.0.get_row_size(width) as u64;
+        let slice_rows = format.0.get_num_rows(height) as u64;
+        let rows = slice_rows * depth as u64 * layers;
+        if row_size * rows != bytes.len() as u64 {
+            return Err(UploadError::SizeMismatch { expected: row_size * rows, actual: bytes.len() as u64 });
+        }
+
+        let row_pitch = align(row_size, self.copy_pitch_alignment);
+        // The offset into the staging buffer has to be a multiple of the block size as well.
+        let alignment = self.copy_offset_alignment / gcd(self.copy_offset_alignment, block_size) * block_size;
+        let (chunk, src) = self.stage(device, row_pitch * rows, alignment)?;
+        {
+            let batch = self.current.as_mut().unwrap();
+            let staging = &batch.chunks[chunk].buffer;
+            {
+                let mut writer = device.write_mapping::<u8>(staging, src, row_pitch * rows)?;
+                for (dst, src) in writer.chunks_mut(row_pitch as usize).zip(bytes.chunks(row_size as usize)) {
+                    dst[.. row_size as usize].copy_from_slice(src);
+                }
+            }
+            let mut cmd_buffer = batch.pool.acquire_command_buffer();
+            cmd_buffer.copy_buffer_to_image(staging, dst, dst_layout, &[BufferImageCopy {
+                buffer_offset: src,
+                buffer_row_pitch: row_pitch as u32,
+                buffer_slice_pitch: (row_pitch * slice_rows) as u32,
+                image_aspect: image::ASPECT_COLOR,
+                image_subresource: region.subresource,
+                image_offset: region.offset,
+                image_extent: region.extent,
+            }]);
+            batch.submits.push(cmd_buffer.finish());
+        }
+        Ok(())
+    }
+
+    /// Submit the recorded uploads to `queue`, signaling the `signal` semaphores once done.
+    ///
+    /// The queues using the uploaded resources should wait on one of the semaphores.
+    /// Returns the fence of the batch, which is signaled at the same time, or `None`
+    /// if nothing has been recorded.
+    pub fn submit<C>(
+        &mut self,
+        queue: &mut CommandQueue<B, C>,
+        signal: &[&B::Semaphore],
+    ) -> Option<&B::Fence>
+    where
+        C: Supports<Transfer>,
+    {
+        let batch = match self.current.take() {
+            Some(batch) => batch,
+            None => return None,
+        };
+        let submission = Submission::new()
+            .signal(signal)
+            .submit(&batch.submits);
+        queue.submit(submission, Some(&batch.fence));
+        self.pending.push_back(batch);
+        self.pending.back().map(|batch| &batch.fence)
+    }
+
+    /// Reclaim the staging memory of all completed batches.
+    pub fn cleanup(&mut self, device: &mut B::Device) {
+        while self.pending.front().map_or(false, |batch| device.wait_for_fences(&[&batch.fence], WaitFor::All, 0)) {
+            let batch = self.pending.pop_front().unwrap();
+            self.recycle(device, batch);
+        }
+    }
+
+    /// Wait for all submitted batches and destroy the uploader.
+    ///
+    /// Uploads which haven't been submitted are discarded.
+    pub fn destroy(mut self, device: &mut B::Device) {
+        if !self.pending.is_empty() {
+            let fences = self.pending.iter().map(|batch| &batch.fence).collect::<Vec<_>>();
+            device.wait_for_fences(&fences, WaitFor::All, !0);
+        }
+        let batches = self.pending.drain(..)
+            .chain(self.current.take())
+            .collect::<Vec<_>>();
+        for batch in batches {
+            self.recycle(device, batch);
+        }
+        for batch in self.free.drain(..) {
+            device.destroy_fence(batch.fence);
+        }
+        self.allocator.destroy(device);
+    }
+
+    /// Reserve `size` bytes of staging memory for the current batch, returning the index
+    /// of the chunk and the offset into it.
+    fn stage(&mut self, device: &mut B::Device, size: u64, alignment: u64) -> Result<(usize, u64), UploadError> {
+        if self.current.is_none() {
+            let batch = match self.free.pop() {
+                Some(batch) => batch,
+                None => {
+                    // All batches are in flight, wait for the oldest one.
+                    let batch = self.pending.pop_front().unwrap();
+                    device.wait_for_fences(&[&batch.fence], WaitFor::All, !0);
+                    self.recycle(device, batch);
+                    self.free.pop().unwrap()
+                }
+            };
+            self.current = Some(batch);
+        }
+
+        let block_size = self.block_size;
+        let batch = self.current.as_mut().unwrap();
+        if let Some(chunk) = batch.chunks.last_mut() {
+            let offset = align(chunk.used, alignment);
+            if offset + size <= chunk.size {
+                chunk.used = offset + size;
+                return Ok((batch.chunks.len() - 1, offset));
+            }
+        }
+
+        let chunk_size = cmp::max(size, block_size);
+        let (buffer, memory) = self.allocator.create_buffer(
+            device,
+            chunk_size,
+            1,
+            buffer::TRANSFER_SRC,
+            memory::CPU_VISIBLE | memory::COHERENT,
+        )?;
+        batch.chunks.push(Chunk { buffer, memory, size: chunk_size, used: size });
+        Ok((batch.chunks.len() - 1, 0))
+    }
+
+    /// Release the staging buffers of a batch, which has completed, and make it available again.
+    fn recycle(&mut self, device: &mut B::Device, mut batch: Batch<B>) {
+        for chunk in batch.chunks.drain(..) {
+            device.destroy_buffer(chunk.buffer);
+            self.allocator.free(chunk.memory);
+        }
+        device.reset_fences(&[&batch.fence]);
+        batch.pool.reset();
+        batch.submits.clear();
+        self.free.push(batch);
+    }
+}
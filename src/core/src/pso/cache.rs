@@ -0,0 +1,142 @@
+//! Deduplication of pipeline creation.
+//!
+//! The `PipelineCache` wraps a backend pipeline cache and additionally keeps the pipelines it
+//! created, so identical creation requests within a process return the same pipeline instead of
+//! compiling it again.
+
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::hash::Hash;
+use {Backend, Device};
+use pass::{Subpass, SubpassId};
+use super::{CreationError, EntryPoint, GraphicsPipelineDesc};
+
+/// Everything a graphics pipeline is created from.
+#[derive(Debug, Eq, Hash, PartialEq)]
+struct GraphicsKey<K> {
+    shaders: Vec<(EntryPoint, Vec<u8>)>,
+    desc: GraphicsPipelineDesc,
+    key: K,
+    subpass: SubpassId,
+}
+
+/// Everything a compute pipeline is created from.
+#[derive(Debug, Eq, Hash, PartialEq)]
+struct ComputeKey<K> {
+    shader: Vec<u8>,
+    entry_point: EntryPoint,
+    key: K,
+}
+
+/// Backend independent pipeline cache.
+///
+/// Pipelines are keyed on their description, shader code and subpass index. Backend objects
+/// can't be compared, so the pipeline layout and render pass are identified by a key `K`
+/// supplied by the caller: requests with the same key must use the same layout and, for
+/// graphics pipelines, the same render pass.
+///
+/// The data of the underlying backend cache can be retrieved with `data` and passed to `new`
+/// on the next run to speed up compilation of pipelines not yet created in this process.
+#[derive(Debug)]
+pub struct PipelineCache<B: Backend, K: Hash + Eq> {
+    raw: B::PipelineCache,
+    graphics: HashMap<GraphicsKey<K>, B::GraphicsPipeline>,
+    compute: HashMap<ComputeKey<K>, B::ComputePipeline>,
+}
+
+impl<B: Backend, K: Hash + Eq> PipelineCache<B, K> {
+    /// Create a new cache, filling the backend cache with `data` from a previous run.
+    pub fn new(device: &mut B::Device, data: &[u8]) -> Self {
+        PipelineCache {
+            raw: device.create_pipeline_cache(data),
+            graphics: HashMap::new(),
+            compute: HashMap::new(),
+        }
+    }
+
+    /// Get the underlying backend cache.
+    pub fn raw(&self) -> &B::PipelineCache {
+        &self.raw
+    }
+
+    /// Get the graphics pipeline for the given description, creating it on first use.
+    ///
+    /// `key` identifies the layout and render pass, `shaders` contains the code `shader_lib`
+    /// has been created from.
+    pub fn graphics_pipeline<'a>(
+        &mut self,
+        device: &mut B::Device,
+        key: K,
+        shaders: &[(EntryPoint, &[u8])],
+        (shader_lib, layout, subpass, desc):
+            (&B::ShaderLib, &B::PipelineLayout, Subpass<'a, B>, &GraphicsPipelineDesc),
+    ) -> Result<&B::GraphicsPipeline, CreationError> {
+        let key = GraphicsKey {
+            shaders: shaders.iter().map(|&(entry, code)| (entry, code.to_vec())).collect(),
+            desc: desc.clone(),
+            key,
+            subpass: subpass.index,
+        };
+        match self.graphics.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let pipeline = device
+                    .create_graphics_pipelines(&[(shader_lib, layout, subpass, desc)], Some(&self.raw))
+                    .remove(0)?;
+                Ok(entry.insert(pipeline))
+            }
+        }
+    }
+
+    /// Get the compute pipeline for the given entry point, creating it on first use.
+    ///
+    /// `key` identifies the layout, `shader` contains the code `shader_lib` has been created from.
+    pub fn compute_pipeline(
+        &mut self,
+        device: &mut B::Device,
+        key: K,
+        shader: &[u8],
+        (shader_lib, entry_point, layout): (&B::ShaderLib, EntryPoint, &B::PipelineLayout),
+    ) -> Result<&B::ComputePipeline, CreationError> {
+        let key = ComputeKey {
+            shader: shader.to_vec(),
+            entry_point,
+            key,
+        };
+        match self.compute.entry(key) {
+            Entry::Occupied(entry) => Ok(entry.into_mut()),
+            Entry::Vacant(entry) => {
+                let pipeline = device
+                    .create_compute_pipelines(&[(shader_lib, entry_point, layout)], Some(&self.raw))
+                    .remove(0)?;
+                Ok(entry.insert(pipeline))
+            }
+        }
+    }
+
+    /// Retrieve the data of the backend cache, e.g. to store it on disk.
+    pub fn data(&self, device: &mut B::Device) -> Vec<u8> {
+        device.get_pipeline_cache_data(&self.raw)
+    }
+
+    /// Number of pipelines created through this cache.
+    pub fn len(&self) -> usize {
+        self.graphics.len() + self.compute.len()
+    }
+
+    /// Whether no pipeline has been created through this cache yet.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Destroy the cache and all pipelines created through it.
+    pub fn destroy(self, device: &mut B::Device) {
+        for (_, pipeline) in self.graphics {
+            device.destroy_graphics_pipeline(pipeline);
+        }
+        for (_, pipeline) in self.compute {
+            device.destroy_compute_pipeline(pipeline);
+        }
+        device.destroy_pipeline_cache(self.raw);
+    }
+}
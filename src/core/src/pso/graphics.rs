@@ -1,5 +1,6 @@
 //! Graphics pipeline descriptor.
 
+use std::hash::{Hash, Hasher};
 use {state as s, Primitive};
 use super::EntryPoint;
 use super::input_assembler::{AttributeDesc, InputAssemblerDesc, VertexBufferDesc};
@@ -30,6 +31,7 @@ pub struct GraphicsShaderSet {
 }
 
 ///
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct GraphicsPipelineDesc {
     /// Rasterizer setup
     pub rasterizer: Rasterizer,
@@ -76,8 +78,27 @@ pub struct DepthBias {
     pub slope_factor: f32,
 }
 
+impl Hash for DepthBias {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.const_factor.to_bits().hash(state);
+        self.clamp.to_bits().hash(state);
+        self.slope_factor.to_bits().hash(state);
+    }
+}
+
+// Compared bitwise to stay consistent with `Hash`.
+impl PartialEq for DepthBias {
+    fn eq(&self, other: &Self) -> bool {
+        self.const_factor.to_bits() == other.const_factor.to_bits() &&
+        self.clamp.to_bits() == other.clamp.to_bits() &&
+        self.slope_factor.to_bits() == other.slope_factor.to_bits()
+    }
+}
+
+impl Eq for DepthBias {}
+
 /// Rasterization state.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 #[cfg_attr(feature="serialize", derive(Serialize, Deserialize))]
 pub struct Rasterizer {
     /// How to rasterize this primitive.
@@ -112,6 +133,7 @@ impl Rasterizer {
 }
 
 ///
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct BlendDesc {
     ///
     pub alpha_coverage: bool,
@@ -133,6 +155,7 @@ impl BlendDesc {
 }
 
 ///
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum LogicOp {
     ///
     Clear,
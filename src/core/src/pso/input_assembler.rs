@@ -62,6 +62,7 @@ pub enum PrimitiveRestart {
 }
 
 ///
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct InputAssemblerDesc {
     /// Type of the primitive
     pub primitive: Primitive,
@@ -8,11 +8,14 @@ use pass;
 use std::error::Error;
 use std::fmt;
 
+mod cache;
 mod descriptor;
 mod graphics;
 mod input_assembler;
 mod output_merger;
+pub mod reflect;
 
+pub use self::cache::PipelineCache;
 pub use self::descriptor::*;
 pub use self::graphics::*;
 pub use self::input_assembler::*;
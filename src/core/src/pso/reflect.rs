@@ -0,0 +1,604 @@
+//! Shader reflection.
+//!
+//! Parses SPIR-V modules and derives the pipeline interface of their entry
+//! points: descriptor set layouts, vertex inputs, push constant sizes and
+//! compute workgroup sizes.
+//!
+//! Resources aren't traced through the call graph, all descriptors and push
+//! constants declared by a module are attributed to each of its entry points.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use format::{ChannelType, Format, SurfaceType};
+use super::{
+    AttributeDesc, BufferIndex, DescriptorSetLayoutBinding, DescriptorType, Element,
+    Location, Stage, VertexBufferDesc,
+};
+use super::{STAGE_COMPUTE, STAGE_DOMAIN, STAGE_GEOMETRY, STAGE_HULL, STAGE_PIXEL, STAGE_VERTEX};
+
+const MAGIC: u32 = 0x0723_0203;
+
+// Opcodes
+const OP_ENTRY_POINT: u16 = 15;
+const OP_EXECUTION_MODE: u16 = 16;
+const OP_TYPE_INT: u16 = 21;
+const OP_TYPE_FLOAT: u16 = 22;
+const OP_TYPE_VECTOR: u16 = 23;
+const OP_TYPE_MATRIX: u16 = 24;
+const OP_TYPE_IMAGE: u16 = 25;
+const OP_TYPE_SAMPLER: u16 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u16 = 27;
+const OP_TYPE_ARRAY: u16 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u16 = 29;
+const OP_TYPE_STRUCT: u16 = 30;
+const OP_TYPE_POINTER: u16 = 32;
+const OP_CONSTANT: u16 = 43;
+const OP_SPEC_CONSTANT: u16 = 50;
+const OP_FUNCTION: u16 = 54;
+const OP_VARIABLE: u16 = 59;
+const OP_DECORATE: u16 = 71;
+const OP_MEMBER_DECORATE: u16 = 72;
+
+// Decorations
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/// Error parsing a SPIR-V module.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReflectError {
+    /// The size of the byte code isn't a multiple of 4.
+    InvalidSize(usize),
+    /// The module doesn't start with the SPIR-V magic number.
+    InvalidMagic(u32),
+    /// An instruction reaches past the end of the module.
+    UnexpectedEnd,
+    /// An id is referenced without being defined as the expected kind of object.
+    InvalidId(u32),
+    /// An interface variable has a type without descriptor or format equivalent.
+    UnsupportedType(u32),
+    /// Entry points declare the same binding with different types or counts.
+    BindingMismatch {
+        /// Descriptor set of the binding.
+        set: usize,
+        /// Index of the binding.
+        binding: usize,
+    },
+}
+
+impl fmt::Display for ReflectError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let description = self.description();
+        match *self {
+            ReflectError::InvalidSize(size) => write!(f, "{}: {}", description, size),
+            ReflectError::InvalidMagic(magic) => write!(f, "{}: {:#x}", description, magic),
+            ReflectError::InvalidId(id) |
+            ReflectError::UnsupportedType(id) => write!(f, "{}: %{}", description, id),
+            ReflectError::BindingMismatch { set, binding } =>
+                write!(f, "{}: set {}, binding {}", description, set, binding),
+            ReflectError::UnexpectedEnd => write!(f, "{}", description),
+        }
+    }
+}
+
+impl Error for ReflectError {
+    fn description(&self) -> &str {
+        match *self {
+            ReflectError::InvalidSize(_) => "SPIR-V byte code size isn't a multiple of 4",
+            ReflectError::InvalidMagic(_) => "Missing SPIR-V magic number",
+            ReflectError::UnexpectedEnd => "Unexpected end of the SPIR-V module",
+            ReflectError::InvalidId(_) => "Invalid SPIR-V id",
+            ReflectError::UnsupportedType(_) => "Unsupported interface variable type",
+            ReflectError::BindingMismatch { .. } => "Conflicting declarations of a descriptor binding",
+        }
+    }
+}
+
+/// Pipeline interface of a shader entry point.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryPointInfo {
+    /// Name of the entry point.
+    pub name: String,
+    /// Shader stage executing the entry point.
+    pub stage: Stage,
+    /// Bindings of each descriptor set, sorted by binding index.
+    ///
+    /// Sets without any bindings, but followed by used ones, are empty.
+    /// Runtime sized arrays have a count of 0.
+    pub descriptor_sets: Vec<Vec<DescriptorSetLayoutBinding>>,
+    /// Formats of the stage inputs, sorted by location.
+    ///
+    /// Matrices, arrays and structures occupy consecutive locations.
+    pub inputs: Vec<(Location, Format)>,
+    /// Size of the push constant block in bytes, 0 if there is none.
+    pub push_constant_size: u32,
+    /// Local workgroup size of compute shaders.
+    pub workgroup_size: Option<[u32; 3]>,
+}
+
+impl EntryPointInfo {
+    /// Describe the vertex inputs as attributes of a single, tightly packed vertex buffer.
+    pub fn vertex_attributes(&self, binding: BufferIndex) -> (VertexBufferDesc, Vec<AttributeDesc>) {
+        let mut offset = 0;
+        let attributes = self.inputs
+            .iter()
+            .map(|&(location, format)| {
+                let attribute = AttributeDesc {
+                    location,
+                    binding,
+                    element: Element { format, offset },
+                };
+                offset += format.0.get_total_bits() as u32 / 8;
+                attribute
+            })
+            .collect();
+        (VertexBufferDesc { stride: offset, rate: 0 }, attributes)
+    }
+}
+
+/// Combine the descriptor sets of multiple entry points, e.g. all stages of a pipeline.
+///
+/// Stage flags of bindings used by multiple entry points are merged.
+pub fn merge_descriptor_sets(entry_points: &[&EntryPointInfo]) -> Result<Vec<Vec<DescriptorSetLayoutBinding>>, ReflectError> {
+    let mut sets: Vec<Vec<DescriptorSetLayoutBinding>> = Vec::new();
+    for entry_point in entry_points {
+        for (set, bindings) in entry_point.descriptor_sets.iter().enumerate() {
+            if sets.len() <= set {
+                sets.resize(set + 1, Vec::new());
+            }
+            for binding in bindings {
+                match sets[set].iter().position(|b| b.binding == binding.binding) {
+                    Some(index) => {
+                        let existing = &mut sets[set][index];
+                        if existing.ty != binding.ty || existing.count != binding.count {
+                            return Err(ReflectError::BindingMismatch { set, binding: binding.binding });
+                        }
+                        existing.stage_flags |= binding.stage_flags;
+                    }
+                    None => sets[set].push(*binding),
+                }
+            }
+            sets[set].sort_by_key(|b| b.binding);
+        }
+    }
+    Ok(sets)
+}
+
+/// Parse a SPIR-V module and reflect all of its entry points.
+pub fn reflect(spirv: &[u8]) -> Result<Vec<EntryPointInfo>, ReflectError> {
+    let words = to_words(spirv)?;
+    let module = Module::parse(&words)?;
+
+    let mut entry_points = Vec::new();
+    for entry in &module.entry_points {
+        let stage = match entry.model {
+            0 => Stage::Vertex,
+            1 => Stage::Hull,
+            2 => Stage::Domain,
+            3 => Stage::Geometry,
+            4 => Stage::Pixel,
+            5 => Stage::Compute,
+            // Kernels aren't usable in pipelines.
+            _ => continue,
+        };
+        let stage_flags = match stage {
+            Stage::Vertex => STAGE_VERTEX,
+            Stage::Hull => STAGE_HULL,
+            Stage::Domain => STAGE_DOMAIN,
+            Stage::Geometry => STAGE_GEOMETRY,
+            Stage::Pixel => STAGE_PIXEL,
+            Stage::Compute => STAGE_COMPUTE,
+        };
+
+        let mut descriptor_sets: Vec<Vec<DescriptorSetLayoutBinding>> = Vec::new();
+        let mut push_constant_size = 0;
+        for &(id, pointer_type, storage) in &module.variables {
+            match storage {
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let decoration = module.decoration(id);
+                    let set = decoration.set.unwrap_or(0) as usize;
+                    let (ty, count) = module.descriptor(module.pointee(pointer_type)?, storage)?;
+                    if descriptor_sets.len() <= set {
+                        descriptor_sets.resize(set + 1, Vec::new());
+                    }
+                    descriptor_sets[set].push(DescriptorSetLayoutBinding {
+                        binding: decoration.binding.unwrap_or(0) as usize,
+                        ty,
+                        count,
+                        stage_flags,
+                    });
+                }
+                STORAGE_PUSH_CONSTANT => {
+                    let size = module.size_of(module.pointee(pointer_type)?, None)?;
+                    push_constant_size = push_constant_size.max(size);
+                }
+                _ => {}
+            }
+        }
+        for bindings in &mut descriptor_sets {
+            bindings.sort_by_key(|b| b.binding);
+        }
+
+        let mut inputs = Vec::new();
+        for &id in &entry.interface {
+            let (pointer_type, storage) = match module.variables.iter().find(|&&(var, _, _)| var == id) {
+                Some(&(_, pointer_type, storage)) => (pointer_type, storage),
+                None => return Err(ReflectError::InvalidId(id)),
+            };
+            let decoration = module.decoration(id);
+            if storage != STORAGE_INPUT || decoration.built_in {
+                continue;
+            }
+            let mut ty = module.pointee(pointer_type)?;
+            // Inputs of tessellation and geometry shaders are per-vertex arrays.
+            match stage {
+                Stage::Hull | Stage::Domain | Stage::Geometry => {
+                    if let Some(&Type::Array { element, .. }) = module.types.get(&ty) {
+                        ty = element;
+                    }
+                }
+                _ => {}
+            }
+            if module.has_built_in_members(ty) {
+                continue;
+            }
+            let location = match decoration.location {
+                Some(location) => location,
+                None => return Err(ReflectError::InvalidId(id)),
+            };
+            let formats = module.input_formats(ty)?;
+            inputs.extend(formats.into_iter().enumerate().map(|(i, format)| (location + i as Location, format)));
+        }
+        inputs.sort_by_key(|&(location, _)| location);
+
+        entry_points.push(EntryPointInfo {
+            name: entry.name.clone(),
+            stage,
+            descriptor_sets,
+            inputs,
+            push_constant_size,
+            workgroup_size: module.local_sizes.get(&entry.function).cloned(),
+        });
+    }
+
+    Ok(entry_points)
+}
+
+fn to_words(spirv: &[u8]) -> Result<Vec<u32>, ReflectError> {
+    if spirv.len() % 4 != 0 {
+        return Err(ReflectError::InvalidSize(spirv.len()));
+    }
+    let mut words = spirv
+        .chunks(4)
+        .map(|b| b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16 | (b[3] as u32) << 24)
+        .collect::<Vec<_>>();
+    match words.first() {
+        Some(&MAGIC) => {}
+        Some(&magic) if magic.swap_bytes() == MAGIC => {
+            for word in &mut words {
+                *word = word.swap_bytes();
+            }
+        }
+        Some(&magic) => return Err(ReflectError::InvalidMagic(magic)),
+        None => return Err(ReflectError::UnexpectedEnd),
+    }
+    Ok(words)
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Type {
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage { image: u32 },
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Pointer { pointee: u32 },
+    Other,
+}
+
+#[derive(Clone, Debug, Default)]
+struct Decoration {
+    location: Option<u32>,
+    binding: Option<u32>,
+    set: Option<u32>,
+    array_stride: Option<u32>,
+    built_in: bool,
+    block: bool,
+    buffer_block: bool,
+}
+
+#[derive(Clone, Debug, Default)]
+struct MemberDecoration {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+    built_in: bool,
+}
+
+struct RawEntryPoint {
+    model: u32,
+    function: u32,
+    name: String,
+    interface: Vec<u32>,
+}
+
+/// Global declarations of a module.
+#[derive(Default)]
+struct Module {
+    entry_points: Vec<RawEntryPoint>,
+    local_sizes: HashMap<u32, [u32; 3]>,
+    decorations: HashMap<u32, Decoration>,
+    member_decorations: HashMap<(u32, u32), MemberDecoration>,
+    types: HashMap<u32, Type>,
+    structs: HashMap<u32, Vec<u32>>,
+    constants: HashMap<u32, u32>,
+    /// Variable id, pointer type and storage class.
+    variables: Vec<(u32, u32, u32)>,
+}
+
+impl Module {
+    fn parse(words: &[u32]) -> Result<Module, ReflectError> {
+        let mut module = Module::default();
+        let mut offset = 5;
+        while offset < words.len() {
+            let count = (words[offset] >> 16) as usize;
+            let opcode = (words[offset] & 0xFFFF) as u16;
+            if count == 0 || offset + count > words.len() {
+                return Err(ReflectError::UnexpectedEnd);
+            }
+            let operands = &words[offset + 1 .. offset + count];
+            offset += count;
+
+            let operand = |index: usize| operands.get(index).cloned().ok_or(ReflectError::UnexpectedEnd);
+            match opcode {
+                OP_ENTRY_POINT => {
+                    let (model, function) = (operand(0)?, operand(1)?);
+                    let (name, length) = parse_string(&operands[2 ..]);
+                    module.entry_points.push(RawEntryPoint {
+                        model,
+                        function,
+                        name,
+                        interface: operands[2 + length ..].to_vec(),
+                    });
+                }
+                OP_EXECUTION_MODE if operand(1)? == EXECUTION_MODE_LOCAL_SIZE => {
+                    module.local_sizes.insert(operand(0)?, [operand(2)?, operand(3)?, operand(4)?]);
+                }
+                OP_DECORATE => {
+                    let decoration = module.decorations.entry(operand(0)?).or_insert_with(Decoration::default);
+                    match operand(1)? {
+                        DECORATION_BLOCK => decoration.block = true,
+                        DECORATION_BUFFER_BLOCK => decoration.buffer_block = true,
+                        DECORATION_ARRAY_STRIDE => decoration.array_stride = Some(operand(2)?),
+                        DECORATION_BUILT_IN => decoration.built_in = true,
+                        DECORATION_LOCATION => decoration.location = Some(operand(2)?),
+                        DECORATION_BINDING => decoration.binding = Some(operand(2)?),
+                        DECORATION_DESCRIPTOR_SET => decoration.set = Some(operand(2)?),
+                        _ => {}
+                    }
+                }
+                OP_MEMBER_DECORATE => {
+                    let key = (operand(0)?, operand(1)?);
+                    let decoration = module.member_decorations.entry(key).or_insert_with(MemberDecoration::default);
+                    match operand(2)? {
+                        DECORATION_OFFSET => decoration.offset = Some(operand(3)?),
+                        DECORATION_MATRIX_STRIDE => decoration.matrix_stride = Some(operand(3)?),
+                        DECORATION_BUILT_IN => decoration.built_in = true,
+                        _ => {}
+                    }
+                }
+                OP_TYPE_INT => {
+                    module.types.insert(operand(0)?, Type::Int { width: operand(1)?, signed: operand(2)? != 0 });
+                }
+                OP_TYPE_FLOAT => {
+                    module.types.insert(operand(0)?, Type::Float { width: operand(1)? });
+                }
+                OP_TYPE_VECTOR => {
+                    module.types.insert(operand(0)?, Type::Vector { component: operand(1)?, count: operand(2)? });
+                }
+                OP_TYPE_MATRIX => {
+                    module.types.insert(operand(0)?, Type::Matrix { column: operand(1)?, count: operand(2)? });
+                }
+                OP_TYPE_IMAGE => {
+                    module.types.insert(operand(0)?, Type::Image { dim: operand(2)?, sampled: operand(6)? });
+                }
+                OP_TYPE_SAMPLER => {
+                    module.types.insert(operand(0)?, Type::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    module.types.insert(operand(0)?, Type::SampledImage { image: operand(1)? });
+                }
+                OP_TYPE_ARRAY => {
+                    let length = module.constants.get(&operand(2)?).cloned().ok_or(ReflectError::InvalidId(operand(2)?))?;
+                    module.types.insert(operand(0)?, Type::Array { element: operand(1)?, length });
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    module.types.insert(operand(0)?, Type::RuntimeArray { element: operand(1)? });
+                }
+                OP_TYPE_STRUCT => {
+                    module.types.insert(operand(0)?, Type::Other);
+                    module.structs.insert(operand(0)?, operands[1 ..].to_vec());
+                }
+                OP_TYPE_POINTER => {
+                    module.types.insert(operand(0)?, Type::Pointer { pointee: operand(2)? });
+                }
+                OP_CONSTANT | OP_SPEC_CONSTANT => {
+                    module.constants.insert(operand(1)?, operand(2)?);
+                }
+                OP_VARIABLE => {
+                    module.variables.push((operand(1)?, operand(0)?, operand(2)?));
+                }
+                // Global declarations precede all function definitions.
+                OP_FUNCTION => break,
+                opcode if opcode >= 19 && opcode <= 39 => {
+                    module.types.insert(operand(0)?, Type::Other);
+                }
+                _ => {}
+            }
+        }
+        Ok(module)
+    }
+
+    fn decoration(&self, id: u32) -> Decoration {
+        self.decorations.get(&id).cloned().unwrap_or_default()
+    }
+
+    fn get(&self, id: u32) -> Result<Type, ReflectError> {
+        self.types.get(&id).cloned().ok_or(ReflectError::InvalidId(id))
+    }
+
+    fn pointee(&self, pointer: u32) -> Result<u32, ReflectError> {
+        match self.get(pointer)? {
+            Type::Pointer { pointee } => Ok(pointee),
+            _ => Err(ReflectError::InvalidId(pointer)),
+        }
+    }
+
+    fn has_built_in_members(&self, ty: u32) -> bool {
+        self.structs
+            .get(&ty)
+            .map_or(false, |members| (0 .. members.len() as u32).any(|index| {
+                self.member_decorations.get(&(ty, index)).map_or(false, |d| d.built_in)
+            }))
+    }
+
+    fn descriptor(&self, ty: u32, storage: u32) -> Result<(DescriptorType, usize), ReflectError> {
+        match self.get(ty)? {
+            Type::Array { element, length } => {
+                let (ty, count) = self.descriptor(element, storage)?;
+                Ok((ty, count * length as usize))
+            }
+            Type::RuntimeArray { element } => {
+                let (ty, _) = self.descriptor(element, storage)?;
+                Ok((ty, 0))
+            }
+            Type::Sampler => Ok((DescriptorType::Sampler, 1)),
+            // Combined image samplers are exposed as sampled images or texel buffers.
+            Type::SampledImage { image } => self.descriptor(image, storage),
+            Type::Image { dim: DIM_BUFFER, sampled: 2 } => Ok((DescriptorType::StorageTexelBuffer, 1)),
+            Type::Image { dim: DIM_BUFFER, .. } => Ok((DescriptorType::UniformTexelBuffer, 1)),
+            Type::Image { dim: DIM_SUBPASS_DATA, .. } => Ok((DescriptorType::InputAttachment, 1)),
+            Type::Image { sampled: 2, .. } => Ok((DescriptorType::StorageImage, 1)),
+            Type::Image { .. } => Ok((DescriptorType::SampledImage, 1)),
+            _ if self.structs.contains_key(&ty) => {
+                let decoration = self.decoration(ty);
+                match storage {
+                    STORAGE_UNIFORM if decoration.buffer_block => Ok((DescriptorType::StorageBuffer, 1)),
+                    STORAGE_UNIFORM => Ok((DescriptorType::ConstantBuffer, 1)),
+                    STORAGE_STORAGE_BUFFER => Ok((DescriptorType::StorageBuffer, 1)),
+                    _ => Err(ReflectError::UnsupportedType(ty)),
+                }
+            }
+            _ => Err(ReflectError::UnsupportedType(ty)),
+        }
+    }
+
+    fn input_formats(&self, ty: u32) -> Result<Vec<Format>, ReflectError> {
+        let scalar = |ty: u32| match self.get(ty)? {
+            Type::Float { width: 32 } => Ok(ChannelType::Float),
+            Type::Int { width: 32, signed: true } => Ok(ChannelType::Int),
+            Type::Int { width: 32, signed: false } => Ok(ChannelType::Uint),
+            _ => Err(ReflectError::UnsupportedType(ty)),
+        };
+        match self.get(ty)? {
+            Type::Float { .. } | Type::Int { .. } => Ok(vec![Format(SurfaceType::R32, scalar(ty)?)]),
+            Type::Vector { component, count } => {
+                let surface = match count {
+                    2 => SurfaceType::R32_G32,
+                    3 => SurfaceType::R32_G32_B32,
+                    4 => SurfaceType::R32_G32_B32_A32,
+                    _ => return Err(ReflectError::UnsupportedType(ty)),
+                };
+                Ok(vec![Format(surface, scalar(component)?)])
+            }
+            Type::Matrix { column, count } => {
+                let column = self.input_formats(column)?;
+                Ok((0 .. count).flat_map(|_| column.clone()).collect())
+            }
+            Type::Array { element, length } => {
+                let element = self.input_formats(element)?;
+                Ok((0 .. length).flat_map(|_| element.clone()).collect())
+            }
+            _ if self.structs.contains_key(&ty) => {
+                let mut formats = Vec::new();
+                for &member in &self.structs[&ty] {
+                    formats.extend(self.input_formats(member)?);
+                }
+                Ok(formats)
+            }
+            _ => Err(ReflectError::UnsupportedType(ty)),
+        }
+    }
+
+    /// Size of a type in bytes, matrix strides are decorated on struct members.
+    fn size_of(&self, ty: u32, matrix_stride: Option<u32>) -> Result<u32, ReflectError> {
+        match self.get(ty)? {
+            Type::Int { width, .. } | Type::Float { width } => Ok(width / 8),
+            Type::Vector { component, count } => Ok(count * self.size_of(component, None)?),
+            Type::Matrix { column, count } => {
+                let stride = match matrix_stride {
+                    Some(stride) => stride,
+                    None => self.size_of(column, None)?,
+                };
+                Ok(count * stride)
+            }
+            Type::Array { element, length } => {
+                let stride = match self.decoration(ty).array_stride {
+                    Some(stride) => stride,
+                    None => self.size_of(element, matrix_stride)?,
+                };
+                Ok(length * stride)
+            }
+            _ if self.structs.contains_key(&ty) => {
+                let mut size = 0;
+                for (index, &member) in self.structs[&ty].iter().enumerate() {
+                    let decoration = self.member_decorations
+                        .get(&(ty, index as u32))
+                        .cloned()
+                        .unwrap_or_default();
+                    let offset = decoration.offset.unwrap_or(size);
+                    size = size.max(offset + self.size_of(member, decoration.matrix_stride)?);
+                }
+                Ok(size)
+            }
+            _ => Err(ReflectError::UnsupportedType(ty)),
+        }
+    }
+}
+
+/// Parse a nul-terminated literal string, returning it with the number of words occupied.
+fn parse_string(words: &[u32]) -> (String, usize) {
+    let mut bytes = Vec::new();
+    for (index, &word) in words.iter().enumerate() {
+        for shift in 0 .. 4 {
+            let byte = (word >> (shift * 8)) as u8;
+            if byte == 0 {
+                return (String::from_utf8_lossy(&bytes).into_owned(), index + 1);
+            }
+            bytes.push(byte);
+        }
+    }
+    (String::from_utf8_lossy(&bytes).into_owned(), words.len())
+}
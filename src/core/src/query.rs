@@ -0,0 +1,117 @@
+//! Queries are commands that answer questions about the execution of other commands,
+//! like the number of samples passing the depth test or the point in time at which
+//! the preceding commands completed a pipeline stage.
+//!
+//! Queries are allocated from a `QueryPool` of a single `QueryType`. Each query has to be
+//! reset before it can be used, see `RawCommandBuffer::reset_query_pool`. The results are
+//! retrieved on the host with `Device::get_query_pool_results` or copied into a buffer on
+//! the device with `RawCommandBuffer::copy_query_pool_results`.
+//!
+//! All results are 64-bit unsigned integers. Timestamps are counted in ticks, which last
+//! `Limits::timestamp_period` nanoseconds each.
+
+use Backend;
+
+/// Index of a query within a query pool.
+pub type QueryId = u32;
+
+/// Query within a query pool.
+#[derive(Debug)]
+pub struct Query<'a, B: Backend> {
+    /// Pool the query is allocated from.
+    pub pool: &'a B::QueryPool,
+    /// Index of the query within the pool.
+    pub id: QueryId,
+}
+
+// Derived implementations would require `B: Clone`.
+impl<'a, B: Backend> Clone for Query<'a, B> {
+    fn clone(&self) -> Self {
+        Query { pool: self.pool, id: self.id }
+    }
+}
+
+impl<'a, B: Backend> Copy for Query<'a, B> {}
+
+bitflags!(
+    /// Control flags of occlusion queries.
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    pub flags QueryControl: u8 {
+        /// Count the exact number of samples passing the depth and stencil tests.
+        ///
+        /// Otherwise the result is only guaranteed to be non-zero if any sample passed.
+        /// Requires the `precise_occlusion_query` feature.
+        const PRECISE = 0x1,
+    }
+);
+
+bitflags!(
+    /// Statistics counted by pipeline statistics queries.
+    ///
+    /// Each statistic takes one result value, ordered by increasing bit value.
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    pub flags PipelineStatistic: u16 {
+        /// Number of vertices read by the input assembler.
+        const INPUT_ASSEMBLY_VERTICES = 0x1,
+        /// Number of primitives read by the input assembler.
+        const INPUT_ASSEMBLY_PRIMITIVES = 0x2,
+        /// Number of vertex shader invocations.
+        const VERTEX_SHADER_INVOCATIONS = 0x4,
+        /// Number of geometry shader invocations.
+        const GEOMETRY_SHADER_INVOCATIONS = 0x8,
+        /// Number of primitives generated by geometry shaders.
+        const GEOMETRY_SHADER_PRIMITIVES = 0x10,
+        /// Number of primitives processed by the clipping stage.
+        const CLIPPING_INVOCATIONS = 0x20,
+        /// Number of primitives output by the clipping stage.
+        const CLIPPING_PRIMITIVES = 0x40,
+        /// Number of pixel shader invocations.
+        const PIXEL_SHADER_INVOCATIONS = 0x80,
+        /// Number of patches processed by hull shaders.
+        const HULL_SHADER_PATCHES = 0x100,
+        /// Number of domain shader invocations.
+        const DOMAIN_SHADER_INVOCATIONS = 0x200,
+        /// Number of compute shader invocations.
+        const COMPUTE_SHADER_INVOCATIONS = 0x400,
+    }
+);
+
+bitflags!(
+    /// Flags controlling how query results are retrieved.
+    #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+    pub flags ResultFlags: u8 {
+        /// Wait until the results of all queries are available.
+        const WAIT = 0x1,
+        /// Follow the values of each query with its availability, non-zero if available.
+        const WITH_AVAILABILITY = 0x2,
+        /// Write intermediate values for queries whose results aren't available yet.
+        const PARTIAL = 0x4,
+    }
+);
+
+/// Type of the queries of a query pool.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum QueryType {
+    /// Number of samples passing the depth and stencil tests between the beginning
+    /// and the end of the query.
+    Occlusion,
+    /// Pipeline statistics counted between the beginning and the end of the query.
+    ///
+    /// Requires the `pipeline_statistics_query` feature.
+    PipelineStatistics(PipelineStatistic),
+    /// Point in time at which the preceding commands completed a pipeline stage.
+    ///
+    /// Requires the `timestamp_query` feature.
+    Timestamp,
+}
+
+impl QueryType {
+    /// Number of result values of each query.
+    pub fn num_values(&self) -> usize {
+        match *self {
+            QueryType::Occlusion | QueryType::Timestamp => 1,
+            QueryType::PipelineStatistics(statistics) => statistics.bits().count_ones() as usize,
+        }
+    }
+}
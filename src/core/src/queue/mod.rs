@@ -18,7 +18,7 @@ pub use self::capability::{Compute, Graphics, General, Transfer, Supports};
 pub use self::submission::{RawSubmission, Submission};
 
 ///
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
 pub enum QueueType {
     ///
@@ -80,6 +80,11 @@ impl<B: Backend, C> CommandQueue<B, C> {
         CommandQueue(raw, PhantomData)
     }
 
+    #[doc(hidden)]
+    pub fn into_raw(self) -> B::CommandQueue {
+        self.0
+    }
+
     /// Get a reference to the raw command queue
     pub fn as_raw(&self) -> &B::CommandQueue {
         &self.0
@@ -34,10 +34,10 @@
 //! let acquisition_semaphore = device.create_semaphore();
 //! let render_semaphore = device.create_semaphore();
 //!
-//! let frame = swapchain.acquire_frame(FrameSync::Semaphore(&acquisition_semaphore));
+//! let frame = swapchain.acquire_frame(FrameSync::Semaphore(&acquisition_semaphore)).unwrap();
 //! // render the scene..
 //! // `render_semaphore` will be signalled once rendering has been finished
-//! swapchain.present(&mut present_queue, &[&render_semaphore]);
+//! swapchain.present(&mut present_queue, &[&render_semaphore]).unwrap();
 //! # }
 //! ```
 //!
@@ -47,7 +47,33 @@
 //!
 //! ### Recreation
 //!
-//! //TODO
+//! The backbuffers of a swapchain keep the extent they were created with. Once the window
+//! gets resized, acquisition or presentation report that the swapchain is out of date or
+//! suboptimal. It can then be replaced with one matching the new size of the surface,
+//! without tearing down the device:
+//!
+//! ```no_run
+//! # extern crate gfx_backend_empty as empty;
+//! # extern crate gfx_core;
+//! # fn main() {
+//! use gfx_core::{Surface, SwapchainConfig};
+//! # use gfx_core::{CommandQueue, Graphics};
+//!
+//! # let mut surface: empty::Surface = return;
+//! # let adapter: empty::Adapter = return;
+//! # let queue: CommandQueue<empty::Backend, Graphics> = return;
+//! # let swapchain: empty::Swapchain = return;
+//! let capabilities = surface.get_capabilities(&adapter);
+//! let config = SwapchainConfig::new()
+//!     .with_image_count(capabilities.min_image_count.max(3));
+//! let swapchain = surface.recreate_swapchain(swapchain, config, &queue);
+//! # }
+//! ```
+//!
+//! All backbuffers of the old swapchain have to be released before it is recreated.
+
+use std::error::Error;
+use std::fmt;
 
 use Backend;
 use format::{self, Formatted};
@@ -65,6 +91,9 @@ pub trait Surface<B: Backend> {
     /// ```
     fn supports_queue(&self, queue_family: &B::QueueFamily) -> bool;
 
+    /// Query the capabilities of the surface for presentation with the given adapter.
+    fn get_capabilities(&self, adapter: &B::Adapter) -> SurfaceCapabilities;
+
     /// Create a new swapchain from a surface and a queue.
     ///
     /// # Safety
@@ -94,17 +123,80 @@ pub trait Surface<B: Backend> {
         config: SwapchainConfig,
         present_queue: &CommandQueue<B, C>,
     ) -> B::Swapchain;
+
+    /// Replace a swapchain of this surface by a new one, e.g. after the window got resized.
+    ///
+    /// Backends may reuse resources of the old swapchain, which is consumed.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`build_swapchain`](trait.Surface.html#tymethod.build_swapchain)
+    /// apply. None of the backbuffers of the old swapchain may be in use.
+    fn recreate_swapchain<C>(&mut self,
+        old_swapchain: B::Swapchain,
+        config: SwapchainConfig,
+        present_queue: &CommandQueue<B, C>,
+    ) -> B::Swapchain {
+        drop(old_swapchain);
+        self.build_swapchain(config, present_queue)
+    }
+}
+
+/// Extent of a surface or of the backbuffers of a swapchain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct Extent2D {
+    /// Width in pixels.
+    pub width: u32,
+    /// Height in pixels.
+    pub height: u32,
+}
+
+/// Presentation mode of a swapchain, controlling how presented frames are queued for display.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum PresentMode {
+    /// Frames are shown immediately, which may cause tearing.
+    Immediate,
+    /// The most recently presented frame is shown at the next vertical blank,
+    /// replacing any frame still waiting.
+    Mailbox,
+    /// Frames are queued and shown one per vertical blank. Always supported.
+    Fifo,
+    /// Like `Fifo`, but late frames are shown immediately instead of waiting
+    /// for the next vertical blank.
+    Relaxed,
+}
+
+/// Capabilities of a surface, see `Surface::get_capabilities`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SurfaceCapabilities {
+    /// Color formats supported for the backbuffers, `None` if any format can be used.
+    pub formats: Option<Vec<format::Format>>,
+    /// Minimum number of backbuffers of a swapchain.
+    pub min_image_count: u32,
+    /// Maximum number of backbuffers of a swapchain, `None` if there is no limit.
+    pub max_image_count: Option<u32>,
+    /// Current extent of the surface, `None` if it's determined by the extent of the swapchain.
+    pub current_extent: Option<Extent2D>,
+    /// Supported presentation modes.
+    pub present_modes: Vec<PresentMode>,
 }
 
 /// Handle to a backbuffer of the swapchain.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
-pub struct Frame(usize);
+pub struct Frame(usize, Suboptimal);
 
 impl Frame {
     #[doc(hidden)]
     pub fn new(id: usize) -> Self {
-        Frame(id)
+        Frame(id, false)
+    }
+
+    #[doc(hidden)]
+    pub fn new_suboptimal(id: usize) -> Self {
+        Frame(id, true)
     }
 
     /// Retrieve frame id.
@@ -120,6 +212,14 @@ impl Frame {
     pub fn id(&self) -> usize {
         self.0
     }
+
+    /// Check if the swapchain has become suboptimal for the surface.
+    ///
+    /// The frame has still been acquired and can be presented as usual, but the swapchain
+    /// should be recreated afterwards.
+    pub fn is_suboptimal(&self) -> Suboptimal {
+        self.1
+    }
 }
 
 /// Synchronization primitives which will be signalled once a frame got retrieved.
@@ -144,6 +244,16 @@ pub struct SwapchainConfig {
     pub color_format: format::Format,
     /// Depth stencil format of the backbuffer images (optional).
     pub depth_stencil_format: Option<format::Format>,
+    /// Requested number of backbuffers.
+    ///
+    /// Backends clamp the number to the limits of the surface.
+    pub image_count: u32,
+    /// Presentation mode, falling back to `PresentMode::Fifo` if unsupported by the surface.
+    pub present_mode: PresentMode,
+    /// Extent of the backbuffer images, `None` to match the current extent of the surface.
+    ///
+    /// Ignored by surfaces with a fixed extent.
+    pub extent: Option<Extent2D>,
 }
 
 impl SwapchainConfig {
@@ -158,6 +268,9 @@ impl SwapchainConfig {
         SwapchainConfig {
             color_format: format::Rgba8::get_format(), // TODO: try to find best default format
             depth_stencil_format: None,
+            image_count: 2,
+            present_mode: PresentMode::Fifo,
+            extent: None,
         }
     }
 
@@ -187,9 +300,106 @@ impl SwapchainConfig {
         self
     }
 
-    // TODO: depth-only, stencil-only, swapchain size, present modes, etc.
+    /// Specify the number of backbuffer images.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    ///
+    /// ```
+    pub fn with_image_count(mut self, count: u32) -> Self {
+        self.image_count = count;
+        self
+    }
+
+    /// Specify the presentation mode.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    ///
+    /// ```
+    pub fn with_present_mode(mut self, mode: PresentMode) -> Self {
+        self.present_mode = mode;
+        self
+    }
+
+    /// Specify the extent of the backbuffer images.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    ///
+    /// ```
+    pub fn with_extent(mut self, width: u32, height: u32) -> Self {
+        self.extent = Some(Extent2D { width, height });
+        self
+    }
+
+    // TODO: depth-only, stencil-only, etc.
+}
+
+/// Error on acquiring a frame of a swapchain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum AcquireError {
+    /// The swapchain doesn't match the surface anymore and has to be recreated.
+    OutOfDate,
+    /// The surface is no longer available.
+    SurfaceLost,
+    /// No backbuffer became available in time.
+    Timeout,
+    /// No backbuffer is available right now.
+    NotReady,
 }
 
+impl fmt::Display for AcquireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl Error for AcquireError {
+    fn description(&self) -> &str {
+        match *self {
+            AcquireError::OutOfDate => "The swapchain is out of date",
+            AcquireError::SurfaceLost => "The surface was lost",
+            AcquireError::Timeout => "Timed out acquiring a frame",
+            AcquireError::NotReady => "No frame is ready",
+        }
+    }
+}
+
+/// Error on presenting a frame of a swapchain.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum PresentError {
+    /// The swapchain doesn't match the surface anymore and has to be recreated.
+    OutOfDate,
+    /// The surface is no longer available.
+    SurfaceLost,
+}
+
+impl fmt::Display for PresentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.description())
+    }
+}
+
+impl Error for PresentError {
+    fn description(&self) -> &str {
+        match *self {
+            PresentError::OutOfDate => "The swapchain is out of date",
+            PresentError::SurfaceLost => "The surface was lost",
+        }
+    }
+}
+
+/// Successful presentation, indicating if the swapchain no longer matches the surface exactly.
+///
+/// A suboptimal swapchain can still be used, but should be recreated when convenient.
+pub type Suboptimal = bool;
+
 /// Swapchain backbuffer type
 pub struct Backbuffer<B: Backend> {
     /// Back buffer color
@@ -221,15 +431,20 @@ pub trait Swapchain<B: Backend> {
     /// This can either be a [`Semaphore`](../trait.Resources.html#associatedtype.Semaphore)
     /// or a [`Fence`](../trait.Resources.html#associatedtype.Fence).
     ///
+    /// On error, `sync` won't be signaled. A frame acquired from a suboptimal swapchain is
+    /// returned as usual, see [`Frame::is_suboptimal`](struct.Frame.html#method.is_suboptimal).
+    ///
     /// # Examples
     ///
     /// ```no_run
     ///
     /// ```
-    fn acquire_frame(&mut self, sync: FrameSync<B>) -> Frame;
+    fn acquire_frame(&mut self, sync: FrameSync<B>) -> Result<Frame, AcquireError>;
 
     /// Present one acquired frame in FIFO order.
     ///
+    /// Returns if the swapchain has become suboptimal for the surface.
+    ///
     /// # Safety
     ///
     /// The passed queue _must_ be the **same** queue as used for creation.
@@ -243,5 +458,5 @@ pub trait Swapchain<B: Backend> {
         &mut self,
         present_queue: &mut CommandQueue<B, C>,
         wait_semaphores: &[&B::Semaphore],
-    );
+    ) -> Result<Suboptimal, PresentError>;
 }
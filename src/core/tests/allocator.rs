@@ -0,0 +1,188 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{buffer, format, image, memory};
+use core::{Adapter, Device, QueueFamily};
+use core::device::ResourceHeapType;
+use core::memory::{AllocationError, Allocator, Requirements, Strategy};
+
+fn open() -> core::Gpu<empty::Backend> {
+    let adapter = empty::Adapter::new();
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    adapter.open(&descs)
+}
+
+fn requirements(size: u64, alignment: u64) -> Requirements {
+    Requirements { size, alignment }
+}
+
+#[test]
+fn heap_type_selection() {
+    let core::Gpu { mut device, heap_types, .. } = open();
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1024);
+
+    let (upload, upload_memory) = allocator
+        .create_buffer(&mut device, 64, 0, buffer::TRANSFER_SRC, memory::CPU_VISIBLE)
+        .unwrap();
+    assert!(device.write_mapping::<u8>(&upload, 0, 64).is_ok());
+
+    let err = allocator
+        .allocate(&mut device, memory::DEVICE_LOCAL | memory::CPU_VISIBLE, ResourceHeapType::Buffers, requirements(64, 16))
+        .unwrap_err();
+    assert_eq!(err, AllocationError::NoHeapType(memory::DEVICE_LOCAL | memory::CPU_VISIBLE));
+
+    device.destroy_buffer(upload);
+    allocator.free(upload_memory);
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn free_list() {
+    let core::Gpu { mut device, heap_types, .. } = open();
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1024);
+    let mut allocate = |allocator: &mut Allocator<_>, size, alignment| {
+        allocator
+            .allocate(&mut device, memory::DEVICE_LOCAL, ResourceHeapType::Any, requirements(size, alignment))
+            .unwrap()
+    };
+
+    let a = allocate(&mut allocator, 100, 1);
+    let b = allocate(&mut allocator, 100, 256);
+    assert_eq!((a.offset(), b.offset()), (0, 256));
+
+    // The padding in front of `b` is reused.
+    let c = allocate(&mut allocator, 100, 1);
+    assert_eq!(c.offset(), 100);
+
+    let stats = allocator.stats();
+    assert_eq!(stats.heaps, 1);
+    assert_eq!(stats.allocations, 3);
+    assert_eq!(stats.used, 300);
+    assert_eq!(stats.free_ranges, 2);
+    assert_eq!(stats.largest_free, 1024 - 356);
+    assert!(stats.fragmentation() > 0.0);
+
+    // Adjacent ranges are merged again.
+    allocator.free(c);
+    allocator.free(b);
+    assert_eq!(allocator.stats().free_ranges, 1);
+    allocator.free(a);
+    let stats = allocator.stats();
+    assert_eq!((stats.free_ranges, stats.largest_free), (1, 1024));
+    assert_eq!(stats.fragmentation(), 0.0);
+
+    // Allocations exceeding the block size get their own heap.
+    let large = allocate(&mut allocator, 4096, 1);
+    assert_eq!(allocator.stats().heaps, 2);
+    allocator.free(large);
+    allocator.shrink(&mut device);
+    assert_eq!(allocator.stats().heaps, 0);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn linear() {
+    let core::Gpu { mut device, heap_types, .. } = open();
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::Linear, 1024);
+    let mut allocate = |allocator: &mut Allocator<_>, size| {
+        allocator
+            .allocate(&mut device, memory::DEVICE_LOCAL, ResourceHeapType::Any, requirements(size, 16))
+            .unwrap()
+    };
+
+    let a = allocate(&mut allocator, 500);
+    let b = allocate(&mut allocator, 500);
+    assert_eq!((a.offset(), b.offset()), (0, 512));
+
+    // Freed memory isn't reused until the heap is empty.
+    allocator.free(a);
+    let stats = allocator.stats();
+    assert_eq!((stats.used, stats.free), (500, 12));
+    let c = allocate(&mut allocator, 500);
+    assert_eq!(allocator.stats().heaps, 2);
+
+    let first_heap = allocator.heap(&b).id();
+    allocator.free(b);
+    let d = allocate(&mut allocator, 500);
+    assert_eq!((allocator.heap(&d).id(), d.offset()), (first_heap, 0));
+    assert_ne!(allocator.heap(&c).id(), first_heap);
+
+    allocator.free(c);
+    allocator.free(d);
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn ring() {
+    let core::Gpu { mut device, heap_types, .. } = open();
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::Ring, 1024);
+    let mut allocate = |allocator: &mut Allocator<_>, size| {
+        allocator
+            .allocate(&mut device, memory::CPU_VISIBLE, ResourceHeapType::Buffers, requirements(size, 16))
+            .unwrap()
+    };
+
+    let frames = (0 .. 3).map(|_| allocate(&mut allocator, 300)).collect::<Vec<_>>();
+    assert_eq!(frames.iter().map(|a| a.offset()).collect::<Vec<_>>(), vec![0, 304, 608]);
+
+    // Freeing out of order only reclaims memory once the oldest allocation is freed.
+    let mut frames = frames.into_iter();
+    let (first, second, third) = (frames.next().unwrap(), frames.next().unwrap(), frames.next().unwrap());
+    allocator.free(second);
+    assert_eq!(allocator.stats().largest_free, 1024 - 908);
+    allocator.free(first);
+    assert_eq!(allocator.stats().largest_free, 608);
+
+    // Wraps around to the start of the heap.
+    let fourth = allocate(&mut allocator, 500);
+    assert_eq!(fourth.offset(), 0);
+    assert_eq!(allocator.stats().heaps, 1);
+    let stats = allocator.stats();
+    assert_eq!((stats.free_ranges, stats.largest_free), (1, 108));
+
+    allocator.free(third);
+    allocator.free(fourth);
+    let stats = allocator.stats();
+    assert_eq!((stats.allocations, stats.largest_free), (0, 1024));
+    allocator.destroy(&mut device);
+}
+
+#[test]
+fn create_resources() {
+    let core::Gpu { mut device, heap_types, .. } = open();
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1 << 16);
+
+    let (vertices, vb_memory) = allocator
+        .create_buffer(&mut device, 100, 0, buffer::VERTEX, memory::CPU_VISIBLE)
+        .unwrap();
+    let (indices, ib_memory) = allocator
+        .create_buffer(&mut device, 100, 0, buffer::INDEX, memory::CPU_VISIBLE)
+        .unwrap();
+    assert_eq!((vb_memory.offset(), ib_memory.offset()), (0, 112));
+
+    let kind = image::Kind::D2(16, 16, image::AaMode::Single);
+    let rgba8 = format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm);
+    let (texture, texture_memory) = allocator
+        .create_image(&mut device, kind, 1, rgba8, image::SAMPLED, memory::DEVICE_LOCAL)
+        .unwrap();
+    let (target, target_memory) = allocator
+        .create_image(&mut device, kind, 1, rgba8, image::COLOR_ATTACHMENT, memory::DEVICE_LOCAL)
+        .unwrap();
+    // Buffers, images and targets are placed into separate heaps.
+    assert_eq!(allocator.stats().heaps, 3);
+
+    device.destroy_buffer(vertices);
+    device.destroy_buffer(indices);
+    device.destroy_image(texture);
+    device.destroy_image(target);
+    for allocation in vec![vb_memory, ib_memory, texture_memory, target_memory] {
+        allocator.free(allocation);
+    }
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
@@ -0,0 +1,248 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{buffer, format, image, pso};
+use core::{Adapter, Device, QueueFamily, QueueType};
+use core::command::{ClearColor, ClearValue};
+use core::device::ResourceHeapType;
+use core::graph::{GraphBuilder, PassDesc, PassId, Queues};
+use core::image::ImageLayout;
+use core::queue::capability::{Graphics, Transfer};
+
+const KIND: image::Kind = image::Kind::D2(16, 16, image::AaMode::Single);
+const RGBA8: format::Format = format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm);
+
+struct Context {
+    device: empty::Device,
+    graphics: core::CommandQueue<empty::Backend, core::General>,
+    transfer: core::CommandQueue<empty::Backend, core::Transfer>,
+    heap_types: Vec<core::HeapType>,
+    heap: empty::Heap,
+}
+
+fn setup() -> Context {
+    let adapter = empty::Adapter::new();
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    let core::Gpu { mut device, mut general_queues, mut transfer_queues, heap_types, .. } = adapter.open(&descs);
+    let heap = device.create_heap(&heap_types[0], ResourceHeapType::Any, 1 << 16).unwrap();
+    Context {
+        device,
+        graphics: general_queues.remove(0),
+        transfer: transfer_queues.remove(0),
+        heap_types,
+        heap,
+    }
+}
+
+fn backbuffer(ctx: &mut Context) -> empty::Image {
+    let unbound = ctx.device.create_image(KIND, 1, RGBA8, image::COLOR_ATTACHMENT).unwrap();
+    ctx.device.bind_image_memory(&ctx.heap, 0, unbound).unwrap()
+}
+
+fn present_states() -> std::ops::Range<image::State> {
+    (image::Access::empty(), ImageLayout::Undefined) .. (image::Access::empty(), ImageLayout::Present)
+}
+
+#[test]
+fn cull_unused_passes() {
+    let mut ctx = setup();
+    let backbuffer = backbuffer(&mut ctx);
+    let black = Some(ClearColor::Float([0.0, 0.0, 0.0, 1.0]));
+
+    let mut builder = GraphBuilder::<empty::Backend>::new();
+    let target = builder.import_image(&backbuffer, KIND, RGBA8, present_states());
+    let unused = builder.create_image(KIND, 1, RGBA8);
+    builder.add_pass(PassDesc::new::<Graphics>("debug").with_color(unused, black));
+    let main = builder.add_pass(PassDesc::new::<Graphics>("main").with_color(target, black));
+    let graph = builder.build(&mut ctx.device, &ctx.heap_types).unwrap();
+
+    assert_eq!(graph.order(), vec![main]);
+    assert_eq!(graph.name(main), "main");
+    assert!(graph.image(unused).is_none());
+    assert!(graph.render_pass(main).is_some());
+
+    graph.destroy(&mut ctx.device);
+    ctx.device.destroy_image(backbuffer);
+    ctx.device.destroy_heap(ctx.heap);
+    assert!(ctx.device.live_objects().is_empty());
+}
+
+#[test]
+fn alias_transient_images() {
+    let mut ctx = setup();
+    let backbuffer = backbuffer(&mut ctx);
+
+    let mut builder = GraphBuilder::<empty::Backend>::new();
+    let target = builder.import_image(&backbuffer, KIND, RGBA8, present_states());
+    let images = (0 .. 3).map(|_| builder.create_image(KIND, 1, RGBA8)).collect::<Vec<_>>();
+    builder.add_pass(PassDesc::new::<Graphics>("0").with_color(images[0], None));
+    for i in 1 .. 3 {
+        builder.add_pass(PassDesc::new::<Graphics>(&i.to_string())
+            .with_sampled(images[i - 1], pso::PIXEL_SHADER)
+            .with_color(images[i], None));
+    }
+    let last = builder.add_pass(PassDesc::new::<Graphics>("3")
+        .with_sampled(images[2], pso::PIXEL_SHADER)
+        .with_color(target, None));
+    let mut graph = builder.build(&mut ctx.device, &ctx.heap_types).unwrap();
+    assert_eq!(graph.order().len(), 4);
+
+    // The first and last image are never used at the same time.
+    let ranges = images.iter().map(|&id| graph.memory_range(id).unwrap()).collect::<Vec<_>>();
+    assert_eq!(ranges[0], ranges[2]);
+    assert!(ranges[1].start >= ranges[0].end);
+    assert!(graph.memory_range(target).is_none());
+
+    let mut recorded = Vec::new();
+    {
+        let mut queues = Queues { graphics: ctx.graphics.as_mut(), compute: None, transfer: None };
+        graph.execute(&mut queues, None, |pass, _| recorded.push(pass));
+    }
+    assert_eq!(recorded, graph.order());
+
+    let submissions = ctx.graphics.as_raw().submissions();
+    assert_eq!(submissions.len(), 1);
+    let barriers = submissions[0].command_buffers[0]
+        .iter()
+        .filter_map(|command| match *command {
+            empty::Command::PipelineBarrier { ref barriers, .. } => Some(barriers.clone()),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let color = (image::COLOR_ATTACHMENT_READ | image::COLOR_ATTACHMENT_WRITE, ImageLayout::ColorAttachmentOptimal);
+    let sampled = (image::SHADER_READ, ImageLayout::ShaderReadOnlyOptimal);
+    let undefined = (image::Access::empty(), ImageLayout::Undefined);
+    let image_states = |barriers: &[empty::Barrier]| -> Vec<(empty::Id, std::ops::Range<image::State>)> {
+        barriers
+            .iter()
+            .map(|barrier| match *barrier {
+                empty::Barrier::Image { ref states, target, .. } => (target, states.clone()),
+                ref other => panic!("Unexpected barrier {:?}", other),
+            })
+            .collect()
+    };
+    let id = |image| graph.image(image).unwrap().id();
+    // Images are created in the color attachment layout, images reusing memory start undefined.
+    assert_eq!(barriers.len(), 5);
+    assert_eq!(image_states(&barriers[0]), vec![(id(images[0]), undefined .. color)]);
+    assert_eq!(image_states(&barriers[1]), vec![(id(images[1]), undefined .. color), (id(images[0]), color .. sampled)]);
+    assert_eq!(image_states(&barriers[2]), vec![(id(images[2]), undefined .. color), (id(images[1]), color .. sampled)]);
+    assert_eq!(image_states(&barriers[3]), vec![(id(target), undefined .. color), (id(images[2]), color .. sampled)]);
+    assert_eq!(image_states(&barriers[4]), vec![(id(target), color .. present_states().end)]);
+
+    match submissions[0].command_buffers[0].iter().find(|command| match **command {
+        empty::Command::BeginRenderPass { .. } => true,
+        _ => false,
+    }) {
+        Some(&empty::Command::BeginRenderPass { render_pass, area, .. }) => {
+            assert_eq!(render_pass, graph.render_pass(graph.order()[0]).unwrap().id());
+            assert_eq!((area.w, area.h), (16, 16));
+        }
+        other => panic!("Unexpected command {:?}", other),
+    }
+    assert!(graph.render_pass(last).is_some());
+
+    graph.destroy(&mut ctx.device);
+    ctx.device.destroy_image(backbuffer);
+    ctx.device.destroy_heap(ctx.heap);
+    assert!(ctx.device.live_objects().is_empty());
+}
+
+#[test]
+fn synchronize_queues() {
+    let mut ctx = setup();
+    let backbuffer = backbuffer(&mut ctx);
+    let unbound = ctx.device.create_buffer(256, 0, buffer::TRANSFER_DST | buffer::VERTEX).unwrap();
+    let vertices = ctx.device.bind_buffer_memory(&ctx.heap, 1 << 12, unbound).unwrap();
+    let fence = ctx.device.create_fence(false);
+    let clear = ClearColor::Float([0.0, 0.0, 0.0, 1.0]);
+
+    let mut builder = GraphBuilder::<empty::Backend>::new();
+    builder.dedicated_queue(QueueType::Transfer);
+    let target = builder.import_image(&backbuffer, KIND, RGBA8, present_states());
+    let vertex_buffer = builder.import_buffer(&vertices, 256);
+    let upload = builder.add_pass(PassDesc::new::<Transfer>("upload")
+        .with_buffer(vertex_buffer, buffer::TRANSFER_WRITE, pso::TRANSFER));
+    let draw = builder.add_pass(PassDesc::new::<Graphics>("draw")
+        .with_buffer(vertex_buffer, buffer::VERTEX_BUFFER_READ, pso::VERTEX_INPUT)
+        .with_color(target, Some(clear)));
+    let mut graph = builder.build(&mut ctx.device, &ctx.heap_types).unwrap();
+    assert_eq!(graph.order(), vec![upload, draw]);
+    assert_eq!(graph.semaphore_count(), 1);
+
+    for _ in 0 .. 2 {
+        let mut recorded: Vec<PassId> = Vec::new();
+        let mut queues = Queues {
+            graphics: ctx.graphics.as_mut(),
+            compute: None,
+            transfer: Some(ctx.transfer.as_mut()),
+        };
+        graph.execute(&mut queues, Some(&fence), |pass, _| recorded.push(pass));
+        assert_eq!(recorded, vec![upload, draw]);
+    }
+
+    let uploads = ctx.transfer.as_raw().submissions();
+    let draws = ctx.graphics.as_raw().submissions();
+    assert_eq!((uploads.len(), draws.len()), (2, 2));
+    assert!(uploads[0].wait_semaphores.is_empty());
+    assert_eq!(uploads[0].signal_semaphores.len(), 1);
+    assert_eq!(draws[0].wait_semaphores, vec![(uploads[0].signal_semaphores[0], pso::TOP_OF_PIPE)]);
+    assert_eq!((uploads[0].fence, draws[0].fence), (None, Some(fence.id())));
+    assert!(draws[0].command_buffers[0].iter().any(|command| match *command {
+        empty::Command::BeginRenderPass { ref clear_values, .. } => clear_values == &vec![ClearValue::Color(clear)],
+        _ => false,
+    }));
+
+    graph.destroy(&mut ctx.device);
+    ctx.device.destroy_fence(fence);
+    ctx.device.destroy_buffer(vertices);
+    ctx.device.destroy_image(backbuffer);
+    ctx.device.destroy_heap(ctx.heap);
+    assert!(ctx.device.live_objects().is_empty());
+}
+
+#[test]
+fn fence_covers_all_queues() {
+    let mut ctx = setup();
+    let backbuffer = backbuffer(&mut ctx);
+    let unbound = ctx.device.create_image(KIND, 1, RGBA8, image::TRANSFER_DST).unwrap();
+    let staging = ctx.device.bind_image_memory(&ctx.heap, 1 << 13, unbound).unwrap();
+    let fence = ctx.device.create_fence(false);
+    let clear = ClearColor::Float([0.0, 0.0, 0.0, 1.0]);
+    let transfer_dst = (image::TRANSFER_WRITE, ImageLayout::TransferDstOptimal);
+
+    // The passes don't depend on each other, the draw still waits for the upload.
+    let mut builder = GraphBuilder::<empty::Backend>::new();
+    builder.dedicated_queue(QueueType::Transfer);
+    let target = builder.import_image(&backbuffer, KIND, RGBA8, present_states());
+    let image = builder.import_image(&staging, KIND, RGBA8, transfer_dst .. transfer_dst);
+    let upload = builder.add_pass(PassDesc::new::<Transfer>("upload")
+        .with_image(image, transfer_dst, pso::TRANSFER));
+    let draw = builder.add_pass(PassDesc::new::<Graphics>("draw")
+        .with_color(target, Some(clear)));
+    let mut graph = builder.build(&mut ctx.device, &ctx.heap_types).unwrap();
+    assert_eq!(graph.order(), vec![upload, draw]);
+    assert_eq!(graph.semaphore_count(), 1);
+
+    let mut queues = Queues {
+        graphics: ctx.graphics.as_mut(),
+        compute: None,
+        transfer: Some(ctx.transfer.as_mut()),
+    };
+    graph.execute(&mut queues, Some(&fence), |_, _| ());
+
+    let uploads = ctx.transfer.as_raw().submissions();
+    let draws = ctx.graphics.as_raw().submissions();
+    assert_eq!(draws[0].wait_semaphores, vec![(uploads[0].signal_semaphores[0], pso::TOP_OF_PIPE)]);
+    assert_eq!((uploads[0].fence, draws[0].fence), (None, Some(fence.id())));
+
+    graph.destroy(&mut ctx.device);
+    ctx.device.destroy_fence(fence);
+    ctx.device.destroy_image(staging);
+    ctx.device.destroy_image(backbuffer);
+    ctx.device.destroy_heap(ctx.heap);
+    assert!(ctx.device.live_objects().is_empty());
+}
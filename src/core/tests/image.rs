@@ -0,0 +1,23 @@
+extern crate gfx_core as core;
+
+use core::image::{AaMode, Kind};
+
+#[test]
+fn num_levels_down_to_one() {
+    // Every chain ends with a 1x1 level, e.g. 4x4, 2x2 and 1x1.
+    assert_eq!(Kind::D2(4, 4, AaMode::Single).get_num_levels(), 3);
+    assert_eq!(Kind::D2(2, 2, AaMode::Single).get_num_levels(), 2);
+    assert_eq!(Kind::D2(1, 1, AaMode::Single).get_num_levels(), 1);
+    // Non-power-of-two sizes round down.
+    assert_eq!(Kind::D2(5, 3, AaMode::Single).get_num_levels(), 3);
+    // The largest dimension determines the length of the chain.
+    assert_eq!(Kind::D1(256).get_num_levels(), 9);
+    assert_eq!(Kind::D3(2, 4, 8).get_num_levels(), 4);
+    assert_eq!(Kind::Cube(16).get_num_levels(), 5);
+    // Sizes with the top bit set don't overflow.
+    assert_eq!(Kind::D2(32768, 1, AaMode::Single).get_num_levels(), 16);
+    assert_eq!(Kind::D2(1, 65535, AaMode::Single).get_num_levels(), 16);
+    assert_eq!(Kind::D1(0).get_num_levels(), 1);
+    // Multisampled images have no mipmaps.
+    assert_eq!(Kind::D2(4, 4, AaMode::Multi(4)).get_num_levels(), 1);
+}
@@ -0,0 +1,114 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{pass, pso, Adapter, Device, Primitive};
+use core::pso::PipelineCache;
+
+const VS: &[u8] = &[0x03, 0x02, 0x23, 0x07, 1];
+const PS: &[u8] = &[0x03, 0x02, 0x23, 0x07, 2];
+
+fn desc(primitive: Primitive) -> pso::GraphicsPipelineDesc {
+    pso::GraphicsPipelineDesc::new(
+        primitive,
+        pso::Rasterizer::new_fill(),
+        pso::GraphicsShaderSet {
+            vertex_shader: "vs",
+            hull_shader: None,
+            domain_shader: None,
+            geometry_shader: None,
+            pixel_shader: Some("ps"),
+        },
+    )
+}
+
+fn open() -> empty::Device {
+    let adapter = empty::Adapter::new();
+    let family = &adapter.get_queue_families()[0];
+    adapter.open(&[(&family.0, family.1, 1)]).device
+}
+
+#[test]
+fn deduplicate_pipelines() {
+    let mut device = open();
+    let shaders = [("vs", VS), ("ps", PS)];
+    let shader_lib = device.create_shader_library(&shaders).unwrap();
+    let layout = device.create_pipeline_layout(&[]);
+    let render_pass = device.create_renderpass(&[], &[pass::SubpassDesc { color_attachments: &[] }], &[]);
+    let subpass = |index| pass::Subpass { index, main_pass: &render_pass };
+
+    // Pipelines using `layout` and `render_pass` are keyed with 0.
+    let mut cache = PipelineCache::<empty::Backend, u32>::new(&mut device, &[]);
+    let triangles = cache
+        .graphics_pipeline(&mut device, 0, &shaders, (&shader_lib, &layout, subpass(0), &desc(Primitive::TriangleList)))
+        .unwrap()
+        .id();
+    let again = cache
+        .graphics_pipeline(&mut device, 0, &shaders, (&shader_lib, &layout, subpass(0), &desc(Primitive::TriangleList)))
+        .unwrap()
+        .id();
+    assert_eq!(triangles, again);
+
+    // Different descriptions or shader code result in new pipelines.
+    let lines = cache
+        .graphics_pipeline(&mut device, 0, &shaders, (&shader_lib, &layout, subpass(0), &desc(Primitive::LineList)))
+        .unwrap()
+        .id();
+    let other_shaders = [("vs", VS), ("ps", VS)];
+    let other = cache
+        .graphics_pipeline(&mut device, 0, &other_shaders, (&shader_lib, &layout, subpass(0), &desc(Primitive::TriangleList)))
+        .unwrap()
+        .id();
+    assert!(lines != triangles && other != triangles && other != lines);
+
+    // So do other layouts and render passes, given their own keys.
+    let other_layout = device.create_pipeline_layout(&[]);
+    let other_pass = device.create_renderpass(&[], &[pass::SubpassDesc { color_attachments: &[] }], &[]);
+    let with_layout = cache
+        .graphics_pipeline(&mut device, 1, &shaders, (&shader_lib, &other_layout, subpass(0), &desc(Primitive::TriangleList)))
+        .unwrap()
+        .id();
+    let with_pass = cache
+        .graphics_pipeline(
+            &mut device, 2, &shaders,
+            (&shader_lib, &layout, pass::Subpass { index: 0, main_pass: &other_pass }, &desc(Primitive::TriangleList)),
+        )
+        .unwrap()
+        .id();
+    assert!(with_layout != triangles && with_pass != triangles && with_layout != with_pass);
+
+    let compute = cache.compute_pipeline(&mut device, 0, VS, (&shader_lib, "main", &layout)).unwrap().id();
+    assert_eq!(cache.compute_pipeline(&mut device, 0, VS, (&shader_lib, "main", &layout)).unwrap().id(), compute);
+    assert!(cache.compute_pipeline(&mut device, 1, VS, (&shader_lib, "main", &other_layout)).unwrap().id() != compute);
+    assert_eq!(cache.len(), 7);
+
+    // Failed creations are not cached.
+    assert_eq!(
+        cache.graphics_pipeline(&mut device, 0, &shaders, (&shader_lib, &layout, subpass(1), &desc(Primitive::TriangleList))).unwrap_err(),
+        pso::CreationError::InvalidSubpass(1)
+    );
+    assert_eq!(cache.len(), 7);
+
+    cache.destroy(&mut device);
+    device.destroy_renderpass(other_pass);
+    device.destroy_renderpass(render_pass);
+    device.destroy_pipeline_layout(other_layout);
+    device.destroy_pipeline_layout(layout);
+    device.destroy_shader_lib(shader_lib);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn cache_data() {
+    let mut device = open();
+    let cache = PipelineCache::<empty::Backend, ()>::new(&mut device, b"gfx");
+    assert!(cache.is_empty());
+    assert_eq!(cache.data(&mut device), b"gfx");
+
+    let other = device.create_pipeline_cache(b"-rs");
+    device.merge_pipeline_caches(cache.raw(), &[&other]);
+    assert_eq!(cache.data(&mut device), b"gfx-rs");
+
+    device.destroy_pipeline_cache(other);
+    cache.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
@@ -0,0 +1,37 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{pool, pso, query};
+use core::{Adapter, Device, QueueFamily};
+use core::device::WaitFor;
+use core::query::Query;
+
+#[test]
+fn deferred_results() {
+    let adapter = empty::Adapter::new_deferred();
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    let core::Gpu { mut device, mut general_queues, .. } = adapter.open(&descs);
+    let mut queue = general_queues.remove(0);
+    let mut pool = queue.create_general_pool(1, pool::TRANSIENT);
+    let queries = device.create_query_pool(query::QueryType::Timestamp, 2);
+    let fence = device.create_fence(false);
+
+    let submit = {
+        let mut cmd_buffer = pool.acquire_command_buffer();
+        cmd_buffer.reset_query_pool(&queries, 0 .. 2);
+        cmd_buffer.write_timestamp(pso::TOP_OF_PIPE, Query { pool: &queries, id: 0 });
+        cmd_buffer.write_timestamp(pso::BOTTOM_OF_PIPE, Query { pool: &queries, id: 1 });
+        cmd_buffer.finish()
+    };
+    queue.submit(core::Submission::new().submit(&[submit]), Some(&fence));
+
+    // Results become available once the submission completes.
+    let mut data = [0; 2];
+    assert!(!device.get_query_pool_results(&queries, 0 .. 2, &mut data, query::ResultFlags::empty()));
+    assert!(device.wait_for_fences(&[&fence], WaitFor::All, !0));
+    assert!(device.get_query_pool_results(&queries, 0 .. 2, &mut data, query::ResultFlags::empty()));
+    assert_eq!(data, [1, 2]);
+}
@@ -0,0 +1,106 @@
+extern crate gfx_core as core;
+
+use core::format::{ChannelType, Format, SurfaceType};
+use core::pso::{self, DescriptorType, Stage};
+use core::pso::reflect::{self, ReflectError};
+
+// Compiled from the GLSL shaders of the cube example.
+const CUBE_VERT: &[u8] = include_bytes!("data/cube.vert.spv");
+const CUBE_FRAG: &[u8] = include_bytes!("data/cube.frag.spv");
+// Hand-assembled module equivalent to:
+//
+// layout(local_size_x = 8, local_size_y = 8) in;
+// layout(set = 0, binding = 0, rgba8) uniform writeonly image2D u_Output;
+// layout(set = 0, binding = 1) uniform samplerBuffer u_Weights;
+// layout(set = 1, binding = 0) buffer Particles { vec4 positions[]; };
+// layout(set = 1, binding = 2) uniform texture2D u_Textures[4];
+// layout(push_constant) uniform Constants { mat4 transform; vec2 offset; float scale; };
+const COMPUTE: &[u8] = include_bytes!("data/compute.spv");
+
+fn binding(binding: usize, ty: DescriptorType, count: usize, stage_flags: pso::ShaderStageFlags) -> pso::DescriptorSetLayoutBinding {
+    pso::DescriptorSetLayoutBinding { binding, ty, count, stage_flags }
+}
+
+#[test]
+fn vertex_shader() {
+    let entry_points = reflect::reflect(CUBE_VERT).unwrap();
+    assert_eq!(entry_points.len(), 1);
+    let vs = &entry_points[0];
+    assert_eq!((vs.name.as_str(), vs.stage), ("main", Stage::Vertex));
+    assert_eq!(vs.descriptor_sets, vec![
+        vec![binding(0, DescriptorType::ConstantBuffer, 1, pso::STAGE_VERTEX)],
+    ]);
+    assert_eq!(vs.push_constant_size, 0);
+    assert_eq!(vs.workgroup_size, None);
+
+    // Built-in inputs and outputs are skipped.
+    let vec4 = Format(SurfaceType::R32_G32_B32_A32, ChannelType::Float);
+    let vec2 = Format(SurfaceType::R32_G32, ChannelType::Float);
+    assert_eq!(vs.inputs, vec![(0, vec4), (1, vec2)]);
+
+    let (buffer, attributes) = vs.vertex_attributes(0);
+    assert_eq!(buffer.stride, 24);
+    assert_eq!(attributes.iter().map(|a| (a.location, a.element.offset)).collect::<Vec<_>>(), vec![(0, 0), (1, 16)]);
+}
+
+#[test]
+fn merge_stages() {
+    let vs = reflect::reflect(CUBE_VERT).unwrap().remove(0);
+    let ps = reflect::reflect(CUBE_FRAG).unwrap().remove(0);
+    assert_eq!(ps.stage, Stage::Pixel);
+    assert_eq!(ps.descriptor_sets, vec![vec![
+        binding(1, DescriptorType::SampledImage, 1, pso::STAGE_PIXEL),
+        binding(2, DescriptorType::Sampler, 1, pso::STAGE_PIXEL),
+    ]]);
+
+    let sets = reflect::merge_descriptor_sets(&[&vs, &ps]).unwrap();
+    assert_eq!(sets, vec![vec![
+        binding(0, DescriptorType::ConstantBuffer, 1, pso::STAGE_VERTEX),
+        binding(1, DescriptorType::SampledImage, 1, pso::STAGE_PIXEL),
+        binding(2, DescriptorType::Sampler, 1, pso::STAGE_PIXEL),
+    ]]);
+
+    // Merging the same stage twice only combines stage flags.
+    let sets = reflect::merge_descriptor_sets(&[&ps, &ps]).unwrap();
+    assert_eq!(sets[0].len(), 2);
+
+    let mut conflicting = ps.clone();
+    conflicting.descriptor_sets[0][0].ty = DescriptorType::StorageImage;
+    assert_eq!(
+        reflect::merge_descriptor_sets(&[&ps, &conflicting]).unwrap_err(),
+        ReflectError::BindingMismatch { set: 0, binding: 1 }
+    );
+}
+
+#[test]
+fn compute_shader() {
+    let cs = reflect::reflect(COMPUTE).unwrap().remove(0);
+    assert_eq!(cs.stage, Stage::Compute);
+    assert_eq!(cs.workgroup_size, Some([8, 8, 1]));
+    assert_eq!(cs.push_constant_size, 76);
+    assert!(cs.inputs.is_empty());
+    assert_eq!(cs.descriptor_sets, vec![
+        vec![
+            binding(0, DescriptorType::StorageImage, 1, pso::STAGE_COMPUTE),
+            binding(1, DescriptorType::UniformTexelBuffer, 1, pso::STAGE_COMPUTE),
+        ],
+        vec![
+            binding(0, DescriptorType::StorageBuffer, 1, pso::STAGE_COMPUTE),
+            binding(2, DescriptorType::SampledImage, 4, pso::STAGE_COMPUTE),
+        ],
+    ]);
+}
+
+#[test]
+fn invalid_modules() {
+    assert_eq!(reflect::reflect(&CUBE_VERT[.. 6]).unwrap_err(), ReflectError::InvalidSize(6));
+    assert_eq!(reflect::reflect(&[1, 0, 0, 0]).unwrap_err(), ReflectError::InvalidMagic(1));
+    assert_eq!(reflect::reflect(&CUBE_VERT[.. 64]).unwrap_err(), ReflectError::UnexpectedEnd);
+
+    // Big endian modules are accepted as well.
+    let swapped = CUBE_FRAG
+        .chunks(4)
+        .flat_map(|word| word.iter().rev().cloned())
+        .collect::<Vec<_>>();
+    assert_eq!(reflect::reflect(&swapped).unwrap(), reflect::reflect(CUBE_FRAG).unwrap());
+}
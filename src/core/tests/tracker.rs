@@ -0,0 +1,169 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{buffer, format, image, pool, pso};
+use core::{Adapter, Device, QueueFamily, RawCommandPool};
+use core::command::StateTracker;
+use core::device::ResourceHeapType;
+use core::image::ImageLayout;
+
+struct Resources {
+    buffer: empty::Buffer,
+    image: empty::Image,
+    cmd_buffer: empty::RawCommandBuffer,
+}
+
+fn setup() -> Resources {
+    let adapter = empty::Adapter::new();
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    let core::Gpu { mut device, general_queues, heap_types, .. } = adapter.open(&descs);
+
+    let heap = device.create_heap(&heap_types[0], ResourceHeapType::Any, 1 << 16).unwrap();
+    let unbound = device.create_buffer(256, 0, buffer::TRANSFER_DST | buffer::VERTEX | buffer::INDEX).unwrap();
+    let buffer = device.bind_buffer_memory(&heap, 0, unbound).unwrap();
+    let kind = image::Kind::D2Array(16, 16, 4, image::AaMode::Single);
+    let rgba8 = format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm);
+    let unbound = device.create_image(kind, 2, rgba8, image::TRANSFER_DST | image::SAMPLED).unwrap();
+    let image = device.bind_image_memory(&heap, 1 << 12, unbound).unwrap();
+
+    let mut pool = unsafe {
+        empty::RawCommandPool::from_queue(general_queues[0].as_raw(), pool::CommandPoolCreateFlags::empty())
+    };
+    let cmd_buffer = pool.allocate(1).pop().unwrap();
+
+    Resources { buffer, image, cmd_buffer }
+}
+
+fn barriers(cmd_buffer: &empty::RawCommandBuffer) -> Vec<(std::ops::Range<pso::PipelineStage>, Vec<empty::Barrier>)> {
+    cmd_buffer.commands()
+        .iter()
+        .map(|command| match *command {
+            empty::Command::PipelineBarrier { ref stages, ref barriers } => (stages.clone(), barriers.clone()),
+            ref other => panic!("Unexpected command {:?}", other),
+        })
+        .collect()
+}
+
+#[test]
+fn buffer_transitions() {
+    let Resources { buffer, mut cmd_buffer, .. } = setup();
+    let mut tracker = StateTracker::new();
+
+    // First use doesn't need to wait on anything.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.buffer(0, &buffer, 0 .. 256, buffer::TRANSFER_WRITE, pso::TRANSFER);
+    assert!(transitions.is_empty());
+    transitions.record_raw(&mut cmd_buffer);
+
+    // Reads following the upload are combined.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.buffer(0, &buffer, 0 .. 128, buffer::VERTEX_BUFFER_READ, pso::VERTEX_INPUT);
+    transitions.buffer(0, &buffer, 128 .. 256, buffer::INDEX_BUFFER_READ, pso::VERTEX_INPUT);
+    transitions.record_raw(&mut cmd_buffer);
+
+    // Consecutive reads don't need barriers.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.buffer(0, &buffer, 0 .. 128, buffer::VERTEX_BUFFER_READ, pso::VERTEX_INPUT);
+    assert!(transitions.is_empty());
+
+    // Overwriting needs to wait for all reads.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.buffer(0, &buffer, 64 .. 192, buffer::TRANSFER_WRITE, pso::TRANSFER);
+    transitions.record_raw(&mut cmd_buffer);
+
+    let barriers = barriers(&cmd_buffer);
+    assert_eq!(barriers.len(), 2);
+    assert_eq!(barriers[0].0, pso::TRANSFER .. pso::VERTEX_INPUT);
+    match &barriers[0].1[..] {
+        &[empty::Barrier::Buffer { ref states, target, ref range }, empty::Barrier::Buffer { states: ref states2, range: ref range2, .. }] => {
+            assert_eq!(target, buffer.id());
+            assert_eq!((states.clone(), range.clone()), (buffer::TRANSFER_WRITE .. buffer::VERTEX_BUFFER_READ, 0 .. 128));
+            assert_eq!((states2.clone(), range2.clone()), (buffer::TRANSFER_WRITE .. buffer::INDEX_BUFFER_READ, 128 .. 256));
+        }
+        other => panic!("Unexpected barriers {:?}", other),
+    }
+    assert_eq!(barriers[1].0, pso::VERTEX_INPUT .. pso::TRANSFER);
+    match &barriers[1].1[..] {
+        &[empty::Barrier::Buffer { ref states, ref range, .. }, empty::Barrier::Buffer { states: ref states2, range: ref range2, .. }] => {
+            assert_eq!((states.clone(), range.clone()), (buffer::VERTEX_BUFFER_READ .. buffer::TRANSFER_WRITE, 64 .. 128));
+            assert_eq!((states2.clone(), range2.clone()), (buffer::INDEX_BUFFER_READ .. buffer::TRANSFER_WRITE, 128 .. 192));
+        }
+        other => panic!("Unexpected barriers {:?}", other),
+    }
+}
+
+#[test]
+fn image_transitions() {
+    let Resources { image, mut cmd_buffer, .. } = setup();
+    let mut tracker = StateTracker::new();
+    let upload = (image::TRANSFER_WRITE, ImageLayout::TransferDstOptimal);
+    let sample = (image::SHADER_READ, ImageLayout::ShaderReadOnlyOptimal);
+
+    // All subresources share the same initial state and are transitioned at once.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.image("image", &image, (0 .. 2, 0 .. 4), upload, pso::TRANSFER);
+    transitions.record_raw(&mut cmd_buffer);
+
+    // Only sample the first level, in the vertex and pixel shader.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.image("image", &image, (0 .. 1, 0 .. 4), sample, pso::VERTEX_SHADER);
+    transitions.record_raw(&mut cmd_buffer);
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.image("image", &image, (0 .. 1, 0 .. 4), sample, pso::PIXEL_SHADER);
+    assert!(transitions.is_empty());
+
+    // Writing to a layer of both levels transitions from different states.
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    transitions.image("image", &image, (0 .. 2, 1 .. 2), upload, pso::TRANSFER);
+    transitions.record_raw(&mut cmd_buffer);
+
+    let barriers = barriers(&cmd_buffer);
+    assert_eq!(barriers.len(), 3);
+    let image_barriers = |index: usize| -> Vec<(std::ops::Range<image::State>, image::SubresourceRange)> {
+        barriers[index].1
+            .iter()
+            .map(|barrier| match *barrier {
+                empty::Barrier::Image { ref states, target, ref range } => {
+                    assert_eq!(target, image.id());
+                    (states.clone(), range.clone())
+                }
+                ref other => panic!("Unexpected barrier {:?}", other),
+            })
+            .collect()
+    };
+
+    assert_eq!(barriers[0].0, pso::TOP_OF_PIPE .. pso::TRANSFER);
+    assert_eq!(image_barriers(0), vec![
+        ((image::Access::empty(), ImageLayout::Undefined) .. upload, (0 .. 2, 0 .. 4)),
+    ]);
+    assert_eq!(barriers[1].0, pso::TRANSFER .. pso::VERTEX_SHADER);
+    assert_eq!(image_barriers(1), vec![(upload .. sample, (0 .. 1, 0 .. 4))]);
+    assert_eq!(barriers[2].0, pso::VERTEX_SHADER | pso::PIXEL_SHADER | pso::TRANSFER .. pso::TRANSFER);
+    assert_eq!(image_barriers(2), vec![
+        (sample .. upload, (0 .. 1, 1 .. 2)),
+        (upload .. upload, (1 .. 2, 1 .. 2)),
+    ]);
+}
+
+#[test]
+fn external_image_state() {
+    let Resources { image, mut cmd_buffer, .. } = setup();
+    let mut tracker = StateTracker::new();
+    let present = (image::Access::empty(), ImageLayout::Present);
+    tracker.set_image_state(0, (0 .. 1, 0 .. 1), present);
+
+    let mut transitions = tracker.transitions::<empty::Backend>();
+    let target = (image::COLOR_ATTACHMENT_WRITE, ImageLayout::ColorAttachmentOptimal);
+    transitions.image(0, &image, (0 .. 1, 0 .. 1), target, pso::COLOR_ATTACHMENT_OUTPUT);
+    transitions.record_raw(&mut cmd_buffer);
+
+    let barriers = barriers(&cmd_buffer);
+    assert_eq!(barriers[0].0, pso::BOTTOM_OF_PIPE .. pso::COLOR_ATTACHMENT_OUTPUT);
+    match &barriers[0].1[..] {
+        &[empty::Barrier::Image { ref states, .. }] => assert_eq!(states.clone(), present .. target),
+        other => panic!("Unexpected barriers {:?}", other),
+    }
+}
@@ -0,0 +1,163 @@
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+use core::{buffer, format, image, memory};
+use core::{Adapter, Device, QueueFamily};
+use core::command::Offset;
+use core::device::Extent;
+use core::memory::{Allocator, ImageRegion, Strategy, Uploader};
+
+fn open() -> core::Gpu<empty::Backend> {
+    let adapter = empty::Adapter::new();
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    adapter.open(&descs)
+}
+
+#[test]
+fn upload_buffer_and_image() {
+    let core::Gpu { mut device, mut transfer_queues, heap_types, .. } = open();
+    let mut queue = transfer_queues.remove(0);
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1 << 16);
+    let mut uploader = Uploader::new(&mut device, &queue, &heap_types, 2, 1 << 12);
+
+    let (dst_buffer, buffer_memory) = allocator
+        .create_buffer(&mut device, 64, 4, buffer::TRANSFER_DST, memory::DEVICE_LOCAL)
+        .unwrap();
+    let kind = image::Kind::D2(3, 2, image::AaMode::Single);
+    let (dst_image, image_memory) = allocator
+        .create_image(&mut device, kind, 1, format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm),
+                      image::TRANSFER_DST, memory::DEVICE_LOCAL)
+        .unwrap();
+
+    uploader.upload_buffer(&mut device, &dst_buffer, 16, &[1u32, 2, 3]).unwrap();
+    let region = ImageRegion {
+        subresource: (0, 0 .. 1),
+        offset: Offset { x: 0, y: 0, z: 0 },
+        extent: Extent { width: 3, height: 2, depth: 1 },
+    };
+    let texels = [[0xFFu8; 4]; 6];
+    uploader
+        .upload_image(&mut device, &dst_image, image::ImageLayout::TransferDstOptimal,
+                      format::Format(format::SurfaceType::R8_G8_B8_A8, format::ChannelType::Unorm),
+                      region, &texels)
+        .unwrap();
+
+    let semaphore = device.create_semaphore();
+    assert!(uploader.submit(&mut queue, &[&semaphore]).is_some());
+    // Nothing left to submit.
+    assert!(uploader.submit(&mut queue, &[]).is_none());
+
+    {
+        let submissions = queue.as_raw().submissions();
+        assert_eq!(submissions.len(), 1);
+        assert_eq!(submissions[0].signal_semaphores.len(), 1);
+        let commands = submissions[0].command_buffers
+            .iter()
+            .flat_map(|commands| commands.iter())
+            .collect::<Vec<_>>();
+        match commands[..] {
+            [&empty::Command::CopyBuffer { ref regions, .. },
+             &empty::Command::CopyBufferToImage { regions: ref image_regions, .. }] => {
+                assert_eq!((regions[0].src, regions[0].dst, regions[0].size), (0, 16, 12));
+                // Rows of 12 bytes are padded to the pitch alignment of the empty backend,
+                // and the image data starts at an offset aligned to the texel size.
+                assert_eq!(image_regions[0].buffer_row_pitch, 12);
+                assert_eq!(image_regions[0].buffer_offset, 12);
+            }
+            _ => panic!("unexpected commands {:?}", commands),
+        }
+    }
+
+    uploader.cleanup(&mut device);
+    uploader.destroy(&mut device);
+    device.destroy_semaphore(semaphore);
+    device.destroy_buffer(dst_buffer);
+    device.destroy_image(dst_image);
+    allocator.free(buffer_memory);
+    allocator.free(image_memory);
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn upload_compressed_image() {
+    let core::Gpu { mut device, mut transfer_queues, heap_types, .. } = open();
+    let mut queue = transfer_queues.remove(0);
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1 << 16);
+    let mut uploader = Uploader::new(&mut device, &queue, &heap_types, 2, 1 << 12);
+    let bc1 = format::Format(format::SurfaceType::BC1_R8_G8_B8_A8, format::ChannelType::Unorm);
+
+    assert_eq!(device.create_image(image::Kind::D1(8), 1, bc1, image::TRANSFER_DST).err(),
+               Some(image::CreationError::Kind));
+    // The last row of blocks is only partially covered by the image.
+    let kind = image::Kind::D2(8, 6, image::AaMode::Single);
+    let (dst_image, image_memory) = allocator
+        .create_image(&mut device, kind, 1, bc1, image::TRANSFER_DST, memory::DEVICE_LOCAL)
+        .unwrap();
+
+    let region = ImageRegion {
+        subresource: (0, 0 .. 1),
+        offset: Offset { x: 0, y: 0, z: 0 },
+        extent: Extent { width: 8, height: 6, depth: 1 },
+    };
+    let blocks = [[0xFFu8; 8]; 4];
+    assert_eq!(
+        uploader.upload_image(&mut device, &dst_image, image::ImageLayout::TransferDstOptimal, bc1, region.clone(), &blocks[.. 3]),
+        Err(memory::UploadError::SizeMismatch { expected: 32, actual: 24 })
+    );
+    uploader
+        .upload_image(&mut device, &dst_image, image::ImageLayout::TransferDstOptimal, bc1, region, &blocks)
+        .unwrap();
+    assert!(uploader.submit(&mut queue, &[]).is_some());
+
+    {
+        let submissions = queue.as_raw().submissions();
+        let commands = submissions[0].command_buffers
+            .iter()
+            .flat_map(|commands| commands.iter())
+            .collect::<Vec<_>>();
+        match commands[..] {
+            [&empty::Command::CopyBufferToImage { ref regions, .. }] => {
+                // Two rows of two blocks.
+                assert_eq!(regions[0].buffer_row_pitch, 16);
+                assert_eq!(regions[0].buffer_slice_pitch, 32);
+            }
+            _ => panic!("unexpected commands {:?}", commands),
+        }
+    }
+
+    uploader.cleanup(&mut device);
+    uploader.destroy(&mut device);
+    device.destroy_image(dst_image);
+    allocator.free(image_memory);
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
+
+#[test]
+fn reuse_batches() {
+    let core::Gpu { mut device, mut transfer_queues, heap_types, .. } = open();
+    let mut queue = transfer_queues.remove(0);
+    let mut allocator = Allocator::<empty::Backend>::new(&heap_types, Strategy::FreeList, 1 << 16);
+    let mut uploader = Uploader::new(&mut device, &queue, &heap_types, 1, 256);
+
+    let (dst, dst_memory) = allocator
+        .create_buffer(&mut device, 1024, 1, buffer::TRANSFER_DST, memory::DEVICE_LOCAL)
+        .unwrap();
+    // Uploads larger than the block size get a dedicated staging buffer.
+    uploader.upload_buffer(&mut device, &dst, 0, &[0u8; 1024]).unwrap();
+    uploader.submit(&mut queue, &[]);
+    // The single batch is reclaimed once its fence is signaled.
+    uploader.upload_buffer(&mut device, &dst, 0, &[0u8; 16]).unwrap();
+    uploader.submit(&mut queue, &[]);
+    assert_eq!(queue.as_raw().submissions().len(), 2);
+
+    uploader.destroy(&mut device);
+    device.destroy_buffer(dst);
+    allocator.free(dst_memory);
+    allocator.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
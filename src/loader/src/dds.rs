@@ -0,0 +1,227 @@
+//! DirectDraw Surface containers.
+//!
+//! Both legacy pixel formats and the DX10 header extension are understood. Legacy cube
+//! maps need to contain all six faces. The subresources are stored element after element,
+//! each with all its mip levels.
+
+use std::borrow::Cow;
+use core::format::{ChannelType, Format, SurfaceType};
+use super::{image_kind, num_levels, Alignment, Container, Error, Reader, Texture};
+
+/// Magic number at the start of DDS files.
+pub const MAGIC: &'static [u8] = b"DDS ";
+
+const HEADER_SIZE: u32 = 124;
+const PIXEL_FORMAT_SIZE: u32 = 32;
+
+const DDPF_ALPHAPIXELS: u32 = 0x1;
+const DDPF_FOURCC: u32 = 0x4;
+const DDPF_RGB: u32 = 0x40;
+const DDPF_LUMINANCE: u32 = 0x2_0000;
+
+const DDSCAPS2_CUBEMAP: u32 = 0x200;
+const DDSCAPS2_CUBEMAP_ALLFACES: u32 = 0xFC00;
+const DDSCAPS2_VOLUME: u32 = 0x20_0000;
+
+const DIMENSION_TEXTURE1D: u32 = 2;
+const DIMENSION_TEXTURE3D: u32 = 4;
+const MISC_TEXTURECUBE: u32 = 0x4;
+
+/// Build the FourCC code of four characters.
+fn four_cc(code: &[u8; 4]) -> u32 {
+    code.iter().rev().fold(0, |value, &c| value << 8 | c as u32)
+}
+
+/// Pixel format description of the header.
+struct PixelFormat {
+    flags: u32,
+    four_cc: u32,
+    rgb_bits: u32,
+    masks: [u32; 4],
+}
+
+/// Map a DXGI format onto a `Format`.
+fn map_dxgi_format(format: u32) -> Option<Format> {
+    use core::format::ChannelType::*;
+    use core::format::SurfaceType::*;
+    let (surface, channel) = match format {
+        2 => (R32_G32_B32_A32, Float),
+        3 => (R32_G32_B32_A32, Uint),
+        4 => (R32_G32_B32_A32, Int),
+        6 => (R32_G32_B32, Float),
+        7 => (R32_G32_B32, Uint),
+        8 => (R32_G32_B32, Int),
+        10 => (R16_G16_B16_A16, Float),
+        11 => (R16_G16_B16_A16, Unorm),
+        12 => (R16_G16_B16_A16, Uint),
+        13 => (R16_G16_B16_A16, Inorm),
+        14 => (R16_G16_B16_A16, Int),
+        16 => (R32_G32, Float),
+        17 => (R32_G32, Uint),
+        18 => (R32_G32, Int),
+        24 => (R10_G10_B10_A2, Unorm),
+        25 => (R10_G10_B10_A2, Uint),
+        26 => (R11_G11_B10, Float),
+        28 => (R8_G8_B8_A8, Unorm),
+        29 => (R8_G8_B8_A8, Srgb),
+        30 => (R8_G8_B8_A8, Uint),
+        31 => (R8_G8_B8_A8, Inorm),
+        32 => (R8_G8_B8_A8, Int),
+        34 => (R16_G16, Float),
+        35 => (R16_G16, Unorm),
+        36 => (R16_G16, Uint),
+        37 => (R16_G16, Inorm),
+        38 => (R16_G16, Int),
+        41 => (R32, Float),
+        42 => (R32, Uint),
+        43 => (R32, Int),
+        49 => (R8_G8, Unorm),
+        50 => (R8_G8, Uint),
+        51 => (R8_G8, Inorm),
+        52 => (R8_G8, Int),
+        54 => (R16, Float),
+        56 => (R16, Unorm),
+        57 => (R16, Uint),
+        58 => (R16, Inorm),
+        59 => (R16, Int),
+        61 => (R8, Unorm),
+        62 => (R8, Uint),
+        63 => (R8, Inorm),
+        64 => (R8, Int),
+        71 => (BC1_R8_G8_B8_A8, Unorm),
+        72 => (BC1_R8_G8_B8_A8, Srgb),
+        74 => (BC2_R8_G8_B8_A8, Unorm),
+        75 => (BC2_R8_G8_B8_A8, Srgb),
+        77 => (BC3_R8_G8_B8_A8, Unorm),
+        78 => (BC3_R8_G8_B8_A8, Srgb),
+        80 => (BC4_R8, Unorm),
+        81 => (BC4_R8, Inorm),
+        83 => (BC5_R8_G8, Unorm),
+        84 => (BC5_R8_G8, Inorm),
+        85 => (R5_G6_B5, Unorm),
+        87 => (B8_G8_R8_A8, Unorm),
+        91 => (B8_G8_R8_A8, Srgb),
+        95 => (BC6_R16_G16_B16, Float),
+        98 => (BC7_R8_G8_B8_A8, Unorm),
+        99 => (BC7_R8_G8_B8_A8, Srgb),
+        _ => return None,
+    };
+    Some(Format(surface, channel))
+}
+
+/// Map a legacy pixel format onto a `Format`.
+fn map_pixel_format(pf: &PixelFormat) -> Result<Format, Error> {
+    if pf.flags & DDPF_FOURCC != 0 {
+        let (surface, channel) = match pf.four_cc {
+            36 => (SurfaceType::R16_G16_B16_A16, ChannelType::Unorm),
+            110 => (SurfaceType::R16_G16_B16_A16, ChannelType::Inorm),
+            111 => (SurfaceType::R16, ChannelType::Float),
+            112 => (SurfaceType::R16_G16, ChannelType::Float),
+            113 => (SurfaceType::R16_G16_B16_A16, ChannelType::Float),
+            114 => (SurfaceType::R32, ChannelType::Float),
+            115 => (SurfaceType::R32_G32, ChannelType::Float),
+            116 => (SurfaceType::R32_G32_B32_A32, ChannelType::Float),
+            code if code == four_cc(b"DXT1") => (SurfaceType::BC1_R8_G8_B8_A8, ChannelType::Unorm),
+            code if code == four_cc(b"DXT2") || code == four_cc(b"DXT3") =>
+                (SurfaceType::BC2_R8_G8_B8_A8, ChannelType::Unorm),
+            code if code == four_cc(b"DXT4") || code == four_cc(b"DXT5") =>
+                (SurfaceType::BC3_R8_G8_B8_A8, ChannelType::Unorm),
+            code if code == four_cc(b"ATI1") || code == four_cc(b"BC4U") =>
+                (SurfaceType::BC4_R8, ChannelType::Unorm),
+            code if code == four_cc(b"BC4S") => (SurfaceType::BC4_R8, ChannelType::Inorm),
+            code if code == four_cc(b"ATI2") || code == four_cc(b"BC5U") =>
+                (SurfaceType::BC5_R8_G8, ChannelType::Unorm),
+            code if code == four_cc(b"BC5S") => (SurfaceType::BC5_R8_G8, ChannelType::Inorm),
+            code => return Err(Error::UnsupportedFormat(code)),
+        };
+        return Ok(Format(surface, channel));
+    }
+
+    let alpha = if pf.flags & DDPF_ALPHAPIXELS != 0 { pf.masks[3] } else { 0 };
+    let surface = if pf.flags & DDPF_RGB != 0 {
+        match (pf.rgb_bits, pf.masks[0], pf.masks[1], pf.masks[2], alpha) {
+            (32, 0xFF, 0xFF00, 0xFF_0000, 0xFF00_0000) => SurfaceType::R8_G8_B8_A8,
+            (32, 0xFF_0000, 0xFF00, 0xFF, 0xFF00_0000) => SurfaceType::B8_G8_R8_A8,
+            (32, 0x3FF, 0xF_FC00, 0x3FF0_0000, 0xC000_0000) => SurfaceType::R10_G10_B10_A2,
+            (32, 0xFFFF, 0xFFFF_0000, 0, 0) => SurfaceType::R16_G16,
+            (16, 0xF800, 0x7E0, 0x1F, 0) => SurfaceType::R5_G6_B5,
+            _ => return Err(Error::Unsupported("Legacy pixel format")),
+        }
+    } else if pf.flags & DDPF_LUMINANCE != 0 {
+        match (pf.rgb_bits, pf.masks[0], alpha) {
+            (8, 0xFF, 0) => SurfaceType::R8,
+            (16, 0xFFFF, 0) => SurfaceType::R16,
+            (16, 0xFF, 0xFF00) => SurfaceType::R8_G8,
+            _ => return Err(Error::Unsupported("Legacy pixel format")),
+        }
+    } else {
+        return Err(Error::Unsupported("Legacy pixel format"));
+    };
+    Ok(Format(surface, ChannelType::Unorm))
+}
+
+/// Parse a DDS file.
+pub fn load(data: &[u8], alignment: Alignment) -> Result<Texture, Error> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(4)? != MAGIC {
+        return Err(Error::UnknownContainer);
+    }
+    if reader.u32()? != HEADER_SIZE {
+        return Err(Error::InvalidHeader("Invalid header size"));
+    }
+    let _flags = reader.u32()?;
+    let height = reader.u32()?;
+    let width = reader.u32()?;
+    let _pitch_or_linear_size = reader.u32()?;
+    // Only meaningful for volume textures.
+    let depth = reader.u32()?;
+    let levels = reader.u32()?;
+    reader.bytes(11 * 4)?;
+
+    if reader.u32()? != PIXEL_FORMAT_SIZE {
+        return Err(Error::InvalidHeader("Invalid pixel format size"));
+    }
+    let pixel_format = PixelFormat {
+        flags: reader.u32()?,
+        four_cc: reader.u32()?,
+        rgb_bits: reader.u32()?,
+        masks: [reader.u32()?, reader.u32()?, reader.u32()?, reader.u32()?],
+    };
+    let _caps = reader.u32()?;
+    let caps2 = reader.u32()?;
+    reader.bytes(3 * 4)?;
+
+    let (format, kind) = if pixel_format.flags & DDPF_FOURCC != 0 && pixel_format.four_cc == four_cc(b"DX10") {
+        let dxgi_format = reader.u32()?;
+        let dimension = reader.u32()?;
+        let misc = reader.u32()?;
+        let array_size = reader.u32()?;
+        let _misc2 = reader.u32()?;
+
+        let format = map_dxgi_format(dxgi_format).ok_or(Error::UnsupportedFormat(dxgi_format))?;
+        let layers = if array_size > 1 { Some(array_size) } else { None };
+        let depth = if dimension == DIMENSION_TEXTURE3D { depth } else { 1 };
+        let cube = misc & MISC_TEXTURECUBE != 0;
+        let kind = image_kind((width, height, depth), layers, cube, dimension == DIMENSION_TEXTURE1D)?;
+        (format, kind)
+    } else {
+        let format = map_pixel_format(&pixel_format)?;
+        let cube = caps2 & DDSCAPS2_CUBEMAP != 0;
+        if cube && caps2 & DDSCAPS2_CUBEMAP_ALLFACES != DDSCAPS2_CUBEMAP_ALLFACES {
+            return Err(Error::Unsupported("Cube maps with missing faces"));
+        }
+        let depth = if caps2 & DDSCAPS2_VOLUME != 0 { depth } else { 1 };
+        let kind = image_kind((width, height, depth), None, cube, false)?;
+        (format, kind)
+    };
+
+    let mut container = Container::new(kind, num_levels(kind, levels)?, format);
+    for layer in 0 .. container.layers() {
+        for level in 0 .. container.levels {
+            let row_size = container.row_size(level);
+            let bytes = reader.bytes(row_size * container.rows(level))?;
+            container.push(level, layer, Cow::Borrowed(bytes), row_size);
+        }
+    }
+    Ok(container.pack(alignment))
+}
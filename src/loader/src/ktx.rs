@@ -0,0 +1,157 @@
+//! Khronos texture containers, version 1.
+//!
+//! Files in either byte order are understood, the texel data of big endian files is
+//! swapped according to the size of its data type. The subresources are stored level
+//! after level, with rows padded to four bytes.
+
+use std::borrow::Cow;
+use core::format::Format;
+use super::{align, image_kind, num_levels, Alignment, Container, Error, Reader, Texture, ASTC_SURFACES};
+
+/// Identifier at the start of KTX files.
+pub const IDENTIFIER: &'static [u8] = b"\xABKTX 11\xBB\r\n\x1A\n";
+
+const ENDIANNESS: u32 = 0x0403_0201;
+const ROW_ALIGNMENT: usize = 4;
+
+/// Map a sized OpenGL internal format onto a `Format`.
+fn map_internal_format(format: u32) -> Option<Format> {
+    use core::format::ChannelType::*;
+    use core::format::SurfaceType::*;
+    let (surface, channel) = match format {
+        0x8229 => (R8, Unorm),
+        0x8F94 => (R8, Inorm),
+        0x8232 => (R8, Uint),
+        0x8231 => (R8, Int),
+        0x822B => (R8_G8, Unorm),
+        0x8F95 => (R8_G8, Inorm),
+        0x8238 => (R8_G8, Uint),
+        0x8237 => (R8_G8, Int),
+        0x8058 => (R8_G8_B8_A8, Unorm),
+        0x8F97 => (R8_G8_B8_A8, Inorm),
+        0x8D7C => (R8_G8_B8_A8, Uint),
+        0x8D8E => (R8_G8_B8_A8, Int),
+        0x8C43 => (R8_G8_B8_A8, Srgb),
+        0x8D62 => (R5_G6_B5, Unorm),
+        0x8059 => (R10_G10_B10_A2, Unorm),
+        0x906F => (R10_G10_B10_A2, Uint),
+        0x8C3A => (R11_G11_B10, Float),
+        0x822A => (R16, Unorm),
+        0x8234 => (R16, Uint),
+        0x8233 => (R16, Int),
+        0x822D => (R16, Float),
+        0x822C => (R16_G16, Unorm),
+        0x823A => (R16_G16, Uint),
+        0x8239 => (R16_G16, Int),
+        0x822F => (R16_G16, Float),
+        0x805B => (R16_G16_B16_A16, Unorm),
+        0x8D76 => (R16_G16_B16_A16, Uint),
+        0x8D88 => (R16_G16_B16_A16, Int),
+        0x881A => (R16_G16_B16_A16, Float),
+        0x8236 => (R32, Uint),
+        0x8235 => (R32, Int),
+        0x822E => (R32, Float),
+        0x823C => (R32_G32, Uint),
+        0x823B => (R32_G32, Int),
+        0x8230 => (R32_G32, Float),
+        0x8D71 => (R32_G32_B32, Uint),
+        0x8D83 => (R32_G32_B32, Int),
+        0x8815 => (R32_G32_B32, Float),
+        0x8D70 => (R32_G32_B32_A32, Uint),
+        0x8D82 => (R32_G32_B32_A32, Int),
+        0x8814 => (R32_G32_B32_A32, Float),
+        0x83F0 => (BC1_R8_G8_B8, Unorm),
+        0x83F1 => (BC1_R8_G8_B8_A8, Unorm),
+        0x83F2 => (BC2_R8_G8_B8_A8, Unorm),
+        0x83F3 => (BC3_R8_G8_B8_A8, Unorm),
+        0x8C4C => (BC1_R8_G8_B8, Srgb),
+        0x8C4D => (BC1_R8_G8_B8_A8, Srgb),
+        0x8C4E => (BC2_R8_G8_B8_A8, Srgb),
+        0x8C4F => (BC3_R8_G8_B8_A8, Srgb),
+        0x8DBB => (BC4_R8, Unorm),
+        0x8DBC => (BC4_R8, Inorm),
+        0x8DBD => (BC5_R8_G8, Unorm),
+        0x8DBE => (BC5_R8_G8, Inorm),
+        0x8E8F => (BC6_R16_G16_B16, Float),
+        0x8E8C => (BC7_R8_G8_B8_A8, Unorm),
+        0x8E8D => (BC7_R8_G8_B8_A8, Srgb),
+        0x9270 => (EAC_R11, Unorm),
+        0x9271 => (EAC_R11, Inorm),
+        0x9272 => (EAC_R11_G11, Unorm),
+        0x9273 => (EAC_R11_G11, Inorm),
+        0x9274 => (ETC2_R8_G8_B8, Unorm),
+        0x9275 => (ETC2_R8_G8_B8, Srgb),
+        0x9276 => (ETC2_R8_G8_B8_A1, Unorm),
+        0x9277 => (ETC2_R8_G8_B8_A1, Srgb),
+        0x9278 => (ETC2_R8_G8_B8_A8, Unorm),
+        0x9279 => (ETC2_R8_G8_B8_A8, Srgb),
+        0x93B0 ... 0x93BD => (ASTC_SURFACES[(format - 0x93B0) as usize], Unorm),
+        0x93D0 ... 0x93DD => (ASTC_SURFACES[(format - 0x93D0) as usize], Srgb),
+        _ => return None,
+    };
+    Some(Format(surface, channel))
+}
+
+/// Swap the bytes of each element of `data`, which are `size` bytes large.
+fn swap_bytes(data: &[u8], size: usize) -> Vec<u8> {
+    data.chunks(size)
+        .flat_map(|element| element.iter().rev().cloned())
+        .collect()
+}
+
+/// Parse a KTX file.
+pub fn load(data: &[u8], alignment: Alignment) -> Result<Texture, Error> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(IDENTIFIER.len())? != IDENTIFIER {
+        return Err(Error::UnknownContainer);
+    }
+    match reader.u32()? {
+        ENDIANNESS => (),
+        endianness if endianness == ENDIANNESS.swap_bytes() => reader.big_endian = true,
+        _ => return Err(Error::InvalidHeader("Invalid endianness")),
+    }
+    let _gl_type = reader.u32()?;
+    let type_size = reader.u32()? as usize;
+    let _gl_format = reader.u32()?;
+    let internal_format = reader.u32()?;
+    let _base_internal_format = reader.u32()?;
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let depth = reader.u32()?;
+    let elements = reader.u32()?;
+    let faces = reader.u32()?;
+    let levels = reader.u32()?;
+    let key_value_size = reader.u32()? as usize;
+    reader.bytes(key_value_size)?;
+
+    let format = map_internal_format(internal_format).ok_or(Error::UnsupportedFormat(internal_format))?;
+    let cube = match faces {
+        1 => false,
+        6 => true,
+        _ => return Err(Error::InvalidHeader("Invalid number of faces")),
+    };
+    let layers = if elements > 0 { Some(elements) } else { None };
+    let kind = image_kind((width, height, depth), layers, cube, height == 0)?;
+    let swap = reader.big_endian && type_size > 1;
+
+    let mut container = Container::new(kind, num_levels(kind, levels)?, format);
+    for level in 0 .. container.levels {
+        // The size is the one of a single face for cube maps which aren't arrays.
+        let image_size = reader.u32()? as usize;
+        let row_pitch = align(container.row_size(level), ROW_ALIGNMENT);
+        let layer_size = row_pitch * container.rows(level);
+        let expected = if cube && layers.is_none() { layer_size } else { layer_size * container.layers() as usize };
+        if image_size != expected {
+            return Err(Error::InvalidHeader("Image size doesn't match the dimensions"));
+        }
+
+        for layer in 0 .. container.layers() {
+            let bytes = reader.bytes(layer_size)?;
+            let bytes = if swap { Cow::Owned(swap_bytes(bytes, type_size)) } else { Cow::Borrowed(bytes) };
+            container.push(level, layer, bytes, row_pitch);
+        }
+        let offset = align(reader.offset(), ROW_ALIGNMENT);
+        reader.seek(offset)?;
+    }
+    Ok(container.pack(alignment))
+}
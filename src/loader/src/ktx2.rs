@@ -0,0 +1,157 @@
+//! Khronos texture containers, version 2.
+//!
+//! Supercompressed files aren't supported. The data of each level is located through
+//! the level index and holds the tightly packed rows of all its layers.
+
+use std::borrow::Cow;
+use core::format::Format;
+use super::{image_kind, num_levels, Alignment, Container, Error, Reader, Texture, ASTC_SURFACES};
+
+/// Identifier at the start of KTX 2 files.
+pub const IDENTIFIER: &'static [u8] = b"\xABKTX 20\xBB\r\n\x1A\n";
+
+/// Offset of the level index, following the header and the section index.
+const LEVEL_INDEX_OFFSET: usize = 80;
+
+/// Map a Vulkan format onto a `Format`.
+fn map_vk_format(format: u32) -> Option<Format> {
+    use core::format::ChannelType::*;
+    use core::format::SurfaceType::*;
+    let (surface, channel) = match format {
+        4 => (R5_G6_B5, Unorm),
+        9 => (R8, Unorm),
+        10 => (R8, Inorm),
+        13 => (R8, Uint),
+        14 => (R8, Int),
+        15 => (R8, Srgb),
+        16 => (R8_G8, Unorm),
+        17 => (R8_G8, Inorm),
+        20 => (R8_G8, Uint),
+        21 => (R8_G8, Int),
+        22 => (R8_G8, Srgb),
+        37 => (R8_G8_B8_A8, Unorm),
+        38 => (R8_G8_B8_A8, Inorm),
+        41 => (R8_G8_B8_A8, Uint),
+        42 => (R8_G8_B8_A8, Int),
+        43 => (R8_G8_B8_A8, Srgb),
+        44 => (B8_G8_R8_A8, Unorm),
+        50 => (B8_G8_R8_A8, Srgb),
+        64 => (R10_G10_B10_A2, Unorm),
+        68 => (R10_G10_B10_A2, Uint),
+        70 => (R16, Unorm),
+        71 => (R16, Inorm),
+        74 => (R16, Uint),
+        75 => (R16, Int),
+        76 => (R16, Float),
+        77 => (R16_G16, Unorm),
+        78 => (R16_G16, Inorm),
+        81 => (R16_G16, Uint),
+        82 => (R16_G16, Int),
+        83 => (R16_G16, Float),
+        91 => (R16_G16_B16_A16, Unorm),
+        92 => (R16_G16_B16_A16, Inorm),
+        95 => (R16_G16_B16_A16, Uint),
+        96 => (R16_G16_B16_A16, Int),
+        97 => (R16_G16_B16_A16, Float),
+        98 => (R32, Uint),
+        99 => (R32, Int),
+        100 => (R32, Float),
+        101 => (R32_G32, Uint),
+        102 => (R32_G32, Int),
+        103 => (R32_G32, Float),
+        104 => (R32_G32_B32, Uint),
+        105 => (R32_G32_B32, Int),
+        106 => (R32_G32_B32, Float),
+        107 => (R32_G32_B32_A32, Uint),
+        108 => (R32_G32_B32_A32, Int),
+        109 => (R32_G32_B32_A32, Float),
+        122 => (R11_G11_B10, Float),
+        131 => (BC1_R8_G8_B8, Unorm),
+        132 => (BC1_R8_G8_B8, Srgb),
+        133 => (BC1_R8_G8_B8_A8, Unorm),
+        134 => (BC1_R8_G8_B8_A8, Srgb),
+        135 => (BC2_R8_G8_B8_A8, Unorm),
+        136 => (BC2_R8_G8_B8_A8, Srgb),
+        137 => (BC3_R8_G8_B8_A8, Unorm),
+        138 => (BC3_R8_G8_B8_A8, Srgb),
+        139 => (BC4_R8, Unorm),
+        140 => (BC4_R8, Inorm),
+        141 => (BC5_R8_G8, Unorm),
+        142 => (BC5_R8_G8, Inorm),
+        143 => (BC6_R16_G16_B16, Float),
+        145 => (BC7_R8_G8_B8_A8, Unorm),
+        146 => (BC7_R8_G8_B8_A8, Srgb),
+        147 => (ETC2_R8_G8_B8, Unorm),
+        148 => (ETC2_R8_G8_B8, Srgb),
+        149 => (ETC2_R8_G8_B8_A1, Unorm),
+        150 => (ETC2_R8_G8_B8_A1, Srgb),
+        151 => (ETC2_R8_G8_B8_A8, Unorm),
+        152 => (ETC2_R8_G8_B8_A8, Srgb),
+        153 => (EAC_R11, Unorm),
+        154 => (EAC_R11, Inorm),
+        155 => (EAC_R11_G11, Unorm),
+        156 => (EAC_R11_G11, Inorm),
+        // Unorm and sRGB variants alternate.
+        157 ... 184 => {
+            let index = format - 157;
+            let channel = if index % 2 == 0 { Unorm } else { Srgb };
+            (ASTC_SURFACES[index as usize / 2], channel)
+        }
+        _ => return None,
+    };
+    Some(Format(surface, channel))
+}
+
+/// Parse a KTX 2 file.
+pub fn load(data: &[u8], alignment: Alignment) -> Result<Texture, Error> {
+    let mut reader = Reader::new(data);
+    if reader.bytes(IDENTIFIER.len())? != IDENTIFIER {
+        return Err(Error::UnknownContainer);
+    }
+    let vk_format = reader.u32()?;
+    let _type_size = reader.u32()?;
+    let width = reader.u32()?;
+    let height = reader.u32()?;
+    let depth = reader.u32()?;
+    let layer_count = reader.u32()?;
+    let faces = reader.u32()?;
+    let levels = reader.u32()?;
+    if reader.u32()? != 0 {
+        return Err(Error::Unsupported("Supercompression"));
+    }
+
+    let format = map_vk_format(vk_format).ok_or(Error::UnsupportedFormat(vk_format))?;
+    let cube = match faces {
+        1 => false,
+        6 => true,
+        _ => return Err(Error::InvalidHeader("Invalid number of faces")),
+    };
+    let layers = if layer_count > 0 { Some(layer_count) } else { None };
+    let kind = image_kind((width, height, depth), layers, cube, height == 0)?;
+
+    let mut container = Container::new(kind, num_levels(kind, levels)?, format);
+    reader.seek(LEVEL_INDEX_OFFSET)?;
+    let index = (0 .. container.levels)
+        .map(|_| {
+            let offset = reader.u64()?;
+            let length = reader.u64()?;
+            let _uncompressed_length = reader.u64()?;
+            Ok((offset as usize, length as usize))
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    for (level, (offset, length)) in index.into_iter().enumerate() {
+        let level = level as _;
+        let row_size = container.row_size(level);
+        let layer_size = row_size * container.rows(level);
+        if length != layer_size * container.layers() as usize {
+            return Err(Error::InvalidHeader("Level size doesn't match the dimensions"));
+        }
+        reader.seek(offset)?;
+        for layer in 0 .. container.layers() {
+            let bytes = reader.bytes(layer_size)?;
+            container.push(level, layer, Cow::Borrowed(bytes), row_size);
+        }
+    }
+    Ok(container.pack(alignment))
+}
@@ -0,0 +1,359 @@
+//! Loading of textures from DDS and KTX containers.
+//!
+//! A container is parsed into a `Texture`, which describes the image to create and holds
+//! the texel data of all its mip levels and layers. The data of each subresource is
+//! repacked to satisfy the copy alignments of a device, so that the texture data can be
+//! written into a staging buffer and the regions passed to `copy_buffer_to_image` as is.
+//!
+//! Supported containers:
+//!
+//!   - DDS, with legacy pixel formats or the DX10 header extension
+//!   - KTX 1, in either byte order
+//!   - KTX 2, without supercompression
+//!
+//! The layers of cube images are the faces of each array element,
+//! in the order of `image::CUBE_FACES`.
+
+#![deny(missing_docs)]
+
+extern crate gfx_core as core;
+
+use std::borrow::Cow;
+use std::error::Error as StdError;
+use std::fmt;
+use core::{format, image, Limits};
+use core::command::{BufferImageCopy, Offset};
+use core::device::Extent;
+
+pub mod dds;
+pub mod ktx;
+pub mod ktx2;
+
+/// Error parsing a texture container.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    /// The data doesn't start with the identifier of a supported container.
+    UnknownContainer,
+    /// The data ends before the end of the described contents.
+    Truncated,
+    /// The header describes an invalid image.
+    InvalidHeader(&'static str),
+    /// The pixel format has no equivalent `format::Format`.
+    ///
+    /// Contains the code of the format in the container: the DXGI format or FourCC
+    /// code for DDS, the OpenGL internal format for KTX and the Vulkan format for KTX 2.
+    UnsupportedFormat(u32),
+    /// The container uses a feature which isn't supported.
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidHeader(reason) |
+            Error::Unsupported(reason) => write!(f, "{}: {}", self.description(), reason),
+            Error::UnsupportedFormat(code) => write!(f, "{}: {:#x}", self.description(), code),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::UnknownContainer => "Unknown texture container",
+            Error::Truncated => "The texture data is truncated",
+            Error::InvalidHeader(_) => "Invalid texture header",
+            Error::UnsupportedFormat(_) => "Unsupported pixel format",
+            Error::Unsupported(_) => "Unsupported container feature",
+        }
+    }
+}
+
+/// Alignments of the texel data of each copy region, in bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Alignment {
+    /// Alignment of the offset of a region.
+    pub offset: usize,
+    /// Alignment of the row pitch of a region.
+    pub row_pitch: usize,
+}
+
+impl Alignment {
+    /// The alignments required for buffer to image copies by a device.
+    pub fn from_limits(limits: &Limits) -> Self {
+        Alignment {
+            offset: limits.min_buffer_copy_offset_alignment,
+            row_pitch: limits.min_buffer_copy_pitch_alignment,
+        }
+    }
+}
+
+impl Default for Alignment {
+    /// Tightly packed texel data.
+    fn default() -> Self {
+        Alignment {
+            offset: 1,
+            row_pitch: 1,
+        }
+    }
+}
+
+/// An image described by a texture container, with the texel data of all subresources.
+#[derive(Clone, Debug)]
+pub struct Texture {
+    /// Kind of the image.
+    pub kind: image::Kind,
+    /// Number of mip levels stored in the container.
+    pub levels: image::Level,
+    /// Format of the texels.
+    pub format: format::Format,
+    /// Texel data of all subresources.
+    pub data: Vec<u8>,
+    /// Copy regions out of `data` for each mip level and layer, level after level.
+    pub regions: Vec<BufferImageCopy>,
+}
+
+/// Parse a DDS or KTX container, detected by its identifier.
+pub fn load(data: &[u8], alignment: Alignment) -> Result<Texture, Error> {
+    if data.starts_with(dds::MAGIC) {
+        dds::load(data, alignment)
+    } else if data.starts_with(ktx::IDENTIFIER) {
+        ktx::load(data, alignment)
+    } else if data.starts_with(ktx2::IDENTIFIER) {
+        ktx2::load(data, alignment)
+    } else {
+        Err(Error::UnknownContainer)
+    }
+}
+
+/// Texel data of a subresource inside a container.
+struct Subresource<'a> {
+    level: image::Level,
+    layer: image::Layer,
+    /// Rows of all depth slices, `row_pitch` bytes apart.
+    data: Cow<'a, [u8]>,
+    row_pitch: usize,
+}
+
+/// Contents of a parsed container.
+struct Container<'a> {
+    kind: image::Kind,
+    levels: image::Level,
+    format: format::Format,
+    subresources: Vec<Subresource<'a>>,
+}
+
+impl<'a> Container<'a> {
+    fn new(kind: image::Kind, levels: image::Level, format: format::Format) -> Self {
+        Container {
+            kind,
+            levels,
+            format,
+            subresources: Vec::new(),
+        }
+    }
+
+    /// Number of layers of the image, counting each face of cube images.
+    fn layers(&self) -> image::Layer {
+        let faces = if self.kind.is_cube() { image::CUBE_FACES.len() as image::Layer } else { 1 };
+        self.kind.get_num_slices().unwrap_or(1) * faces
+    }
+
+    /// Width, height and depth of a mip level, each at least 1.
+    fn extent(&self, level: image::Level) -> Extent {
+        let (width, height, depth, _) = self.kind.get_level_dimensions(level);
+        // The faces of cube images are reported as depth.
+        let depth = match self.kind {
+            image::Kind::D3(..) => depth.max(1),
+            _ => 1,
+        };
+        Extent {
+            width: width.max(1) as u32,
+            height: height.max(1) as u32,
+            depth: depth as u32,
+        }
+    }
+
+    /// Size of a row of texels, or of blocks for compressed formats, of a mip level in bytes.
+    fn row_size(&self, level: image::Level) -> usize {
+        self.format.0.get_row_size(self.extent(level).width)
+    }
+
+    /// Number of rows of a mip level, over all depth slices.
+    fn rows(&self, level: image::Level) -> usize {
+        let extent = self.extent(level);
+        self.format.0.get_num_rows(extent.height) * extent.depth as usize
+    }
+
+    /// Add the subresource `(level, layer)` stored in `data`, which starts with
+    /// the rows of the subresource, `row_pitch` bytes apart.
+    fn push(&mut self, level: image::Level, layer: image::Layer, data: Cow<'a, [u8]>, row_pitch: usize) {
+        self.subresources.push(Subresource { level, layer, data, row_pitch });
+    }
+
+    /// Repack the texel data of all subresources, level after level.
+    fn pack(mut self, alignment: Alignment) -> Texture {
+        let block = self.format.0.get_block_size() as usize;
+        // Regions have to start at a multiple of the texel or block size as well.
+        let offset_alignment = lcm(alignment.offset.max(1), block);
+        let pitch_alignment = alignment.row_pitch.max(1);
+        self.subresources.sort_by_key(|sub| (sub.level, sub.layer));
+
+        let mut data = Vec::new();
+        let mut regions = Vec::with_capacity(self.subresources.len());
+        for sub in &self.subresources {
+            let extent = self.extent(sub.level);
+            let row_size = self.row_size(sub.level);
+            let row_pitch = align(row_size, pitch_alignment);
+            let offset = align(data.len(), offset_alignment);
+            data.resize(offset, 0);
+            for row in 0 .. self.rows(sub.level) {
+                let start = row * sub.row_pitch;
+                data.extend_from_slice(&sub.data[start .. start + row_size]);
+                data.resize(offset + (row + 1) * row_pitch, 0);
+            }
+            regions.push(BufferImageCopy {
+                buffer_offset: offset as u64,
+                buffer_row_pitch: row_pitch as u32,
+                buffer_slice_pitch: (row_pitch * self.format.0.get_num_rows(extent.height)) as u32,
+                image_aspect: image::ASPECT_COLOR,
+                image_subresource: (sub.level, sub.layer .. sub.layer + 1),
+                image_offset: Offset { x: 0, y: 0, z: 0 },
+                image_extent: extent,
+            });
+        }
+
+        Texture {
+            kind: self.kind,
+            levels: self.levels,
+            format: self.format,
+            data,
+            regions,
+        }
+    }
+}
+
+/// Kind of an image of the given size, with `layers` array elements for array images.
+///
+/// Images with a depth of more than one are 3D images, images marked as one
+/// dimensional need a height of one.
+fn image_kind(
+    (width, height, depth): (u32, u32, u32),
+    layers: Option<u32>,
+    cube: bool,
+    one_dimensional: bool,
+) -> Result<image::Kind, Error> {
+    let max = image::Size::max_value() as u32;
+    if width == 0 || width > max || height > max || depth > max || layers.map_or(false, |n| n == 0 || n > max) {
+        return Err(Error::InvalidHeader("Invalid image dimensions"));
+    }
+    let (w, h, d) = (width as image::Size, height.max(1) as image::Size, depth.max(1) as image::Size);
+    let layers = layers.map(|n| n as image::Layer);
+
+    Ok(if cube {
+        if w != h || d > 1 {
+            return Err(Error::InvalidHeader("Cube faces need to be square"));
+        }
+        match layers {
+            Some(n) => image::Kind::CubeArray(w, n),
+            None => image::Kind::Cube(w),
+        }
+    } else if d > 1 {
+        if layers.is_some() {
+            return Err(Error::Unsupported("Arrays of 3D images"));
+        }
+        image::Kind::D3(w, h, d)
+    } else if one_dimensional {
+        if h > 1 {
+            return Err(Error::InvalidHeader("1D images can't have a height"));
+        }
+        match layers {
+            Some(n) => image::Kind::D1Array(w, n),
+            None => image::Kind::D1(w),
+        }
+    } else {
+        match layers {
+            Some(n) => image::Kind::D2Array(w, h, n, image::AaMode::Single),
+            None => image::Kind::D2(w, h, image::AaMode::Single),
+        }
+    })
+}
+
+/// Check the number of mip levels stored in a container, where 0 stands for a single level.
+fn num_levels(kind: image::Kind, levels: u32) -> Result<image::Level, Error> {
+    let levels = levels.max(1);
+    if levels > kind.get_num_levels() as u32 {
+        return Err(Error::InvalidHeader("Too many mip levels"));
+    }
+    Ok(levels as image::Level)
+}
+
+/// ASTC surface types, by increasing block size.
+const ASTC_SURFACES: [format::SurfaceType; 14] = {
+    use core::format::SurfaceType::*;
+    [
+        ASTC_4x4, ASTC_5x4, ASTC_5x5, ASTC_6x5, ASTC_6x6, ASTC_8x5, ASTC_8x6,
+        ASTC_8x8, ASTC_10x5, ASTC_10x6, ASTC_10x8, ASTC_10x10, ASTC_12x10, ASTC_12x12,
+    ]
+};
+
+fn align(offset: usize, alignment: usize) -> usize {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+/// Reader of the fields of a container.
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Reader {
+            data,
+            offset: 0,
+            big_endian: false,
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.offset
+    }
+
+    fn seek(&mut self, offset: usize) -> Result<(), Error> {
+        if offset > self.data.len() {
+            return Err(Error::Truncated);
+        }
+        self.offset = offset;
+        Ok(())
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let end = self.offset.checked_add(count).ok_or(Error::Truncated)?;
+        let bytes = self.data.get(self.offset .. end).ok_or(Error::Truncated)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        let bytes = self.bytes(4)?;
+        let value = (0 .. 4).fold(0, |value, i| value | (bytes[i] as u32) << (8 * i));
+        Ok(if self.big_endian { value.swap_bytes() } else { value })
+    }
+
+    fn u64(&mut self) -> Result<u64, Error> {
+        let low = self.u32()? as u64;
+        let high = self.u32()? as u64;
+        Ok(if self.big_endian { low << 32 | high } else { high << 32 | low })
+    }
+}
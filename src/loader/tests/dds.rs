@@ -0,0 +1,104 @@
+extern crate gfx_core as core;
+extern crate gfx_loader as loader;
+
+use core::format::{ChannelType, Format, SurfaceType};
+use core::image::{AaMode, Kind};
+use loader::{Alignment, Error};
+
+fn four_cc(code: &[u8; 4]) -> u32 {
+    code.iter().rev().fold(0, |value, &c| value << 8 | c as u32)
+}
+
+fn header(width: u32, height: u32, levels: u32, pixel_format: [u32; 8], caps2: u32) -> Vec<u32> {
+    let mut header = vec![124, 0x1007, height, width, 0, 0, levels];
+    header.extend(&[0; 11]);
+    header.extend(&pixel_format);
+    header.extend(&[0x1000, caps2, 0, 0, 0]);
+    header
+}
+
+fn file(header: &[u32], data: &[u8]) -> Vec<u8> {
+    let mut file = b"DDS ".to_vec();
+    for value in header {
+        file.extend((0 .. 4).map(|i| (value >> (8 * i)) as u8));
+    }
+    file.extend(data);
+    file
+}
+
+const RGBA8: [u32; 8] = [32, 0x41, 0, 32, 0xFF, 0xFF00, 0xFF_0000, 0xFF00_0000];
+
+#[test]
+fn legacy_mipmaps() {
+    let texels = (0 .. 20).collect::<Vec<u8>>();
+    let data = file(&header(2, 2, 2, RGBA8, 0), &texels);
+    let alignment = Alignment { offset: 16, row_pitch: 16 };
+    let texture = loader::load(&data, alignment).unwrap();
+
+    assert_eq!(texture.kind, Kind::D2(2, 2, AaMode::Single));
+    assert_eq!(texture.levels, 2);
+    assert_eq!(texture.format, Format(SurfaceType::R8_G8_B8_A8, ChannelType::Unorm));
+    let layouts = texture.regions
+        .iter()
+        .map(|r| (r.image_subresource.clone(), r.buffer_offset, r.buffer_row_pitch, r.image_extent.width))
+        .collect::<Vec<_>>();
+    assert_eq!(layouts, vec![((0, 0 .. 1), 0, 16, 2), ((1, 0 .. 1), 32, 16, 1)]);
+    // Rows are padded to the pitch alignment.
+    assert_eq!(&texture.data[.. 8], &texels[.. 8]);
+    assert_eq!(&texture.data[16 .. 24], &texels[8 .. 16]);
+    assert_eq!(&texture.data[32 .. 36], &texels[16 ..]);
+    assert_eq!(texture.data.len(), 48);
+}
+
+#[test]
+fn dx10_cube_array() {
+    let mut header = header(1, 1, 1, [32, 0x4, four_cc(b"DX10"), 0, 0, 0, 0, 0], 0);
+    // R8_UNORM, 2D texture, cube, two elements
+    header.extend(&[61, 3, 0x4, 2, 0]);
+    let texels = (0 .. 12).collect::<Vec<u8>>();
+    let texture = loader::load(&file(&header, &texels), Alignment::default()).unwrap();
+
+    assert_eq!(texture.kind, Kind::CubeArray(1, 2));
+    assert_eq!(texture.format, Format(SurfaceType::R8, ChannelType::Unorm));
+    assert_eq!(texture.data, texels);
+    let layers = texture.regions
+        .iter()
+        .map(|r| (r.image_subresource.1.start, r.buffer_offset))
+        .collect::<Vec<_>>();
+    assert_eq!(layers, (0 .. 12).map(|i| (i, i as u64)).collect::<Vec<_>>());
+}
+
+#[test]
+fn dxt1_blocks() {
+    let dxt1 = [32, 0x4, four_cc(b"DXT1"), 0, 0, 0, 0, 0];
+    // 2x2 blocks for the first level, a single one for the second.
+    let blocks = (0 .. 40).collect::<Vec<u8>>();
+    let alignment = Alignment { offset: 4, row_pitch: 16 };
+    let texture = loader::load(&file(&header(8, 6, 2, dxt1, 0), &blocks), alignment).unwrap();
+
+    assert_eq!(texture.format, Format(SurfaceType::BC1_R8_G8_B8_A8, ChannelType::Unorm));
+    let layouts = texture.regions
+        .iter()
+        .map(|r| (r.buffer_offset, r.buffer_row_pitch, r.buffer_slice_pitch,
+                  r.image_extent.width, r.image_extent.height))
+        .collect::<Vec<_>>();
+    assert_eq!(layouts, vec![(0, 16, 32, 8, 6), (32, 16, 16, 4, 3)]);
+    assert_eq!(&texture.data[.. 32], &blocks[.. 32]);
+    assert_eq!(&texture.data[32 .. 40], &blocks[32 ..]);
+}
+
+#[test]
+fn errors() {
+    let yuv = [32, 0x4, four_cc(b"UYVY"), 0, 0, 0, 0, 0];
+    assert_eq!(loader::load(&file(&header(4, 4, 1, yuv, 0), &[0; 32]), Alignment::default()).unwrap_err(),
+               Error::UnsupportedFormat(four_cc(b"UYVY")));
+    assert_eq!(loader::load(&file(&header(2, 2, 1, RGBA8, 0), &[0; 15]), Alignment::default()).unwrap_err(),
+               Error::Truncated);
+    // A cube map with a single face.
+    assert_eq!(loader::load(&file(&header(1, 1, 1, RGBA8, 0x600), &[0; 4]), Alignment::default()).unwrap_err(),
+               Error::Unsupported("Cube maps with missing faces"));
+    // Tall images are rejected without looping over the mip chain.
+    assert_eq!(loader::load(&file(&header(1, 50692, 1, RGBA8, 0), &[0; 4]), Alignment::default()).unwrap_err(),
+               Error::Truncated);
+    assert_eq!(loader::load(b"PNG", Alignment::default()).unwrap_err(), Error::UnknownContainer);
+}
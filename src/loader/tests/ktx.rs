@@ -0,0 +1,80 @@
+extern crate gfx_core as core;
+extern crate gfx_loader as loader;
+
+use core::format::{ChannelType, Format, SurfaceType};
+use core::image::{AaMode, Kind};
+use loader::Alignment;
+
+fn bytes(values: &[u32], big_endian: bool) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|&value| {
+            let value = if big_endian { value.swap_bytes() } else { value };
+            (0 .. 4).map(move |i| (value >> (8 * i)) as u8)
+        })
+        .collect()
+}
+
+#[test]
+fn ktx_row_padding() {
+    let mut file = b"\xABKTX 11\xBB\r\n\x1A\n".to_vec();
+    // R8, 3x2, one level, with 4 bytes of key-value data
+    file.extend(bytes(&[0x0403_0201, 0x1401, 1, 0x1903, 0x8229, 0x1903, 3, 2, 0, 0, 1, 1, 4, 0], false));
+    file.extend(bytes(&[8], false));
+    file.extend(&[1, 2, 3, 0, 4, 5, 6, 0]);
+    let texture = loader::load(&file, Alignment::default()).unwrap();
+
+    assert_eq!(texture.kind, Kind::D2(3, 2, AaMode::Single));
+    assert_eq!(texture.format, Format(SurfaceType::R8, ChannelType::Unorm));
+    assert_eq!(texture.data, vec![1, 2, 3, 4, 5, 6]);
+    assert_eq!(texture.regions[0].buffer_row_pitch, 3);
+}
+
+#[test]
+fn ktx_big_endian() {
+    let mut file = b"\xABKTX 11\xBB\r\n\x1A\n".to_vec();
+    // R16UI, 2x2 cube map with two levels
+    file.extend(bytes(&[0x0403_0201, 0x1403, 2, 0x8D94, 0x8234, 0x8D94, 2, 2, 0, 0, 6, 2, 0], true));
+    file.extend(bytes(&[8], true));
+    for face in 0 .. 6u8 {
+        file.extend(&[0, face, 1, face, 2, face, 3, face]);
+    }
+    file.extend(bytes(&[4], true));
+    for face in 0 .. 6u8 {
+        file.extend(&[4, face, 0, 0]);
+    }
+    let texture = loader::load(&file, Alignment::default()).unwrap();
+
+    assert_eq!(texture.kind, Kind::Cube(2));
+    assert_eq!(texture.levels, 2);
+    assert_eq!(texture.regions.len(), 12);
+    assert_eq!(&texture.data[.. 8], &[0, 0, 0, 1, 0, 2, 0, 3]);
+    let last = texture.regions.last().unwrap();
+    assert_eq!(last.image_subresource, (1, 5 .. 6));
+    assert_eq!(&texture.data[last.buffer_offset as usize ..], &[5, 4]);
+}
+
+#[test]
+fn ktx2_array_levels() {
+    let mut file = b"\xABKTX 20\xBB\r\n\x1A\n".to_vec();
+    // R8G8_UNORM, 2x2 array of two layers with two levels
+    file.extend(bytes(&[16, 1, 2, 2, 0, 2, 1, 2, 0], false));
+    // Section index, without any descriptors or key-value data
+    file.extend(bytes(&[0; 8], false));
+    // Level index
+    file.extend(bytes(&[136, 0, 16, 0, 16, 0, 128, 0, 4, 0, 4, 0], false));
+    file.extend(&[20, 21, 30, 31, 0, 0, 0, 0]);
+    file.extend((0 .. 16).collect::<Vec<u8>>());
+    let texture = loader::load(&file, Alignment { offset: 4, row_pitch: 8 }).unwrap();
+
+    assert_eq!(texture.kind, Kind::D2Array(2, 2, 2, AaMode::Single));
+    assert_eq!(texture.format, Format(SurfaceType::R8_G8, ChannelType::Unorm));
+    let layouts = texture.regions
+        .iter()
+        .map(|r| (r.image_subresource.clone(), r.buffer_offset))
+        .collect::<Vec<_>>();
+    assert_eq!(layouts, vec![((0, 0 .. 1), 0), ((0, 1 .. 2), 16), ((1, 0 .. 1), 32), ((1, 1 .. 2), 40)]);
+    assert_eq!(&texture.data[.. 4], &[0, 1, 2, 3]);
+    assert_eq!(&texture.data[8 .. 12], &[4, 5, 6, 7]);
+    assert_eq!(&texture.data[32 ..], &[20, 21, 0, 0, 0, 0, 0, 0, 30, 31, 0, 0, 0, 0, 0, 0]);
+}
@@ -1,4 +1,4 @@
-use core::memory;
+use core;
 
 /// An information block that is immutable and associated to each buffer.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
@@ -6,10 +6,8 @@ use core::memory;
 pub struct Info {
     /// Role
     pub role: Role,
-    /// Usage
-    pub usage: memory::Usage,
-    /// Bind flags
-    pub bind: memory::Bind,
+    /// Usage flags
+    pub usage: core::buffer::Usage,
     /// Size in bytes
     pub size: usize,
     // /// Stride of a single element, in bytes. Only used for structured buffers
@@ -32,3 +30,15 @@ pub enum Role {
     /// Staging buffer
     Staging,
 }
+
+impl Role {
+    /// Access of the buffer by the commands using it in this role.
+    pub(crate) fn access(&self) -> core::buffer::Access {
+        match *self {
+            Role::Vertex => core::buffer::VERTEX_BUFFER_READ,
+            Role::Index => core::buffer::INDEX_BUFFER_READ,
+            Role::Constant => core::buffer::CONSTANT_BUFFER_READ,
+            Role::Staging => core::buffer::TRANSFER_READ,
+        }
+    }
+}
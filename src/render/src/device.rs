@@ -1,49 +1,56 @@
-// use std::error::Error;
-// use std::fmt;
-// use core::{self, buffer, format, state};
-use core::{Backend, Primitive, HeapType};
-// use core::pso::{CreationError, Descriptor};
-// use core::memory::{self, Bind, Pod};
-// use pso;
-// use shade::ProgramError;
-use handle::GarbageSender;
-
-/*
+use std::{cmp, fmt, mem};
+use std::ops::Range;
+use std::error::Error;
+use std::sync::Arc;
+use core::{self, mapping, memory, Backend, Device as CoreDevice, HeapType, Primitive};
+use core::command::{BufferCopy, BufferImageCopy, CommandBuffer, Offset};
+use core::device::Extent;
+use core::format::{Format, Formatted, SurfaceTyped};
+use core::image::ImageLayout;
+use core::memory::{Allocation, AllocationError, Allocator, Barrier, HeapProperties, Pod, Strategy};
+use core::pass;
+use core::pso::{self, reflect};
+use core::pso::reflect::ReflectError;
+use core::device::TargetViewError;
+use core::format::{RenderFormat, TextureFormat};
+use pso::{Descriptor, InitError, PipelineInit, PipelineState, ProgramInfo};
+use {buffer, handle, image};
+use handle::{GarbageSender, Typed};
+use slice::{IndexBuffer, IntoIndexBuffer, Slice};
+
 /// Error creating a PipelineState
-#[derive(Clone, PartialEq, Debug)]
-pub enum PipelineStateError<S> {
-    /// Shader program failed to link.
-    Program(ProgramError),
+#[derive(Clone, Debug, PartialEq)]
+pub enum PipelineStateError {
+    /// Shader code failed to be reflected.
+    Reflect(ReflectError),
+    /// No shader code declares a used entry point.
+    MissingEntryPoint(pso::EntryPoint),
+    /// Unable to create the shader library.
+    Shader(pso::CreateShaderError),
     /// Unable to create PSO descriptor due to mismatched formats.
-    DescriptorInit(pso::InitError<S>),
+    DescriptorInit(InitError),
     /// Device failed to create the handle give the descriptor.
-    DeviceCreate(CreationError),
+    DeviceCreate(pso::CreationError),
 }
 
-impl<'a> From<PipelineStateError<&'a str>> for PipelineStateError<String> {
-    fn from(pse: PipelineStateError<&'a str>) -> PipelineStateError<String> {
-        match pse {
-            PipelineStateError::Program(e) => PipelineStateError::Program(e),
-            PipelineStateError::DescriptorInit(e) => PipelineStateError::DescriptorInit(e.into()),
-            PipelineStateError::DeviceCreate(e) => PipelineStateError::DeviceCreate(e),
-        }
-    }
-}
-
-impl<S: fmt::Debug + fmt::Display> fmt::Display for PipelineStateError<S> {
+impl fmt::Display for PipelineStateError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            PipelineStateError::Program(ref e) => write!(f, "{}: {}", self.description(), e),
+            PipelineStateError::Reflect(ref e) => write!(f, "{}: {}", self.description(), e),
+            PipelineStateError::MissingEntryPoint(name) => write!(f, "{}: {}", self.description(), name),
+            PipelineStateError::Shader(ref e) => write!(f, "{}: {}", self.description(), e),
             PipelineStateError::DescriptorInit(ref e) => write!(f, "{}: {}", self.description(), e),
             PipelineStateError::DeviceCreate(ref e) => write!(f, "{}: {}", self.description(), e),
         }
     }
 }
 
-impl<S: fmt::Debug + fmt::Display> Error for PipelineStateError<S> {
+impl Error for PipelineStateError {
     fn description(&self) -> &str {
         match *self {
-            PipelineStateError::Program(_) => "Shader program failed to link",
+            PipelineStateError::Reflect(_) => "Shader code failed to be reflected",
+            PipelineStateError::MissingEntryPoint(_) => "No shader code declares the entry point",
+            PipelineStateError::Shader(_) => "Unable to create the shader library",
             PipelineStateError::DescriptorInit(_) =>
                 "Unable to create PSO descriptor due to mismatched formats",
             PipelineStateError::DeviceCreate(_) => "Device failed to create the handle give the descriptor",
@@ -52,38 +59,220 @@ impl<S: fmt::Debug + fmt::Display> Error for PipelineStateError<S> {
 
     fn cause(&self) -> Option<&Error> {
         match *self {
-            PipelineStateError::Program(ref program_error) => Some(program_error),
-            PipelineStateError::DescriptorInit(ref init_error) => Some(init_error),
-            PipelineStateError::DeviceCreate(ref creation_error) => Some(creation_error),
+            PipelineStateError::Reflect(ref e) => Some(e),
+            PipelineStateError::MissingEntryPoint(_) => None,
+            PipelineStateError::Shader(ref e) => Some(e),
+            PipelineStateError::DescriptorInit(ref e) => Some(e),
+            PipelineStateError::DeviceCreate(ref e) => Some(e),
         }
     }
 }
 
-impl<S> From<ProgramError> for PipelineStateError<S> {
-    fn from(e: ProgramError) -> Self {
-        PipelineStateError::Program(e)
+impl From<ReflectError> for PipelineStateError {
+    fn from(e: ReflectError) -> Self {
+        PipelineStateError::Reflect(e)
+    }
+}
+
+impl From<pso::CreateShaderError> for PipelineStateError {
+    fn from(e: pso::CreateShaderError) -> Self {
+        PipelineStateError::Shader(e)
     }
 }
 
-impl<S> From<pso::InitError<S>> for PipelineStateError<S> {
-    fn from(e: pso::InitError<S>) -> Self {
+impl From<InitError> for PipelineStateError {
+    fn from(e: InitError) -> Self {
         PipelineStateError::DescriptorInit(e)
     }
 }
 
-impl<S> From<CreationError> for PipelineStateError<S> {
-    fn from(e: CreationError) -> Self {
+impl From<pso::CreationError> for PipelineStateError {
+    fn from(e: pso::CreationError) -> Self {
         PipelineStateError::DeviceCreate(e)
     }
 }
-*/
 
-#[derive(Clone)]
+/// Reflect the interface of the entry points of a graphics pipeline.
+fn reflect_program(
+    shaders: &[(pso::EntryPoint, &[u8])],
+    entries: &pso::GraphicsShaderSet,
+) -> Result<ProgramInfo, PipelineStateError> {
+    let mut reflected = Vec::new();
+    for &(_, code) in shaders {
+        reflected.extend(reflect::reflect(code)?);
+    }
+    let stages = [
+        (Some(entries.vertex_shader), pso::Stage::Vertex),
+        (entries.hull_shader, pso::Stage::Hull),
+        (entries.domain_shader, pso::Stage::Domain),
+        (entries.geometry_shader, pso::Stage::Geometry),
+        (entries.pixel_shader, pso::Stage::Pixel),
+    ];
+    let mut entry_points = Vec::new();
+    for &(name, stage) in &stages {
+        if let Some(name) = name {
+            match reflected.iter().find(|e| e.name == name && e.stage == stage) {
+                Some(entry_point) => entry_points.push(entry_point),
+                None => return Err(PipelineStateError::MissingEntryPoint(name)),
+            }
+        }
+    }
+
+    let mut sets = reflect::merge_descriptor_sets(&entry_points)?;
+    if let Some(set) = sets.iter().skip(1).position(|bindings| !bindings.is_empty()) {
+        return Err(InitError::DescriptorSet(set + 1).into());
+    }
+    Ok(ProgramInfo {
+        vertex_attributes: entry_points[0].inputs.clone(),
+        descriptors: if sets.is_empty() { Vec::new() } else { sets.swap_remove(0) },
+    })
+}
+
+/// Error creating a resource.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CreationError {
+    /// Failed to allocate memory or to create the resource.
+    Allocation(AllocationError),
+    /// Failed to write the initial data to a staging buffer.
+    Mapping(mapping::Error),
+}
+
+impl fmt::Display for CreationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CreationError::Allocation(ref e) => write!(f, "{}: {}", self.description(), e),
+            CreationError::Mapping(ref e) => write!(f, "{}: {}", self.description(), e),
+        }
+    }
+}
+
+impl Error for CreationError {
+    fn description(&self) -> &str {
+        match *self {
+            CreationError::Allocation(_) => "Failed to allocate the resource",
+            CreationError::Mapping(_) => "Failed to write the initial data",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            CreationError::Allocation(ref e) => Some(e),
+            CreationError::Mapping(ref e) => Some(e),
+        }
+    }
+}
+
+impl From<AllocationError> for CreationError {
+    fn from(e: AllocationError) -> Self {
+        CreationError::Allocation(e)
+    }
+}
+
+impl From<mapping::Error> for CreationError {
+    fn from(e: mapping::Error) -> Self {
+        CreationError::Mapping(e)
+    }
+}
+
+/// Copy from a staging buffer, recorded on the next submission.
+pub(crate) enum Upload<B: Backend> {
+    Buffer {
+        staging: handle::raw::Buffer<B>,
+        dst: handle::raw::Buffer<B>,
+        access: core::buffer::Access,
+    },
+    Image {
+        staging: handle::raw::Buffer<B>,
+        dst: handle::raw::Image<B>,
+        regions: Vec<BufferImageCopy>,
+        range: core::image::SubresourceRange,
+    },
+    /// Transition of a new image into the `General` layout, without any copy.
+    Layout {
+        dst: handle::raw::Image<B>,
+        range: core::image::SubresourceRange,
+    },
+}
+
+/// Access of images in the `General` layout, which all images created through the device use.
+pub(crate) fn general_access() -> core::image::Access {
+    core::image::COLOR_ATTACHMENT_READ | core::image::COLOR_ATTACHMENT_WRITE |
+        core::image::TRANSFER_READ | core::image::TRANSFER_WRITE | core::image::SHADER_READ
+}
+
+/// Record the copies of `uploads`, making the data available to all later commands.
+pub(crate) fn record_uploads<B: Backend>(cmd_buffer: &mut CommandBuffer<B, core::Transfer>, uploads: &[Upload<B>]) {
+    let transfer_dst = (core::image::TRANSFER_WRITE, ImageLayout::TransferDstOptimal);
+    let before = uploads
+        .iter()
+        .filter_map(|upload| match *upload {
+            Upload::Image { ref dst, ref range, .. } => Some(Barrier::Image {
+                states: (core::image::Access::empty(), ImageLayout::Undefined) .. transfer_dst,
+                target: dst.resource(),
+                range: range.clone(),
+            }),
+            Upload::Buffer { .. } | Upload::Layout { .. } => None,
+        })
+        .collect::<Vec<_>>();
+    if !before.is_empty() {
+        cmd_buffer.pipeline_barrier(pso::TOP_OF_PIPE .. pso::TRANSFER, &before);
+    }
+
+    for upload in uploads {
+        match *upload {
+            Upload::Buffer { ref staging, ref dst, .. } => {
+                let region = BufferCopy { src: 0, dst: 0, size: dst.info().size as u64 };
+                cmd_buffer.copy_buffer(staging.resource(), dst.resource(), &[region]);
+            }
+            Upload::Image { ref staging, ref dst, ref regions, .. } => {
+                cmd_buffer.copy_buffer_to_image(
+                    staging.resource(),
+                    dst.resource(),
+                    ImageLayout::TransferDstOptimal,
+                    regions,
+                );
+            }
+            Upload::Layout { .. } => (),
+        }
+    }
+
+    let after = uploads
+        .iter()
+        .map(|upload| match *upload {
+            Upload::Buffer { ref dst, access, .. } => Barrier::Buffer {
+                states: core::buffer::TRANSFER_WRITE .. access,
+                target: dst.resource(),
+                range: 0 .. dst.info().size as u64,
+            },
+            Upload::Image { ref dst, ref range, .. } => Barrier::Image {
+                states: transfer_dst .. (general_access(), ImageLayout::General),
+                target: dst.resource(),
+                range: range.clone(),
+            },
+            Upload::Layout { ref dst, ref range } => Barrier::Image {
+                states: (core::image::Access::empty(), ImageLayout::Undefined) ..
+                    (general_access(), ImageLayout::General),
+                target: dst.resource(),
+                range: range.clone(),
+            },
+        })
+        .collect::<Vec<_>>();
+    let consumers = pso::VERTEX_INPUT | pso::VERTEX_SHADER | pso::HULL_SHADER | pso::DOMAIN_SHADER |
+        pso::GEOMETRY_SHADER | pso::PIXEL_SHADER | pso::COMPUTE_SHADER | pso::TRANSFER |
+        pso::COLOR_ATTACHMENT_OUTPUT;
+    cmd_buffer.pipeline_barrier(pso::TRANSFER .. consumers, &after);
+}
+
+/// Size of the heaps created by the device allocator.
+const HEAP_SIZE: u64 = 64 << 20;
+
 pub struct Device<B: Backend> {
     raw: B::Device,
     // TODO: could be shared instead of cloned
     heap_types: Vec<HeapType>,
     memory_heaps: Vec<u64>,
+    allocator: Allocator<B>,
+    uploads: Vec<Upload<B>>,
     garbage: GarbageSender<B>,
 }
 
@@ -94,7 +283,8 @@ impl<B: Backend> Device<B> {
         memory_heaps: Vec<u64>,
         garbage: GarbageSender<B>) -> Self
     {
-        Device { raw, heap_types, memory_heaps, garbage }
+        let allocator = Allocator::new(&heap_types, Strategy::FreeList, HEAP_SIZE);
+        Device { raw, heap_types, memory_heaps, allocator, uploads: Vec::new(), garbage }
     }
 
     pub fn ref_raw(&self) -> &B::Device {
@@ -105,23 +295,94 @@ impl<B: Backend> Device<B> {
         &mut self.raw
     }
 
-/*
+    /// Heap types exposed by the adapter.
+    pub fn heap_types(&self) -> &[HeapType] {
+        &self.heap_types
+    }
+
+    /// Sizes of the memory heaps exposed by the adapter.
+    pub fn memory_heaps(&self) -> &[u64] {
+        &self.memory_heaps
+    }
+
+    /// Memory usage of the resources created through this device.
+    pub fn memory_stats(&self) -> memory::Stats {
+        self.allocator.stats()
+    }
+
+    pub(crate) fn garbage(&self) -> &GarbageSender<B> {
+        &self.garbage
+    }
+
+    pub(crate) fn free_memory(&mut self, allocation: Allocation) {
+        self.allocator.free(allocation);
+    }
+
+    pub(crate) fn take_uploads(&mut self) -> Vec<Upload<B>> {
+        mem::replace(&mut self.uploads, Vec::new())
+    }
+
+    pub(crate) fn destroy_allocator(&mut self) {
+        let allocator = mem::replace(&mut self.allocator, Allocator::new(&[], Strategy::FreeList, HEAP_SIZE));
+        allocator.destroy(&mut self.raw);
+    }
+
+    /// Create a buffer for `num` elements of type `T` in memory with the given properties.
+    pub fn create_buffer<T>(
+        &mut self,
+        num: usize,
+        role: buffer::Role,
+        usage: core::buffer::Usage,
+        properties: HeapProperties,
+    ) -> Result<handle::Buffer<B, T>, CreationError> {
+        let size = num * mem::size_of::<T>();
+        let stride = mem::size_of::<T>() as u64;
+        let (raw, memory) = self.allocator
+            .create_buffer(&mut self.raw, size as u64, stride, usage, properties)?;
+        let info = buffer::Info { role, usage, size };
+        Ok(Typed::new(Arc::new(handle::inner::Buffer::with_memory(raw, info, memory, self))))
+    }
+
+    /// Create a device local buffer initialized with `data`.
+    ///
+    /// The data is written into a staging buffer and copied on the next submission
+    /// made through `Gpu::submit`, before any of the submitted commands are executed.
+    pub fn create_buffer_immutable<T: Pod>(
+        &mut self,
+        data: &[T],
+        role: buffer::Role,
+        usage: core::buffer::Usage,
+    ) -> Result<handle::Buffer<B, T>, CreationError> {
+        let bytes: &[u8] = memory::cast_slice(data);
+        let staging = self.create_staging(bytes.len())?;
+        {
+            let mut writer = self.raw.write_mapping::<u8>(staging.resource(), 0, bytes.len() as u64)?;
+            writer.copy_from_slice(bytes);
+        }
+
+        let buffer = self.create_buffer::<T>(
+            data.len(),
+            role,
+            usage | core::buffer::TRANSFER_DST,
+            memory::DEVICE_LOCAL,
+        )?;
+        self.uploads.push(Upload::Buffer {
+            staging,
+            dst: (*buffer).clone(),
+            access: role.access(),
+        });
+        Ok(buffer)
+    }
+
     /// Creates an immutable vertex buffer from the supplied vertices.
     /// A `Slice` will have to manually be constructed.
-    fn create_vertex_buffer<T>(&mut self, vertices: &[T])
-                               -> handle::Buffer<B, T>
-        where T: Pod + pso::buffer::Structure<format::Format>
-    {
-        //debug_assert!(nv <= self.get_capabilities().max_vertex_count);
-        self.create_buffer_immutable(vertices, buffer::Role::Vertex, Bind::empty())
+    pub fn create_vertex_buffer<T: Pod>(&mut self, vertices: &[T]) -> handle::Buffer<B, T> {
+        self.create_buffer_immutable(vertices, buffer::Role::Vertex, core::buffer::VERTEX)
             .unwrap()
     }
 
     /// Creates an immutable index buffer from the supplied vertices.
-    ///
-    /// The paramater `indices` is typically a &[u16] or &[u32] slice.
-    fn create_index_buffer<T>(&mut self, indices: T)
-                              -> IndexBuffer<B>
+    pub fn create_index_buffer<T>(&mut self, indices: T) -> IndexBuffer<B>
         where T: IntoIndexBuffer<B>
     {
         indices.into_index_buffer(self)
@@ -129,9 +390,9 @@ impl<B: Backend> Device<B> {
 
     /// Creates an immutable vertex buffer from the supplied vertices,
     /// together with a `Slice` from the supplied indices.
-    fn create_vertex_buffer_with_slice<I, V>(&mut self, vertices: &[V], indices: I)
-                                             -> (handle::Buffer<B, V>, Slice<B>)
-        where V: Pod + pso::buffer::Structure<format::Format>,
+    pub fn create_vertex_buffer_with_slice<I, V>(&mut self, vertices: &[V], indices: I)
+                                                 -> (handle::Buffer<B, V>, Slice<B>)
+        where V: Pod,
               I: IntoIndexBuffer<B>
     {
         let vertex_buffer = self.create_vertex_buffer(vertices);
@@ -151,150 +412,233 @@ impl<B: Backend> Device<B> {
         })
     }
 
-    /// Creates a constant buffer for `num` identical elements of type `T`.
-    fn create_constant_buffer<T>(&mut self, num: usize) -> handle::Buffer<B, T>
+    /// Creates a constant buffer for `num` elements of type `T`.
+    ///
+    /// The buffer is placed in CPU visible memory, to be updated with `write_mapping`.
+    pub fn create_constant_buffer<T>(&mut self, num: usize) -> handle::Buffer<B, T>
         where T: Copy
     {
         self.create_buffer(num,
                            buffer::Role::Constant,
-                           memory::Usage::Dynamic,
-                           Bind::empty()).unwrap()
+                           core::buffer::CONSTANT | core::buffer::TRANSFER_DST,
+                           memory::CPU_VISIBLE | memory::COHERENT).unwrap()
     }
 
     /// Creates an upload buffer for `num` elements of type `T`.
-    fn create_upload_buffer<T>(&mut self, num: usize)
-                               -> Result<handle::Buffer<B, T>, buffer::CreationError>
+    pub fn create_upload_buffer<T>(&mut self, num: usize)
+                                   -> Result<handle::Buffer<B, T>, CreationError>
     {
         self.create_buffer(num,
                            buffer::Role::Staging,
-                           memory::Usage::Upload,
-                           memory::TRANSFER_SRC)
+                           core::buffer::TRANSFER_SRC,
+                           memory::CPU_VISIBLE | memory::COHERENT)
     }
 
-    /// Creates a download buffer for `num` elements of type `T`.
-    fn create_download_buffer<T>(&mut self, num: usize)
-                                 -> Result<handle::Buffer<B, T>, buffer::CreationError>
-    {
-        self.create_buffer(num,
-                           buffer::Role::Staging,
-                           memory::Usage::Download,
-                           memory::TRANSFER_DST)
+    /// Create a device local image with the format `F`.
+    ///
+    /// Like all images created through the device, the image is used in the `General` layout.
+    /// It's transitioned into this layout on the next submission made through `Gpu::submit`.
+    pub fn create_image<F: Formatted>(
+        &mut self,
+        kind: image::Kind,
+        levels: image::Level,
+        usage: image::Usage,
+    ) -> Result<handle::Image<B, F>, CreationError> {
+        self.create_image_raw(kind, levels, F::get_format(), usage).map(Typed::new)
     }
 
-    /// Creates a `ShaderSet` from the supplied vertex and pixel shader source code.
-    fn create_shader_set(&mut self, vs_code: &[u8], ps_code: &[u8])
-                         -> Result<ShaderSet<B>, ProgramError> {
-        let vs = match self.create_shader_vertex(vs_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Vertex(e)),
-        };
-        let ps = match self.create_shader_pixel(ps_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Pixel(e)),
-        };
-        Ok(ShaderSet::Simple(vs, ps))
+    /// Create a device local image with a format only known at runtime, see `create_image`.
+    pub fn create_image_raw(
+        &mut self,
+        kind: image::Kind,
+        levels: image::Level,
+        format: Format,
+        usage: image::Usage,
+    ) -> Result<handle::raw::Image<B>, CreationError> {
+        let image = self.allocate_image(kind, levels, format, usage)?;
+        self.uploads.push(Upload::Layout {
+            dst: image.clone(),
+            range: (0 .. levels, 0 .. image::num_layers(kind)),
+        });
+        Ok(image)
     }
 
-    /// Creates a `ShaderSet` from the supplied vertex, geometry, and pixel
-    /// shader source code. Mainly used for testing.
-    fn create_shader_set_geometry(&mut self, vs_code: &[u8], gs_code: &[u8], ps_code: &[u8])
-                         -> Result<ShaderSet<B>, ProgramError> {
-        let vs = match self.create_shader_vertex(vs_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Vertex(e)),
-        };
-        let gs = match self.create_shader_geometry(gs_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Geometry(e)),
-        };
-        let ps = match self.create_shader_pixel(ps_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Pixel(e)),
-        };
-        Ok(ShaderSet::Geometry(vs, gs, ps))
+    fn allocate_image(
+        &mut self,
+        kind: image::Kind,
+        levels: image::Level,
+        format: Format,
+        usage: image::Usage,
+    ) -> Result<handle::raw::Image<B>, CreationError> {
+        let (raw, memory) = self.allocator
+            .create_image(&mut self.raw, kind, levels, format, usage, memory::DEVICE_LOCAL)?;
+        let info = image::Info { kind, levels, format: format.0, usage };
+        Ok(Arc::new(handle::inner::Image::with_memory(raw, info, memory, self)))
     }
 
-    /// Creates a `ShaderSet` from the supplied vertex, hull, domain, and pixel
-    /// shader source code. Mainly used for testing.
-    fn create_shader_set_tessellation(&mut self, vs_code: &[u8], hs_code: &[u8], ds_code: &[u8], ps_code: &[u8])
-                         -> Result<ShaderSet<B>, ProgramError> {
-        let vs = match self.create_shader_vertex(vs_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Vertex(e)),
-        };
-
-        let hs = match self.create_shader_hull(hs_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Hull(e)),
+    /// Create a sampled image with a single level, initialized with `data`.
+    ///
+    /// `data` contains the tightly packed texels of all layers. Like buffers,
+    /// it's uploaded on the next submission made through `Gpu::submit`.
+    pub fn create_image_immutable<F: Formatted>(
+        &mut self,
+        kind: image::Kind,
+        data: &[<F::Surface as SurfaceTyped>::DataType],
+    ) -> Result<handle::Image<B, F>, CreationError> {
+        let bytes: &[u8] = memory::cast_slice(data);
+        let (width, height, _, _) = kind.get_dimensions();
+        let height = cmp::max(height, 1) as usize;
+        let depth = match kind {
+            image::Kind::D3(_, _, depth) => depth as usize,
+            _ => 1,
         };
+        let layers = image::num_layers(kind);
+        let rows = height * depth * layers as usize;
+        let row_size = bytes.len() / rows;
+        assert_eq!(row_size * rows, bytes.len(), "Texel data doesn't match the image size");
+
+        // Rows of the staging buffer need to satisfy the copy pitch alignment.
+        let alignment = cmp::max(self.raw.get_limits().min_buffer_copy_pitch_alignment, 1);
+        let row_pitch = (row_size + alignment - 1) / alignment * alignment;
+        let staging = self.create_staging(row_pitch * rows)?;
+        {
+            let mut writer = self.raw.write_mapping::<u8>(staging.resource(), 0, (row_pitch * rows) as u64)?;
+            for (dst, src) in writer.chunks_mut(row_pitch).zip(bytes.chunks(row_size)) {
+                dst[.. row_size].copy_from_slice(src);
+            }
+        }
 
-        let ds = match self.create_shader_domain(ds_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Domain(e)),
-        };
+        let usage = core::image::SAMPLED | core::image::TRANSFER_DST;
+        let image = self.allocate_image(kind, 1, F::get_format(), usage).map(Typed::<_, F>::new)?;
+        let regions = vec![BufferImageCopy {
+            buffer_offset: 0,
+            buffer_row_pitch: row_pitch as u32,
+            buffer_slice_pitch: (row_pitch * height) as u32,
+            image_aspect: core::image::ASPECT_COLOR,
+            image_subresource: (0, 0 .. layers),
+            image_offset: Offset { x: 0, y: 0, z: 0 },
+            image_extent: Extent {
+                width: width as u32,
+                height: height as u32,
+                depth: depth as u32,
+            },
+        }];
+        self.uploads.push(Upload::Image {
+            staging,
+            dst: (*image).clone(),
+            regions,
+            range: (0 .. 1, 0 .. layers),
+        });
+        Ok(image)
+    }
 
-        let ps = match self.create_shader_pixel(ps_code) {
-            Ok(s) => s,
-            Err(e) => return Err(ProgramError::Pixel(e)),
-        };
-        Ok(ShaderSet::Tessellated(vs, hs, ds, ps))
+    /// Create a sampler.
+    pub fn create_sampler(&mut self, info: core::image::SamplerInfo) -> handle::raw::Sampler<B> {
+        let raw = self.raw.create_sampler(info);
+        Arc::new(handle::inner::Sampler::new(raw, (), self))
     }
 
-    /// Creates a basic shader `Program` from the supplied vertex and pixel shader source code.
-    fn link_program(&mut self, vs_code: &[u8], ps_code: &[u8])
-                    -> Result<handle::Program<B>, ProgramError> {
+    /// Create a linear sampler with clamping to border.
+    pub fn create_sampler_linear(&mut self) -> handle::raw::Sampler<B> {
+        self.create_sampler(core::image::SamplerInfo::new(
+            core::image::FilterMethod::Trilinear,
+            core::image::WrapMode::Clamp,
+        ))
+    }
 
-        let set = try!(self.create_shader_set(vs_code, ps_code));
-        self.create_program(&set).map_err(|e| ProgramError::Link(e))
+    /// Create a pool of `count` queries of the given type.
+    pub fn create_query_pool(&mut self, ty: core::query::QueryType, count: u32) -> handle::raw::QueryPool<B> {
+        let raw = self.raw.create_query_pool(ty, count);
+        Arc::new(handle::inner::QueryPool::new(raw, ty, self))
     }
 
-    /// Similar to `create_pipeline_from_program(..)`, but takes a `ShaderSet` as opposed to a
-    /// shader `Program`.
-    fn create_pipeline_state<I: pso::PipelineInit>(&mut self, shaders: &ShaderSet<B>,
-                             primitive: Primitive, rasterizer: state::Rasterizer, init: I)
-                             -> Result<pso::PipelineState<B, I::Meta>, PipelineStateError<String>>
-    {
-        let program = try!(self.create_program(shaders).map_err(|e| ProgramError::Link(e)));
-        self.create_pipeline_from_program(&program, primitive, rasterizer, init).map_err(|error| {
-            use self::PipelineStateError::*;
-            match error {
-                Program(e) => Program(e),
-                DescriptorInit(e) => DescriptorInit(e.into()),
-                DeviceCreate(e) => DeviceCreate(e),
-            }
-        })
+    fn create_staging(&mut self, size: usize) -> Result<handle::raw::Buffer<B>, CreationError> {
+        self.create_upload_buffer::<u8>(size).map(|buffer| (*buffer).clone())
     }
 
-    /// Creates a strongly typed `PipelineState` from its `Init` structure, a shader `Program`, a
-    /// primitive type and a `Rasterizer`.
-    fn create_pipeline_from_program<'a, I: pso::PipelineInit>(&mut self, program: &'a handle::Program<B>,
-                                    primitive: Primitive, rasterizer: state::Rasterizer, init: I)
-                                    -> Result<pso::PipelineState<B, I::Meta>, PipelineStateError<&'a str>>
+    /// Create a view of a byte range of a buffer as constant buffer.
+    ///
+    /// The start of the range has to be aligned to `min_constant_buffer_offset_alignment`.
+    pub fn view_buffer_as_constant(&mut self, buffer: &handle::raw::Buffer<B>, range: Range<u64>)
+                                   -> Result<handle::raw::ConstantBufferView<B>, TargetViewError>
     {
-        let mut descriptor = Descriptor::new(primitive, rasterizer);
-        let meta = try!(init.link_to(&mut descriptor, program.get_info()));
-        let raw = try!(self.create_pipeline_state_raw(program, &descriptor));
+        let raw = self.raw.view_buffer_as_constant(buffer.resource(), range)?;
+        Ok(Arc::new(handle::inner::ConstantBufferView::new(raw, buffer.clone(), self)))
+    }
 
-        Ok(pso::PipelineState::new(raw, primitive, meta))
+    /// Create a view of a mip level and range of layers of an image, to render into it.
+    pub fn view_image_as_render_target<F: RenderFormat>(
+        &mut self,
+        image: &handle::Image<B, F>,
+        level: image::Level,
+        layers: Range<image::Layer>,
+    ) -> Result<handle::RenderTargetView<B, F>, TargetViewError> {
+        let range = (level .. level + 1, layers);
+        let raw = self.raw.view_image_as_render_target(image.resource(), F::get_format(), range)?;
+        let info = ((**image).clone(), level);
+        Ok(Typed::new(Arc::new(handle::inner::RenderTargetView::new(raw, info, self))))
     }
 
-    /// Creates a strongly typed `PipelineState` from its `Init` structure. Automatically creates a
-    /// shader `Program` from a vertex and pixel shader source, as well as a `Rasterizer` capable
-    /// of rendering triangle faces without culling.
-    fn create_pipeline_simple<I: pso::PipelineInit>(&mut self, vs: &[u8], ps: &[u8], init: I)
-                              -> Result<pso::PipelineState<B, I::Meta>, PipelineStateError<String>>
+    /// Create a view of an image, to sample it in shaders.
+    pub fn view_image_as_shader_resource<F: TextureFormat>(&mut self, image: &handle::Image<B, F>)
+                                         -> Result<handle::ShaderResourceView<B, F::View>, TargetViewError>
     {
-        let set = try!(self.create_shader_set(vs, ps));
-        self.create_pipeline_state(&set, Primitive::TriangleList, state::Rasterizer::new_fill(),
-                                   init)
+        let raw = self.raw.view_image_as_shader_resource(image.resource(), F::get_format())?;
+        Ok(Typed::new(Arc::new(handle::inner::ShaderResourceView::new(raw, (**image).clone(), self))))
     }
 
-    /// Create a linear sampler with clamping to border.
-    fn create_sampler_linear(&mut self) -> handle::Sampler<B> {
-        self.create_sampler(texture::SamplerInfo::new(
-            texture::FilterMethod::Trilinear,
-            texture::WrapMode::Clamp,
-        ))
+    /// Creates a strongly typed `PipelineState` from its `Init` structure, the code of its
+    /// shaders, a primitive type and a `Rasterizer`.
+    ///
+    /// The shader interface is reflected from the SPIR-V code in `shaders`, which contains
+    /// the entry points of `entries`. The pipeline renders into a render pass loading and
+    /// storing its color attachments in the `General` layout.
+    pub fn create_pipeline_state<I: PipelineInit>(
+        &mut self,
+        shaders: &[(pso::EntryPoint, &[u8])],
+        entries: pso::GraphicsShaderSet,
+        primitive: Primitive,
+        rasterizer: pso::Rasterizer,
+        init: I,
+    ) -> Result<PipelineState<B, I::Meta>, PipelineStateError> {
+        let info = reflect_program(shaders, &entries)?;
+        let mut desc = Descriptor::new();
+        let meta = init.link_to(&mut desc, &info)?;
+
+        let set_layout = self.raw.create_descriptor_set_layout(&desc.descriptors);
+        let set_layout = Arc::new(handle::inner::DescriptorSetLayout::new(set_layout, (), self));
+        let layout = self.raw.create_pipeline_layout(&[set_layout.resource()]);
+        let layout = Arc::new(handle::inner::PipelineLayout::new(layout, (), self));
+
+        let attachments = desc.color_targets
+            .iter()
+            .map(|&(format, _)| pass::Attachment {
+                format,
+                ops: pass::AttachmentOps::new(pass::AttachmentLoadOp::Load, pass::AttachmentStoreOp::Store),
+                stencil_ops: pass::AttachmentOps::DONT_CARE,
+                layouts: ImageLayout::General .. ImageLayout::General,
+            })
+            .collect::<Vec<_>>();
+        let color_attachments = (0 .. attachments.len())
+            .map(|index| (index, ImageLayout::General))
+            .collect::<Vec<_>>();
+        let subpass = pass::SubpassDesc { color_attachments: &color_attachments };
+        let render_pass = self.raw.create_renderpass(&attachments, &[subpass], &[]);
+        let render_pass = Arc::new(handle::inner::RenderPass::new(render_pass, (), self));
+
+        let shader_lib = self.raw.create_shader_library(shaders)?;
+        let shader_lib = handle::inner::ShaderLib::new(shader_lib, (), self);
+
+        let mut pipeline_desc = pso::GraphicsPipelineDesc::new(primitive, rasterizer, entries);
+        pipeline_desc.vertex_buffers = desc.vertex_buffers;
+        pipeline_desc.attributes = desc.attributes;
+        pipeline_desc.blender.targets = desc.color_targets.iter().map(|&(_, info)| info).collect();
+        let subpass = pass::Subpass { index: 0, main_pass: render_pass.resource() };
+        let raw = self.raw
+            .create_graphics_pipelines(&[(shader_lib.resource(), layout.resource(), subpass, &pipeline_desc)], None)
+            .remove(0)?;
+        let raw = Arc::new(handle::inner::GraphicsPipeline::new(raw, (), self));
+
+        Ok(PipelineState::new(raw, layout, set_layout, render_pass, desc.descriptors, meta))
     }
-    */
 }
@@ -2,18 +2,32 @@
 
 #![deny(missing_docs)]
 
-use draw_state::target::{Depth, Stencil};
 use std::error::Error;
 use std::any::Any;
-use std::{fmt, mem};
-
-use core::{Backend, CommandQueue, GraphicsCommandPool, GraphicsQueue,
-           IndexType, SubmissionResult, VertexCount};
-use core::{self, command, format, handle, texture};
-use core::command::{GraphicsCommandBuffer, Submit};
-use core::memory::{self, cast_slice, Typed, Pod, Usage};
-use slice;
-use pso;
+use std::{cmp, fmt, mem};
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use core::{self, Backend, Device as CoreDevice, DescriptorPool, IndexType, InstanceCount,
+           VertexOffset, Viewport};
+use core::buffer::IndexBufferView;
+use core::command::{BufferCopy, BufferImageCopy, ClearColor, CommandBuffer, Offset,
+                    RenderPassInlineEncoder, Submit};
+use core::device::Extent;
+use core::format;
+use core::image::ImageLayout;
+use core::memory::{self, cast_slice, Barrier, Pod};
+use core::query::{Query, QueryId};
+use core::pool::CommandPool;
+use core::pso::{PipelineStage, DescriptorRangeDesc, DescriptorSetLayoutBinding, DescriptorSetWrite, DescriptorType,
+                DescriptorWrite, VertexBufferSet};
+use core::target::Rect;
+use handle::{self, Epoch};
+use device::{general_access, CreationError};
+use pso::RawDescriptor;
+use readback::{Readback, RowLayout, SubmissionEpoch};
+use {buffer, image, pso, slice, Device, Gpu};
 
 /// An error occuring in memory copies.
 #[allow(missing_docs)]
@@ -34,16 +48,18 @@ pub enum CopyError<S, D> {
     },
     NoSrcBindFlag,
     NoDstBindFlag,
+    UnalignedRegion,
+    NoBlitSupport,
 }
 
 /// Result type returned when copying a buffer into another buffer.
 pub type CopyBufferResult = Result<(), CopyError<usize, usize>>;
 
 /// Result type returned when copying buffer data into a texture.
-pub type CopyBufferTextureResult = Result<(), CopyError<usize, [texture::Size; 3]>>;
+pub type CopyBufferTextureResult = Result<(), CopyError<usize, [image::Size; 3]>>;
 
 /// Result type returned when copying texture data into a buffer.
-pub type CopyTextureBufferResult = Result<(), CopyError<[texture::Size; 3], usize>>;
+pub type CopyTextureBufferResult = Result<(), CopyError<[image::Size; 3], usize>>;
 
 impl<S, D> fmt::Display for CopyError<S, D>
     where S: fmt::Debug + fmt::Display, D: fmt::Debug + fmt::Display
@@ -76,6 +92,8 @@ impl<S, D> Error for CopyError<S, D>
             Overlap {..} => "Copy source and destination are overlapping",
             NoSrcBindFlag => "Copy source is missing `TRANSFER_SRC`",
             NoDstBindFlag => "Copy destination is missing `TRANSFER_DST`",
+            UnalignedRegion => "Copy region doesn't cover whole blocks of the compressed format",
+            NoBlitSupport => "The device can't blit images of this format",
         }
     }
 }
@@ -92,15 +110,9 @@ pub enum UpdateError<T> {
         target: usize,
         slice: usize,
     },
-    InvalidUsage(Usage),
-}
-
-fn check_update_usage<T>(usage: Usage) -> Result<(), UpdateError<T>> {
-    if usage == Usage::Dynamic {
-        Ok(())
-    } else {
-        Err(UpdateError::InvalidUsage(usage))
-    }
+    InvalidUsage,
+    UnalignedRegion,
+    Staging(CreationError),
 }
 
 impl<T: Any + fmt::Debug + fmt::Display> fmt::Display for UpdateError<T> {
@@ -110,8 +122,10 @@ impl<T: Any + fmt::Debug + fmt::Display> fmt::Display for UpdateError<T> {
                 write!(f, "Write to {} from {} is out of bounds", target, source),
             UpdateError::UnitCountMismatch {ref target, ref slice} =>
                 write!(f, "{}: expected {}, found {}", self.description(), target, slice),
-            UpdateError::InvalidUsage(usage) =>
-                write!(f, "{}: {:?}", self.description(), usage),
+            UpdateError::InvalidUsage | UpdateError::UnalignedRegion =>
+                write!(f, "{}", self.description()),
+            UpdateError::Staging(ref e) =>
+                write!(f, "{}: {}", self.description(), e),
         }
     }
 }
@@ -121,23 +135,57 @@ impl<T: Any + fmt::Debug + fmt::Display> Error for UpdateError<T> {
         match *self {
             UpdateError::OutOfBounds {..} => "Write to data is out of bounds",
             UpdateError::UnitCountMismatch {..} => "Unit count mismatch",
-            UpdateError::InvalidUsage(_) => "This memory usage does not allow updates",
+            UpdateError::InvalidUsage => "The resource is missing `TRANSFER_DST`",
+            UpdateError::UnalignedRegion => "Write doesn't cover whole blocks of the compressed format",
+            UpdateError::Staging(_) => "Failed to create or fill the staging buffer",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            UpdateError::Staging(ref e) => Some(e),
+            _ => None,
         }
     }
 }
 
 /// Extension for graphics command buffer pools to acquire a graphics encoder.
 pub trait GraphicsPoolExt<B: Backend> {
-    /// Acquire a `GraphicsEncoder` from the pool.
-    fn acquire_graphics_encoder(&mut self) -> GraphicsEncoder<B>;
+    /// Acquire a `GraphicsEncoder` from the pool, creating the resources it needs on `device`.
+    fn acquire_graphics_encoder<'a>(&'a mut self, device: &'a mut Device<B>) -> GraphicsEncoder<'a, B>;
 }
 
-impl<B: Backend> GraphicsPoolExt<B> for GraphicsCommandPool<B> {
-    fn acquire_graphics_encoder(&mut self) -> GraphicsEncoder<B> {
-        GraphicsEncoder::from(self.acquire_command_buffer())
+impl<B: Backend> GraphicsPoolExt<B> for CommandPool<B, core::Graphics> {
+    fn acquire_graphics_encoder<'a>(&'a mut self, device: &'a mut Device<B>) -> GraphicsEncoder<'a, B> {
+        GraphicsEncoder {
+            command_buffer: self.acquire_command_buffer(),
+            device,
+            raw_pso_data: pso::RawDataSet::new(),
+            descriptor_pools: Vec::new(),
+            descriptor_sets: Vec::new(),
+            handles: Vec::new(),
+            readback_epoch: None,
+        }
     }
 }
 
+/// Descriptor types used by PSO components.
+const DESCRIPTOR_TYPES: [DescriptorType; 3] = [
+    DescriptorType::ConstantBuffer,
+    DescriptorType::SampledImage,
+    DescriptorType::Sampler,
+];
+
+/// Number of descriptor sets per descriptor pool of an encoder.
+const POOL_SETS: usize = 64;
+
+/// Descriptor pool of an encoder, with its remaining capacity.
+struct PoolState<B: Backend> {
+    pool: handle::raw::DescriptorPool<B>,
+    sets: usize,
+    descriptors: [usize; 3],
+}
+
 /// Graphics Command Encoder
 ///
 /// # Overview
@@ -145,118 +193,114 @@ impl<B: Backend> GraphicsPoolExt<B> for GraphicsCommandPool<B> {
 /// commands to the `CommandBuffer`.
 ///
 /// The encoder exposes multiple functions that add commands to its internal `CommandBuffer`. To
-/// submit these commands to the GPU so they can be rendered, call `flush` or `synced_flush`.
-pub struct GraphicsEncoder<'a, B: Backend + 'a> {
-    command_buffer: GraphicsCommandBuffer<'a, B>,
+/// submit these commands to the GPU so they can be rendered, call `finish` and `flush` the
+/// resulting submission.
+///
+/// Descriptor sets, framebuffers and views needed by the commands are created on the fly
+/// and kept alive, together with all used resources, until the submission has completed.
+pub struct GraphicsEncoder<'a, B: Backend> {
+    command_buffer: CommandBuffer<'a, B, core::Graphics>,
+    device: &'a mut Device<B>,
     raw_pso_data: pso::RawDataSet<B>,
-    access_info: command::AccessInfo<B>,
-    handles: handle::Manager<B>,
-}
-
-impl<'a, B: Backend> From<GraphicsCommandBuffer<'a, B>> for GraphicsEncoder<'a, B> {
-    fn from(combuf: GraphicsCommandBuffer<B>) -> GraphicsEncoder<B> {
-        GraphicsEncoder {
-            command_buffer: combuf,
-            raw_pso_data: pso::RawDataSet::new(),
-            access_info: command::AccessInfo::new(),
-            handles: handle::Manager::new(),
-        }
-    }
+    descriptor_pools: Vec<PoolState<B>>,
+    descriptor_sets: Vec<B::DescriptorSet>,
+    handles: Vec<handle::Any>,
+    readback_epoch: Option<SubmissionEpoch>,
 }
 
-///
+/// Commands recorded by a `GraphicsEncoder`, ready to be submitted.
 pub struct GraphicsSubmission<B: Backend> {
-    submission: Submit<B, core::queue::Graphics>,
-    access_info: command::AccessInfo<B>,
-    handles: handle::Manager<B>,
+    submission: Submit<B, core::Graphics>,
+    // Descriptor sets and handles used by the commands.
+    garbage: Vec<handle::Garbage<B>>,
+    readback_epoch: Option<SubmissionEpoch>,
 }
 
 impl<B: Backend> GraphicsSubmission<B> {
-     /// Submits the commands in the internal `CommandBuffer` to the GPU, so they can
-    /// be executed.
-    pub fn synced_flush(self,
-                        queue: &mut GraphicsQueue<B>,
-                        wait_semaphores: &[&handle::Semaphore<B>],
-                        signal_semaphores: &[&handle::Semaphore<B>],
-                        fence: Option<&handle::Fence<B>>) -> SubmissionResult<()> {
-        let wait_semaphores = &wait_semaphores.iter()
-                                              .map(|&wait| (wait, core::pso::BOTTOM_OF_PIPE))
-                                              .collect::<Vec<_>>();
-        queue.pin_submitted_resources(&self.handles);
-        let submission =
-            core::Submission::new()
-                    .wait_on(wait_semaphores)
-                    .submit(&[self.submission])
-                    .signal(signal_semaphores);
-
-        queue.submit(
-            &[submission],
-            fence,
-            &self.access_info
-        );
-
-        Ok(()) // TODO
-    }
-}
-
-impl<'a, B: Backend> GraphicsEncoder<'a, B> {
-    /// Submits the internal `CommandBuffer` to the GPU, so it can be executed.
+    /// Submits the commands to the GPU, so they can be executed.
     ///
-    /// Calling `flush` before swapping buffers is critical as without it the commands of the
-    /// internal ´CommandBuffer´ will not be sent to the GPU, and as a result they will not be
-    /// processed. Calling flush too often however will result in a performance hit. It is
-    /// generally recommended to call flush once per frame, when all draw calls have been made.
-    pub fn flush(self, queue: &mut GraphicsQueue<B>) -> SubmissionResult<()> {
-        self.synced_flush(queue, &[], &[], None)
+    /// Calling `flush` before swapping buffers is critical as without it the commands will not
+    /// be sent to the GPU, and as a result they will not be processed. Calling flush too often
+    /// however will result in a performance hit. It is generally recommended to call flush once
+    /// per frame, when all draw calls have been made.
+    pub fn flush(self, gpu: &mut Gpu<B>) -> Epoch {
+        self.synced_flush(gpu, &[], &[])
     }
 
-    /// Submits the commands in the internal `CommandBuffer` to the GPU, so they can
-    /// be executed.
+    /// Submits the commands to the GPU, waiting on and signalling the given semaphores.
+    ///
+    /// Each wait semaphore comes with the pipeline stages which have to wait for it, e.g.
+    /// `COLOR_ATTACHMENT_OUTPUT` for a semaphore signaled by `Swapchain::acquire_frame`.
     pub fn synced_flush(self,
-                        queue: &mut GraphicsQueue<B>,
-                        wait_semaphores: &[&handle::Semaphore<B>],
-                        signal_semaphores: &[&handle::Semaphore<B>],
-                        fence: Option<&handle::Fence<B>>) -> SubmissionResult<()> {
-        self.finish().synced_flush(queue, wait_semaphores, signal_semaphores, fence)
+                        gpu: &mut Gpu<B>,
+                        wait_semaphores: &[(&handle::raw::Semaphore<B>, core::pso::PipelineStage)],
+                        signal_semaphores: &[&handle::raw::Semaphore<B>]) -> Epoch {
+        let wait_semaphores = wait_semaphores
+            .iter()
+            .map(|&(wait, stages)| (wait.resource(), stages))
+            .collect::<Vec<_>>();
+        let signal_semaphores = signal_semaphores
+            .iter()
+            .map(|signal| signal.resource())
+            .collect::<Vec<_>>();
+        let submission = core::Submission::new()
+            .wait_on(&wait_semaphores)
+            .submit(&[self.submission])
+            .signal(&signal_semaphores);
+        let epoch = gpu.submit(submission);
+        if let Some(readback_epoch) = self.readback_epoch {
+            readback_epoch.store(epoch, Ordering::Release);
+        }
+        gpu.retire(self.garbage, epoch);
+        epoch
     }
+}
 
-    ///
-    pub fn finish(self) -> GraphicsSubmission<B> {
+impl<'a, B: Backend> GraphicsEncoder<'a, B> {
+    /// Finish recording, returning the commands to submit.
+    pub fn finish(mut self) -> GraphicsSubmission<B> {
+        for state in self.descriptor_pools.drain(..) {
+            self.handles.push(Box::new(state.pool));
+        }
         GraphicsSubmission {
             submission: self.command_buffer.finish(),
-            access_info: self.access_info,
-            handles: self.handles,
+            // The sets are retired first, as the handles include their pools.
+            garbage: vec![
+                handle::Garbage::DescriptorSets(self.descriptor_sets),
+                handle::Garbage::Handles(self.handles),
+            ],
+            readback_epoch: self.readback_epoch,
         }
     }
 
     /// Copy part of a buffer to another
     pub fn copy_buffer<T: Pod>(&mut self, src: &handle::Buffer<B, T>, dst: &handle::Buffer<B, T>,
                                src_offset: usize, dst_offset: usize, size: usize) -> CopyBufferResult {
-        if !src.get_info().bind.contains(memory::TRANSFER_SRC) {
+        if !src.info().usage.contains(core::buffer::TRANSFER_SRC) {
             return Err(CopyError::NoSrcBindFlag);
         }
-        if !dst.get_info().bind.contains(memory::TRANSFER_DST) {
+        if !dst.info().usage.contains(core::buffer::TRANSFER_DST) {
             return Err(CopyError::NoDstBindFlag);
         }
 
         let size_bytes = mem::size_of::<T>() * size;
         let src_offset_bytes = mem::size_of::<T>() * src_offset;
         let src_copy_end = src_offset_bytes + size_bytes;
-        if src_copy_end > src.get_info().size {
+        if src_copy_end > src.info().size {
             return Err(CopyError::OutOfSrcBounds {
-                size: src.get_info().size,
+                size: src.info().size,
                 copy_end: src_copy_end,
             });
         }
         let dst_offset_bytes = mem::size_of::<T>() * dst_offset;
         let dst_copy_end = dst_offset_bytes + size_bytes;
-        if dst_copy_end > dst.get_info().size {
+        if dst_copy_end > dst.info().size {
             return Err(CopyError::OutOfDstBounds {
-                size: dst.get_info().size,
+                size: dst.info().size,
                 copy_end: dst_copy_end,
             });
         }
-        if src == dst &&
+        if Arc::ptr_eq(src, dst) &&
            src_offset_bytes < dst_copy_end &&
            dst_offset_bytes < src_copy_end
         {
@@ -266,98 +310,19 @@ impl<'a, B: Backend> GraphicsEncoder<'a, B> {
                 size: size_bytes,
             });
         }
-        self.access_info.buffer_read(src.raw());
-        self.access_info.buffer_write(dst.raw());
-
-        self.command_buffer.copy_buffer(
-            self.handles.ref_buffer(src.raw()).clone(),
-            self.handles.ref_buffer(dst.raw()).clone(),
-            src_offset_bytes, dst_offset_bytes, size_bytes);
-        Ok(())
-    }
-
-    /// Copy part of a buffer to a texture
-    pub fn copy_buffer_to_texture_raw(
-        &mut self, src: &handle::RawBuffer<B>, src_offset_bytes: usize,
-        dst: &handle::RawTexture<B>, face: Option<texture::CubeFace>, info: texture::RawImageInfo)
-        -> CopyBufferTextureResult
-    {
-        if !src.get_info().bind.contains(memory::TRANSFER_SRC) {
-            return Err(CopyError::NoSrcBindFlag);
-        }
-        if !dst.get_info().bind.contains(memory::TRANSFER_DST) {
-            return Err(CopyError::NoDstBindFlag);
-        }
-
-        let size_bytes = info.get_byte_count();
-        let src_copy_end = src_offset_bytes + size_bytes;
-        if src_copy_end > src.get_info().size {
-            return Err(CopyError::OutOfSrcBounds {
-                size: src.get_info().size,
-                copy_end: src_copy_end,
-            });
-        }
-
-        let dim = dst.get_info().kind.get_dimensions();
-        if !info.is_inside(dim) {
-            let (w, h, d, _) = dim;
-            return Err(CopyError::OutOfDstBounds {
-                size: [w, h, d],
-                copy_end: [info.xoffset + info.width,
-                           info.yoffset + info.height,
-                           info.zoffset + info.depth]
-            });
-        }
-
-        self.access_info.buffer_read(src);
-
-        self.command_buffer.copy_buffer_to_texture(
-            self.handles.ref_buffer(src).clone(), src_offset_bytes,
-            self.handles.ref_texture(dst).clone(), dst.get_info().kind,
-            face, info);
-        Ok(())
-    }
-
-    /// Copy part of a texture to a buffer
-    pub fn copy_texture_to_buffer_raw(
-        &mut self, src: &handle::RawTexture<B>,
-        face: Option<texture::CubeFace>, info: texture::RawImageInfo,
-        dst: &handle::RawBuffer<B>, dst_offset_bytes: usize)
-        -> CopyTextureBufferResult
-    {
-        if !src.get_info().bind.contains(memory::TRANSFER_SRC) {
-            return Err(CopyError::NoSrcBindFlag);
-        }
-        if !dst.get_info().bind.contains(memory::TRANSFER_DST) {
-            return Err(CopyError::NoDstBindFlag);
-        }
 
-        let size_bytes = info.get_byte_count();
-        let dst_copy_end = dst_offset_bytes + size_bytes;
-        if dst_copy_end > dst.get_info().size {
-            return Err(CopyError::OutOfDstBounds {
-                size: dst.get_info().size,
-                copy_end: dst_copy_end,
-            });
-        }
-
-        let dim = src.get_info().kind.get_dimensions();
-        if !info.is_inside(dim) {
-            let (w, h, d, _) = dim;
-            return Err(CopyError::OutOfSrcBounds {
-                size: [w, h, d],
-                copy_end: [info.xoffset + info.width,
-                           info.yoffset + info.height,
-                           info.zoffset + info.depth]
-            });
-        }
-
-        self.access_info.buffer_write(dst);
-
-        self.command_buffer.copy_texture_to_buffer(
-            self.handles.ref_texture(src).clone(), src.get_info().kind,
-            face, info,
-            self.handles.ref_buffer(dst).clone(), dst_offset_bytes);
+        self.buffer_barrier(src, src.info().role.access() .. core::buffer::TRANSFER_READ);
+        self.buffer_barrier(dst, dst.info().role.access() .. core::buffer::TRANSFER_WRITE);
+        let region = BufferCopy {
+            src: src_offset_bytes as u64,
+            dst: dst_offset_bytes as u64,
+            size: size_bytes as u64,
+        };
+        self.command_buffer.copy_buffer(src.resource(), dst.resource(), &[region]);
+        self.buffer_barrier(src, core::buffer::TRANSFER_READ .. src.info().role.access());
+        self.buffer_barrier(dst, core::buffer::TRANSFER_WRITE .. dst.info().role.access());
+        self.retain(&**src);
+        self.retain(&**dst);
         Ok(())
     }
 
@@ -367,20 +332,20 @@ impl<'a, B: Backend> GraphicsEncoder<'a, B> {
                          -> Result<(), UpdateError<usize>>
     {
         if data.is_empty() { return Ok(()); }
-        try!(check_update_usage(buf.raw().get_info().usage));
+        if !buf.info().usage.contains(core::buffer::TRANSFER_DST) {
+            return Err(UpdateError::InvalidUsage);
+        }
 
         let elem_size = mem::size_of::<T>();
         let offset_bytes = elem_size * offset_elements;
         let bound = data.len().wrapping_mul(elem_size) + offset_bytes;
-        if bound <= buf.get_info().size {
-            self.command_buffer.update_buffer(
-                self.handles.ref_buffer(buf.raw()).clone(),
-                cast_slice(data), offset_bytes);
+        if bound <= buf.info().size {
+            self.update_buffer_raw(buf, cast_slice(data), offset_bytes);
             Ok(())
         } else {
             Err(UpdateError::OutOfBounds {
                 target: bound,
-                source: buf.get_info().size,
+                source: buf.info().size,
             })
         }
     }
@@ -389,29 +354,42 @@ impl<'a, B: Backend> GraphicsEncoder<'a, B> {
     pub fn update_constant_buffer<T: Copy>(&mut self, buf: &handle::Buffer<B, T>, data: &T) {
         use std::slice;
 
-        check_update_usage::<usize>(buf.raw().get_info().usage).unwrap();
-
+        assert!(buf.info().usage.contains(core::buffer::TRANSFER_DST),
+                "The constant buffer is missing `TRANSFER_DST`");
         let slice = unsafe {
             slice::from_raw_parts(data as *const T as *const u8, mem::size_of::<T>())
         };
-        self.command_buffer.update_buffer(
-            self.handles.ref_buffer(buf.raw()).clone(), slice, 0);
+        self.update_buffer_raw(buf, slice, 0);
+    }
+
+    fn update_buffer_raw(&mut self, buf: &handle::raw::Buffer<B>, data: &[u8], offset_bytes: usize) {
+        let access = buf.info().role.access();
+        self.buffer_barrier(buf, access .. core::buffer::TRANSFER_WRITE);
+        self.command_buffer.update_buffer(buf.resource(), offset_bytes as u64, data);
+        self.buffer_barrier(buf, core::buffer::TRANSFER_WRITE .. access);
+        self.retain(buf);
     }
 
     /// Update the contents of a texture.
-    pub fn update_texture<S, T>(&mut self, tex: &handle::Texture<B, T::Surface>,
-                          face: Option<texture::CubeFace>,
-                          img: texture::NewImageInfo, data: &[S::DataType])
-                          -> Result<(), UpdateError<[texture::Size; 3]>>
+    ///
+    /// The data is written into a staging buffer, which is copied into the image.
+    /// Staging rows are padded to the device's `min_buffer_copy_pitch_alignment`.
+    /// For compressed formats, `data` holds the blocks covering the region.
+    pub fn update_texture<S, T>(&mut self, tex: &handle::Image<B, T>,
+                          img: image::NewImageInfo, data: &[S::DataType])
+                          -> Result<(), UpdateError<[image::Size; 3]>>
     where
         S: format::SurfaceTyped,
         S::DataType: Copy,
         T: format::Formatted<Surface = S>,
     {
         if data.is_empty() { return Ok(()); }
-        try!(check_update_usage(tex.raw().get_info().usage));
+        if !tex.info().usage.contains(core::image::TRANSFER_DST) {
+            return Err(UpdateError::InvalidUsage);
+        }
 
-        let target_count = img.get_texel_count();
+        let surface = S::get_surface_type();
+        let target_count = img_rows(&img, surface) * img_row_blocks(&img, surface);
         if target_count != data.len() {
             return Err(UpdateError::UnitCountMismatch {
                 target: target_count,
@@ -419,7 +397,7 @@ impl<'a, B: Backend> GraphicsEncoder<'a, B> {
             })
         }
 
-        let dim = tex.get_info().kind.get_dimensions();
+        let dim = tex.info().kind.get_level_dimensions(img.mipmap);
         if !img.is_inside(dim) {
             let (w, h, d, _) = dim;
             return Err(UpdateError::OutOfBounds {
@@ -431,86 +409,463 @@ impl<'a, B: Backend> GraphicsEncoder<'a, B> {
                 source: [w, h, d],
             })
         }
+        if !img.is_block_aligned(surface.get_block_dimensions(), dim) {
+            return Err(UpdateError::UnalignedRegion);
+        }
 
-        self.command_buffer.update_texture(
-            self.handles.ref_texture(tex.raw()).clone(),
-            tex.get_info().kind, face, cast_slice(data),
-            img.convert(T::get_format()));
+        // Rows of the staging buffer need to satisfy the copy pitch alignment.
+        let bytes: &[u8] = cast_slice(data);
+        let rows = img_rows(&img, surface);
+        let row_size = bytes.len() / rows;
+        let alignment = cmp::max(self.device.ref_raw().get_limits().min_buffer_copy_pitch_alignment, 1);
+        let row_pitch = (row_size + alignment - 1) / alignment * alignment;
+        let staging = self.device.create_upload_buffer::<u8>(row_pitch * rows)
+            .map_err(UpdateError::Staging)?;
+        {
+            let mut writer = self.device.mut_raw()
+                .write_mapping::<u8>(staging.resource(), 0, (row_pitch * rows) as u64)
+                .map_err(|e| UpdateError::Staging(e.into()))?;
+            for (dst, src) in writer.chunks_mut(row_pitch).zip(bytes.chunks(row_size)) {
+                dst[.. row_size].copy_from_slice(src);
+            }
+        }
+
+        let (range, region) = image_copy_region(tex.info().kind, &img, surface, row_pitch);
+        self.image_barrier(tex, range.clone(), core::image::SHADER_READ .. core::image::TRANSFER_WRITE);
+        self.command_buffer.copy_buffer_to_image(
+            staging.resource(),
+            tex.resource(),
+            ImageLayout::General,
+            &[region],
+        );
+        self.image_barrier(tex, range, core::image::TRANSFER_WRITE .. core::image::SHADER_READ);
+        self.retain(&*staging);
+        self.retain(&**tex);
         Ok(())
     }
 
-    fn draw_indexed<T>(&mut self, buf: &handle::Buffer<B, T>, ty: IndexType,
-                    slice: &slice::Slice<B>, base: VertexCount,
-                    instances: Option<command::InstanceParams>) {
-        self.access_info.buffer_read(buf.raw());
-        self.command_buffer.bind_index(self.handles.ref_buffer(buf.raw()).clone(), ty);
-        self.command_buffer.call_draw_indexed(slice.start, slice.end - slice.start, base, instances);
+    /// Copy the contents of a buffer into host visible memory, to read them back once
+    /// the submission of the encoder has completed.
+    pub fn read_buffer<T: Pod>(&mut self, buf: &handle::Buffer<B, T>)
+                               -> Result<Readback<B, T>, CopyError<usize, usize>> {
+        if !buf.info().usage.contains(core::buffer::TRANSFER_SRC) {
+            return Err(CopyError::NoSrcBindFlag);
+        }
+
+        let size = buf.info().size;
+        let staging = self.create_readback_buffer(size);
+        let access = buf.info().role.access();
+        self.buffer_barrier(buf, access .. core::buffer::TRANSFER_READ);
+        let region = BufferCopy {
+            src: 0,
+            dst: 0,
+            size: size as u64,
+        };
+        self.command_buffer.copy_buffer(buf.resource(), staging.resource(), &[region]);
+        self.buffer_barrier(buf, core::buffer::TRANSFER_READ .. access);
+        self.retain(&**buf);
+        Ok(self.readback(staging, RowLayout { row_size: size, row_pitch: size, rows: 1 }))
     }
 
-    fn draw_slice(&mut self, slice: &slice::Slice<B>, instances: Option<command::InstanceParams>) {
-        match slice.buffer {
-            slice::IndexBuffer::Auto => self.command_buffer.call_draw(
-                slice.start + slice.base_vertex, slice.end - slice.start, instances),
-            slice::IndexBuffer::Index16(ref buf) =>
-                self.draw_indexed(buf, IndexType::U16, slice, slice.base_vertex, instances),
-            slice::IndexBuffer::Index32(ref buf) =>
-                self.draw_indexed(buf, IndexType::U32, slice, slice.base_vertex, instances),
+    /// Copy a region of a texture into host visible memory, to read it back once
+    /// the submission of the encoder has completed.
+    ///
+    /// The texels are read back tightly packed, layer after layer,
+    /// as rows of blocks for compressed formats.
+    pub fn read_texture<S, T>(&mut self, tex: &handle::Image<B, T>, img: image::NewImageInfo)
+                              -> Result<Readback<B, S::DataType>, CopyError<[image::Size; 3], usize>>
+    where
+        S: format::SurfaceTyped,
+        S::DataType: Pod,
+        T: format::Formatted<Surface = S>,
+    {
+        self.read_image(tex, img, S::get_surface_type())
+    }
+
+    /// Copy a region of an image with texels of the given surface type into host visible
+    /// memory, see `read_texture`.
+    pub(crate) fn read_image<T: Pod>(&mut self, tex: &handle::raw::Image<B>, img: image::NewImageInfo,
+                                     surface: format::SurfaceType)
+                                     -> Result<Readback<B, T>, CopyError<[image::Size; 3], usize>> {
+        if !tex.info().usage.contains(core::image::TRANSFER_SRC) {
+            return Err(CopyError::NoSrcBindFlag);
+        }
+
+        let dim = tex.info().kind.get_level_dimensions(img.mipmap);
+        if !img.is_inside(dim) {
+            let (w, h, d, _) = dim;
+            return Err(CopyError::OutOfSrcBounds {
+                size: [w, h, d],
+                copy_end: [
+                    img.xoffset + img.width,
+                    img.yoffset + img.height,
+                    img.zoffset + img.depth,
+                ],
+            })
         }
+        if !img.is_block_aligned(surface.get_block_dimensions(), dim) {
+            return Err(CopyError::UnalignedRegion);
+        }
+
+        // Rows of the staging buffer need to satisfy the copy pitch alignment.
+        let alignment = cmp::max(self.device.ref_raw().get_limits().min_buffer_copy_pitch_alignment, 1);
+        let row_size = img_row_blocks(&img, surface) * surface.get_block_size() as usize;
+        let row_pitch = (row_size + alignment - 1) / alignment * alignment;
+        let rows = img_rows(&img, surface);
+        let staging = self.create_readback_buffer(row_pitch * rows);
+
+        let (range, region) = image_copy_region(tex.info().kind, &img, surface, row_pitch);
+        self.image_barrier(tex, range.clone(), general_access() .. core::image::TRANSFER_READ);
+        self.command_buffer.copy_image_to_buffer(
+            tex.resource(),
+            ImageLayout::General,
+            staging.resource(),
+            &[region],
+        );
+        self.image_barrier(tex, range, core::image::TRANSFER_READ .. general_access());
+        self.retain(tex);
+        Ok(self.readback(staging, RowLayout { row_size, row_pitch, rows }))
     }
 
-    /// Clears the supplied `RenderTargetView` to the supplied `ClearColor`.
-    pub fn clear<T: format::RenderFormat>(&mut self,
-                 view: &handle::RenderTargetView<B, T>, value: T::View)
-    where T::View: Into<command::ClearColor> {
-        let target = self.handles.ref_rtv(view.raw()).clone();
-        self.command_buffer.clear_color(target, value.into())
+    /// Generate the mip levels of a texture after the first one, each by downsampling
+    /// the level before it.
+    ///
+    /// The texture needs to be usable as source and destination of transfers, and the
+    /// device has to support blits of its format, see `Device::get_format_properties`.
+    pub fn generate_mipmaps<T: format::Formatted>(&mut self, tex: &handle::Image<B, T>)
+                               -> Result<(), CopyError<[image::Size; 3], [image::Size; 3]>> {
+        let info = tex.info();
+        if !info.usage.contains(core::image::TRANSFER_SRC) {
+            return Err(CopyError::NoSrcBindFlag);
+        }
+        if !info.usage.contains(core::image::TRANSFER_DST) {
+            return Err(CopyError::NoDstBindFlag);
+        }
+        let features = self.device.ref_raw().get_format_properties(T::get_format()).optimal_tiling;
+        if !features.contains(format::BLIT_SRC | format::BLIT_DST) {
+            return Err(CopyError::NoBlitSupport);
+        }
+
+        if info.levels > 1 {
+            let state = (general_access(), ImageLayout::General);
+            self.command_buffer.generate_mipmaps(tex.resource(), info.kind, 1 .. info.levels, state);
+        }
+        self.retain(&**tex);
+        Ok(())
     }
-    /// Clear a depth view with a specified value.
-    pub fn clear_depth<T: format::DepthFormat>(&mut self,
-                       view: &handle::DepthStencilView<B, T>, depth: Depth) {
-        let target = self.handles.ref_dsv(view.raw()).clone();
-        self.command_buffer.clear_depth_stencil(target, Some(depth), None)
+
+    /// Reset a range of queries of a pool, which is required before writing them again.
+    pub fn reset_queries(&mut self, pool: &handle::raw::QueryPool<B>, queries: Range<QueryId>) {
+        self.command_buffer.reset_query_pool(pool.resource(), queries);
+        self.retain(pool);
     }
 
-    /// Clear a stencil view with a specified value.
-    pub fn clear_stencil<T: format::StencilFormat>(&mut self,
-                         view: &handle::DepthStencilView<B, T>, stencil: Stencil) {
-        let target = self.handles.ref_dsv(view.raw()).clone();
-        self.command_buffer.clear_depth_stencil(target, None, Some(stencil))
+    /// Write the point in time at which all previous commands have completed
+    /// into a timestamp query.
+    ///
+    /// Requires the `timestamp_query` feature.
+    pub fn write_timestamp(&mut self, pool: &handle::raw::QueryPool<B>, id: QueryId) {
+        let query = Query { pool: pool.resource(), id };
+        self.command_buffer.write_timestamp(core::pso::BOTTOM_OF_PIPE, query);
+        self.retain(pool);
+    }
+
+    /// Clears the supplied `RenderTargetView` to the supplied `ClearColor`.
+    pub fn clear<T: format::RenderFormat>(&mut self,
+                 view: &handle::RenderTargetView<B, T>, value: T::View)
+    where T::View: Into<ClearColor> {
+        self.command_buffer.clear_color(view.resource(), ImageLayout::General, value.into());
+        self.retain(&**view);
     }
 
     /// Draws a `slice::Slice` using a pipeline state object, and its matching `Data` structure.
+    ///
+    /// The draw is recorded in its own render pass, covering the render targets of the data.
     pub fn draw<D: pso::PipelineData<B>>(&mut self, slice: &slice::Slice<B>,
                 pipeline: &pso::PipelineState<B, D::Meta>, user_data: &D)
     {
-        let (pso, _) = self.handles.ref_pso(pipeline.get_handle());
-        //TODO: make `raw_data` a member to this struct, to re-use the heap allocation
         self.raw_pso_data.clear();
-        user_data.bake_to(&mut self.raw_pso_data, pipeline.get_meta(), &mut self.handles, &mut self.access_info);
-        self.command_buffer.bind_pixel_targets(self.raw_pso_data.pixel_targets.clone());
-        self.command_buffer.bind_pipeline_state(pso.clone());
-        self.command_buffer.bind_vertex_buffers(self.raw_pso_data.vertex_buffers.clone());
-        self.command_buffer.set_ref_values(self.raw_pso_data.ref_values);
-        self.command_buffer.set_scissor(self.raw_pso_data.scissor);
-        self.command_buffer.bind_constant_buffers(&self.raw_pso_data.constant_buffers);
-        for &(location, value) in &self.raw_pso_data.global_constants {
-            self.command_buffer.bind_global_constant(location, value);
-        }
-        self.command_buffer.bind_unordered_views(&self.raw_pso_data.unordered_views);
-        //Note: it's important to bind RTV, DSV, and UAV before SRV
-        self.command_buffer.bind_resource_views(&self.raw_pso_data.resource_views);
-        self.command_buffer.bind_samplers(&self.raw_pso_data.samplers);
-        self.draw_slice(slice, slice.instances);
+        user_data.bake_to(&mut self.raw_pso_data, pipeline.get_meta());
+        let data = mem::replace(&mut self.raw_pso_data, pso::RawDataSet::new());
+
+        let descriptor_set = if pipeline.get_descriptors().is_empty() {
+            None
+        } else {
+            Some(self.write_descriptor_set(pipeline, &data.descriptors))
+        };
+
+        let mut color_targets = data.color_targets.iter().collect::<Vec<_>>();
+        color_targets.sort_by_key(|&&(index, _)| index);
+        let (width, height) = match color_targets.first() {
+            Some(&&(_, ref view)) => {
+                let &(ref image, level) = view.info();
+                let (w, h, _, _) = image.info().kind.get_level_dimensions(level);
+                (w, h)
+            }
+            None => panic!("Drawing requires at least one render target"),
+        };
+        let framebuffer = {
+            let views = color_targets.iter().map(|&&(_, ref view)| view.resource()).collect::<Vec<_>>();
+            let extent = Extent { width: width as u32, height: height as u32, depth: 1 };
+            let raw = self.device.mut_raw()
+                .create_framebuffer(pipeline.get_render_pass().resource(), &views, &[], extent);
+            Arc::new(handle::inner::FrameBuffer::new(raw, (), self.device))
+        };
+
+        let area = Rect { x: 0, y: 0, w: width, h: height };
+        {
+            let mut encoder = self.command_buffer.begin_renderpass_inline(
+                pipeline.get_render_pass().resource(),
+                framebuffer.resource(),
+                area,
+                &[],
+            );
+            encoder.bind_graphics_pipeline(pipeline.get_handle().resource());
+            if let Some(ref set) = descriptor_set {
+                encoder.bind_graphics_descriptor_sets(pipeline.get_layout().resource(), 0, &[set]);
+            }
+            encoder.set_viewports(&[Viewport { x: 0, y: 0, w: width, h: height, near: 0.0, far: 1.0 }]);
+            encoder.set_scissors(&[data.scissor.unwrap_or(area)]);
+            if let Some(color) = data.blend_ref {
+                encoder.set_blend_constants(color);
+            }
+            let mut vertex_buffers = data.vertex_buffers.iter().collect::<Vec<_>>();
+            vertex_buffers.sort_by_key(|&&(index, _, _)| index);
+            encoder.bind_vertex_buffers(VertexBufferSet(
+                vertex_buffers
+                    .iter()
+                    .map(|&&(_, ref buffer, offset)| (buffer.resource(), offset))
+                    .collect()
+            ));
+            draw_slice(&mut encoder, slice);
+        }
+
+        if let Some(set) = descriptor_set {
+            self.descriptor_sets.push(set);
+        }
+        self.handles.push(Box::new(framebuffer));
+        self.handles.push(Box::new(pipeline.get_handle().clone()));
+        self.handles.push(Box::new(pipeline.get_layout().clone()));
+        self.handles.push(Box::new(pipeline.get_render_pass().clone()));
+        match slice.buffer {
+            slice::IndexBuffer::Auto => (),
+            slice::IndexBuffer::Index16(ref buf) => self.retain(&**buf),
+            slice::IndexBuffer::Index32(ref buf) => self.retain(&**buf),
+        }
+        let pso::RawDataSet { vertex_buffers, descriptors, color_targets, .. } = data;
+        for (_, buffer, _) in vertex_buffers {
+            self.handles.push(Box::new(buffer));
+        }
+        for (_, descriptor) in descriptors {
+            let handle: handle::Any = match descriptor {
+                RawDescriptor::ConstantBuffer(buffer, _) => Box::new(buffer),
+                RawDescriptor::SampledImage(view) => Box::new(view),
+                RawDescriptor::Sampler(sampler) => Box::new(sampler),
+            };
+            self.handles.push(handle);
+        }
+        for (_, view) in color_targets {
+            self.handles.push(Box::new(view));
+        }
+    }
+
+    /// Allocate a descriptor set for the pipeline and write the descriptors into it.
+    fn write_descriptor_set<M>(&mut self, pipeline: &pso::PipelineState<B, M>,
+                               descriptors: &[(usize, RawDescriptor<B>)]) -> B::DescriptorSet {
+        let set = self.allocate_descriptor_set(pipeline.get_set_layout().resource(), pipeline.get_descriptors());
+
+        let constant_buffers = descriptors
+            .iter()
+            .filter_map(|&(binding, ref descriptor)| match *descriptor {
+                RawDescriptor::ConstantBuffer(ref buffer, ref range) => {
+                    let view = self.device.view_buffer_as_constant(buffer, range.clone())
+                        .expect("Unable to view the buffer as constant buffer");
+                    Some((binding, view))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        {
+            let writes = descriptors
+                .iter()
+                .map(|&(binding, ref descriptor)| DescriptorSetWrite {
+                    set: &set,
+                    binding,
+                    array_offset: 0,
+                    write: match *descriptor {
+                        RawDescriptor::ConstantBuffer(..) => {
+                            let &(_, ref view) = constant_buffers
+                                .iter()
+                                .find(|&&(b, _)| b == binding)
+                                .unwrap();
+                            DescriptorWrite::ConstantBuffer(vec![view.resource()])
+                        }
+                        RawDescriptor::SampledImage(ref view) =>
+                            DescriptorWrite::SampledImage(vec![(view.resource(), ImageLayout::General)]),
+                        RawDescriptor::Sampler(ref sampler) =>
+                            DescriptorWrite::Sampler(vec![sampler.resource()]),
+                    },
+                })
+                .collect::<Vec<_>>();
+            self.device.mut_raw().update_descriptor_sets(&writes);
+        }
+        for (_, view) in constant_buffers {
+            self.handles.push(Box::new(view));
+        }
+        set
+    }
+
+    fn allocate_descriptor_set(&mut self, layout: &B::DescriptorSetLayout,
+                               bindings: &[DescriptorSetLayoutBinding]) -> B::DescriptorSet {
+        let mut needed = [0; 3];
+        for (count, &ty) in needed.iter_mut().zip(DESCRIPTOR_TYPES.iter()) {
+            *count = bindings.iter().filter(|b| b.ty == ty).map(|b| b.count).sum();
+        }
+        let fits = self.descriptor_pools.last().map_or(false, |state| {
+            state.sets > 0 && state.descriptors.iter().zip(needed.iter()).all(|(left, n)| left >= n)
+        });
+        if !fits {
+            let mut descriptors = [0; 3];
+            for (count, &n) in descriptors.iter_mut().zip(needed.iter()) {
+                *count = cmp::max(n, 1) * POOL_SETS;
+            }
+            let ranges = DESCRIPTOR_TYPES
+                .iter()
+                .zip(descriptors.iter())
+                .map(|(&ty, &count)| DescriptorRangeDesc { ty, count })
+                .collect::<Vec<_>>();
+            let raw = self.device.mut_raw().create_descriptor_pool(POOL_SETS, &ranges);
+            let pool = Arc::new(handle::inner::DescriptorPool::new(raw, (), self.device));
+            self.descriptor_pools.push(PoolState { pool, sets: POOL_SETS, descriptors });
+        }
+
+        let state = self.descriptor_pools.last_mut().unwrap();
+        state.sets -= 1;
+        for (left, n) in state.descriptors.iter_mut().zip(needed.iter()) {
+            *left -= *n;
+        }
+        Arc::get_mut(&mut state.pool)
+            .unwrap()
+            .resource_mut()
+            .allocate_sets(&[layout])
+            .remove(0)
+    }
+
+    fn buffer_barrier(&mut self, buffer: &handle::raw::Buffer<B>,
+                      states: Range<core::buffer::Access>) {
+        let barrier = Barrier::Buffer {
+            states,
+            target: buffer.resource(),
+            range: 0 .. buffer.info().size as u64,
+        };
+        let stages = PipelineStage::all();
+        self.command_buffer.pipeline_barrier(stages .. stages, &[barrier]);
+    }
+
+    fn image_barrier(&mut self, image: &handle::raw::Image<B>, range: core::image::SubresourceRange,
+                     access: Range<core::image::Access>) {
+        let barrier = Barrier::Image {
+            states: (access.start, ImageLayout::General) .. (access.end, ImageLayout::General),
+            target: image.resource(),
+            range,
+        };
+        let stages = PipelineStage::all();
+        self.command_buffer.pipeline_barrier(stages .. stages, &[barrier]);
+    }
+
+    /// Create a buffer in host visible memory for the data of a readback.
+    fn create_readback_buffer(&mut self, size: usize) -> handle::raw::Buffer<B> {
+        // Mapped memory can't be invalidated, so it has to be coherent. Cached memory is
+        // faster to read from, but not always available together with coherence.
+        let cached = memory::CPU_VISIBLE | memory::COHERENT | memory::CPU_CACHED;
+        let properties = if self.device.heap_types().iter().any(|ty| ty.properties.contains(cached)) {
+            cached
+        } else {
+            memory::CPU_VISIBLE | memory::COHERENT
+        };
+        let staging = self.device
+            .create_buffer::<u8>(size, buffer::Role::Staging, core::buffer::TRANSFER_DST, properties)
+            .expect("Unable to create a readback buffer");
+        (*staging).clone()
     }
 
-    /// Generate a mipmap chain for the given resource view.
-    pub fn generate_mipmap<T: format::BlendFormat>(&mut self, view: &handle::ShaderResourceView<B, T>) {
-        self.generate_mipmap_raw(view.raw())
+    fn readback<T: Pod>(&mut self, staging: handle::raw::Buffer<B>, layout: RowLayout) -> Readback<B, T> {
+        // The staging buffer may be dropped with the readback before the submission is made.
+        self.retain(&staging);
+        let epoch = self.readback_epoch
+            .get_or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+            .clone();
+        Readback::new(staging, epoch, layout)
     }
 
-    /// Untyped version of mipmap generation.
-    pub fn generate_mipmap_raw(&mut self, view: &handle::RawShaderResourceView<B>) {
-        let srv = self.handles.ref_srv(view).clone();
-        self.command_buffer.generate_mipmap(srv);
+    /// Keep a resource alive until the commands have been executed.
+    fn retain<H: Clone + Send + Sync + 'static>(&mut self, handle: &H) {
+        self.handles.push(Box::new(handle.clone()));
+    }
+}
+
+/// Number of rows of texels, or of blocks for compressed formats, in the region `img`,
+/// over all slices or layers.
+fn img_rows(img: &image::NewImageInfo, surface: format::SurfaceType) -> usize {
+    surface.get_num_rows(cmp::max(img.height, 1) as u32) * cmp::max(img.depth, 1) as usize
+}
+
+/// Number of texels, or of blocks for compressed formats, in a row of the region `img`.
+fn img_row_blocks(img: &image::NewImageInfo, surface: format::SurfaceType) -> usize {
+    surface.get_row_size(cmp::max(img.width, 1) as u32) / surface.get_block_size() as usize
+}
+
+/// Subresources of the region `img` of an image of the given kind, and the copy of the
+/// region from or to a buffer with rows of `row_pitch` bytes.
+fn image_copy_region(kind: image::Kind, img: &image::NewImageInfo,
+                     surface: format::SurfaceType, row_pitch: usize)
+                     -> (core::image::SubresourceRange, BufferImageCopy) {
+    // The texels of all layers of a cube or array image are tightly packed.
+    let depth = cmp::max(img.depth, 1);
+    let layers = match kind {
+        image::Kind::D1Array(..) | image::Kind::D2Array(..) |
+        image::Kind::Cube(..) | image::Kind::CubeArray(..) => depth,
+        _ => 1,
+    };
+    let depth = depth / layers;
+    let range = (img.mipmap .. img.mipmap + 1, img.zoffset .. img.zoffset + layers);
+    let region = BufferImageCopy {
+        buffer_offset: 0,
+        buffer_row_pitch: row_pitch as u32,
+        buffer_slice_pitch: (row_pitch * surface.get_num_rows(img.height as u32)) as u32,
+        image_aspect: core::image::ASPECT_COLOR,
+        image_subresource: (img.mipmap, range.1.clone()),
+        image_offset: Offset {
+            x: img.xoffset as i32,
+            y: img.yoffset as i32,
+            z: if layers > 1 { 0 } else { img.zoffset as i32 },
+        },
+        image_extent: Extent {
+            width: img.width as u32,
+            height: img.height as u32,
+            depth: depth as u32,
+        },
+    };
+    (range, region)
+}
+
+fn draw_indexed<B: Backend, T>(encoder: &mut RenderPassInlineEncoder<B>, buf: &handle::Buffer<B, T>,
+                               ty: IndexType, slice: &slice::Slice<B>, instances: Range<InstanceCount>) {
+    encoder.bind_index_buffer(IndexBufferView { buffer: buf.resource(), offset: 0, index_type: ty });
+    encoder.draw_indexed(slice.start .. slice.end, slice.base_vertex as VertexOffset, instances);
+}
+
+fn draw_slice<B: Backend>(encoder: &mut RenderPassInlineEncoder<B>, slice: &slice::Slice<B>) {
+    let instances = match slice.instances {
+        Some((count, base)) => base .. base + count,
+        None => 0 .. 1,
+    };
+    match slice.buffer {
+        slice::IndexBuffer::Auto => encoder.draw(
+            slice.start + slice.base_vertex .. slice.end + slice.base_vertex, instances),
+        slice::IndexBuffer::Index16(ref buf) =>
+            draw_indexed(encoder, buf, IndexType::U16, slice, instances),
+        slice::IndexBuffer::Index32(ref buf) =>
+            draw_indexed(encoder, buf, IndexType::U32, slice, instances),
     }
 }
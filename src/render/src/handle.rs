@@ -1,52 +1,135 @@
 use std::marker::PhantomData;
-use std::{ops, cmp, hash};
-use std::sync::mpsc;
+use std::{mem, ops, cmp, hash};
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
+use core::memory::Allocation;
 use Backend;
 
-pub(crate) type GarbageSender<B> = mpsc::Sender<Garbage<B>>;
-pub(crate) type GarbageReceiver<B> = mpsc::Receiver<Garbage<B>>;
-pub(crate) fn garbage_channel<B: Backend>() -> (GarbageSender<B>, GarbageReceiver<B>) {
-    mpsc::channel()
+/// Index of a submission on the queue timeline, starting at 1.
+///
+/// Epoch 0 denotes that nothing has been submitted yet.
+pub type Epoch = usize;
+
+/// Sending end of the garbage channel, held by every handle.
+///
+/// Resources are tagged with the epoch of the latest submission at the time
+/// they are dropped, as any submission up to this one may still use them.
+#[derive(Debug)]
+pub(crate) struct GarbageSender<B: Backend> {
+    sender: mpsc::Sender<(Garbage<B>, Epoch)>,
+    last_submitted: Arc<AtomicUsize>,
+}
+
+impl<B: Backend> Clone for GarbageSender<B> {
+    fn clone(&self) -> Self {
+        GarbageSender {
+            sender: self.sender.clone(),
+            last_submitted: self.last_submitted.clone(),
+        }
+    }
+}
+
+impl<B: Backend> GarbageSender<B> {
+    fn send(&self, garbage: Garbage<B>) -> Result<(), mpsc::SendError<(Garbage<B>, Epoch)>> {
+        let epoch = self.last_submitted.load(Ordering::Acquire);
+        self.sender.send((garbage, epoch))
+    }
+}
+
+pub(crate) type GarbageReceiver<B> = mpsc::Receiver<(Garbage<B>, Epoch)>;
+
+/// Create a garbage channel, tagging garbage with the epoch stored in `last_submitted`.
+pub(crate) fn garbage_channel<B: Backend>(last_submitted: Arc<AtomicUsize>) -> (GarbageSender<B>, GarbageReceiver<B>) {
+    let (sender, receiver) = mpsc::channel();
+    (GarbageSender { sender, last_submitted }, receiver)
 }
 
 macro_rules! define_resources {
-    ($($name:ident: $info:path,)*) => {
+    ($($name:ident: $info:ty => $destroy:ident,)*) => {
+        /// A dropped resource awaiting destruction.
         pub enum Garbage<B: Backend> {
             $( $name(B::$name), )*
+            /// Memory of a resource, sent after the resource itself.
+            Memory(Allocation),
+            /// Descriptor sets used by a submission, retired before their pools.
+            DescriptorSets(Vec<B::DescriptorSet>),
+            /// Handles used by a submission.
+            Handles(Vec<Any>),
+        }
+
+        impl<B: Backend> Garbage<B> {
+            /// Destroy the resource.
+            pub(crate) fn destroy(self, device: &mut ::Device<B>) {
+                use core::Device;
+                match self {
+                    $( Garbage::$name(resource) => device.mut_raw().$destroy(resource), )*
+                    Garbage::Memory(allocation) => device.free_memory(allocation),
+                    // Sets are freed with their pool, whose handle is released afterwards.
+                    Garbage::DescriptorSets(_) => (),
+                    // Resources are sent to the garbage channel once their last handle is dropped.
+                    Garbage::Handles(_) => (),
+                }
+            }
         }
 
         pub mod inner {
             use Backend;
+            use core::memory::Allocation;
+            use device::Device;
             use super::{Garbage, GarbageSender};
             use std::{cmp, hash};
 
             $(
             
-            #[derive(Clone, Debug)]
+            #[derive(Debug)]
             pub struct $name<B: Backend> {
                 resource: Option<B::$name>,
                 info: $info,
+                memory: Option<Allocation>,
                 garbage: GarbageSender<B>
             }
 
             impl<B: Backend> $name<B> {
-                pub(crate) fn new(
+                /// Take ownership of a raw resource created on `device`.
+                ///
+                /// The resource is destroyed by `Gpu::cleanup` once it's dropped
+                /// and all submissions which may use it have completed.
+                pub fn new(
                     resource: B::$name,
                     info: $info,
-                    garbage: GarbageSender<B>) -> Self
+                    device: &Device<B>) -> Self
                 {
                     $name {
                         resource: Some(resource),
                         info,
-                        garbage,
+                        memory: None,
+                        garbage: device.garbage().clone(),
                     }
                 }
 
+                /// Take ownership of a resource bound to memory of the device allocator.
+                #[allow(dead_code)]
+                pub(crate) fn with_memory(
+                    resource: B::$name,
+                    info: $info,
+                    memory: Allocation,
+                    device: &Device<B>) -> Self
+                {
+                    let mut handle = Self::new(resource, info, device);
+                    handle.memory = Some(memory);
+                    handle
+                }
+
                 pub fn resource(&self) -> &B::$name {
                     self.resource.as_ref().unwrap()
                 }
 
+                #[allow(dead_code)]
+                pub(crate) fn resource_mut(&mut self) -> &mut B::$name {
+                    self.resource.as_mut().unwrap()
+                }
+
                 pub fn info(&self) -> &$info {
                     &self.info
                 }
@@ -78,6 +161,11 @@ macro_rules! define_resources {
                     self.garbage.send(Garbage::$name(res))
                         .unwrap_or_else(|e|
                             error!("Could not drop {}: {}", stringify!($name), e));
+                    if let Some(memory) = self.memory.take() {
+                        self.garbage.send(Garbage::Memory(memory))
+                            .unwrap_or_else(|e|
+                                error!("Could not free memory of {}: {}", stringify!($name), e));
+                    }
                 }
             }
 
@@ -88,33 +176,55 @@ macro_rules! define_resources {
             use std::sync::Arc;
             $( pub type $name<B> = Arc<super::inner::$name<B>>; )*
         }
+
     }
 }
 
+/// A handle of any type, keeping its resource alive.
+///
+/// Dropping the last handle of a resource sends it to the garbage channel.
+pub(crate) type Any = Box<::std::any::Any + Send + Sync>;
+
 define_resources! {
-    // Heap
-    // ShaderLib,
-    // RenderPass
-    // PipelineLayout
-    // GraphicsPipeline
-    // ComputePipeline
-    // FrameBuffer
-    Buffer: ::buffer::Info,
-    Image: ::image::Info,
-    // RenderTargetView,
-    // DepthStencilView,
-    // ConstantBufferView,
-    // ShaderResourceView,
-    // UnorderedAccessView,
-    // Sampler,
-    // DescriptorPool
-    // DescriptorSetLayout
-    // Fence
-    // Semaphore
+    Heap: () => destroy_heap,
+    ShaderLib: () => destroy_shader_lib,
+    RenderPass: () => destroy_renderpass,
+    PipelineLayout: () => destroy_pipeline_layout,
+    GraphicsPipeline: () => destroy_graphics_pipeline,
+    ComputePipeline: () => destroy_compute_pipeline,
+    FrameBuffer: () => destroy_framebuffer,
+    Buffer: ::buffer::Info => destroy_buffer,
+    Image: ::image::Info => destroy_image,
+    // Views keep the viewed resource alive.
+    RenderTargetView: (::handle::raw::Image<B>, ::image::Level) => destroy_render_target_view,
+    DepthStencilView: () => destroy_depth_stencil_view,
+    ConstantBufferView: ::handle::raw::Buffer<B> => destroy_constant_buffer_view,
+    ShaderResourceView: ::handle::raw::Image<B> => destroy_shader_resource_view,
+    UnorderedAccessView: () => destroy_unordered_access_view,
+    Sampler: () => destroy_sampler,
+    DescriptorPool: () => destroy_descriptor_pool,
+    DescriptorSetLayout: () => destroy_descriptor_set_layout,
+    Fence: () => destroy_fence,
+    Semaphore: () => destroy_semaphore,
+    QueryPool: ::core::query::QueryType => destroy_query_pool,
 }
 
 pub type Buffer<B, T> = Typed<raw::Buffer<B>, T>;
 pub type Image<B, S> = Typed<raw::Image<B>, S>;
+pub type RenderTargetView<B, F> = Typed<raw::RenderTargetView<B>, F>;
+pub type ShaderResourceView<B, F> = Typed<raw::ShaderResourceView<B>, F>;
+
+impl<B: Backend, T> Buffer<B, T> {
+    /// Number of elements of the buffer.
+    pub fn len(&self) -> usize {
+        self.info().size / mem::size_of::<T>()
+    }
+
+    /// Whether the buffer contains no elements.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 #[derive(Debug)]
 pub struct Typed<I, T> {
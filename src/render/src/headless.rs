@@ -0,0 +1,468 @@
+//! Headless swapchain rendering into offscreen images.
+//!
+//! A headless `Swapchain` isn't tied to a window: its backbuffers are ordinary images created
+//! on the device and presenting a frame copies its color image into a staging buffer. Once
+//! read back, the contents are handed to a user callback or written into a directory, one file
+//! per frame. This is useful for running applications on machines without a display, for example
+//! to compare their output against reference images in tests.
+//!
+//! The swapchain implements `core::Swapchain`, so code driving a window swapchain runs unchanged.
+//! As there is no presentation engine signaling the acquired frames, the swapchain owns a queue
+//! of its own, which executes the signals of `acquire_frame` and the copies of `present`.
+//! Presented frames are read back by `read_presented`, a backbuffer can't be acquired again
+//! before its previous frame has been read.
+//!
+//! # Example
+//!
+//! ```no_run
+//! extern crate gfx_backend_software as software;
+//! extern crate gfx_core as core;
+//! extern crate gfx;
+//!
+//! use core::{Adapter, Device, FrameSync, QueueType, Swapchain as CoreSwapchain};
+//! use gfx::headless::{Output, Swapchain};
+//!
+//! # fn main() {
+//! let adapter = software::Adapter::new();
+//! let core::Gpu { mut device, mut graphics_queues, heap_types, .. } =
+//!     adapter.open_with(|_, _| (2, QueueType::Graphics));
+//! let queue = graphics_queues.pop().unwrap();
+//! let config = gfx::SwapchainConfig::new().with_extent(256, 256);
+//! let output = Output::Callback(Box::new(|frame| println!("frame {}", frame.number)));
+//! let mut swapchain = Swapchain::new(&mut device, &heap_types, queue, config, output).unwrap();
+//!
+//! let acquisition = device.create_semaphore();
+//! let frame = swapchain.acquire_frame(FrameSync::Semaphore(&acquisition)).unwrap();
+//! // Render into `swapchain.get_backbuffers()[frame.id()].color` on `graphics_queues[0]` ..
+//! swapchain.present(&mut graphics_queues[0], &[]).unwrap();
+//! swapchain.read_presented(&mut device).unwrap();
+//! # }
+//! ```
+
+use std::{cmp, fmt, io};
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+
+use core::{self, buffer, image, mapping, pso, Device, HeapType};
+use core::{AcquireError, Frame, FrameSync, PresentError, Suboptimal, SwapchainConfig};
+use core::command::{BufferImageCopy, Offset};
+use core::device::{Extent, WaitFor};
+use core::format::{ChannelType, Format, SurfaceType};
+use core::memory::{self, Allocation, AllocationError, Allocator, Barrier, Strategy};
+use core::pool::{self, CommandPool};
+use core::queue::{CommandQueue, Submission, Transfer};
+use core::queue::capability::Supports;
+use Backend;
+
+/// Destination of the presented frames.
+pub enum Output {
+    /// Call the function with the contents of each presented frame.
+    Callback(Box<FnMut(&PresentedFrame)>),
+    /// Write each presented frame as a binary PPM image into the directory.
+    ///
+    /// The alpha channel is dropped. Only 8-bit RGBA and BGRA color formats are supported.
+    Directory(PathBuf),
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Output::Callback(_) => write!(f, "Callback"),
+            Output::Directory(ref path) => write!(f, "Directory({:?})", path),
+        }
+    }
+}
+
+/// Contents of the color image of a presented frame.
+#[derive(Debug)]
+pub struct PresentedFrame<'a> {
+    /// Number of frames presented before this one.
+    pub number: usize,
+    /// Index of the backbuffer of the frame.
+    pub index: usize,
+    /// Width of the image in pixels.
+    pub width: image::Size,
+    /// Height of the image in pixels.
+    pub height: image::Size,
+    /// Color format of the image.
+    pub format: Format,
+    /// Texel data, rows tightly packed from top to bottom.
+    pub data: &'a [u8],
+}
+
+/// Error creating a headless swapchain or reading back a presented frame.
+#[derive(Debug)]
+pub enum Error {
+    /// The configuration has no extent, which is required without a window.
+    MissingExtent,
+    /// The color format can't be written to the output.
+    UnsupportedFormat(Format),
+    /// Failed to create the backbuffers or staging buffers.
+    Allocation(AllocationError),
+    /// Failed to map a staging buffer.
+    Mapping(mapping::Error),
+    /// Failed to write a presented frame.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::UnsupportedFormat(format) => write!(f, "{}: {:?}", self.description(), format),
+            Error::Allocation(ref e) => write!(f, "{}: {}", self.description(), e),
+            Error::Mapping(ref e) => write!(f, "{}: {}", self.description(), e),
+            Error::Io(ref e) => write!(f, "{}: {}", self.description(), e),
+            _ => write!(f, "{}", self.description()),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::MissingExtent => "A headless swapchain requires an extent",
+            Error::UnsupportedFormat(_) => "The color format is not supported by the output",
+            Error::Allocation(_) => "Failed to create the swapchain images or buffers",
+            Error::Mapping(_) => "Failed to map the presented frame",
+            Error::Io(_) => "Failed to write the presented frame",
+        }
+    }
+
+    fn cause(&self) -> Option<&StdError> {
+        match *self {
+            Error::Allocation(ref e) => Some(e),
+            Error::Mapping(ref e) => Some(e),
+            Error::Io(ref e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<AllocationError> for Error {
+    fn from(e: AllocationError) -> Self {
+        Error::Allocation(e)
+    }
+}
+
+impl From<mapping::Error> for Error {
+    fn from(e: mapping::Error) -> Self {
+        Error::Mapping(e)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+/// Staging buffer and command pool reading back the frames of one backbuffer.
+struct Readback<B: Backend> {
+    pool: CommandPool<B, Transfer>,
+    buffer: B::Buffer,
+    memory: Allocation,
+    fence: B::Fence,
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+/// Swapchain presenting into offscreen images.
+///
+/// Backbuffers are handed out in a round-robin fashion and have to be in the `Present`
+/// layout when their frame is presented, like the ones of a window swapchain.
+pub struct Swapchain<B: Backend, C> {
+    queue: CommandQueue<B, C>,
+    allocator: Allocator<B>,
+    backbuffers: Vec<core::Backbuffer<B>>,
+    memory: Vec<Allocation>,
+    readbacks: Vec<Readback<B>>,
+    format: Format,
+    width: image::Size,
+    height: image::Size,
+    row_pitch: u64,
+    output: Output,
+    next_frame: usize,
+    acquired: VecDeque<usize>,
+    presented: VecDeque<usize>,
+    num_presented: usize,
+}
+
+impl<B: Backend, C: Supports<Transfer>> Swapchain<B, C> {
+    /// Create a swapchain with images on `device`, allocated from `heap_types`.
+    ///
+    /// `queue` is owned by the swapchain for signaling acquired frames and reading back the
+    /// presented ones. It can be taken back with `destroy`.
+    /// The extent of the configuration is required, the number of images is at least one.
+    pub fn new(
+        device: &mut B::Device,
+        heap_types: &[HeapType],
+        queue: CommandQueue<B, C>,
+        config: SwapchainConfig,
+        output: Output,
+    ) -> Result<Self, Error> {
+        let extent = config.extent.ok_or(Error::MissingExtent)?;
+        let format = config.color_format;
+        if let Output::Directory(_) = output {
+            if ppm_channels(format).is_none() {
+                return Err(Error::UnsupportedFormat(format));
+            }
+        }
+
+        let (width, height) = (extent.width as image::Size, extent.height as image::Size);
+        let row_pitch = {
+            let alignment = cmp::max(device.get_limits().min_buffer_copy_pitch_alignment, 1) as u64;
+            align(format.0.get_row_size(width as u32) as u64, alignment)
+        };
+        let mut swapchain = Swapchain {
+            queue,
+            allocator: Allocator::new(heap_types, Strategy::Linear, 0),
+            backbuffers: Vec::new(),
+            memory: Vec::new(),
+            readbacks: Vec::new(),
+            format,
+            width,
+            height,
+            row_pitch,
+            output,
+            next_frame: 0,
+            acquired: VecDeque::new(),
+            presented: VecDeque::new(),
+            num_presented: 0,
+        };
+
+        let kind = image::Kind::D2(width, height, image::AaMode::Single);
+        let color_usage = image::COLOR_ATTACHMENT | image::TRANSFER_SRC | image::TRANSFER_DST;
+        for _ in 0 .. cmp::max(config.image_count, 1) {
+            if let Err(e) = swapchain.add_backbuffer(device, kind, color_usage, config.depth_stencil_format) {
+                swapchain.destroy(device);
+                return Err(e.into());
+            }
+        }
+        Ok(swapchain)
+    }
+
+    fn add_backbuffer(
+        &mut self,
+        device: &mut B::Device,
+        kind: image::Kind,
+        color_usage: image::Usage,
+        depth_stencil_format: Option<Format>,
+    ) -> Result<(), AllocationError> {
+        let (color, memory) = self.allocator.create_image(
+            device, kind, 1, self.format, color_usage, memory::DEVICE_LOCAL)?;
+        self.memory.push(memory);
+        let depth_stencil = match depth_stencil_format {
+            Some(ds_format) => {
+                let result = self.allocator.create_image(
+                    device, kind, 1, ds_format, image::DEPTH_STENCIL_ATTACHMENT, memory::DEVICE_LOCAL);
+                match result {
+                    Ok((image, memory)) => {
+                        self.memory.push(memory);
+                        Some(image)
+                    }
+                    Err(e) => {
+                        device.destroy_image(color);
+                        return Err(e);
+                    }
+                }
+            }
+            None => None,
+        };
+        self.backbuffers.push(core::Backbuffer { color, depth_stencil });
+
+        let (buffer, memory) = self.allocator.create_buffer(
+            device,
+            self.row_pitch * self.height as u64,
+            1,
+            buffer::TRANSFER_DST,
+            memory::CPU_VISIBLE | memory::COHERENT,
+        )?;
+        self.readbacks.push(Readback {
+            pool: CommandPool::from_queue(&self.queue, 1, pool::TRANSIENT),
+            buffer,
+            memory,
+            fence: device.create_fence(false),
+        });
+        Ok(())
+    }
+
+    /// Number of frames read back so far.
+    pub fn num_presented(&self) -> usize {
+        self.num_presented
+    }
+
+    /// Wait for the presented frames to be read back and hand them to the output,
+    /// in the order they have been presented.
+    ///
+    /// Returns the number of frames handed to the output.
+    pub fn read_presented(&mut self, device: &mut B::Device) -> Result<usize, Error> {
+        let size = self.row_pitch * self.height as u64;
+        let row_size = self.format.0.get_row_size(self.width as u32);
+        let mut data = Vec::with_capacity(row_size * self.height as usize);
+        let mut count = 0;
+
+        while let Some(index) = self.presented.pop_front() {
+            let readback = &mut self.readbacks[index];
+            device.wait_for_fences(&[&readback.fence], WaitFor::All, !0);
+            device.reset_fences(&[&readback.fence]);
+            readback.pool.reset();
+
+            data.clear();
+            {
+                let reader = device.read_mapping::<u8>(&readback.buffer, 0, size)?;
+                for row in reader.chunks(self.row_pitch as usize) {
+                    data.extend_from_slice(&row[.. row_size]);
+                }
+            }
+
+            let frame = PresentedFrame {
+                number: self.num_presented,
+                index,
+                width: self.width,
+                height: self.height,
+                format: self.format,
+                data: &data,
+            };
+            self.num_presented += 1;
+            count += 1;
+
+            match self.output {
+                Output::Callback(ref mut callback) => callback(&frame),
+                Output::Directory(ref dir) => {
+                    let path = dir.join(format!("frame_{:05}.ppm", frame.number));
+                    write_ppm(&frame, BufWriter::new(File::create(path)?))?;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    /// Wait for all submitted work and destroy the swapchain, returning its queue.
+    ///
+    /// Presented frames which haven't been read back are discarded.
+    pub fn destroy(mut self, device: &mut B::Device) -> CommandQueue<B, C> {
+        if !self.presented.is_empty() {
+            let fences = self.presented.iter().map(|&index| &self.readbacks[index].fence).collect::<Vec<_>>();
+            device.wait_for_fences(&fences, WaitFor::All, !0);
+        }
+        for readback in self.readbacks.drain(..) {
+            device.destroy_buffer(readback.buffer);
+            device.destroy_fence(readback.fence);
+            self.allocator.free(readback.memory);
+        }
+        for backbuffer in self.backbuffers.drain(..) {
+            device.destroy_image(backbuffer.color);
+            if let Some(depth_stencil) = backbuffer.depth_stencil {
+                device.destroy_image(depth_stencil);
+            }
+        }
+        for memory in self.memory.drain(..) {
+            self.allocator.free(memory);
+        }
+        self.allocator.destroy(device);
+        self.queue
+    }
+}
+
+impl<B: Backend, C: Supports<Transfer>> core::Swapchain<B> for Swapchain<B, C> {
+    fn get_backbuffers(&mut self) -> &[core::Backbuffer<B>] {
+        &self.backbuffers
+    }
+
+    fn acquire_frame(&mut self, sync: FrameSync<B>) -> Result<Frame, AcquireError> {
+        let index = self.next_frame;
+        if self.acquired.contains(&index) || self.presented.contains(&index) {
+            // The backbuffer is still in use, its frame needs to be read back first.
+            return Err(AcquireError::NotReady);
+        }
+        self.next_frame = (index + 1) % self.backbuffers.len();
+        self.acquired.push_back(index);
+
+        // The backbuffer is available once the copies of earlier presents have completed,
+        // which the queue guarantees for everything submitted after them.
+        match sync {
+            FrameSync::Semaphore(semaphore) => self.queue.submit(Submission::new().signal(&[semaphore]), None),
+            FrameSync::Fence(fence) => self.queue.submit(Submission::new(), Some(fence)),
+        }
+        Ok(Frame::new(index))
+    }
+
+    /// Present the oldest acquired frame, copying its color image into a staging buffer.
+    ///
+    /// The copy is submitted to the queue owned by the swapchain, waiting on
+    /// `wait_semaphores`, so `present_queue` isn't used.
+    ///
+    /// Panics if no frame has been acquired.
+    fn present<D>(
+        &mut self,
+        _present_queue: &mut CommandQueue<B, D>,
+        wait_semaphores: &[&B::Semaphore],
+    ) -> Result<Suboptimal, PresentError> {
+        let index = self.acquired.pop_front().expect("No frame has been acquired");
+        let color = &self.backbuffers[index].color;
+        let readback = &mut self.readbacks[index];
+
+        let submit = {
+            let range = (0 .. 1, 0 .. 1);
+            let mut cmd_buffer = readback.pool.acquire_command_buffer();
+            cmd_buffer.pipeline_barrier(pso::TRANSFER .. pso::TRANSFER, &[Barrier::Image {
+                states: (image::Access::empty(), image::ImageLayout::Present) ..
+                        (image::TRANSFER_READ, image::ImageLayout::TransferSrcOptimal),
+                target: color,
+                range: range.clone(),
+            }]);
+            cmd_buffer.copy_image_to_buffer(color, image::ImageLayout::TransferSrcOptimal, &readback.buffer, &[BufferImageCopy {
+                buffer_offset: 0,
+                buffer_row_pitch: self.row_pitch as u32,
+                buffer_slice_pitch: (self.row_pitch * self.height as u64) as u32,
+                image_aspect: image::ASPECT_COLOR,
+                image_subresource: (0, 0 .. 1),
+                image_offset: Offset { x: 0, y: 0, z: 0 },
+                image_extent: Extent { width: self.width as u32, height: self.height as u32, depth: 1 },
+            }]);
+            cmd_buffer.pipeline_barrier(pso::TRANSFER .. pso::BOTTOM_OF_PIPE, &[Barrier::Image {
+                states: (image::TRANSFER_READ, image::ImageLayout::TransferSrcOptimal) ..
+                        (image::Access::empty(), image::ImageLayout::Present),
+                target: color,
+                range,
+            }]);
+            cmd_buffer.finish()
+        };
+
+        let waits = wait_semaphores
+            .iter()
+            .map(|&semaphore| (semaphore, pso::TRANSFER))
+            .collect::<Vec<_>>();
+        let submission = Submission::new()
+            .wait_on(&waits)
+            .submit(&[submit]);
+        self.queue.submit(submission, Some(&readback.fence));
+        self.presented.push_back(index);
+        Ok(false)
+    }
+}
+
+/// Offsets of the red, green and blue channels in a texel, if writable as PPM.
+fn ppm_channels(format: Format) -> Option<[usize; 3]> {
+    match format {
+        Format(SurfaceType::R8_G8_B8_A8, ChannelType::Unorm) |
+        Format(SurfaceType::R8_G8_B8_A8, ChannelType::Srgb) => Some([0, 1, 2]),
+        Format(SurfaceType::B8_G8_R8_A8, ChannelType::Unorm) |
+        Format(SurfaceType::B8_G8_R8_A8, ChannelType::Srgb) => Some([2, 1, 0]),
+        _ => None,
+    }
+}
+
+fn write_ppm<W: Write>(frame: &PresentedFrame, mut writer: W) -> io::Result<()> {
+    let channels = ppm_channels(frame.format).unwrap();
+    write!(writer, "P6\n{} {}\n255\n", frame.width, frame.height)?;
+    for texel in frame.data.chunks(4) {
+        writer.write_all(&[texel[channels[0]], texel[channels[1]], texel[channels[2]]])?;
+    }
+    writer.flush()
+}
@@ -1,8 +1,7 @@
 use core;
-use core::memory;
 
 pub use core::image::{
-    Kind, Level, Usage, ImageInfoCommon, RawImageInfo, NewImageInfo
+    Kind, Layer, Level, Size, Usage, ImageInfoCommon, RawImageInfo, NewImageInfo
 };
 
 /// Texture storage descriptor.
@@ -13,7 +12,6 @@ pub struct Info {
     pub kind: Kind,
     pub levels: Level,
     pub format: core::format::SurfaceType,
-    pub bind: memory::Bind,
     pub usage: Usage,
 }
 
@@ -39,3 +37,13 @@ impl Info {
         self.to_image_info(mip).convert(format)
     }
 }
+
+/// Number of array layers of an image, counting each face of cube images.
+pub(crate) fn num_layers(kind: Kind) -> core::image::Layer {
+    match kind {
+        Kind::D1Array(_, layers) | Kind::D2Array(_, _, layers, _) => layers,
+        Kind::Cube(_) => 6,
+        Kind::CubeArray(_, layers) => 6 * layers,
+        Kind::D1(_) | Kind::D2(..) | Kind::D3(..) => 1,
+    }
+}
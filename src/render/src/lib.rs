@@ -66,7 +66,9 @@
 //!
 //! Handles internally use atomically reference counted pointers to deal with memory management.
 //! GPU resources are not destroyed right away when all references to them are gone. Instead they
-//! are destroyed the next time `cleanup` is called on the queue.
+//! are tagged with the epoch of the latest submission made through `Gpu::submit` and destroyed
+//! by `Gpu::cleanup` once the fence of this submission is signaled, so handles can be dropped
+//! while the GPU may still be using them.
 //!
 //! # Examples
 //!
@@ -88,6 +90,10 @@ extern crate log;
 extern crate draw_state;
 extern crate gfx_core as core;
 
+use std::collections::VecDeque;
+use std::mem;
+use core::pool::{self, CommandPool};
+
 /// public re-exported traits
 pub mod traits {
     pub use core::memory::Pod;
@@ -99,51 +105,60 @@ pub use draw_state::target::*;
 
 // public re-exports
 pub use core::memory;
-pub use core::{Adapter, Backend, CommandQueue, Frame, FrameSync, Headless, Primitive, QueueFamily, QueueType,
-               SubmissionError, SubmissionResult, Surface, Swapchain, SwapchainConfig, WindowExt};
-/*
+pub use core::{AcquireError, Adapter, Backend, CommandQueue, Frame, FrameSync, PresentError, PresentMode,
+               Primitive, QueueFamily, QueueType, SubmissionError, SubmissionResult, Suboptimal, Surface,
+               SurfaceCapabilities, Swapchain, SwapchainConfig};
 pub use core::{VertexCount, InstanceCount};
+/*
 pub use core::{ShaderSet, VertexShader, HullShader, DomainShader, GeometryShader, PixelShader};
 pub use core::{GeneralCommandPool, GraphicsCommandPool, ComputeCommandPool, SubpassCommandPool};
+*/
 pub use core::{format, mapping, queue};
-pub use core::device::{ResourceViewError, TargetViewError, CombinedError, WaitFor};
-pub use core::command::{InstanceParams};
-pub use core::shade::{ProgramInfo, UniformValue};
+pub use core::device::{TargetViewError, WaitFor};
+pub use slice::InstanceParams;
+pub use pso::ProgramInfo;
 
 pub use encoder::{CopyBufferResult, CopyBufferTextureResult, CopyError,
-                  CopyTextureBufferResult, GraphicsEncoder, GraphicsSubmission, UpdateError, 
+                  CopyTextureBufferResult, GraphicsEncoder, GraphicsSubmission, UpdateError,
                   GraphicsPoolExt, };
-*/
-pub use device::{Device};
-/*
+pub use device::{CreationError, Device, PipelineStateError};
 pub use slice::{Slice, IntoIndexBuffer, IndexBuffer};
+pub use ring::FrameRing;
+pub use readback::Readback;
+pub use profiler::Profiler;
+/*
 pub use swapchain::SwapchainExt;
+*/
 pub use pso::{PipelineState};
 pub use pso::buffer::{VertexBuffer, InstanceBuffer, RawVertexBuffer,
-                      ConstantBuffer, RawConstantBuffer, Global, RawGlobal};
-pub use pso::resource::{ShaderResource, RawShaderResource, UnorderedAccess,
-                        Sampler, TextureSampler};
-pub use pso::target::{DepthStencilTarget, DepthTarget, StencilTarget,
-                      RenderTarget, RawRenderTarget, BlendTarget, BlendRef, Scissor};
+                      ConstantBuffer, DynamicConstantBuffer, RawConstantBuffer};
+pub use pso::resource::{ShaderResource, RawShaderResource, Sampler, TextureSampler};
+pub use pso::target::{RenderTarget, RawRenderTarget, BlendTarget, BlendRef, Scissor};
 pub use pso::bundle::{Bundle};
-*/
 
 /// Render commands encoder
 pub mod handle;
 /// Device extensions
 mod device;
-// pub mod encoder;
+mod timeline;
+pub mod encoder;
 pub mod buffer;
 pub mod image;
+pub mod slice;
+pub mod ring;
+pub mod readback;
+pub mod headless;
+pub mod profiler;
+// Pipeline states
+pub mod pso;
+/// Convenience macros
+#[macro_use]
+pub mod macros;
 /*
 /// Swapchain extensions
 mod swapchain;
-// Pipeline states
-pub mod pso;
 /// Shaders
 pub mod shade;
-/// Convenience macros
-pub mod macros;
 */
 
 pub(crate) enum Queue<B: Backend> {
@@ -154,7 +169,12 @@ pub(crate) enum Queue<B: Backend> {
 pub struct Gpu<B: Backend> {
     device: Device<B>,
     queue: Queue<B>,
+    timeline: timeline::Timeline<B>,
     garbage: handle::GarbageReceiver<B>,
+    // Dropped resources which may still be in use, with their epoch.
+    pending_garbage: Vec<(handle::Garbage<B>, handle::Epoch)>,
+    // Pools used to record uploads, with the epoch of their last submission.
+    upload_pools: VecDeque<(handle::Epoch, CommandPool<B, core::Transfer>)>,
 }
 
 impl<B: Backend> Gpu<B> {
@@ -181,35 +201,131 @@ impl<B: Backend> Gpu<B> {
             Queue::General(general_queues.remove(0))
         };
 
-        let (garbage_sender, garbage_receiver) = handle::garbage_channel();
+        let timeline = timeline::Timeline::new();
+        let (garbage_sender, garbage_receiver) = handle::garbage_channel(timeline.last_submitted().clone());
 
         Gpu {
             device: Device::new(device, heap_types, memory_heaps, garbage_sender),
             queue,
+            timeline,
             garbage: garbage_receiver,
+            pending_garbage: Vec::new(),
+            upload_pools: VecDeque::new(),
         }
     }
 
+    /// Submit command buffers to the queue, returning the epoch of the submission.
+    ///
+    /// Pending uploads of resources created through the device are submitted beforehand.
+    /// Resources dropped after this call are kept alive until the submission has completed.
+    pub fn submit<D>(&mut self, submission: core::Submission<B, D>) -> handle::Epoch
+    where
+        core::General: core::queue::Supports<D>,
+        core::Graphics: core::queue::Supports<D>,
+    {
+        self.flush_uploads();
+        self.submit_fenced(submission)
+    }
+
+    fn flush_uploads(&mut self) {
+        let uploads = self.device.take_uploads();
+        if uploads.is_empty() {
+            return;
+        }
+
+        let completed = self.timeline.poll(self.device.mut_raw());
+        let reuse = self.upload_pools.front().map_or(false, |&(epoch, _)| epoch <= completed);
+        let mut pool = if reuse {
+            let (_, mut pool) = self.upload_pools.pop_front().unwrap();
+            pool.reset();
+            pool
+        } else {
+            match self.queue {
+                Queue::General(ref queue) => CommandPool::from_queue(queue, 1, pool::TRANSIENT),
+                Queue::Graphics(ref queue) => CommandPool::from_queue(queue, 1, pool::TRANSIENT),
+            }
+        };
+
+        let submit = {
+            let mut cmd_buffer = pool.acquire_command_buffer();
+            device::record_uploads(&mut cmd_buffer, &uploads);
+            cmd_buffer.finish()
+        };
+        let epoch = self.submit_fenced(core::Submission::new().submit(&[submit]));
+        self.upload_pools.push_back((epoch, pool));
+        // Staging buffers are dropped here, after the upload epoch has been submitted.
+    }
+
+    fn submit_fenced<D>(&mut self, submission: core::Submission<B, D>) -> handle::Epoch
+    where
+        core::General: core::queue::Supports<D>,
+        core::Graphics: core::queue::Supports<D>,
+    {
+        let (epoch, fence) = self.timeline.begin_submission(self.device.mut_raw());
+        match self.queue {
+            Queue::General(ref mut queue) => queue.submit(submission, Some(&fence)),
+            Queue::Graphics(ref mut queue) => queue.submit(submission, Some(&fence)),
+        }
+        self.timeline.end_submission(epoch, fence);
+        epoch
+    }
+
+    /// Signal `fence` once all the submissions made so far have completed.
+    pub(crate) fn signal_fence(&mut self, fence: &B::Fence) {
+        match self.queue {
+            Queue::General(ref mut queue) => queue.submit(core::Submission::new(), Some(fence)),
+            Queue::Graphics(ref mut queue) => queue.submit(core::Submission::new(), Some(fence)),
+        }
+    }
+
+    /// Epoch of the latest submission known to have completed on the GPU.
+    pub fn last_completed(&self) -> handle::Epoch {
+        self.timeline.last_completed()
+    }
+
+    /// Retire completed submissions, returning the epoch of the latest one.
+    pub(crate) fn poll(&mut self) -> handle::Epoch {
+        self.timeline.poll(self.device.mut_raw())
+    }
+
+    /// Block until the submission of `epoch` has completed.
+    pub(crate) fn wait_for(&mut self, epoch: handle::Epoch) -> handle::Epoch {
+        self.timeline.wait_for(self.device.mut_raw(), epoch)
+    }
+
+    /// Destroy `garbage` once the submission of `epoch` has completed.
+    pub(crate) fn retire(&mut self, garbage: Vec<handle::Garbage<B>>, epoch: handle::Epoch) {
+        self.pending_garbage.extend(garbage.into_iter().map(|garbage| (garbage, epoch)));
+    }
+
+    /// Destroy dropped resources which are no longer used by any pending submission.
     pub fn cleanup(&mut self) {
-        use core::Device;
-
-        let dev = self.device.mut_raw();
-        for garbage in self.garbage.try_iter() {
-            use handle::Garbage::*;
-            match garbage {
-                // ShaderLib(sl) => dev.destroy_shader_lib(sl),
-                Buffer(b) => dev.destroy_buffer(b),
-                Image(i) => dev.destroy_image(i),
-                // RenderTargetView(rtv) => dev.destroy_render_target_view(rtv),
-                // DepthStencilView(dsv) => dev.destroy_depth_stencil_view(dsv),
-                // ConstantBufferView(cbv) => dev.destroy_constant_buffer_view(cbv),
-                // ShaderResourceView(srv) => dev.destroy_shader_resource_view(srv),
-                // UnorderedAccessView(uav) => dev.destroy_unordered_access_view(uav),
-                // Sampler(s) => dev.destroy_sampler(s),
+        let completed = self.timeline.poll(self.device.mut_raw());
+
+        let pending = mem::replace(&mut self.pending_garbage, Vec::new());
+        for (garbage, epoch) in pending.into_iter().chain(self.garbage.try_iter()) {
+            if epoch <= completed {
+                garbage.destroy(&mut self.device);
+            } else {
+                self.pending_garbage.push((garbage, epoch));
             }
         }
     }
 
+    /// Block until all submissions have completed and destroy all dropped resources.
+    pub fn wait_idle(&mut self) {
+        self.timeline.wait_idle(self.device.mut_raw());
+        self.cleanup();
+    }
+
+    /// Create a pool of graphics command buffers, e.g. to acquire a `GraphicsEncoder` from.
+    pub fn create_graphics_pool(&self, capacity: usize) -> CommandPool<B, core::Graphics> {
+        match self.queue {
+            Queue::General(ref queue) => CommandPool::from_queue(queue, capacity, pool::CommandPoolCreateFlags::empty()),
+            Queue::Graphics(ref queue) => CommandPool::from_queue(queue, capacity, pool::CommandPoolCreateFlags::empty()),
+        }
+    }
+
     pub fn ref_device(&self) -> &Device<B> {
         &self.device
     }
@@ -221,6 +337,10 @@ impl<B: Backend> Gpu<B> {
 
 impl<B: Backend> Drop for Gpu<B> {
     fn drop(&mut self) {
-        self.cleanup();
+        self.device.take_uploads();
+        self.wait_idle();
+        self.upload_pools.clear();
+        self.timeline.destroy(self.device.mut_raw());
+        self.device.destroy_allocator();
     }
 }
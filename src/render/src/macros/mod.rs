@@ -36,15 +36,13 @@ macro_rules! gfx_format {
 ///     }
 ///
 ///     pipeline pipe {
+///         // Attributes of the vertex buffers occupy consecutive locations.
 ///         vbuf: gfx::VertexBuffer<Vertex> = (),
-///         // Global buffers are added for compatibility when
-///         // constant buffers are not supported.
-///         transform: gfx::Global<[[f32; 4]; 4]> = "u_Transform",
-///         locals: gfx::ConstantBuffer<Locals> = "Locals",
-///         color: gfx::TextureSampler<[f32; 4]> = "t_Color",
-///         out_color: gfx::RenderTarget<gfx::format::Rgba8> = "Target0",
-///         out_depth: gfx::DepthTarget<gfx::format::DepthStencil> = 
-///             gfx::preset::depth::LESS_EQUAL_WRITE,
+///         // Descriptors are identified by their binding in the first set.
+///         locals: gfx::ConstantBuffer<Locals> = 0,
+///         color: gfx::TextureSampler<[f32; 4]> = (1, 2),
+///         // Render targets are assigned to color attachments in order.
+///         out_color: gfx::RenderTarget<gfx::format::Rgba8> = (),
 ///     }
 /// }
 ///
@@ -94,20 +92,17 @@ macro_rules! gfx_format {
 ///
 /// - A [vertex buffer](pso/buffer/type.VertexBuffer.html) component to hold the vertices.
 /// - An [instance buffer](pso/buffer/type.InstanceBuffer.html) component.
-/// - Single or multiple [constant buffer](pso/buffer/struct.ConstantBuffer.html) components. (DX11 and OpenGL3)
-/// - Single or multiple [global buffer](pso/buffer/struct.Global.html) components.
+/// - Single or multiple [constant buffer](pso/buffer/struct.ConstantBuffer.html) components.
 /// - Single or multiple [samplers](pso/resource/struct.Sampler.html).
-/// - [Render](pso/target/struct.RenderTarget.html), [blend](pso/target/struct.BlendTarget.html), 
-///   [depth](pso/target/struct.DepthTarget.html), [stencil](pso/target/struct.StencilTarget.html) targets.
-/// - A [shader resource view](pso/resource/struct.ShaderResource.html) (SRV, DX11)
-/// - An [unordered access view](pso/resource/struct.UnorderedAccess.html) (UAV, DX11, not yet implemented in the OpenGL backend)
-/// - A [scissor](pso/target/struct.Scissor.html) rectangle value (DX11)
+/// - [Render](pso/target/struct.RenderTarget.html) and [blend](pso/target/struct.BlendTarget.html) targets.
+/// - A [shader resource view](pso/resource/struct.ShaderResource.html) (SRV)
+/// - A [scissor](pso/target/struct.Scissor.html) rectangle value
 ///
 /// Structure of a `pipeline state object` can be defined freely.
 ///
 /// It should be noted however, that you can have multiple objects of everything but
-/// depth/stencil and scissor objects in a `pipeline state object`, which is the only
-/// restriction in the freedom of defining a `pipeline state object`.
+/// scissor objects in a `pipeline state object`, which is the only restriction in the
+/// freedom of defining a `pipeline state object`.
 ///
 /// # `vertex`
 ///
@@ -117,9 +112,8 @@ macro_rules! gfx_format {
 /// # `constant`
 ///
 /// Defines a structure for shader constant data. This constant data
-/// is then appended into a constant buffer in the `pso`. Constant buffers
-/// are supported by DirectX 11 and OpenGL3 backend, but in OpenGL they
-/// are called `Uniform Buffer Object`s or `UBO`s.
+/// is then appended into a constant buffer in the `pso`. The layout of the
+/// structure has to match the shader declaration, it isn't validated.
 #[macro_export]
 macro_rules! gfx_defines {
     ($(#[$attr:meta])* vertex $name:ident {
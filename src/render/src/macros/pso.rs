@@ -5,14 +5,14 @@ macro_rules! gfx_pipeline_inner {
     {
         $( $field:ident: $ty:ty, )*
     } => {
-        use $crate::pso::{DataLink, DataBind, Descriptor, InitError, RawDataSet, AccessInfo};
+        use $crate::pso::{DataLink, DataBind, Descriptor, InitError, ProgramInfo, RawDataSet};
 
-        #[derive(Clone, Debug, PartialEq)]
-        pub struct Data<R: $crate::Resources> {
-            $( pub $field: <$ty as DataBind<R>>::Data, )*
+        #[derive(Clone, Debug)]
+        pub struct Data<B: $crate::Backend> {
+            $( pub $field: <$ty as DataBind<B>>::Data, )*
         }
 
-        #[derive(Clone, Debug, PartialEq)]
+        #[derive(Clone, Debug)]
         pub struct Meta {
             $( $field: $ty, )*
         }
@@ -24,184 +24,63 @@ macro_rules! gfx_pipeline_inner {
 
         impl<'a> $crate::pso::PipelineInit for Init<'a> {
             type Meta = Meta;
-            fn link_to<'s>(&self, desc: &mut Descriptor, info: &'s $crate::ProgramInfo)
-                       -> ::std::result::Result<Self::Meta, InitError<&'s str>>
+            fn link_to(&self, desc: &mut Descriptor, info: &ProgramInfo)
+                       -> ::std::result::Result<Self::Meta, InitError>
             {
                 let mut meta = Meta {
                     $( $field: <$ty as DataLink<'a>>::new(), )*
                 };
-                // v#
+                // vertex buffers, with consecutive attribute locations
                 let mut _num_vb = 0;
                 $(
-                    if let Some(d) = meta.$field.link_vertex_buffer(_num_vb, &self.$field) {
+                    let location = desc.attributes.len() as $crate::pso::buffer::Location;
+                    if let Some((vb, attributes)) = meta.$field.link_vertex_buffer(_num_vb, location, &self.$field) {
                         assert!(meta.$field.is_active());
-                        desc.vertex_buffers[_num_vb as usize] = Some(d);
+                        desc.vertex_buffers.push(vb);
+                        desc.attributes.extend(attributes);
                         _num_vb += 1;
                     }
                 )*
-                for at in &info.vertex_attributes {
+                desc.check_vertex_inputs(info)?;
+                // descriptors
+                for binding in &info.descriptors {
                     $(
-                        match meta.$field.link_input(at, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.attributes[at.slot as usize] = Some(d);
-                                continue;
-                            },
-                            Some(Err(fm)) => return Err(
-                                InitError::VertexImport(&at.name, Some(fm))
-                            ),
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::VertexImport(&at.name, None));
-                }
-                // c#
-                for cb in &info.constant_buffers {
-                    $(
-                        match meta.$field.link_constant_buffer(cb, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.constant_buffers[cb.slot as usize] = Some(d);
-                                continue;
-                            },
-                            Some(Err(e)) => return Err(
-                                InitError::ConstantBuffer(&cb.name, Some(e))
-                            ),
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::ConstantBuffer(&cb.name, None));
-                }
-                // global constants
-                for gc in &info.globals {
-                    $(
-                        match meta.$field.link_global_constant(gc, &self.$field) {
+                        match meta.$field.link_descriptor(binding, &self.$field) {
                             Some(Ok(())) => {
                                 assert!(meta.$field.is_active());
+                                desc.descriptors.push(*binding);
                                 continue;
                             },
-                            Some(Err(e)) => return Err(
-                                InitError::GlobalConstant(&gc.name, Some(e))
-                            ),
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::GlobalConstant(&gc.name, None));
-                }
-                // t#
-                for srv in &info.textures {
-                    $(
-                        match meta.$field.link_resource_view(srv, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.resource_views[srv.slot as usize] = Some(d);
-                                continue;
-                            },
-                            Some(Err(_)) => return Err(
-                                InitError::ResourceView(&srv.name, Some(()))
-                            ),
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::ResourceView(&srv.name, None));
-                }
-                // u#
-                for uav in &info.unordereds {
-                    $(
-                        match meta.$field.link_unordered_view(uav, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.unordered_views[uav.slot as usize] = Some(d);
-                                continue;
-                            },
-                            Some(Err(_)) => return Err(
-                                InitError::UnorderedView(&uav.name, Some(()))
+                            Some(Err(ty)) => return Err(
+                                InitError::Descriptor(binding.binding, Some(ty))
                             ),
                             None => (),
                         }
                     )*
-                    return Err(InitError::UnorderedView(&uav.name, None));
-                }
-                // s#
-                for sm in &info.samplers {
-                    $(
-                        match meta.$field.link_sampler(sm, &self.$field) {
-                            Some(d) => {
-                                assert!(meta.$field.is_active());
-                                desc.samplers[sm.slot as usize] = Some(d);
-                                continue;
-                            },
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::Sampler(&sm.name, None));
-                }
-                // color targets
-                for out in &info.outputs {
-                    $(
-                        match meta.$field.link_output(out, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.color_targets[out.slot as usize] = Some(d);
-                                continue;
-                            },
-                            Some(Err(fm)) => return Err(
-                                InitError::PixelExport(&out.name, Some(fm))
-                            ),
-                            None => (),
-                        }
-                    )*
-                    return Err(InitError::PixelExport(&out.name, None));
-                }
-                if !info.knows_outputs {
-                    use $crate::shade::core as s;
-                    let mut out = s::OutputVar {
-                        name: String::new(),
-                        slot: 0,
-                        base_type: s::BaseType::F32,
-                        container: s::ContainerType::Vector(4),
-                    };
-                    $(
-                        match meta.$field.link_output(&out, &self.$field) {
-                            Some(Ok(d)) => {
-                                assert!(meta.$field.is_active());
-                                desc.color_targets[out.slot as usize] = Some(d);
-                                out.slot += 1;
-                            },
-                            Some(Err(fm)) => return Err(
-                                InitError::PixelExport(&"!known", Some(fm))
-                            ),
-                            None => (),
-                        }
-                    )*
-                }
-                // depth-stencil, scissor
-                for _ in 0 .. 1 {
-                    $(
-                        if let Some(d) = meta.$field.link_depth_stencil(&self.$field) {
-                            assert!(meta.$field.is_active());
-                            desc.depth_stencil = Some(d);
-                        }
-                        if meta.$field.link_scissor() {
-                            assert!(meta.$field.is_active());
-                            desc.scissor = true;
-                        }
-                    )*
+                    return Err(InitError::Descriptor(binding.binding, None));
                 }
+                // color targets, scissor
+                $(
+                    let index = desc.color_targets.len();
+                    if let Some(d) = meta.$field.link_output(index, &self.$field) {
+                        assert!(meta.$field.is_active());
+                        desc.color_targets.push(d);
+                    }
+                    if meta.$field.link_scissor() {
+                        assert!(meta.$field.is_active());
+                        desc.scissor = true;
+                    }
+                )*
                 // done
                 Ok(meta)
             }
         }
 
-        impl<R: $crate::Resources> $crate::pso::PipelineData<R> for Data<R> {
+        impl<B: $crate::Backend> $crate::pso::PipelineData<B> for Data<B> {
             type Meta = Meta;
-            fn bake_to(&self,
-                       out: &mut RawDataSet<R>,
-                       meta: &Self::Meta,
-                       man: &mut $crate::handle::Manager<R>,
-                       access: &mut AccessInfo<R>) {
+            fn bake_to(&self, out: &mut RawDataSet<B>, meta: &Self::Meta) {
                 $(
-                    meta.$field.bind_to(out, &self.$field, man, access);
+                    meta.$field.bind_to(out, &self.$field);
                 )*
             }
         }
@@ -1,4 +1,4 @@
-//! Macro for implementing Structure for vertex and constant buffers.
+//! Macros for defining the structures of vertex and constant buffers.
 
 #[macro_export]
 macro_rules! gfx_impl_struct {
@@ -58,6 +58,19 @@ macro_rules! gfx_impl_struct_meta {
                     _ => None,
                 }
             }
+
+            fn elements() -> ::std::vec::Vec<$crate::pso::buffer::Element<$runtime_format>> {
+                use std::mem::transmute;
+                use $crate::pso::buffer::{Element, ElemOffset};
+                let tmp: &$root = unsafe{ transmute(1usize) };
+                let base = tmp as *const _ as usize;
+                vec![$(
+                    Element {
+                        format: <$ty as $compile_format>::get_format(),
+                        offset: ((&tmp.$field as *const _ as usize) - base) as ElemOffset,
+                    },
+                )*]
+            }
         }
     }
 }
@@ -97,15 +110,19 @@ macro_rules! gfx_constant_struct {
     })
 }
 
+// Constant buffer layouts aren't reflected, the element names are unused.
 #[macro_export]
 macro_rules! gfx_constant_struct_meta {
     ($(#[$attr:meta])* constant_struct_meta $root:ident {
         $( $field:ident: $ty:ty = $name:expr, )*
-    }) => (gfx_impl_struct_meta!{
-        $(#[$attr])* impl_struct_meta
-        $crate::shade::ConstFormat : $crate::shade::Formatted =
-        $root {
-            $( $field: $ty = $name, )*
+    }) => {
+        #[allow(missing_docs)]
+        #[derive(Clone, Copy, Debug, PartialEq)]
+        $(#[$attr])*
+        pub struct $root {
+            $( pub $field: $ty, )*
         }
-    })
+
+        unsafe impl $crate::traits::Pod for $root {}
+    }
 }
@@ -0,0 +1,455 @@
+//! Measuring the GPU time spent in named scopes of command buffers.
+//!
+//! A `Profiler` writes a timestamp query at the beginning and at the end of each scope.
+//! The queries of a frame are allocated from one pool of a ring, like the regions of a
+//! `FrameRing`, and resolved without blocking once the frame's fence is signaled. The
+//! resolved timings are accumulated into per-scope statistics and kept for a number of
+//! frames, which can be exported as a trace viewable in `chrome://tracing`.
+//!
+//! Requires the `timestamp_query` feature, scopes are ignored otherwise.
+//!
+//! # Example
+//!
+//! ```no_run
+//! extern crate gfx;
+//! extern crate gfx_backend_software as software;
+//!
+//! use gfx::GraphicsPoolExt;
+//! use gfx::profiler::Profiler;
+//!
+//! # fn main() {
+//! let mut gpu = gfx::Gpu::new(&software::Adapter::new());
+//! let mut profiler = Profiler::new(gpu.mut_device(), 3, 64);
+//! let mut pool = gpu.create_graphics_pool(1);
+//!
+//! profiler.begin_frame(&mut gpu);
+//! let submission = {
+//!     let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+//!     {
+//!         let mut shadows = profiler.scope(&mut encoder, "shadow pass");
+//!         // Record commands through `shadows` ..
+//!     }
+//!     encoder.finish()
+//! };
+//! submission.flush(&mut gpu);
+//! profiler.end_frame(&mut gpu);
+//!
+//! profiler.save_chrome_trace("trace.json").unwrap();
+//! # }
+//! ```
+
+use std::{fmt, io, ops};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use core::Device as CoreDevice;
+use core::device::WaitFor;
+use core::query::{self, QueryId, QueryType};
+use device::Device;
+use encoder::GraphicsEncoder;
+use {handle, Backend, Gpu};
+
+/// Number of frames of timings kept by default.
+const DEFAULT_HISTORY: usize = 256;
+
+/// GPU time spent in a scope of a frame, in nanoseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeTiming {
+    /// Name of the scope.
+    pub name: String,
+    /// Number of scopes enclosing this one.
+    pub depth: usize,
+    /// Start of the scope, relative to the first timestamp resolved by the profiler.
+    pub start: u64,
+    /// Duration of the scope.
+    pub duration: u64,
+}
+
+/// Timings of the scopes of a frame, in the order they have been begun.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FrameTimings {
+    /// Number of frames begun before this one.
+    pub frame: usize,
+    /// Timings of the scopes.
+    pub scopes: Vec<ScopeTiming>,
+}
+
+/// Statistics of all resolved occurrences of a scope, in nanoseconds.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScopeStats {
+    /// Name of the scope.
+    pub name: String,
+    /// Number of resolved occurrences.
+    pub samples: usize,
+    /// Sum of the durations.
+    pub total: u64,
+    /// Shortest duration.
+    pub min: u64,
+    /// Longest duration.
+    pub max: u64,
+    /// Duration of the latest occurrence.
+    pub last: u64,
+}
+
+impl ScopeStats {
+    fn new(name: &str) -> Self {
+        ScopeStats {
+            name: name.to_owned(),
+            samples: 0,
+            total: 0,
+            min: !0,
+            max: 0,
+            last: 0,
+        }
+    }
+
+    fn add(&mut self, duration: u64) {
+        self.samples += 1;
+        self.total += duration;
+        self.min = self.min.min(duration);
+        self.max = self.max.max(duration);
+        self.last = duration;
+    }
+
+    /// Average duration.
+    pub fn average(&self) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            self.total as f64 / self.samples as f64
+        }
+    }
+}
+
+/// Scope recorded in a frame, whose timestamps are queries `2 * i` and `2 * i + 1`.
+struct PendingScope {
+    name: String,
+    depth: usize,
+}
+
+/// Queries of a frame of the ring.
+struct FrameQueries<B: Backend> {
+    pool: handle::raw::QueryPool<B>,
+    fence: handle::raw::Fence<B>,
+    scopes: Vec<PendingScope>,
+    frame: usize,
+    in_flight: bool,
+}
+
+/// Profiler measuring the GPU time of named scopes, with a pool of queries per frame in flight.
+///
+/// Every `begin_frame` has to be matched by an `end_frame`, and scopes can only be
+/// recorded in between.
+pub struct Profiler<B: Backend> {
+    frames: Vec<FrameQueries<B>>,
+    max_scopes: usize,
+    // Nanoseconds per timestamp tick.
+    timestamp_period: f64,
+    // First resolved timestamp, in ticks.
+    origin: Option<u64>,
+    current: usize,
+    num_frames: usize,
+    depth: usize,
+    recording: bool,
+    stats: Vec<ScopeStats>,
+    history: VecDeque<FrameTimings>,
+    max_history: usize,
+}
+
+impl<B: Backend> fmt::Debug for Profiler<B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Profiler")
+            .field("frames", &self.frames.len())
+            .field("max_scopes", &self.max_scopes)
+            .field("num_frames", &self.num_frames)
+            .field("stats", &self.stats)
+            .finish()
+    }
+}
+
+impl<B: Backend> Profiler<B> {
+    /// Create a profiler for `frames` frames in flight with up to `max_scopes` scopes each.
+    ///
+    /// If the device doesn't support timestamp queries no queries are created and
+    /// scopes don't measure anything.
+    pub fn new(device: &mut Device<B>, frames: usize, max_scopes: usize) -> Self {
+        assert!(frames > 0, "A profiler needs at least one frame");
+        let (supported, timestamp_period) = {
+            let raw = device.ref_raw();
+            (raw.get_features().timestamp_query, raw.get_limits().timestamp_period)
+        };
+
+        let frames = if supported {
+            (0 .. frames)
+                .map(|_| {
+                    let pool = device.create_query_pool(QueryType::Timestamp, 2 * max_scopes as u32);
+                    let fence = device.mut_raw().create_fence(false);
+                    FrameQueries {
+                        pool,
+                        fence: Arc::new(handle::inner::Fence::new(fence, (), device)),
+                        scopes: Vec::new(),
+                        frame: 0,
+                        in_flight: false,
+                    }
+                })
+                .collect()
+        } else {
+            warn!("Timestamp queries are not supported, GPU scopes will not be profiled");
+            Vec::new()
+        };
+
+        Profiler {
+            frames,
+            max_scopes,
+            timestamp_period: timestamp_period as f64,
+            origin: None,
+            current: 0,
+            num_frames: 0,
+            depth: 0,
+            recording: false,
+            stats: Vec::new(),
+            history: VecDeque::new(),
+            max_history: DEFAULT_HISTORY,
+        }
+    }
+
+    /// Whether the scopes are measured, which requires timestamp queries.
+    pub fn is_supported(&self) -> bool {
+        !self.frames.is_empty()
+    }
+
+    /// Set the number of resolved frames whose timings are kept, dropping older ones.
+    pub fn set_history(&mut self, frames: usize) {
+        self.max_history = frames;
+        while self.history.len() > frames {
+            self.history.pop_front();
+        }
+    }
+
+    /// Start recording the scopes of a new frame.
+    ///
+    /// Resolves all frames which have completed, and blocks until the last frame using
+    /// the same queries has completed if it hasn't yet.
+    pub fn begin_frame(&mut self, gpu: &mut Gpu<B>) {
+        assert!(!self.recording, "The previous frame has not been ended");
+        self.poll(gpu);
+        if self.is_supported() {
+            let index = self.current;
+            if self.frames[index].in_flight {
+                let device = gpu.mut_device().mut_raw();
+                device.wait_for_fences(&[self.frames[index].fence.resource()], WaitFor::All, !0);
+                self.resolve(index, device);
+            }
+            let queries = &mut self.frames[index];
+            queries.scopes.clear();
+            queries.frame = self.num_frames;
+        }
+        self.recording = true;
+    }
+
+    /// Begin a named scope of the current frame, ended when the returned guard is dropped.
+    ///
+    /// Commands of the scope are recorded through the guard, which dereferences to `encoder`.
+    /// Scopes exceeding the capacity of a frame are not measured.
+    pub fn scope<'s, 'e>(&'s mut self, encoder: &'s mut GraphicsEncoder<'e, B>, name: &str)
+                         -> Scope<'s, 'e, B> {
+        assert!(self.recording, "Scopes can only be recorded between begin_frame and end_frame");
+        let query = match self.frames.get_mut(self.current) {
+            Some(queries) if queries.scopes.len() < self.max_scopes => {
+                let query = 2 * queries.scopes.len() as QueryId;
+                queries.scopes.push(PendingScope { name: name.to_owned(), depth: self.depth });
+                encoder.reset_queries(&queries.pool, query .. query + 2);
+                encoder.write_timestamp(&queries.pool, query);
+                Some(query + 1)
+            }
+            Some(_) => {
+                warn!("Too many scopes in a frame, {} is not profiled", name);
+                None
+            }
+            None => None,
+        };
+        self.depth += 1;
+        Scope { profiler: self, encoder, end_query: query }
+    }
+
+    /// End the current frame, once all the commands of its scopes have been submitted to `gpu`.
+    pub fn end_frame(&mut self, gpu: &mut Gpu<B>) {
+        assert!(self.recording, "No frame has been begun");
+        if let Some(queries) = self.frames.get_mut(self.current) {
+            gpu.signal_fence(queries.fence.resource());
+            queries.in_flight = true;
+            self.current = (self.current + 1) % self.frames.len();
+        }
+        self.num_frames += 1;
+        self.recording = false;
+    }
+
+    /// Resolve the timings of all completed frames without blocking.
+    pub fn poll(&mut self, gpu: &mut Gpu<B>) {
+        let device = gpu.mut_device().mut_raw();
+        // Visit the frames from the oldest to the most recent one.
+        // After `end_frame`, `current` points at the oldest frame in flight.
+        for i in 0 .. self.frames.len() {
+            let index = (self.current + i) % self.frames.len();
+            if !self.frames[index].in_flight {
+                continue;
+            }
+            if !device.wait_for_fences(&[self.frames[index].fence.resource()], WaitFor::All, 0) {
+                // Frames complete in order.
+                break;
+            }
+            self.resolve(index, device);
+        }
+    }
+
+    /// Read back the timestamps of a completed frame.
+    fn resolve(&mut self, index: usize, device: &mut B::Device) {
+        let scopes = {
+            let queries = &mut self.frames[index];
+            device.reset_fences(&[queries.fence.resource()]);
+            queries.in_flight = false;
+
+            let mut data = vec![0; 2 * queries.scopes.len()];
+            if !queries.scopes.is_empty() {
+                let range = 0 .. data.len() as QueryId;
+                device.get_query_pool_results(queries.pool.resource(), range, &mut data, query::WAIT);
+            }
+            if let Some(&first) = data.first() {
+                self.origin.get_or_insert(first);
+            }
+            let origin = self.origin.unwrap_or(0);
+            let period = self.timestamp_period;
+            let to_ns = |ticks: u64| (ticks as f64 * period) as u64;
+
+            queries.scopes
+                .iter()
+                .zip(data.chunks(2))
+                .map(|(scope, timestamps)| ScopeTiming {
+                    name: scope.name.clone(),
+                    depth: scope.depth,
+                    start: to_ns(timestamps[0].saturating_sub(origin)),
+                    duration: to_ns(timestamps[1].saturating_sub(timestamps[0])),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        for timing in &scopes {
+            let position = self.stats.iter().position(|stats| stats.name == timing.name);
+            let index = position.unwrap_or_else(|| {
+                self.stats.push(ScopeStats::new(&timing.name));
+                self.stats.len() - 1
+            });
+            self.stats[index].add(timing.duration);
+        }
+
+        if self.max_history > 0 {
+            if self.history.len() == self.max_history {
+                self.history.pop_front();
+            }
+            self.history.push_back(FrameTimings { frame: self.frames[index].frame, scopes });
+        }
+    }
+
+    /// Statistics of each scope, in the order they have first been resolved.
+    pub fn statistics(&self) -> &[ScopeStats] {
+        &self.stats
+    }
+
+    /// Clear the statistics of all scopes.
+    pub fn reset_statistics(&mut self) {
+        self.stats.clear();
+    }
+
+    /// Timings of the latest resolved frames, oldest first.
+    pub fn history(&self) -> &VecDeque<FrameTimings> {
+        &self.history
+    }
+
+    /// Write the timings of the resolved frames in the Chrome trace event format.
+    pub fn write_chrome_trace<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(b"{\"traceEvents\":[")?;
+        let events = self.history
+            .iter()
+            .flat_map(|frame| frame.scopes.iter().map(move |scope| (frame.frame, scope)));
+        for (i, (frame, scope)) in events.enumerate() {
+            if i > 0 {
+                writer.write_all(b",")?;
+            }
+            // Timestamps and durations are given in microseconds.
+            write!(writer,
+                   "\n{{\"name\":\"{}\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\
+                    \"pid\":0,\"tid\":0,\"args\":{{\"frame\":{},\"depth\":{}}}}}",
+                   JsonEscaped(&scope.name),
+                   scope.start as f64 / 1000.0,
+                   scope.duration as f64 / 1000.0,
+                   frame,
+                   scope.depth)?;
+        }
+        writer.write_all(b"\n],\"displayTimeUnit\":\"ns\"}\n")?;
+        writer.flush()
+    }
+
+    /// Write the timings of the resolved frames into a Chrome trace file.
+    pub fn save_chrome_trace<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.write_chrome_trace(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// Guard of a profiled scope, writing the end timestamp when dropped.
+pub struct Scope<'s, 'e: 's, B: Backend> {
+    profiler: &'s mut Profiler<B>,
+    encoder: &'s mut GraphicsEncoder<'e, B>,
+    end_query: Option<QueryId>,
+}
+
+impl<'s, 'e: 's, B: Backend> Scope<'s, 'e, B> {
+    /// Begin a nested scope, see `Profiler::scope`.
+    pub fn scope<'t>(&'t mut self, name: &str) -> Scope<'t, 'e, B> {
+        self.profiler.scope(self.encoder, name)
+    }
+}
+
+impl<'s, 'e: 's, B: Backend> ops::Deref for Scope<'s, 'e, B> {
+    type Target = GraphicsEncoder<'e, B>;
+
+    fn deref(&self) -> &GraphicsEncoder<'e, B> {
+        self.encoder
+    }
+}
+
+impl<'s, 'e: 's, B: Backend> ops::DerefMut for Scope<'s, 'e, B> {
+    fn deref_mut(&mut self) -> &mut GraphicsEncoder<'e, B> {
+        self.encoder
+    }
+}
+
+impl<'s, 'e: 's, B: Backend> Drop for Scope<'s, 'e, B> {
+    fn drop(&mut self) {
+        if let Some(query) = self.end_query {
+            let pool = &self.profiler.frames[self.profiler.current].pool;
+            self.encoder.write_timestamp(pool, query);
+        }
+        self.profiler.depth -= 1;
+    }
+}
+
+/// String escaped for a JSON string literal.
+struct JsonEscaped<'a>(&'a str);
+
+impl<'a> fmt::Display for JsonEscaped<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                '\n' => write!(f, "\\n")?,
+                c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,32 +1,38 @@
 //! Buffer components for a PSO.
 
+use std::{fmt, mem};
 use std::marker::PhantomData;
-use core::{ConstantBufferSlot, Backend, MAX_VERTEX_ATTRIBUTES};
-use core::{handle, pso, shade};
-use core::memory::Typed;
+use core::Backend;
 use core::format::Format;
-use shade::{ToUniform, Usage};
-use super::{DataLink, DataBind, ElementError, RawDataSet, AccessInfo};
+use core::pso::{self, DescriptorSetLayoutBinding, DescriptorType};
+use handle;
+use super::{DataLink, DataBind, RawDataSet, RawDescriptor, link_binding};
 
-pub use core::pso::{BufferIndex, Element, ElemOffset, ElemStride, InstanceRate};
+pub use core::pso::{BufferIndex, Element, ElemOffset, ElemStride, InstanceRate, Location};
 
 /// A trait to be implemented by any struct having the layout described
 /// in the graphics API, like a vertex buffer.
 pub trait Structure<F> {
     /// Get the layout of an element by name.
     fn query(&str) -> Option<Element<F>>;
+    /// Get the layouts of all elements, in declaration order.
+    fn elements() -> Vec<Element<F>>;
 }
 
-type AttributeSlotSet = usize;
-
 /// Service struct to simplify the implementations of `VertexBuffer` and `InstanceBuffer`.
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct VertexBufferCommon<T, I=InstanceRate>(
-    RawVertexBuffer,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<(T, I)>
-);
+pub struct VertexBufferCommon<T, I=InstanceRate>(RawVertexBuffer, PhantomData<(T, I)>);
+
+impl<T, I> Clone for VertexBufferCommon<T, I> {
+    fn clone(&self) -> Self {
+        VertexBufferCommon(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T, I> fmt::Debug for VertexBufferCommon<T, I> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("VertexBufferCommon").field(&self.0).finish()
+    }
+}
 
 /// Helper trait for `VertexBufferCommon` to support variable instance rate.
 pub trait ToInstanceRate {
@@ -56,6 +62,8 @@ impl ToInstanceRate for NonInstanced {
 
 /// Vertex buffer component. Advanced per vertex.
 ///
+/// Provides one attribute per field of `T`, at consecutive locations.
+///
 /// - init: `()`
 /// - data: `Buffer<T>`
 pub type VertexBuffer<T> = VertexBufferCommon<T, NonInstanced>;
@@ -66,311 +74,186 @@ pub type InstanceBuffer<T> = VertexBufferCommon<T, Instanced>;
 /// Raw vertex/instance buffer component. Can be used when the formats of vertex attributes
 /// are not known at compile time.
 ///
-/// - init: `(&[&str, element], stride, inst_rate)`
-/// - data: `RawBuffer`
+/// - init: `(&[element], stride, inst_rate)`
+/// - data: `raw::Buffer`
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RawVertexBuffer(Option<BufferIndex>, AttributeSlotSet);
+pub struct RawVertexBuffer(Option<BufferIndex>);
 
 /// Constant buffer component.
 ///
-/// - init: `&str` = name of the buffer
+/// - init: `usize` = binding of the buffer
 /// - data: `Buffer<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct ConstantBuffer<T: Structure<shade::ConstFormat>>(
-    RawConstantBuffer,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+pub struct ConstantBuffer<T>(RawConstantBuffer, PhantomData<T>);
 
-/// Raw constant buffer component.
-///
-/// - init: `&str` = name of the buffer
-/// - data: `RawBuffer`
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RawConstantBuffer(Option<(Usage, ConstantBufferSlot)>);
+impl<T> Clone for ConstantBuffer<T> {
+    fn clone(&self) -> Self {
+        ConstantBuffer(self.0.clone(), PhantomData)
+    }
+}
 
-/// Global (uniform) constant component. Describes a free-standing value passed
-/// into the shader, which is not enclosed into any constant buffer.
-///
-/// - init: `&str` = name of the constant
-/// - data: `T` = value
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Global<T: ToUniform>(
-    RawGlobal,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+impl<T> fmt::Debug for ConstantBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ConstantBuffer").field(&self.0).finish()
+    }
+}
 
-/// Raw global (uniform) constant component. Describes a free-standing value
-/// passed into the shader, which is not enclosed in any constant buffer.
+/// Constant buffer component, bound to a sub-range of a larger buffer.
 ///
-/// - init: `&str` = name of the constant
-/// - data: `UniformValue` = value
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RawGlobal(Option<shade::Location>);
-
-fn match_attribute(attr: &shade::AttributeVar, fmt: Format) -> bool {
-    use core::shade::{BaseType, ContainerType};
-    use core::format::ChannelType;
+/// The data is typically a sub-allocation of a `FrameRing`.
+///
+/// - init: `usize` = binding of the buffer
+/// - data: (`raw::Buffer`, offset of `T` in bytes)
+pub struct DynamicConstantBuffer<T>(RawConstantBuffer, PhantomData<T>);
 
-    // TODO: Fill in `_` with compatible `core::format::SurfaceType` variants.
+impl<T> Clone for DynamicConstantBuffer<T> {
+    fn clone(&self) -> Self {
+        DynamicConstantBuffer(self.0.clone(), PhantomData)
+    }
+}
 
-    match (attr.base_type, fmt.1) {
-        (BaseType::I32, ChannelType::Int) |
-        (BaseType::F32, ChannelType::Float) |
-        (BaseType::F32, ChannelType::Inorm) |
-        (BaseType::F32, ChannelType::Srgb) |
-        (BaseType::F32, ChannelType::Unorm) |
-        (BaseType::U32, ChannelType::Uint) => match (attr.container, fmt.0) {
-                (ContainerType::Single, _) |
-                (ContainerType::Vector(1), _) |
-                (ContainerType::Vector(2), _) |
-                (ContainerType::Vector(3), _) |
-                (ContainerType::Vector(4), _) => true,
-                _ => false,
-        },
-        (BaseType::F64, ChannelType::Float) |
-        (BaseType::F64, ChannelType::Inorm) |
-        (BaseType::F64, ChannelType::Srgb) |
-        (BaseType::F64, ChannelType::Unorm) => match (attr.container, fmt.0) {
-                (ContainerType::Single, _) |
-                (ContainerType::Vector(2), _) |
-                (ContainerType::Vector(3), _) |
-                (ContainerType::Vector(4), _) => true,
-                _ => false,
-        },
-        _ => false,
+impl<T> fmt::Debug for DynamicConstantBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("DynamicConstantBuffer").field(&self.0).finish()
     }
 }
 
-impl<'a,
-    T: Structure<Format>,
-    I: ToInstanceRate + 'a,
-> DataLink<'a> for VertexBufferCommon<T, I> {
+/// Raw constant buffer component.
+///
+/// - init: `usize` = binding of the buffer
+/// - data: `raw::Buffer`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RawConstantBuffer(Option<usize>);
+
+impl<'a, T: Structure<Format>, I: ToInstanceRate + 'a>
+DataLink<'a> for VertexBufferCommon<T, I> {
     type Init = I::Init;
     fn new() -> Self {
-        VertexBufferCommon(DataLink::new(), PhantomData)
+        VertexBufferCommon(RawVertexBuffer::new(), PhantomData)
     }
     fn is_active(&self) -> bool {
         self.0.is_active()
     }
-    fn link_vertex_buffer(&mut self, index: BufferIndex, init: &Self::Init)
-                          -> Option<pso::VertexBufferDesc> {
-        use std::mem;
-        (self.0).0 = Some(index);
-        let rate = I::get_rate(init);
-        Some(pso::VertexBufferDesc {
-            stride: mem::size_of::<T>() as ElemStride,
-            rate: rate as InstanceRate,
-        })
-    }
-    fn link_input(&mut self, at: &shade::AttributeVar, _: &Self::Init) ->
-                  Option<Result<pso::AttributeDesc, Format>> {
-        T::query(&at.name).map(|el| {
-            self.0.link(at, el)
-        })
+    fn link_vertex_buffer(&mut self, index: BufferIndex, location: Location, init: &Self::Init)
+                          -> Option<(pso::VertexBufferDesc, Vec<pso::AttributeDesc>)> {
+        let stride = mem::size_of::<T>() as ElemStride;
+        Some(self.0.link(index, location, &T::elements(), stride, I::get_rate(init)))
     }
 }
 
 impl<B: Backend, T, I> DataBind<B> for VertexBufferCommon<T, I> {
     type Data = handle::Buffer<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        self.0.bind_to(out, data.raw(), man, access)
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, data)
     }
 }
 
 impl RawVertexBuffer {
-    fn link(&mut self, at: &shade::AttributeVar, el: Element<Format>)
-            -> Result<pso::AttributeDesc, Format> {
-        self.1 |= 1 << (at.slot as AttributeSlotSet);
-        if match_attribute(at, el.format) {
-            Ok((self.0.unwrap(), el))
-        } else {
-            Err(el.format)
-        }
+    fn link(&mut self, index: BufferIndex, location: Location, elements: &[Element<Format>],
+            stride: ElemStride, rate: InstanceRate)
+            -> (pso::VertexBufferDesc, Vec<pso::AttributeDesc>) {
+        self.0 = Some(index);
+        let attributes = elements
+            .iter()
+            .enumerate()
+            .map(|(i, &element)| pso::AttributeDesc {
+                location: location + i as Location,
+                binding: index,
+                element,
+            })
+            .collect();
+        (pso::VertexBufferDesc { stride, rate }, attributes)
     }
 }
 
 impl<'a> DataLink<'a> for RawVertexBuffer {
-    type Init = (&'a [(&'a str, Element<Format>)], ElemStride, InstanceRate);
+    type Init = (&'a [Element<Format>], ElemStride, InstanceRate);
     fn new() -> Self {
-        RawVertexBuffer(None, 0)
+        RawVertexBuffer(None)
     }
     fn is_active(&self) -> bool {
         self.0.is_some()
     }
-    fn link_vertex_buffer(&mut self, index: BufferIndex, init: &Self::Init)
-                          -> Option<pso::VertexBufferDesc> {
-        self.0 = Some(index);
-        Some(pso::VertexBufferDesc {
-            stride: init.1,
-            rate: init.2,
-        })
-    }
-    fn link_input(&mut self, at: &shade::AttributeVar, init: &Self::Init) ->
-                  Option<Result<pso::AttributeDesc, Format>> {
-        init.0.iter().find(|x| x.0 == &at.name)
-            .map(|x| self.link(at, x.1))
+    fn link_vertex_buffer(&mut self, index: BufferIndex, location: Location, init: &Self::Init)
+                          -> Option<(pso::VertexBufferDesc, Vec<pso::AttributeDesc>)> {
+        Some(self.link(index, location, init.0, init.1, init.2))
     }
 }
 
 impl<B: Backend> DataBind<B> for RawVertexBuffer {
-    type Data = handle::RawBuffer<B>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        let value = Some((man.ref_buffer(data).clone(), 0));
-        for i in 0 .. MAX_VERTEX_ATTRIBUTES {
-            if (self.1 & (1<<i)) != 0 {
-                out.vertex_buffers.0[i] = value;
-            }
+    type Data = handle::raw::Buffer<B>;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(index) = self.0 {
+            out.vertex_buffers.push((index, data.clone(), 0));
         }
-        if self.1 != 0 { access.buffer_read(data); }
     }
 }
 
-
-impl<'a, T: Structure<shade::ConstFormat>>
-DataLink<'a> for ConstantBuffer<T> {
-    type Init = &'a str;
+impl<'a, T> DataLink<'a> for ConstantBuffer<T> {
+    type Init = usize;
     fn new() -> Self {
         ConstantBuffer(RawConstantBuffer::new(), PhantomData)
     }
     fn is_active(&self) -> bool {
         self.0.is_active()
     }
-    fn link_constant_buffer<'b>(&mut self, cb: &'b shade::ConstantBufferVar, init: &Self::Init)
-                            -> Option<Result<pso::ConstantBufferDesc, ElementError<&'b str>>> {
-        let raw_out = self.0.link_constant_buffer(cb, init);
-        if raw_out.is_some() {
-            for el in cb.elements.iter() {
-                let err = match T::query(&el.name) {
-                    Some(e) if e.offset != el.location as pso::ElemOffset =>
-                        ElementError::Offset {
-                            name: el.name.as_str(),
-                            shader_offset: el.location as pso::ElemOffset,
-                            code_offset: e.offset,
-                        },
-                    None => ElementError::NotFound(el.name.as_str()),
-                    Some(_) => continue, //TODO: check format
-                };
-                self.0 = RawConstantBuffer::new();
-                return Some(Err(err));
-            }
-        }
-        raw_out
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        self.0.link_descriptor(binding, init)
     }
 }
 
-impl<B: Backend, T: Structure<shade::ConstFormat>>
-DataBind<B> for ConstantBuffer<T> {
+impl<B: Backend, T> DataBind<B> for ConstantBuffer<T> {
     type Data = handle::Buffer<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        self.0.bind_to(out, data.raw(), man, access)
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, data)
     }
 }
 
 impl<'a> DataLink<'a> for RawConstantBuffer {
-    type Init = &'a str;
+    type Init = usize;
     fn new() -> Self {
         RawConstantBuffer(None)
     }
     fn is_active(&self) -> bool {
         self.0.is_some()
     }
-    fn link_constant_buffer<'b>(&mut self, cb: &'b shade::ConstantBufferVar, init: &Self::Init)
-                            -> Option<Result<pso::ConstantBufferDesc, ElementError<&'b str>>> {
-        if cb.name.as_str() == *init {
-            self.0 = Some((cb.usage, cb.slot));
-            Some(Ok(cb.usage))
-        } else {
-            None
-        }
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        link_binding(&mut self.0, binding, *init, DescriptorType::ConstantBuffer)
     }
 }
 
 impl<B: Backend> DataBind<B> for RawConstantBuffer {
-    type Data = handle::RawBuffer<B>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        if let Some((usage, slot)) = self.0 {
-            let buf = man.ref_buffer(data).clone();
-            out.constant_buffers.push(pso::ConstantBufferParam(buf, usage, slot));
-            access.buffer_read(data)
+    type Data = handle::raw::Buffer<B>;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(binding) = self.0 {
+            let range = 0 .. data.info().size as u64;
+            out.descriptors.push((binding, RawDescriptor::ConstantBuffer(data.clone(), range)));
         }
     }
 }
 
-impl<'a, T: ToUniform + Default> DataLink<'a> for Global<T> {
-    type Init = &'a str;
+impl<'a, T> DataLink<'a> for DynamicConstantBuffer<T> {
+    type Init = usize;
     fn new() -> Self {
-        Global(RawGlobal::new(), PhantomData)
+        DynamicConstantBuffer(RawConstantBuffer::new(), PhantomData)
     }
     fn is_active(&self) -> bool {
         self.0.is_active()
     }
-    fn link_global_constant(&mut self, var: &shade::ConstVar, init: &Self::Init) ->
-                            Option<Result<(), shade::CompatibilityError>> {
-        let kind = ToUniform::convert(T::default());
-        self.0.link_global_constant(var, init).and(Some(var.is_compatible(&kind)))
-    }
-}
-
-impl<B: Backend, T: ToUniform> DataBind<B> for Global<T> {
-    type Data = T;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        let val = data.convert();
-        self.0.bind_to(out, &val, man, access);
-    }
-}
-
-impl<'a> DataLink<'a> for RawGlobal {
-    type Init = &'a str;
-    fn new() -> Self {
-        RawGlobal(None)
-    }
-    fn is_active(&self) -> bool {
-        self.0.is_some()
-    }
-    fn link_global_constant(&mut self, var: &shade::ConstVar, init: &Self::Init) ->
-                            Option<Result<(), shade::CompatibilityError>> {
-        if var.name.as_str() == *init {
-            self.0 = Some(var.location);
-            Some(Ok(()))
-        } else {
-            None
-        }
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        self.0.link_descriptor(binding, init)
     }
 }
 
-impl<B: Backend> DataBind<B> for RawGlobal {
-    type Data = shade::UniformValue;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               _: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        if let Some(loc) = self.0 {
-            out.global_constants.push((loc, *data));
+impl<B: Backend, T> DataBind<B> for DynamicConstantBuffer<T> {
+    type Data = (handle::raw::Buffer<B>, u64);
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(binding) = (self.0).0 {
+            let (ref buffer, offset) = *data;
+            let range = offset .. offset + mem::size_of::<T>() as u64;
+            out.descriptors.push((binding, RawDescriptor::ConstantBuffer(buffer.clone(), range)));
         }
     }
 }
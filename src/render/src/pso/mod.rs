@@ -19,219 +19,194 @@
 //! - The `Data` structure contains the data of all components, to be sent to the GPU.
 //!
 //! # Construction and Handling
-//! A Pipeline State Object is constructed by a factory, from its `Init` structure, a `Rasterizer`,
-//! a primitive type and a shader program.
+//! A Pipeline State Object is constructed by a `Device`, from its `Init` structure, a
+//! `Rasterizer`, a primitive type and the SPIR-V code of its shaders.
 //!
-//! After construction an `Encoder` can use the PSO along with a `Data` structure matching that
-//! PSO to process the shader pipeline, for instance, using the `draw` method.
+//! Shader interfaces are reflected from SPIR-V, which doesn't carry variable names. Components
+//! are therefore linked by position: vertex buffers provide consecutive attribute locations in
+//! declaration order, descriptors are identified by their binding in the first descriptor set
+//! and render targets are assigned to the color attachments in declaration order.
+//!
+//! After construction a `GraphicsEncoder` can use the PSO along with a `Data` structure matching
+//! that PSO to process the shader pipeline, for instance, using the `draw` method.
 
 pub mod buffer;
 pub mod resource;
 pub mod target;
 pub mod bundle;
 
-use std::default::Default;
 use std::error::Error;
 use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
 use core as c;
-pub use core::pso::Descriptor;
-pub use core::command::AccessInfo;
+use core::format::{ChannelType, Format};
+use core::pso::{AttributeDesc, BufferIndex, BufferOffset, ColorTargetDesc, DescriptorSetLayoutBinding,
+                DescriptorType, Location, VertexBufferDesc};
+use handle;
+
+/// Description of a pipeline, filled by linking the components of a PSO to the shader interface.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Descriptor {
+    /// Vertex buffers, in the order of their indices.
+    pub vertex_buffers: Vec<VertexBufferDesc>,
+    /// Vertex attributes provided by the vertex buffers.
+    pub attributes: Vec<AttributeDesc>,
+    /// Bindings of the descriptor set, a subset of the reflected ones.
+    pub descriptors: Vec<DescriptorSetLayoutBinding>,
+    /// Color attachments of the render pass, in the order of their indices.
+    pub color_targets: Vec<ColorTargetDesc>,
+    /// Whether the scissor rectangle is provided by the data.
+    pub scissor: bool,
+}
+
+impl Descriptor {
+    /// Create an empty descriptor.
+    pub fn new() -> Self {
+        Descriptor {
+            vertex_buffers: Vec::new(),
+            attributes: Vec::new(),
+            descriptors: Vec::new(),
+            color_targets: Vec::new(),
+            scissor: false,
+        }
+    }
+
+    /// Check that the attributes provide all vertex inputs of the program.
+    pub fn check_vertex_inputs(&self, info: &ProgramInfo) -> Result<(), InitError> {
+        for &(location, format) in &info.vertex_attributes {
+            match self.attributes.iter().find(|a| a.location == location) {
+                Some(a) if match_attribute(format, a.element.format) => (),
+                Some(a) => return Err(InitError::VertexImport(location, Some(a.element.format))),
+                None => return Err(InitError::VertexImport(location, None)),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check whether an attribute format can feed a vertex input of the given format.
+///
+/// Only the class of the channels is compared, as missing components are filled in
+/// and extra ones are ignored by the input assembler.
+fn match_attribute(shader: Format, code: Format) -> bool {
+    use self::ChannelType::*;
+    match (shader.1, code.1) {
+        (Int, Int) | (Uint, Uint) => true,
+        (Float, Float) | (Float, Unorm) | (Float, Inorm) | (Float, Srgb) => true,
+        _ => false,
+    }
+}
+
+/// Interface of the shaders of a graphics pipeline, as reflected from their SPIR-V code.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProgramInfo {
+    /// Formats of the vertex shader inputs, sorted by location.
+    pub vertex_attributes: Vec<(Location, Format)>,
+    /// Bindings of the first descriptor set, merged over all stages and sorted by binding.
+    pub descriptors: Vec<DescriptorSetLayoutBinding>,
+}
+
+/// A descriptor of the raw data set.
+#[allow(missing_docs)]
+#[derive(Clone, Debug)]
+pub enum RawDescriptor<B: c::Backend> {
+    ConstantBuffer(handle::raw::Buffer<B>, Range<u64>),
+    SampledImage(handle::raw::ShaderResourceView<B>),
+    Sampler(handle::raw::Sampler<B>),
+}
 
 /// A complete set of raw data that needs to be specified at run-time
 /// whenever we draw something with a PSO. This is what "data" struct
 /// gets transformed into when we call `encoder.draw(...)` with it.
 /// It doesn't have any typing information, since PSO knows what
 /// format and layout to expect from each resource.
+///
+/// The handles are kept alive by the encoder until its commands have been executed.
 #[allow(missing_docs)]
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct RawDataSet<B: c::Backend>{
-    pub vertex_buffers: c::pso::VertexBufferSet<B>,
-    pub constant_buffers: Vec<c::pso::ConstantBufferParam<B>>,
-    pub global_constants: Vec<(c::shade::Location, c::shade::UniformValue)>,
-    pub resource_views: Vec<c::pso::ResourceViewParam<B>>,
-    pub unordered_views: Vec<c::pso::UnorderedViewParam<B>>,
-    pub samplers: Vec<c::pso::SamplerParam<B>>,
-    pub pixel_targets: c::pso::PixelTargetSet<B>,
-    pub ref_values: c::state::RefValues,
-    pub scissor: c::target::Rect,
+    pub vertex_buffers: Vec<(BufferIndex, handle::raw::Buffer<B>, BufferOffset)>,
+    pub descriptors: Vec<(usize, RawDescriptor<B>)>,
+    pub color_targets: Vec<(usize, handle::raw::RenderTargetView<B>)>,
+    pub blend_ref: Option<c::target::ColorValue>,
+    pub scissor: Option<c::target::Rect>,
 }
 
 impl<B: c::Backend> RawDataSet<B> {
     /// Create an empty data set.
     pub fn new() -> RawDataSet<B> {
         RawDataSet {
-            vertex_buffers: c::pso::VertexBufferSet::new(),
-            constant_buffers: Vec::new(),
-            global_constants: Vec::new(),
-            resource_views: Vec::new(),
-            unordered_views: Vec::new(),
-            samplers: Vec::new(),
-            pixel_targets: c::pso::PixelTargetSet::new(),
-            ref_values: Default::default(),
-            scissor: c::target::Rect{x:0, y:0, w:1, h:1},
+            vertex_buffers: Vec::new(),
+            descriptors: Vec::new(),
+            color_targets: Vec::new(),
+            blend_ref: None,
+            scissor: None,
         }
     }
     /// Clear all contained data.
     pub fn clear(&mut self) {
-        self.vertex_buffers = c::pso::VertexBufferSet::new();
-        self.constant_buffers.clear();
-        self.global_constants.clear();
-        self.resource_views.clear();
-        self.unordered_views.clear();
-        self.samplers.clear();
-        self.pixel_targets = c::pso::PixelTargetSet::new();
-        self.ref_values = Default::default();
-        self.scissor = c::target::Rect{x:0, y:0, w:1, h:1};
-    }
-}
-
-/// Error matching an element inside the constant buffer.
-#[derive(Clone, Debug, PartialEq)]
-pub enum ElementError<S> {
-    /// Element not found.
-    NotFound(S),
-    /// Element offset mismatch.
-    Offset {
-        /// Element name.
-        name: S,
-        /// Element byte offset in the shader-side constant buffer.
-        shader_offset: c::pso::ElemOffset,
-        /// Element byte offset in the code-side constant buffer.
-        code_offset: c::pso::ElemOffset
-    },
-    /// Element format mismatch.
-    Format {
-        /// Element name.
-        name: S,
-        /// Element format in the shader-side constant buffer.
-        shader_format: c::shade::ConstFormat,
-        /// Element format in the code-side constant buffer.
-        code_format: c::shade::ConstFormat,
-    },
-}
-
-impl<S: fmt::Debug + fmt::Display> fmt::Display for ElementError<S> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            ElementError::NotFound(ref s) => write!(f, "{}: {:?}", self.description(), s),
-            ElementError::Offset{ ref name, ref shader_offset, ref code_offset } =>
-                write!(f, "{}: ({:?}, {:?}, {:?})", self.description(), name, shader_offset, code_offset),
-            ElementError::Format{ ref name, ref shader_format, ref code_format } =>
-                write!(f, "{}: ({:?}, {:?}, {:?})", self.description(), name, shader_format, code_format),
-        }
-    }
-}
-
-impl<S: fmt::Debug + fmt::Display> Error for ElementError<S> {
-    fn description(&self) -> &str {
-        match *self {
-            ElementError::NotFound(_) => "Element not found",
-            ElementError::Offset{..} => "Element offset mismatch",
-            ElementError::Format{..} => "Element format mismatch",
-        }
-    }
-}
-
-impl<'a> From<ElementError<&'a str>> for ElementError<String> {
-    fn from(other: ElementError<&'a str>) -> ElementError<String> {
-        use self::ElementError::*;
-        match other {
-            NotFound(s) => NotFound(s.to_owned()),
-            Offset{ name, shader_offset, code_offset } => Offset{
-                name: name.to_owned(),
-                shader_offset: shader_offset,
-                code_offset: code_offset,
-            },
-            Format{ name, shader_format, code_format } => Format{
-                name: name.to_owned(),
-                shader_format: shader_format,
-                code_format: code_format,
-            },
-        }
+        self.vertex_buffers.clear();
+        self.descriptors.clear();
+        self.color_targets.clear();
+        self.blend_ref = None;
+        self.scissor = None;
     }
 }
 
 /// Failure to initilize the link between the shader and the data.
 #[derive(Clone, Debug, PartialEq)]
-pub enum InitError<S> {
+pub enum InitError {
     /// Vertex attribute mismatch.
-    VertexImport(S, Option<c::format::Format>),
-    /// Constant buffer mismatch.
-    ConstantBuffer(S, Option<ElementError<S>>),
-    /// Global constant mismatch.
-    GlobalConstant(S, Option<c::shade::CompatibilityError>),
-    /// Shader resource view mismatch.
-    ResourceView(S, Option<()>),
-    /// Unordered access view mismatch.
-    UnorderedView(S, Option<()>),
-    /// Sampler mismatch.
-    Sampler(S, Option<()>),
-    /// Pixel target mismatch.
-    PixelExport(S, Option<c::format::Format>),
+    VertexImport(Location, Option<Format>),
+    /// Descriptor mismatch, with the type expected by the data.
+    Descriptor(usize, Option<DescriptorType>),
+    /// Descriptors are used in a set other than the first one.
+    DescriptorSet(usize),
 }
 
-impl<'a> From<InitError<&'a str>> for InitError<String> {
-    fn from(other: InitError<&'a str>) -> InitError<String> {
-        use self::InitError::*;
-        match other {
-            VertexImport(s, v) => VertexImport(s.to_owned(), v),
-            ConstantBuffer(s, v) => ConstantBuffer(s.to_owned(), v.map(|e| e.into())),
-            GlobalConstant(s, v) => GlobalConstant(s.to_owned(), v),
-            ResourceView(s, v) => ResourceView(s.to_owned(), v),
-            UnorderedView(s, v) => UnorderedView(s.to_owned(), v),
-            Sampler(s, v) => Sampler(s.to_owned(), v),
-            PixelExport(s, v) => PixelExport(s.to_owned(), v),
-        }
-    }
-}
-
-impl<S: fmt::Debug + fmt::Display> fmt::Display for InitError<S> {
+impl fmt::Display for InitError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         use self::InitError::*;
         let desc = self.description();
         match *self {
-            VertexImport(ref name, format) => write!(f, "{}: ({}, {:?})", desc, name, format),
-            ConstantBuffer(ref name, ref opt) => write!(f, "{}: ({}, {:?})", desc, name, opt),
-            GlobalConstant(ref name, opt) => write!(f, "{}: ({}, {:?})", desc, name, opt),
-            ResourceView(ref name, opt) => write!(f, "{}: ({}, {:?})", desc, name, opt),
-            UnorderedView(ref name, opt) => write!(f, "{}: ({}, {:?})", desc, name, opt),
-            Sampler(ref name, opt) => write!(f, "{}: ({}, {:?})", desc, name, opt),
-            PixelExport(ref name, format) => write!(f, "{}: ({}, {:?})", desc, name, format),
+            VertexImport(location, format) => write!(f, "{}: ({}, {:?})", desc, location, format),
+            Descriptor(binding, ty) => write!(f, "{}: ({}, {:?})", desc, binding, ty),
+            DescriptorSet(set) => write!(f, "{}: {}", desc, set),
         }
     }
 }
 
-impl<S: fmt::Debug + fmt::Display> Error for InitError<S> {
+impl Error for InitError {
     fn description(&self) -> &str {
         use self::InitError::*;
         match *self {
             VertexImport(_, None) => "Vertex attribute not found",
             VertexImport(..) => "Vertex attribute format mismatch",
-            ConstantBuffer(_, None) => "Constant buffer not found",
-            ConstantBuffer(..) => "Constant buffer element mismatch",
-            GlobalConstant(_, None) => "Global constant not found",
-            GlobalConstant(..) => "Global constant format mismatch",
-            ResourceView(_, None) => "Shader resource view not found",
-            ResourceView(..) => "Shader resource view mismatch",
-            UnorderedView(_, None) => "Unordered access view not found",
-            UnorderedView(..) => "Unordered access view mismatch",
-            Sampler(_, None) => "Sampler not found",
-            Sampler(..) => "Sampler mismatch",
-            PixelExport(_, None) => "Pixel target not found",
-            PixelExport(..) => "Pixel target mismatch",
+            Descriptor(_, None) => "Descriptor not found",
+            Descriptor(..) => "Descriptor type mismatch",
+            DescriptorSet(_) => "Only the first descriptor set is supported",
         }
     }
+}
 
-    fn cause(&self) -> Option<&Error> {
-        if let InitError::ConstantBuffer(_, Some(ref e)) = *self {
-            Some(e)
-        } else {
-            None
-        }
+/// Link a descriptor component to the binding given by its init value.
+fn link_binding(
+    slot: &mut Option<usize>,
+    binding: &DescriptorSetLayoutBinding,
+    init: usize,
+    ty: DescriptorType,
+) -> Option<Result<(), DescriptorType>> {
+    if binding.binding != init {
+        None
+    } else if binding.ty != ty {
+        Some(Err(ty))
+    } else {
+        *slot = Some(init);
+        Some(Ok(()))
     }
 }
 
-
 /// A service trait implemented by the "init" structure of PSO.
 pub trait PipelineInit {
     /// The associated "meta" struct.
@@ -239,8 +214,7 @@ pub trait PipelineInit {
     /// Attempt to map a PSO descriptor to a give shader program,
     /// represented by `ProgramInfo`. Returns an instance of the
     /// "meta" struct upon successful mapping.
-    fn link_to<'s>(&self, &mut Descriptor, &'s c::shade::ProgramInfo)
-               -> Result<Self::Meta, InitError<&'s str>>;
+    fn link_to(&self, &mut Descriptor, &ProgramInfo) -> Result<Self::Meta, InitError>;
 }
 
 /// a service trait implemented the "data" structure of PSO.
@@ -248,33 +222,65 @@ pub trait PipelineData<B: c::Backend> {
     /// The associated "meta" struct.
     type Meta;
     /// Dump all the contained data into the raw data set,
-    /// given the mapping ("meta"), and a handle manager.
-    fn bake_to(&self,
-               &mut RawDataSet<B>,
-               &Self::Meta,
-               &mut c::handle::Manager<B>,
-               &mut AccessInfo<B>);
+    /// given the mapping ("meta").
+    fn bake_to(&self, &mut RawDataSet<B>, &Self::Meta);
 }
 
 /// A strongly typed Pipleline State Object. See the module documentation for more information.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct PipelineState<B: c::Backend, M>(c::handle::RawPipelineState<B>,
-                                             c::Primitive, M);
+#[derive(Clone, Debug)]
+pub struct PipelineState<B: c::Backend, M> {
+    raw: handle::raw::GraphicsPipeline<B>,
+    layout: handle::raw::PipelineLayout<B>,
+    set_layout: handle::raw::DescriptorSetLayout<B>,
+    render_pass: handle::raw::RenderPass<B>,
+    descriptors: Arc<Vec<DescriptorSetLayoutBinding>>,
+    meta: M,
+}
 
 impl<B: c::Backend, M> PipelineState<B, M> {
-    /// Create a new PSO from a raw handle and the "meta" instance.
-    pub fn new(raw: c::handle::RawPipelineState<B>, prim: c::Primitive, meta: M)
-               -> PipelineState<B, M> {
-        PipelineState(raw, prim, meta)
+    /// Create a new PSO from its raw handles, the bindings of its descriptor set
+    /// and the "meta" instance.
+    pub fn new(
+        raw: handle::raw::GraphicsPipeline<B>,
+        layout: handle::raw::PipelineLayout<B>,
+        set_layout: handle::raw::DescriptorSetLayout<B>,
+        render_pass: handle::raw::RenderPass<B>,
+        descriptors: Vec<DescriptorSetLayoutBinding>,
+        meta: M,
+    ) -> PipelineState<B, M> {
+        PipelineState {
+            raw,
+            layout,
+            set_layout,
+            render_pass,
+            descriptors: Arc::new(descriptors),
+            meta,
+        }
     }
     /// Get a raw handle reference.
-    pub fn get_handle(&self) -> &c::handle::RawPipelineState<B> {
-        &self.0
+    pub fn get_handle(&self) -> &handle::raw::GraphicsPipeline<B> {
+        &self.raw
+    }
+    /// Get the pipeline layout.
+    pub fn get_layout(&self) -> &handle::raw::PipelineLayout<B> {
+        &self.layout
+    }
+    /// Get the layout of the descriptor set.
+    pub fn get_set_layout(&self) -> &handle::raw::DescriptorSetLayout<B> {
+        &self.set_layout
+    }
+    /// Get the render pass the pipeline has been created for.
+    pub fn get_render_pass(&self) -> &handle::raw::RenderPass<B> {
+        &self.render_pass
+    }
+    /// Get the bindings of the descriptor set.
+    pub fn get_descriptors(&self) -> &[DescriptorSetLayoutBinding] {
+        &self.descriptors
     }
     /// Get a "meta" struct reference. Can be used by the user to check
     /// what resources are actually used and what not.
     pub fn get_meta(&self) -> &M {
-        &self.2
+        &self.meta
     }
 }
 
@@ -287,33 +293,15 @@ pub trait DataLink<'a>: Sized {
     fn new() -> Self;
     /// Check if this link is actually used by the shader.
     fn is_active(&self) -> bool;
-    /// Attempt to link with a vertex buffer containing multiple attributes.
-    fn link_vertex_buffer(&mut self, _: c::pso::BufferIndex, _: &Self::Init) ->
-                          Option<c::pso::VertexBufferDesc> { None }
-    /// Attempt to link with a vertex attribute.
-    fn link_input(&mut self, _: &c::shade::AttributeVar, _: &Self::Init) ->
-                  Option<Result<c::pso::AttributeDesc, c::format::Format>> { None }
-    /// Attempt to link with a constant buffer.
-    fn link_constant_buffer<'b>(&mut self, _: &'b c::shade::ConstantBufferVar, _: &Self::Init) ->
-                            Option<Result<c::pso::ConstantBufferDesc, ElementError<&'b str>>> { None }
-    /// Attempt to link with a global constant.
-    fn link_global_constant(&mut self, _: &c::shade::ConstVar, _: &Self::Init) ->
-                            Option<Result<(), c::shade::CompatibilityError>> { None }
-    /// Attempt to link with an output render target (RTV).
-    fn link_output(&mut self, _: &c::shade::OutputVar, _: &Self::Init) ->
-                   Option<Result<c::pso::ColorTargetDesc, c::format::Format>> { None }
-    /// Attempt to link with a depth-stencil target (DSV).
-    fn link_depth_stencil(&mut self, _: &Self::Init) ->
-                          Option<c::pso::DepthStencilDesc> { None }
-    /// Attempt to link with a shader resource (SRV).
-    fn link_resource_view(&mut self, _: &c::shade::TextureVar, _: &Self::Init) ->
-                          Option<Result<c::pso::ResourceViewDesc, c::format::Format>> { None }
-    /// Attempt to link with an unordered access (UAV).
-    fn link_unordered_view(&mut self, _: &c::shade::UnorderedVar, _: &Self::Init) ->
-                           Option<Result<c::pso::UnorderedViewDesc, c::format::Format>> { None }
-    /// Attempt to link with a sampler.
-    fn link_sampler(&mut self, _: &c::shade::SamplerVar, _: &Self::Init)
-                    -> Option<c::pso::SamplerDesc> { None }
+    /// Attempt to link with a vertex buffer, providing attributes starting at the given location.
+    fn link_vertex_buffer(&mut self, _: BufferIndex, _: Location, _: &Self::Init) ->
+                          Option<(VertexBufferDesc, Vec<AttributeDesc>)> { None }
+    /// Attempt to link with a descriptor binding.
+    fn link_descriptor(&mut self, _: &DescriptorSetLayoutBinding, _: &Self::Init) ->
+                       Option<Result<(), DescriptorType>> { None }
+    /// Attempt to link with the color attachment of the given index.
+    fn link_output(&mut self, _: usize, _: &Self::Init) ->
+                   Option<ColorTargetDesc> { None }
     /// Attempt to enable scissor test.
     fn link_scissor(&mut self) -> bool { false }
 }
@@ -324,9 +312,5 @@ pub trait DataBind<B: c::Backend> {
     /// The associated "data" type - a member of the PSO "data" struct.
     type Data;
     /// Dump the given data into the raw data set.
-    fn bind_to(&self,
-               &mut RawDataSet<B>,
-               &Self::Data,
-               &mut c::handle::Manager<B>,
-               &mut AccessInfo<B>);
+    fn bind_to(&self, &mut RawDataSet<B>, &Self::Data);
 }
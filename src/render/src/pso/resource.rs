@@ -1,214 +1,139 @@
 //! Resource components for a PSO.
 
+use std::fmt;
 use std::marker::PhantomData;
-use core::{ResourceViewSlot, UnorderedViewSlot, SamplerSlot, Backend};
-use core::{handle, pso, shade};
-use core::memory::Typed;
-use core::format::Format;
-use super::{DataLink, DataBind, RawDataSet, AccessInfo};
+use core::Backend;
+use core::pso::{DescriptorSetLayoutBinding, DescriptorType};
+use handle;
+use super::{DataLink, DataBind, RawDataSet, RawDescriptor, link_binding};
 
-/// Shader resource component (SRV). Typically is a view into some texture,
-/// but can also be a buffer.
+/// Shader resource component (SRV), a view into some texture.
 ///
-/// - init: `&str` = name of the resource
+/// - init: `usize` = binding of the resource
 /// - data: `ShaderResourceView<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct ShaderResource<T>(
-    RawShaderResource,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+pub struct ShaderResource<T>(RawShaderResource, PhantomData<T>);
+
+impl<T> Clone for ShaderResource<T> {
+    fn clone(&self) -> Self {
+        ShaderResource(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T> fmt::Debug for ShaderResource<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("ShaderResource").field(&self.0).finish()
+    }
+}
 
 /// Raw (untyped) shader resource (SRV).
 ///
-/// - init: `&str` = name of the resource. This may change in the future.
-/// - data: `RawShaderResourceView`
+/// - init: `usize` = binding of the resource
+/// - data: `raw::ShaderResourceView`
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RawShaderResource(Option<(ResourceViewSlot, shade::Usage)>);
-
-/// Unordered access component (UAV). A writable resource (texture/buffer)
-/// with no defined access order across simultaneously executing shaders.
-/// Supported on DX10 and higher.
-///
-/// - init: `&str` = name of the resource
-/// - data: `UnorderedAccessView<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct UnorderedAccess<T>(
-    Option<(UnorderedViewSlot, shade::Usage)>,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+pub struct RawShaderResource(Option<usize>);
 
 /// Sampler component.
 ///
-/// - init: `&str` = name of the sampler
-/// - data: `Sampler`
+/// - init: `usize` = binding of the sampler
+/// - data: `raw::Sampler`
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct Sampler(Option<(SamplerSlot, shade::Usage)>);
+pub struct Sampler(Option<usize>);
 
 /// A convenience type for a texture paired with a sampler.
-/// It only makes sense for DX9 class hardware, where every texture by default
-/// is bundled with a sampler, hence they are represented by the same name.
-/// In DX10 and higher samplers are totally separated from the textures.
 ///
-/// - init: `&str` = name of the sampler/texture (assuming they match)
-/// - data: (`ShaderResourceView<T>`, `Sampler`)
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+/// The texture and the sampler are separate descriptors in the shader.
+///
+/// - init: `(usize, usize)` = bindings of the texture and the sampler
+/// - data: (`ShaderResourceView<T>`, `raw::Sampler`)
+#[derive(Clone, Debug)]
 pub struct TextureSampler<T>(ShaderResource<T>, Sampler);
 
 impl<'a, T> DataLink<'a> for ShaderResource<T> {
-    type Init = &'a str;
+    type Init = usize;
     fn new() -> Self {
         ShaderResource(RawShaderResource(None), PhantomData)
     }
     fn is_active(&self) -> bool {
         self.0.is_active()
     }
-    fn link_resource_view(&mut self, var: &shade::TextureVar, init: &Self::Init)
-                          -> Option<Result<pso::ResourceViewDesc, Format>> {
-        self.0.link_resource_view(var, init)
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        self.0.link_descriptor(binding, init)
     }
 }
 
 impl<B: Backend, T> DataBind<B> for ShaderResource<T> {
     type Data = handle::ShaderResourceView<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        self.0.bind_to(out, data.raw(), man, access)
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, data)
     }
 }
 
 impl<'a> DataLink<'a> for RawShaderResource {
-    type Init = &'a str;
+    type Init = usize;
     fn new() -> Self {
         RawShaderResource(None)
     }
     fn is_active(&self) -> bool {
         self.0.is_some()
     }
-    fn link_resource_view(&mut self, var: &shade::TextureVar, init: &Self::Init)
-                          -> Option<Result<pso::ResourceViewDesc, Format>> {
-        if *init == var.name {
-            self.0 = Some((var.slot, var.usage));
-            Some(Ok(var.usage)) //TODO: check format
-        }else {
-            None
-        }
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        link_binding(&mut self.0, binding, *init, DescriptorType::SampledImage)
     }
 }
 
 impl<B: Backend> DataBind<B> for RawShaderResource {
-    type Data = handle::RawShaderResourceView<B>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        // TODO: register buffer view source access
-        if let Some((slot, usage)) = self.0 {
-            let view = man.ref_srv(data).clone();
-            out.resource_views.push(pso::ResourceViewParam(view, usage, slot));
-        }
-    }
-}
-
-impl<'a, T> DataLink<'a> for UnorderedAccess<T> {
-    type Init = &'a str;
-    fn new() -> Self {
-        UnorderedAccess(None, PhantomData)
-    }
-    fn is_active(&self) -> bool {
-        self.0.is_some()
-    }
-    fn link_unordered_view(&mut self, var: &shade::UnorderedVar, init: &Self::Init)
-                           -> Option<Result<pso::UnorderedViewDesc, Format>> {
-        if *init == var.name {
-            self.0 = Some((var.slot, var.usage));
-            Some(Ok(var.usage)) //TODO: check format
-        }else {
-            None
-        }
-    }
-}
-
-impl<B: Backend, T> DataBind<B> for UnorderedAccess<T> {
-    type Data = handle::UnorderedAccessView<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        // TODO: register buffer view source access
-        if let Some((slot, usage)) = self.0 {
-            let view =  man.ref_uav(data.raw()).clone();
-            out.unordered_views.push(pso::UnorderedViewParam(view, usage, slot));
+    type Data = handle::raw::ShaderResourceView<B>;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(binding) = self.0 {
+            out.descriptors.push((binding, RawDescriptor::SampledImage(data.clone())));
         }
     }
 }
 
 impl<'a> DataLink<'a> for Sampler {
-    type Init = &'a str;
+    type Init = usize;
     fn new() -> Self {
         Sampler(None)
     }
     fn is_active(&self) -> bool {
         self.0.is_some()
     }
-    fn link_sampler(&mut self, var: &shade::SamplerVar, init: &Self::Init)
-                    -> Option<pso::SamplerDesc> {
-        if *init == var.name {
-            self.0 = Some((var.slot, var.usage));
-            Some(var.usage)
-        }else {
-            None
-        }
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        link_binding(&mut self.0, binding, *init, DescriptorType::Sampler)
     }
 }
 
 impl<B: Backend> DataBind<B> for Sampler {
-    type Data = handle::Sampler<B>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        if let Some((slot, usage)) = self.0 {
-            let sm = man.ref_sampler(data).clone();
-            out.samplers.push(pso::SamplerParam(sm, usage, slot));
+    type Data = handle::raw::Sampler<B>;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(binding) = self.0 {
+            out.descriptors.push((binding, RawDescriptor::Sampler(data.clone())));
         }
     }
 }
 
 impl<'a, T> DataLink<'a> for TextureSampler<T> {
-    type Init = &'a str;
+    type Init = (usize, usize);
     fn new() -> Self {
         TextureSampler(ShaderResource::new(), Sampler::new())
     }
     fn is_active(&self) -> bool {
-        self.0.is_active()
-    }
-    fn link_resource_view(&mut self, var: &shade::TextureVar, init: &Self::Init)
-                          -> Option<Result<pso::ResourceViewDesc, Format>> {
-        self.0.link_resource_view(var, init)
+        self.0.is_active() || self.1.is_active()
     }
-    fn link_sampler(&mut self, var: &shade::SamplerVar, init: &Self::Init) -> Option<pso::SamplerDesc> {
-        self.1.link_sampler(var, init)
+    fn link_descriptor(&mut self, binding: &DescriptorSetLayoutBinding, init: &Self::Init)
+                       -> Option<Result<(), DescriptorType>> {
+        self.0.link_descriptor(binding, &init.0)
+            .or_else(|| self.1.link_descriptor(binding, &init.1))
     }
 }
 
 impl<B: Backend, T> DataBind<B> for TextureSampler<T> {
-    type Data = (handle::ShaderResourceView<B, T>, handle::Sampler<B>);
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        self.0.bind_to(out, &data.0, man, access);
-        self.1.bind_to(out, &data.1, man, access);
+    type Data = (handle::ShaderResourceView<B, T>, handle::raw::Sampler<B>);
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, &data.0);
+        self.1.bind_to(out, &data.1);
     }
 }
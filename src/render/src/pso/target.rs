@@ -1,75 +1,57 @@
 //! Render target components for a PSO.
+//!
+//! Depth and stencil targets aren't supported yet, as render passes are created
+//! with color attachments only.
 
+use std::fmt;
 use std::marker::PhantomData;
-use core::{ColorSlot, Backend};
-use core::{format, handle, pso, state, target};
-use core::memory::Typed;
-use core::shade::OutputVar;
-use super::{DataLink, DataBind, RawDataSet, AccessInfo};
+use core::Backend;
+use core::{format, pso, state, target};
+use handle;
+use super::{DataLink, DataBind, RawDataSet};
 
 /// Render target component. Typically points to a color-formatted texture.
 ///
-/// - init: `&str` = name of the target
+/// - init: `()`
 /// - data: `RenderTargetView<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RenderTarget<T>(
-    Option<ColorSlot>,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+pub struct RenderTarget<T>(RawRenderTarget, PhantomData<T>);
+
+impl<T> Clone for RenderTarget<T> {
+    fn clone(&self) -> Self {
+        RenderTarget(self.0.clone(), PhantomData)
+    }
+}
+
+impl<T> fmt::Debug for RenderTarget<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("RenderTarget").field(&self.0).finish()
+    }
+}
 
 /// Render target component with active blending mode.
 ///
-/// - init: (`&str`, `ColorMask`, `Blend` = blending state)
+/// - init: (`ColorMask`, `Blend` = blending state)
 /// - data: `RenderTargetView<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct BlendTarget<T>(
-    RawRenderTarget,
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+pub struct BlendTarget<T>(RawRenderTarget, PhantomData<T>);
 
-/// Raw (untyped) render target component with optional blending.
-///
-/// - init: (`&str`, `Format`, `ColorMask`, `Option<Blend>`)
-/// - data: `RawRenderTargetView`
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct RawRenderTarget(Option<ColorSlot>);
-
-/// Depth target component.
-///
-/// - init: `Depth` = depth state
-/// - data: `DepthStencilView<T>`
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct DepthTarget<T>(
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+impl<T> Clone for BlendTarget<T> {
+    fn clone(&self) -> Self {
+        BlendTarget(self.0.clone(), PhantomData)
+    }
+}
 
-/// Stencil target component.
-///
-/// - init: `Stencil` = stencil state
-/// - data: (`DepthStencilView<T>`, `(front, back)` = stencil reference values)
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct StencilTarget<T>(
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+impl<T> fmt::Debug for BlendTarget<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("BlendTarget").field(&self.0).finish()
+    }
+}
 
-/// Depth + stencil target component.
+/// Raw (untyped) render target component with optional blending.
 ///
-/// - init: (`Depth` = depth state, `Stencil` = stencil state)
-/// - data: (`DepthStencilView<T>`, `(front, back)` = stencil reference values)
-#[derive(Derivative)]
-#[derivative(Clone, Debug, Eq, Hash, PartialEq)]
-pub struct DepthStencilTarget<T>(
-    #[derivative(Hash = "ignore", PartialEq = "ignore")]
-    PhantomData<T>
-);
+/// - init: (`Format`, `ColorMask`, `Option<Blend>`)
+/// - data: `raw::RenderTargetView`
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct RawRenderTarget(Option<usize>);
 
 /// Scissor component. Sets up the scissor test for rendering.
 ///
@@ -86,168 +68,72 @@ pub struct Scissor(bool);
 pub struct BlendRef;
 
 impl<'a, T: format::RenderFormat> DataLink<'a> for RenderTarget<T> {
-    type Init = &'a str;
+    type Init = ();
     fn new() -> Self {
-        RenderTarget(None, PhantomData)
+        RenderTarget(RawRenderTarget(None), PhantomData)
     }
     fn is_active(&self) -> bool {
-        self.0.is_some()
+        self.0.is_active()
     }
-    fn link_output(&mut self, out: &OutputVar, init: &Self::Init) ->
-                   Option<Result<pso::ColorTargetDesc, format::Format>> {
-        if out.name.is_empty() || &out.name == init {
-            self.0 = Some(out.slot);
-            let desc = (T::get_format(), state::MASK_ALL.into());
-            Some(Ok(desc))
-        } else {
-            None
-        }
+    fn link_output(&mut self, index: usize, _: &Self::Init) -> Option<pso::ColorTargetDesc> {
+        self.0.link_output(index, &(T::get_format(), state::MASK_ALL, None))
     }
 }
 
 impl<B: Backend, T> DataBind<B> for RenderTarget<T> {
     type Data = handle::RenderTargetView<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        if let Some(slot) = self.0 {
-            out.pixel_targets.add_color(slot, man.ref_rtv(data.raw()), data.raw().get_dimensions());
-        }
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, data)
     }
 }
 
-
 impl<'a, T: format::BlendFormat> DataLink<'a> for BlendTarget<T> {
-    type Init = (&'a str, state::ColorMask, state::Blend);
+    type Init = (state::ColorMask, state::Blend);
     fn new() -> Self {
         BlendTarget(RawRenderTarget(None), PhantomData)
     }
     fn is_active(&self) -> bool {
         self.0.is_active()
     }
-    fn link_output(&mut self, out: &OutputVar, init: &Self::Init) ->
-                   Option<Result<pso::ColorTargetDesc, format::Format>> {
-        self.0.link_output(out, &(init.0, T::get_format(), init.1, Some(init.2)))
+    fn link_output(&mut self, index: usize, init: &Self::Init) -> Option<pso::ColorTargetDesc> {
+        self.0.link_output(index, &(T::get_format(), init.0, Some(init.1)))
     }
 }
 
 impl<B: Backend, T> DataBind<B> for BlendTarget<T> {
     type Data = handle::RenderTargetView<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               access: &mut AccessInfo<B>) {
-        self.0.bind_to(out, data.raw(), man, access)
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        self.0.bind_to(out, data)
     }
 }
 
 impl<'a> DataLink<'a> for RawRenderTarget {
-    type Init = (&'a str, format::Format, state::ColorMask, Option<state::Blend>);
+    type Init = (format::Format, state::ColorMask, Option<state::Blend>);
     fn new() -> Self {
         RawRenderTarget(None)
     }
     fn is_active(&self) -> bool {
         self.0.is_some()
     }
-    fn link_output(&mut self, out: &OutputVar, init: &Self::Init) ->
-                   Option<Result<pso::ColorTargetDesc, format::Format>> {
-        if out.name.is_empty() || &out.name == init.0 {
-            self.0 = Some(out.slot);
-            let desc = (init.1, pso::ColorInfo {
-                mask: init.2,
-                color: init.3.map(|b| b.color),
-                alpha: init.3.map(|b| b.alpha),
-            });
-            Some(Ok(desc))
-        }else {
-            None
-        }
+    fn link_output(&mut self, index: usize, init: &Self::Init) -> Option<pso::ColorTargetDesc> {
+        self.0 = Some(index);
+        Some((init.0, pso::ColorInfo {
+            mask: init.1,
+            color: init.2.map(|b| b.color),
+            alpha: init.2.map(|b| b.alpha),
+        }))
     }
 }
 
 impl<B: Backend> DataBind<B> for RawRenderTarget {
-    type Data = handle::RawRenderTargetView<B>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        if let Some(slot) = self.0 {
-            out.pixel_targets.add_color(slot, man.ref_rtv(data), data.get_dimensions());
+    type Data = handle::raw::RenderTargetView<B>;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        if let Some(index) = self.0 {
+            out.color_targets.push((index, data.clone()));
         }
     }
 }
 
-
-impl<'a, T: format::DepthFormat> DataLink<'a> for DepthTarget<T> {
-    type Init = state::Depth;
-    fn new() -> Self { DepthTarget(PhantomData) }
-    fn is_active(&self) -> bool { true }
-    fn link_depth_stencil(&mut self, init: &Self::Init) -> Option<pso::DepthStencilDesc> {
-        Some((T::get_format(), (*init).into()))
-    }
-}
-
-impl<B: Backend, T> DataBind<B> for DepthTarget<T> {
-    type Data = handle::DepthStencilView<B, T>;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        let dsv = data.raw();
-        out.pixel_targets.add_depth_stencil(man.ref_dsv(dsv), true, false, dsv.get_dimensions());
-    }
-}
-
-impl<'a, T: format::StencilFormat> DataLink<'a> for StencilTarget<T> {
-    type Init = state::Stencil;
-    fn new() -> Self { StencilTarget(PhantomData) }
-    fn is_active(&self) -> bool { true }
-    fn link_depth_stencil(&mut self, init: &Self::Init) -> Option<pso::DepthStencilDesc> {
-        Some((T::get_format(), (*init).into()))
-    }
-}
-
-impl<B: Backend, T> DataBind<B> for StencilTarget<T> {
-    type Data = (handle::DepthStencilView<B, T>, (target::Stencil, target::Stencil));
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        let dsv = data.0.raw();
-        out.pixel_targets.add_depth_stencil(man.ref_dsv(dsv), false, true, dsv.get_dimensions());
-        out.ref_values.stencil = data.1;
-    }
-}
-
-impl<'a, T: format::DepthStencilFormat> DataLink<'a> for DepthStencilTarget<T> {
-    type Init = (state::Depth, state::Stencil);
-    fn new() -> Self { DepthStencilTarget(PhantomData) }
-    fn is_active(&self) -> bool { true }
-    fn link_depth_stencil(&mut self, init: &Self::Init) -> Option<pso::DepthStencilDesc> {
-        Some((T::get_format(), (*init).into()))
-    }
-}
-
-impl<B: Backend, T> DataBind<B> for DepthStencilTarget<T> {
-    type Data = (handle::DepthStencilView<B, T>, (target::Stencil, target::Stencil));
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               man: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        let dsv = data.0.raw();
-        out.pixel_targets.add_depth_stencil(man.ref_dsv(dsv), true, true, dsv.get_dimensions());
-        out.ref_values.stencil = data.1;
-    }
-}
-
-
 impl<'a> DataLink<'a> for Scissor {
     type Init = ();
     fn new() -> Self { Scissor(false) }
@@ -257,12 +143,8 @@ impl<'a> DataLink<'a> for Scissor {
 
 impl<B: Backend> DataBind<B> for Scissor {
     type Data = target::Rect;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               _: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        out.scissor = *data;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        out.scissor = Some(*data);
     }
 }
 
@@ -274,11 +156,7 @@ impl<'a> DataLink<'a> for BlendRef {
 
 impl<B: Backend> DataBind<B> for BlendRef {
     type Data = target::ColorValue;
-    fn bind_to(&self,
-               out: &mut RawDataSet<B>,
-               data: &Self::Data,
-               _: &mut handle::Manager<B>,
-               _: &mut AccessInfo<B>) {
-        out.ref_values.blend = *data;
+    fn bind_to(&self, out: &mut RawDataSet<B>, data: &Self::Data) {
+        out.blend_ref = Some(*data);
     }
 }
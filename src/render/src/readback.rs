@@ -0,0 +1,98 @@
+//! Reading back the contents of buffers and images on the host.
+
+use std::{mem, ptr};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use core::Device as CoreDevice;
+use core::memory::Pod;
+use handle::{self, Epoch};
+use {Backend, Gpu};
+
+/// Epoch of the submission containing the copies of an encoder, 0 until it's flushed.
+pub(crate) type SubmissionEpoch = Arc<AtomicUsize>;
+
+/// Layout of the rows of data in a staging buffer.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct RowLayout {
+    /// Size of the data of each row in bytes.
+    pub row_size: usize,
+    /// Distance between the starts of two rows in bytes.
+    pub row_pitch: usize,
+    /// Number of rows.
+    pub rows: usize,
+}
+
+/// Data copied into host visible memory by a `GraphicsEncoder`, like a future of its contents.
+///
+/// The data can be read once the submission containing the copy has completed on the GPU.
+/// Padding added to the rows of images for the copy is stripped when reading.
+#[derive(Debug)]
+pub struct Readback<B: Backend, T> {
+    staging: handle::raw::Buffer<B>,
+    epoch: SubmissionEpoch,
+    layout: RowLayout,
+    phantom: PhantomData<T>,
+}
+
+impl<B: Backend, T: Pod> Readback<B, T> {
+    pub(crate) fn new(staging: handle::raw::Buffer<B>, epoch: SubmissionEpoch, layout: RowLayout) -> Self {
+        Readback {
+            staging,
+            epoch,
+            layout,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Epoch of the submission containing the copy, if it has been flushed already.
+    pub fn epoch(&self) -> Option<Epoch> {
+        match self.epoch.load(Ordering::Acquire) {
+            0 => None,
+            epoch => Some(epoch),
+        }
+    }
+
+    /// Whether the copy has completed and the data can be read without blocking.
+    pub fn is_ready(&self, gpu: &mut Gpu<B>) -> bool {
+        self.epoch().map_or(false, |epoch| epoch <= gpu.poll())
+    }
+
+    /// Read the data if the copy has completed, without blocking.
+    pub fn poll(&self, gpu: &mut Gpu<B>) -> Option<Vec<T>> {
+        if self.is_ready(gpu) {
+            Some(self.read(gpu))
+        } else {
+            None
+        }
+    }
+
+    /// Block until the copy has completed and read the data.
+    ///
+    /// Panics if the submission containing the copy hasn't been flushed.
+    pub fn wait(self, gpu: &mut Gpu<B>) -> Vec<T> {
+        let epoch = self.epoch().expect("The readback has not been submitted");
+        gpu.wait_for(epoch);
+        self.read(gpu)
+    }
+
+    fn read(&self, gpu: &Gpu<B>) -> Vec<T> {
+        let RowLayout { row_size, row_pitch, rows } = self.layout;
+        let size = row_size * rows;
+        let reader = gpu.ref_device()
+            .ref_raw()
+            .read_mapping::<u8>(self.staging.resource(), 0, (row_pitch * rows) as u64)
+            .expect("Unable to map a readback buffer");
+
+        let mut data = Vec::<T>::with_capacity(size / mem::size_of::<T>());
+        unsafe {
+            let dst = data.as_mut_ptr() as *mut u8;
+            for (i, row) in reader.chunks(row_pitch).enumerate() {
+                ptr::copy_nonoverlapping(row.as_ptr(), dst.offset((i * row_size) as isize), row_size);
+            }
+            data.set_len(size / mem::size_of::<T>());
+        }
+        data
+    }
+}
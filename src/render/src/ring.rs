@@ -0,0 +1,139 @@
+//! Ring buffer for data written by the CPU every frame, like the constants of each draw.
+
+use std::{cmp, mem, ptr};
+use std::sync::Arc;
+
+use core::{self, memory, Device as CoreDevice};
+use core::device::WaitFor;
+use device::{CreationError, Device};
+use {buffer, handle, Backend, Gpu};
+
+/// A persistently mapped buffer, split into a region per frame in flight.
+///
+/// Data is sub-allocated linearly from the region of the current frame. A region is
+/// reused once the submissions made during its frame have completed, which is tracked
+/// by a fence per region, so no buffer update needs to be recorded for each draw.
+///
+/// Every `begin_frame` has to be matched by an `end_frame`.
+pub struct FrameRing<B: Backend> {
+    // Released before the buffer is dropped.
+    _mapping: B::Mapping,
+    memory: *mut u8,
+    buffer: handle::raw::Buffer<B>,
+    fences: Vec<handle::raw::Fence<B>>,
+    frame_size: u64,
+    alignment: u64,
+    frame: usize,
+    offset: u64,
+    recording: bool,
+}
+
+fn align(offset: u64, alignment: u64) -> u64 {
+    (offset + alignment - 1) / alignment * alignment
+}
+
+impl<B: Backend> FrameRing<B> {
+    /// Create a ring of `frames` regions of at least `frame_size` bytes each.
+    ///
+    /// The buffer is placed in CPU visible and coherent memory, and stays mapped
+    /// for the lifetime of the ring.
+    pub fn new(device: &mut Device<B>, frames: usize, frame_size: usize) -> Result<Self, CreationError> {
+        assert!(frames > 0, "A frame ring needs at least one frame");
+        let alignment = cmp::max(device.ref_raw().get_limits().min_constant_buffer_offset_alignment, 1) as u64;
+        // Regions start at aligned offsets, as the first allocation of a frame is placed at the start.
+        let frame_size = align(frame_size as u64, alignment);
+
+        let buffer = device.create_buffer::<u8>(
+            (frame_size * frames as u64) as usize,
+            buffer::Role::Constant,
+            core::buffer::CONSTANT,
+            memory::CPU_VISIBLE | memory::COHERENT,
+        )?;
+        let buffer = (*buffer).clone();
+        let (memory, mapping) = {
+            let writer = device.mut_raw()
+                .write_mapping::<u8>(buffer.resource(), 0, buffer.info().size as u64)?;
+            // The mapped memory outlives the borrow of the buffer, which is kept alive by the ring.
+            let (slice, mapping) = unsafe { writer.into_parts() };
+            (slice.as_mut_ptr(), mapping)
+        };
+
+        let fences = (0 .. frames)
+            .map(|_| {
+                // Created signaled, as no submission is using the regions yet.
+                let fence = device.mut_raw().create_fence(true);
+                Arc::new(handle::inner::Fence::new(fence, (), device))
+            })
+            .collect();
+
+        Ok(FrameRing {
+            _mapping: mapping,
+            memory,
+            buffer,
+            fences,
+            frame_size,
+            alignment,
+            frame: 0,
+            offset: 0,
+            recording: false,
+        })
+    }
+
+    /// The buffer containing the regions of all frames.
+    pub fn buffer(&self) -> &handle::raw::Buffer<B> {
+        &self.buffer
+    }
+
+    /// Size of the region of each frame, in bytes.
+    pub fn frame_size(&self) -> u64 {
+        self.frame_size
+    }
+
+    /// Number of frames which can be in flight.
+    pub fn frames(&self) -> usize {
+        self.fences.len()
+    }
+
+    /// Start writing the data of a new frame.
+    ///
+    /// Blocks until the submissions made during the last frame using the same region have completed.
+    pub fn begin_frame(&mut self, gpu: &mut Gpu<B>) {
+        assert!(!self.recording, "The previous frame has not been ended");
+        let device = gpu.mut_device().mut_raw();
+        let fence = self.fences[self.frame].resource();
+        device.wait_for_fences(&[fence], WaitFor::All, !0);
+        device.reset_fences(&[fence]);
+        self.offset = 0;
+        self.recording = true;
+    }
+
+    /// Write `data` into the region of the current frame.
+    ///
+    /// Returns the buffer and the offset of the data in it, which is aligned for constant
+    /// buffer views, or `None` if there is not enough space left in the region.
+    pub fn write<T: Copy>(&mut self, data: &[T]) -> Option<(handle::raw::Buffer<B>, u64)> {
+        assert!(self.recording, "Data can only be written between begin_frame and end_frame");
+        let size = (data.len() * mem::size_of::<T>()) as u64;
+        let offset = align(self.offset, self.alignment);
+        if offset + size > self.frame_size {
+            return None;
+        }
+        self.offset = offset + size;
+
+        let start = self.frame as u64 * self.frame_size + offset;
+        unsafe {
+            ptr::copy_nonoverlapping(data.as_ptr() as *const u8,
+                                     self.memory.offset(start as isize),
+                                     size as usize);
+        }
+        Some((self.buffer.clone(), start))
+    }
+
+    /// End the current frame, once all the commands using its data have been submitted to `gpu`.
+    pub fn end_frame(&mut self, gpu: &mut Gpu<B>) {
+        assert!(self.recording, "No frame has been begun");
+        gpu.signal_fence(self.fences[self.frame].resource());
+        self.frame = (self.frame + 1) % self.fences.len();
+        self.recording = false;
+    }
+}
@@ -2,13 +2,12 @@
 //!
 //! See `Slice`-structure documentation for more information on this module.
 
-use core::{handle, buffer};
-use core::{Primitive, Backend, VertexCount};
-use core::command::InstanceParams;
-use core::device::Device;
-use core::memory::Bind;
-use format::Format;
-use pso;
+use core::{self, Primitive, Backend, InstanceCount, VertexCount};
+use {buffer, handle};
+use device::Device;
+
+/// Number of instances to draw and the index of the first one.
+pub type InstanceParams = (InstanceCount, InstanceCount);
 
 /// A `Slice` dictates in which and in what order vertices get processed. It is required for
 /// processing a PSO.
@@ -43,7 +42,7 @@ use pso;
 ///
 /// A `Slice` is required to process a PSO, as it contains the needed information on in what order
 /// to draw which vertices. As such, every `draw` call on an `Encoder` requires a `Slice`.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct Slice<B: Backend> {
     /// The start index of the index-buffer. Processing will start at this location in the
     /// index-buffer.
@@ -63,8 +62,7 @@ pub struct Slice<B: Backend> {
 
 impl<B: Backend> Slice<B> {
     /// Creates a new `Slice` to match the supplied vertex buffer, from start to end, in order.
-    pub fn new_match_vertex_buffer<V>(vbuf: &handle::Buffer<B, V>) -> Self
-                                      where V: pso::buffer::Structure<Format> {
+    pub fn new_match_vertex_buffer<V>(vbuf: &handle::Buffer<B, V>) -> Self {
         Slice {
             start: 0,
             end: vbuf.len() as u32,
@@ -124,7 +122,7 @@ impl<B: Backend> Slice<B> {
 /// `Buffer` of integers, using a device.
 ///
 /// An `IndexBuffer` is exclusively used to create `Slice`s.
-#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug)]
 pub enum IndexBuffer<B: Backend> {
     /// Represents a hypothetical index-buffer from 0 to infinity. In other words, all vertices
     /// get processed in order.
@@ -143,17 +141,17 @@ impl<B: Backend> Default for IndexBuffer<B> {
 /// A helper trait to create `IndexBuffers` from different kinds of data.
 pub trait IntoIndexBuffer<B: Backend> {
     /// Turns self into an `IndexBuffer`.
-    fn into_index_buffer<F: Device<B> + ?Sized>(self, device: &mut F) -> IndexBuffer<B>;
+    fn into_index_buffer(self, device: &mut Device<B>) -> IndexBuffer<B>;
 }
 
 impl<B: Backend> IntoIndexBuffer<B> for IndexBuffer<B> {
-    fn into_index_buffer<F: Device<B> + ?Sized>(self, _: &mut F) -> IndexBuffer<B> {
+    fn into_index_buffer(self, _: &mut Device<B>) -> IndexBuffer<B> {
         self
     }
 }
 
 impl<B: Backend> IntoIndexBuffer<B> for () {
-    fn into_index_buffer<F: Device<B> + ?Sized>(self, _: &mut F) -> IndexBuffer<B> {
+    fn into_index_buffer(self, _: &mut Device<B>) -> IndexBuffer<B> {
         IndexBuffer::Auto
     }
 }
@@ -161,14 +159,14 @@ impl<B: Backend> IntoIndexBuffer<B> for () {
 macro_rules! impl_index_buffer {
     ($prim_ty:ty, $buf_ty:ident) => (
         impl<B: Backend> IntoIndexBuffer<B> for handle::Buffer<B, $prim_ty> {
-            fn into_index_buffer<F: Device<B> + ?Sized>(self, _: &mut F) -> IndexBuffer<B> {
+            fn into_index_buffer(self, _: &mut Device<B>) -> IndexBuffer<B> {
                 IndexBuffer::$buf_ty(self)
             }
         }
 
         impl<'s, B: Backend> IntoIndexBuffer<B> for &'s [$prim_ty] {
-            fn into_index_buffer<F: Device<B> + ?Sized>(self, device: &mut F) -> IndexBuffer<B> {
-                device.create_buffer_immutable(self, buffer::Role::Index, Bind::empty())
+            fn into_index_buffer(self, device: &mut Device<B>) -> IndexBuffer<B> {
+                device.create_buffer_immutable(self, buffer::Role::Index, core::buffer::INDEX)
                       .unwrap()
                       .into_index_buffer(device)
             }
@@ -0,0 +1,97 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use core::Device;
+use core::device::WaitFor;
+use handle::Epoch;
+use Backend;
+
+/// Progress of the submissions on a queue, tracked by a fence per submission.
+pub(crate) struct Timeline<B: Backend> {
+    // Shared with the garbage channel to tag dropped resources.
+    last_submitted: Arc<AtomicUsize>,
+    last_completed: Epoch,
+    pending: VecDeque<(Epoch, B::Fence)>,
+    free_fences: Vec<B::Fence>,
+}
+
+impl<B: Backend> Timeline<B> {
+    pub fn new() -> Self {
+        Timeline {
+            last_submitted: Arc::new(AtomicUsize::new(0)),
+            last_completed: 0,
+            pending: VecDeque::new(),
+            free_fences: Vec::new(),
+        }
+    }
+
+    pub fn last_submitted(&self) -> &Arc<AtomicUsize> {
+        &self.last_submitted
+    }
+
+    pub fn last_completed(&self) -> Epoch {
+        self.last_completed
+    }
+
+    /// Start a new submission, returning its epoch and the fence to signal on completion.
+    ///
+    /// The epoch is advanced before the submission is made, so resources dropped
+    /// concurrently are never tagged with an older epoch.
+    pub fn begin_submission(&mut self, device: &mut B::Device) -> (Epoch, B::Fence) {
+        let fence = self.free_fences
+            .pop()
+            .unwrap_or_else(|| device.create_fence(false));
+        let epoch = self.last_submitted.fetch_add(1, Ordering::AcqRel) + 1;
+        (epoch, fence)
+    }
+
+    /// Track the fence of a submission made with `begin_submission`.
+    pub fn end_submission(&mut self, epoch: Epoch, fence: B::Fence) {
+        self.pending.push_back((epoch, fence));
+    }
+
+    /// Retire all submissions whose fences are signaled, returning the last completed epoch.
+    pub fn poll(&mut self, device: &mut B::Device) -> Epoch {
+        while let Some((epoch, fence)) = self.pending.pop_front() {
+            if !device.wait_for_fences(&[&fence], WaitFor::All, 0) {
+                self.pending.push_front((epoch, fence));
+                break;
+            }
+            device.reset_fences(&[&fence]);
+            self.free_fences.push(fence);
+            self.last_completed = epoch;
+        }
+        self.last_completed
+    }
+
+    /// Block until the submission of `epoch` and all earlier ones have completed.
+    pub fn wait_for(&mut self, device: &mut B::Device, epoch: Epoch) -> Epoch {
+        let fences = self.pending
+            .iter()
+            .take_while(|&&(pending, _)| pending <= epoch)
+            .map(|&(_, ref fence)| fence)
+            .collect::<Vec<_>>();
+        if !fences.is_empty() {
+            device.wait_for_fences(&fences, WaitFor::All, !0);
+        }
+        self.poll(device)
+    }
+
+    /// Block until all submissions have completed.
+    pub fn wait_idle(&mut self, device: &mut B::Device) -> Epoch {
+        if !self.pending.is_empty() {
+            let fences = self.pending.iter().map(|&(_, ref fence)| fence).collect::<Vec<_>>();
+            device.wait_for_fences(&fences, WaitFor::All, !0);
+        }
+        self.poll(device)
+    }
+
+    /// Destroy all fences, the timeline has to be idle.
+    pub fn destroy(&mut self, device: &mut B::Device) {
+        debug_assert!(self.pending.is_empty());
+        for fence in self.free_fences.drain(..) {
+            device.destroy_fence(fence);
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use empty;
+use gfx;
+
+/// Number of live objects of `kind` on the device of an empty backend.
+pub fn live_objects(gpu: &gfx::Gpu<empty::Backend>, kind: empty::ObjectKind) -> usize {
+    gpu.ref_device()
+        .ref_raw()
+        .live_objects()
+        .iter()
+        .filter(|&&(_, k)| k == kind)
+        .count()
+}
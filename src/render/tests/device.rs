@@ -0,0 +1,53 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+mod common;
+
+use core::{format, image};
+use gfx::{buffer, IndexBuffer};
+use common::live_objects;
+
+#[test]
+fn immutable_buffers() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let vertices = [[0.0f32, 1.0], [1.0, 0.0], [0.0, 0.0]];
+    let (vbuf, slice) = gpu.mut_device().create_vertex_buffer_with_slice(&vertices, &[0u16, 1, 2, 2][..]);
+    assert_eq!(vbuf.len(), 3);
+    assert_eq!(vbuf.info().role, buffer::Role::Vertex);
+    assert_eq!((slice.start, slice.end), (0, 4));
+    match slice.buffer {
+        IndexBuffer::Index16(ref ibuf) => assert_eq!(ibuf.len(), 4),
+        _ => panic!("expected 16 bit indices"),
+    }
+    // Both buffers are filled through a staging buffer each.
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 4);
+
+    // The uploads are submitted ahead of the first submission.
+    assert_eq!(gpu.submit(core::Submission::new()), 2);
+    gpu.cleanup();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 2);
+
+    drop((vbuf, slice));
+    gpu.cleanup();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 0);
+    assert_eq!(gpu.ref_device().memory_stats().allocations, 0);
+}
+
+#[test]
+fn immutable_image() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let kind = image::Kind::D2(4, 4, image::AaMode::Single);
+    let image = gpu.mut_device()
+        .create_image_immutable::<format::Rgba8>(kind, &[[0xFF; 4]; 16])
+        .unwrap();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Image), 1);
+    assert_eq!(gpu.ref_device().memory_stats().allocations, 2);
+
+    gpu.submit(core::Submission::new());
+    drop(image);
+    gpu.wait_idle();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Image), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 0);
+    assert_eq!(gpu.ref_device().memory_stats().allocations, 0);
+}
@@ -0,0 +1,148 @@
+#[macro_use]
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+mod common;
+
+use core::{format, image, pso};
+use gfx::GraphicsPoolExt;
+use gfx::pso::InitError;
+use common::live_objects;
+
+const CUBE_VERT: &[u8] = include_bytes!("../../core/tests/data/cube.vert.spv");
+const CUBE_FRAG: &[u8] = include_bytes!("../../core/tests/data/cube.frag.spv");
+
+gfx_defines! {
+    vertex Vertex {
+        pos: [f32; 4] = "a_Pos",
+        tex_coord: [f32; 2] = "a_TexCoord",
+    }
+
+    vertex IntVertex {
+        pos: [f32; 4] = "a_Pos",
+        tex_coord: [i32; 2] = "a_TexCoord",
+    }
+
+    constant Locals {
+        transform: [[f32; 4]; 4] = "u_Transform",
+    }
+
+    pipeline pipe {
+        vbuf: gfx::VertexBuffer<Vertex> = (),
+        locals: gfx::ConstantBuffer<Locals> = 0,
+        color: gfx::TextureSampler<[f32; 4]> = (1, 2),
+        out_color: gfx::RenderTarget<format::Rgba8> = (),
+    }
+
+    pipeline int_pipe {
+        vbuf: gfx::VertexBuffer<IntVertex> = (),
+    }
+}
+
+const IDENTITY: [[f32; 4]; 4] = [
+    [1.0, 0.0, 0.0, 0.0],
+    [0.0, 1.0, 0.0, 0.0],
+    [0.0, 0.0, 1.0, 0.0],
+    [0.0, 0.0, 0.0, 1.0],
+];
+
+fn cube_entries() -> pso::GraphicsShaderSet {
+    pso::GraphicsShaderSet {
+        vertex_shader: "main",
+        hull_shader: None,
+        domain_shader: None,
+        geometry_shader: None,
+        pixel_shader: Some("main"),
+    }
+}
+
+fn create_pipeline<I: gfx::pso::PipelineInit>(gpu: &mut gfx::Gpu<empty::Backend>, init: I)
+    -> Result<gfx::PipelineState<empty::Backend, I::Meta>, gfx::PipelineStateError>
+{
+    gpu.mut_device().create_pipeline_state(
+        &[("main", CUBE_VERT), ("main", CUBE_FRAG)],
+        cube_entries(),
+        gfx::Primitive::TriangleList,
+        pso::Rasterizer::new_fill(),
+        init,
+    )
+}
+
+#[test]
+fn draw_cube() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new_deferred());
+    let pso = create_pipeline(&mut gpu, pipe::new()).unwrap();
+
+    let vertices = [
+        Vertex { pos: [-1.0, -1.0, 0.0, 1.0], tex_coord: [0.0, 0.0] },
+        Vertex { pos: [1.0, -1.0, 0.0, 1.0], tex_coord: [1.0, 0.0] },
+        Vertex { pos: [1.0, 1.0, 0.0, 1.0], tex_coord: [1.0, 1.0] },
+    ];
+    let data = {
+        let device = gpu.mut_device();
+        let (vbuf, slice) = device.create_vertex_buffer_with_slice(&vertices, ());
+        let locals = device.create_constant_buffer(1);
+        let texture = device
+            .create_image_immutable::<format::Rgba8>(image::Kind::D2(1, 1, image::AaMode::Single), &[[0xFF; 4]])
+            .unwrap();
+        let target = device
+            .create_image::<format::Rgba8>(image::Kind::D2(4, 4, image::AaMode::Single), 1,
+                                           image::COLOR_ATTACHMENT)
+            .unwrap();
+        let data = pipe::Data {
+            vbuf,
+            locals,
+            color: (device.view_image_as_shader_resource(&texture).unwrap(), device.create_sampler_linear()),
+            out_color: device.view_image_as_render_target(&target, 0, 0 .. 1).unwrap(),
+        };
+        (data, slice)
+    };
+
+    let mut pool = gpu.create_graphics_pool(1);
+    let submission = {
+        let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+        encoder.clear(&data.0.out_color, [0.0, 0.0, 0.0, 1.0]);
+        encoder.update_constant_buffer(&data.0.locals, &Locals { transform: IDENTITY });
+        encoder.draw(&data.1, &pso, &data.0);
+        encoder.finish()
+    };
+    // The draw owns its framebuffer and descriptor set until the submission completes.
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::FrameBuffer), 1);
+    submission.flush(&mut gpu);
+
+    drop((data, pso));
+    gpu.cleanup();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::FrameBuffer), 1);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::DescriptorPool), 1);
+    gpu.wait_idle();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::FrameBuffer), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::DescriptorPool), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::GraphicsPipeline), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::RenderTargetView), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::ShaderResourceView), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Image), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 0);
+}
+
+#[test]
+fn link_descriptor_mismatch() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let init = pipe::Init {
+        color: (2, 1),
+        .. pipe::new()
+    };
+    match create_pipeline(&mut gpu, init) {
+        Err(gfx::PipelineStateError::DescriptorInit(InitError::Descriptor(1, _))) => (),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
+
+#[test]
+fn link_vertex_mismatch() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    match create_pipeline(&mut gpu, int_pipe::new()) {
+        Err(gfx::PipelineStateError::DescriptorInit(InitError::VertexImport(1, Some(_)))) => (),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
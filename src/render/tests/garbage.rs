@@ -0,0 +1,80 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+mod common;
+
+use std::sync::Arc;
+use std::thread;
+use core::{image, Device};
+use gfx::handle;
+use common::live_objects;
+
+fn sampler(gpu: &mut gfx::Gpu<empty::Backend>) -> handle::raw::Sampler<empty::Backend> {
+    let info = image::SamplerInfo::new(image::FilterMethod::Bilinear, image::WrapMode::Clamp);
+    let raw = gpu.mut_device().mut_raw().create_sampler(info);
+    Arc::new(handle::inner::Sampler::new(raw, (), gpu.ref_device()))
+}
+
+#[test]
+fn destroy_after_completion() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+
+    // Nothing has been submitted yet, so the sampler can be destroyed right away.
+    drop(sampler(&mut gpu));
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 1);
+    gpu.cleanup();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 0);
+
+    let used = sampler(&mut gpu);
+    assert_eq!(gpu.submit(core::Submission::new()), 1);
+    assert_eq!(gpu.last_completed(), 0);
+    drop(used);
+    gpu.cleanup();
+    assert_eq!(gpu.last_completed(), 1);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 0);
+}
+
+#[test]
+fn keep_while_pending() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new_deferred());
+
+    let used = sampler(&mut gpu);
+    assert_eq!(gpu.submit(core::Submission::new()), 1);
+    drop(used);
+    // The fence of the submission hasn't been signaled yet.
+    gpu.cleanup();
+    assert_eq!(gpu.last_completed(), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 1);
+
+    gpu.wait_idle();
+    assert_eq!(gpu.last_completed(), 1);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 0);
+}
+
+#[test]
+fn drop_on_other_thread() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+
+    let used = sampler(&mut gpu);
+    thread::spawn(move || drop(used)).join().unwrap();
+    gpu.cleanup();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 0);
+}
+
+#[test]
+fn recycle_fences() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    for epoch in 1 .. 4 {
+        assert_eq!(gpu.submit(core::Submission::new()), epoch);
+        gpu.cleanup();
+    }
+    assert_eq!(gpu.last_completed(), 3);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Fence), 1);
+
+    let handles = (0 .. 3).map(|_| sampler(&mut gpu)).collect::<Vec<_>>();
+    gpu.submit(core::Submission::new());
+    drop(handles);
+    gpu.wait_idle();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Sampler), 0);
+}
@@ -0,0 +1,170 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_backend_software as software;
+extern crate gfx_core as core;
+
+use std::cell::RefCell;
+use std::fs::{self, File};
+use std::io::Read;
+use std::rc::Rc;
+use core::{format, image, pool, pso};
+use core::{AcquireError, Adapter, Device, FrameSync, QueueFamily, Swapchain as CoreSwapchain};
+use core::command::ClearColor;
+use core::format::Formatted;
+use gfx::headless::{Error, Output, Swapchain};
+use gfx::SwapchainConfig;
+
+fn open<A: Adapter<B>, B: core::Backend>(adapter: &A) -> core::Gpu<B> {
+    let descs = adapter.get_queue_families()
+        .iter()
+        .map(|&(ref family, qtype)| (family, qtype, family.num_queues()))
+        .collect::<Vec<_>>();
+    adapter.open(&descs)
+}
+
+#[test]
+fn present_to_callback() {
+    let core::Gpu { mut device, mut graphics_queues, heap_types, .. } = open(&software::Adapter::new());
+    let mut queue = graphics_queues.remove(0);
+    let frames = Rc::new(RefCell::new(Vec::new()));
+    let output = {
+        let frames = frames.clone();
+        Output::Callback(Box::new(move |frame| {
+            frames.borrow_mut().push((frame.number, frame.index, frame.width, frame.height, frame.data.to_vec()));
+        }))
+    };
+    let config = SwapchainConfig::new().with_color::<format::Rgba8>().with_extent(2, 3);
+    let mut swapchain = Swapchain::new(&mut device, &heap_types, graphics_queues.remove(0), config, output).unwrap();
+    assert_eq!(swapchain.get_backbuffers().len(), 2);
+
+    let acquisition = device.create_semaphore();
+    let rendering = device.create_semaphore();
+    let mut pool = queue.create_graphics_pool(1, pool::TRANSIENT);
+    for &value in &[[1.0, 0.0, 0.0, 1.0], [0.0, 0.0, 1.0, 0.0], [0.0, 1.0, 0.0, 1.0]] {
+        let frame = swapchain.acquire_frame(FrameSync::Semaphore(&acquisition)).unwrap();
+        let rtv = device
+            .view_image_as_render_target(&swapchain.get_backbuffers()[frame.id()].color,
+                                         format::Rgba8::get_format(), (0 .. 1, 0 .. 1))
+            .unwrap();
+        pool.reset();
+        let submit = {
+            let mut cmd_buffer = pool.acquire_command_buffer();
+            cmd_buffer.clear_color(&rtv, image::ImageLayout::Present, ClearColor::Float(value));
+            cmd_buffer.finish()
+        };
+        let submission = core::Submission::new()
+            .wait_on(&[(&acquisition, pso::COLOR_ATTACHMENT_OUTPUT)])
+            .signal(&[&rendering])
+            .submit(&[submit]);
+        queue.submit(submission, None);
+        swapchain.present(&mut queue, &[&rendering]).unwrap();
+        assert_eq!(swapchain.read_presented(&mut device).unwrap(), 1);
+        device.destroy_render_target_view(rtv);
+    }
+    assert_eq!(swapchain.num_presented(), 3);
+
+    let frames = frames.borrow();
+    let indices = frames.iter().map(|f| (f.0, f.1, f.2, f.3)).collect::<Vec<_>>();
+    assert_eq!(indices, vec![(0, 0, 2, 3), (1, 1, 2, 3), (2, 0, 2, 3)]);
+    assert_eq!(frames[0].4, [255, 0, 0, 255].iter().cloned().cycle().take(2 * 3 * 4).collect::<Vec<u8>>());
+    assert_eq!(frames[1].4, [0, 0, 255, 0].iter().cloned().cycle().take(2 * 3 * 4).collect::<Vec<u8>>());
+    assert_eq!(&frames[2].4[.. 4], &[0, 255, 0, 255]);
+
+    drop(pool);
+    swapchain.destroy(&mut device);
+    device.destroy_semaphore(acquisition);
+    device.destroy_semaphore(rendering);
+}
+
+#[test]
+fn present_to_directory() {
+    let dir = std::env::temp_dir().join("gfx-headless-present");
+    fs::create_dir_all(&dir).unwrap();
+    let core::Gpu { mut device, mut graphics_queues, heap_types, .. } = open(&software::Adapter::new());
+    let mut queue = graphics_queues.remove(0);
+    let config = SwapchainConfig::new().with_color::<format::Bgra8>().with_extent(4, 1);
+    let mut swapchain = Swapchain::new(&mut device, &heap_types, graphics_queues.remove(0), config,
+                                       Output::Directory(dir.clone())).unwrap();
+    let fence = device.create_fence(false);
+    swapchain.acquire_frame(FrameSync::Fence(&fence)).unwrap();
+    assert!(device.wait_for_fences(&[&fence], gfx::WaitFor::All, !0));
+    swapchain.present(&mut queue, &[]).unwrap();
+    swapchain.read_presented(&mut device).unwrap();
+
+    let mut contents = Vec::new();
+    File::open(dir.join("frame_00000.ppm")).unwrap().read_to_end(&mut contents).unwrap();
+    let header = b"P6\n4 1\n255\n";
+    assert_eq!(&contents[.. header.len()], header);
+    assert_eq!(contents.len(), header.len() + 4 * 3);
+    fs::remove_dir_all(&dir).unwrap();
+
+    swapchain.destroy(&mut device);
+    device.destroy_fence(fence);
+}
+
+#[test]
+fn synchronization() {
+    let core::Gpu { mut device, mut general_queues, mut transfer_queues, heap_types, .. } = open(&empty::Adapter::new());
+    let mut queue = general_queues.remove(0);
+    let config = SwapchainConfig::new().with_color::<format::Rgba8>().with_extent(3, 2);
+    let output = Output::Callback(Box::new(|_| ()));
+    let mut swapchain = Swapchain::new(&mut device, &heap_types, transfer_queues.remove(0), config, output).unwrap();
+
+    let acquisition = device.create_semaphore();
+    let rendering = device.create_semaphore();
+    for _ in 0 .. 2 {
+        swapchain.acquire_frame(FrameSync::Semaphore(&acquisition)).unwrap();
+        swapchain.present(&mut queue, &[&rendering]).unwrap();
+    }
+    // Both backbuffers wait for their frames to be read back.
+    match swapchain.acquire_frame(FrameSync::Semaphore(&acquisition)) {
+        Err(AcquireError::NotReady) => (),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert_eq!(swapchain.read_presented(&mut device).unwrap(), 2);
+    assert_eq!(swapchain.acquire_frame(FrameSync::Semaphore(&acquisition)).unwrap().id(), 0);
+
+    let transfer_queue = swapchain.destroy(&mut device);
+    let submissions = transfer_queue.as_raw().submissions();
+    assert_eq!(submissions.len(), 5);
+    for &i in &[0, 2, 4] {
+        assert!(submissions[i].command_buffers.is_empty());
+        assert_eq!(submissions[i].signal_semaphores.len(), 1);
+    }
+    for &i in &[1, 3] {
+        assert_eq!(submissions[i].wait_semaphores.len(), 1);
+        assert_eq!(submissions[i].wait_semaphores[0].1, pso::TRANSFER);
+        assert!(submissions[i].fence.is_some());
+        match submissions[i].command_buffers[0][..] {
+            [empty::Command::PipelineBarrier { .. },
+             empty::Command::CopyImageToBuffer { ref regions, .. },
+             empty::Command::PipelineBarrier { .. }] => {
+                assert_eq!(regions[0].buffer_row_pitch, 12);
+            }
+            ref commands => panic!("unexpected commands {:?}", commands),
+        }
+    }
+    device.destroy_semaphore(acquisition);
+    device.destroy_semaphore(rendering);
+}
+
+#[test]
+fn invalid_config() {
+    let core::Gpu { mut device, mut general_queues, mut compute_queues, mut transfer_queues, heap_types, .. } =
+        open(&empty::Adapter::new());
+    let callback = || Output::Callback(Box::new(|_| ()));
+    match Swapchain::new(&mut device, &heap_types, general_queues.remove(0), SwapchainConfig::new(), callback()) {
+        Err(Error::MissingExtent) => (),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+
+    let config = SwapchainConfig::new().with_color::<format::Rgba16F>().with_extent(1, 1);
+    let dir = std::env::temp_dir();
+    match Swapchain::new(&mut device, &heap_types, transfer_queues.remove(0), config.clone(), Output::Directory(dir)) {
+        Err(Error::UnsupportedFormat(f)) => assert_eq!(f, format::Rgba16F::get_format()),
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+    let swapchain = Swapchain::new(&mut device, &heap_types, compute_queues.remove(0), config, callback()).unwrap();
+    swapchain.destroy(&mut device);
+    assert!(device.live_objects().is_empty());
+}
@@ -0,0 +1,63 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_backend_software as software;
+extern crate gfx_core as core;
+
+use core::{format, image};
+use gfx::GraphicsPoolExt;
+
+#[test]
+fn generate_mipmaps() {
+    let mut gpu = gfx::Gpu::new(&software::Adapter::new());
+    let kind = image::Kind::D2(4, 2, image::AaMode::Single);
+    let usage = core::image::SAMPLED | core::image::TRANSFER_SRC | core::image::TRANSFER_DST;
+    let tex = gpu.mut_device().create_image::<format::Rgba8>(kind, 3, usage).unwrap();
+    let texels = [[0u8, 0, 0, 255], [4, 8, 0, 255], [8, 16, 0, 255], [12, 24, 0, 255],
+                  [16, 32, 0, 255], [20, 40, 0, 255], [24, 48, 0, 255], [28, 56, 4, 255]];
+
+    let mut pool = gpu.create_graphics_pool(1);
+    let (levels, submission) = {
+        let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+        encoder.update_texture::<_, format::Rgba8>(&tex, tex.info().to_image_info(0), &texels).unwrap();
+        encoder.generate_mipmaps(&tex).unwrap();
+        let levels = (1 .. 3)
+            .map(|level| encoder.read_texture::<_, format::Rgba8>(&tex, tex.info().to_image_info(level)).unwrap())
+            .collect::<Vec<_>>();
+        (levels, encoder.finish())
+    };
+    submission.flush(&mut gpu);
+
+    let levels = levels
+        .into_iter()
+        .map(|readback| readback.wait(&mut gpu))
+        .collect::<Vec<_>>();
+    // Each texel is the average of the texels it covers in the level before.
+    assert_eq!(levels[0], vec![[10, 20, 0, 255], [18, 36, 1, 255]]);
+    assert_eq!(levels[1], vec![[14, 28, 1, 255]]);
+}
+
+#[test]
+fn generate_mipmaps_usage() {
+    let mut gpu = gfx::Gpu::new(&software::Adapter::new());
+    let kind = image::Kind::D2(4, 4, image::AaMode::Single);
+    let tex = gpu.mut_device()
+        .create_image::<format::Rgba8>(kind, 3, core::image::SAMPLED | core::image::TRANSFER_DST)
+        .unwrap();
+    let mut pool = gpu.create_graphics_pool(1);
+    let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+    assert_eq!(encoder.generate_mipmaps(&tex), Err(gfx::CopyError::NoSrcBindFlag));
+}
+
+#[test]
+fn generate_mipmaps_unsupported_format() {
+    // Compressed images can't be blitted into.
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let kind = image::Kind::D2(8, 8, image::AaMode::Single);
+    let usage = core::image::SAMPLED | core::image::TRANSFER_SRC | core::image::TRANSFER_DST;
+    let tex = gpu.mut_device()
+        .create_image::<(format::BC1_R8_G8_B8_A8, format::Unorm)>(kind, 3, usage)
+        .unwrap();
+    let mut pool = gpu.create_graphics_pool(1);
+    let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+    assert_eq!(encoder.generate_mipmaps(&tex), Err(gfx::CopyError::NoBlitSupport));
+}
@@ -0,0 +1,110 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+
+use gfx::GraphicsPoolExt;
+use gfx::profiler::Profiler;
+
+fn record_frame(gpu: &mut gfx::Gpu<empty::Backend>, profiler: &mut Profiler<empty::Backend>) {
+    let mut pool = gpu.create_graphics_pool(1);
+    profiler.begin_frame(gpu);
+    let submission = {
+        let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+        {
+            let mut frame = profiler.scope(&mut encoder, "frame");
+            frame.scope("shadow pass");
+            frame.scope("lighting \"main\"");
+        }
+        encoder.finish()
+    };
+    submission.flush(gpu);
+    profiler.end_frame(gpu);
+}
+
+#[test]
+fn nested_scopes() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let mut profiler = Profiler::new(gpu.mut_device(), 2, 4);
+    assert!(profiler.is_supported());
+    for _ in 0 .. 3 {
+        record_frame(&mut gpu, &mut profiler);
+    }
+    profiler.poll(&mut gpu);
+
+    // The timestamps of the empty backend advance by one nanosecond each.
+    let frames = profiler.history().iter().map(|f| f.frame).collect::<Vec<_>>();
+    assert_eq!(frames, vec![0, 1, 2]);
+    let timings = profiler.history()[1].scopes
+        .iter()
+        .map(|s| (s.name.as_str(), s.depth, s.start, s.duration))
+        .collect::<Vec<_>>();
+    assert_eq!(timings, vec![("frame", 0, 6, 5), ("shadow pass", 1, 7, 1), ("lighting \"main\"", 1, 9, 1)]);
+
+    let stats = profiler.statistics()
+        .iter()
+        .map(|s| (s.name.as_str(), s.samples, s.total, s.min, s.max))
+        .collect::<Vec<_>>();
+    assert_eq!(stats, vec![("frame", 3, 15, 5, 5), ("shadow pass", 3, 3, 1, 1), ("lighting \"main\"", 3, 3, 1, 1)]);
+    assert_eq!(profiler.statistics()[0].average(), 5.0);
+}
+
+#[test]
+fn frames_in_flight() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new_deferred());
+    let mut profiler = Profiler::new(gpu.mut_device(), 3, 4);
+    for _ in 0 .. 3 {
+        record_frame(&mut gpu, &mut profiler);
+    }
+    assert!(profiler.history().is_empty());
+
+    // Waiting for a later submission completes all frames in flight.
+    gpu.submit(gfx::queue::Submission::new());
+    gpu.wait_idle();
+    profiler.poll(&mut gpu);
+    let frames = profiler.history().iter().map(|f| f.frame).collect::<Vec<_>>();
+    assert_eq!(frames, vec![0, 1, 2]);
+    let starts = profiler.history().iter().map(|f| f.scopes[0].start).collect::<Vec<_>>();
+    assert!(starts[0] < starts[1] && starts[1] < starts[2]);
+
+    // Reusing the oldest frame resolves the pending ones in order.
+    record_frame(&mut gpu, &mut profiler);
+    record_frame(&mut gpu, &mut profiler);
+    gpu.submit(gfx::queue::Submission::new());
+    gpu.wait_idle();
+    profiler.poll(&mut gpu);
+    let frames = profiler.history().iter().map(|f| f.frame).collect::<Vec<_>>();
+    assert_eq!(frames, vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn scope_capacity_and_history() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let mut profiler = Profiler::new(gpu.mut_device(), 1, 2);
+    profiler.set_history(1);
+    record_frame(&mut gpu, &mut profiler);
+    record_frame(&mut gpu, &mut profiler);
+    profiler.poll(&mut gpu);
+
+    assert_eq!(profiler.history().len(), 1);
+    let frame = &profiler.history()[0];
+    assert_eq!(frame.frame, 1);
+    let names = frame.scopes.iter().map(|s| s.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec!["frame", "shadow pass"]);
+}
+
+#[test]
+fn chrome_trace() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let mut profiler = Profiler::new(gpu.mut_device(), 1, 4);
+    record_frame(&mut gpu, &mut profiler);
+    profiler.poll(&mut gpu);
+
+    let mut trace = Vec::new();
+    profiler.write_chrome_trace(&mut trace).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(trace.starts_with("{\"traceEvents\":["));
+    assert!(trace.contains(
+        "{\"name\":\"frame\",\"cat\":\"gpu\",\"ph\":\"X\",\"ts\":0.000,\"dur\":0.005,\
+         \"pid\":0,\"tid\":0,\"args\":{\"frame\":0,\"depth\":0}}"));
+    assert!(trace.contains("\"name\":\"lighting \\\"main\\\"\""));
+    assert_eq!(trace.matches("\"ph\":\"X\"").count(), 3);
+}
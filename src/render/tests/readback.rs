@@ -0,0 +1,77 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_backend_software as software;
+extern crate gfx_core as core;
+
+use core::{format, image};
+use gfx::{buffer, GraphicsPoolExt};
+
+#[test]
+fn read_buffer() {
+    let mut gpu = gfx::Gpu::new(&software::Adapter::new());
+    let data = [1u32, 2, 3, 4, 5];
+    let buf = gpu.mut_device()
+        .create_buffer_immutable(&data, buffer::Role::Vertex, core::buffer::VERTEX | core::buffer::TRANSFER_SRC)
+        .unwrap();
+
+    let mut pool = gpu.create_graphics_pool(1);
+    let (readback, submission) = {
+        let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+        let readback = encoder.read_buffer(&buf).unwrap();
+        (readback, encoder.finish())
+    };
+    assert_eq!(readback.epoch(), None);
+    assert!(readback.poll(&mut gpu).is_none());
+
+    let epoch = submission.flush(&mut gpu);
+    assert_eq!(readback.epoch(), Some(epoch));
+    assert_eq!(readback.wait(&mut gpu), data.to_vec());
+}
+
+#[test]
+fn read_texture() {
+    let mut gpu = gfx::Gpu::new(&software::Adapter::new());
+    let kind = image::Kind::D2(3, 2, image::AaMode::Single);
+    let texels = [[0u8, 1, 2, 3], [4, 5, 6, 7], [8, 9, 10, 11],
+                  [12, 13, 14, 15], [16, 17, 18, 19], [20, 21, 22, 23]];
+    let tex = gpu.mut_device()
+        .create_image_immutable::<format::Rgba8>(kind, &texels)
+        .unwrap();
+    // Immutable images can't be read back.
+    let usage = core::image::SAMPLED | core::image::TRANSFER_SRC | core::image::TRANSFER_DST;
+    let copy = gpu.mut_device().create_image::<format::Rgba8>(kind, 1, usage).unwrap();
+
+    let mut pool = gpu.create_graphics_pool(1);
+    let (readback, submission) = {
+        let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+        encoder.update_texture::<_, format::Rgba8>(&copy, copy.info().to_image_info(0), &texels).unwrap();
+        assert_eq!(encoder.read_texture::<_, format::Rgba8>(&tex, tex.info().to_image_info(0)).unwrap_err(),
+                   gfx::CopyError::NoSrcBindFlag);
+        let mut region = copy.info().to_image_info(0);
+        region.xoffset = 1;
+        region.width = 2;
+        let readback = encoder.read_texture::<_, format::Rgba8>(&copy, region).unwrap();
+        (readback, encoder.finish())
+    };
+    submission.flush(&mut gpu);
+    assert_eq!(readback.wait(&mut gpu), vec![texels[1], texels[2], texels[4], texels[5]]);
+}
+
+#[test]
+fn read_out_of_bounds() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let kind = image::Kind::D2(4, 4, image::AaMode::Single);
+    let tex = gpu.mut_device()
+        .create_image::<format::Rgba8>(kind, 1, core::image::TRANSFER_SRC)
+        .unwrap();
+    let mut pool = gpu.create_graphics_pool(1);
+    let mut encoder = pool.acquire_graphics_encoder(gpu.mut_device());
+    let mut region = tex.info().to_image_info(0);
+    region.width = 5;
+    match encoder.read_texture::<_, format::Rgba8>(&tex, region) {
+        Err(gfx::CopyError::OutOfSrcBounds { size, copy_end }) => {
+            assert_eq!((size, copy_end), ([4, 4, 0], [5, 4, 0]));
+        }
+        other => panic!("unexpected result {:?}", other.map(|_| ())),
+    }
+}
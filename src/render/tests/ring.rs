@@ -0,0 +1,60 @@
+extern crate gfx;
+extern crate gfx_backend_empty as empty;
+extern crate gfx_core as core;
+
+mod common;
+
+use core::Device;
+use common::live_objects;
+
+fn read(gpu: &gfx::Gpu<empty::Backend>, ring: &gfx::FrameRing<empty::Backend>, offset: u64) -> [f32; 4] {
+    let reader = gpu.ref_device()
+        .ref_raw()
+        .read_mapping::<[f32; 4]>(ring.buffer().resource(), offset, 16)
+        .unwrap();
+    reader[0]
+}
+
+#[test]
+fn frame_regions() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let mut ring = gfx::FrameRing::new(gpu.mut_device(), 2, 400).unwrap();
+    // The empty backend aligns constant buffer views to 256 bytes.
+    assert_eq!(ring.frame_size(), 512);
+    assert_eq!(ring.buffer().info().size, 1024);
+
+    ring.begin_frame(&mut gpu);
+    let (_, first) = ring.write(&[[1.0f32, 2.0, 3.0, 4.0]]).unwrap();
+    let (_, second) = ring.write(&[[5.0f32, 6.0, 7.0, 8.0]]).unwrap();
+    assert_eq!((first, second), (0, 256));
+    assert!(ring.write(&[0u8]).is_none());
+    ring.end_frame(&mut gpu);
+
+    ring.begin_frame(&mut gpu);
+    let (_, third) = ring.write(&[[9.0f32, 10.0, 11.0, 12.0]]).unwrap();
+    assert_eq!(third, 512);
+    ring.end_frame(&mut gpu);
+
+    // The first region is reused once its fence is signaled.
+    ring.begin_frame(&mut gpu);
+    let (_, fourth) = ring.write(&[[0.0f32; 4]]).unwrap();
+    assert_eq!(fourth, 0);
+    ring.end_frame(&mut gpu);
+
+    assert_eq!(read(&gpu, &ring, second), [5.0, 6.0, 7.0, 8.0]);
+    assert_eq!(read(&gpu, &ring, third), [9.0, 10.0, 11.0, 12.0]);
+    assert_eq!(read(&gpu, &ring, fourth), [0.0; 4]);
+
+    drop(ring);
+    gpu.wait_idle();
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Fence), 0);
+    assert_eq!(live_objects(&gpu, empty::ObjectKind::Buffer), 0);
+}
+
+#[test]
+#[should_panic]
+fn write_outside_frame() {
+    let mut gpu = gfx::Gpu::new(&empty::Adapter::new());
+    let mut ring = gfx::FrameRing::new(gpu.mut_device(), 1, 256).unwrap();
+    ring.write(&[0u32]);
+}
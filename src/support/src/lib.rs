@@ -32,6 +32,7 @@ use gfx::memory::Typed;
 use gfx::queue::GraphicsQueue;
 use gfx::{Adapter, Backend, CommandQueue, FrameSync, GraphicsCommandPool,
           Swapchain, QueueType, WindowExt};
+use gfx::headless;
 
 pub mod shade;
 
@@ -97,11 +98,6 @@ fn run<A, B, S>((width, height): (u32, u32),
           S: gfx_core::Surface<B>,
           B::Device: shade::ShadeExt,
 {
-    use shade::ShadeExt;
-    use gfx::format::Formatted;
-    use gfx::traits::Device;
-    use gfx::texture;
-
     // Init device, requesting (at least) one graphics queue with presentation support
     let gfx_core::Gpu { mut device, mut graphics_queues, .. } =
         adapters[0].open_with(|family, ty| ((ty.supports_graphics() && surface.supports_queue(&family)) as u32, QueueType::Graphics));
@@ -112,6 +108,80 @@ fn run<A, B, S>((width, height): (u32, u32),
                     .with_depth_stencil::<DepthFormat>();
     let mut swap_chain = surface.build_swapchain(config, &queue);
 
+    let targets = create_targets(&mut device, &mut swap_chain, (width, height));
+    let mut frames = Frames::new(&mut device, &mut queue, targets);
+    let mut running = true;
+
+    while running {
+        events_loop.poll_events(|event| {
+            if let glutin::Event::WindowEvent { event, .. } = event {
+                match event {
+                    winit::WindowEvent::Closed => running = false,
+                    winit::WindowEvent::KeyboardInput {
+                        input: winit::KeyboardInput {
+                            state: winit::ElementState::Pressed,
+                            virtual_keycode, ..
+                        }, ..
+                    }  if virtual_keycode == A::get_exit_key() => return,
+                    winit::WindowEvent::Resized(_width, _height) => {
+                        warn!("TODO: resize not implemented");
+                    },
+                    _ => frames.app.on(event),
+                }
+            }
+        });
+
+        frames.render(&mut device, &mut queue, &mut swap_chain);
+    }
+}
+
+/// Run an application for `num_frames` frames on a headless swapchain of the given size.
+///
+/// The adapter needs to provide two graphics queues, one of them is owned by the swapchain.
+pub fn run_headless<A, B>(adapter: &B::Adapter,
+                          (width, height): (u32, u32),
+                          num_frames: usize,
+                          output: headless::Output)
+    where A: Sized + Application<B>,
+          B: Backend,
+          B::Device: shade::ShadeExt,
+{
+    use std::cmp;
+    use gfx_core::QueueFamily;
+
+    let gfx_core::Gpu { mut device, mut graphics_queues, heap_types, .. } =
+        adapter.open_with(|family, ty| (ty.supports_graphics() as u32 * cmp::min(family.num_queues(), 2), QueueType::Graphics));
+    if graphics_queues.len() < 2 {
+        panic!("Headless rendering requires two graphics queues.");
+    }
+    let mut queue = graphics_queues.pop().unwrap();
+
+    let config = gfx_core::SwapchainConfig::new()
+                    .with_color::<ColorFormat>()
+                    .with_depth_stencil::<DepthFormat>()
+                    .with_extent(width, height);
+    let mut swap_chain = headless::Swapchain::new(&mut device, &heap_types, graphics_queues.pop().unwrap(), config, output)
+        .expect("Unable to create a headless swapchain.");
+
+    let targets = create_targets(&mut device, &mut swap_chain, (width, height));
+    let mut frames = Frames::new(&mut device, &mut queue, targets);
+    for _ in 0 .. num_frames {
+        frames.render(&mut device, &mut queue, &mut swap_chain);
+        swap_chain.read_presented(&mut device).expect("Unable to read back a frame.");
+    }
+    drop(frames);
+    swap_chain.destroy(&mut device);
+}
+
+fn create_targets<B, W>(device: &mut B::Device, swap_chain: &mut W, (width, height): (u32, u32))
+    -> WindowTargets<B::Resources>
+    where B: Backend,
+          W: Swapchain<B>,
+{
+    use gfx::format::Formatted;
+    use gfx::traits::Device;
+    use gfx::texture;
+
     let views =
         swap_chain
             .get_backbuffers()
@@ -139,51 +209,55 @@ fn run<A, B, S>((width, height): (u32, u32),
             })
             .collect();
 
-    let shader_backend = device.shader_backend();
-    let mut app = A::new(&mut device, &mut queue, shader_backend, WindowTargets {
+    WindowTargets {
         views: views,
         aspect_ratio: width as f32 / height as f32, //TODO
-    });
-
-    let mut harness = Harness::new();
-    let mut running = true;
+    }
+}
 
+/// Application and the resources driving its frames, shared by window and headless runs.
+struct Frames<A, B: Backend> {
+    app: A,
+    harness: Harness,
     // TODO: For optimal performance we should use a ring-buffer
-    let sync = SyncPrimitives {
-        acquisition: device.create_semaphore(),
-        rendering: device.create_semaphore(),
-        frame_fence: device.create_fence(false),
-    };
+    sync: SyncPrimitives<B::Resources>,
+    graphics_pool: GraphicsCommandPool<B>,
+}
 
-    let mut graphics_pool = queue.create_graphics_pool(1);
+impl<A: Application<B>, B: Backend> Frames<A, B>
+    where B::Device: shade::ShadeExt,
+{
+    fn new(device: &mut B::Device, queue: &mut GraphicsQueue<B>, targets: WindowTargets<B::Resources>) -> Self {
+        use shade::ShadeExt;
+        use gfx::traits::Device;
+
+        let shader_backend = device.shader_backend();
+        let app = A::new(device, queue, shader_backend, targets);
+        let sync = SyncPrimitives {
+            acquisition: device.create_semaphore(),
+            rendering: device.create_semaphore(),
+            frame_fence: device.create_fence(false),
+        };
 
-    while running {
-        events_loop.poll_events(|event| {
-            if let glutin::Event::WindowEvent { event, .. } = event {
-                match event {
-                    winit::WindowEvent::Closed => running = false,
-                    winit::WindowEvent::KeyboardInput {
-                        input: winit::KeyboardInput {
-                            state: winit::ElementState::Pressed,
-                            virtual_keycode, ..
-                        }, ..
-                    }  if virtual_keycode == A::get_exit_key() => return,
-                    winit::WindowEvent::Resized(_width, _height) => {
-                        warn!("TODO: resize not implemented");
-                    },
-                    _ => app.on(event),
-                }
-            }
-        });
+        Frames {
+            app,
+            harness: Harness::new(),
+            sync,
+            graphics_pool: queue.create_graphics_pool(1),
+        }
+    }
+
+    fn render<W: Swapchain<B>>(&mut self, device: &mut B::Device, queue: &mut GraphicsQueue<B>, swap_chain: &mut W) {
+        use gfx::traits::Device;
 
-        graphics_pool.reset();
-        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&sync.acquisition));
-        app.render((frame, &sync), &mut graphics_pool, &mut queue);
-        swap_chain.present(&mut queue, &[]);
+        self.graphics_pool.reset();
+        let frame = swap_chain.acquire_frame(FrameSync::Semaphore(&self.sync.acquisition)).unwrap();
+        self.app.render((frame, &self.sync), &mut self.graphics_pool, queue);
+        swap_chain.present(queue, &[]).unwrap();
 
-        device.wait_for_fences(&[&sync.frame_fence], gfx::WaitFor::All, 1_000_000);
+        device.wait_for_fences(&[&self.sync.frame_fence], gfx::WaitFor::All, 1_000_000);
         queue.cleanup();
-        harness.bump();
+        self.harness.bump();
     }
 }
 